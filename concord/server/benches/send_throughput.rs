@@ -0,0 +1,124 @@
+//! Message throughput with many concurrent senders.
+//!
+//! Run with `cargo bench --bench send_throughput`. Each sender is a separate
+//! account posting into one shared channel, so every message goes through the
+//! full pipeline: permission lookup, slow mode and automod queries, persistence
+//! and fan-out to every member. Both runtime flavors are measured; the
+//! current-thread runtime only works because the pipeline never blocks.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use concord_server::db::pool::{create_pool, run_migrations};
+use concord_server::db::queries::users;
+use concord_server::engine::chat_engine::ChatEngine;
+use concord_server::engine::user_session::Protocol;
+use uuid::Uuid;
+
+/// Stays within the per-user burst allowance of the message rate limiter.
+const MESSAGES_PER_SENDER: usize = 10;
+const SENDER_COUNTS: [usize; 4] = [1, 16, 64, 256];
+
+async fn run(senders: usize) -> Duration {
+    let pool = create_pool("sqlite::memory:").await.unwrap();
+    run_migrations(&pool).await.unwrap();
+    let engine = Arc::new(ChatEngine::new(Some(pool.clone()), 4000, 100));
+
+    let mut user_ids = Vec::with_capacity(senders);
+    for i in 0..senders {
+        let user_id = Uuid::new_v4().to_string();
+        users::create_with_oauth(
+            &pool,
+            &users::CreateOAuthUser {
+                user_id: &user_id,
+                username: &format!("sender{i}"),
+                email: None,
+                avatar_url: None,
+                oauth_id: &Uuid::new_v4().to_string(),
+                provider: "github",
+                provider_id: &Uuid::new_v4().to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        user_ids.push(user_id);
+    }
+
+    let server_id = engine
+        .create_server("Bench".into(), user_ids[0].clone(), None)
+        .await
+        .unwrap();
+    let mut sessions = Vec::with_capacity(senders);
+    for (i, user_id) in user_ids.iter().enumerate() {
+        if i > 0 {
+            engine.join_server(user_id, &server_id).await.unwrap();
+        }
+        let (session_id, mut events) = engine
+            .connect(
+                Some(user_id.clone()),
+                format!("sender{i}"),
+                Protocol::WebSocket,
+                None,
+            )
+            .unwrap();
+        engine
+            .join_channel(session_id, &server_id, "#general")
+            .await
+            .unwrap();
+        // Act like a connected client and keep the outbound queue drained.
+        tokio::spawn(async move { while events.recv().await.is_some() {} });
+        sessions.push(session_id);
+    }
+
+    let start = Instant::now();
+    let tasks: Vec<_> = sessions
+        .into_iter()
+        .map(|session_id| {
+            let engine = engine.clone();
+            let server_id = server_id.clone();
+            tokio::spawn(async move {
+                for n in 0..MESSAGES_PER_SENDER {
+                    engine
+                        .send_message(
+                            session_id,
+                            &server_id,
+                            "#general",
+                            &format!("message {n}"),
+                            None,
+                            None,
+                            None,
+                        )
+                        .await
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+    start.elapsed()
+}
+
+fn main() {
+    for (flavor, multi_thread) in [("multi-thread", true), ("current-thread", false)] {
+        println!("{flavor} runtime");
+        for senders in SENDER_COUNTS {
+            let runtime = if multi_thread {
+                tokio::runtime::Builder::new_multi_thread()
+            } else {
+                tokio::runtime::Builder::new_current_thread()
+            }
+            .enable_all()
+            .build()
+            .unwrap();
+            let elapsed = runtime.block_on(run(senders));
+            let messages = senders * MESSAGES_PER_SENDER;
+            println!(
+                "  {senders:>4} senders  {messages:>5} messages  {:>8.1} ms  {:>8.0} msg/s",
+                elapsed.as_secs_f64() * 1000.0,
+                messages as f64 / elapsed.as_secs_f64()
+            );
+        }
+    }
+}
@@ -0,0 +1,5 @@
+-- Migration 017: Channel modes
+-- Read-only channels (IRC +m) and topic locking (IRC +t)
+
+ALTER TABLE channels ADD COLUMN is_read_only INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE channels ADD COLUMN topic_locked INTEGER NOT NULL DEFAULT 0;
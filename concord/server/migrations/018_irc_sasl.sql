@@ -0,0 +1,17 @@
+-- Migration 018: IRC SASL EXTERNAL and SCRAM-SHA-256
+-- SCRAM credentials derived from each IRC token at creation (tokens created
+-- earlier only support PLAIN), and TLS client certificate fingerprints.
+
+ALTER TABLE irc_tokens ADD COLUMN scram_salt TEXT;
+ALTER TABLE irc_tokens ADD COLUMN scram_iterations INTEGER;
+ALTER TABLE irc_tokens ADD COLUMN scram_stored_key TEXT;
+ALTER TABLE irc_tokens ADD COLUMN scram_server_key TEXT;
+
+CREATE TABLE IF NOT EXISTS irc_cert_fingerprints (
+    fingerprint TEXT PRIMARY KEY,
+    user_id     TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    label       TEXT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_irc_cert_fingerprints_user ON irc_cert_fingerprints(user_id);
@@ -0,0 +1,23 @@
+-- Migration 019: Matrix appservice bridge
+-- Rooms created for aliases in the bridge's namespace, and the Matrix event
+-- behind each bridged message or reaction so edits, redactions and replies
+-- can be matched up in both directions.
+
+CREATE TABLE IF NOT EXISTS matrix_rooms (
+    room_id       TEXT PRIMARY KEY,
+    server_id     TEXT NOT NULL,
+    channel_name  TEXT NOT NULL,
+    created_at    TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE(server_id, channel_name)
+);
+
+CREATE TABLE IF NOT EXISTS matrix_events (
+    event_id    TEXT PRIMARY KEY,
+    room_id     TEXT NOT NULL,
+    message_id  TEXT NOT NULL,
+    sender      TEXT NOT NULL,
+    emoji       TEXT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_matrix_events_message ON matrix_events(message_id);
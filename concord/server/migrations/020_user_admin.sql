@@ -0,0 +1,6 @@
+-- Migration 020: Account administration from the concord-server CLI
+-- Suspended accounts can't sign in, and sessions issued before
+-- tokens_revoked_at (unix seconds) are rejected even though the JWT is valid.
+
+ALTER TABLE users ADD COLUMN suspended_at TEXT;
+ALTER TABLE users ADD COLUMN tokens_revoked_at INTEGER;
@@ -0,0 +1,22 @@
+-- Migration 021: AutoMod review queue
+-- Messages a rule flagged (delivered, message_id set) or held for approval
+-- (not delivered until a moderator approves them).
+
+CREATE TABLE IF NOT EXISTS automod_queue (
+    id          TEXT PRIMARY KEY,
+    server_id   TEXT NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+    channel_id  TEXT NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    rule_id     TEXT REFERENCES automod_rules(id) ON DELETE SET NULL,
+    rule_name   TEXT NOT NULL,
+    user_id     TEXT NOT NULL,
+    user_nick   TEXT NOT NULL,
+    content     TEXT NOT NULL,
+    reply_to_id TEXT,
+    message_id  TEXT,
+    status      TEXT NOT NULL CHECK(status IN ('flagged', 'pending', 'approved', 'rejected')),
+    reviewed_by TEXT,
+    reviewed_at TEXT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_automod_queue_server ON automod_queue(server_id, status, created_at);
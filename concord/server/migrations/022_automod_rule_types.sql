@@ -0,0 +1,34 @@
+-- Migration 022: Behavioural AutoMod rule types
+-- SQLite can't alter a CHECK constraint, so automod_rules is rebuilt with the
+-- wider rule_type list. pool.rs runs migrations with foreign_keys OFF, so
+-- automod_queue's reference to automod_rules survives the swap.
+
+CREATE TABLE automod_rules_v2 (
+    id          TEXT PRIMARY KEY,
+    server_id   TEXT NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+    name        TEXT NOT NULL,
+    enabled     INTEGER NOT NULL DEFAULT 1,
+    rule_type   TEXT NOT NULL CHECK(rule_type IN (
+        'keyword', 'mention_spam', 'link_filter',
+        'duplicate_spam', 'message_flood', 'excessive_caps', 'zalgo',
+        'invite_spam', 'join_burst', 'raid'
+    )),
+    config      TEXT NOT NULL DEFAULT '{}',
+    action_type TEXT NOT NULL DEFAULT 'delete' CHECK(action_type IN ('delete', 'timeout', 'flag')),
+    timeout_duration_seconds INTEGER,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE(server_id, name)
+);
+
+INSERT INTO automod_rules_v2
+    (id, server_id, name, enabled, rule_type, config, action_type,
+     timeout_duration_seconds, created_at, updated_at)
+SELECT id, server_id, name, enabled, rule_type, config, action_type,
+       timeout_duration_seconds, created_at, updated_at
+FROM automod_rules;
+
+DROP TABLE automod_rules;
+ALTER TABLE automod_rules_v2 RENAME TO automod_rules;
+
+CREATE INDEX IF NOT EXISTS idx_automod_server ON automod_rules(server_id, enabled);
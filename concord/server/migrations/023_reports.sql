@@ -0,0 +1,27 @@
+-- Migration 023: User reports
+-- Members report a message or a user; moderators resolve open reports from a
+-- queue. The reported message's content is copied so the report survives the
+-- message being edited or deleted.
+
+CREATE TABLE IF NOT EXISTS reports (
+    id              TEXT PRIMARY KEY,
+    server_id       TEXT NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+    reporter_id     TEXT NOT NULL,
+    target_user_id  TEXT NOT NULL,
+    message_id      TEXT,
+    channel_id      TEXT,
+    message_content TEXT,
+    reason          TEXT NOT NULL,
+    status          TEXT NOT NULL DEFAULT 'open' CHECK(status IN ('open', 'resolved')),
+    resolution      TEXT CHECK(resolution IN ('dismiss', 'delete', 'timeout', 'kick', 'ban')),
+    resolution_note TEXT,
+    resolved_by     TEXT,
+    resolved_at     TEXT,
+    created_at      TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_reports_server ON reports(server_id, status, created_at);
+-- One open report per reporter for the same message (or user, for user reports)
+CREATE UNIQUE INDEX IF NOT EXISTS idx_reports_open_unique
+    ON reports(reporter_id, target_user_id, COALESCE(message_id, ''))
+    WHERE status = 'open';
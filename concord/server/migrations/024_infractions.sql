@@ -0,0 +1,33 @@
+-- Migration 024: Infractions ledger and escalation policies
+-- Every warn, timeout, kick and ban, whether by a moderator, AutoMod or an
+-- escalation policy, is recorded against the member. Unexpired, unpardoned
+-- manual and AutoMod infractions count as strikes toward the server's
+-- escalation policies.
+
+CREATE TABLE IF NOT EXISTS infractions (
+    id          TEXT PRIMARY KEY,
+    server_id   TEXT NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+    user_id     TEXT NOT NULL,
+    actor_id    TEXT,
+    action      TEXT NOT NULL CHECK(action IN ('warn', 'timeout', 'kick', 'ban', 'automod')),
+    source      TEXT NOT NULL CHECK(source IN ('manual', 'automod', 'escalation')),
+    reason      TEXT,
+    expires_at  TEXT,
+    pardoned_by TEXT,
+    pardoned_at TEXT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_infractions_member ON infractions(server_id, user_id, created_at);
+
+-- "threshold strikes within window_seconds -> action"
+CREATE TABLE IF NOT EXISTS escalation_policies (
+    id               TEXT PRIMARY KEY,
+    server_id        TEXT NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+    threshold        INTEGER NOT NULL CHECK(threshold > 0),
+    window_seconds   INTEGER NOT NULL CHECK(window_seconds > 0),
+    action           TEXT NOT NULL CHECK(action IN ('timeout', 'kick', 'ban')),
+    duration_seconds INTEGER,
+    created_at       TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE(server_id, threshold)
+);
@@ -0,0 +1,28 @@
+-- Migration 025: Temporary and pre-emptive bans
+-- Bans gain an optional expiry and record the message purge window in
+-- seconds. user_id loses its foreign key so accounts that have never signed
+-- in here can be banned ahead of time. SQLite can't drop a constraint, so
+-- the table is rebuilt.
+
+CREATE TABLE bans_v2 (
+    id         TEXT PRIMARY KEY,
+    server_id  TEXT NOT NULL REFERENCES servers(id) ON DELETE CASCADE,
+    user_id    TEXT NOT NULL,
+    banned_by  TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason     TEXT,
+    delete_message_seconds INTEGER NOT NULL DEFAULT 0,
+    expires_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE(server_id, user_id)
+);
+
+INSERT INTO bans_v2
+    (id, server_id, user_id, banned_by, reason, delete_message_seconds, created_at)
+SELECT id, server_id, user_id, banned_by, reason, delete_message_days * 86400, created_at
+FROM bans;
+
+DROP TABLE bans;
+ALTER TABLE bans_v2 RENAME TO bans;
+
+CREATE INDEX IF NOT EXISTS idx_bans_server ON bans(server_id);
+CREATE INDEX IF NOT EXISTS idx_bans_expiry ON bans(expires_at) WHERE expires_at IS NOT NULL;
@@ -0,0 +1,29 @@
+-- Migration 026: Instance administration
+-- System admins can ban an identity (a DID) from the whole instance, including
+-- ones that have never signed in here, and quarantine a server: it is hidden
+-- from discovery and frozen read-only. Every instance-level admin action is
+-- recorded in instance_audit_log. Actor columns carry no foreign key so the
+-- record outlives the admin's account.
+
+CREATE TABLE IF NOT EXISTS instance_bans (
+    user_id    TEXT PRIMARY KEY,
+    reason     TEXT,
+    banned_by  TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS instance_audit_log (
+    id          TEXT PRIMARY KEY,
+    actor_id    TEXT NOT NULL,
+    action_type TEXT NOT NULL,
+    target_type TEXT,
+    target_id   TEXT,
+    reason      TEXT,
+    changes     TEXT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_instance_audit_created ON instance_audit_log(created_at);
+
+ALTER TABLE servers ADD COLUMN quarantined_at TEXT;
+ALTER TABLE servers ADD COLUMN quarantine_reason TEXT;
@@ -0,0 +1,7 @@
+-- Migration 027: System user
+-- Strike escalations ban, kick and write audit entries on their own, and those
+-- tables' actor columns reference users. This reserved account is their actor.
+-- It has no OAuth link, password or DID, so nobody can sign in as it, and its
+-- username isn't a valid nickname, so nobody can register it.
+
+INSERT OR IGNORE INTO users (id, username) VALUES ('system', 'AutoMod [System]');
@@ -0,0 +1,13 @@
+-- Migration 028: Admin change log
+-- The admin CLI writes to the database directly, while the running server
+-- keeps servers, channels and sessions in memory. Each CLI command that
+-- touches that state appends a row here; the server polls for new rows and
+-- applies them to its in-memory copy. `restore` leaves this table alone so
+-- IDs only ever grow.
+
+CREATE TABLE IF NOT EXISTS admin_changes (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind       TEXT NOT NULL,
+    target_id  TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
@@ -1,9 +1,11 @@
 use argon2::password_hash::SaltString;
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 /// JWT claims for web session tokens.
 #[derive(Debug, Serialize, Deserialize)]
@@ -57,6 +59,9 @@ pub fn validate_session_token(
 pub struct JwtBlocklist {
     /// Map from jti -> expiry timestamp (unix seconds)
     entries: std::sync::Mutex<std::collections::HashMap<String, i64>>,
+    /// Map from user_id -> (sessions issued at or before this are revoked,
+    /// when the last such session expires)
+    users: std::sync::Mutex<std::collections::HashMap<String, (i64, i64)>>,
 }
 
 impl Default for JwtBlocklist {
@@ -69,6 +74,7 @@ impl JwtBlocklist {
     pub fn new() -> Self {
         Self {
             entries: std::sync::Mutex::new(std::collections::HashMap::new()),
+            users: std::sync::Mutex::new(std::collections::HashMap::new()),
         }
     }
 
@@ -90,11 +96,34 @@ impl JwtBlocklist {
         entries.contains_key(jti)
     }
 
+    /// Revoke every session a user was issued up to now. `expiry_hours` is the
+    /// session lifetime, after which the entry is no longer needed.
+    pub fn revoke_user(&self, user_id: &str, expiry_hours: i64) {
+        let now = Utc::now();
+        let keep_until = (now + Duration::hours(expiry_hours)).timestamp();
+        let mut users = self.users.lock().unwrap();
+        users.insert(user_id.to_string(), (now.timestamp(), keep_until));
+    }
+
+    /// Check if a session is revoked, either on its own or with all of its user's sessions.
+    pub fn is_session_revoked(&self, claims: &Claims) -> bool {
+        if self.is_revoked(&claims.jti) {
+            return true;
+        }
+        let users = self.users.lock().unwrap();
+        users
+            .get(&claims.sub)
+            .is_some_and(|(revoked_at, _)| claims.iat <= *revoked_at)
+    }
+
     /// Remove expired entries from the blocklist.
     pub fn cleanup(&self) {
         let now = Utc::now().timestamp();
         let mut entries = self.entries.lock().unwrap();
         entries.retain(|_, exp| *exp > now);
+        drop(entries);
+        let mut users = self.users.lock().unwrap();
+        users.retain(|_, (_, keep_until)| *keep_until > now);
     }
 }
 
@@ -123,6 +152,77 @@ pub fn verify_irc_token(token: &str, hash: &str) -> bool {
         .is_ok()
 }
 
+/// PBKDF2 iteration count for SCRAM-SHA-256 credentials (the RFC 7677 minimum).
+pub const SCRAM_ITERATIONS: u32 = 4096;
+
+/// SCRAM-SHA-256 verifier for an IRC token (RFC 5802 StoredKey/ServerKey).
+/// Lets clients prove they hold the token without sending it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScramCredentials {
+    pub salt: Vec<u8>,
+    pub iterations: u32,
+    pub stored_key: Vec<u8>,
+    pub server_key: Vec<u8>,
+}
+
+/// Generate a random salt for SCRAM credentials.
+pub fn generate_scram_salt() -> Vec<u8> {
+    let mut salt = vec![0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt
+}
+
+/// Derive the SCRAM-SHA-256 verifier for an IRC token.
+pub fn scram_credentials(token: &str, salt: &[u8], iterations: u32) -> ScramCredentials {
+    // Hi(password, salt, i) — PBKDF2 with HMAC-SHA-256 and a single output block
+    let mac = Hmac::<Sha256>::new_from_slice(token.as_bytes()).expect("HMAC accepts any key");
+    let mut block = mac.clone();
+    block.update(salt);
+    block.update(&1u32.to_be_bytes());
+    let mut u = block.finalize().into_bytes();
+    let mut salted = u;
+    for _ in 1..iterations {
+        let mut next = mac.clone();
+        next.update(&u);
+        u = next.finalize().into_bytes();
+        for (s, b) in salted.iter_mut().zip(u.iter()) {
+            *s ^= b;
+        }
+    }
+
+    let client_key = hmac_sha256(&salted, b"Client Key");
+    ScramCredentials {
+        salt: salt.to_vec(),
+        iterations,
+        stored_key: Sha256::digest(client_key).to_vec(),
+        server_key: hmac_sha256(&salted, b"Server Key").to_vec(),
+    }
+}
+
+/// HMAC-SHA-256 of `data` under `key`.
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// SHA-256 fingerprint of a DER-encoded TLS client certificate (lowercase hex),
+/// as used for IRC CertFP and SASL EXTERNAL.
+pub fn cert_fingerprint(der: &[u8]) -> String {
+    hex_encode(&Sha256::digest(der))
+}
+
+/// Normalize a user-supplied SHA-256 certificate fingerprint: case-insensitive
+/// hex, optionally colon-separated. Returns None if it isn't 32 bytes of hex.
+pub fn normalize_cert_fingerprint(input: &str) -> Option<String> {
+    let hex: String = input
+        .chars()
+        .filter(|c| *c != ':')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    (hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit())).then_some(hex)
+}
+
 fn hex_encode(bytes: &[u8]) -> String {
     bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
@@ -145,6 +245,37 @@ mod tests {
         assert!(validate_session_token(&token, "secret2").is_err());
     }
 
+    #[test]
+    fn test_blocklist_revokes_user_sessions() {
+        let blocklist = JwtBlocklist::new();
+        let token = create_session_token("user-1", "secret", 24).unwrap();
+        let claims = validate_session_token(&token, "secret").unwrap();
+        assert!(!blocklist.is_session_revoked(&claims));
+
+        blocklist.revoke_user("user-1", 24);
+        assert!(blocklist.is_session_revoked(&claims));
+
+        // Sessions issued afterwards, and other users' sessions, are unaffected
+        let later = Claims {
+            iat: claims.iat + 5,
+            ..claims
+        };
+        assert!(!blocklist.is_session_revoked(&later));
+        let other = Claims {
+            sub: "user-2".into(),
+            ..later
+        };
+        assert!(!blocklist.is_session_revoked(&other));
+
+        blocklist.cleanup();
+        let claims = Claims {
+            sub: "user-1".into(),
+            iat: other.iat - 5,
+            ..other
+        };
+        assert!(blocklist.is_session_revoked(&claims));
+    }
+
     #[test]
     fn test_irc_token_generation() {
         let token = generate_irc_token();
@@ -299,6 +430,48 @@ mod tests {
         assert!(!verify_irc_token("sometoken", ""));
     }
 
+    #[test]
+    fn test_scram_credentials_rfc7677_vector() {
+        use base64::Engine as _;
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let salt = b64.decode("W22ZaJ0SNY7soEsUEjb6gQ==").unwrap();
+        let creds = scram_credentials("pencil", &salt, SCRAM_ITERATIONS);
+        // ServerSignature from RFC 7677 section 3 depends on ServerKey
+        let auth_message = "n=user,r=rOprNGfwEbeRWgbNEkqO,\
+            r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096,\
+            c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0";
+        let signature = hmac_sha256(&creds.server_key, auth_message.as_bytes());
+        assert_eq!(
+            b64.encode(signature),
+            "6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4="
+        );
+        assert_eq!(creds.stored_key.len(), 32);
+    }
+
+    #[test]
+    fn test_cert_fingerprint_is_sha256_hex() {
+        let fp = cert_fingerprint(b"abc");
+        assert_eq!(
+            fp,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_normalize_cert_fingerprint() {
+        let fp = cert_fingerprint(b"abc");
+        let colons = fp
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| std::str::from_utf8(pair).unwrap().to_ascii_uppercase())
+            .collect::<Vec<_>>()
+            .join(":");
+        assert_eq!(normalize_cert_fingerprint(&colons), Some(fp.clone()));
+        assert_eq!(normalize_cert_fingerprint(&fp), Some(fp));
+        assert_eq!(normalize_cert_fingerprint("abcd"), None);
+        assert_eq!(normalize_cert_fingerprint(&"zz".repeat(32)), None);
+    }
+
     #[test]
     fn test_hex_encode_known_values() {
         assert_eq!(hex_encode(&[0x00]), "00");
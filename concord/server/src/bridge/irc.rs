@@ -0,0 +1,1142 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashSet;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use super::{nick_candidates, resolve_channel};
+use crate::config::IrcBridgeConfig;
+use crate::engine::chat_engine::ChatEngine;
+use crate::engine::events::{ChatEvent, SessionId};
+use crate::engine::user_session::{MAX_OUTBOUND_QUEUE, Protocol};
+use crate::irc::connection::read_bounded_line;
+use crate::irc::formatter;
+use crate::irc::multiline::client_privmsg_lines;
+use crate::irc::parser::{IrcMessage, split_tags};
+
+/// Delay before the first reconnect attempt; doubled after each failure.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Longest delay between reconnect attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(300);
+
+/// Status prefixes in front of nicknames in NAMES replies.
+const NAME_PREFIXES: &[char] = &['~', '&', '@', '%', '+'];
+
+/// A connection to an IRC server, plain or TLS.
+trait IrcStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> IrcStream for T {}
+
+/// A remote channel and the Concord channel it is mirrored into.
+#[derive(Debug, Clone)]
+struct Mapping {
+    remote: String,
+    server_id: String,
+    channel: String,
+}
+
+/// A user on the remote network, present in Concord as a bridge session.
+struct Ghost {
+    session_id: SessionId,
+    nick: String,
+    channels: HashSet<usize>,
+}
+
+/// A Concord user's own connection to the remote network.
+struct Puppet {
+    out: mpsc::Sender<String>,
+    channels: HashSet<usize>,
+}
+
+/// What woke up the bridge's event loop.
+enum Input {
+    Line(std::io::Result<usize>),
+    Event(Option<Box<ChatEvent>>),
+    Cancelled,
+}
+
+/// Start a bridge task for each configured network.
+pub fn start_irc_bridges(
+    engine: Arc<ChatEngine>,
+    bridges: Vec<IrcBridgeConfig>,
+    cancel: CancellationToken,
+) {
+    for config in bridges {
+        tokio::spawn(run_bridge(engine.clone(), config, cancel.clone()));
+    }
+}
+
+/// Keep one network bridged until `cancel` fires, reconnecting with backoff
+/// when the connection drops.
+pub async fn run_bridge(
+    engine: Arc<ChatEngine>,
+    config: IrcBridgeConfig,
+    cancel: CancellationToken,
+) {
+    let config = Arc::new(config);
+    let channels = resolve_channels(&engine, &config);
+    if channels.is_empty() {
+        warn!(network = %config.name, "IRC bridge has no channels to mirror");
+        return;
+    }
+
+    let mut delay = RECONNECT_DELAY;
+    loop {
+        match connect(&config.address, config.tls).await {
+            Ok(stream) => {
+                info!(network = %config.name, address = %config.address, "IRC bridge connected");
+                if run_connection(&engine, &config, &channels, stream, &cancel).await {
+                    delay = RECONNECT_DELAY;
+                }
+                info!(network = %config.name, "IRC bridge disconnected");
+            }
+            Err(e) => warn!(network = %config.name, error = %e, "IRC bridge failed to connect"),
+        }
+
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            _ = tokio::time::sleep(delay) => {}
+        }
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+/// Look up the Concord server of each bridged channel, skipping unknown servers.
+fn resolve_channels(engine: &ChatEngine, config: &IrcBridgeConfig) -> Vec<Mapping> {
+    config
+        .channels
+        .iter()
+        .filter_map(|ch| match resolve_channel(engine, ch) {
+            Ok((server_id, channel)) => Some(Mapping {
+                remote: ch.remote.clone(),
+                server_id,
+                channel,
+            }),
+            Err(server) => {
+                warn!(network = %config.name, %server, "IRC bridge: unknown Concord server");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Open a connection to `address` (`host:port`).
+async fn connect(address: &str, tls: bool) -> Result<Box<dyn IrcStream>, String> {
+    let tcp = TcpStream::connect(address)
+        .await
+        .map_err(|e| format!("Failed to connect to {address}: {e}"))?;
+    if !tls {
+        return Ok(Box::new(tcp));
+    }
+
+    let host = address
+        .rsplit_once(':')
+        .map_or(address, |(host, _)| host)
+        .trim_matches(['[', ']']);
+    let server_name = ServerName::try_from(host.to_string())
+        .map_err(|e| format!("Invalid TLS server name {host}: {e}"))?;
+    let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let tls_config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let stream = TlsConnector::from(Arc::new(tls_config))
+        .connect(server_name, tcp)
+        .await
+        .map_err(|e| format!("TLS handshake with {address} failed: {e}"))?;
+    Ok(Box::new(stream))
+}
+
+/// Spawn a task writing queued lines to `writer`. It ends when the sender is dropped.
+fn spawn_writer<W: AsyncWrite + Send + Unpin + 'static>(mut writer: W) -> mpsc::Sender<String> {
+    let (tx, mut rx) = mpsc::channel::<String>(MAX_OUTBOUND_QUEUE);
+    tokio::spawn(async move {
+        while let Some(line) = rx.recv().await {
+            if writer
+                .write_all(format!("{line}\r\n").as_bytes())
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+    tx
+}
+
+/// Bridge one connection until it drops or `cancel` fires. Returns whether
+/// the network accepted our registration.
+async fn run_connection(
+    engine: &Arc<ChatEngine>,
+    config: &Arc<IrcBridgeConfig>,
+    channels: &[Mapping],
+    stream: Box<dyn IrcStream>,
+    cancel: &CancellationToken,
+) -> bool {
+    let (reader, writer) = tokio::io::split(stream);
+    let out = spawn_writer(writer);
+    let (mut bridge, mut events) = match Bridge::new(
+        engine.clone(),
+        config.clone(),
+        channels.to_vec(),
+        out,
+        cancel,
+    )
+    .await
+    {
+        Ok(bridge) => bridge,
+        Err(e) => {
+            warn!(network = %config.name, error = %e, "IRC bridge failed to start");
+            return false;
+        }
+    };
+    bridge.register();
+
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let input = tokio::select! {
+            _ = cancel.cancelled() => Input::Cancelled,
+            read = read_bounded_line(&mut reader, &mut line) => Input::Line(read),
+            event = events.recv() => Input::Event(event.map(Box::new)),
+        };
+        match input {
+            Input::Line(Ok(0)) | Input::Event(None) => break,
+            Input::Line(Ok(_)) => bridge.handle_line(line.trim_end()).await,
+            Input::Line(Err(e)) => {
+                warn!(network = %config.name, error = %e, "IRC bridge read failed");
+                break;
+            }
+            Input::Event(Some(event)) => bridge.handle_event(*event),
+            Input::Cancelled => {
+                bridge.send("QUIT :Bridge shutting down".into());
+                break;
+            }
+        }
+    }
+
+    bridge.shutdown().await;
+    bridge.registered
+}
+
+/// State of one bridged connection: the remote users mirrored into Concord
+/// and, when puppeting, the Concord users' connections to the network.
+struct Bridge {
+    engine: Arc<ChatEngine>,
+    config: Arc<IrcBridgeConfig>,
+    channels: Vec<Mapping>,
+    /// Lines to send on the relay connection.
+    out: mpsc::Sender<String>,
+    /// Our nickname on the network.
+    nick: String,
+    registered: bool,
+    /// Concord session watching the bridged channels.
+    listener: SessionId,
+    listener_nick: String,
+    remote_suffix: String,
+    /// Remote users by lowercased nickname.
+    ghosts: HashMap<String, Ghost>,
+    /// Puppet connections by Concord nickname.
+    puppets: HashMap<String, Puppet>,
+    /// Lowercased nicknames our puppets hold on the network.
+    puppet_nicks: Arc<DashSet<String>>,
+    cancel: CancellationToken,
+}
+
+impl Bridge {
+    /// Connect the listener session and join it to the bridged channels.
+    async fn new(
+        engine: Arc<ChatEngine>,
+        config: Arc<IrcBridgeConfig>,
+        channels: Vec<Mapping>,
+        out: mpsc::Sender<String>,
+        cancel: &CancellationToken,
+    ) -> Result<(Self, mpsc::Receiver<ChatEvent>), String> {
+        let listener_nick = free_nick(&engine, &config.name, "")
+            .ok_or_else(|| format!("No free nickname for bridge {}", config.name))?;
+        let (listener, events) =
+            engine.connect(None, listener_nick.clone(), Protocol::Bridge, None)?;
+        for m in &channels {
+            if let Err(e) = engine
+                .join_channel(listener, &m.server_id, &m.channel)
+                .await
+            {
+                warn!(network = %config.name, channel = %m.channel, error = %e, "IRC bridge failed to join channel");
+            }
+        }
+
+        let remote_suffix = config
+            .remote_suffix
+            .clone()
+            .unwrap_or_else(|| format!("-{}", config.name));
+        let bridge = Self {
+            nick: config.nickname.clone(),
+            engine,
+            config,
+            channels,
+            out,
+            registered: false,
+            listener,
+            listener_nick,
+            remote_suffix,
+            ghosts: HashMap::new(),
+            puppets: HashMap::new(),
+            puppet_nicks: Arc::new(DashSet::new()),
+            cancel: cancel.clone(),
+        };
+        Ok((bridge, events))
+    }
+
+    fn send(&self, line: String) {
+        if self.out.try_send(line).is_err() {
+            warn!(network = %self.config.name, "IRC bridge outbound queue full, dropping line");
+        }
+    }
+
+    fn register(&self) {
+        for line in registration(&self.config, &self.nick) {
+            self.send(line);
+        }
+    }
+
+    /// Disconnect everyone this connection brought into Concord and close the puppets.
+    async fn shutdown(&mut self) {
+        for (_, ghost) in self.ghosts.drain() {
+            self.engine.disconnect(ghost.session_id).await;
+        }
+        for (_, puppet) in self.puppets.drain() {
+            let _ = puppet.out.try_send("QUIT :Bridge disconnected".into());
+        }
+        self.engine.disconnect(self.listener).await;
+    }
+
+    /// Index of the mapping for a remote channel.
+    fn remote_mapping(&self, channel: &str) -> Option<usize> {
+        self.channels
+            .iter()
+            .position(|m| m.remote.eq_ignore_ascii_case(channel))
+    }
+
+    /// Index of the mapping for a Concord channel.
+    fn concord_mapping(&self, server_id: &str, channel: &str) -> Option<usize> {
+        self.channels
+            .iter()
+            .position(|m| m.server_id == server_id && m.channel == channel)
+    }
+
+    /// Whether a remote nickname is the relay or one of our puppets.
+    fn is_ours(&self, nick: &str) -> bool {
+        nick.eq_ignore_ascii_case(&self.nick)
+            || self.puppet_nicks.contains(&nick.to_ascii_lowercase())
+    }
+
+    /// Whether a Concord nickname belongs to this bridge rather than a Concord user.
+    fn is_bridged(&self, nick: &str) -> bool {
+        nick == self.listener_nick || self.ghosts.values().any(|g| g.nick == nick)
+    }
+
+    // ── Network → Concord ──
+
+    async fn handle_line(&mut self, line: &str) {
+        let (_, line) = split_tags(line);
+        let Ok(msg) = IrcMessage::parse(line) else {
+            return;
+        };
+        let source = msg
+            .prefix
+            .as_deref()
+            .map_or("", |p| p.split('!').next().unwrap_or(p));
+
+        match (msg.command.as_str(), msg.params.as_slice()) {
+            ("PING", params) => {
+                let token = params.first().cloned().unwrap_or_default();
+                self.send(command("PONG", vec![token]));
+            }
+            ("001", [nick, ..]) => {
+                self.registered = true;
+                self.nick.clone_from(nick);
+                info!(network = %self.config.name, nick = %self.nick, "IRC bridge registered");
+                for m in &self.channels {
+                    self.send(command("JOIN", vec![m.remote.clone()]));
+                }
+            }
+            ("433", _) if !self.registered => {
+                self.nick.push('_');
+                self.send(command("NICK", vec![self.nick.clone()]));
+            }
+            ("JOIN", [channel, ..]) => {
+                if let Some(idx) = self.remote_mapping(channel)
+                    && !self.is_ours(source)
+                {
+                    self.ghost_join(source, idx).await;
+                }
+            }
+            ("353", [_, _, channel, names]) => {
+                if let Some(idx) = self.remote_mapping(channel) {
+                    for name in names.split_whitespace() {
+                        let name = name.trim_start_matches(NAME_PREFIXES);
+                        if !self.is_ours(name) {
+                            self.ghost_join(name, idx).await;
+                        }
+                    }
+                }
+            }
+            ("PART", [channel, rest @ ..]) => {
+                if let Some(idx) = self.remote_mapping(channel) {
+                    self.ghost_part(source, idx, rest.first().cloned()).await;
+                }
+            }
+            ("KICK", [channel, victim, rest @ ..]) => {
+                let Some(idx) = self.remote_mapping(channel) else {
+                    return;
+                };
+                if victim.eq_ignore_ascii_case(&self.nick) {
+                    self.send(command("JOIN", vec![channel.clone()]));
+                } else {
+                    let reason = rest.first().map_or("", String::as_str);
+                    self.ghost_part(victim, idx, Some(format!("Kicked by {source}: {reason}")))
+                        .await;
+                }
+            }
+            ("QUIT", _) => self.ghost_quit(source).await,
+            ("NICK", [new_nick, ..]) => {
+                if source.eq_ignore_ascii_case(&self.nick) {
+                    self.nick.clone_from(new_nick);
+                } else {
+                    self.ghost_rename(source, new_nick).await;
+                }
+            }
+            ("TOPIC", [channel, topic]) => {
+                if let Some(idx) = self.remote_mapping(channel)
+                    && !self.is_ours(source)
+                {
+                    self.set_topic(idx, Some(source), topic).await;
+                }
+            }
+            ("332", [_, channel, topic]) => {
+                if let Some(idx) = self.remote_mapping(channel) {
+                    self.set_topic(idx, None, topic).await;
+                }
+            }
+            ("PRIVMSG", [target, text]) => {
+                if let Some(idx) = self.remote_mapping(target)
+                    && !self.is_ours(source)
+                {
+                    self.relay_to_concord(source, idx, text).await;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Bring a remote user into a Concord channel, connecting them first if needed.
+    async fn ghost_join(&mut self, remote: &str, idx: usize) {
+        if remote.is_empty() {
+            return;
+        }
+        let key = remote.to_ascii_lowercase();
+        if !self.ghosts.contains_key(&key) {
+            let Some(nick) = free_nick(&self.engine, remote, &self.remote_suffix) else {
+                warn!(network = %self.config.name, %remote, "IRC bridge: no free nickname for remote user");
+                return;
+            };
+            let (session_id, mut events) = match self.engine.connect(
+                None,
+                nick.clone(),
+                Protocol::Bridge,
+                None,
+            ) {
+                Ok(session) => session,
+                Err(e) => {
+                    warn!(network = %self.config.name, %remote, error = %e, "IRC bridge: failed to connect remote user");
+                    return;
+                }
+            };
+            // Nothing reads a ghost's events; drain them until it disconnects
+            tokio::spawn(async move { while events.recv().await.is_some() {} });
+            self.ghosts.insert(
+                key.clone(),
+                Ghost {
+                    session_id,
+                    nick,
+                    channels: HashSet::new(),
+                },
+            );
+        }
+
+        let Some(ghost) = self.ghosts.get_mut(&key) else {
+            return;
+        };
+        let m = &self.channels[idx];
+        if ghost.channels.insert(idx)
+            && let Err(e) = self
+                .engine
+                .join_channel(ghost.session_id, &m.server_id, &m.channel)
+                .await
+        {
+            warn!(network = %self.config.name, %remote, channel = %m.channel, error = %e, "IRC bridge: remote user failed to join");
+            ghost.channels.remove(&idx);
+        }
+        if ghost.channels.is_empty() {
+            self.ghost_quit(remote).await;
+        }
+    }
+
+    /// Take a remote user out of a Concord channel, disconnecting them once
+    /// they are in none.
+    async fn ghost_part(&mut self, remote: &str, idx: usize, reason: Option<String>) {
+        let key = remote.to_ascii_lowercase();
+        let Some(ghost) = self.ghosts.get_mut(&key) else {
+            return;
+        };
+        if ghost.channels.remove(&idx) {
+            let m = &self.channels[idx];
+            let _ = self
+                .engine
+                .part_channel(ghost.session_id, &m.server_id, &m.channel, reason);
+        }
+        if ghost.channels.is_empty() {
+            self.ghost_quit(remote).await;
+        }
+    }
+
+    async fn ghost_quit(&mut self, remote: &str) {
+        if let Some(ghost) = self.ghosts.remove(&remote.to_ascii_lowercase()) {
+            self.engine.disconnect(ghost.session_id).await;
+        }
+    }
+
+    async fn ghost_rename(&mut self, old: &str, new: &str) {
+        let Some(mut ghost) = self.ghosts.remove(&old.to_ascii_lowercase()) else {
+            return;
+        };
+        if let Some(nick) = free_nick(&self.engine, new, &self.remote_suffix) {
+            match self.engine.change_nickname(ghost.session_id, &nick).await {
+                Ok(_) => ghost.nick = nick,
+                Err(e) => {
+                    warn!(network = %self.config.name, %old, %new, error = %e, "IRC bridge: failed to rename remote user");
+                }
+            }
+        }
+        self.ghosts.insert(new.to_ascii_lowercase(), ghost);
+    }
+
+    /// Mirror a remote topic, set by `source` or (for RPL_TOPIC) the bridge itself.
+    async fn set_topic(&self, idx: usize, source: Option<&str>, topic: &str) {
+        let m = &self.channels[idx];
+        let topic = formatter::irc_to_markdown(topic);
+        let current = self
+            .engine
+            .list_channels(&m.server_id)
+            .into_iter()
+            .find(|c| c.name == m.channel)
+            .map(|c| c.topic);
+        if current.as_deref() == Some(topic.as_str()) {
+            return;
+        }
+
+        let session_id = source
+            .and_then(|s| self.ghosts.get(&s.to_ascii_lowercase()))
+            .map_or(self.listener, |g| g.session_id);
+        if let Err(e) = self
+            .engine
+            .set_topic(session_id, &m.server_id, &m.channel, topic)
+            .await
+        {
+            warn!(network = %self.config.name, channel = %m.channel, error = %e, "IRC bridge: failed to mirror topic");
+        }
+    }
+
+    async fn relay_to_concord(&mut self, source: &str, idx: usize, text: &str) {
+        // CTCP ACTION is stored as /me; other CTCP requests aren't messages
+        let content = match text.strip_prefix("\x01ACTION ") {
+            Some(action) => format!(
+                "/me {}",
+                formatter::irc_to_markdown(action.trim_end_matches('\x01'))
+            ),
+            None if text.starts_with('\x01') => return,
+            None => formatter::irc_to_markdown(text),
+        };
+        if content.trim().is_empty() {
+            return;
+        }
+
+        // Messages can arrive from users whose JOIN we missed
+        self.ghost_join(source, idx).await;
+        let Some(session_id) = self
+            .ghosts
+            .get(&source.to_ascii_lowercase())
+            .map(|g| g.session_id)
+        else {
+            return;
+        };
+        let m = &self.channels[idx];
+        if let Err(e) = self
+            .engine
+            .send_message(
+                session_id,
+                &m.server_id,
+                &m.channel,
+                &content,
+                None,
+                None,
+                None,
+            )
+            .await
+        {
+            warn!(network = %self.config.name, %source, channel = %m.channel, error = %e, "IRC bridge: failed to relay message");
+        }
+    }
+
+    // ── Concord → Network ──
+
+    fn handle_event(&mut self, event: ChatEvent) {
+        match event {
+            ChatEvent::Message {
+                server_id: Some(server_id),
+                from,
+                target,
+                content,
+                attachments,
+                ..
+            } => {
+                let Some(idx) = self.concord_mapping(&server_id, &target) else {
+                    return;
+                };
+                if self.is_bridged(&from) {
+                    return;
+                }
+                let mut text = formatter::markdown_to_irc(&content);
+                for att in attachments.iter().flatten() {
+                    text.push('\n');
+                    text.push_str(&att.url);
+                }
+                if self.config.puppet {
+                    self.puppet_say(&from, idx, &text);
+                } else {
+                    let remote = &self.channels[idx].remote;
+                    for line in client_privmsg_lines(remote, &relay_text(&from, &text)) {
+                        self.send(line);
+                    }
+                }
+            }
+            ChatEvent::TopicChange {
+                server_id,
+                channel,
+                set_by,
+                topic,
+            } => {
+                if let Some(idx) = self.concord_mapping(&server_id, &channel)
+                    && !self.is_bridged(&set_by)
+                {
+                    let remote = self.channels[idx].remote.clone();
+                    self.send(command(
+                        "TOPIC",
+                        vec![remote, formatter::markdown_to_irc(&topic)],
+                    ));
+                }
+            }
+            ChatEvent::Part {
+                nickname,
+                server_id,
+                channel,
+                reason,
+            } => {
+                let Some(idx) = self.concord_mapping(&server_id, &channel) else {
+                    return;
+                };
+                if let Some(puppet) = self.puppets.get_mut(&nickname)
+                    && puppet.channels.remove(&idx)
+                {
+                    let mut params = vec![self.channels[idx].remote.clone()];
+                    params.extend(reason);
+                    let _ = puppet.out.try_send(command("PART", params));
+                }
+            }
+            ChatEvent::Quit { nickname, .. } => {
+                if let Some(puppet) = self.puppets.remove(&nickname) {
+                    let _ = puppet.out.try_send("QUIT :Left Concord".into());
+                }
+            }
+            ChatEvent::NickChange { old_nick, new_nick } => {
+                if let Some(puppet) = self.puppets.remove(&old_nick) {
+                    let nick = puppet_nick(&new_nick, &self.config.puppet_suffix);
+                    let _ = puppet.out.try_send(command("NICK", vec![nick]));
+                    self.puppets.insert(new_nick, puppet);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Send a Concord user's message from their puppet, connecting it first if needed.
+    fn puppet_say(&mut self, from: &str, idx: usize, text: &str) {
+        if self.puppets.get(from).is_none_or(|p| p.out.is_closed()) {
+            let (out, lines) = mpsc::channel(MAX_OUTBOUND_QUEUE);
+            tokio::spawn(run_puppet(
+                self.config.clone(),
+                puppet_nick(from, &self.config.puppet_suffix),
+                lines,
+                self.puppet_nicks.clone(),
+                self.cancel.clone(),
+            ));
+            self.puppets.insert(
+                from.to_string(),
+                Puppet {
+                    out,
+                    channels: HashSet::new(),
+                },
+            );
+        }
+        let Some(puppet) = self.puppets.get_mut(from) else {
+            return;
+        };
+
+        let remote = &self.channels[idx].remote;
+        let mut lines = client_privmsg_lines(remote, text);
+        if puppet.channels.insert(idx) {
+            lines.insert(0, command("JOIN", vec![remote.clone()]));
+        }
+        for line in lines {
+            if puppet.out.try_send(line).is_err() {
+                warn!(network = %self.config.name, %from, "IRC bridge: puppet queue full, dropping line");
+                break;
+            }
+        }
+    }
+}
+
+/// Run one puppet connection. Lines from `lines` are sent once the network
+/// has accepted the puppet; it quits when the sender is dropped.
+async fn run_puppet(
+    config: Arc<IrcBridgeConfig>,
+    mut nick: String,
+    mut lines: mpsc::Receiver<String>,
+    puppet_nicks: Arc<DashSet<String>>,
+    cancel: CancellationToken,
+) {
+    let stream = match connect(&config.address, config.tls).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            warn!(network = %config.name, %nick, error = %e, "IRC bridge: puppet failed to connect");
+            return;
+        }
+    };
+    let (reader, writer) = tokio::io::split(stream);
+    let out = spawn_writer(writer);
+    let mut reader = BufReader::new(reader);
+
+    puppet_nicks.insert(nick.to_ascii_lowercase());
+    for line in registration(&config, &nick) {
+        let _ = out.send(line).await;
+    }
+
+    let mut registered = false;
+    let mut buf = String::new();
+    loop {
+        buf.clear();
+        let input = tokio::select! {
+            _ = cancel.cancelled() => Input::Cancelled,
+            read = read_bounded_line(&mut reader, &mut buf) => Input::Line(read),
+            line = lines.recv(), if registered => {
+                match line {
+                    Some(line) => {
+                        let _ = out.send(line).await;
+                        continue;
+                    }
+                    None => Input::Cancelled,
+                }
+            }
+        };
+        let read = match input {
+            Input::Line(Ok(n)) if n > 0 => buf.trim_end(),
+            Input::Cancelled => {
+                let _ = out.send("QUIT :Bridge shutting down".into()).await;
+                break;
+            }
+            _ => break,
+        };
+
+        let Ok(msg) = IrcMessage::parse(split_tags(read).1) else {
+            continue;
+        };
+        let source = msg
+            .prefix
+            .as_deref()
+            .map_or("", |p| p.split('!').next().unwrap_or(p));
+        match (msg.command.as_str(), msg.params.as_slice()) {
+            ("PING", params) => {
+                let token = params.first().cloned().unwrap_or_default();
+                let _ = out.send(command("PONG", vec![token])).await;
+            }
+            ("001", _) => registered = true,
+            ("433", _) if !registered => {
+                puppet_nicks.remove(&nick.to_ascii_lowercase());
+                nick.push('_');
+                puppet_nicks.insert(nick.to_ascii_lowercase());
+                let _ = out.send(command("NICK", vec![nick.clone()])).await;
+            }
+            ("NICK", [new_nick, ..]) if source.eq_ignore_ascii_case(&nick) => {
+                puppet_nicks.remove(&nick.to_ascii_lowercase());
+                nick.clone_from(new_nick);
+                puppet_nicks.insert(nick.to_ascii_lowercase());
+            }
+            _ => {}
+        }
+    }
+    puppet_nicks.remove(&nick.to_ascii_lowercase());
+}
+
+/// PASS, NICK and USER lines to register with the network.
+fn registration(config: &IrcBridgeConfig, nick: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(password) = &config.password {
+        lines.push(command("PASS", vec![password.clone()]));
+    }
+    lines.push(command("NICK", vec![nick.to_string()]));
+    lines.push(command(
+        "USER",
+        vec![
+            nick.to_string(),
+            "0".into(),
+            "*".into(),
+            "Concord bridge".into(),
+        ],
+    ));
+    lines
+}
+
+fn command(command: &str, params: Vec<String>) -> String {
+    IrcMessage {
+        prefix: None,
+        command: command.into(),
+        params,
+    }
+    .format()
+}
+
+/// A Concord message as relayed by the single relay client: every line
+/// attributed as `<nick> text`, and actions as `* nick text`.
+fn relay_text(from: &str, text: &str) -> String {
+    match text.strip_prefix("/me ") {
+        Some(action) => format!("* {from} {action}"),
+        None => text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| format!("<{from}> {l}"))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// A free Concord nickname for `base` with `suffix`.
+fn free_nick(engine: &ChatEngine, base: &str, suffix: &str) -> Option<String> {
+    nick_candidates(base, suffix).find(|nick| engine.is_nick_available(nick))
+}
+
+/// A Concord user's nickname on the remote network, with characters IRC
+/// doesn't allow replaced by `_`.
+fn puppet_nick(nick: &str, suffix: &str) -> String {
+    let mut irc_nick: String = nick
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_[]\\`^{|}".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if irc_nick.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+        irc_nick.insert(0, '_');
+    }
+    irc_nick.push_str(suffix);
+    irc_nick
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BridgedChannel;
+    use crate::engine::chat_engine::DEFAULT_SERVER_ID;
+    use tokio::io::AsyncBufReadExt;
+    use tokio::net::TcpListener;
+    use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+
+    /// One client's connection to the stand-in IRC server.
+    struct StandIn {
+        lines: tokio::io::Lines<BufReader<OwnedReadHalf>>,
+        writer: OwnedWriteHalf,
+    }
+
+    impl StandIn {
+        async fn accept(listener: &TcpListener) -> Self {
+            let (stream, _) = within(listener.accept()).await.unwrap();
+            let (reader, writer) = stream.into_split();
+            Self {
+                lines: BufReader::new(reader).lines(),
+                writer,
+            }
+        }
+
+        /// Read lines until one starts with `prefix`.
+        async fn expect(&mut self, prefix: &str) -> String {
+            loop {
+                let line = within(self.lines.next_line())
+                    .await
+                    .unwrap()
+                    .expect("connection closed");
+                if line.starts_with(prefix) {
+                    return line;
+                }
+            }
+        }
+
+        async fn send(&mut self, line: &str) {
+            self.writer
+                .write_all(format!("{line}\r\n").as_bytes())
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn within<F: Future>(future: F) -> F::Output {
+        tokio::time::timeout(Duration::from_secs(5), future)
+            .await
+            .expect("timed out")
+    }
+
+    async fn next_event(
+        events: &mut mpsc::Receiver<ChatEvent>,
+        pred: impl Fn(&ChatEvent) -> bool,
+    ) -> ChatEvent {
+        loop {
+            let event = within(events.recv()).await.expect("session closed");
+            if pred(&event) {
+                return event;
+            }
+        }
+    }
+
+    fn config(address: String, puppet: bool) -> IrcBridgeConfig {
+        IrcBridgeConfig {
+            name: "libera".into(),
+            address,
+            tls: false,
+            nickname: "concord".into(),
+            password: None,
+            puppet,
+            puppet_suffix: "[c]".into(),
+            remote_suffix: None,
+            channels: vec![BridgedChannel {
+                remote: "#Rust".into(),
+                server: None,
+                channel: "#rust".into(),
+            }],
+        }
+    }
+
+    /// A Concord user in #rust, and a bridge connected to a stand-in server.
+    async fn setup(
+        puppet: bool,
+    ) -> (
+        Arc<ChatEngine>,
+        SessionId,
+        mpsc::Receiver<ChatEvent>,
+        TcpListener,
+        CancellationToken,
+    ) {
+        let engine = Arc::new(ChatEngine::new(None, 4000, 100));
+        let (alice, events) = engine
+            .connect(None, "alice".into(), Protocol::Irc, None)
+            .unwrap();
+        engine
+            .join_channel(alice, DEFAULT_SERVER_ID, "#rust")
+            .await
+            .unwrap();
+
+        let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = server.local_addr().unwrap().to_string();
+        let cancel = CancellationToken::new();
+        tokio::spawn(run_bridge(
+            engine.clone(),
+            config(address, puppet),
+            cancel.clone(),
+        ));
+        (engine, alice, events, server, cancel)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_relay_mirrors_remote_channel() {
+        let (engine, alice, mut events, server, cancel) = setup(false).await;
+
+        let mut remote = StandIn::accept(&server).await;
+        remote.expect("NICK concord").await;
+        remote.expect("USER concord 0 *").await;
+        remote
+            .send(":irc.test 433 * concord :Nickname is already in use")
+            .await;
+        remote.expect("NICK concord_").await;
+        remote.send(":irc.test 001 concord_ :Welcome").await;
+        remote.expect("JOIN #Rust").await;
+        remote.send(":concord_!u@h JOIN #Rust").await;
+        remote
+            .send(":irc.test 332 concord_ #Rust :Rust \x02talk\x02")
+            .await;
+        remote
+            .send(":irc.test 353 concord_ = #Rust :@concord_ +bob")
+            .await;
+
+        let event = next_event(&mut events, |e| matches!(e, ChatEvent::TopicChange { .. })).await;
+        let ChatEvent::TopicChange { set_by, topic, .. } = event else {
+            unreachable!()
+        };
+        assert_eq!(set_by, "libera");
+        assert_eq!(topic, "Rust **talk**");
+        next_event(
+            &mut events,
+            |e| matches!(e, ChatEvent::Join { nickname, .. } if nickname == "bob-libera"),
+        )
+        .await;
+
+        remote.send(":bob!b@h PRIVMSG #Rust :\x1Dhello\x1D").await;
+        let event = next_event(&mut events, |e| matches!(e, ChatEvent::Message { .. })).await;
+        let ChatEvent::Message { from, content, .. } = event else {
+            unreachable!()
+        };
+        assert_eq!((from.as_str(), content.as_str()), ("bob-libera", "*hello*"));
+
+        engine
+            .send_message(
+                alice,
+                DEFAULT_SERVER_ID,
+                "#rust",
+                "**hi** bob",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            remote.expect("PRIVMSG").await,
+            "PRIVMSG #Rust :<alice> \x02hi\x02 bob"
+        );
+
+        remote.send(":bob!b@h NICK bobby").await;
+        let event = next_event(&mut events, |e| matches!(e, ChatEvent::NickChange { .. })).await;
+        let ChatEvent::NickChange { old_nick, new_nick } = event else {
+            unreachable!()
+        };
+        assert_eq!(
+            (old_nick.as_str(), new_nick.as_str()),
+            ("bob-libera", "bobby-libera")
+        );
+
+        remote.send(":bobby!b@h PART #Rust :bye").await;
+        let event = next_event(&mut events, |e| matches!(e, ChatEvent::Part { .. })).await;
+        let ChatEvent::Part {
+            nickname, reason, ..
+        } = event
+        else {
+            unreachable!()
+        };
+        assert_eq!(nickname, "bobby-libera");
+        assert_eq!(reason.as_deref(), Some("bye"));
+
+        remote.send("PING :irc.test").await;
+        remote.expect("PONG irc.test").await;
+        // Having left every bridged channel, bobby is disconnected
+        assert!(engine.is_nick_available("bobby-libera"));
+
+        cancel.cancel();
+        remote.expect("QUIT").await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_puppets_speak_for_concord_users() {
+        let (engine, alice, mut events, server, cancel) = setup(true).await;
+
+        let mut relay = StandIn::accept(&server).await;
+        relay.expect("USER").await;
+        relay.send(":irc.test 001 concord :Welcome").await;
+        relay.expect("JOIN #Rust").await;
+
+        engine
+            .send_message(
+                alice,
+                DEFAULT_SERVER_ID,
+                "#rust",
+                "hi there",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let mut puppet = StandIn::accept(&server).await;
+        puppet.expect("NICK alice[c]").await;
+        puppet.expect("USER").await;
+        puppet.send(":irc.test 001 alice[c] :Welcome").await;
+        puppet.expect("JOIN #Rust").await;
+        assert_eq!(puppet.expect("PRIVMSG").await, "PRIVMSG #Rust :hi there");
+
+        // The puppet's own join and message are not mirrored back
+        relay.send(":alice[c]!u@h JOIN #Rust").await;
+        relay.send(":alice[c]!u@h PRIVMSG #Rust :hi there").await;
+        relay.send(":carol!c@h PRIVMSG #Rust :hey").await;
+        let event = next_event(
+            &mut events,
+            |e| matches!(e, ChatEvent::Message { from, .. } if from != "alice"),
+        )
+        .await;
+        let ChatEvent::Message { from, .. } = event else {
+            unreachable!()
+        };
+        assert_eq!(from, "carol-libera");
+        assert!(engine.is_nick_available("alice_c_-libera"));
+
+        engine.change_nickname(alice, "alicia").await.unwrap();
+        assert_eq!(puppet.expect("NICK").await, "NICK alicia[c]");
+        engine
+            .part_channel(alice, DEFAULT_SERVER_ID, "#rust", None)
+            .unwrap();
+        puppet.expect("PART #Rust").await;
+
+        cancel.cancel();
+        puppet.expect("QUIT").await;
+    }
+
+    #[test]
+    fn test_relay_text_attributes_each_line() {
+        assert_eq!(
+            relay_text("alice", "hi\n\nthere"),
+            "<alice> hi\n<alice> there"
+        );
+        assert_eq!(relay_text("alice", "/me waves"), "* alice waves");
+    }
+
+    #[test]
+    fn test_bridged_nicknames() {
+        assert_eq!(puppet_nick("alice.b", "[c]"), "alice_b[c]");
+        assert_eq!(puppet_nick("9lives", "[c]"), "_9lives[c]");
+
+        let engine = ChatEngine::new(None, 4000, 100);
+        let _session = engine
+            .connect(None, "bob-libera".into(), Protocol::Irc, None)
+            .unwrap();
+        assert_eq!(
+            free_nick(&engine, "bob", "-libera").as_deref(),
+            Some("bob_-libera")
+        );
+    }
+}
@@ -0,0 +1,1579 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use dashmap::{DashMap, DashSet};
+use reqwest::Method;
+use serde_json::{Value, json};
+use sqlx::SqlitePool;
+use tokio::sync::{Mutex, mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::{nick_candidates, resolve_channel};
+use crate::config::MatrixBridgeConfig;
+use crate::db::queries;
+use crate::db::queries::users::CreateOAuthUser;
+use crate::engine::chat_engine::{ChatEngine, DEFAULT_SERVER_ID};
+use crate::engine::events::{AttachmentInfo, ChatEvent, MessageId, ReplyInfo, SessionId};
+use crate::engine::user_session::Protocol;
+
+/// How long a Matrix message waits for Concord to assign it an ID.
+const ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Transaction IDs remembered so the homeserver's retries aren't replayed.
+const SEEN_TRANSACTIONS: usize = 1000;
+
+/// OAuth provider recorded on the Concord accounts of Matrix users.
+const MATRIX_PROVIDER: &str = "matrix";
+
+/// Run the Matrix bridge until `cancel` fires: serve the appservice API on
+/// the configured address and relay Concord events to the homeserver.
+pub async fn run_matrix_bridge(
+    engine: Arc<ChatEngine>,
+    config: MatrixBridgeConfig,
+    cancel: CancellationToken,
+) {
+    let listener = match tokio::net::TcpListener::bind(&config.address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!(address = %config.address, error = %e, "Matrix bridge: failed to bind");
+            return;
+        }
+    };
+    let address = config.address.clone();
+    let (bridge, events) = match MatrixBridge::start(engine, config).await {
+        Ok(started) => started,
+        Err(e) => {
+            warn!(error = %e, "Matrix bridge: failed to start");
+            return;
+        }
+    };
+    info!(%address, "Matrix appservice listening");
+
+    tokio::spawn(bridge.clone().relay_events(events, cancel.clone()));
+    let shutdown = cancel.clone();
+    if let Err(e) = axum::serve(listener, router(bridge.clone()))
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await
+    {
+        warn!(error = %e, "Matrix bridge: appservice server failed");
+    }
+    bridge.shutdown().await;
+}
+
+/// The appservice API the homeserver pushes events and queries to.
+pub fn router(bridge: Arc<MatrixBridge>) -> Router {
+    Router::new()
+        .route("/_matrix/app/v1/transactions/{txn_id}", put(transaction))
+        .route("/_matrix/app/v1/users/{user_id}", get(query_user))
+        .route("/_matrix/app/v1/rooms/{alias}", get(query_alias))
+        .with_state(bridge)
+}
+
+/// Client-server API calls made with the appservice's token.
+struct Homeserver {
+    http: reqwest::Client,
+    base_url: String,
+    as_token: String,
+    txn_prefix: i64,
+    next_txn: AtomicU64,
+}
+
+impl Homeserver {
+    /// Call the homeserver, masquerading as `as_user` when given (the bridge's
+    /// own user otherwise).
+    async fn call(
+        &self,
+        method: Method,
+        path: &str,
+        as_user: Option<&str>,
+        body: &Value,
+    ) -> Result<Value, String> {
+        let mut request = self
+            .http
+            .request(method, format!("{}{path}", self.base_url))
+            .bearer_auth(&self.as_token)
+            .json(body);
+        if let Some(user_id) = as_user {
+            request = request.query(&[("user_id", user_id)]);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Matrix request failed: {e}"))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if status.is_success() {
+            Ok(body)
+        } else {
+            Err(format!(
+                "Homeserver returned {}: {} {}",
+                status.as_u16(),
+                body["errcode"].as_str().unwrap_or("M_UNKNOWN"),
+                body["error"].as_str().unwrap_or_default()
+            ))
+        }
+    }
+
+    fn txn_id(&self) -> String {
+        let n = self.next_txn.fetch_add(1, Ordering::Relaxed);
+        format!("concord.{}.{n}", self.txn_prefix)
+    }
+
+    /// Register a user in the appservice's namespace; existing users are fine.
+    async fn register(&self, localpart: &str) -> Result<(), String> {
+        let body = json!({ "type": "m.login.application_service", "username": localpart });
+        match self
+            .call(Method::POST, "/_matrix/client/v3/register", None, &body)
+            .await
+        {
+            Err(e) if !e.contains("M_USER_IN_USE") => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    async fn set_displayname(&self, user_id: &str, name: &str) -> Result<(), String> {
+        let path = format!(
+            "/_matrix/client/v3/profile/{}/displayname",
+            urlencoding::encode(user_id)
+        );
+        self.call(
+            Method::PUT,
+            &path,
+            Some(user_id),
+            &json!({ "displayname": name }),
+        )
+        .await
+        .map(drop)
+    }
+
+    async fn join(&self, room_id: &str, as_user: Option<&str>) -> Result<(), String> {
+        let path = format!("/_matrix/client/v3/join/{}", urlencoding::encode(room_id));
+        self.call(Method::POST, &path, as_user, &json!({}))
+            .await
+            .map(drop)
+    }
+
+    async fn invite(&self, room_id: &str, user_id: &str) -> Result<(), String> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/invite",
+            urlencoding::encode(room_id)
+        );
+        self.call(Method::POST, &path, None, &json!({ "user_id": user_id }))
+            .await
+            .map(drop)
+    }
+
+    /// Send an event, returning its ID.
+    async fn send(
+        &self,
+        room_id: &str,
+        event_type: &str,
+        content: &Value,
+        as_user: &str,
+    ) -> Result<String, String> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/send/{event_type}/{}",
+            urlencoding::encode(room_id),
+            self.txn_id()
+        );
+        let response = self
+            .call(Method::PUT, &path, Some(as_user), content)
+            .await?;
+        response["event_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "Homeserver did not return an event ID".to_string())
+    }
+
+    async fn redact(
+        &self,
+        room_id: &str,
+        event_id: &str,
+        as_user: Option<&str>,
+    ) -> Result<(), String> {
+        let path = format!(
+            "/_matrix/client/v3/rooms/{}/redact/{}/{}",
+            urlencoding::encode(room_id),
+            urlencoding::encode(event_id),
+            self.txn_id()
+        );
+        self.call(Method::PUT, &path, as_user, &json!({}))
+            .await
+            .map(drop)
+    }
+
+    /// Create a public room with the alias `#{alias_localpart}:domain`.
+    async fn create_room(&self, alias_localpart: &str, name: &str) -> Result<String, String> {
+        let body = json!({
+            "room_alias_name": alias_localpart,
+            "name": name,
+            "preset": "public_chat",
+        });
+        let response = self
+            .call(Method::POST, "/_matrix/client/v3/createRoom", None, &body)
+            .await?;
+        response["room_id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "Homeserver did not return a room ID".to_string())
+    }
+}
+
+/// A Matrix user, present in Concord as a bridge session.
+struct Ghost {
+    session_id: SessionId,
+    nick: String,
+    channels: HashSet<(String, String)>,
+}
+
+/// A running Matrix application service.
+pub struct MatrixBridge {
+    engine: Arc<ChatEngine>,
+    pool: SqlitePool,
+    config: MatrixBridgeConfig,
+    homeserver: Homeserver,
+    /// Bridged rooms: room ID → (server ID, channel).
+    rooms: DashMap<String, (String, String)>,
+    /// Session that receives events from every bridged channel.
+    listener: SessionId,
+    listener_nick: String,
+    ghosts: Mutex<HashMap<String, Ghost>>,
+    ghost_nicks: DashSet<String>,
+    /// Virtual users already registered, and the rooms they have joined.
+    registered: DashSet<String>,
+    joined: DashSet<(String, String)>,
+    /// Matrix messages waiting for their Concord ID, by event ID.
+    pending: Arc<DashMap<String, oneshot::Sender<MessageId>>>,
+    seen_transactions: std::sync::Mutex<VecDeque<String>>,
+}
+
+impl MatrixBridge {
+    /// Connect the bridge to the engine and homeserver. Returns the bridge and
+    /// the event stream to pass to [`MatrixBridge::relay_events`].
+    pub async fn start(
+        engine: Arc<ChatEngine>,
+        config: MatrixBridgeConfig,
+    ) -> Result<(Arc<Self>, mpsc::Receiver<ChatEvent>), String> {
+        let pool = engine
+            .get_db()
+            .ok_or("The Matrix bridge requires a database")?;
+
+        let rooms = DashMap::new();
+        for row in queries::matrix::list_rooms(&pool).await.map_err(db_error)? {
+            rooms.insert(row.room_id, (row.server_id, row.channel_name));
+        }
+        for bridged in &config.rooms {
+            match resolve_channel(&engine, bridged) {
+                Ok(mapping) => {
+                    rooms.insert(bridged.remote.clone(), mapping);
+                }
+                Err(server) => warn!(%server, "Matrix bridge: unknown Concord server"),
+            }
+        }
+
+        let listener_nick = nick_candidates("matrix", "")
+            .find(|nick| engine.is_nick_available(nick))
+            .ok_or("No free nickname for the Matrix bridge")?;
+        let (listener, events) =
+            engine.connect(None, listener_nick.clone(), Protocol::Bridge, None)?;
+
+        let homeserver = Homeserver {
+            http: reqwest::Client::new(),
+            base_url: config.homeserver_url.trim_end_matches('/').to_string(),
+            as_token: config.as_token.clone(),
+            txn_prefix: chrono::Utc::now().timestamp_millis(),
+            next_txn: AtomicU64::new(0),
+        };
+        if let Err(e) = homeserver.register(&config.sender_localpart).await {
+            warn!(error = %e, "Matrix bridge: failed to register the bridge user");
+        }
+
+        let bridge = Arc::new(Self {
+            engine,
+            pool,
+            config,
+            homeserver,
+            rooms,
+            listener,
+            listener_nick,
+            ghosts: Mutex::new(HashMap::new()),
+            ghost_nicks: DashSet::new(),
+            registered: DashSet::new(),
+            joined: DashSet::new(),
+            pending: Arc::new(DashMap::new()),
+            seen_transactions: std::sync::Mutex::new(VecDeque::new()),
+        });
+        let rooms: Vec<_> = bridge
+            .rooms
+            .iter()
+            .map(|r| (r.key().clone(), r.value().clone()))
+            .collect();
+        for (room_id, (server_id, channel)) in rooms {
+            bridge.watch_room(&room_id, &server_id, &channel).await;
+        }
+        Ok((bridge, events))
+    }
+
+    /// Relay the listener session's events to Matrix until `cancel` fires.
+    pub async fn relay_events(
+        self: Arc<Self>,
+        mut events: mpsc::Receiver<ChatEvent>,
+        cancel: CancellationToken,
+    ) {
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = cancel.cancelled() => None,
+            };
+            let Some(event) = event else { break };
+            if let Err(e) = self.relay(event).await {
+                warn!(error = %e, "Matrix bridge: failed to relay to Matrix");
+            }
+        }
+    }
+
+    /// Disconnect the listener and every Matrix user's session.
+    pub async fn shutdown(&self) {
+        for (_, ghost) in self.ghosts.lock().await.drain() {
+            self.engine.disconnect(ghost.session_id).await;
+        }
+        self.engine.disconnect(self.listener).await;
+    }
+
+    /// Join the bridge's user to `room_id` and its listener to the channel.
+    async fn watch_room(&self, room_id: &str, server_id: &str, channel: &str) {
+        if let Err(e) = self.homeserver.join(room_id, None).await {
+            warn!(%room_id, error = %e, "Matrix bridge: failed to join room");
+        }
+        if let Err(e) = self
+            .engine
+            .join_channel(self.listener, server_id, channel)
+            .await
+        {
+            warn!(%channel, error = %e, "Matrix bridge: failed to join channel");
+        }
+    }
+
+    fn room_for(&self, server_id: &str, channel: &str) -> Option<String> {
+        self.rooms
+            .iter()
+            .find(|r| r.value().0 == server_id && r.value().1 == channel)
+            .map(|r| r.key().clone())
+    }
+
+    /// Whether a Concord nickname belongs to the bridge itself.
+    fn is_bridged(&self, nick: &str) -> bool {
+        nick == self.listener_nick || self.ghost_nicks.contains(nick)
+    }
+
+    /// Whether a Matrix user ID is the bridge's own user or one of its virtual users.
+    fn in_namespace(&self, user_id: &str) -> bool {
+        let Some((localpart, domain)) = user_id.strip_prefix('@').and_then(|u| u.split_once(':'))
+        else {
+            return false;
+        };
+        domain == self.config.domain
+            && (localpart.starts_with(&self.config.prefix)
+                || localpart == self.config.sender_localpart)
+    }
+
+    /// The Concord channel behind a room alias in the bridge's namespace,
+    /// e.g. `#concord_general:example.org` → `#general`.
+    fn alias_channel(&self, alias: &str) -> Option<String> {
+        let (localpart, domain) = alias.strip_prefix('#')?.split_once(':')?;
+        let name = localpart.strip_prefix(&self.config.prefix)?;
+        (domain == self.config.domain && !name.is_empty())
+            .then(|| format!("#{}", name.to_lowercase()))
+    }
+
+    /// The error response for a request without the homeserver's token, if any.
+    fn reject_token(
+        &self,
+        headers: &HeaderMap,
+        query: &HashMap<String, String>,
+    ) -> Option<Response> {
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .or(query.get("access_token").map(String::as_str));
+        match token {
+            Some(token) if token == self.config.hs_token => None,
+            Some(_) => Some(matrix_error(
+                StatusCode::FORBIDDEN,
+                "M_FORBIDDEN",
+                "Bad homeserver token",
+            )),
+            None => Some(matrix_error(
+                StatusCode::UNAUTHORIZED,
+                "M_UNAUTHORIZED",
+                "Missing homeserver token",
+            )),
+        }
+    }
+
+    /// Record a transaction ID, returning false if it was already handled.
+    fn first_delivery(&self, txn_id: &str) -> bool {
+        let mut seen = self
+            .seen_transactions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if seen.iter().any(|id| id == txn_id) {
+            return false;
+        }
+        if seen.len() >= SEEN_TRANSACTIONS {
+            seen.pop_front();
+        }
+        seen.push_back(txn_id.to_string());
+        true
+    }
+
+    // ── Concord → Matrix ────────────────────────────────────────
+
+    async fn relay(&self, event: ChatEvent) -> Result<(), String> {
+        match event {
+            ChatEvent::Message {
+                id,
+                server_id: Some(server_id),
+                from,
+                target,
+                content,
+                reply_to,
+                attachments,
+                ..
+            } if !self.is_bridged(&from) => {
+                let Some(room_id) = self.room_for(&server_id, &target) else {
+                    return Ok(());
+                };
+                let attachments = attachments.unwrap_or_default();
+                self.send_message(
+                    &room_id,
+                    id,
+                    &from,
+                    &content,
+                    reply_to.as_ref(),
+                    &attachments,
+                )
+                .await
+            }
+            ChatEvent::MessageEdit { id, content, .. } => {
+                self.send_edit(&id.to_string(), &content).await
+            }
+            ChatEvent::MessageDelete { id, .. } => self.send_redaction(&id.to_string()).await,
+            ChatEvent::ReactionAdd {
+                message_id,
+                nickname,
+                emoji,
+                ..
+            } if !self.is_bridged(&nickname) => {
+                self.send_reaction(&message_id.to_string(), &nickname, &emoji)
+                    .await
+            }
+            ChatEvent::ReactionRemove {
+                message_id,
+                nickname,
+                emoji,
+                ..
+            } if !self.is_bridged(&nickname) => {
+                self.send_reaction_removal(&message_id.to_string(), &nickname, &emoji)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The virtual Matrix user speaking for a Concord nickname.
+    fn virtual_user_id(&self, nick: &str) -> (String, String) {
+        let localpart: String = nick
+            .to_lowercase()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '=' | '/') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let localpart = format!("{}{localpart}", self.config.prefix);
+        let user_id = format!("@{localpart}:{}", self.config.domain);
+        (localpart, user_id)
+    }
+
+    /// Register a Concord user's virtual user if needed and join it to `room_id`.
+    async fn virtual_user(&self, nick: &str, room_id: &str) -> Result<String, String> {
+        let (localpart, user_id) = self.virtual_user_id(nick);
+        if !self.registered.contains(&user_id) {
+            self.homeserver.register(&localpart).await?;
+            if let Err(e) = self.homeserver.set_displayname(&user_id, nick).await {
+                warn!(%user_id, error = %e, "Matrix bridge: failed to set display name");
+            }
+            self.registered.insert(user_id.clone());
+        }
+        let membership = (user_id.clone(), room_id.to_string());
+        if !self.joined.contains(&membership) {
+            if let Err(e) = self.homeserver.join(room_id, Some(&user_id)).await {
+                // Rooms that aren't public need an invite from the bridge's user.
+                self.homeserver
+                    .invite(room_id, &user_id)
+                    .await
+                    .map_err(|_| e)?;
+                self.homeserver.join(room_id, Some(&user_id)).await?;
+            }
+            self.joined.insert(membership);
+        }
+        Ok(user_id)
+    }
+
+    async fn send_message(
+        &self,
+        room_id: &str,
+        id: MessageId,
+        from: &str,
+        text: &str,
+        reply_to: Option<&ReplyInfo>,
+        attachments: &[AttachmentInfo],
+    ) -> Result<(), String> {
+        let sender = self.virtual_user(from, room_id).await?;
+        let mut content = message_content(text, attachments);
+        if let Some(reply) = reply_to
+            && let Some(original) = queries::matrix::get_message_event(&self.pool, &reply.id)
+                .await
+                .map_err(db_error)?
+        {
+            content["m.relates_to"] = json!({ "m.in_reply_to": { "event_id": original.event_id } });
+        }
+        let event_id = self
+            .homeserver
+            .send(room_id, "m.room.message", &content, &sender)
+            .await?;
+        queries::matrix::insert_event(
+            &self.pool,
+            &event_id,
+            room_id,
+            &id.to_string(),
+            &sender,
+            None,
+        )
+        .await
+        .map_err(db_error)
+    }
+
+    async fn send_edit(&self, message_id: &str, text: &str) -> Result<(), String> {
+        let Some(original) = queries::matrix::get_message_event(&self.pool, message_id)
+            .await
+            .map_err(db_error)?
+        else {
+            return Ok(());
+        };
+        // Edits by Matrix users already happened on Matrix.
+        if !self.in_namespace(&original.sender) {
+            return Ok(());
+        }
+        let new_content = message_content(text, &[]);
+        let mut content = new_content.clone();
+        content["body"] = json!(format!(
+            "* {}",
+            new_content["body"].as_str().unwrap_or_default()
+        ));
+        content["m.new_content"] = new_content;
+        content["m.relates_to"] = json!({ "rel_type": "m.replace", "event_id": original.event_id });
+        self.homeserver
+            .send(
+                &original.room_id,
+                "m.room.message",
+                &content,
+                &original.sender,
+            )
+            .await
+            .map(drop)
+    }
+
+    async fn send_redaction(&self, message_id: &str) -> Result<(), String> {
+        let Some(original) = queries::matrix::get_message_event(&self.pool, message_id)
+            .await
+            .map_err(db_error)?
+        else {
+            return Ok(());
+        };
+        queries::matrix::delete_event(&self.pool, &original.event_id)
+            .await
+            .map_err(db_error)?;
+        // Virtual users redact their own messages; moderators' deletions of
+        // Matrix users' messages go through the bridge's user.
+        let as_user = self
+            .in_namespace(&original.sender)
+            .then_some(original.sender.as_str());
+        self.homeserver
+            .redact(&original.room_id, &original.event_id, as_user)
+            .await
+    }
+
+    async fn send_reaction(&self, message_id: &str, nick: &str, emoji: &str) -> Result<(), String> {
+        let Some(original) = queries::matrix::get_message_event(&self.pool, message_id)
+            .await
+            .map_err(db_error)?
+        else {
+            return Ok(());
+        };
+        let sender = self.virtual_user(nick, &original.room_id).await?;
+        let content = json!({
+            "m.relates_to": {
+                "rel_type": "m.annotation",
+                "event_id": original.event_id,
+                "key": emoji,
+            }
+        });
+        let event_id = self
+            .homeserver
+            .send(&original.room_id, "m.reaction", &content, &sender)
+            .await?;
+        queries::matrix::insert_event(
+            &self.pool,
+            &event_id,
+            &original.room_id,
+            message_id,
+            &sender,
+            Some(emoji),
+        )
+        .await
+        .map_err(db_error)
+    }
+
+    async fn send_reaction_removal(
+        &self,
+        message_id: &str,
+        nick: &str,
+        emoji: &str,
+    ) -> Result<(), String> {
+        let (_, sender) = self.virtual_user_id(nick);
+        let Some(reaction) =
+            queries::matrix::get_reaction_event(&self.pool, message_id, &sender, emoji)
+                .await
+                .map_err(db_error)?
+        else {
+            return Ok(());
+        };
+        queries::matrix::delete_event(&self.pool, &reaction.event_id)
+            .await
+            .map_err(db_error)?;
+        self.homeserver
+            .redact(&reaction.room_id, &reaction.event_id, Some(&sender))
+            .await
+    }
+
+    // ── Matrix → Concord ────────────────────────────────────────
+
+    async fn receive(&self, event: &Value) {
+        let (Some(kind), Some(room_id), Some(sender), Some(event_id)) = (
+            event["type"].as_str(),
+            event["room_id"].as_str(),
+            event["sender"].as_str(),
+            event["event_id"].as_str(),
+        ) else {
+            return;
+        };
+        if self.in_namespace(sender) {
+            return;
+        }
+        let Some((server_id, channel)) = self.rooms.get(room_id).map(|r| r.value().clone()) else {
+            return;
+        };
+        let content = &event["content"];
+        let room = (room_id, server_id.as_str(), channel.as_str());
+
+        let result = match kind {
+            "m.room.message" if content["m.relates_to"]["rel_type"] == "m.replace" => {
+                self.receive_edit(room, sender, content).await
+            }
+            "m.room.message" => self.receive_message(room, sender, event_id, content).await,
+            "m.reaction" => self.receive_reaction(room, sender, event_id, content).await,
+            "m.room.redaction" => match event["redacts"].as_str().or(content["redacts"].as_str()) {
+                Some(redacts) => self.receive_redaction(room, sender, redacts).await,
+                None => Ok(()),
+            },
+            "m.room.member" => {
+                let member = event["state_key"].as_str().unwrap_or(sender);
+                match content["membership"].as_str() {
+                    _ if self.in_namespace(member) => Ok(()),
+                    Some("join") => self.ghost(member, &server_id, &channel).await.map(drop),
+                    Some("leave" | "ban") => {
+                        self.ghost_leave(member, &server_id, &channel).await;
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            warn!(%event_id, error = %e, "Matrix bridge: failed to relay to Concord");
+        }
+    }
+
+    /// The Concord session for a Matrix user, joined to `channel`.
+    async fn ghost(
+        &self,
+        user_id: &str,
+        server_id: &str,
+        channel: &str,
+    ) -> Result<SessionId, String> {
+        let mut ghosts = self.ghosts.lock().await;
+        if !ghosts.contains_key(user_id) {
+            let (account_id, nick) = self.ghost_account(user_id).await?;
+            let (session_id, events) =
+                self.engine
+                    .connect(Some(account_id), nick.clone(), Protocol::Bridge, None)?;
+            tokio::spawn(forward_acks(events, self.pending.clone()));
+            self.ghost_nicks.insert(nick.clone());
+            ghosts.insert(
+                user_id.to_string(),
+                Ghost {
+                    session_id,
+                    nick,
+                    channels: HashSet::new(),
+                },
+            );
+        }
+        let ghost = ghosts.get_mut(user_id).expect("ghost was just inserted");
+        let key = (server_id.to_string(), channel.to_string());
+        if !ghost.channels.contains(&key) {
+            self.engine
+                .join_channel(ghost.session_id, server_id, channel)
+                .await?;
+            ghost.channels.insert(key);
+        }
+        Ok(ghost.session_id)
+    }
+
+    /// The Concord account linked to a Matrix user, created on first sight.
+    async fn ghost_account(&self, user_id: &str) -> Result<(String, String), String> {
+        if let Some(account) = queries::users::find_by_oauth(&self.pool, MATRIX_PROVIDER, user_id)
+            .await
+            .map_err(db_error)?
+        {
+            return Ok(account);
+        }
+        let localpart = user_id
+            .trim_start_matches('@')
+            .split(':')
+            .next()
+            .unwrap_or_default();
+        for nick in nick_candidates(localpart, &self.config.remote_suffix) {
+            if !self.engine.is_nick_available(&nick)
+                || queries::users::get_user_by_nickname(&self.pool, &nick)
+                    .await
+                    .map_err(db_error)?
+                    .is_some()
+            {
+                continue;
+            }
+            let account_id = Uuid::new_v4().to_string();
+            queries::users::create_with_oauth(
+                &self.pool,
+                &CreateOAuthUser {
+                    user_id: &account_id,
+                    username: &nick,
+                    email: None,
+                    avatar_url: None,
+                    oauth_id: &Uuid::new_v4().to_string(),
+                    provider: MATRIX_PROVIDER,
+                    provider_id: user_id,
+                },
+            )
+            .await
+            .map_err(db_error)?;
+            return Ok((account_id, nick));
+        }
+        Err(format!("No free nickname for {user_id}"))
+    }
+
+    async fn ghost_leave(&self, user_id: &str, server_id: &str, channel: &str) {
+        let mut ghosts = self.ghosts.lock().await;
+        let Some(ghost) = ghosts.get_mut(user_id) else {
+            return;
+        };
+        if ghost
+            .channels
+            .remove(&(server_id.to_string(), channel.to_string()))
+        {
+            let _ = self
+                .engine
+                .part_channel(ghost.session_id, server_id, channel, None);
+        }
+        if ghost.channels.is_empty()
+            && let Some(ghost) = ghosts.remove(user_id)
+        {
+            self.ghost_nicks.remove(&ghost.nick);
+            self.engine.disconnect(ghost.session_id).await;
+        }
+    }
+
+    async fn receive_message(
+        &self,
+        (room_id, server_id, channel): (&str, &str, &str),
+        sender: &str,
+        event_id: &str,
+        content: &Value,
+    ) -> Result<(), String> {
+        let Some(text) = message_text(content, &self.homeserver.base_url) else {
+            return Ok(());
+        };
+        let reply_to = match content["m.relates_to"]["m.in_reply_to"]["event_id"].as_str() {
+            Some(original) => queries::matrix::get_event(&self.pool, original)
+                .await
+                .map_err(db_error)?
+                .map(|row| row.message_id),
+            None => None,
+        };
+        let session_id = self.ghost(sender, server_id, channel).await?;
+
+        let (ack, acked) = oneshot::channel();
+        self.pending.insert(event_id.to_string(), ack);
+        if let Err(e) = self
+            .engine
+            .send_message(
+                session_id,
+                server_id,
+                channel,
+                &text,
+                reply_to.as_deref(),
+                None,
+                Some(event_id),
+            )
+            .await
+        {
+            self.pending.remove(event_id);
+            return Err(e.into());
+        }
+        let message_id = match tokio::time::timeout(ACK_TIMEOUT, acked).await {
+            Ok(Ok(id)) => id,
+            _ => {
+                self.pending.remove(event_id);
+                return Err("Message was not acknowledged".into());
+            }
+        };
+        queries::matrix::insert_event(
+            &self.pool,
+            event_id,
+            room_id,
+            &message_id.to_string(),
+            sender,
+            None,
+        )
+        .await
+        .map_err(db_error)
+    }
+
+    async fn receive_edit(
+        &self,
+        (_, server_id, channel): (&str, &str, &str),
+        sender: &str,
+        content: &Value,
+    ) -> Result<(), String> {
+        let Some(original) = content["m.relates_to"]["event_id"].as_str() else {
+            return Ok(());
+        };
+        let Some(row) = queries::matrix::get_event(&self.pool, original)
+            .await
+            .map_err(db_error)?
+        else {
+            return Ok(());
+        };
+        let Some(text) = message_text(&content["m.new_content"], &self.homeserver.base_url) else {
+            return Ok(());
+        };
+        if row.emoji.is_some() {
+            return Ok(());
+        }
+        let session_id = self.ghost(sender, server_id, channel).await?;
+        self.engine
+            .edit_message(session_id, &row.message_id, &text)
+            .await
+            .map_err(String::from)
+    }
+
+    async fn receive_reaction(
+        &self,
+        (room_id, server_id, channel): (&str, &str, &str),
+        sender: &str,
+        event_id: &str,
+        content: &Value,
+    ) -> Result<(), String> {
+        let relates = &content["m.relates_to"];
+        let (Some("m.annotation"), Some(original), Some(emoji)) = (
+            relates["rel_type"].as_str(),
+            relates["event_id"].as_str(),
+            relates["key"].as_str(),
+        ) else {
+            return Ok(());
+        };
+        let Some(row) = queries::matrix::get_event(&self.pool, original)
+            .await
+            .map_err(db_error)?
+        else {
+            return Ok(());
+        };
+        if row.emoji.is_some() {
+            return Ok(());
+        }
+        let session_id = self.ghost(sender, server_id, channel).await?;
+        self.engine
+            .add_reaction(session_id, &row.message_id, emoji)
+            .await?;
+        queries::matrix::insert_event(
+            &self.pool,
+            event_id,
+            room_id,
+            &row.message_id,
+            sender,
+            Some(emoji),
+        )
+        .await
+        .map_err(db_error)
+    }
+
+    async fn receive_redaction(
+        &self,
+        (_, server_id, channel): (&str, &str, &str),
+        sender: &str,
+        redacts: &str,
+    ) -> Result<(), String> {
+        let Some(row) = queries::matrix::get_event(&self.pool, redacts)
+            .await
+            .map_err(db_error)?
+        else {
+            return Ok(());
+        };
+        // Forget the event first so the resulting Concord event isn't echoed back.
+        queries::matrix::delete_event(&self.pool, redacts)
+            .await
+            .map_err(db_error)?;
+        match &row.emoji {
+            // Only the reacting Matrix user's own reactions can be taken back.
+            Some(emoji) if !self.in_namespace(&row.sender) => {
+                let session_id = self.ghost(&row.sender, server_id, channel).await?;
+                self.engine
+                    .remove_reaction(session_id, &row.message_id, emoji)
+                    .await
+                    .map_err(String::from)
+            }
+            Some(_) => Ok(()),
+            None => {
+                let session_id = self.ghost(sender, server_id, channel).await?;
+                self.engine
+                    .delete_message(session_id, &row.message_id)
+                    .await
+                    .map_err(String::from)
+            }
+        }
+    }
+
+    /// Create a room for a Concord channel when a Matrix user looks up its alias.
+    async fn provision_alias(&self, alias: &str) -> Result<bool, String> {
+        let Some(channel) = self.alias_channel(alias) else {
+            return Ok(false);
+        };
+        if !self
+            .engine
+            .list_channels(DEFAULT_SERVER_ID)
+            .iter()
+            .any(|c| c.name == channel)
+        {
+            return Ok(false);
+        }
+        let localpart = alias[1..].split(':').next().unwrap_or_default();
+        let room_id = self.homeserver.create_room(localpart, &channel).await?;
+        queries::matrix::upsert_room(&self.pool, &room_id, DEFAULT_SERVER_ID, &channel)
+            .await
+            .map_err(db_error)?;
+        self.rooms.insert(
+            room_id.clone(),
+            (DEFAULT_SERVER_ID.to_string(), channel.clone()),
+        );
+        self.watch_room(&room_id, DEFAULT_SERVER_ID, &channel).await;
+        Ok(true)
+    }
+}
+
+/// Hand a Matrix user's message acknowledgements to whoever awaits them.
+async fn forward_acks(
+    mut events: mpsc::Receiver<ChatEvent>,
+    pending: Arc<DashMap<String, oneshot::Sender<MessageId>>>,
+) {
+    while let Some(event) = events.recv().await {
+        if let ChatEvent::MessageAck {
+            id,
+            nonce: Some(nonce),
+            ..
+        } = event
+            && let Some((_, ack)) = pending.remove(&nonce)
+        {
+            let _ = ack.send(id);
+        }
+    }
+}
+
+async fn transaction(
+    State(bridge): State<Arc<MatrixBridge>>,
+    Path(txn_id): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    if let Some(response) = bridge.reject_token(&headers, &query) {
+        return response;
+    }
+    if bridge.first_delivery(&txn_id) {
+        for event in body["events"].as_array().into_iter().flatten() {
+            bridge.receive(event).await;
+        }
+    }
+    Json(json!({})).into_response()
+}
+
+async fn query_user(
+    State(bridge): State<Arc<MatrixBridge>>,
+    Path(user_id): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(response) = bridge.reject_token(&headers, &query) {
+        return response;
+    }
+    let localpart = user_id
+        .trim_start_matches('@')
+        .split(':')
+        .next()
+        .unwrap_or_default();
+    if !bridge.in_namespace(&user_id) {
+        return matrix_error(StatusCode::NOT_FOUND, "M_NOT_FOUND", "Unknown user");
+    }
+    match bridge.homeserver.register(localpart).await {
+        Ok(()) => Json(json!({})).into_response(),
+        Err(e) => matrix_error(StatusCode::INTERNAL_SERVER_ERROR, "M_UNKNOWN", &e),
+    }
+}
+
+async fn query_alias(
+    State(bridge): State<Arc<MatrixBridge>>,
+    Path(alias): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(response) = bridge.reject_token(&headers, &query) {
+        return response;
+    }
+    match bridge.provision_alias(&alias).await {
+        Ok(true) => Json(json!({})).into_response(),
+        Ok(false) => matrix_error(StatusCode::NOT_FOUND, "M_NOT_FOUND", "Unknown alias"),
+        Err(e) => matrix_error(StatusCode::INTERNAL_SERVER_ERROR, "M_UNKNOWN", &e),
+    }
+}
+
+fn matrix_error(status: StatusCode, errcode: &str, error: &str) -> Response {
+    (status, Json(json!({ "errcode": errcode, "error": error }))).into_response()
+}
+
+fn db_error(e: sqlx::Error) -> String {
+    format!("Database error: {e}")
+}
+
+/// `m.room.message` content for a Concord message; `/me` becomes an emote
+/// and attachments are linked after the text.
+fn message_content(text: &str, attachments: &[AttachmentInfo]) -> Value {
+    let (msgtype, text) = match text.strip_prefix("/me ") {
+        Some(action) => ("m.emote", action),
+        None => ("m.text", text),
+    };
+    let mut body = text.to_string();
+    for attachment in attachments {
+        if !body.is_empty() {
+            body.push('\n');
+        }
+        body.push_str(&attachment.url);
+    }
+    json!({ "msgtype": msgtype, "body": body })
+}
+
+/// Concord text for `m.room.message` content: reply quotes are dropped,
+/// emotes become `/me`, and media is linked through the homeserver.
+fn message_text(content: &Value, homeserver_url: &str) -> Option<String> {
+    let mut body = content["body"].as_str()?;
+    if content["m.relates_to"]["m.in_reply_to"].is_object() {
+        body = strip_reply_fallback(body);
+    }
+    let text = match content["msgtype"].as_str()? {
+        "m.text" | "m.notice" => body.to_string(),
+        "m.emote" => format!("/me {body}"),
+        "m.image" | "m.file" | "m.audio" | "m.video" => {
+            match content["url"]
+                .as_str()
+                .and_then(|url| media_url(homeserver_url, url))
+            {
+                Some(url) => format!("{body} {url}"),
+                None => body.to_string(),
+            }
+        }
+        _ => return None,
+    };
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// Replies start with the original quoted in `> ` lines and a blank line.
+fn strip_reply_fallback(body: &str) -> &str {
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        if line.starts_with('>') {
+            offset += line.len();
+        } else if offset > 0 && line.trim().is_empty() {
+            return &body[offset + line.len()..];
+        } else {
+            break;
+        }
+    }
+    body
+}
+
+/// Download URL for an `mxc://server/media` URI.
+fn media_url(homeserver_url: &str, mxc: &str) -> Option<String> {
+    let (server, media) = mxc.strip_prefix("mxc://")?.split_once('/')?;
+    Some(format!(
+        "{homeserver_url}/_matrix/media/v3/download/{server}/{media}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BridgedChannel;
+    use crate::db::pool::{create_pool, run_migrations};
+    use axum::http::{Method as HttpMethod, Uri};
+    use tokio::net::TcpListener;
+
+    /// A request received by the mock homeserver.
+    struct Request {
+        method: String,
+        path: String,
+        query: String,
+        body: Value,
+    }
+
+    fn config(homeserver_url: String) -> MatrixBridgeConfig {
+        MatrixBridgeConfig {
+            homeserver_url,
+            domain: "test".into(),
+            address: "127.0.0.1:0".into(),
+            as_token: "as-secret".into(),
+            hs_token: "hs-secret".into(),
+            sender_localpart: "concord".into(),
+            prefix: "concord_".into(),
+            remote_suffix: "-matrix".into(),
+            rooms: vec![BridgedChannel {
+                remote: "!room:test".into(),
+                server: Some("Team".into()),
+                channel: "general".into(),
+            }],
+        }
+    }
+
+    async fn within<F: Future>(future: F) -> F::Output {
+        tokio::time::timeout(Duration::from_secs(5), future)
+            .await
+            .expect("timed out")
+    }
+
+    /// Serve a homeserver that records requests and numbers the events it accepts.
+    async fn mock_homeserver() -> (String, mpsc::UnboundedReceiver<Request>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let next_event = Arc::new(AtomicU64::new(1));
+        let app = Router::new().fallback(move |method: HttpMethod, uri: Uri, body: String| {
+            let tx = tx.clone();
+            let next_event = next_event.clone();
+            async move {
+                let path = uri.path().to_string();
+                let response = if path.contains("/send/") {
+                    json!({ "event_id": format!("$e{}", next_event.fetch_add(1, Ordering::Relaxed)) })
+                } else if path.contains("/redact/") {
+                    json!({ "event_id": "$redaction" })
+                } else {
+                    json!({})
+                };
+                let _ = tx.send(Request {
+                    method: method.to_string(),
+                    path,
+                    query: uri.query().unwrap_or_default().to_string(),
+                    body: serde_json::from_str(&body).unwrap_or(Value::Null),
+                });
+                Json(response)
+            }
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, rx)
+    }
+
+    /// The next request whose path contains `fragment`.
+    async fn expect_request(rx: &mut mpsc::UnboundedReceiver<Request>, fragment: &str) -> Request {
+        loop {
+            let request = within(rx.recv()).await.expect("homeserver closed");
+            if request.path.contains(fragment) {
+                return request;
+            }
+        }
+    }
+
+    async fn next_event(
+        rx: &mut mpsc::Receiver<ChatEvent>,
+        matches: impl Fn(&ChatEvent) -> bool,
+    ) -> ChatEvent {
+        loop {
+            let event = within(rx.recv()).await.expect("session closed");
+            if matches(&event) {
+                return event;
+            }
+        }
+    }
+
+    struct Setup {
+        engine: Arc<ChatEngine>,
+        server_id: String,
+        homeserver: mpsc::UnboundedReceiver<Request>,
+        appservice: String,
+        alice: SessionId,
+        alice_events: mpsc::Receiver<ChatEvent>,
+    }
+
+    async fn setup() -> Setup {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        queries::users::create_with_oauth(
+            &pool,
+            &CreateOAuthUser {
+                user_id: "alice-id",
+                username: "alice",
+                email: None,
+                avatar_url: None,
+                oauth_id: "alice-oauth",
+                provider: "github",
+                provider_id: "1",
+            },
+        )
+        .await
+        .unwrap();
+        let engine = Arc::new(ChatEngine::new(Some(pool), 4000, 100));
+        let server_id = engine
+            .create_server("Team".into(), "alice-id".into(), None)
+            .await
+            .unwrap();
+        let (alice, alice_events) = engine
+            .connect(
+                Some("alice-id".into()),
+                "alice".into(),
+                Protocol::WebSocket,
+                None,
+            )
+            .unwrap();
+        engine
+            .join_channel(alice, &server_id, "#general")
+            .await
+            .unwrap();
+
+        let (url, homeserver) = mock_homeserver().await;
+        let (bridge, events) = MatrixBridge::start(engine.clone(), config(url))
+            .await
+            .unwrap();
+        tokio::spawn(
+            bridge
+                .clone()
+                .relay_events(events, CancellationToken::new()),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let appservice = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router(bridge)).await });
+
+        Setup {
+            engine,
+            server_id,
+            homeserver,
+            appservice,
+            alice,
+            alice_events,
+        }
+    }
+
+    /// Wait for the engine's background insert of a message to land.
+    async fn stored(engine: &ChatEngine, id: &str) {
+        let pool = engine.get_db().unwrap();
+        within(async {
+            while queries::messages::get_message_by_id(&pool, id)
+                .await
+                .unwrap()
+                .is_none()
+            {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+    }
+
+    async fn push(appservice: &str, txn_id: &str, token: Option<&str>, events: Value) -> u16 {
+        let mut request = reqwest::Client::new()
+            .put(format!("{appservice}/_matrix/app/v1/transactions/{txn_id}"))
+            .json(&json!({ "events": events }));
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        request.send().await.unwrap().status().as_u16()
+    }
+
+    fn room_event(kind: &str, event_id: &str, content: Value) -> Value {
+        json!({
+            "type": kind,
+            "room_id": "!room:test",
+            "sender": "@bob:test",
+            "event_id": event_id,
+            "content": content,
+        })
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concord_messages_reach_matrix() {
+        let mut s = setup().await;
+        expect_request(&mut s.homeserver, "/join/").await;
+
+        s.engine
+            .send_message(s.alice, &s.server_id, "#general", "hello", None, None, None)
+            .await
+            .unwrap();
+        let register = expect_request(&mut s.homeserver, "/register").await;
+        assert_eq!(register.body["username"], "concord_alice");
+        let sent = expect_request(&mut s.homeserver, "/send/m.room.message/").await;
+        assert_eq!(sent.method, "PUT");
+        assert!(sent.path.contains("%21room%3Atest"));
+        assert!(sent.query.contains("user_id=%40concord_alice%3Atest"));
+        assert_eq!(sent.body, json!({ "msgtype": "m.text", "body": "hello" }));
+
+        let id = match next_event(&mut s.alice_events, |e| {
+            matches!(e, ChatEvent::MessageAck { .. })
+        })
+        .await
+        {
+            ChatEvent::MessageAck { id, .. } => id.to_string(),
+            _ => unreachable!(),
+        };
+        stored(&s.engine, &id).await;
+
+        s.engine
+            .edit_message(s.alice, &id, "hello again")
+            .await
+            .unwrap();
+        let edit = expect_request(&mut s.homeserver, "/send/m.room.message/").await;
+        assert_eq!(edit.body["body"], "* hello again");
+        assert_eq!(edit.body["m.new_content"]["body"], "hello again");
+        assert_eq!(edit.body["m.relates_to"]["rel_type"], "m.replace");
+        assert_eq!(edit.body["m.relates_to"]["event_id"], "$e1");
+
+        s.engine.add_reaction(s.alice, &id, "👍").await.unwrap();
+        let reaction = expect_request(&mut s.homeserver, "/send/m.reaction/").await;
+        assert_eq!(reaction.body["m.relates_to"]["key"], "👍");
+        assert_eq!(reaction.body["m.relates_to"]["event_id"], "$e1");
+
+        s.engine.delete_message(s.alice, &id).await.unwrap();
+        let redact = expect_request(&mut s.homeserver, "/redact/").await;
+        assert!(redact.path.contains("/redact/%24e1/"));
+        assert!(redact.query.contains("user_id=%40concord_alice%3Atest"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_matrix_events_reach_concord() {
+        let mut s = setup().await;
+        let hello = room_event(
+            "m.room.message",
+            "$m1",
+            json!({ "msgtype": "m.text", "body": "hi" }),
+        );
+
+        assert_eq!(push(&s.appservice, "t0", None, json!([hello])).await, 401);
+        assert_eq!(
+            push(&s.appservice, "t0", Some("wrong"), json!([hello])).await,
+            403
+        );
+
+        // The bridge's own virtual users are never mirrored back.
+        let mut echo = hello.clone();
+        echo["sender"] = json!("@concord_alice:test");
+        echo["event_id"] = json!("$echo");
+        assert_eq!(
+            push(&s.appservice, "t1", Some("hs-secret"), json!([echo, hello])).await,
+            200
+        );
+        let (id, from, content) = match next_event(&mut s.alice_events, |e| {
+            matches!(e, ChatEvent::Message { .. })
+        })
+        .await
+        {
+            ChatEvent::Message {
+                id, from, content, ..
+            } => (id.to_string(), from, content),
+            _ => unreachable!(),
+        };
+        assert_eq!(from, "bob-matrix");
+        assert_eq!(content, "hi");
+        stored(&s.engine, &id).await;
+
+        // A retried transaction is not replayed.
+        push(&s.appservice, "t1", Some("hs-secret"), json!([hello])).await;
+
+        let reply = room_event(
+            "m.room.message",
+            "$m2",
+            json!({
+                "msgtype": "m.text",
+                "body": "> <@bob:test> hi\n\nanswer",
+                "m.relates_to": { "m.in_reply_to": { "event_id": "$m1" } },
+            }),
+        );
+        push(&s.appservice, "t2", Some("hs-secret"), json!([reply])).await;
+        match next_event(&mut s.alice_events, |e| {
+            matches!(e, ChatEvent::Message { .. })
+        })
+        .await
+        {
+            ChatEvent::Message {
+                content, reply_to, ..
+            } => {
+                assert_eq!(content, "answer");
+                assert_eq!(reply_to.unwrap().id, id);
+            }
+            _ => unreachable!(),
+        }
+
+        let edit = room_event(
+            "m.room.message",
+            "$m3",
+            json!({
+                "msgtype": "m.text",
+                "body": "* hi there",
+                "m.new_content": { "msgtype": "m.text", "body": "hi there" },
+                "m.relates_to": { "rel_type": "m.replace", "event_id": "$m1" },
+            }),
+        );
+        push(&s.appservice, "t3", Some("hs-secret"), json!([edit])).await;
+        match next_event(&mut s.alice_events, |e| {
+            matches!(e, ChatEvent::MessageEdit { .. })
+        })
+        .await
+        {
+            ChatEvent::MessageEdit {
+                id: edited,
+                content,
+                ..
+            } => {
+                assert_eq!(edited.to_string(), id);
+                assert_eq!(content, "hi there");
+            }
+            _ => unreachable!(),
+        }
+
+        let reaction = room_event(
+            "m.reaction",
+            "$m4",
+            json!({ "m.relates_to": { "rel_type": "m.annotation", "event_id": "$m1", "key": "🎉" } }),
+        );
+        push(&s.appservice, "t4", Some("hs-secret"), json!([reaction])).await;
+        match next_event(&mut s.alice_events, |e| {
+            matches!(e, ChatEvent::ReactionAdd { .. })
+        })
+        .await
+        {
+            ChatEvent::ReactionAdd {
+                nickname, emoji, ..
+            } => {
+                assert_eq!(nickname, "bob-matrix");
+                assert_eq!(emoji, "🎉");
+            }
+            _ => unreachable!(),
+        }
+
+        let unreact = room_event("m.room.redaction", "$m5", json!({ "redacts": "$m4" }));
+        let delete = room_event("m.room.redaction", "$m6", json!({ "redacts": "$m1" }));
+        push(
+            &s.appservice,
+            "t5",
+            Some("hs-secret"),
+            json!([unreact, delete]),
+        )
+        .await;
+        next_event(&mut s.alice_events, |e| {
+            matches!(e, ChatEvent::ReactionRemove { .. })
+        })
+        .await;
+        match next_event(&mut s.alice_events, |e| {
+            matches!(e, ChatEvent::MessageDelete { .. })
+        })
+        .await
+        {
+            ChatEvent::MessageDelete { id: deleted, .. } => assert_eq!(deleted.to_string(), id),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_message_text() {
+        let url = "https://matrix.test";
+        let reply = json!({
+            "msgtype": "m.text",
+            "body": "> <@bob:test> first\n> second\n\nmy answer",
+            "m.relates_to": { "m.in_reply_to": { "event_id": "$1" } },
+        });
+        assert_eq!(message_text(&reply, url).unwrap(), "my answer");
+
+        let quote = json!({ "msgtype": "m.text", "body": "> quoted\n\nnot a reply" });
+        assert_eq!(
+            message_text(&quote, url).unwrap(),
+            "> quoted\n\nnot a reply"
+        );
+
+        let emote = json!({ "msgtype": "m.emote", "body": "waves" });
+        assert_eq!(message_text(&emote, url).unwrap(), "/me waves");
+
+        let image = json!({ "msgtype": "m.image", "body": "cat.png", "url": "mxc://test/abc" });
+        assert_eq!(
+            message_text(&image, url).unwrap(),
+            "cat.png https://matrix.test/_matrix/media/v3/download/test/abc"
+        );
+
+        let unknown = json!({ "msgtype": "m.location", "body": "here" });
+        assert!(message_text(&unknown, url).is_none());
+    }
+
+    #[test]
+    fn test_message_content() {
+        assert_eq!(
+            message_content("/me waves", &[]),
+            json!({ "msgtype": "m.emote", "body": "waves" })
+        );
+        let attachment = AttachmentInfo {
+            id: "a".into(),
+            filename: "cat.png".into(),
+            content_type: "image/png".into(),
+            file_size: 10,
+            url: "/api/uploads/a".into(),
+        };
+        assert_eq!(
+            message_content("look", &[attachment])["body"],
+            "look\n/api/uploads/a"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+pub mod irc;
+pub mod matrix;
+
+use crate::config::BridgedChannel;
+use crate::engine::chat_engine::{ChatEngine, DEFAULT_SERVER_ID};
+use crate::engine::validation::MAX_NICKNAME_LENGTH;
+
+/// Nickname retries (each appending `_`) before giving up on a remote user.
+const NICK_RETRIES: usize = 3;
+
+/// Concord nicknames to try, in order, for a remote user called `base`.
+/// Characters Concord doesn't allow become `_`, and each retry appends `_`.
+fn nick_candidates(base: &str, suffix: &str) -> impl Iterator<Item = String> {
+    let max_base = MAX_NICKNAME_LENGTH.saturating_sub(suffix.len() + NICK_RETRIES);
+    let base: String = base
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(max_base)
+        .collect();
+    let suffix = suffix.to_string();
+    (0..=NICK_RETRIES).map(move |retry| format!("{base}{}{suffix}", "_".repeat(retry)))
+}
+
+/// The Concord `(server ID, channel)` a bridged channel maps to. The server
+/// may be given by name or ID; unknown servers are returned as the error.
+fn resolve_channel(
+    engine: &ChatEngine,
+    bridged: &BridgedChannel,
+) -> Result<(String, String), String> {
+    let server_id = match &bridged.server {
+        None => DEFAULT_SERVER_ID.to_string(),
+        Some(server) => match engine.find_server_by_name(server) {
+            Some(id) => id,
+            None if engine.get_server_name(server).is_some() => server.clone(),
+            None => return Err(server.clone()),
+        },
+    };
+    let channel = bridged.channel.to_lowercase();
+    let channel = if channel.starts_with('#') {
+        channel
+    } else {
+        format!("#{channel}")
+    };
+    Ok((server_id, channel))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nick_candidates() {
+        let candidates: Vec<_> = nick_candidates("[bob]", "-libera").collect();
+        assert_eq!(
+            candidates,
+            vec![
+                "_bob_-libera",
+                "_bob__-libera",
+                "_bob___-libera",
+                "_bob____-libera"
+            ]
+        );
+        assert!(
+            nick_candidates(&"x".repeat(40), "-libera")
+                .all(|nick| nick.len() <= MAX_NICKNAME_LENGTH)
+        );
+    }
+}
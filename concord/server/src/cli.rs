@@ -0,0 +1,500 @@
+//! Administrative subcommands of the `concord-server` binary.
+//!
+//! Each command opens its own pool on the configured database. SQLite's WAL
+//! mode and busy timeout let them run while the server is up. The running
+//! server keeps servers, channels and sessions in memory, so commands that
+//! change those (restores, server deletes and ownership transfers, user
+//! suspensions and deletes) also record an admin change, which the server
+//! polls for and applies within a few seconds.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{Parser, Subcommand};
+use sqlx::{Connection, SqlitePool};
+
+use crate::config::ServerConfig;
+use crate::db::models::UserAccountRow;
+use crate::db::pool::{create_pool, dry_run_migrations, migration_status, run_migrations};
+use crate::db::queries::{instance, servers, users};
+
+/// Concord - open source IRC-compatible chat server.
+#[derive(Parser)]
+#[command(name = "concord-server", version)]
+pub struct Cli {
+    /// Path to the configuration file.
+    #[arg(long, short, global = true, default_value = "concord.toml")]
+    pub config: String,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the server (the default when no subcommand is given).
+    Serve,
+    /// Inspect or apply database migrations.
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Manage user accounts.
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Manage servers (guilds).
+    #[command(subcommand)]
+    Server(ServerCommand),
+    /// Inspect the configuration.
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Write a consistent snapshot of the database to a new file.
+    Backup {
+        /// Destination file. Must not exist yet.
+        path: PathBuf,
+    },
+    /// Replace the database contents with a backup taken at the same schema version.
+    Restore {
+        /// Backup file written by `backup`.
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MigrateCommand {
+    /// List migrations and when each was applied.
+    Status,
+    /// Apply pending migrations.
+    Run,
+    /// Apply pending migrations in a transaction that is rolled back.
+    DryRun,
+}
+
+#[derive(Subcommand)]
+pub enum UserCommand {
+    /// List all users.
+    List,
+    /// Make a user a system admin.
+    Promote { user: String },
+    /// Remove a user's system admin flag.
+    Demote { user: String },
+    /// Block a user from signing in and revoke their tokens.
+    Suspend { user: String },
+    /// Lift a user's suspension.
+    Unsuspend { user: String },
+    /// Delete a user and their data. Refuses if they still own servers.
+    Delete { user: String },
+    /// Revoke a user's web sessions, IRC tokens, bot tokens and OAuth2 grants.
+    RevokeTokens { user: String },
+}
+
+#[derive(Subcommand)]
+pub enum ServerCommand {
+    /// List all servers.
+    List,
+    /// Delete a server and everything in it.
+    Delete { server: String },
+    /// Give a server to another user.
+    TransferOwner { server: String, user: String },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Parse and validate the configuration file.
+    Check,
+}
+
+/// Run an administrative command (anything but `serve`).
+pub async fn run(command: Command, config_path: &str) -> Result<()> {
+    let config = ServerConfig::try_load(config_path).map_err(|e| anyhow!(e))?;
+    if let Command::Config(ConfigCommand::Check) = command {
+        return check_config(&config, config_path);
+    }
+
+    let pool = create_pool(&config.database.url)
+        .await
+        .with_context(|| format!("failed to open {}", config.database.url))?;
+
+    match command {
+        Command::Serve | Command::Config(_) => {
+            unreachable!("serve runs from main and config check needs no pool")
+        }
+        Command::Migrate(command) => migrate(&pool, command).await,
+        Command::User(command) => user(&pool, command).await,
+        Command::Server(command) => server(&pool, command).await,
+        Command::Backup { path } => backup(&pool, &path).await,
+        Command::Restore { path } => restore(&pool, &path).await,
+    }
+}
+
+fn check_config(config: &ServerConfig, path: &str) -> Result<()> {
+    if config.auth.jwt_secret == crate::config::AuthSection::default().jwt_secret
+        || config.auth.jwt_secret.is_empty()
+    {
+        println!("warning: auth.jwt_secret is not set; sessions won't survive restarts");
+    }
+    let problems = config.validate();
+    if problems.is_empty() {
+        println!("{path}: ok");
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("error: {problem}");
+    }
+    bail!("{path}: {} problem(s) found", problems.len())
+}
+
+async fn migrate(pool: &SqlitePool, command: MigrateCommand) -> Result<()> {
+    match command {
+        MigrateCommand::Status => {
+            for migration in migration_status(pool).await? {
+                let applied = migration.applied_at.as_deref().unwrap_or("pending");
+                println!(
+                    "{:>3}  {:<19}  {}",
+                    migration.version, applied, migration.description
+                );
+            }
+        }
+        MigrateCommand::Run => {
+            run_migrations(pool).await?;
+            println!("database is up to date");
+        }
+        MigrateCommand::DryRun => {
+            let pending = dry_run_migrations(pool).await?;
+            if pending.is_empty() {
+                println!("no pending migrations");
+            } else {
+                println!("pending migrations apply cleanly: {pending:?}");
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn find_user(pool: &SqlitePool, user: &str) -> Result<UserAccountRow> {
+    users::find_user_account(pool, user)
+        .await?
+        .ok_or_else(|| anyhow!("no user with ID or username {user:?}"))
+}
+
+async fn user(pool: &SqlitePool, command: UserCommand) -> Result<()> {
+    match command {
+        UserCommand::List => {
+            for account in users::list_user_accounts(pool).await? {
+                let mut flags = Vec::new();
+                if account.is_system_admin != 0 {
+                    flags.push("admin");
+                }
+                if account.is_bot != 0 {
+                    flags.push("bot");
+                }
+                if account.suspended_at.is_some() {
+                    flags.push("suspended");
+                }
+                println!(
+                    "{}  {}  {}  {}",
+                    account.id,
+                    account.username,
+                    account.created_at,
+                    flags.join(",")
+                );
+            }
+        }
+        UserCommand::Promote { user } => {
+            let account = find_user(pool, &user).await?;
+            servers::set_system_admin(pool, &account.id, true).await?;
+            println!("{} is now a system admin", account.username);
+        }
+        UserCommand::Demote { user } => {
+            let account = find_user(pool, &user).await?;
+            servers::set_system_admin(pool, &account.id, false).await?;
+            println!("{} is no longer a system admin", account.username);
+        }
+        UserCommand::Suspend { user } => {
+            let account = find_user(pool, &user).await?;
+            users::set_suspended(pool, &account.id, true).await?;
+            let revoked = users::revoke_user_tokens(pool, &account.id).await?;
+            instance::record_admin_change(pool, "user_suspend", Some(&account.id)).await?;
+            println!(
+                "suspended {} and revoked {revoked} token(s)",
+                account.username
+            );
+        }
+        UserCommand::Unsuspend { user } => {
+            let account = find_user(pool, &user).await?;
+            users::set_suspended(pool, &account.id, false).await?;
+            println!("{} is no longer suspended", account.username);
+        }
+        UserCommand::Delete { user } => {
+            let account = find_user(pool, &user).await?;
+            let owned = servers::list_servers_owned_by(pool, &account.id).await?;
+            if !owned.is_empty() {
+                let names: Vec<_> = owned.iter().map(|s| s.name.as_str()).collect();
+                bail!(
+                    "{} owns {}; transfer ownership with `server transfer-owner` first",
+                    account.username,
+                    names.join(", ")
+                );
+            }
+            users::delete_user(pool, &account.id).await?;
+            instance::record_admin_change(pool, "user_delete", Some(&account.id)).await?;
+            println!("deleted {}", account.username);
+        }
+        UserCommand::RevokeTokens { user } => {
+            let account = find_user(pool, &user).await?;
+            let revoked = users::revoke_user_tokens(pool, &account.id).await?;
+            println!(
+                "revoked all sessions and {revoked} token(s) for {}",
+                account.username
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn server(pool: &SqlitePool, command: ServerCommand) -> Result<()> {
+    match command {
+        ServerCommand::List => {
+            for server in servers::list_all_servers(pool).await? {
+                let members = servers::get_member_count(pool, &server.id).await?;
+                println!(
+                    "{}  {}  owner={}  members={members}",
+                    server.id, server.name, server.owner_id
+                );
+            }
+        }
+        ServerCommand::Delete { server } => {
+            let row = servers::get_server(pool, &server)
+                .await?
+                .ok_or_else(|| anyhow!("no server with ID {server:?}"))?;
+            servers::delete_server(pool, &row.id).await?;
+            instance::record_admin_change(pool, "server_delete", Some(&row.id)).await?;
+            println!("deleted server {}", row.name);
+        }
+        ServerCommand::TransferOwner { server, user } => {
+            let row = servers::get_server(pool, &server)
+                .await?
+                .ok_or_else(|| anyhow!("no server with ID {server:?}"))?;
+            let account = find_user(pool, &user).await?;
+            servers::transfer_ownership(pool, &row.id, &account.id).await?;
+            instance::record_admin_change(pool, "server_transfer", Some(&row.id)).await?;
+            println!("{} now owns {}", account.username, row.name);
+        }
+    }
+    Ok(())
+}
+
+async fn backup(pool: &SqlitePool, path: &Path) -> Result<()> {
+    if path.exists() {
+        bail!("{} already exists", path.display());
+    }
+    // VACUUM INTO reads a consistent snapshot, so writers aren't blocked
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().as_ref())
+        .execute(pool)
+        .await?;
+    println!("backed up to {}", path.display());
+    Ok(())
+}
+
+/// Copy every table of `path` into the database in a single transaction.
+/// Readers see either the old or the restored contents, never a mix.
+async fn restore(pool: &SqlitePool, path: &Path) -> Result<()> {
+    if !path.is_file() {
+        bail!("{} does not exist", path.display());
+    }
+
+    let mut conn = pool.acquire().await?;
+    sqlx::query("ATTACH DATABASE ? AS backup")
+        .bind(path.to_string_lossy().as_ref())
+        .execute(&mut *conn)
+        .await?;
+    // The backup is copied verbatim, so foreign keys are neither checked nor cascaded
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await?;
+    // Undo both even when the restore fails so the connection goes back to the pool clean
+    let result = restore_attached(&mut conn).await;
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DETACH DATABASE backup")
+        .execute(&mut *conn)
+        .await?;
+    let restored = result?;
+    instance::record_admin_change(pool, "restore", None).await?;
+
+    println!("restored {restored} table(s) from {}", path.display());
+    Ok(())
+}
+
+async fn restore_attached(conn: &mut sqlx::SqliteConnection) -> Result<usize> {
+    let version = "SELECT COALESCE(MAX(version), 0) FROM {}.schema_version";
+    let current: i64 = sqlx::query_scalar(&version.replace("{}", "main"))
+        .fetch_one(&mut *conn)
+        .await?;
+    let backup: i64 = sqlx::query_scalar(&version.replace("{}", "backup"))
+        .fetch_one(&mut *conn)
+        .await
+        .context("not a Concord database")?;
+    if backup != current {
+        bail!(
+            "backup is at schema version {backup} but the database is at {current}; \
+             restore into a database at the same version"
+        );
+    }
+
+    // The FTS index and SQLite's own tables are maintained by triggers and rebuilt
+    // below, and the admin change log keeps growing so the server sees the restore
+    let tables: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM main.sqlite_master WHERE type = 'table' \
+         AND name NOT LIKE 'sqlite_%' AND name NOT LIKE 'messages_fts%' \
+         AND name <> 'admin_changes'",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut tx = conn.begin().await?;
+    for table in &tables {
+        sqlx::query(&format!("DELETE FROM main.\"{table}\""))
+            .execute(&mut *tx)
+            .await?;
+    }
+    for table in &tables {
+        let columns: Vec<String> =
+            sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{table}')"))
+                .fetch_all(&mut *tx)
+                .await?;
+        let columns = columns
+            .iter()
+            .map(|c| format!("\"{c}\""))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sqlx::query(&format!(
+            "INSERT INTO main.\"{table}\" ({columns}) SELECT {columns} FROM backup.\"{table}\""
+        ))
+        .execute(&mut *tx)
+        .await
+        .with_context(|| format!("failed to restore {table}"))?;
+    }
+    sqlx::query("INSERT INTO messages_fts(messages_fts) VALUES('rebuild')")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(tables.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::pool::create_pool;
+
+    async fn setup_db(url: &str) -> SqlitePool {
+        let pool = create_pool(url).await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    async fn create_user(pool: &SqlitePool, id: &str, username: &str) {
+        sqlx::query("INSERT INTO users (id, username) VALUES (?, ?)")
+            .bind(id)
+            .bind(username)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_parse_subcommands() {
+        let cli = Cli::try_parse_from(["concord-server"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.config, "concord.toml");
+
+        let cli = Cli::try_parse_from([
+            "concord-server",
+            "server",
+            "transfer-owner",
+            "s1",
+            "alice",
+            "--config",
+            "other.toml",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Server(ServerCommand::TransferOwner { .. }))
+        ));
+        assert_eq!(cli.config, "other.toml");
+
+        assert!(Cli::try_parse_from(["concord-server", "migrate", "dry-run"]).is_ok());
+        assert!(Cli::try_parse_from(["concord-server", "user", "revoke-tokens", "bob"]).is_ok());
+        assert!(Cli::try_parse_from(["concord-server", "user", "frobnicate"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_refuses_server_owner() {
+        let pool = setup_db("sqlite::memory:").await;
+        create_user(&pool, "u1", "alice").await;
+        servers::create_server(&pool, "s1", "Test", "u1", None)
+            .await
+            .unwrap();
+
+        let result = user(
+            &pool,
+            UserCommand::Delete {
+                user: "alice".into(),
+            },
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(servers::get_server(&pool, "s1").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let dir = std::env::temp_dir().join(format!("concord-cli-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_url = format!("sqlite:{}?mode=rwc", dir.join("live.db").display());
+        let backup_path = dir.join("backup.db");
+
+        let pool = setup_db(&db_url).await;
+        create_user(&pool, "u1", "alice").await;
+        servers::create_server(&pool, "s1", "Test", "u1", None)
+            .await
+            .unwrap();
+
+        backup(&pool, &backup_path).await.unwrap();
+        assert!(backup(&pool, &backup_path).await.is_err());
+
+        create_user(&pool, "u2", "bob").await;
+        server(
+            &pool,
+            ServerCommand::Delete {
+                server: "s1".into(),
+            },
+        )
+        .await
+        .unwrap();
+
+        restore(&pool, &backup_path).await.unwrap();
+        assert!(
+            users::find_user_account(&pool, "bob")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            users::find_user_account(&pool, "alice")
+                .await
+                .unwrap()
+                .is_some()
+        );
+        let server = servers::get_server(&pool, "s1").await.unwrap().unwrap();
+        assert_eq!(server.owner_id, "u1");
+        // The running server is told, and the change log isn't rolled back
+        let changes = instance::list_admin_changes_after(&pool, 0).await.unwrap();
+        let kinds: Vec<&str> = changes.iter().map(|c| c.kind.as_str()).collect();
+        assert_eq!(kinds, ["server_delete", "restore"]);
+
+        pool.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
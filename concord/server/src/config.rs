@@ -1,5 +1,8 @@
 use serde::Deserialize;
+use sqlx::sqlite::SqliteConnectOptions;
+use std::net::ToSocketAddrs;
 use std::path::Path;
+use std::str::FromStr;
 use tracing::info;
 
 use crate::auth::config::AuthConfig;
@@ -14,6 +17,8 @@ pub struct ServerConfig {
     pub storage: StorageSection,
     pub admin: AdminSection,
     pub irc: IrcSection,
+    pub bridge: BridgeSection,
+    pub metrics: MetricsSection,
 }
 
 #[derive(Deserialize, Default)]
@@ -93,29 +98,241 @@ impl Default for StorageSection {
     }
 }
 
-#[derive(Deserialize, Default)]
+#[derive(Deserialize)]
 #[serde(default)]
 pub struct IrcSection {
     /// Message of the day lines. If empty, clients see ERR_NOMOTD.
     pub motd: Vec<String>,
+    /// Keep IRC sessions alive after the client disconnects and replay missed
+    /// messages on reconnect (bouncer-style).
+    pub always_on: bool,
+    /// Close always-on sessions that have had no client attached for this many
+    /// hours. 0 keeps them until the server restarts.
+    pub always_on_idle_hours: u64,
+    /// Addresses of reverse proxies (HAProxy, stunnel) in front of the IRC listener.
+    /// Their connections must start with a PROXY protocol v1/v2 header, whose
+    /// client address is then used for limits, logging, and WHOIS.
+    pub trusted_proxies: Vec<String>,
+    /// Web IRC gateways allowed to pass on their users' addresses with WEBIRC.
+    pub webirc: Vec<WebircGateway>,
+}
+
+impl Default for IrcSection {
+    fn default() -> Self {
+        Self {
+            motd: Vec::new(),
+            always_on: false,
+            always_on_idle_hours: 72,
+            trusted_proxies: Vec::new(),
+            webirc: Vec::new(),
+        }
+    }
+}
+
+/// A `[[irc.webirc]]` block: a web IRC gateway trusted to send WEBIRC.
+#[derive(Deserialize, Clone)]
+pub struct WebircGateway {
+    /// Name of the gateway, used in logs.
+    pub name: String,
+    /// Password the gateway sends in WEBIRC.
+    pub password: String,
+    /// IP addresses the gateway connects from.
+    pub hosts: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct MetricsSection {
+    /// Serve Prometheus metrics at `/metrics`. Off by default: without an
+    /// `address` the endpoint is public and unauthenticated.
+    pub enabled: bool,
+    /// Serve `/metrics` on this address (e.g. `127.0.0.1:9100`) instead of the
+    /// public web address.
+    pub address: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct BridgeSection {
+    /// Relays to channels on external IRC networks, one `[[bridge.irc]]` block each.
+    pub irc: Vec<IrcBridgeConfig>,
+    /// Matrix application service, if `[bridge.matrix]` is present.
+    pub matrix: Option<MatrixBridgeConfig>,
+}
+
+/// A `[[bridge.irc]]` block: a connection to an external IRC network.
+#[derive(Deserialize, Clone)]
+pub struct IrcBridgeConfig {
+    /// Short name of the network, used in logs and as the relay's nickname in Concord.
+    pub name: String,
+    /// `host:port` of the network's IRC server.
+    pub address: String,
+    /// Connect with TLS.
+    #[serde(default)]
+    pub tls: bool,
+    /// Nickname of the relay client on the network.
+    pub nickname: String,
+    /// Server password sent with PASS, if the network needs one.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Give each Concord user who speaks their own connection to the network,
+    /// instead of relaying everything through one client as `<nick> text`.
+    #[serde(default)]
+    pub puppet: bool,
+    /// Appended to Concord users' nicknames on the network when puppeting.
+    #[serde(default = "default_puppet_suffix")]
+    pub puppet_suffix: String,
+    /// Appended to the network's users' nicknames in Concord. Defaults to `-<name>`.
+    #[serde(default)]
+    pub remote_suffix: Option<String>,
+    /// Channels mirrored between the network and Concord.
+    pub channels: Vec<BridgedChannel>,
+}
+
+fn default_puppet_suffix() -> String {
+    "[c]".into()
+}
+
+/// `[bridge.matrix]`: a Matrix application service mirroring rooms into Concord.
+#[derive(Deserialize, Clone)]
+pub struct MatrixBridgeConfig {
+    /// Base URL of the homeserver's client-server API, e.g. `http://localhost:8008`.
+    pub homeserver_url: String,
+    /// The homeserver's server name, the part after `:` in Matrix IDs.
+    pub domain: String,
+    /// Address the appservice API listens on for the homeserver's transactions.
+    #[serde(default = "default_matrix_address")]
+    pub address: String,
+    /// Token the bridge presents to the homeserver (`as_token` in the registration).
+    pub as_token: String,
+    /// Token the homeserver presents to the bridge (`hs_token` in the registration).
+    pub hs_token: String,
+    /// Localpart of the bridge's own Matrix user (`sender_localpart`).
+    #[serde(default = "default_sender_localpart")]
+    pub sender_localpart: String,
+    /// Prefix of the Matrix users and room aliases the bridge owns, e.g. `@concord_alice`.
+    #[serde(default = "default_namespace_prefix")]
+    pub prefix: String,
+    /// Appended to Matrix users' nicknames in Concord.
+    #[serde(default = "default_matrix_suffix")]
+    pub remote_suffix: String,
+    /// Rooms mirrored into Concord channels, by room ID.
+    #[serde(default)]
+    pub rooms: Vec<BridgedChannel>,
+}
+
+fn default_matrix_address() -> String {
+    "127.0.0.1:9009".into()
+}
+
+fn default_sender_localpart() -> String {
+    "concord".into()
+}
+
+fn default_namespace_prefix() -> String {
+    "concord_".into()
+}
+
+fn default_matrix_suffix() -> String {
+    "-matrix".into()
+}
+
+/// A remote channel and the Concord channel it is mirrored into.
+#[derive(Deserialize, Clone)]
+pub struct BridgedChannel {
+    /// The channel on the other side: an IRC channel name or a Matrix room ID.
+    pub remote: String,
+    /// Name of the Concord server holding the channel. Defaults to the default server.
+    #[serde(default)]
+    pub server: Option<String>,
+    /// Concord channel name.
+    pub channel: String,
 }
 
 impl ServerConfig {
     /// Load config from a TOML file. Falls back to defaults if the file doesn't exist.
     /// Environment variables override TOML values.
     pub fn load(path: &str) -> Self {
+        Self::try_load(path).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`ServerConfig::load`], but returns read and parse errors instead of panicking.
+    pub fn try_load(path: &str) -> Result<Self, String> {
         let mut config = if Path::new(path).exists() {
             let contents = std::fs::read_to_string(path)
-                .unwrap_or_else(|e| panic!("failed to read config file {}: {}", path, e));
+                .map_err(|e| format!("failed to read config file {}: {}", path, e))?;
             toml::from_str(&contents)
-                .unwrap_or_else(|e| panic!("failed to parse config file {}: {}", path, e))
+                .map_err(|e| format!("failed to parse config file {}: {}", path, e))?
         } else {
             info!("No config file found at {}, using defaults", path);
             Self::default()
         };
 
         config.apply_env_overrides();
-        config
+        Ok(config)
+    }
+
+    /// Check the settings the server would otherwise only reject at startup
+    /// (or not at all). Returns one message per problem.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let mut addresses = vec![
+            ("server.web_address", &self.server.web_address),
+            ("server.irc_address", &self.server.irc_address),
+        ];
+        if let Some(address) = &self.metrics.address {
+            addresses.push(("metrics.address", address));
+        }
+        if let Some(matrix) = &self.bridge.matrix {
+            addresses.push(("bridge.matrix.address", &matrix.address));
+        }
+        for (key, address) in addresses {
+            if address.to_socket_addrs().is_err() {
+                problems.push(format!("{key}: {address:?} is not a valid listen address"));
+            }
+        }
+
+        match (&self.server.irc_tls_cert, &self.server.irc_tls_key) {
+            (Some(cert), Some(key)) => {
+                for (key_name, path) in [("irc_tls_cert", cert), ("irc_tls_key", key)] {
+                    if !Path::new(path).is_file() {
+                        problems.push(format!("server.{key_name}: {path} does not exist"));
+                    }
+                }
+            }
+            (Some(_), None) | (None, Some(_)) => problems
+                .push("server.irc_tls_cert and server.irc_tls_key must be set together".into()),
+            (None, None) => {}
+        }
+
+        if SqliteConnectOptions::from_str(&self.database.url).is_err() {
+            problems.push(format!(
+                "database.url: {:?} is not a SQLite URL",
+                self.database.url
+            ));
+        }
+        if self.auth.session_expiry_hours <= 0 {
+            problems.push("auth.session_expiry_hours must be positive".into());
+        }
+        if self.storage.max_message_length == 0 {
+            problems.push("storage.max_message_length must be positive".into());
+        }
+        for bridge in &self.bridge.irc {
+            if bridge.channels.is_empty() {
+                problems.push(format!(
+                    "bridge.irc {}: no channels configured",
+                    bridge.name
+                ));
+            }
+        }
+        for gateway in &self.irc.webirc {
+            if gateway.password.is_empty() {
+                problems.push(format!("irc.webirc {}: password is empty", gateway.name));
+            }
+        }
+
+        problems
     }
 
     fn apply_env_overrides(&mut self) {
@@ -155,6 +372,10 @@ impl ServerConfig {
         {
             self.storage.max_message_length = len;
         }
+        if let Ok(v) = std::env::var("METRICS_ADDRESS") {
+            self.metrics.enabled = true;
+            self.metrics.address = Some(v);
+        }
         if let Ok(v) = std::env::var("ADMIN_USERS") {
             self.admin.admin_users = v
                 .split(',')
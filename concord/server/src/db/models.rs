@@ -17,6 +17,9 @@ pub struct ServerRow {
     pub allow_external_emoji: i32,
     pub shareable_emoji: i32,
     pub vanity_code: Option<String>,
+    /// Set while a system admin has quarantined the server.
+    pub quarantined_at: Option<String>,
+    pub quarantine_reason: Option<String>,
 }
 
 /// A server membership record.
@@ -29,6 +32,60 @@ pub struct ServerMemberRow {
     pub avatar_url: Option<String>,
 }
 
+/// A user account as listed for instance administration.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UserAccountRow {
+    pub id: String,
+    pub username: String,
+    pub email: Option<String>,
+    pub is_system_admin: i32,
+    pub is_bot: i32,
+    pub suspended_at: Option<String>,
+    pub created_at: String,
+}
+
+/// An instance-wide ban of a user ID (a DID for Bluesky accounts).
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InstanceBanRow {
+    pub user_id: String,
+    pub reason: Option<String>,
+    pub banned_by: String,
+    pub created_at: String,
+}
+
+/// A change the admin CLI made underneath the running server.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AdminChangeRow {
+    pub id: i64,
+    pub kind: String,
+    pub target_id: Option<String>,
+    pub created_at: String,
+}
+
+/// An instance audit log entry, recording a system admin's action.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InstanceAuditLogRow {
+    pub id: String,
+    pub actor_id: String,
+    pub action_type: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub reason: Option<String>,
+    pub changes: Option<String>,
+    pub created_at: String,
+}
+
+/// Parameters for creating an instance audit log entry (avoids too-many-arguments).
+pub struct CreateInstanceAuditParams<'a> {
+    pub id: &'a str,
+    pub actor_id: &'a str,
+    pub action_type: &'a str,
+    pub target_type: Option<&'a str>,
+    pub target_id: Option<&'a str>,
+    pub reason: Option<&'a str>,
+    pub changes: Option<&'a str>,
+}
+
 /// A custom sticker in a server.
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct StickerRow {
@@ -78,6 +135,8 @@ pub struct ChannelRow {
     pub slowmode_seconds: i32,
     pub is_nsfw: i32,
     pub is_announcement: i32,
+    pub is_read_only: i32,
+    pub topic_locked: i32,
 }
 
 /// A channel membership record.
@@ -232,10 +291,23 @@ pub struct BanRow {
     pub user_id: String,
     pub banned_by: String,
     pub reason: Option<String>,
-    pub delete_message_days: i32,
+    pub delete_message_seconds: i64,
+    pub expires_at: Option<String>,
     pub created_at: String,
 }
 
+/// Parameters for creating a ban (avoids too-many-arguments).
+pub struct CreateBanParams<'a> {
+    pub id: &'a str,
+    pub server_id: &'a str,
+    pub user_id: &'a str,
+    pub banned_by: &'a str,
+    pub reason: Option<&'a str>,
+    pub delete_message_seconds: i64,
+    /// When the ban lifts; permanent when `None`.
+    pub expires_at: Option<&'a str>,
+}
+
 /// An audit log entry.
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct AuditLogRow {
@@ -265,6 +337,120 @@ pub struct AutomodRuleRow {
     pub updated_at: String,
 }
 
+/// A message in the AutoMod review queue.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AutomodQueueRow {
+    pub id: String,
+    pub server_id: String,
+    pub channel_id: String,
+    pub rule_id: Option<String>,
+    pub rule_name: String,
+    pub user_id: String,
+    pub user_nick: String,
+    pub content: String,
+    pub reply_to_id: Option<String>,
+    pub message_id: Option<String>,
+    pub status: String,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<String>,
+    pub created_at: String,
+}
+
+/// Parameters for queueing a message for AutoMod review (avoids too-many-arguments).
+pub struct CreateAutomodQueueParams<'a> {
+    pub id: &'a str,
+    pub server_id: &'a str,
+    pub channel_id: &'a str,
+    pub rule_id: &'a str,
+    pub rule_name: &'a str,
+    pub user_id: &'a str,
+    pub user_nick: &'a str,
+    pub content: &'a str,
+    pub reply_to_id: Option<&'a str>,
+    pub message_id: Option<&'a str>,
+    pub status: &'a str,
+}
+
+/// A member's report of a message or user.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ReportRow {
+    pub id: String,
+    pub server_id: String,
+    pub reporter_id: String,
+    pub target_user_id: String,
+    pub message_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub message_content: Option<String>,
+    pub reason: String,
+    pub status: String,
+    pub resolution: Option<String>,
+    pub resolution_note: Option<String>,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<String>,
+    pub created_at: String,
+}
+
+/// Parameters for creating a report (avoids too-many-arguments).
+pub struct CreateReportParams<'a> {
+    pub id: &'a str,
+    pub server_id: &'a str,
+    pub reporter_id: &'a str,
+    pub target_user_id: &'a str,
+    pub message_id: Option<&'a str>,
+    pub channel_id: Option<&'a str>,
+    pub message_content: Option<&'a str>,
+    pub reason: &'a str,
+}
+
+/// An entry in a member's infractions ledger.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InfractionRow {
+    pub id: String,
+    pub server_id: String,
+    pub user_id: String,
+    pub actor_id: Option<String>,
+    pub action: String,
+    pub source: String,
+    pub reason: Option<String>,
+    pub expires_at: Option<String>,
+    pub pardoned_by: Option<String>,
+    pub pardoned_at: Option<String>,
+    pub created_at: String,
+}
+
+/// Parameters for recording an infraction (avoids too-many-arguments).
+pub struct CreateInfractionParams<'a> {
+    pub id: &'a str,
+    pub server_id: &'a str,
+    pub user_id: &'a str,
+    pub actor_id: Option<&'a str>,
+    pub action: &'a str,
+    pub source: &'a str,
+    pub reason: Option<&'a str>,
+    pub expires_at: Option<&'a str>,
+}
+
+/// A server's "N strikes within a window -> action" rule.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct EscalationPolicyRow {
+    pub id: String,
+    pub server_id: String,
+    pub threshold: i64,
+    pub window_seconds: i64,
+    pub action: String,
+    pub duration_seconds: Option<i64>,
+    pub created_at: String,
+}
+
+/// One step of a server's escalation ladder, as saved.
+pub struct CreateEscalationPolicyParams<'a> {
+    pub id: &'a str,
+    pub threshold: i64,
+    pub window_seconds: i64,
+    pub action: &'a str,
+    pub duration_seconds: Option<i64>,
+}
+
 /// Parameters for creating an audit log entry (avoids too-many-arguments).
 pub struct CreateAuditLogParams<'a> {
     pub id: &'a str,
@@ -492,6 +678,26 @@ pub struct OAuth2AuthorizationRow {
     pub created_at: String,
 }
 
+/// A Matrix room mirrored into a Concord channel by the Matrix bridge.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MatrixRoomRow {
+    pub room_id: String,
+    pub server_id: String,
+    pub channel_name: String,
+    pub created_at: String,
+}
+
+/// The Matrix event behind a bridged message, or a reaction when `emoji` is set.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MatrixEventRow {
+    pub event_id: String,
+    pub room_id: String,
+    pub message_id: String,
+    pub sender: String,
+    pub emoji: Option<String>,
+    pub created_at: String,
+}
+
 /// Parameters for creating a slash command (avoids too-many-arguments).
 pub struct CreateSlashCommandParams<'a> {
     pub id: &'a str,
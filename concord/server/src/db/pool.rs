@@ -21,6 +21,59 @@ pub async fn create_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error>
     Ok(pool)
 }
 
+/// Every migration, in the order it is applied.
+const MIGRATIONS: &[(i64, &str)] = &[
+    (1, include_str!("../../migrations/001_initial.sql")),
+    (2, include_str!("../../migrations/002_servers.sql")),
+    (
+        3,
+        include_str!("../../migrations/003_messaging_enhancements.sql"),
+    ),
+    (4, include_str!("../../migrations/004_media_files.sql")),
+    (
+        5,
+        include_str!("../../migrations/005_atproto_blob_storage.sql"),
+    ),
+    (6, include_str!("../../migrations/006_server_config.sql")),
+    (
+        7,
+        include_str!("../../migrations/007_organization_permissions.sql"),
+    ),
+    (8, include_str!("../../migrations/008_user_experience.sql")),
+    (9, include_str!("../../migrations/009_threads_pinning.sql")),
+    (10, include_str!("../../migrations/010_moderation.sql")),
+    (11, include_str!("../../migrations/011_community.sql")),
+    (12, include_str!("../../migrations/012_integrations.sql")),
+    (
+        13,
+        include_str!("../../migrations/013_atproto_integration.sql"),
+    ),
+    (14, include_str!("../../migrations/014_user_id_to_did.sql")),
+    (
+        15,
+        include_str!("../../migrations/015_premium_for_free.sql"),
+    ),
+    (
+        16,
+        include_str!("../../migrations/016_fts_delete_trigger.sql"),
+    ),
+    (17, include_str!("../../migrations/017_channel_modes.sql")),
+    (18, include_str!("../../migrations/018_irc_sasl.sql")),
+    (19, include_str!("../../migrations/019_matrix_bridge.sql")),
+    (20, include_str!("../../migrations/020_user_admin.sql")),
+    (21, include_str!("../../migrations/021_automod_queue.sql")),
+    (
+        22,
+        include_str!("../../migrations/022_automod_rule_types.sql"),
+    ),
+    (23, include_str!("../../migrations/023_reports.sql")),
+    (24, include_str!("../../migrations/024_infractions.sql")),
+    (25, include_str!("../../migrations/025_temp_bans.sql")),
+    (26, include_str!("../../migrations/026_instance_admin.sql")),
+    (27, include_str!("../../migrations/027_system_user.sql")),
+    (28, include_str!("../../migrations/028_admin_changes.sql")),
+];
+
 /// Split SQL text into statements, respecting BEGIN...END blocks (triggers).
 fn split_sql_statements(sql: &str) -> Vec<String> {
     let mut statements = Vec::new();
@@ -81,9 +134,9 @@ fn split_sql_statements(sql: &str) -> Vec<String> {
     statements
 }
 
-/// Run all pending migration SQL files against the database.
-pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    // Ensure schema_version table exists for tracking
+/// Create the schema_version tracking table if needed and return the
+/// highest applied migration version.
+async fn current_schema_version(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS schema_version (\
             version     INTEGER PRIMARY KEY, \
@@ -93,49 +146,16 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     .execute(pool)
     .await?;
 
-    let current_version: i64 =
-        sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM schema_version")
-            .fetch_one(pool)
-            .await?;
+    sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM schema_version")
+        .fetch_one(pool)
+        .await
+}
 
-    let migrations: &[(i64, &str)] = &[
-        (1, include_str!("../../migrations/001_initial.sql")),
-        (2, include_str!("../../migrations/002_servers.sql")),
-        (
-            3,
-            include_str!("../../migrations/003_messaging_enhancements.sql"),
-        ),
-        (4, include_str!("../../migrations/004_media_files.sql")),
-        (
-            5,
-            include_str!("../../migrations/005_atproto_blob_storage.sql"),
-        ),
-        (6, include_str!("../../migrations/006_server_config.sql")),
-        (
-            7,
-            include_str!("../../migrations/007_organization_permissions.sql"),
-        ),
-        (8, include_str!("../../migrations/008_user_experience.sql")),
-        (9, include_str!("../../migrations/009_threads_pinning.sql")),
-        (10, include_str!("../../migrations/010_moderation.sql")),
-        (11, include_str!("../../migrations/011_community.sql")),
-        (12, include_str!("../../migrations/012_integrations.sql")),
-        (
-            13,
-            include_str!("../../migrations/013_atproto_integration.sql"),
-        ),
-        (14, include_str!("../../migrations/014_user_id_to_did.sql")),
-        (
-            15,
-            include_str!("../../migrations/015_premium_for_free.sql"),
-        ),
-        (
-            16,
-            include_str!("../../migrations/016_fts_delete_trigger.sql"),
-        ),
-    ];
-
-    for &(version, sql) in migrations {
+/// Run all pending migration SQL files against the database.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let current_version = current_schema_version(pool).await?;
+
+    for &(version, sql) in MIGRATIONS {
         if version <= current_version {
             continue;
         }
@@ -188,11 +208,89 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             .await;
     }
 
-    let final_version = migrations.last().map(|m| m.0).unwrap_or(0);
+    let final_version = MIGRATIONS.last().map(|m| m.0).unwrap_or(0);
     info!("database migrations applied (version: {final_version})");
     Ok(())
 }
 
+/// A known migration and when it was applied, if it has been.
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied_at: Option<String>,
+}
+
+/// List every known migration alongside its applied_at time.
+pub async fn migration_status(pool: &SqlitePool) -> Result<Vec<MigrationStatus>, sqlx::Error> {
+    current_schema_version(pool).await?;
+    let applied: std::collections::HashMap<i64, String> =
+        sqlx::query_as::<_, (i64, String)>("SELECT version, applied_at FROM schema_version")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .collect();
+
+    Ok(MIGRATIONS
+        .iter()
+        .map(|&(version, sql)| MigrationStatus {
+            version,
+            description: migration_description(sql),
+            applied_at: applied.get(&version).cloned(),
+        })
+        .collect())
+}
+
+/// The migration's header comment, without the "Migration NNN:" prefix.
+fn migration_description(sql: &str) -> String {
+    let header = sql
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("--"))
+        .unwrap_or("")
+        .trim();
+    match header.split_once(": ") {
+        Some((prefix, rest)) if prefix.starts_with("Migration ") => rest.to_string(),
+        _ => header.to_string(),
+    }
+}
+
+/// Apply every pending migration inside a transaction that is then rolled
+/// back, so failures surface without touching the schema. Returns the
+/// versions that would be applied.
+pub async fn dry_run_migrations(pool: &SqlitePool) -> Result<Vec<i64>, sqlx::Error> {
+    let current_version = current_schema_version(pool).await?;
+    let pending: Vec<_> = MIGRATIONS
+        .iter()
+        .filter(|(version, _)| *version > current_version)
+        .collect();
+    if pending.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Same connection setup as run_migrations, which applies them with foreign keys off
+    let mut conn = pool.acquire().await?;
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await?;
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = conn.begin().await?;
+        for &&(version, sql) in &pending {
+            info!("checking migration {version}...");
+            for statement in split_sql_statements(sql) {
+                sqlx::query(&statement).execute(&mut *tx).await?;
+            }
+        }
+        tx.rollback().await
+    }
+    .await;
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *conn)
+        .await?;
+    result?;
+
+    Ok(pending.iter().map(|(version, _)| *version).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -286,7 +384,7 @@ END;";
             .fetch_one(&pool)
             .await
             .unwrap();
-        assert_eq!(count, 16);
+        assert_eq!(count, 28);
 
         // Running again should not duplicate (INSERT OR IGNORE)
         run_migrations(&pool).await.unwrap();
@@ -295,7 +393,7 @@ END;";
             .fetch_one(&pool)
             .await
             .unwrap();
-        assert_eq!(count_after, 16, "No duplicate version rows after re-run");
+        assert_eq!(count_after, 28, "No duplicate version rows after re-run");
     }
 
     #[tokio::test]
@@ -347,10 +445,38 @@ END;";
                 .fetch_all(&pool)
                 .await
                 .unwrap();
-        let expected: Vec<i64> = (1..=16).collect();
+        let expected: Vec<i64> = (1..=28).collect();
         assert_eq!(
             versions, expected,
             "Migration versions should be 1 through 12"
         );
     }
+
+    #[tokio::test]
+    async fn test_dry_run_leaves_schema_untouched() {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+
+        let pending = dry_run_migrations(&pool).await.unwrap();
+        assert_eq!(pending, (1..=28).collect::<Vec<i64>>());
+        let users_exists: bool = sqlx::query_scalar(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name='users'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(!users_exists, "dry run must roll back");
+        let status = migration_status(&pool).await.unwrap();
+        assert!(status.iter().all(|m| m.applied_at.is_none()));
+
+        run_migrations(&pool).await.unwrap();
+        assert!(dry_run_migrations(&pool).await.unwrap().is_empty());
+        let status = migration_status(&pool).await.unwrap();
+        assert_eq!(status.len(), 28);
+        assert!(status.iter().all(|m| m.applied_at.is_some()));
+        assert_eq!(
+            status[1].description,
+            "Multi-server (guild) support + system admin"
+        );
+        assert_eq!(status[0].description, "Concord initial schema");
+    }
 }
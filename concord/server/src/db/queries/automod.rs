@@ -1,6 +1,8 @@
 use sqlx::SqlitePool;
 
-use crate::db::models::{AutomodRuleRow, CreateAutomodRuleParams};
+use crate::db::models::{
+    AutomodQueueRow, AutomodRuleRow, CreateAutomodQueueParams, CreateAutomodRuleParams,
+};
 
 pub async fn get_rule(
     pool: &SqlitePool,
@@ -86,6 +88,71 @@ pub async fn get_enabled_rules(
     .await
 }
 
+pub async fn create_queue_entry(
+    pool: &SqlitePool,
+    params: &CreateAutomodQueueParams<'_>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO automod_queue (id, server_id, channel_id, rule_id, rule_name, user_id, user_nick, content, reply_to_id, message_id, status) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(params.id)
+    .bind(params.server_id)
+    .bind(params.channel_id)
+    .bind(params.rule_id)
+    .bind(params.rule_name)
+    .bind(params.user_id)
+    .bind(params.user_nick)
+    .bind(params.content)
+    .bind(params.reply_to_id)
+    .bind(params.message_id)
+    .bind(params.status)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_queue_entry(
+    pool: &SqlitePool,
+    id: &str,
+) -> Result<Option<AutomodQueueRow>, sqlx::Error> {
+    sqlx::query_as::<_, AutomodQueueRow>("SELECT * FROM automod_queue WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Entries awaiting review (flagged or pending), oldest first.
+pub async fn list_open_queue(
+    pool: &SqlitePool,
+    server_id: &str,
+) -> Result<Vec<AutomodQueueRow>, sqlx::Error> {
+    sqlx::query_as::<_, AutomodQueueRow>(
+        "SELECT * FROM automod_queue WHERE server_id = ? AND status IN ('flagged', 'pending') ORDER BY created_at",
+    )
+    .bind(server_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Record a review decision. Returns false if the entry was already reviewed,
+/// so two moderators can't both act on it.
+pub async fn review_queue_entry(
+    pool: &SqlitePool,
+    id: &str,
+    status: &str,
+    reviewed_by: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE automod_queue SET status = ?, reviewed_by = ?, reviewed_at = datetime('now') WHERE id = ? AND status IN ('flagged', 'pending')",
+    )
+    .bind(status)
+    .bind(reviewed_by)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -309,4 +376,63 @@ mod tests {
         let rules = list_rules(&pool, "s1").await.unwrap();
         assert_eq!(rules.len(), 3);
     }
+
+    #[tokio::test]
+    async fn test_queue_entries_are_reviewed_once() {
+        let pool = setup_db().await;
+        setup_server(&pool).await;
+        crate::db::queries::channels::ensure_channel(&pool, "c1", "s1", "#general")
+            .await
+            .unwrap();
+        create_rule(
+            &pool,
+            &CreateAutomodRuleParams {
+                id: "am1",
+                server_id: "s1",
+                name: "No Spam",
+                rule_type: "keyword",
+                config: "{\"words\":[\"spam\"]}",
+                action_type: "delete",
+                timeout_duration_seconds: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        create_queue_entry(
+            &pool,
+            &CreateAutomodQueueParams {
+                id: "q1",
+                server_id: "s1",
+                channel_id: "c1",
+                rule_id: "am1",
+                rule_name: "No Spam",
+                user_id: "u1",
+                user_nick: "alice",
+                content: "spam",
+                reply_to_id: None,
+                message_id: None,
+                status: "pending",
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(list_open_queue(&pool, "s1").await.unwrap().len(), 1);
+
+        assert!(
+            review_queue_entry(&pool, "q1", "approved", "u1")
+                .await
+                .unwrap()
+        );
+        assert!(
+            !review_queue_entry(&pool, "q1", "rejected", "u1")
+                .await
+                .unwrap()
+        );
+
+        let entry = get_queue_entry(&pool, "q1").await.unwrap().unwrap();
+        assert_eq!(entry.status, "approved");
+        assert_eq!(entry.reviewed_by.as_deref(), Some("u1"));
+        assert!(list_open_queue(&pool, "s1").await.unwrap().is_empty());
+    }
 }
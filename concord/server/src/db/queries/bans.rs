@@ -1,25 +1,24 @@
 use sqlx::SqlitePool;
 
-use crate::db::models::BanRow;
+use crate::db::models::{BanRow, CreateBanParams};
 
+/// Ban a user, or update the existing ban's reason, purge window and expiry.
 pub async fn create_ban(
     pool: &SqlitePool,
-    id: &str,
-    server_id: &str,
-    user_id: &str,
-    banned_by: &str,
-    reason: Option<&str>,
-    delete_message_days: i32,
+    params: &CreateBanParams<'_>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
-        "INSERT INTO bans (id, server_id, user_id, banned_by, reason, delete_message_days) VALUES (?, ?, ?, ?, ?, ?)",
+        "INSERT INTO bans (id, server_id, user_id, banned_by, reason, delete_message_seconds, expires_at) VALUES (?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(server_id, user_id) DO UPDATE SET banned_by = excluded.banned_by, reason = excluded.reason, \
+         delete_message_seconds = excluded.delete_message_seconds, expires_at = excluded.expires_at",
     )
-    .bind(id)
-    .bind(server_id)
-    .bind(user_id)
-    .bind(banned_by)
-    .bind(reason)
-    .bind(delete_message_days)
+    .bind(params.id)
+    .bind(params.server_id)
+    .bind(params.user_id)
+    .bind(params.banned_by)
+    .bind(params.reason)
+    .bind(params.delete_message_seconds)
+    .bind(params.expires_at)
     .execute(pool)
     .await?;
     Ok(())
@@ -50,27 +49,59 @@ pub async fn get_ban(
         .await
 }
 
+/// Bans still in force, newest first.
 pub async fn list_bans(pool: &SqlitePool, server_id: &str) -> Result<Vec<BanRow>, sqlx::Error> {
-    sqlx::query_as::<_, BanRow>("SELECT * FROM bans WHERE server_id = ? ORDER BY created_at DESC")
-        .bind(server_id)
-        .fetch_all(pool)
-        .await
+    sqlx::query_as::<_, BanRow>(
+        "SELECT * FROM bans WHERE server_id = ? AND (expires_at IS NULL OR expires_at > datetime('now')) ORDER BY created_at DESC",
+    )
+    .bind(server_id)
+    .fetch_all(pool)
+    .await
 }
 
+/// Whether a ban is in force. Expired bans no longer count even before the
+/// sweep removes them.
 pub async fn is_banned(
     pool: &SqlitePool,
     server_id: &str,
     user_id: &str,
 ) -> Result<bool, sqlx::Error> {
-    let count: i64 =
-        sqlx::query_scalar("SELECT COUNT(*) FROM bans WHERE server_id = ? AND user_id = ?")
-            .bind(server_id)
-            .bind(user_id)
-            .fetch_one(pool)
-            .await?;
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM bans WHERE server_id = ? AND user_id = ? AND (expires_at IS NULL OR expires_at > datetime('now'))",
+    )
+    .bind(server_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
     Ok(count > 0)
 }
 
+/// Temporary bans whose time is up, across all servers.
+pub async fn list_expired_bans(pool: &SqlitePool) -> Result<Vec<BanRow>, sqlx::Error> {
+    sqlx::query_as::<_, BanRow>(
+        "SELECT * FROM bans WHERE expires_at IS NOT NULL AND expires_at <= datetime('now')",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Remove a ban only if it has expired, so a ban re-issued since the sweep
+/// listed it is left alone.
+pub async fn remove_expired_ban(
+    pool: &SqlitePool,
+    server_id: &str,
+    user_id: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "DELETE FROM bans WHERE server_id = ? AND user_id = ? AND expires_at IS NOT NULL AND expires_at <= datetime('now')",
+    )
+    .bind(server_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,12 +149,29 @@ mod tests {
             .unwrap();
     }
 
+    fn ban<'a>(
+        id: &'a str,
+        user_id: &'a str,
+        reason: Option<&'a str>,
+        delete_message_seconds: i64,
+    ) -> CreateBanParams<'a> {
+        CreateBanParams {
+            id,
+            server_id: "s1",
+            user_id,
+            banned_by: "u1",
+            reason,
+            delete_message_seconds,
+            expires_at: None,
+        }
+    }
+
     #[tokio::test]
     async fn test_create_and_get_ban() {
         let pool = setup_db().await;
         setup_server(&pool).await;
 
-        create_ban(&pool, "b1", "s1", "u2", "u1", Some("Spam"), 0)
+        create_ban(&pool, &ban("b1", "u2", Some("Spam"), 0))
             .await
             .unwrap();
 
@@ -142,9 +190,7 @@ mod tests {
 
         assert!(!is_banned(&pool, "s1", "u2").await.unwrap());
 
-        create_ban(&pool, "b1", "s1", "u2", "u1", None, 0)
-            .await
-            .unwrap();
+        create_ban(&pool, &ban("b1", "u2", None, 0)).await.unwrap();
 
         assert!(is_banned(&pool, "s1", "u2").await.unwrap());
     }
@@ -168,10 +214,8 @@ mod tests {
         .await
         .unwrap();
 
-        create_ban(&pool, "b1", "s1", "u2", "u1", None, 0)
-            .await
-            .unwrap();
-        create_ban(&pool, "b2", "s1", "u3", "u1", Some("Abuse"), 7)
+        create_ban(&pool, &ban("b1", "u2", None, 0)).await.unwrap();
+        create_ban(&pool, &ban("b2", "u3", Some("Abuse"), 604800))
             .await
             .unwrap();
 
@@ -183,9 +227,7 @@ mod tests {
     async fn test_remove_ban() {
         let pool = setup_db().await;
         setup_server(&pool).await;
-        create_ban(&pool, "b1", "s1", "u2", "u1", None, 0)
-            .await
-            .unwrap();
+        create_ban(&pool, &ban("b1", "u2", None, 0)).await.unwrap();
 
         let removed = remove_ban(&pool, "s1", "u2").await.unwrap();
         assert!(removed);
@@ -198,16 +240,81 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_ban_with_delete_message_days() {
+    async fn test_ban_with_delete_message_seconds() {
         let pool = setup_db().await;
         setup_server(&pool).await;
 
-        create_ban(&pool, "b1", "s1", "u2", "u1", None, 7)
+        create_ban(&pool, &ban("b1", "u2", None, 604800))
             .await
             .unwrap();
 
         let ban = get_ban(&pool, "s1", "u2").await.unwrap().unwrap();
-        assert_eq!(ban.delete_message_days, 7);
+        assert_eq!(ban.delete_message_seconds, 7 * 86400);
+    }
+
+    #[tokio::test]
+    async fn test_reban_updates_existing_ban() {
+        let pool = setup_db().await;
+        setup_server(&pool).await;
+
+        create_ban(&pool, &ban("b1", "u2", Some("Spam"), 0))
+            .await
+            .unwrap();
+        create_ban(
+            &pool,
+            &CreateBanParams {
+                expires_at: Some("2099-01-01 00:00:00"),
+                ..ban("b2", "u2", Some("Spam again"), 3600)
+            },
+        )
+        .await
+        .unwrap();
+
+        let bans = list_bans(&pool, "s1").await.unwrap();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].reason.as_deref(), Some("Spam again"));
+        assert_eq!(bans[0].expires_at.as_deref(), Some("2099-01-01 00:00:00"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_bans() {
+        let pool = setup_db().await;
+        setup_server(&pool).await;
+
+        // Pre-emptive ban of an account that has never signed in
+        create_ban(
+            &pool,
+            &CreateBanParams {
+                expires_at: Some("2000-01-01 00:00:00"),
+                ..ban("b1", "did:plc:stranger", None, 0)
+            },
+        )
+        .await
+        .unwrap();
+        create_ban(
+            &pool,
+            &CreateBanParams {
+                expires_at: Some("2099-01-01 00:00:00"),
+                ..ban("b2", "u2", None, 0)
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(!is_banned(&pool, "s1", "did:plc:stranger").await.unwrap());
+        assert!(is_banned(&pool, "s1", "u2").await.unwrap());
+        assert_eq!(list_bans(&pool, "s1").await.unwrap().len(), 1);
+
+        let expired = list_expired_bans(&pool).await.unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].user_id, "did:plc:stranger");
+        assert!(!remove_expired_ban(&pool, "s1", "u2").await.unwrap());
+        assert!(
+            remove_expired_ban(&pool, "s1", "did:plc:stranger")
+                .await
+                .unwrap()
+        );
+        assert!(list_expired_bans(&pool).await.unwrap().is_empty());
     }
 
     #[tokio::test]
@@ -3,6 +3,7 @@ use sqlx::SqlitePool;
 use crate::db::models::{ChannelFollowRow, ServerRow, ServerTemplateRow};
 
 /// List discoverable servers with optional category filter and pagination.
+/// Quarantined servers are never listed.
 pub async fn list_discoverable_servers(
     pool: &SqlitePool,
     category: Option<&str>,
@@ -11,7 +12,8 @@ pub async fn list_discoverable_servers(
 ) -> Result<Vec<ServerRow>, sqlx::Error> {
     if let Some(cat) = category {
         sqlx::query_as::<_, ServerRow>(
-            "SELECT * FROM servers WHERE is_discoverable = 1 AND category = ? ORDER BY name LIMIT ? OFFSET ?",
+            "SELECT * FROM servers WHERE is_discoverable = 1 AND quarantined_at IS NULL AND category = ? \
+             ORDER BY name LIMIT ? OFFSET ?",
         )
         .bind(cat)
         .bind(limit)
@@ -20,7 +22,8 @@ pub async fn list_discoverable_servers(
         .await
     } else {
         sqlx::query_as::<_, ServerRow>(
-            "SELECT * FROM servers WHERE is_discoverable = 1 ORDER BY name LIMIT ? OFFSET ?",
+            "SELECT * FROM servers WHERE is_discoverable = 1 AND quarantined_at IS NULL \
+             ORDER BY name LIMIT ? OFFSET ?",
         )
         .bind(limit)
         .bind(offset)
@@ -289,6 +292,15 @@ mod tests {
             discoverable[0].description,
             Some("A great server".to_string())
         );
+
+        // Quarantined servers drop out of discovery
+        servers::set_quarantine(&pool, "s1", true, None)
+            .await
+            .unwrap();
+        let discoverable = list_discoverable_servers(&pool, None, 100, 0)
+            .await
+            .unwrap();
+        assert!(discoverable.is_empty());
     }
 
     #[tokio::test]
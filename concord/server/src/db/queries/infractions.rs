@@ -0,0 +1,257 @@
+use sqlx::SqlitePool;
+
+use crate::db::models::{
+    CreateEscalationPolicyParams, CreateInfractionParams, EscalationPolicyRow, InfractionRow,
+};
+
+pub async fn create_infraction(
+    pool: &SqlitePool,
+    params: &CreateInfractionParams<'_>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO infractions (id, server_id, user_id, actor_id, action, source, reason, expires_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(params.id)
+    .bind(params.server_id)
+    .bind(params.user_id)
+    .bind(params.actor_id)
+    .bind(params.action)
+    .bind(params.source)
+    .bind(params.reason)
+    .bind(params.expires_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_infraction(
+    pool: &SqlitePool,
+    id: &str,
+) -> Result<Option<InfractionRow>, sqlx::Error> {
+    sqlx::query_as::<_, InfractionRow>("SELECT * FROM infractions WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// A member's full history in a server, newest first, including expired and
+/// pardoned entries.
+pub async fn list_member_infractions(
+    pool: &SqlitePool,
+    server_id: &str,
+    user_id: &str,
+) -> Result<Vec<InfractionRow>, sqlx::Error> {
+    sqlx::query_as::<_, InfractionRow>(
+        "SELECT * FROM infractions WHERE server_id = ? AND user_id = ? ORDER BY created_at DESC, rowid DESC",
+    )
+    .bind(server_id)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Pardon an infraction so it no longer counts as a strike. Returns false if
+/// it was already pardoned.
+pub async fn pardon_infraction(
+    pool: &SqlitePool,
+    id: &str,
+    pardoned_by: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE infractions SET pardoned_by = ?, pardoned_at = datetime('now') WHERE id = ? AND pardoned_at IS NULL",
+    )
+    .bind(pardoned_by)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Count a member's strikes in the last `window_seconds`: unexpired,
+/// unpardoned infractions that weren't themselves issued by escalation.
+pub async fn count_strikes(
+    pool: &SqlitePool,
+    server_id: &str,
+    user_id: &str,
+    window_seconds: i64,
+) -> Result<i64, sqlx::Error> {
+    let row: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM infractions WHERE server_id = ? AND user_id = ? AND source != 'escalation' AND pardoned_at IS NULL AND (expires_at IS NULL OR expires_at > datetime('now')) AND created_at > datetime('now', ?)",
+    )
+    .bind(server_id)
+    .bind(user_id)
+    .bind(format!("-{window_seconds} seconds"))
+    .fetch_one(pool)
+    .await?;
+    Ok(row.0)
+}
+
+/// A server's escalation ladder, lowest threshold first.
+pub async fn list_escalation_policies(
+    pool: &SqlitePool,
+    server_id: &str,
+) -> Result<Vec<EscalationPolicyRow>, sqlx::Error> {
+    sqlx::query_as::<_, EscalationPolicyRow>(
+        "SELECT * FROM escalation_policies WHERE server_id = ? ORDER BY threshold",
+    )
+    .bind(server_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Replace a server's escalation ladder.
+pub async fn replace_escalation_policies(
+    pool: &SqlitePool,
+    server_id: &str,
+    policies: &[CreateEscalationPolicyParams<'_>],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM escalation_policies WHERE server_id = ?")
+        .bind(server_id)
+        .execute(&mut *tx)
+        .await?;
+    for policy in policies {
+        sqlx::query(
+            "INSERT INTO escalation_policies (id, server_id, threshold, window_seconds, action, duration_seconds) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(policy.id)
+        .bind(server_id)
+        .bind(policy.threshold)
+        .bind(policy.window_seconds)
+        .bind(policy.action)
+        .bind(policy.duration_seconds)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::pool::{create_pool, run_migrations};
+    use crate::db::queries::servers;
+    use crate::db::queries::users::{self, CreateOAuthUser};
+
+    async fn setup_db() -> SqlitePool {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        users::create_with_oauth(
+            &pool,
+            &CreateOAuthUser {
+                user_id: "u1",
+                username: "alice",
+                email: None,
+                avatar_url: None,
+                oauth_id: "oauth-u1",
+                provider: "github",
+                provider_id: "gh-u1",
+            },
+        )
+        .await
+        .unwrap();
+        servers::create_server(&pool, "s1", "Test", "u1", None)
+            .await
+            .unwrap();
+        pool
+    }
+
+    fn infraction<'a>(id: &'a str, source: &'a str) -> CreateInfractionParams<'a> {
+        CreateInfractionParams {
+            id,
+            server_id: "s1",
+            user_id: "u2",
+            actor_id: Some("u1"),
+            action: "warn",
+            source,
+            reason: Some("be nice"),
+            expires_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_count_strikes() {
+        let pool = setup_db().await;
+
+        create_infraction(&pool, &infraction("i1", "manual"))
+            .await
+            .unwrap();
+        create_infraction(&pool, &infraction("i2", "automod"))
+            .await
+            .unwrap();
+        // Escalations, expired and pardoned entries don't count
+        create_infraction(&pool, &infraction("i3", "escalation"))
+            .await
+            .unwrap();
+        create_infraction(
+            &pool,
+            &CreateInfractionParams {
+                expires_at: Some("2000-01-01 00:00:00"),
+                ..infraction("i4", "manual")
+            },
+        )
+        .await
+        .unwrap();
+        create_infraction(&pool, &infraction("i5", "manual"))
+            .await
+            .unwrap();
+        assert!(pardon_infraction(&pool, "i5", "u1").await.unwrap());
+        assert!(!pardon_infraction(&pool, "i5", "u1").await.unwrap());
+        assert_eq!(count_strikes(&pool, "s1", "u2", 3600).await.unwrap(), 2);
+
+        // Outside the window
+        sqlx::query(
+            "UPDATE infractions SET created_at = datetime('now', '-2 hours') WHERE id = 'i1'",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(count_strikes(&pool, "s1", "u2", 3600).await.unwrap(), 1);
+        assert_eq!(count_strikes(&pool, "s1", "u2", 86400).await.unwrap(), 2);
+
+        let history = list_member_infractions(&pool, "s1", "u2").await.unwrap();
+        assert_eq!(history.len(), 5);
+        assert_eq!(history.last().unwrap().id, "i1");
+    }
+
+    #[tokio::test]
+    async fn test_replace_escalation_policies() {
+        let pool = setup_db().await;
+        let ladder = [
+            CreateEscalationPolicyParams {
+                id: "p2",
+                threshold: 5,
+                window_seconds: 86400,
+                action: "ban",
+                duration_seconds: None,
+            },
+            CreateEscalationPolicyParams {
+                id: "p1",
+                threshold: 3,
+                window_seconds: 86400,
+                action: "timeout",
+                duration_seconds: Some(3600),
+            },
+        ];
+        replace_escalation_policies(&pool, "s1", &ladder)
+            .await
+            .unwrap();
+        let thresholds: Vec<i64> = list_escalation_policies(&pool, "s1")
+            .await
+            .unwrap()
+            .iter()
+            .map(|p| p.threshold)
+            .collect();
+        assert_eq!(thresholds, [3, 5]);
+
+        replace_escalation_policies(&pool, "s1", &ladder[..1])
+            .await
+            .unwrap();
+        let policies = list_escalation_policies(&pool, "s1").await.unwrap();
+        assert_eq!(policies.len(), 1);
+        assert_eq!(policies[0].action, "ban");
+    }
+}
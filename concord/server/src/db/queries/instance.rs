@@ -0,0 +1,222 @@
+use sqlx::SqlitePool;
+
+use crate::db::models::{
+    AdminChangeRow, CreateInstanceAuditParams, InstanceAuditLogRow, InstanceBanRow,
+};
+
+/// Ban a user ID from the whole instance. Banning again replaces the reason.
+pub async fn create_instance_ban(
+    pool: &SqlitePool,
+    user_id: &str,
+    banned_by: &str,
+    reason: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO instance_bans (user_id, banned_by, reason) VALUES (?, ?, ?) \
+         ON CONFLICT(user_id) DO UPDATE SET banned_by = excluded.banned_by, \
+         reason = excluded.reason, created_at = datetime('now')",
+    )
+    .bind(user_id)
+    .bind(banned_by)
+    .bind(reason)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Lift an instance ban. Returns false if the user ID wasn't banned.
+pub async fn delete_instance_ban(pool: &SqlitePool, user_id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM instance_bans WHERE user_id = ?")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// List instance bans, newest first.
+pub async fn list_instance_bans(pool: &SqlitePool) -> Result<Vec<InstanceBanRow>, sqlx::Error> {
+    sqlx::query_as::<_, InstanceBanRow>(
+        "SELECT * FROM instance_bans ORDER BY created_at DESC, user_id",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn is_instance_banned(pool: &SqlitePool, user_id: &str) -> Result<bool, sqlx::Error> {
+    let banned: Option<i32> = sqlx::query_scalar("SELECT 1 FROM instance_bans WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(banned.is_some())
+}
+
+pub async fn create_audit_entry(
+    pool: &SqlitePool,
+    params: &CreateInstanceAuditParams<'_>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO instance_audit_log (id, actor_id, action_type, target_type, target_id, reason, changes) \
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(params.id)
+    .bind(params.actor_id)
+    .bind(params.action_type)
+    .bind(params.target_type)
+    .bind(params.target_id)
+    .bind(params.reason)
+    .bind(params.changes)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// List instance audit log entries, newest first, optionally filtered by
+/// action type and paged with `before` (a `created_at` timestamp).
+pub async fn list_audit_entries(
+    pool: &SqlitePool,
+    action_type: Option<&str>,
+    limit: i64,
+    before: Option<&str>,
+) -> Result<Vec<InstanceAuditLogRow>, sqlx::Error> {
+    sqlx::query_as::<_, InstanceAuditLogRow>(
+        "SELECT * FROM instance_audit_log \
+         WHERE (?1 IS NULL OR action_type = ?1) AND (?2 IS NULL OR created_at < ?2) \
+         ORDER BY created_at DESC, rowid DESC LIMIT ?3",
+    )
+    .bind(action_type)
+    .bind(before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Record a change the admin CLI made, for the running server to apply.
+pub async fn record_admin_change(
+    pool: &SqlitePool,
+    kind: &str,
+    target_id: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO admin_changes (kind, target_id) VALUES (?, ?)")
+        .bind(kind)
+        .bind(target_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Admin changes recorded after `after_id`, oldest first.
+pub async fn list_admin_changes_after(
+    pool: &SqlitePool,
+    after_id: i64,
+) -> Result<Vec<AdminChangeRow>, sqlx::Error> {
+    sqlx::query_as::<_, AdminChangeRow>("SELECT * FROM admin_changes WHERE id > ? ORDER BY id")
+        .bind(after_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// ID of the newest admin change, or 0 if there are none.
+pub async fn latest_admin_change_id(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM admin_changes")
+        .fetch_one(pool)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::pool::{create_pool, run_migrations};
+
+    async fn setup_db() -> SqlitePool {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        pool
+    }
+
+    fn entry<'a>(id: &'a str, action_type: &'a str) -> CreateInstanceAuditParams<'a> {
+        CreateInstanceAuditParams {
+            id,
+            actor_id: "admin",
+            action_type,
+            target_type: Some("user"),
+            target_id: Some("did:plc:abc"),
+            reason: None,
+            changes: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_instance_bans() {
+        let pool = setup_db().await;
+
+        // Identities that have never signed in can be banned
+        assert!(!is_instance_banned(&pool, "did:plc:abc").await.unwrap());
+        create_instance_ban(&pool, "did:plc:abc", "admin", Some("spam"))
+            .await
+            .unwrap();
+        assert!(is_instance_banned(&pool, "did:plc:abc").await.unwrap());
+
+        // Banning again replaces the reason
+        create_instance_ban(&pool, "did:plc:abc", "admin2", Some("raids"))
+            .await
+            .unwrap();
+        let bans = list_instance_bans(&pool).await.unwrap();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].banned_by, "admin2");
+        assert_eq!(bans[0].reason.as_deref(), Some("raids"));
+
+        assert!(delete_instance_ban(&pool, "did:plc:abc").await.unwrap());
+        assert!(!delete_instance_ban(&pool, "did:plc:abc").await.unwrap());
+        assert!(!is_instance_banned(&pool, "did:plc:abc").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_audit_entries_filter_and_order() {
+        let pool = setup_db().await;
+
+        create_audit_entry(&pool, &entry("a1", "user_suspend"))
+            .await
+            .unwrap();
+        create_audit_entry(&pool, &entry("a2", "instance_ban"))
+            .await
+            .unwrap();
+        create_audit_entry(&pool, &entry("a3", "user_suspend"))
+            .await
+            .unwrap();
+
+        let all = list_audit_entries(&pool, None, 50, None).await.unwrap();
+        let ids: Vec<_> = all.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["a3", "a2", "a1"]);
+
+        let suspends = list_audit_entries(&pool, Some("user_suspend"), 50, None)
+            .await
+            .unwrap();
+        assert_eq!(suspends.len(), 2);
+
+        let limited = list_audit_entries(&pool, None, 1, None).await.unwrap();
+        assert_eq!(limited.len(), 1);
+
+        let before = list_audit_entries(&pool, None, 50, Some("2000-01-01 00:00:00"))
+            .await
+            .unwrap();
+        assert!(before.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_admin_changes_after_cursor() {
+        let pool = setup_db().await;
+        assert_eq!(latest_admin_change_id(&pool).await.unwrap(), 0);
+
+        record_admin_change(&pool, "server_delete", Some("s1"))
+            .await
+            .unwrap();
+        let cursor = latest_admin_change_id(&pool).await.unwrap();
+        record_admin_change(&pool, "restore", None).await.unwrap();
+
+        let changes = list_admin_changes_after(&pool, cursor).await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, "restore");
+        assert!(changes[0].target_id.is_none());
+        assert_eq!(list_admin_changes_after(&pool, 0).await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,97 @@
+use sqlx::SqlitePool;
+
+use crate::db::models::{MatrixEventRow, MatrixRoomRow};
+
+pub async fn upsert_room(
+    pool: &SqlitePool,
+    room_id: &str,
+    server_id: &str,
+    channel_name: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO matrix_rooms (room_id, server_id, channel_name) VALUES (?, ?, ?)
+         ON CONFLICT(server_id, channel_name) DO UPDATE SET room_id = excluded.room_id",
+    )
+    .bind(room_id)
+    .bind(server_id)
+    .bind(channel_name)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn list_rooms(pool: &SqlitePool) -> Result<Vec<MatrixRoomRow>, sqlx::Error> {
+    sqlx::query_as::<_, MatrixRoomRow>("SELECT * FROM matrix_rooms")
+        .fetch_all(pool)
+        .await
+}
+
+/// Record the Matrix event behind a message, or behind a reaction when `emoji` is set.
+pub async fn insert_event(
+    pool: &SqlitePool,
+    event_id: &str,
+    room_id: &str,
+    message_id: &str,
+    sender: &str,
+    emoji: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT OR REPLACE INTO matrix_events (event_id, room_id, message_id, sender, emoji)
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(event_id)
+    .bind(room_id)
+    .bind(message_id)
+    .bind(sender)
+    .bind(emoji)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_event(
+    pool: &SqlitePool,
+    event_id: &str,
+) -> Result<Option<MatrixEventRow>, sqlx::Error> {
+    sqlx::query_as::<_, MatrixEventRow>("SELECT * FROM matrix_events WHERE event_id = ?")
+        .bind(event_id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// The Matrix event carrying a message (not one of its reactions).
+pub async fn get_message_event(
+    pool: &SqlitePool,
+    message_id: &str,
+) -> Result<Option<MatrixEventRow>, sqlx::Error> {
+    sqlx::query_as::<_, MatrixEventRow>(
+        "SELECT * FROM matrix_events WHERE message_id = ? AND emoji IS NULL",
+    )
+    .bind(message_id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_reaction_event(
+    pool: &SqlitePool,
+    message_id: &str,
+    sender: &str,
+    emoji: &str,
+) -> Result<Option<MatrixEventRow>, sqlx::Error> {
+    sqlx::query_as::<_, MatrixEventRow>(
+        "SELECT * FROM matrix_events WHERE message_id = ? AND sender = ? AND emoji = ?",
+    )
+    .bind(message_id)
+    .bind(sender)
+    .bind(emoji)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn delete_event(pool: &SqlitePool, event_id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM matrix_events WHERE event_id = ?")
+        .bind(event_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -186,6 +186,32 @@ pub async fn mark_channel_read(
     Ok(())
 }
 
+/// Move a user's read marker forward to a message, leaving it alone if it
+/// already points at a newer one.
+pub async fn advance_channel_read(
+    pool: &SqlitePool,
+    user_id: &str,
+    channel_id: &str,
+    last_read_message_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO read_states (user_id, channel_id, last_read_message_id, last_read_at) \
+         VALUES (?, ?, ?, datetime('now')) \
+         ON CONFLICT(user_id, channel_id) DO UPDATE SET \
+         last_read_message_id = excluded.last_read_message_id, \
+         last_read_at = excluded.last_read_at \
+         WHERE NOT EXISTS (SELECT 1 FROM messages WHERE id = read_states.last_read_message_id) \
+            OR (SELECT created_at, rowid FROM messages WHERE id = excluded.last_read_message_id) \
+             > (SELECT created_at, rowid FROM messages WHERE id = read_states.last_read_message_id)",
+    )
+    .bind(user_id)
+    .bind(channel_id)
+    .bind(last_read_message_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 /// Row for unread count results.
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct UnreadCountRow {
@@ -259,6 +285,87 @@ pub async fn fetch_channel_history(
     }
 }
 
+/// Fetch the most recent `limit` channel messages newer than a user's read
+/// marker and created at or after `since`, ordered newest first.
+///
+/// Without a read marker, only `since` applies (and nothing is returned when it
+/// is also None). Ties on `created_at` are broken by insertion order so messages
+/// sent in the same second as the marker aren't lost.
+pub async fn fetch_messages_after_read_marker(
+    pool: &SqlitePool,
+    user_id: &str,
+    channel_id: &str,
+    since: Option<&str>,
+    limit: i64,
+) -> Result<Vec<MessageRow>, sqlx::Error> {
+    sqlx::query_as::<_, MessageRow>(
+        "WITH marker AS ( \
+           SELECT m.created_at, m.rowid AS rid FROM read_states rs \
+           JOIN messages m ON m.id = rs.last_read_message_id \
+           WHERE rs.user_id = ?1 AND rs.channel_id = ?2 \
+         ) \
+         SELECT id, server_id, channel_id, sender_id, sender_nick, content, \
+         created_at, target_user_id, edited_at, deleted_at, reply_to_id \
+         FROM messages \
+         WHERE channel_id = ?2 AND deleted_at IS NULL \
+           AND CASE WHEN EXISTS (SELECT 1 FROM marker) \
+             THEN (created_at, rowid) > (SELECT created_at, rid FROM marker) \
+             ELSE ?3 IS NOT NULL END \
+           AND (?3 IS NULL OR created_at >= ?3) \
+         ORDER BY created_at DESC, rowid DESC \
+         LIMIT ?4",
+    )
+    .bind(user_id)
+    .bind(channel_id)
+    .bind(since)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Up to `radius` messages either side of a message in its channel, and the
+/// message itself even if deleted, oldest first.
+pub async fn fetch_message_context(
+    pool: &SqlitePool,
+    message_id: &str,
+    radius: i64,
+) -> Result<Vec<MessageRow>, sqlx::Error> {
+    let mut before = sqlx::query_as::<_, MessageRow>(
+        "WITH target AS (SELECT channel_id, created_at, rowid AS rid FROM messages WHERE id = ?) \
+         SELECT id, server_id, channel_id, sender_id, sender_nick, content, \
+         created_at, target_user_id, edited_at, deleted_at, reply_to_id \
+         FROM messages \
+         WHERE channel_id = (SELECT channel_id FROM target) AND deleted_at IS NULL \
+           AND (created_at, rowid) < (SELECT created_at, rid FROM target) \
+         ORDER BY created_at DESC, rowid DESC \
+         LIMIT ?",
+    )
+    .bind(message_id)
+    .bind(radius)
+    .fetch_all(pool)
+    .await?;
+    before.reverse();
+
+    let after = sqlx::query_as::<_, MessageRow>(
+        "WITH target AS (SELECT channel_id, created_at, rowid AS rid FROM messages WHERE id = ?) \
+         SELECT id, server_id, channel_id, sender_id, sender_nick, content, \
+         created_at, target_user_id, edited_at, deleted_at, reply_to_id \
+         FROM messages \
+         WHERE channel_id = (SELECT channel_id FROM target) \
+           AND (rowid = (SELECT rid FROM target) OR (deleted_at IS NULL \
+             AND (created_at, rowid) > (SELECT created_at, rid FROM target))) \
+         ORDER BY created_at, rowid \
+         LIMIT ?",
+    )
+    .bind(message_id)
+    .bind(radius + 1)
+    .fetch_all(pool)
+    .await?;
+
+    before.extend(after);
+    Ok(before)
+}
+
 /// Get the timestamp of the last message sent by a user in a channel (for slow mode enforcement).
 /// Uses `sender_id` (permanent user DID) instead of nickname to prevent bypass via handle changes.
 pub async fn get_last_user_message_time(
@@ -582,4 +689,96 @@ mod tests {
             .unwrap();
         mark_channel_read(&pool, "u1", "c1", "m2").await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_fetch_messages_after_read_marker() {
+        let pool = setup_db().await;
+        setup_server_and_channel(&pool).await;
+        for (id, text) in [("m1", "one"), ("m2", "two"), ("m3", "three")] {
+            insert_message(&pool, &msg_params(id, text)).await.unwrap();
+        }
+
+        // No marker and no fallback: nothing to replay
+        let rows = fetch_messages_after_read_marker(&pool, "u1", "c1", None, 10)
+            .await
+            .unwrap();
+        assert!(rows.is_empty());
+
+        // No marker: fall back to `since`
+        let rows =
+            fetch_messages_after_read_marker(&pool, "u1", "c1", Some("2000-01-01 00:00:00"), 10)
+                .await
+                .unwrap();
+        assert_eq!(rows.len(), 3);
+
+        // Marker set: only newer messages, newest first (same-second ties included)
+        mark_channel_read(&pool, "u1", "c1", "m1").await.unwrap();
+        let rows = fetch_messages_after_read_marker(&pool, "u1", "c1", None, 10)
+            .await
+            .unwrap();
+        let ids: Vec<&str> = rows.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["m3", "m2"]);
+
+        let rows = fetch_messages_after_read_marker(&pool, "u1", "c1", None, 1)
+            .await
+            .unwrap();
+        assert_eq!(rows[0].id, "m3");
+
+        // Both apply: nothing newer than the marker was created after `since`
+        let rows =
+            fetch_messages_after_read_marker(&pool, "u1", "c1", Some("2999-01-01 00:00:00"), 10)
+                .await
+                .unwrap();
+        assert!(rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_advance_channel_read_never_moves_back() {
+        let pool = setup_db().await;
+        setup_server_and_channel(&pool).await;
+        for id in ["m1", "m2", "m3"] {
+            insert_message(&pool, &msg_params(id, id)).await.unwrap();
+        }
+        let marker = async || {
+            sqlx::query_scalar::<_, String>(
+                "SELECT last_read_message_id FROM read_states WHERE user_id = 'u1'",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+        };
+
+        advance_channel_read(&pool, "u1", "c1", "m2").await.unwrap();
+        assert_eq!(marker().await, "m2");
+        advance_channel_read(&pool, "u1", "c1", "m1").await.unwrap();
+        assert_eq!(marker().await, "m2");
+        advance_channel_read(&pool, "u1", "c1", "m3").await.unwrap();
+        assert_eq!(marker().await, "m3");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_message_context() {
+        let pool = setup_db().await;
+        setup_server_and_channel(&pool).await;
+        for id in ["m1", "m2", "m3", "m4", "m5", "m6"] {
+            insert_message(&pool, &msg_params(id, id)).await.unwrap();
+        }
+        soft_delete_message(&pool, "m2").await.unwrap();
+        soft_delete_message(&pool, "m4").await.unwrap();
+
+        // Deleted neighbours are skipped, but the message itself is kept
+        let ids: Vec<String> = fetch_message_context(&pool, "m4", 2)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, ["m1", "m3", "m4", "m5", "m6"]);
+        assert!(
+            fetch_message_context(&pool, "no-such", 2)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
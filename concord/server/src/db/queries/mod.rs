@@ -12,7 +12,10 @@ pub mod embeds;
 pub mod emoji;
 pub mod events;
 pub mod forum_tags;
+pub mod infractions;
+pub mod instance;
 pub mod invites;
+pub mod matrix;
 pub mod messages;
 pub mod moderation;
 pub mod notifications;
@@ -20,6 +23,7 @@ pub mod oauth2;
 pub mod pins;
 pub mod presence;
 pub mod profiles;
+pub mod reports;
 pub mod roles;
 pub mod search;
 pub mod servers;
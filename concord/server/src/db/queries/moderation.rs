@@ -83,6 +83,34 @@ pub async fn set_nsfw(
     Ok(result.rows_affected() > 0)
 }
 
+/// Set the read-only flag on a channel.
+pub async fn set_read_only(
+    pool: &SqlitePool,
+    channel_id: &str,
+    is_read_only: bool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE channels SET is_read_only = ? WHERE id = ?")
+        .bind(is_read_only as i32)
+        .bind(channel_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Set the topic-lock flag on a channel.
+pub async fn set_topic_locked(
+    pool: &SqlitePool,
+    channel_id: &str,
+    topic_locked: bool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE channels SET topic_locked = ? WHERE id = ?")
+        .bind(topic_locked as i32)
+        .bind(channel_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
 /// Bulk delete messages by IDs (soft delete). Returns number of messages deleted.
 pub async fn bulk_delete_messages(
     pool: &SqlitePool,
@@ -105,14 +133,14 @@ pub async fn bulk_delete_messages(
     Ok(result.rows_affected())
 }
 
-/// Delete messages from a user in a server within the last N days (for ban purge).
+/// Delete messages from a user in a server within the last N seconds (for ban purge).
 pub async fn delete_user_messages(
     pool: &SqlitePool,
     server_id: &str,
     user_id: &str,
-    days: i32,
+    seconds: i64,
 ) -> Result<u64, sqlx::Error> {
-    if days <= 0 {
+    if seconds <= 0 {
         return Ok(0);
     }
     let result = sqlx::query(
@@ -120,7 +148,7 @@ pub async fn delete_user_messages(
     )
     .bind(server_id)
     .bind(user_id)
-    .bind(format!("-{days} days"))
+    .bind(format!("-{seconds} seconds"))
     .execute(pool)
     .await?;
     Ok(result.rows_affected())
@@ -283,6 +311,26 @@ mod tests {
         assert_eq!(chan.is_nsfw, 0);
     }
 
+    #[tokio::test]
+    async fn test_set_read_only_and_topic_locked() {
+        let pool = setup_db().await;
+        setup_env(&pool).await;
+
+        assert!(set_read_only(&pool, "c1", true).await.unwrap());
+        assert!(set_topic_locked(&pool, "c1", true).await.unwrap());
+        let chan = channels::get_channel(&pool, "c1").await.unwrap().unwrap();
+        assert_eq!(chan.is_read_only, 1);
+        assert_eq!(chan.topic_locked, 1);
+
+        set_read_only(&pool, "c1", false).await.unwrap();
+        set_topic_locked(&pool, "c1", false).await.unwrap();
+        let chan = channels::get_channel(&pool, "c1").await.unwrap().unwrap();
+        assert_eq!(chan.is_read_only, 0);
+        assert_eq!(chan.topic_locked, 0);
+
+        assert!(!set_read_only(&pool, "missing", true).await.unwrap());
+    }
+
     #[tokio::test]
     async fn test_bulk_delete_messages() {
         let pool = setup_db().await;
@@ -355,7 +403,9 @@ mod tests {
         .await
         .unwrap();
 
-        let deleted = delete_user_messages(&pool, "s1", "u2", 7).await.unwrap();
+        let deleted = delete_user_messages(&pool, "s1", "u2", 7 * 86400)
+            .await
+            .unwrap();
         assert_eq!(deleted, 1);
 
         let m1 = messages::get_message_by_id(&pool, "m1")
@@ -0,0 +1,199 @@
+use sqlx::SqlitePool;
+
+use crate::db::models::{CreateReportParams, ReportRow};
+
+/// File a report. Fails with a unique violation if the reporter already has an
+/// open report for the same message (or user).
+pub async fn create_report(
+    pool: &SqlitePool,
+    params: &CreateReportParams<'_>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO reports (id, server_id, reporter_id, target_user_id, message_id, channel_id, message_content, reason) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(params.id)
+    .bind(params.server_id)
+    .bind(params.reporter_id)
+    .bind(params.target_user_id)
+    .bind(params.message_id)
+    .bind(params.channel_id)
+    .bind(params.message_content)
+    .bind(params.reason)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_report(pool: &SqlitePool, id: &str) -> Result<Option<ReportRow>, sqlx::Error> {
+    sqlx::query_as::<_, ReportRow>("SELECT * FROM reports WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Open reports in a server, oldest first.
+pub async fn list_open_reports(
+    pool: &SqlitePool,
+    server_id: &str,
+) -> Result<Vec<ReportRow>, sqlx::Error> {
+    sqlx::query_as::<_, ReportRow>(
+        "SELECT * FROM reports WHERE server_id = ? AND status = 'open' ORDER BY created_at",
+    )
+    .bind(server_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Open reports across every server, oldest first, for instance admins.
+pub async fn list_all_open_reports(
+    pool: &SqlitePool,
+    limit: i64,
+) -> Result<Vec<ReportRow>, sqlx::Error> {
+    sqlx::query_as::<_, ReportRow>(
+        "SELECT * FROM reports WHERE status = 'open' ORDER BY created_at LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Close a report with the moderator's decision. Returns false if it was
+/// already resolved, so two moderators can't both act on it.
+pub async fn resolve_report(
+    pool: &SqlitePool,
+    id: &str,
+    resolution: &str,
+    resolution_note: Option<&str>,
+    resolved_by: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE reports SET status = 'resolved', resolution = ?, resolution_note = ?, resolved_by = ?, resolved_at = datetime('now') WHERE id = ? AND status = 'open'",
+    )
+    .bind(resolution)
+    .bind(resolution_note)
+    .bind(resolved_by)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Reopen a report whose resolution action failed.
+pub async fn reopen_report(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE reports SET status = 'open', resolution = NULL, resolution_note = NULL, resolved_by = NULL, resolved_at = NULL WHERE id = ?",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::pool::{create_pool, run_migrations};
+    use crate::db::queries::servers;
+    use crate::db::queries::users::{self, CreateOAuthUser};
+
+    async fn setup_db() -> SqlitePool {
+        let pool = create_pool("sqlite::memory:").await.unwrap();
+        run_migrations(&pool).await.unwrap();
+        users::create_with_oauth(
+            &pool,
+            &CreateOAuthUser {
+                user_id: "u1",
+                username: "alice",
+                email: None,
+                avatar_url: None,
+                oauth_id: "oauth-u1",
+                provider: "github",
+                provider_id: "gh-u1",
+            },
+        )
+        .await
+        .unwrap();
+        servers::create_server(&pool, "s1", "Test", "u1", None)
+            .await
+            .unwrap();
+        pool
+    }
+
+    fn report<'a>(id: &'a str, message_id: Option<&'a str>) -> CreateReportParams<'a> {
+        CreateReportParams {
+            id,
+            server_id: "s1",
+            reporter_id: "u2",
+            target_user_id: "u3",
+            message_id,
+            channel_id: None,
+            message_content: None,
+            reason: "spam",
+        }
+    }
+
+    #[tokio::test]
+    async fn test_one_open_report_per_target() {
+        let pool = setup_db().await;
+
+        create_report(&pool, &report("r1", Some("m1")))
+            .await
+            .unwrap();
+        assert!(
+            create_report(&pool, &report("r2", Some("m1")))
+                .await
+                .is_err()
+        );
+        // A different message, or the user themselves, can still be reported
+        create_report(&pool, &report("r3", Some("m2")))
+            .await
+            .unwrap();
+        create_report(&pool, &report("r4", None)).await.unwrap();
+        assert!(create_report(&pool, &report("r5", None)).await.is_err());
+
+        // Once resolved, the same message can be reported again
+        assert!(
+            resolve_report(&pool, "r1", "dismiss", None, "u1")
+                .await
+                .unwrap()
+        );
+        create_report(&pool, &report("r6", Some("m1")))
+            .await
+            .unwrap();
+
+        let open: Vec<String> = list_open_reports(&pool, "s1")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(open, ["r3", "r4", "r6"]);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_only_once() {
+        let pool = setup_db().await;
+        create_report(&pool, &report("r1", None)).await.unwrap();
+
+        assert!(
+            resolve_report(&pool, "r1", "ban", Some("repeat offender"), "u1")
+                .await
+                .unwrap()
+        );
+        assert!(
+            !resolve_report(&pool, "r1", "dismiss", None, "u1")
+                .await
+                .unwrap()
+        );
+
+        let row = get_report(&pool, "r1").await.unwrap().unwrap();
+        assert_eq!(row.status, "resolved");
+        assert_eq!(row.resolution.as_deref(), Some("ban"));
+        assert_eq!(row.resolution_note.as_deref(), Some("repeat offender"));
+
+        reopen_report(&pool, "r1").await.unwrap();
+        let row = get_report(&pool, "r1").await.unwrap().unwrap();
+        assert_eq!(row.status, "open");
+        assert!(row.resolved_by.is_none());
+    }
+}
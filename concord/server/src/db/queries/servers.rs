@@ -62,6 +62,48 @@ pub async fn list_all_servers(pool: &SqlitePool) -> Result<Vec<ServerRow>, sqlx:
         .await
 }
 
+/// List the servers a user owns.
+pub async fn list_servers_owned_by(
+    pool: &SqlitePool,
+    user_id: &str,
+) -> Result<Vec<ServerRow>, sqlx::Error> {
+    sqlx::query_as::<_, ServerRow>("SELECT * FROM servers WHERE owner_id = ? ORDER BY name")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// Make `new_owner_id` the owner of a server, adding them as a member if
+/// needed and demoting the previous owner to a regular member.
+pub async fn transfer_ownership(
+    pool: &SqlitePool,
+    server_id: &str,
+    new_owner_id: &str,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE server_members SET role = 'member' WHERE server_id = ? AND role = 'owner'")
+        .bind(server_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO server_members (server_id, user_id, role) VALUES (?, ?, 'owner') \
+         ON CONFLICT(server_id, user_id) DO UPDATE SET role = 'owner'",
+    )
+    .bind(server_id)
+    .bind(new_owner_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE servers SET owner_id = ?, updated_at = datetime('now') WHERE id = ?")
+        .bind(new_owner_id)
+        .bind(server_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
 /// Update a server's name and/or icon.
 pub async fn update_server(
     pool: &SqlitePool,
@@ -323,6 +365,28 @@ pub async fn get_server_by_vanity(
         .await
 }
 
+// ── Quarantine ──────────────────────────────────────────────
+
+/// Quarantine a server with an optional reason, or lift the quarantine.
+/// Returns false if the server doesn't exist.
+pub async fn set_quarantine(
+    pool: &SqlitePool,
+    server_id: &str,
+    quarantined: bool,
+    reason: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE servers SET quarantined_at = CASE WHEN ?1 THEN COALESCE(quarantined_at, datetime('now')) END, \
+         quarantine_reason = CASE WHEN ?1 THEN ?2 END, updated_at = datetime('now') WHERE id = ?3",
+    )
+    .bind(quarantined)
+    .bind(reason)
+    .bind(server_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -526,6 +590,27 @@ mod tests {
         assert!(!is_system_admin(&pool, "u1").await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_transfer_ownership() {
+        let pool = setup_db().await;
+        create_test_user(&pool, "u1", "alice").await;
+        create_test_user(&pool, "u2", "bob").await;
+        create_server(&pool, "s1", "Test", "u1", None)
+            .await
+            .unwrap();
+
+        transfer_ownership(&pool, "s1", "u2").await.unwrap();
+
+        let server = get_server(&pool, "s1").await.unwrap().unwrap();
+        assert_eq!(server.owner_id, "u2");
+        let old = get_server_member(&pool, "s1", "u1").await.unwrap().unwrap();
+        assert_eq!(old.role, "member");
+        let new = get_server_member(&pool, "s1", "u2").await.unwrap().unwrap();
+        assert_eq!(new.role, "owner");
+        assert!(list_servers_owned_by(&pool, "u1").await.unwrap().is_empty());
+        assert_eq!(list_servers_owned_by(&pool, "u2").await.unwrap().len(), 1);
+    }
+
     #[tokio::test]
     async fn test_add_member_idempotent() {
         let pool = setup_db().await;
@@ -656,4 +741,29 @@ mod tests {
         let server = get_server(&pool, "s1").await.unwrap().unwrap();
         assert!(server.vanity_code.is_none());
     }
+
+    #[tokio::test]
+    async fn test_set_quarantine() {
+        let pool = setup_db().await;
+        create_test_user(&pool, "u1", "alice").await;
+        create_server(&pool, "s1", "Test", "u1", None)
+            .await
+            .unwrap();
+
+        assert!(
+            set_quarantine(&pool, "s1", true, Some("spam"))
+                .await
+                .unwrap()
+        );
+        let server = get_server(&pool, "s1").await.unwrap().unwrap();
+        assert!(server.quarantined_at.is_some());
+        assert_eq!(server.quarantine_reason.as_deref(), Some("spam"));
+
+        assert!(set_quarantine(&pool, "s1", false, None).await.unwrap());
+        let server = get_server(&pool, "s1").await.unwrap().unwrap();
+        assert!(server.quarantined_at.is_none());
+        assert!(server.quarantine_reason.is_none());
+
+        assert!(!set_quarantine(&pool, "nope", true, None).await.unwrap());
+    }
 }
@@ -1,5 +1,7 @@
 use sqlx::SqlitePool;
 
+use crate::db::models::UserAccountRow;
+
 /// Parameters for creating a new OAuth-linked user.
 pub struct CreateOAuthUser<'a> {
     pub user_id: &'a str,
@@ -142,6 +144,7 @@ pub async fn get_all_irc_token_hashes(
 }
 
 /// Get IRC token hashes for a specific nickname (scoped lookup for scalability).
+/// Nicknames match case-insensitively, as they do on IRC.
 pub async fn get_irc_token_hashes_by_nick(
     pool: &SqlitePool,
     nickname: &str,
@@ -149,7 +152,7 @@ pub async fn get_irc_token_hashes_by_nick(
     sqlx::query_as::<_, (String, String)>(
         "SELECT t.user_id, t.token_hash \
          FROM irc_tokens t JOIN users u ON t.user_id = u.id \
-         WHERE u.username = ?",
+         WHERE u.username = ? COLLATE NOCASE",
     )
     .bind(nickname)
     .fetch_all(pool)
@@ -302,6 +305,234 @@ pub async fn touch_irc_token(
     Ok(())
 }
 
+/// Store the SCRAM-SHA-256 verifier for an IRC token (salt and keys base64-encoded).
+pub async fn set_irc_token_scram(
+    pool: &SqlitePool,
+    token_id: &str,
+    salt: &str,
+    iterations: i64,
+    stored_key: &str,
+    server_key: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE irc_tokens SET scram_salt = ?, scram_iterations = ?, scram_stored_key = ?, \
+         scram_server_key = ? WHERE id = ?",
+    )
+    .bind(salt)
+    .bind(iterations)
+    .bind(stored_key)
+    .bind(server_key)
+    .bind(token_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Get the SCRAM salt and iteration count of a user's newest SCRAM-capable token.
+/// All of a user's tokens share one salt so a SCRAM exchange can match any of them.
+pub async fn get_irc_scram_salt(
+    pool: &SqlitePool,
+    user_id: &str,
+) -> Result<Option<(String, i64)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, i64)>(
+        "SELECT scram_salt, scram_iterations FROM irc_tokens \
+         WHERE user_id = ? AND scram_salt IS NOT NULL \
+         ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Get the SCRAM verifiers of a nickname's IRC tokens, newest first. The
+/// nickname matches case-insensitively.
+/// Returns (user_id, token_hash, salt, iterations, stored_key, server_key).
+pub async fn get_irc_token_scram_by_nick(
+    pool: &SqlitePool,
+    nickname: &str,
+) -> Result<Vec<(String, String, String, i64, String, String)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, String, String, i64, String, String)>(
+        "SELECT t.user_id, t.token_hash, t.scram_salt, t.scram_iterations, \
+         t.scram_stored_key, t.scram_server_key \
+         FROM irc_tokens t JOIN users u ON t.user_id = u.id \
+         WHERE u.username = ? COLLATE NOCASE AND t.scram_salt IS NOT NULL \
+         ORDER BY t.created_at DESC",
+    )
+    .bind(nickname)
+    .fetch_all(pool)
+    .await
+}
+
+/// Register a TLS client certificate fingerprint for SASL EXTERNAL.
+pub async fn add_irc_cert_fingerprint(
+    pool: &SqlitePool,
+    fingerprint: &str,
+    user_id: &str,
+    label: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO irc_cert_fingerprints (fingerprint, user_id, label) VALUES (?, ?, ?)")
+        .bind(fingerprint)
+        .bind(user_id)
+        .bind(label)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// List a user's certificate fingerprints (fingerprint, label, created_at).
+pub async fn list_irc_cert_fingerprints(
+    pool: &SqlitePool,
+    user_id: &str,
+) -> Result<Vec<(String, Option<String>, String)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, Option<String>, String)>(
+        "SELECT fingerprint, label, created_at FROM irc_cert_fingerprints \
+         WHERE user_id = ? ORDER BY created_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Remove a certificate fingerprint (must belong to the user).
+pub async fn delete_irc_cert_fingerprint(
+    pool: &SqlitePool,
+    fingerprint: &str,
+    user_id: &str,
+) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("DELETE FROM irc_cert_fingerprints WHERE fingerprint = ? AND user_id = ?")
+            .bind(fingerprint)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Find the user a certificate fingerprint is registered to. Returns (user_id, username).
+pub async fn get_user_by_cert_fingerprint(
+    pool: &SqlitePool,
+    fingerprint: &str,
+) -> Result<Option<(String, String)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, String)>(
+        "SELECT u.id, u.username FROM irc_cert_fingerprints f \
+         JOIN users u ON f.user_id = u.id WHERE f.fingerprint = ?",
+    )
+    .bind(fingerprint)
+    .fetch_optional(pool)
+    .await
+}
+
+const USER_ACCOUNT_COLUMNS: &str =
+    "id, username, email, is_system_admin, is_bot, suspended_at, created_at";
+
+/// List every user account except the reserved system user, oldest first.
+pub async fn list_user_accounts(pool: &SqlitePool) -> Result<Vec<UserAccountRow>, sqlx::Error> {
+    sqlx::query_as::<_, UserAccountRow>(&format!(
+        "SELECT {USER_ACCOUNT_COLUMNS} FROM users WHERE id <> 'system' ORDER BY created_at, username"
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// Find a user account by ID or username.
+pub async fn find_user_account(
+    pool: &SqlitePool,
+    id_or_username: &str,
+) -> Result<Option<UserAccountRow>, sqlx::Error> {
+    sqlx::query_as::<_, UserAccountRow>(&format!(
+        "SELECT {USER_ACCOUNT_COLUMNS} FROM users WHERE id = ? OR username = ? LIMIT 1"
+    ))
+    .bind(id_or_username)
+    .bind(id_or_username)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Suspend or reinstate a user. Returns false if the user doesn't exist.
+pub async fn set_suspended(
+    pool: &SqlitePool,
+    user_id: &str,
+    suspended: bool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE users SET suspended_at = CASE WHEN ? THEN COALESCE(suspended_at, datetime('now')) END \
+         WHERE id = ?",
+    )
+    .bind(suspended)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revoke every credential a user holds: web sessions issued before now, IRC
+/// tokens, bot tokens and OAuth2 grants. Returns the number of stored tokens deleted.
+pub async fn revoke_user_tokens(pool: &SqlitePool, user_id: &str) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE users SET tokens_revoked_at = ? WHERE id = ?")
+        .bind(chrono::Utc::now().timestamp())
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    let mut deleted = 0;
+    for table in ["irc_tokens", "bot_tokens", "oauth2_authorizations"] {
+        deleted += sqlx::query(&format!("DELETE FROM {table} WHERE user_id = ?"))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    }
+
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+/// Whether a web session issued at `issued_at` (unix seconds) is still
+/// honoured: the user exists, isn't suspended or banned from the instance, and
+/// hasn't had tokens revoked since.
+pub async fn is_session_valid(
+    pool: &SqlitePool,
+    user_id: &str,
+    issued_at: i64,
+) -> Result<bool, sqlx::Error> {
+    let valid: Option<bool> = sqlx::query_scalar(
+        "SELECT suspended_at IS NULL AND (tokens_revoked_at IS NULL OR tokens_revoked_at < ?) \
+         AND NOT EXISTS (SELECT 1 FROM instance_bans b WHERE b.user_id = users.id) \
+         FROM users WHERE id = ?",
+    )
+    .bind(issued_at)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(valid.unwrap_or(false))
+}
+
+/// Whether a user may sign in at all: the account exists, isn't suspended and
+/// isn't banned from the instance. Checked by every credential that isn't a
+/// web session (IRC tokens and certificates, bot tokens).
+pub async fn is_account_active(pool: &SqlitePool, user_id: &str) -> Result<bool, sqlx::Error> {
+    let active: Option<bool> = sqlx::query_scalar(
+        "SELECT suspended_at IS NULL \
+         AND NOT EXISTS (SELECT 1 FROM instance_bans b WHERE b.user_id = users.id) \
+         FROM users WHERE id = ?",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(active.unwrap_or(false))
+}
+
+/// Delete a user and everything that cascades from them, including servers
+/// they own. Returns false if the user doesn't exist.
+pub async fn delete_user(pool: &SqlitePool, user_id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM users WHERE id = ?")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -420,6 +651,79 @@ mod tests {
         assert!(tokens_after_delete.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_irc_token_scram_credentials() {
+        let pool = setup_db().await;
+        create_test_user(&pool, "u1", "alice").await;
+        create_irc_token(&pool, "t1", "u1", "hash1", None)
+            .await
+            .unwrap();
+        create_irc_token(&pool, "t2", "u1", "hash2", None)
+            .await
+            .unwrap();
+
+        assert!(get_irc_scram_salt(&pool, "u1").await.unwrap().is_none());
+        set_irc_token_scram(&pool, "t2", "c2FsdA==", 4096, "c3RvcmVk", "c2VydmVy")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            get_irc_scram_salt(&pool, "u1").await.unwrap(),
+            Some(("c2FsdA==".to_string(), 4096))
+        );
+        let rows = get_irc_token_scram_by_nick(&pool, "alice").await.unwrap();
+        assert_eq!(rows.len(), 1, "tokens without SCRAM data are skipped");
+        assert_eq!(
+            get_irc_token_scram_by_nick(&pool, "ALICE").await.unwrap(),
+            rows
+        );
+        assert_eq!(rows[0].0, "u1");
+        assert_eq!(rows[0].1, "hash2");
+        assert_eq!(rows[0].4, "c3RvcmVk");
+    }
+
+    #[tokio::test]
+    async fn test_irc_cert_fingerprint_crud() {
+        let pool = setup_db().await;
+        create_test_user(&pool, "u1", "alice").await;
+        create_test_user(&pool, "u2", "bob").await;
+
+        add_irc_cert_fingerprint(&pool, "ab12", "u1", Some("laptop"))
+            .await
+            .unwrap();
+        assert!(
+            add_irc_cert_fingerprint(&pool, "ab12", "u2", None)
+                .await
+                .is_err(),
+            "a fingerprint belongs to one account"
+        );
+
+        assert_eq!(
+            get_user_by_cert_fingerprint(&pool, "ab12").await.unwrap(),
+            Some(("u1".to_string(), "alice".to_string()))
+        );
+        let certs = list_irc_cert_fingerprints(&pool, "u1").await.unwrap();
+        assert_eq!(certs.len(), 1);
+        assert_eq!(certs[0].1.as_deref(), Some("laptop"));
+
+        assert!(
+            !delete_irc_cert_fingerprint(&pool, "ab12", "u2")
+                .await
+                .unwrap()
+        );
+        assert!(
+            delete_irc_cert_fingerprint(&pool, "ab12", "u1")
+                .await
+                .unwrap()
+        );
+        assert!(
+            get_user_by_cert_fingerprint(&pool, "ab12")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
     #[tokio::test]
     async fn test_delete_irc_token_wrong_user() {
         let pool = setup_db().await;
@@ -539,4 +843,71 @@ mod tests {
         assert!(found.is_some());
         assert_eq!(found.unwrap().0, "u1");
     }
+
+    #[tokio::test]
+    async fn test_find_user_account() {
+        let pool = setup_db().await;
+        create_test_user(&pool, "u1", "alice").await;
+
+        let by_id = find_user_account(&pool, "u1").await.unwrap().unwrap();
+        assert_eq!(by_id.username, "alice");
+        let by_name = find_user_account(&pool, "alice").await.unwrap().unwrap();
+        assert_eq!(by_name.id, "u1");
+        assert!(find_user_account(&pool, "bob").await.unwrap().is_none());
+        assert_eq!(list_user_accounts(&pool).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_suspension_invalidates_sessions() {
+        let pool = setup_db().await;
+        create_test_user(&pool, "u1", "alice").await;
+        let now = chrono::Utc::now().timestamp();
+
+        assert!(is_session_valid(&pool, "u1", now).await.unwrap());
+        assert!(set_suspended(&pool, "u1", true).await.unwrap());
+        assert!(!is_session_valid(&pool, "u1", now).await.unwrap());
+        let account = find_user_account(&pool, "u1").await.unwrap().unwrap();
+        assert!(account.suspended_at.is_some());
+
+        assert!(set_suspended(&pool, "u1", false).await.unwrap());
+        assert!(is_session_valid(&pool, "u1", now).await.unwrap());
+        assert!(!set_suspended(&pool, "nobody", true).await.unwrap());
+        assert!(!is_session_valid(&pool, "nobody", now).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_instance_ban_blocks_account() {
+        let pool = setup_db().await;
+        create_test_user(&pool, "u1", "alice").await;
+        let now = chrono::Utc::now().timestamp();
+
+        assert!(is_account_active(&pool, "u1").await.unwrap());
+        crate::db::queries::instance::create_instance_ban(&pool, "u1", "admin", None)
+            .await
+            .unwrap();
+        assert!(!is_account_active(&pool, "u1").await.unwrap());
+        assert!(!is_session_valid(&pool, "u1", now).await.unwrap());
+
+        crate::db::queries::instance::delete_instance_ban(&pool, "u1")
+            .await
+            .unwrap();
+        set_suspended(&pool, "u1", true).await.unwrap();
+        assert!(!is_account_active(&pool, "u1").await.unwrap());
+        assert!(!is_account_active(&pool, "nobody").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_user_tokens() {
+        let pool = setup_db().await;
+        create_test_user(&pool, "u1", "alice").await;
+        create_irc_token(&pool, "t1", "u1", "hash1", None)
+            .await
+            .unwrap();
+        let issued = chrono::Utc::now().timestamp() - 60;
+
+        assert_eq!(revoke_user_tokens(&pool, "u1").await.unwrap(), 1);
+        assert!(list_irc_tokens(&pool, "u1").await.unwrap().is_empty());
+        assert!(!is_session_valid(&pool, "u1", issued).await.unwrap());
+        assert!(is_session_valid(&pool, "u1", issued + 120).await.unwrap());
+    }
 }
@@ -0,0 +1,1041 @@
+use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use regex::{Regex, RegexSet, RegexSetBuilder};
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::warn;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+use super::error::EngineError;
+use super::rate_limiter::SlidingWindowCounter;
+use crate::db::models::AutomodRuleRow;
+
+/// Upper bound on the compiled size of a rule's patterns.
+const MAX_PATTERN_SIZE: usize = 1 << 20;
+
+/// Longest timeout an automod action may apply (28 days).
+pub const MAX_TIMEOUT_SECONDS: u64 = 28 * 24 * 60 * 60;
+
+/// Every `rule_type` a rule can have.
+pub const RULE_TYPES: &[&str] = &[
+    "keyword",
+    "mention_spam",
+    "link_filter",
+    "duplicate_spam",
+    "message_flood",
+    "excessive_caps",
+    "zalgo",
+    "invite_spam",
+    "join_burst",
+    "raid",
+];
+
+/// Longest window a rate-based rule may count over. Counters idle for longer
+/// than this can be dropped.
+pub const MAX_WINDOW_SECONDS: u64 = 60 * 60;
+
+/// `@name` at the start of the message or after a non-word character, so
+/// e-mail addresses don't count as mentions.
+static MENTION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[^\w@])@(\w+)").expect("valid mention regex"));
+
+/// Host part of an http(s) link.
+static LINK_HOST_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\bhttps?://([^\s/?#<>:@]+)").expect("valid link regex"));
+
+/// Invite links: Discord's short and long forms, and `/invite/<code>` paths on
+/// any host, which is how Concord invites are shared.
+static INVITE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?:https?://)?(?:www\.)?(discord(?:app)?\.com/invite|discord\.(?:gg|io|me|li)|[a-z0-9.-]+(?::\d+)?(?:/[\w-]+)*/invite)/([a-z0-9-]+)",
+    )
+    .expect("valid invite regex")
+});
+
+/// A server's enabled AutoMod rules, compiled once and shared by every message
+/// check until a rule changes.
+#[derive(Debug, Default)]
+pub struct AutomodRules {
+    rules: Vec<Arc<CompiledRule>>,
+}
+
+/// One rule with its config parsed and its patterns compiled.
+#[derive(Debug)]
+pub struct CompiledRule {
+    pub id: String,
+    pub name: String,
+    pub rule_type: String,
+    pub actions: Vec<AutomodAction>,
+    exempt_roles: HashSet<String>,
+    exempt_channels: HashSet<String>,
+    matcher: Matcher,
+}
+
+/// What happens when a rule triggers. Configured as the rule's `actions` array,
+/// e.g. `[{"type":"block"},{"type":"alert","channel_id":"..."}]`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AutomodAction {
+    /// Reject the message with an error to the sender.
+    Block,
+    /// Post the offending message to a moderators' channel.
+    Alert { channel_id: String },
+    /// Time the author out for the given duration.
+    Timeout { duration_seconds: u64 },
+    /// Deliver the message but queue it for moderator review.
+    Flag,
+    /// Hold the message until a moderator approves it.
+    RequireApproval,
+}
+
+#[derive(Debug)]
+enum Matcher {
+    /// Words, wildcards and regexes in one set, matched against normalised text
+    /// after allow-listed terms have been blanked out.
+    Keyword {
+        patterns: RegexSet,
+        allow: Option<Regex>,
+    },
+    /// More than `max` distinct users or roles mentioned.
+    MentionSpam { max: usize },
+    /// Links to hosts outside `allowed_domains`, when links are restricted at all.
+    LinkFilter {
+        block_all: bool,
+        allowed_domains: Vec<String>,
+    },
+    /// The same message, after normalisation, sent more than `max` times within
+    /// `window`, in any channels.
+    DuplicateSpam { max: usize, window: Duration },
+    /// More than `max` messages from one sender within `window`.
+    MessageFlood { max: usize, window: Duration },
+    /// More than `max_percent` of the letters in capitals, once a message has at
+    /// least `min_letters` letters.
+    ExcessiveCaps {
+        max_percent: usize,
+        min_letters: usize,
+    },
+    /// A character stacked with more than `max` combining marks.
+    Zalgo { max: usize },
+    /// Invite links to any server but this one and `allowed_servers`.
+    InviteSpam { allowed_servers: HashSet<String> },
+    /// More than `max` joins by accounts younger than `max_account_age` within
+    /// `window`.
+    JoinBurst {
+        max: usize,
+        window: Duration,
+        max_account_age: Duration,
+    },
+    /// More than `max` joins of any kind within `window`.
+    Raid {
+        max: usize,
+        window: Duration,
+        lockdown: LockdownPolicy,
+    },
+}
+
+/// The lockdown a raid rule starts when it triggers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LockdownPolicy {
+    /// How long invites stay paused and new members stay muted.
+    pub duration: Duration,
+    /// Members who joined more recently than this can't send during the lockdown.
+    pub new_member_age: Duration,
+}
+
+/// A message being checked against the rules.
+#[derive(Debug, Default)]
+pub struct MessageCheck<'a> {
+    pub content: &'a str,
+    pub server_id: &'a str,
+    pub channel_id: Option<&'a str>,
+    /// The sender's roles, needed only when `has_role_exemptions`.
+    pub role_ids: &'a [String],
+    /// Identifies the sender in message counters.
+    pub sender: &'a str,
+    /// Server each of `invite_links(content)` belongs to, `None` when the code
+    /// isn't an invite on this instance. Needed only when `has_invite_rules`.
+    pub invite_servers: &'a [Option<String>],
+}
+
+/// An invite link found in a message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InviteLink {
+    /// A `/invite/<code>` link, which may be an invite on this instance.
+    Code(String),
+    /// A link to another chat service's invite.
+    External,
+}
+
+impl AutomodRules {
+    /// Compile a server's enabled rules. Rules whose config no longer compiles
+    /// are skipped with a warning rather than blocking every message.
+    pub fn compile(rows: &[AutomodRuleRow]) -> Self {
+        let rules = rows
+            .iter()
+            .filter_map(|row| match CompiledRule::compile(row) {
+                Ok(rule) => Some(Arc::new(rule)),
+                Err(e) => {
+                    warn!(rule_id = %row.id, error = %e, "skipping invalid automod rule");
+                    None
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether any rule can exempt members by role, i.e. whether `check` needs
+    /// the sender's roles.
+    pub fn has_role_exemptions(&self) -> bool {
+        self.rules.iter().any(|r| !r.exempt_roles.is_empty())
+    }
+
+    /// Whether any rule looks for invite links, i.e. whether `check` needs
+    /// `invite_servers`.
+    pub fn has_invite_rules(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|r| matches!(r.matcher, Matcher::InviteSpam { .. }))
+    }
+
+    /// Whether any rule watches joins, i.e. whether `check_join` can trigger.
+    pub fn has_join_rules(&self) -> bool {
+        self.rules.iter().any(|r| r.is_join_rule())
+    }
+
+    /// Every rule a message triggers, skipping rules the channel or any of the
+    /// sender's roles are exempt from. Rate-based rules count the message in
+    /// `counters` whether or not it triggers them.
+    pub fn check(
+        &self,
+        msg: &MessageCheck<'_>,
+        counters: &SlidingWindowCounter,
+    ) -> Vec<Arc<CompiledRule>> {
+        let content = msg.content;
+        let mut normalized = None;
+        self.rules
+            .iter()
+            .filter(|rule| {
+                if msg
+                    .channel_id
+                    .is_some_and(|id| rule.exempt_channels.contains(id))
+                    || msg.role_ids.iter().any(|id| rule.exempt_roles.contains(id))
+                {
+                    return false;
+                }
+                match &rule.matcher {
+                    Matcher::Keyword { patterns, allow } => {
+                        let text = normalized.get_or_insert_with(|| normalize(content));
+                        match allow {
+                            Some(allow) => patterns.is_match(&allow.replace_all(text, " ")),
+                            None => patterns.is_match(text),
+                        }
+                    }
+                    Matcher::MentionSpam { max } => count_mentions(content) > *max,
+                    Matcher::LinkFilter {
+                        block_all,
+                        allowed_domains,
+                    } => {
+                        (*block_all || !allowed_domains.is_empty())
+                            && link_hosts(content)
+                                .any(|host| !domain_allowed(&host, allowed_domains))
+                    }
+                    Matcher::DuplicateSpam { max, window } => {
+                        let text = normalized.get_or_insert_with(|| normalize(content));
+                        let mut hasher = DefaultHasher::new();
+                        text.split_whitespace().for_each(|w| w.hash(&mut hasher));
+                        let key = format!("{}:{}:{:x}", rule.id, msg.sender, hasher.finish());
+                        counters.record(&key, *window) > *max
+                    }
+                    Matcher::MessageFlood { max, window } => {
+                        counters.record(&format!("{}:{}", rule.id, msg.sender), *window) > *max
+                    }
+                    Matcher::ExcessiveCaps {
+                        max_percent,
+                        min_letters,
+                    } => {
+                        let (letters, caps) = content
+                            .chars()
+                            .filter(|c| c.is_alphabetic())
+                            .fold((0, 0), |(l, u), c| {
+                                (l + 1, u + usize::from(c.is_uppercase()))
+                            });
+                        letters >= *min_letters && caps * 100 > letters * max_percent
+                    }
+                    Matcher::Zalgo { max } => max_combining_marks(content) > *max,
+                    Matcher::InviteSpam { allowed_servers } => {
+                        msg.invite_servers.iter().any(|server| match server {
+                            Some(id) => id != msg.server_id && !allowed_servers.contains(id),
+                            None => true,
+                        })
+                    }
+                    Matcher::JoinBurst { .. } | Matcher::Raid { .. } => false,
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Every join rule a new member triggers. Each join is counted whether or
+    /// not it triggers anything; `account_age` is `None` when unknown, which
+    /// join-burst rules don't count.
+    pub fn check_join(
+        &self,
+        account_age: Option<Duration>,
+        counters: &SlidingWindowCounter,
+    ) -> Vec<Arc<CompiledRule>> {
+        self.rules
+            .iter()
+            .filter(|rule| match &rule.matcher {
+                Matcher::JoinBurst {
+                    max,
+                    window,
+                    max_account_age,
+                } => {
+                    account_age.is_some_and(|age| age < *max_account_age)
+                        && counters.record(&rule.id, *window) > *max
+                }
+                Matcher::Raid { max, window, .. } => counters.record(&rule.id, *window) > *max,
+                _ => false,
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+impl CompiledRule {
+    /// Whether the rule watches server joins rather than messages.
+    pub fn is_join_rule(&self) -> bool {
+        matches!(
+            self.matcher,
+            Matcher::JoinBurst { .. } | Matcher::Raid { .. }
+        )
+    }
+
+    /// The lockdown this rule starts when it triggers, for raid rules.
+    pub fn lockdown(&self) -> Option<LockdownPolicy> {
+        match self.matcher {
+            Matcher::Raid { lockdown, .. } => Some(lockdown),
+            _ => None,
+        }
+    }
+
+    fn compile(row: &AutomodRuleRow) -> Result<Self, EngineError> {
+        let config: Value = serde_json::from_str(&row.config)
+            .map_err(|_| invalid("Invalid JSON in automod config"))?;
+        let actions = match config.get("actions") {
+            Some(actions) => parse_actions(&row.rule_type, actions)?,
+            None => legacy_actions(&row.action_type, row.timeout_duration_seconds),
+        };
+        Ok(Self {
+            id: row.id.clone(),
+            name: row.name.clone(),
+            rule_type: row.rule_type.clone(),
+            actions,
+            exempt_roles: string_array(&config, "exempt_roles")?.into_iter().collect(),
+            exempt_channels: string_array(&config, "exempt_channels")?
+                .into_iter()
+                .collect(),
+            matcher: compile_matcher(&row.rule_type, &config)?,
+        })
+    }
+}
+
+fn compile_matcher(rule_type: &str, config: &Value) -> Result<Matcher, EngineError> {
+    match rule_type {
+        // Config: {"words":["spam","free*"],"patterns":["b(u|uu)y\\s+now"],"allow":["spammer*"]}
+        "keyword" => {
+            let mut sources: Vec<String> = string_array(config, "words")?
+                .iter()
+                .map(|w| word_pattern(w))
+                .collect();
+            sources.extend(string_array(config, "patterns")?);
+            if sources.is_empty() {
+                return Err(invalid(
+                    "keyword config must have a non-empty 'words' or 'patterns' array",
+                ));
+            }
+            let patterns = RegexSetBuilder::new(&sources)
+                .case_insensitive(true)
+                .size_limit(MAX_PATTERN_SIZE)
+                .build()
+                .map_err(|e| invalid(format!("Invalid keyword pattern: {e}")))?;
+
+            let allow = string_array(config, "allow")?;
+            let allow = if allow.is_empty() {
+                None
+            } else {
+                let alternation = allow
+                    .iter()
+                    .map(|w| word_pattern(w))
+                    .collect::<Vec<_>>()
+                    .join("|");
+                Some(
+                    regex::RegexBuilder::new(&alternation)
+                        .case_insensitive(true)
+                        .size_limit(MAX_PATTERN_SIZE)
+                        .build()
+                        .map_err(|e| invalid(format!("Invalid allow-list entry: {e}")))?,
+                )
+            };
+            Ok(Matcher::Keyword { patterns, allow })
+        }
+        // Config: {"max_mentions":5}
+        "mention_spam" => {
+            let max = config
+                .get("max_mentions")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| invalid("mention_spam config must have a 'max_mentions' integer"))?;
+            Ok(Matcher::MentionSpam { max: max as usize })
+        }
+        // Config: {"block_all":true} or {"allowed_domains":["example.com"]}
+        "link_filter" => Ok(Matcher::LinkFilter {
+            block_all: config
+                .get("block_all")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            allowed_domains: string_array(config, "allowed_domains")?
+                .into_iter()
+                .map(|d| d.trim_start_matches("*.").to_lowercase())
+                .collect(),
+        }),
+        // Config: {"max_duplicates":3,"window_seconds":60}
+        "duplicate_spam" => Ok(Matcher::DuplicateSpam {
+            max: bounded(config, "max_duplicates", 3, 1..=100)? as usize,
+            window: window(config, 60)?,
+        }),
+        // Config: {"max_messages":10,"window_seconds":10}
+        "message_flood" => Ok(Matcher::MessageFlood {
+            max: bounded(config, "max_messages", 10, 1..=1000)? as usize,
+            window: window(config, 10)?,
+        }),
+        // Config: {"max_caps_percent":70,"min_letters":10}
+        "excessive_caps" => Ok(Matcher::ExcessiveCaps {
+            max_percent: bounded(config, "max_caps_percent", 70, 1..=99)? as usize,
+            min_letters: bounded(config, "min_letters", 10, 1..=1000)? as usize,
+        }),
+        // Config: {"max_combining_marks":4}
+        "zalgo" => Ok(Matcher::Zalgo {
+            max: bounded(config, "max_combining_marks", 4, 1..=100)? as usize,
+        }),
+        // Config: {"allowed_servers":["<server id>"]}
+        "invite_spam" => Ok(Matcher::InviteSpam {
+            allowed_servers: string_array(config, "allowed_servers")?
+                .into_iter()
+                .collect(),
+        }),
+        // Config: {"max_joins":5,"window_seconds":60,"max_account_age_seconds":86400}
+        "join_burst" => Ok(Matcher::JoinBurst {
+            max: bounded(config, "max_joins", 5, 1..=1000)? as usize,
+            window: window(config, 60)?,
+            max_account_age: Duration::from_secs(bounded(
+                config,
+                "max_account_age_seconds",
+                24 * 60 * 60,
+                1..=365 * 24 * 60 * 60,
+            )?),
+        }),
+        // Config: {"max_joins":10,"window_seconds":30,"lockdown_seconds":600,"new_member_seconds":600}
+        "raid" => Ok(Matcher::Raid {
+            max: bounded(config, "max_joins", 10, 1..=1000)? as usize,
+            window: window(config, 30)?,
+            lockdown: LockdownPolicy {
+                duration: Duration::from_secs(bounded(
+                    config,
+                    "lockdown_seconds",
+                    10 * 60,
+                    1..=MAX_TIMEOUT_SECONDS,
+                )?),
+                new_member_age: Duration::from_secs(bounded(
+                    config,
+                    "new_member_seconds",
+                    10 * 60,
+                    0..=MAX_TIMEOUT_SECONDS,
+                )?),
+            },
+        }),
+        _ => Err(invalid(format!("Unknown rule type: {rule_type}"))),
+    }
+}
+
+/// A rule config or action list that can't be used.
+fn invalid(message: impl Into<String>) -> EngineError {
+    EngineError::Validation(message.into())
+}
+
+/// Optional integer under `key` within `range`; missing means `default`.
+fn bounded(
+    config: &Value,
+    key: &str,
+    default: u64,
+    range: std::ops::RangeInclusive<u64>,
+) -> Result<u64, EngineError> {
+    let value = match config.get(key) {
+        None | Some(Value::Null) => default,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| invalid(format!("'{key}' must be a non-negative integer")))?,
+    };
+    if !range.contains(&value) {
+        return Err(invalid(format!(
+            "'{key}' must be between {} and {}",
+            range.start(),
+            range.end()
+        )));
+    }
+    Ok(value)
+}
+
+/// The rule's `window_seconds`, at most `MAX_WINDOW_SECONDS`.
+fn window(config: &Value, default: u64) -> Result<Duration, EngineError> {
+    bounded(config, "window_seconds", default, 1..=MAX_WINDOW_SECONDS).map(Duration::from_secs)
+}
+
+/// Check that a config compiles, so invalid patterns are rejected when the rule
+/// is saved rather than silently skipped later.
+pub fn validate_config(rule_type: &str, config: &Value) -> Result<(), EngineError> {
+    string_array(config, "exempt_roles")?;
+    string_array(config, "exempt_channels")?;
+    compile_matcher(rule_type, config).map(|_| ())
+}
+
+/// Parse and check a rule's `actions` array.
+pub fn parse_actions(rule_type: &str, actions: &Value) -> Result<Vec<AutomodAction>, EngineError> {
+    let actions: Vec<AutomodAction> = serde_json::from_value(actions.clone())
+        .map_err(|e| invalid(format!("Invalid automod actions: {e}")))?;
+    if actions.is_empty() {
+        return Err(invalid("automod 'actions' must not be empty"));
+    }
+    let has = |wanted: fn(&AutomodAction) -> bool| actions.iter().filter(|a| wanted(a)).count();
+    if has(|a| matches!(a, AutomodAction::Block)) > 1
+        || has(|a| matches!(a, AutomodAction::Flag)) > 1
+        || has(|a| matches!(a, AutomodAction::RequireApproval)) > 1
+        || has(|a| matches!(a, AutomodAction::Timeout { .. })) > 1
+    {
+        return Err(invalid("automod 'actions' must not repeat an action type"));
+    }
+    if has(|a| matches!(a, AutomodAction::Block)) > 0
+        && has(|a| matches!(a, AutomodAction::RequireApproval)) > 0
+    {
+        return Err(invalid(
+            "automod actions 'block' and 'require_approval' are mutually exclusive",
+        ));
+    }
+    // Join rules act on the member, not a message: block refuses the join
+    if matches!(rule_type, "join_burst" | "raid")
+        && has(|a| matches!(a, AutomodAction::Flag | AutomodAction::RequireApproval)) > 0
+    {
+        return Err(invalid(format!(
+            "{rule_type} rules only support 'block', 'alert' and 'timeout' actions"
+        )));
+    }
+    for action in &actions {
+        match action {
+            AutomodAction::Alert { channel_id } if channel_id.is_empty() => {
+                return Err(invalid("automod 'alert' action needs a 'channel_id'"));
+            }
+            AutomodAction::Timeout { duration_seconds }
+                if !(1..=MAX_TIMEOUT_SECONDS).contains(duration_seconds) =>
+            {
+                return Err(invalid(format!(
+                    "automod 'timeout' duration must be between 1 and {MAX_TIMEOUT_SECONDS} seconds"
+                )));
+            }
+            _ => {}
+        }
+    }
+    Ok(actions)
+}
+
+/// Actions for rules saved before `actions` existed, from their `action_type` column.
+fn legacy_actions(action_type: &str, timeout_duration_seconds: Option<i32>) -> Vec<AutomodAction> {
+    match action_type {
+        "timeout" => vec![
+            AutomodAction::Block,
+            AutomodAction::Timeout {
+                duration_seconds: timeout_duration_seconds
+                    .and_then(|s| u64::try_from(s).ok())
+                    .filter(|s| (1..=MAX_TIMEOUT_SECONDS).contains(s))
+                    .unwrap_or(60),
+            },
+        ],
+        "flag" => vec![AutomodAction::Flag],
+        _ => vec![AutomodAction::Block],
+    }
+}
+
+/// Optional array of strings under `key`; missing means empty.
+fn string_array(config: &Value, key: &str) -> Result<Vec<String>, EngineError> {
+    match config.get(key) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| {
+                v.as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| invalid(format!("'{key}' must contain only strings")))
+            })
+            .collect(),
+        Some(_) => Err(invalid(format!("'{key}' must be an array of strings"))),
+    }
+}
+
+/// Regex for a keyword entry: a whole word or phrase, where `*` matches any
+/// run of word characters (`free*`, `*coin`). The entry is normalised the same
+/// way as message text.
+fn word_pattern(word: &str) -> String {
+    let word = normalize(word.trim());
+    let body = word
+        .split('*')
+        .map(|part| {
+            part.split_whitespace()
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(r"\s+")
+        })
+        .collect::<Vec<_>>()
+        .join(r"\w*");
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let start = if word.starts_with(is_word_char) {
+        r"\b"
+    } else {
+        ""
+    };
+    let end = if word.ends_with(is_word_char) {
+        r"\b"
+    } else {
+        ""
+    };
+    format!("{start}{body}{end}")
+}
+
+/// Fold text so look-alike spellings match the same patterns: compatibility
+/// decomposition (fullwidth and styled letters), accents and invisible
+/// characters removed, lowercased, and common Cyrillic/Greek homoglyphs mapped
+/// to their Latin counterparts.
+pub fn normalize(text: &str) -> String {
+    text.nfkd()
+        .filter(|&c| !is_combining_mark(c) && !is_invisible(c))
+        .flat_map(char::to_lowercase)
+        .map(fold_confusable)
+        .collect()
+}
+
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}' | '\u{180E}' | '\u{200B}'..='\u{200F}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}'
+    )
+}
+
+fn fold_confusable(c: char) -> char {
+    match c {
+        'а' | 'α' => 'a',
+        'в' | 'β' => 'b',
+        'с' | 'ϲ' => 'c',
+        'ԁ' => 'd',
+        'е' | 'ё' | 'ε' => 'e',
+        'һ' | 'н' => 'h',
+        'і' | 'ї' | 'ι' | 'ı' => 'i',
+        'ј' => 'j',
+        'к' | 'κ' => 'k',
+        'ӏ' => 'l',
+        'м' => 'm',
+        'η' => 'n',
+        'о' | 'ο' | 'σ' => 'o',
+        'р' | 'ρ' => 'p',
+        'ԛ' => 'q',
+        'ѕ' => 's',
+        'т' | 'τ' => 't',
+        'υ' => 'u',
+        'ν' => 'v',
+        'ԝ' | 'ω' => 'w',
+        'х' | 'χ' => 'x',
+        'у' | 'γ' => 'y',
+        _ => c,
+    }
+}
+
+/// Number of distinct `@user`/`@role` mentions, including `@everyone` and `@here`.
+pub fn count_mentions(content: &str) -> usize {
+    MENTION_RE
+        .captures_iter(content)
+        .map(|c| c[1].to_lowercase())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Longest run of combining marks on a single character.
+fn max_combining_marks(content: &str) -> usize {
+    let (longest, _) = content.nfd().fold((0, 0), |(longest, run), c| {
+        if is_combining_mark(c) {
+            (longest.max(run + 1), run + 1)
+        } else {
+            (longest, 0)
+        }
+    });
+    longest
+}
+
+/// Invite links in a message, in order.
+pub fn invite_links(content: &str) -> Vec<InviteLink> {
+    INVITE_RE
+        .captures_iter(content)
+        .map(|c| {
+            if c[1].to_lowercase().starts_with("discord") {
+                InviteLink::External
+            } else {
+                InviteLink::Code(c[2].to_string())
+            }
+        })
+        .collect()
+}
+
+fn link_hosts(content: &str) -> impl Iterator<Item = String> + '_ {
+    LINK_HOST_RE
+        .captures_iter(content)
+        .map(|c| c[1].trim_end_matches('.').to_lowercase())
+}
+
+/// `host` is an allowed domain or one of its subdomains.
+fn domain_allowed(host: &str, allowed_domains: &[String]) -> bool {
+    allowed_domains.iter().any(|domain| {
+        host == domain
+            || host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|rest| rest.ends_with('.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(rule_type: &str, config: &str) -> AutomodRuleRow {
+        AutomodRuleRow {
+            id: "r1".into(),
+            server_id: "s1".into(),
+            name: "test".into(),
+            enabled: 1,
+            rule_type: rule_type.into(),
+            config: config.into(),
+            action_type: "delete".into(),
+            timeout_duration_seconds: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    fn rules(rule_type: &str, config: &str) -> AutomodRules {
+        AutomodRules::compile(&[row(rule_type, config)])
+    }
+
+    fn check(rules: &AutomodRules, content: &str) -> Vec<Arc<CompiledRule>> {
+        let msg = MessageCheck {
+            content,
+            server_id: "s1",
+            sender: "alice",
+            ..Default::default()
+        };
+        rules.check(&msg, &SlidingWindowCounter::new())
+    }
+
+    fn blocks(rules: &AutomodRules, content: &str) -> bool {
+        !check(rules, content).is_empty()
+    }
+
+    #[test]
+    fn test_keyword_words_and_wildcards() {
+        let r = rules("keyword", r#"{"words":["spam","free*","*coin","buy now"]}"#);
+        assert!(blocks(&r, "no SPAM here"));
+        assert!(!blocks(&r, "spammy"));
+        assert!(blocks(&r, "get freebies"));
+        assert!(blocks(&r, "shitcoin pump"));
+        assert!(blocks(&r, "buy   now!"));
+        assert!(!blocks(&r, "buying nowhere"));
+    }
+
+    #[test]
+    fn test_keyword_regex_and_allow_list() {
+        let r = rules(
+            "keyword",
+            r#"{"patterns":["sp[a4]m+"],"words":["scam*"],"allow":["scampi"]}"#,
+        );
+        assert!(blocks(&r, "sp4mmm"));
+        assert!(blocks(&r, "scammer"));
+        assert!(!blocks(&r, "I ordered scampi"));
+        assert!(blocks(&r, "scampi and a scam"));
+    }
+
+    #[test]
+    fn test_keyword_defeats_confusables() {
+        let r = rules("keyword", r#"{"words":["spam"]}"#);
+        // Cyrillic а, fullwidth, accents, zero-width space, math bold
+        assert!(blocks(&r, "spаm"));
+        assert!(blocks(&r, "ｓｐａｍ"));
+        assert!(blocks(&r, "spâm"));
+        assert!(blocks(&r, "sp\u{200B}am"));
+        assert!(blocks(&r, "𝐬𝐩𝐚𝐦"));
+    }
+
+    #[test]
+    fn test_mentions_are_counted_distinctly() {
+        assert_eq!(count_mentions("@a @b @a"), 2);
+        assert_eq!(count_mentions("mail me at bob@example.com"), 0);
+        assert_eq!(count_mentions("@everyone (@here)"), 2);
+
+        let r = rules("mention_spam", r#"{"max_mentions":2}"#);
+        assert!(blocks(&r, "@a @b @c"));
+        assert!(!blocks(&r, "@a @b @a @b"));
+        assert!(!blocks(&r, "a@b.c d@e.f g@h.i"));
+    }
+
+    #[test]
+    fn test_link_filter_allowed_domains() {
+        let r = rules("link_filter", r#"{"allowed_domains":["example.com"]}"#);
+        assert!(!blocks(&r, "see https://docs.example.com/x"));
+        assert!(blocks(&r, "see https://evil-example.com"));
+        assert!(blocks(&r, "ok https://example.com then http://x.io"));
+
+        assert!(blocks(
+            &rules("link_filter", r#"{"block_all":true}"#),
+            "https://x.io"
+        ));
+        assert!(!blocks(&rules("link_filter", "{}"), "https://x.io"));
+    }
+
+    #[test]
+    fn test_exempt_roles_and_channels() {
+        let r = rules(
+            "keyword",
+            r#"{"words":["spam"],"exempt_roles":["mods"],"exempt_channels":["c1"]}"#,
+        );
+        assert!(r.has_role_exemptions());
+        let counters = SlidingWindowCounter::new();
+        let mods = ["mods".to_string()];
+        let msg = |channel_id, role_ids| MessageCheck {
+            content: "spam",
+            channel_id: Some(channel_id),
+            role_ids,
+            ..Default::default()
+        };
+        assert_eq!(r.check(&msg("c2", &[]), &counters).len(), 1);
+        assert!(r.check(&msg("c1", &[]), &counters).is_empty());
+        assert!(r.check(&msg("c2", &mods), &counters).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_rules_are_rejected_or_skipped() {
+        let config: Value = serde_json::from_str(r#"{"patterns":["("]}"#).unwrap();
+        assert!(validate_config("keyword", &config).is_err());
+        let config: Value =
+            serde_json::from_str(r#"{"words":["x"],"exempt_roles":"mods"}"#).unwrap();
+        assert!(validate_config("keyword", &config).is_err());
+
+        assert!(rules("keyword", r#"{"patterns":["("]}"#).is_empty());
+        assert!(rules("keyword", "not json").is_empty());
+    }
+
+    #[test]
+    fn test_actions_parse_and_validate() {
+        let actions = parse_actions(
+            "keyword",
+            &serde_json::json!([
+            {"type": "alert", "channel_id": "mod-log"},
+            {"type": "timeout", "duration_seconds": 600},
+            {"type": "require_approval"},
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            actions,
+            vec![
+                AutomodAction::Alert {
+                    channel_id: "mod-log".into()
+                },
+                AutomodAction::Timeout {
+                    duration_seconds: 600
+                },
+                AutomodAction::RequireApproval,
+            ]
+        );
+
+        for bad in [
+            serde_json::json!([]),
+            serde_json::json!([{"type": "explode"}]),
+            serde_json::json!([{"type": "alert"}]),
+            serde_json::json!([{"type": "timeout", "duration_seconds": 0}]),
+            serde_json::json!([{"type": "block"}, {"type": "require_approval"}]),
+            serde_json::json!([{"type": "flag"}, {"type": "flag"}]),
+        ] {
+            assert!(
+                parse_actions("keyword", &bad).is_err(),
+                "{bad} should be rejected"
+            );
+        }
+        assert!(parse_actions("raid", &serde_json::json!([{"type": "flag"}])).is_err());
+    }
+
+    #[test]
+    fn test_legacy_action_type_maps_to_actions() {
+        let mut legacy = row("keyword", r#"{"words":["spam"]}"#);
+        legacy.action_type = "timeout".into();
+        legacy.timeout_duration_seconds = Some(300);
+        let legacy_rules = AutomodRules::compile(&[legacy]);
+        assert_eq!(
+            check(&legacy_rules, "spam")[0].actions,
+            vec![
+                AutomodAction::Block,
+                AutomodAction::Timeout {
+                    duration_seconds: 300
+                }
+            ]
+        );
+
+        let flagged = rules(
+            "keyword",
+            r#"{"words":["spam"],"actions":[{"type":"flag"}]}"#,
+        );
+        assert_eq!(
+            check(&flagged, "spam")[0].actions,
+            vec![AutomodAction::Flag]
+        );
+    }
+
+    #[test]
+    fn test_flood_and_duplicates_count_per_sender() {
+        let r = AutomodRules::compile(&[
+            row("message_flood", r#"{"max_messages":3,"window_seconds":60}"#),
+            AutomodRuleRow {
+                id: "r2".into(),
+                ..row("duplicate_spam", r#"{"max_duplicates":2}"#)
+            },
+        ]);
+        let counters = SlidingWindowCounter::new();
+        let send = |sender, content, channel_id| {
+            let msg = MessageCheck {
+                content,
+                sender,
+                channel_id: Some(channel_id),
+                ..Default::default()
+            };
+            r.check(&msg, &counters)
+                .iter()
+                .map(|rule| rule.rule_type.clone())
+                .collect::<Vec<_>>()
+        };
+        assert!(send("alice", "buy now", "c1").is_empty());
+        // Same text in another channel, with different spacing and case
+        assert!(send("alice", "BUY  now", "c2").is_empty());
+        assert_eq!(send("alice", "buy now", "c3"), ["duplicate_spam"]);
+        assert_eq!(send("alice", "hello", "c1"), ["message_flood"]);
+        assert!(send("bob", "buy now", "c1").is_empty());
+    }
+
+    #[test]
+    fn test_excessive_caps_and_zalgo() {
+        let caps = rules(
+            "excessive_caps",
+            r#"{"max_caps_percent":70,"min_letters":8}"#,
+        );
+        assert!(blocks(&caps, "STOP SHOUTING AT ME"));
+        assert!(!blocks(&caps, "OK LOL"));
+        assert!(!blocks(&caps, "Please Read The FAQ"));
+
+        let zalgo = rules("zalgo", r#"{"max_combining_marks":3}"#);
+        assert!(blocks(&zalgo, "he\u{0301}\u{0302}\u{0303}\u{0304}llo"));
+        assert!(!blocks(&zalgo, "Tiếng Việt, café"));
+    }
+
+    #[test]
+    fn test_invite_spam_to_other_servers() {
+        assert_eq!(
+            invite_links("join https://chat.example.org/invite/AbC123 or discord.gg/xyz"),
+            vec![InviteLink::Code("AbC123".into()), InviteLink::External]
+        );
+
+        let r = rules("invite_spam", r#"{"allowed_servers":["partner"]}"#);
+        let counters = SlidingWindowCounter::new();
+        let hits = |invite_servers: &[Option<String>]| {
+            let msg = MessageCheck {
+                content: "come join",
+                server_id: "s1",
+                invite_servers,
+                ..Default::default()
+            };
+            r.check(&msg, &counters).len()
+        };
+        assert_eq!(hits(&[Some("s1".into()), Some("partner".into())]), 0);
+        assert_eq!(hits(&[Some("other".into())]), 1);
+        assert_eq!(hits(&[None]), 1);
+    }
+
+    #[test]
+    fn test_join_burst_and_raid() {
+        let r = AutomodRules::compile(&[
+            row(
+                "join_burst",
+                r#"{"max_joins":1,"window_seconds":60,"max_account_age_seconds":3600}"#,
+            ),
+            AutomodRuleRow {
+                id: "r2".into(),
+                ..row(
+                    "raid",
+                    r#"{"max_joins":2,"lockdown_seconds":300,"new_member_seconds":900}"#,
+                )
+            },
+        ]);
+        assert!(r.has_join_rules());
+        assert!(check(&r, "any message").is_empty());
+
+        let counters = SlidingWindowCounter::new();
+        let old = Some(Duration::from_secs(7200));
+        let new = Some(Duration::from_secs(60));
+        let joined = |age| {
+            r.check_join(age, &counters)
+                .iter()
+                .map(|rule| rule.rule_type.clone())
+                .collect::<Vec<_>>()
+        };
+        assert!(joined(new).is_empty());
+        assert!(joined(old).is_empty());
+        assert_eq!(joined(new), ["join_burst", "raid"]);
+
+        let raid = r.check_join(None, &counters);
+        assert_eq!(
+            raid[0].lockdown(),
+            Some(LockdownPolicy {
+                duration: Duration::from_secs(300),
+                new_member_age: Duration::from_secs(900),
+            })
+        );
+    }
+
+    #[test]
+    fn test_behaviour_rule_config_bounds() {
+        for (rule_type, config) in [
+            ("message_flood", r#"{"max_messages":0}"#),
+            ("duplicate_spam", r#"{"window_seconds":86400}"#),
+            ("excessive_caps", r#"{"max_caps_percent":"lots"}"#),
+            ("raid", r#"{"lockdown_seconds":0}"#),
+            ("invite_spam", r#"{"allowed_servers":"all"}"#),
+        ] {
+            let config: Value = serde_json::from_str(config).unwrap();
+            assert!(
+                matches!(
+                    validate_config(rule_type, &config),
+                    Err(EngineError::Validation(_))
+                ),
+                "{rule_type} {config} should be rejected"
+            );
+        }
+        assert!(validate_config("zalgo", &serde_json::json!({})).is_ok());
+    }
+}
@@ -34,6 +34,10 @@ pub struct ChannelState {
     pub slowmode_seconds: i32,
     /// Whether this channel is marked NSFW.
     pub is_nsfw: bool,
+    /// Whether only moderators may post (IRC `+m`).
+    pub is_read_only: bool,
+    /// Whether only channel managers may change the topic (IRC `+t`).
+    pub topic_locked: bool,
 }
 
 impl ChannelState {
@@ -56,6 +60,8 @@ impl ChannelState {
             archived: false,
             slowmode_seconds: 0,
             is_nsfw: false,
+            is_read_only: false,
+            topic_locked: false,
         }
     }
 
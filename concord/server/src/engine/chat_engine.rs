@@ -1,36 +1,108 @@
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::Instant;
 
 use chrono::Utc;
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
 use sqlx::SqlitePool;
 use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use super::automod::{self, AutomodAction, AutomodRules, CompiledRule};
 use super::channel::ChannelState;
+use super::error::EngineError;
 use super::events::{
-    AuditLogEntry, AutomodRuleInfo, BanInfo, BookmarkInfo, BotTokenInfo, CategoryInfo,
-    ChannelFollowInfo, ChannelInfo, ChannelPositionInfo, ChatEvent, EventInfo, HistoryMessage,
-    InteractionInfo, InteractionResponseData, InviteInfo, MemberInfo, OAuth2AppInfo,
-    PinnedMessageInfo, ReactionGroup, ReplyInfo, RoleInfo, RsvpInfo, ServerCommunityInfo,
-    ServerInfo, SessionId, SlashCommandInfo, SlashCommandOption, TemplateInfo, ThreadInfo,
-    WebhookInfo,
+    AuditLogEntry, AutomodQueueEntryInfo, AutomodRuleInfo, BanInfo, BookmarkInfo, BotTokenInfo,
+    BulkBanFailure, CategoryInfo, ChannelFollowInfo, ChannelInfo, ChannelPositionInfo, ChatEvent,
+    EscalationPolicyInfo, EventInfo, HistoryMessage, InfractionInfo, InstanceBanInfo,
+    InstanceReportInfo, InteractionInfo, InteractionResponseData, InviteInfo, MemberInfo,
+    OAuth2AppInfo, PinnedMessageInfo, ReactionGroup, ReplyInfo, ReportInfo, RoleInfo, RsvpInfo,
+    ServerCommunityInfo, ServerInfo, SessionId, SlashCommandInfo, SlashCommandOption, TemplateInfo,
+    ThreadInfo, WebhookInfo,
 };
 use super::permissions::{
     self, ChannelOverride, DEFAULT_ADMIN, DEFAULT_EVERYONE, DEFAULT_MODERATOR, OverrideTargetType,
     Permissions, ServerRole,
 };
-use super::rate_limiter::RateLimiter;
-use super::server::ServerState;
+use super::rate_limiter::{RateLimiter, SlidingWindowCounter};
+use super::server::{CachedRoles, Lockdown, PermissionCache, ServerState};
 use super::user_session::{Protocol, UserSession};
 use super::validation;
+use crate::irc::bouncer::Bouncer;
+use crate::metrics::METRICS;
 
 /// The default server ID used as a fallback for IRC clients
 /// that don't specify a server. No server with this ID is pre-created;
 /// IRC bare-channel operations will fail unless one is created by a user.
 pub const DEFAULT_SERVER_ID: &str = "default";
 
+/// Maximum nicknames on one session's monitor list (IRC MONITOR/WATCH).
+pub const MONITOR_LIMIT: usize = 100;
+
+/// Display name of messages AutoMod posts to alert channels.
+pub const AUTOMOD_NICK: &str = "AutoMod [System]";
+
+/// Reserved user ID that automatic moderation (strike escalations) acts as.
+pub const SYSTEM_USER_ID: &str = "system";
+
+/// Longest reason a member may give when filing a report.
+pub const MAX_REPORT_REASON_LENGTH: usize = 1000;
+
+/// Messages shown either side of a reported message in the moderator queue.
+const REPORT_CONTEXT_MESSAGES: i64 = 3;
+
+/// Ways a moderator can resolve a report.
+pub const REPORT_ACTIONS: &[&str] = &["dismiss", "delete", "timeout", "kick", "ban"];
+
+/// How long an infraction counts as a strike unless the moderator sets an expiry.
+const STRIKE_EXPIRY_SECONDS: u64 = 90 * 24 * 60 * 60;
+
+/// Longest expiry a moderator may give a warning.
+const MAX_STRIKE_EXPIRY_SECONDS: u64 = 365 * 24 * 60 * 60;
+
+/// Actions an escalation policy can take.
+pub const ESCALATION_ACTIONS: &[&str] = &["timeout", "kick", "ban"];
+
+/// Most steps a server's escalation ladder may have.
+const MAX_ESCALATION_POLICIES: usize = 10;
+
+/// Longest a temporary ban may last.
+pub const MAX_BAN_DURATION_SECONDS: u64 = 365 * 24 * 60 * 60;
+
+/// Furthest back a ban may delete the user's messages.
+pub const MAX_BAN_PURGE_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// Most user IDs one bulk ban may take.
+pub const MAX_BULK_BAN: usize = 200;
+
+/// Longest reason a system admin may give for an instance-level action.
+pub const MAX_ADMIN_REASON_LENGTH: usize = 1000;
+
+/// Most open reports the instance-wide admin view returns.
+const MAX_INSTANCE_REPORTS: i64 = 500;
+
+/// Parameters for banning a user (avoids too-many-arguments).
+#[derive(Default)]
+pub struct BanParams<'a> {
+    pub reason: Option<&'a str>,
+    /// Lift the ban automatically after this long; permanent when unset.
+    pub duration_seconds: Option<u64>,
+    /// Delete the user's messages from this far back, up to
+    /// `MAX_BAN_PURGE_SECONDS`.
+    pub delete_message_seconds: u64,
+}
+
+/// Parameters for resolving a report (avoids too-many-arguments).
+pub struct ResolveReportParams<'a> {
+    pub report_id: &'a str,
+    /// One of `REPORT_ACTIONS`.
+    pub action: &'a str,
+    pub note: Option<&'a str>,
+    /// Length of a `timeout` action; one hour when unset.
+    pub timeout_seconds: Option<u64>,
+}
+
 /// Parameters for updating notification settings (avoids too-many-arguments).
 pub struct UpdateNotificationSettingsParams<'a> {
     pub server_id: &'a str,
@@ -53,12 +125,15 @@ pub struct ChatEngine {
     channels: DashMap<String, ChannelState>,
     /// Index: (server_id, channel_name) -> channel_id for name-based lookups.
     channel_name_index: DashMap<(String, String), String>,
-    /// Reverse lookup: nickname -> session ID (for DMs and WHOIS).
+    /// Reverse lookup: lowercased nickname -> session ID (for DMs and WHOIS).
     nick_to_session: DashMap<String, SessionId>,
     /// Optional database pool. When present, messages and channels are persisted.
     db: Option<SqlitePool>,
     /// Per-user message rate limiter (burst of 10, refill 1 per second).
     message_limiter: RateLimiter,
+    /// Sliding-window counters for behavioural automod rules (floods, duplicate
+    /// messages, join bursts), keyed by rule ID and sender.
+    automod_counters: SlidingWindowCounter,
     /// HTTP client for outbound requests (link embed unfurling).
     http_client: reqwest::Client,
     /// Maximum message content length (configurable, default 4000).
@@ -68,6 +143,14 @@ pub struct ChatEngine {
     /// In-memory slow mode tracker: (user_id, channel_id) -> last message Instant.
     /// Prevents concurrent requests from bypassing the DB-based cooldown check.
     slowmode_last_sent: DashMap<(String, String), Instant>,
+    /// Monitor lists (IRC MONITOR/WATCH): watcher session -> nicknames it watches.
+    monitors: DashMap<SessionId, Vec<String>>,
+    /// Index: lowercased nickname -> sessions watching it.
+    monitor_watchers: DashMap<String, std::collections::HashSet<SessionId>>,
+    /// Users whose presence is invisible; monitors see them as offline.
+    invisible_users: DashSet<String>,
+    /// Always-on IRC sessions and their settings.
+    bouncer: Bouncer,
 }
 
 impl ChatEngine {
@@ -80,10 +163,15 @@ impl ChatEngine {
             nick_to_session: DashMap::new(),
             db,
             message_limiter: RateLimiter::new(10, 1.0),
+            automod_counters: SlidingWindowCounter::new(),
             http_client: reqwest::Client::new(),
             max_message_length,
             max_file_size_mb,
             slowmode_last_sent: DashMap::new(),
+            monitors: DashMap::new(),
+            monitor_watchers: DashMap::new(),
+            invisible_users: DashSet::new(),
+            bouncer: Bouncer::default(),
         }
     }
 
@@ -97,10 +185,17 @@ impl ChatEngine {
         self.max_file_size_mb
     }
 
+    /// This engine's always-on IRC sessions.
+    pub fn bouncer(&self) -> &Bouncer {
+        &self.bouncer
+    }
+
     /// Remove stale rate-limiter buckets that haven't been used recently.
     pub fn cleanup_rate_limiter(&self) {
         self.message_limiter
             .cleanup(std::time::Duration::from_secs(600));
+        self.automod_counters
+            .cleanup(std::time::Duration::from_secs(automod::MAX_WINDOW_SECONDS));
     }
 
     /// Remove stale slow mode cache entries older than the given duration.
@@ -113,22 +208,25 @@ impl ChatEngine {
     // ── Startup loading ─────────────────────────────────────────────
 
     /// Load servers from the database into memory on startup.
-    pub async fn load_servers_from_db(&self) -> Result<(), String> {
+    pub async fn load_servers_from_db(&self) -> Result<(), EngineError> {
         let Some(pool) = &self.db else {
             return Ok(());
         };
 
         let rows = crate::db::queries::servers::list_all_servers(pool)
             .await
-            .map_err(|e| format!("Failed to load servers: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to load servers: {e}")))?;
 
         for row in rows {
             let mut state =
                 ServerState::new(row.id.clone(), row.name, row.owner_id.clone(), row.icon_url);
+            state.quarantined = row.quarantined_at.is_some();
 
             let members = crate::db::queries::servers::get_server_members(pool, &row.id)
                 .await
-                .map_err(|e| format!("Failed to load server members: {e}"))?;
+                .map_err(|e| {
+                    EngineError::Internal(format!("Failed to load server members: {e}"))
+                })?;
             for m in members {
                 state.member_user_ids.insert(m.user_id);
             }
@@ -187,7 +285,7 @@ impl ChatEngine {
     }
 
     /// Load channels from the database into memory on startup.
-    pub async fn load_channels_from_db(&self) -> Result<(), String> {
+    pub async fn load_channels_from_db(&self) -> Result<(), EngineError> {
         let Some(pool) = &self.db else {
             return Ok(());
         };
@@ -199,7 +297,7 @@ impl ChatEngine {
         for server_id in &server_ids {
             let rows = crate::db::queries::channels::list_channels(pool, server_id)
                 .await
-                .map_err(|e| format!("Failed to load channels: {e}"))?;
+                .map_err(|e| EngineError::Internal(format!("Failed to load channels: {e}")))?;
 
             for row in rows {
                 let mut ch =
@@ -215,6 +313,8 @@ impl ChatEngine {
                 ch.archived = row.archived != 0;
                 ch.slowmode_seconds = row.slowmode_seconds;
                 ch.is_nsfw = row.is_nsfw != 0;
+                ch.is_read_only = row.is_read_only != 0;
+                ch.topic_locked = row.topic_locked != 0;
 
                 self.channel_name_index
                     .insert((row.server_id.clone(), row.name), row.id.clone());
@@ -240,13 +340,21 @@ impl ChatEngine {
         nickname: String,
         protocol: Protocol,
         avatar_url: Option<String>,
-    ) -> Result<(SessionId, mpsc::Receiver<ChatEvent>), String> {
+    ) -> Result<(SessionId, mpsc::Receiver<ChatEvent>), EngineError> {
         validation::validate_nickname(&nickname)?;
 
         // If nickname is already in use, disconnect the stale session.
-        if let Some(old_session_id) = self.nick_to_session.get(&nickname).map(|r| *r) {
+        if let Some(old_session_id) = self.get_session_id_by_nick(&nickname) {
             info!(%nickname, "replacing stale session for reconnecting user");
-            self.disconnect(old_session_id);
+            // A user replacing their own session stays online
+            if let Some(offline) = self.remove_session(old_session_id)
+                && user_id.as_deref() != Some(offline.as_str())
+                && let Some(pool) = self.db.clone()
+            {
+                tokio::spawn(async move {
+                    let _ = crate::db::queries::presence::set_offline(&pool, &offline).await;
+                });
+            }
         }
 
         let session_id = Uuid::new_v4();
@@ -265,7 +373,13 @@ impl ChatEngine {
         let session_user_id = session.user_id.clone();
 
         self.sessions.insert(session_id, session);
-        self.nick_to_session.insert(nickname.clone(), session_id);
+        self.nick_to_session.insert(nick_key(&nickname), session_id);
+
+        // A new connection resets presence to online
+        if let Some(uid) = &session_user_id {
+            self.invisible_users.remove(uid);
+        }
+        self.notify_monitors(&nickname, true);
 
         // Update presence to online
         if let (Some(uid), Some(pool)) = (&session_user_id, &self.db) {
@@ -287,13 +401,27 @@ impl ChatEngine {
     }
 
     /// Disconnect a session and clean up all state.
-    pub fn disconnect(&self, session_id: SessionId) {
-        let Some((_, session)) = self.sessions.remove(&session_id) else {
-            return;
-        };
+    pub async fn disconnect(&self, session_id: SessionId) {
+        if let Some(user_id) = self.remove_session(session_id)
+            && let Some(pool) = &self.db
+        {
+            let _ = crate::db::queries::presence::set_offline(pool, &user_id).await;
+        }
+    }
+
+    /// Remove a session from memory and tell everyone it shared a channel
+    /// with. Returns the user ID if this was their last session, so the caller
+    /// can record them as offline.
+    fn remove_session(&self, session_id: SessionId) -> Option<String> {
+        let (_, session) = self.sessions.remove(&session_id)?;
 
         let nickname = session.nickname.clone();
-        self.nick_to_session.remove(&nickname);
+        self.nick_to_session.remove(&nick_key(&nickname));
+
+        self.monitor_clear(session_id);
+        if !self.is_invisible(&session) {
+            self.notify_monitors(&nickname, false);
+        }
 
         // Collect channels this session was in
         let channels_to_leave: Vec<String> = self
@@ -320,42 +448,37 @@ impl ChatEngine {
         }
 
         // Update presence if this was the last session for this user
-        if let Some(ref uid) = session.user_id {
-            let other_sessions = self
+        let offline = session.user_id.clone().filter(|uid| {
+            !self
                 .sessions
                 .iter()
-                .any(|s| s.key() != &session_id && s.user_id.as_deref() == Some(uid));
-            if !other_sessions {
-                if let Some(pool) = &self.db {
-                    let _ = tokio::task::block_in_place(|| {
-                        tokio::runtime::Handle::current()
-                            .block_on(crate::db::queries::presence::set_offline(pool, uid))
-                    });
-                }
-                // Broadcast offline to shared servers, deduplicated per server
-                for server in self.servers.iter() {
-                    if server.member_user_ids.contains(uid) {
-                        let event = ChatEvent::PresenceUpdate {
-                            server_id: server.id.clone(),
-                            presence: super::events::PresenceInfo {
-                                user_id: uid.clone(),
-                                nickname: session.nickname.clone(),
-                                avatar_url: session.avatar_url.clone(),
-                                status: "offline".into(),
-                                custom_status: None,
-                                status_emoji: None,
-                            },
-                        };
-                        let mut notified = std::collections::HashSet::new();
-                        for channel_id in server.channel_ids.iter() {
-                            if let Some(channel) = self.channels.get(channel_id) {
-                                for &member_sid in &channel.members {
-                                    if member_sid != session_id
-                                        && notified.insert(member_sid)
-                                        && let Some(s) = self.sessions.get(&member_sid)
-                                    {
-                                        let _ = s.send(event.clone());
-                                    }
+                .any(|s| s.key() != &session_id && s.user_id.as_deref() == Some(uid))
+        });
+        if let Some(ref uid) = offline {
+            self.invisible_users.remove(uid);
+            // Broadcast offline to shared servers, deduplicated per server
+            for server in self.servers.iter() {
+                if server.member_user_ids.contains(uid) {
+                    let event = ChatEvent::PresenceUpdate {
+                        server_id: server.id.clone(),
+                        presence: super::events::PresenceInfo {
+                            user_id: uid.clone(),
+                            nickname: session.nickname.clone(),
+                            avatar_url: session.avatar_url.clone(),
+                            status: "offline".into(),
+                            custom_status: None,
+                            status_emoji: None,
+                        },
+                    };
+                    let mut notified = std::collections::HashSet::new();
+                    for channel_id in server.channel_ids.iter() {
+                        if let Some(channel) = self.channels.get(channel_id) {
+                            for &member_sid in &channel.members {
+                                if member_sid != session_id
+                                    && notified.insert(member_sid)
+                                    && let Some(s) = self.sessions.get(&member_sid)
+                                {
+                                    let _ = s.send(event.clone());
                                 }
                             }
                         }
@@ -365,6 +488,116 @@ impl ChatEngine {
         }
 
         info!(%session_id, %nickname, "session disconnected");
+        offline
+    }
+
+    /// Change a connected session's nickname. Returns the old nickname.
+    ///
+    /// The new nick is reserved in `nick_to_session` before anything else so
+    /// two sessions racing for the same nick cannot both win. For
+    /// authenticated users the change is persisted as their username.
+    /// `NickChange` goes to the session itself and everyone sharing a channel.
+    pub async fn change_nickname(
+        &self,
+        session_id: SessionId,
+        new_nick: &str,
+    ) -> Result<String, EngineError> {
+        validation::validate_nickname(new_nick)?;
+
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let old_nick = session.nickname.clone();
+        if old_nick == new_nick {
+            return Ok(old_nick);
+        }
+
+        // Atomically reserve the new nickname; a change of case keeps the
+        // session's own reservation
+        let recased = old_nick.eq_ignore_ascii_case(new_nick);
+        if !recased {
+            match self.nick_to_session.entry(nick_key(new_nick)) {
+                dashmap::mapref::entry::Entry::Occupied(_) => {
+                    return Err(EngineError::NicknameInUse(new_nick.to_string()));
+                }
+                dashmap::mapref::entry::Entry::Vacant(vacant) => {
+                    vacant.insert(session_id);
+                }
+            }
+        }
+
+        if let (Some(pool), Some(uid)) = (&self.db, &session.user_id) {
+            let persisted: Result<(), EngineError> = async {
+                if let Some((owner_id, ..)) =
+                    crate::db::queries::users::get_user_by_nickname(pool, new_nick)
+                        .await
+                        .map_err(|e| {
+                            EngineError::Internal(format!("Failed to check nickname: {e}"))
+                        })?
+                    && owner_id != *uid
+                {
+                    return Err(EngineError::NicknameInUse(new_nick.to_string()));
+                }
+                crate::db::queries::users::update_username(pool, uid, new_nick)
+                    .await
+                    .map_err(|e| EngineError::Internal(format!("Failed to change nickname: {e}")))
+            }
+            .await;
+
+            if let Err(e) = persisted {
+                // Release the reservation on failure
+                if !recased {
+                    self.nick_to_session
+                        .remove_if(&nick_key(new_nick), |_, sid| *sid == session_id);
+                }
+                return Err(e);
+            }
+        }
+
+        let updated = Arc::new(UserSession {
+            id: session.id,
+            user_id: session.user_id.clone(),
+            nickname: new_nick.to_string(),
+            protocol: session.protocol,
+            outbound: session.outbound.clone(),
+            connected_at: session.connected_at,
+            avatar_url: session.avatar_url.clone(),
+            client_host: session.client_host.clone(),
+        });
+        self.sessions.insert(session_id, updated);
+        if !recased {
+            self.nick_to_session
+                .remove_if(&nick_key(&old_nick), |_, sid| *sid == session_id);
+        }
+
+        if !self.is_invisible(&session) {
+            self.notify_monitors(&old_nick, false);
+            self.notify_monitors(new_nick, true);
+        }
+
+        // Notify the session itself plus every session sharing a channel with it
+        let event = ChatEvent::NickChange {
+            old_nick: old_nick.clone(),
+            new_nick: new_nick.to_string(),
+        };
+        let mut notified = std::collections::HashSet::new();
+        notified.insert(session_id);
+        let _ = session.send(event.clone());
+        for channel in self.channels.iter() {
+            if !channel.members.contains(&session_id) {
+                continue;
+            }
+            for &member_sid in &channel.members {
+                if notified.insert(member_sid)
+                    && let Some(s) = self.sessions.get(&member_sid)
+                {
+                    let _ = s.send(event.clone());
+                }
+            }
+        }
+
+        info!(%session_id, %old_nick, %new_nick, "nickname changed");
+        Ok(old_nick)
     }
 
     // ── Server management ───────────────────────────────────────────
@@ -375,7 +608,7 @@ impl ChatEngine {
         name: String,
         owner_user_id: String,
         icon_url: Option<String>,
-    ) -> Result<String, String> {
+    ) -> Result<String, EngineError> {
         validation::validate_server_name(&name)?;
 
         // Enforce per-user server creation limit
@@ -385,7 +618,9 @@ impl ChatEngine {
             .filter(|s| s.owner_id == owner_user_id)
             .count();
         if owned_count >= 100 {
-            return Err("You have reached the maximum number of servers (100)".to_string());
+            return Err(EngineError::Conflict(
+                "You have reached the maximum number of servers (100)".into(),
+            ));
         }
 
         let server_id = Uuid::new_v4().to_string();
@@ -399,7 +634,7 @@ impl ChatEngine {
                 icon_url.as_deref(),
             )
             .await
-            .map_err(|e| format!("Failed to create server: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to create server: {e}")))?;
         }
 
         let mut state = ServerState::new(
@@ -480,14 +715,27 @@ impl ChatEngine {
     }
 
     /// Delete a server.
-    pub async fn delete_server(&self, server_id: &str) -> Result<(), String> {
+    pub async fn delete_server(&self, server_id: &str) -> Result<(), EngineError> {
+        self.check_not_quarantined(server_id)?;
+        self.remove_server(server_id).await
+    }
+
+    /// Remove a server and its channels, regardless of quarantine.
+    async fn remove_server(&self, server_id: &str) -> Result<(), EngineError> {
         if let Some(pool) = &self.db {
             crate::db::queries::servers::delete_server(pool, server_id)
                 .await
-                .map_err(|e| format!("Failed to delete server: {e}"))?;
+                .map_err(|e| EngineError::Internal(format!("Failed to delete server: {e}")))?;
         }
+        self.forget_server(server_id);
 
-        if let Some(server) = self.servers.get(server_id) {
+        info!(%server_id, "server deleted");
+        Ok(())
+    }
+
+    /// Drop a server and its channels from memory.
+    fn forget_server(&self, server_id: &str) {
+        if let Some((_, server)) = self.servers.remove(server_id) {
             for ch_id in &server.channel_ids {
                 if let Some((_, ch)) = self.channels.remove(ch_id) {
                     self.channel_name_index
@@ -495,11 +743,6 @@ impl ChatEngine {
                 }
             }
         }
-
-        self.servers.remove(server_id);
-
-        info!(%server_id, "server deleted");
-        Ok(())
     }
 
     /// Update a server's name and/or icon.
@@ -508,7 +751,9 @@ impl ChatEngine {
         server_id: &str,
         name: Option<&str>,
         icon_url: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
+        self.check_not_quarantined(server_id)?;
+
         // Compute new values and apply in-memory update while holding the guard,
         // then drop the guard before any .await to avoid holding the DashMap shard
         // lock across an async suspension point.
@@ -516,7 +761,7 @@ impl ChatEngine {
             let mut server = self
                 .servers
                 .get_mut(server_id)
-                .ok_or_else(|| format!("No such server: {server_id}"))?;
+                .ok_or_else(|| EngineError::NotFound("Server not found".into()))?;
 
             let new_name = name.unwrap_or(&server.name).to_string();
             let new_icon = if icon_url.is_some() {
@@ -541,7 +786,9 @@ impl ChatEngine {
             .await
         {
             warn!(%server_id, error = %e, "failed to persist server settings update to DB");
-            return Err(format!("Failed to update server: {e}"));
+            return Err(EngineError::Internal(format!(
+                "Failed to update server: {e}"
+            )));
         }
 
         info!(%server_id, "server settings updated");
@@ -550,28 +797,34 @@ impl ChatEngine {
 
     /// List servers for a user (by their DB user_id).
     pub async fn list_servers_for_user(&self, user_id: &str) -> Vec<ServerInfo> {
-        let mut servers = Vec::new();
-        for entry in self.servers.iter() {
-            let s = entry.value();
-            if !s.member_user_ids.contains(user_id) {
-                continue;
-            }
-            let role = if s.owner_id == user_id {
-                Some("owner".to_string())
-            } else {
-                Some("member".to_string())
-            };
-            let perms = self
-                .get_effective_permissions(&s.id, None, user_id)
-                .await;
-            servers.push(ServerInfo {
+        // Snapshot first: resolving permissions writes to the server's cache, so
+        // no `servers` guard may be held across it.
+        let mut servers: Vec<ServerInfo> = self
+            .servers
+            .iter()
+            .filter(|s| s.member_user_ids.contains(user_id))
+            .map(|s| ServerInfo {
                 id: s.id.clone(),
                 name: s.name.clone(),
                 icon_url: s.icon_url.clone(),
                 member_count: s.member_user_ids.len(),
-                role,
-                my_permissions: perms.bits() as i64,
-            });
+                role: Some(
+                    if s.owner_id == user_id {
+                        "owner"
+                    } else {
+                        "member"
+                    }
+                    .to_string(),
+                ),
+                my_permissions: 0,
+                quarantined: s.quarantined,
+            })
+            .collect();
+        for server in &mut servers {
+            let perms = self
+                .get_effective_permissions(&server.id, None, user_id)
+                .await;
+            server.my_permissions = perms.bits() as i64;
         }
         servers
     }
@@ -587,6 +840,7 @@ impl ChatEngine {
                 member_count: s.member_user_ids.len(),
                 role: None,
                 my_permissions: 0,
+                quarantined: s.quarantined,
             })
             .collect()
     }
@@ -608,9 +862,14 @@ impl ChatEngine {
     }
 
     /// Join a server (persistent membership).
-    pub async fn join_server(&self, user_id: &str, server_id: &str) -> Result<(), String> {
+    pub async fn join_server(&self, user_id: &str, server_id: &str) -> Result<(), EngineError> {
         if !self.servers.contains_key(server_id) {
-            return Err(format!("No such server: {server_id}"));
+            return Err(EngineError::NotFound("Server not found".into()));
+        }
+        self.check_not_quarantined(server_id)?;
+        // Rejoining is a no-op, so it mustn't count toward raid detection
+        if self.user_is_server_member(server_id, user_id) {
+            return Ok(());
         }
 
         // Check if the user is banned from this server
@@ -619,12 +878,19 @@ impl ChatEngine {
                 .await
                 .unwrap_or(false)
             {
-                return Err("You are banned from this server".into());
+                return Err(EngineError::Banned);
             }
 
+            let timeouts = self.apply_join_automod(pool, server_id, user_id).await?;
+
             crate::db::queries::servers::add_server_member(pool, server_id, user_id, "member")
                 .await
-                .map_err(|e| format!("Failed to join server: {e}"))?;
+                .map_err(|e| EngineError::Internal(format!("Failed to join server: {e}")))?;
+
+            for (rule, duration_seconds) in timeouts {
+                self.automod_timeout(pool, server_id, user_id, &rule, duration_seconds)
+                    .await;
+            }
         }
 
         if let Some(mut server) = self.servers.get_mut(server_id) {
@@ -635,15 +901,16 @@ impl ChatEngine {
     }
 
     /// Leave a server (remove persistent membership).
-    pub async fn leave_server(&self, user_id: &str, server_id: &str) -> Result<(), String> {
+    pub async fn leave_server(&self, user_id: &str, server_id: &str) -> Result<(), EngineError> {
         if let Some(pool) = &self.db {
             crate::db::queries::servers::remove_server_member(pool, server_id, user_id)
                 .await
-                .map_err(|e| format!("Failed to leave server: {e}"))?;
+                .map_err(|e| EngineError::Internal(format!("Failed to leave server: {e}")))?;
         }
 
         if let Some(mut server) = self.servers.get_mut(server_id) {
             server.member_user_ids.remove(user_id);
+            server.permission_cache.invalidate_member(user_id);
         }
 
         Ok(())
@@ -680,7 +947,10 @@ impl ChatEngine {
         self.servers.get(server_id).map(|s| s.owner_id.clone())
     }
 
-    /// Get IRC-style mode string for a channel (e.g., "+ins").
+    /// Get IRC-style mode string for a channel (e.g., "+nstN 10").
+    ///
+    /// `s` = private, `m` = read-only, `t` = topic locked, `N` = slow mode
+    /// (with the cooldown in seconds as its parameter).
     pub fn get_channel_modes(&self, server_id: &str, channel_name: &str) -> String {
         let key = (server_id.to_string(), channel_name.to_string());
         let Some(channel_id) = self.channel_name_index.get(&key).map(|v| v.clone()) else {
@@ -690,15 +960,44 @@ impl ChatEngine {
             return "+".to_string();
         };
         let mut modes = String::from("+n"); // no external messages (always set)
+        if ch.is_read_only {
+            modes.push('m');
+        }
         if ch.is_private {
-            modes.push('i'); // invite-only
+            modes.push('s');
+        }
+        if ch.topic_locked {
+            modes.push('t');
         }
         if ch.slowmode_seconds > 0 {
-            modes.push('m'); // moderated (closest IRC analog to slowmode)
+            modes.push_str(&format!("N {}", ch.slowmode_seconds));
         }
         modes
     }
 
+    /// Channel names in `server_id` that the session holding `nickname` has joined.
+    pub fn joined_channels_in_server(&self, nickname: &str, server_id: &str) -> Vec<String> {
+        let Some(session_id) = self.get_session_id_by_nick(nickname) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = self
+            .channels
+            .iter()
+            .filter(|ch| ch.server_id == server_id && ch.members.contains(&session_id))
+            .map(|ch| ch.name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Nickname of a connected session for a user ID, if any.
+    pub fn nickname_for_user(&self, user_id: &str) -> Option<String> {
+        self.sessions
+            .iter()
+            .find(|s| s.user_id.as_deref() == Some(user_id))
+            .map(|s| s.nickname.clone())
+    }
+
     // ── Channel management ──────────────────────────────────────────
 
     /// Create a channel within a server. Returns the channel ID.
@@ -708,13 +1007,14 @@ impl ChatEngine {
         name: &str,
         category_id: Option<&str>,
         is_private: bool,
-    ) -> Result<String, String> {
+    ) -> Result<String, EngineError> {
         let name = normalize_channel_name(name);
         validation::validate_channel_name(&name)?;
 
         if !self.servers.contains_key(server_id) {
-            return Err(format!("No such server: {server_id}"));
+            return Err(EngineError::NotFound("Server not found".into()));
         }
+        self.check_not_quarantined(server_id)?;
 
         // Atomic check-and-insert to prevent TOCTOU race on channel creation
         let channel_id = Uuid::new_v4().to_string();
@@ -722,7 +1022,9 @@ impl ChatEngine {
         let entry = self.channel_name_index.entry(key);
         match entry {
             dashmap::mapref::entry::Entry::Occupied(_) => {
-                return Err(format!("Channel {name} already exists in this server"));
+                return Err(EngineError::Conflict(format!(
+                    "Channel {name} already exists in this server"
+                )));
             }
             dashmap::mapref::entry::Entry::Vacant(vacant) => {
                 vacant.insert(channel_id.clone());
@@ -730,10 +1032,10 @@ impl ChatEngine {
         }
 
         if let Some(pool) = &self.db {
-            let db_result: Result<(), String> = async {
+            let db_result: Result<(), EngineError> = async {
                 crate::db::queries::channels::ensure_channel(pool, &channel_id, server_id, &name)
                     .await
-                    .map_err(|e| format!("Failed to create channel: {e}"))?;
+                    .map_err(|e| EngineError::Internal(format!("Failed to create channel: {e}")))?;
 
                 if let Some(cat_id) = category_id {
                     crate::db::queries::channels::update_channel_category(
@@ -742,13 +1044,17 @@ impl ChatEngine {
                         Some(cat_id),
                     )
                     .await
-                    .map_err(|e| format!("Failed to set channel category: {e}"))?;
+                    .map_err(|e| {
+                        EngineError::Internal(format!("Failed to set channel category: {e}"))
+                    })?;
                 }
 
                 if is_private {
                     crate::db::queries::channels::set_channel_private(pool, &channel_id, true)
                         .await
-                        .map_err(|e| format!("Failed to set channel private: {e}"))?;
+                        .map_err(|e| {
+                            EngineError::Internal(format!("Failed to set channel private: {e}"))
+                        })?;
                 }
                 Ok(())
             }
@@ -778,14 +1084,15 @@ impl ChatEngine {
         &self,
         server_id: &str,
         channel_name: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
+        self.check_not_quarantined(server_id)?;
         let channel_name = normalize_channel_name(channel_name);
         let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
 
         if let Some(pool) = &self.db {
             crate::db::queries::channels::delete_channel(pool, &channel_id)
                 .await
-                .map_err(|e| format!("Failed to delete channel: {e}"))?;
+                .map_err(|e| EngineError::Internal(format!("Failed to delete channel: {e}")))?;
         }
 
         self.channels.remove(&channel_id);
@@ -793,25 +1100,26 @@ impl ChatEngine {
             .remove(&(server_id.to_string(), channel_name));
         if let Some(mut srv) = self.servers.get_mut(server_id) {
             srv.channel_ids.remove(&channel_id);
+            srv.permission_cache.invalidate_channel(&channel_id);
         }
 
         Ok(())
     }
 
     /// Join a channel within a server.
-    pub fn join_channel(
+    pub async fn join_channel(
         &self,
         session_id: SessionId,
         server_id: &str,
         channel_name: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let channel_name = normalize_channel_name(channel_name);
         validation::validate_channel_name(&channel_name)?;
 
         let session = self
             .sessions
             .get(&session_id)
-            .ok_or("Session not found")?
+            .ok_or(EngineError::SessionNotFound)?
             .clone();
 
         // Check private channel access control
@@ -825,22 +1133,20 @@ impl ChatEngine {
             if let Some(ref uid) = session.user_id
                 && self.db.is_some()
             {
-                let srv = server_id.to_string();
                 let ch_id = id.clone();
-                let uid = uid.clone();
-                let has_view = tokio::task::block_in_place(|| {
-                    tokio::runtime::Handle::current().block_on(async {
-                        let perms = self
-                            .get_effective_permissions(&srv, Some(&ch_id), &uid)
-                            .await;
-                        perms.contains(Permissions::VIEW_CHANNELS)
-                    })
-                });
-                if !has_view {
-                    return Err("You do not have permission to join this private channel".into());
+                // Release the index and channel guards before awaiting
+                drop(ch);
+                drop(id);
+                let perms = self
+                    .get_effective_permissions(server_id, Some(&ch_id), uid)
+                    .await;
+                if !perms.contains(Permissions::VIEW_CHANNELS) {
+                    return Err(EngineError::PermissionDenied {
+                        perm: Permissions::VIEW_CHANNELS,
+                    });
                 }
             } else {
-                return Err("Authentication required to join private channels".into());
+                return Err(EngineError::AuthRequired);
             }
         }
 
@@ -944,14 +1250,14 @@ impl ChatEngine {
         server_id: &str,
         channel_name: &str,
         reason: Option<String>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let channel_name = normalize_channel_name(channel_name);
         let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
 
         let session = self
             .sessions
             .get(&session_id)
-            .ok_or("Session not found")?
+            .ok_or(EngineError::SessionNotFound)?
             .clone();
 
         let mut found = false;
@@ -960,7 +1266,7 @@ impl ChatEngine {
         }
 
         if !found {
-            return Err(format!("Not in channel {channel_name}"));
+            return Err(EngineError::NotInChannel(channel_name));
         }
 
         let part_event = ChatEvent::Part {
@@ -981,8 +1287,12 @@ impl ChatEngine {
     }
 
     /// Send a message to a channel or user (DM), with optional reply and attachments.
+    ///
+    /// Runs as a staged pipeline: validate, permission checks (timeout, channel
+    /// access), automod and slow mode, persist, fan-out, then post-processing such
+    /// as link unfurling, which is spawned off the request path.
     #[allow(clippy::too_many_arguments)]
-    pub fn send_message(
+    pub async fn send_message(
         &self,
         session_id: SessionId,
         server_id: &str,
@@ -991,258 +1301,55 @@ impl ChatEngine {
         reply_to_id: Option<&str>,
         attachment_ids: Option<&[String]>,
         nonce: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
+        // Stage 1: validate
         validation::validate_message_with_limit(content, self.max_message_length)?;
         let content = &validation::sanitize_html(content);
 
         let session = self
             .sessions
             .get(&session_id)
-            .ok_or("Session not found")?
+            .ok_or(EngineError::SessionNotFound)?
             .clone();
 
         if !self.message_limiter.check(&session.nickname) {
-            return Err("Rate limit exceeded. Please slow down.".into());
-        }
-
-        // Enforce timeout: timed-out users cannot send messages
-        if let Some(pool) = &self.db
-            && let Some(ref uid) = session.user_id
-        {
-            let pool = pool.clone();
-            let srv = server_id.to_string();
-            let uid = uid.clone();
-            let timed_out = tokio::task::block_in_place(|| {
-                tokio::runtime::Handle::current().block_on(async {
-                    if let Ok(Some(until)) =
-                        crate::db::queries::moderation::get_member_timeout(&pool, &srv, &uid).await
-                        && let Ok(timeout_dt) =
-                            chrono::NaiveDateTime::parse_from_str(&until, "%Y-%m-%d %H:%M:%S")
-                    {
-                        let timeout_utc = timeout_dt.and_utc();
-                        return timeout_utc > chrono::Utc::now();
-                    }
-                    false
-                })
-            });
-            if timed_out {
-                return Err("You are timed out and cannot send messages".into());
-            }
-        }
-
-        // Enforce slow mode: check per-channel cooldown.
-        // Uses both a DB query and an in-memory DashMap cache to prevent
-        // concurrent requests (e.g. two browser tabs) from bypassing the check.
-        if let Some(pool) = &self.db {
-            let pool = pool.clone();
-            let srv = server_id.to_string();
-            let tgt = target.to_string();
-            let sender_uid = session
-                .user_id
-                .clone()
-                .unwrap_or_else(|| session.nickname.clone());
-            let slowmode_map = &self.slowmode_last_sent;
-            let slow_err = tokio::task::block_in_place(|| {
-                tokio::runtime::Handle::current().block_on(async {
-                    if let Ok(Some(ch)) =
-                        crate::db::queries::channels::get_channel_by_name(&pool, &srv, &tgt).await
-                        && ch.slowmode_seconds > 0
-                    {
-                        let cooldown_dur =
-                            std::time::Duration::from_secs(ch.slowmode_seconds as u64);
-                        let cache_key = (sender_uid.clone(), ch.id.clone());
-
-                        // Check the in-memory cache first (catches concurrent sends)
-                        if let Some(last_instant) = slowmode_map.get(&cache_key)
-                            && last_instant.elapsed() < cooldown_dur
-                        {
-                            return Some(format!(
-                                "Slow mode: wait {} seconds between messages",
-                                ch.slowmode_seconds
-                            ));
-                        }
-
-                        // Also check DB (catches sends from before this process started)
-                        if let Ok(Some(last)) =
-                            crate::db::queries::messages::get_last_user_message_time(
-                                &pool,
-                                &ch.id,
-                                &sender_uid,
-                            )
-                            .await
-                            && let Ok(last_dt) =
-                                chrono::NaiveDateTime::parse_from_str(&last, "%Y-%m-%d %H:%M:%S")
-                        {
-                            let last_utc = last_dt.and_utc();
-                            let cooldown = chrono::Duration::seconds(ch.slowmode_seconds as i64);
-                            if chrono::Utc::now() - last_utc < cooldown {
-                                return Some(format!(
-                                    "Slow mode: wait {} seconds between messages",
-                                    ch.slowmode_seconds
-                                ));
-                            }
-                        }
-
-                        // Both checks passed — record this send in the in-memory cache
-                        slowmode_map.insert(cache_key, Instant::now());
-                    }
-                    None
-                })
-            });
-            if let Some(err) = slow_err {
-                return Err(err);
-            }
-        }
-
-        // Evaluate automod rules (keyword, mention_spam, link_filter)
-        if let Some(pool) = &self.db {
-            let pool = pool.clone();
-            let srv = server_id.to_string();
-            let content_clone = content.to_string();
-            let automod_err = tokio::task::block_in_place(|| {
-                tokio::runtime::Handle::current().block_on(async {
-                    let rules = crate::db::queries::automod::get_enabled_rules(&pool, &srv)
-                        .await
-                        .unwrap_or_default();
-                    for rule in rules {
-                        let triggered = match rule.rule_type.as_str() {
-                            "keyword" => {
-                                // Config: {"words":["bad","spam"]}
-                                if let Ok(config) =
-                                    serde_json::from_str::<serde_json::Value>(&rule.config)
-                                {
-                                    if let Some(words) =
-                                        config.get("words").and_then(|w| w.as_array())
-                                    {
-                                        let lower = content_clone.to_lowercase();
-                                        let msg_words: Vec<&str> =
-                                            lower.split(|c: char| !c.is_alphanumeric()).collect();
-                                        words.iter().any(|w| {
-                                            w.as_str().is_some_and(|kw| {
-                                                let kw_lower = kw.to_lowercase();
-                                                msg_words.iter().any(|mw| *mw == kw_lower)
-                                            })
-                                        })
-                                    } else {
-                                        false
-                                    }
-                                } else {
-                                    false
-                                }
-                            }
-                            "mention_spam" => {
-                                // Config: {"max_mentions":5}
-                                if let Ok(config) =
-                                    serde_json::from_str::<serde_json::Value>(&rule.config)
-                                {
-                                    let max = config
-                                        .get("max_mentions")
-                                        .and_then(|m| m.as_i64())
-                                        .unwrap_or(5)
-                                        as usize;
-                                    let mention_count = content_clone.matches('@').count();
-                                    mention_count > max
-                                } else {
-                                    false
-                                }
-                            }
-                            "link_filter" => {
-                                // Config: {"block_all":true}
-                                if let Ok(config) =
-                                    serde_json::from_str::<serde_json::Value>(&rule.config)
-                                {
-                                    let block_all = config
-                                        .get("block_all")
-                                        .and_then(|b| b.as_bool())
-                                        .unwrap_or(false);
-                                    if block_all {
-                                        content_clone.contains("http://")
-                                            || content_clone.contains("https://")
-                                    } else {
-                                        false
-                                    }
-                                } else {
-                                    false
-                                }
-                            }
-                            _ => false,
-                        };
-                        if triggered {
-                            return Some(format!("Message blocked by automod rule: {}", rule.name));
-                        }
-                    }
-                    None
-                })
-            });
-            if let Some(err) = automod_err {
-                return Err(err);
-            }
-        }
-
-        // Build reply info if replying to a message
-        let reply_to: Option<ReplyInfo> = if let Some(ref_id) = reply_to_id {
-            if let Some(pool) = &self.db {
-                // Synchronous lookup via block_in_place — reply info is needed before broadcast
-                let pool = pool.clone();
-                let ref_id = ref_id.to_string();
-                tokio::task::block_in_place(|| {
-                    tokio::runtime::Handle::current().block_on(async {
-                        match crate::db::queries::messages::get_message_by_id(&pool, &ref_id).await
-                        {
-                            Ok(Some(row)) => Some(ReplyInfo {
-                                id: row.id,
-                                from: row.sender_nick,
-                                content_preview: row.content.chars().take(100).collect::<String>(),
-                            }),
-                            _ => None,
-                        }
-                    })
-                })
-            } else {
-                None
-            }
+            METRICS.rate_limited.inc("message");
+            return Err(EngineError::RateLimited { retry_after: None });
+        }
+
+        // Stage 2: permission checks
+        self.check_not_timed_out(&session, server_id).await?;
+        self.check_lockdown(&session, server_id).await?;
+        let channel = if target.starts_with('#') {
+            self.check_not_quarantined(server_id)?;
+            Some(
+                self.check_channel_send(&session, session_id, server_id, target)
+                    .await?,
+            )
         } else {
             None
         };
 
-        // Look up attachment metadata if attachment_ids provided
-        let attachments: Option<Vec<super::events::AttachmentInfo>> = if let Some(ids) =
-            attachment_ids
-            && !ids.is_empty()
-        {
-            if let Some(pool) = &self.db {
-                let pool = pool.clone();
-                let ids = ids.to_vec();
-                tokio::task::block_in_place(|| {
-                    tokio::runtime::Handle::current().block_on(async {
-                        let infos =
-                            crate::db::queries::attachments::get_attachments_by_ids(&pool, &ids)
-                                .await
-                                .unwrap_or_default();
-                        if infos.is_empty() {
-                            None
-                        } else {
-                            Some(
-                                infos
-                                    .into_iter()
-                                    .map(|a| super::events::AttachmentInfo {
-                                        id: a.id.clone(),
-                                        filename: a.original_filename,
-                                        content_type: a.content_type,
-                                        file_size: a.file_size,
-                                        url: format!("/api/uploads/{}", a.id),
-                                    })
-                                    .collect(),
-                            )
-                        }
-                    })
-                })
-            } else {
-                None
+        // Stage 3: automod, then slow mode so that only accepted messages start
+        // the sender's cooldown. DMs belong to no server, so server rules skip them.
+        let flagged_by = match &channel {
+            Some((channel_id, _)) => {
+                self.apply_automod(
+                    &session,
+                    server_id,
+                    target,
+                    channel_id,
+                    content,
+                    reply_to_id,
+                )
+                .await?
             }
-        } else {
-            None
+            None => Vec::new(),
         };
+        self.check_slowmode(&session, server_id, target).await?;
+
+        let reply_to = self.reply_info(reply_to_id).await;
+        let attachments = self.attachment_infos(attachment_ids).await;
 
         let msg_id = Uuid::new_v4();
         let event = ChatEvent::Message {
@@ -1253,113 +1360,61 @@ impl ChatEngine {
             content: content.to_string(),
             timestamp: Utc::now(),
             avatar_url: session.avatar_url.clone(),
-            reply_to: reply_to.clone(),
-            attachments: attachments.clone(),
+            reply_to,
+            attachments,
         };
+        let sender_uid = session
+            .user_id
+            .clone()
+            .unwrap_or_else(|| session_id.to_string());
 
-        if target.starts_with('#') {
-            let channel_name = normalize_channel_name(target);
-            let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
-
-            let channel = self
-                .channels
-                .get(&channel_id)
-                .ok_or(format!("No such channel: {channel_name}"))?;
-
-            // Check if thread is archived
-            if channel.archived {
-                return Err("This thread is archived and no longer accepts messages".to_string());
-            }
-
-            if !channel.members.contains(&session_id) {
-                return Err(format!("You are not in channel {channel_name}"));
-            }
-
-            // Private channel access control: require VIEW_CHANNELS even if user is in-memory member
-            let ch_is_private = channel.is_private;
-            drop(channel);
-
-            if ch_is_private {
-                if let Some(ref uid) = session.user_id
-                    && self.db.is_some()
+        if let Some((channel_id, channel_name)) = channel {
+            // Stage 4: persist
+            if let Some(pool) = &self.db {
+                let id = msg_id.to_string();
+                let params = crate::db::queries::messages::InsertMessageParams {
+                    id: &id,
+                    server_id,
+                    channel_id: &channel_id,
+                    sender_id: &sender_uid,
+                    sender_nick: &session.nickname,
+                    content,
+                    reply_to_id,
+                };
+                if let Err(e) = crate::db::queries::messages::insert_message(pool, &params).await {
+                    error!(error = %e, "failed to persist message");
+                }
+                // Link attachments to the message (use user_id, not session_id)
+                if let Some(att_ids) = attachment_ids
+                    && let Err(e) = crate::db::queries::attachments::link_attachments_to_message(
+                        pool,
+                        &id,
+                        att_ids,
+                        &sender_uid,
+                    )
+                    .await
                 {
-                    let has_view = tokio::task::block_in_place(|| {
-                        tokio::runtime::Handle::current().block_on(async {
-                            let perms = self
-                                .get_effective_permissions(server_id, Some(&channel_id), uid)
-                                .await;
-                            perms.contains(crate::engine::permissions::Permissions::VIEW_CHANNELS)
-                        })
-                    });
-                    if !has_view {
-                        return Err(
-                            "You do not have permission to access this private channel".to_string()
-                        );
-                    }
-                } else if session.user_id.is_none() {
-                    return Err("Authentication required to access private channels".to_string());
+                    error!(error = %e, "failed to link attachments");
                 }
-            }
-
-            // Check SEND_MESSAGES permission (only when DB is available for role/override lookups)
-            if self.db.is_some() {
-                let sender_user_id = session
-                    .user_id
-                    .clone()
-                    .unwrap_or_else(|| session_id.to_string());
-                let perms = tokio::task::block_in_place(|| {
-                    tokio::runtime::Handle::current().block_on(self.get_effective_permissions(
+                for rule in &flagged_by {
+                    let params = crate::db::models::CreateAutomodQueueParams {
+                        id: &Uuid::new_v4().to_string(),
                         server_id,
-                        Some(&channel_id),
-                        &sender_user_id,
-                    ))
-                });
-                if !perms.contains(crate::engine::permissions::Permissions::SEND_MESSAGES) {
-                    return Err(
-                        "You do not have permission to send messages in this channel".to_string(),
-                    );
-                }
-            }
-
-            if let Some(pool) = &self.db {
-                let pool = pool.clone();
-                let id = msg_id.to_string();
-                let srv = server_id.to_string();
-                let ch = channel_id.clone();
-                let sid = session_id.to_string();
-                let nick = session.nickname.clone();
-                let uid = session.user_id.clone().unwrap_or_else(|| sid.clone());
-                let msg = content.to_string();
-                let reply_id = reply_to_id.map(|s| s.to_string());
-                let att_ids = attachment_ids.map(|ids| ids.to_vec());
-                tokio::spawn(async move {
-                    let params = crate::db::queries::messages::InsertMessageParams {
-                        id: &id,
-                        server_id: &srv,
-                        channel_id: &ch,
-                        sender_id: &uid,
-                        sender_nick: &nick,
-                        content: &msg,
-                        reply_to_id: reply_id.as_deref(),
+                        channel_id: &channel_id,
+                        rule_id: &rule.id,
+                        rule_name: &rule.name,
+                        user_id: &sender_uid,
+                        user_nick: &session.nickname,
+                        content,
+                        reply_to_id,
+                        message_id: Some(&id),
+                        status: "flagged",
                     };
-                    if let Err(e) =
-                        crate::db::queries::messages::insert_message(&pool, &params).await
-                    {
-                        error!(error = %e, "failed to persist message");
-                    }
-                    // Link attachments to the message (use user_id, not session_id)
-                    if let Some(att_ids) = att_ids
-                        && let Err(e) =
-                            crate::db::queries::attachments::link_attachments_to_message(
-                                &pool, &id, &att_ids, &uid,
-                            )
-                            .await
-                    {
-                        error!(error = %e, "failed to link attachments");
-                    }
-                });
+                    self.queue_for_review(pool, &session, rule, &params).await;
+                }
             }
 
+            // Stage 5: fan-out
             self.broadcast_to_channel(&channel_id, &event, Some(session_id));
 
             // Send MessageAck back to the sender with the server-generated message ID
@@ -1372,107 +1427,35 @@ impl ChatEngine {
                 });
             }
 
-            // Async link embed unfurling — extract URLs and resolve OG metadata
-            let urls = super::embeds::extract_urls(content);
-            if !urls.is_empty()
-                && let Some(pool) = &self.db
-            {
-                let pool = pool.clone();
-                let client = self.http_client.clone();
-                let server_id_owned = server_id.to_string();
-                let channel_name_owned = channel_name.clone();
-                // Collect senders for channel members before spawning
-                let member_senders: Vec<mpsc::Sender<ChatEvent>> =
-                    if let Some(channel) = self.channels.get(&channel_id) {
-                        channel
-                            .members
-                            .iter()
-                            .filter_map(|sid| self.sessions.get(sid).map(|s| s.outbound.clone()))
-                            .collect()
-                    } else {
-                        vec![]
-                    };
-                tokio::spawn(async move {
-                    let mut embeds = Vec::new();
-                    for url in urls {
-                        // Check cache first
-                        if let Ok(Some(cached)) =
-                            crate::db::queries::embeds::get_cached_embed(&pool, &url).await
-                        {
-                            embeds.push(super::events::EmbedInfo {
-                                url: cached.url,
-                                title: cached.title,
-                                description: cached.description,
-                                image_url: cached.image_url,
-                                site_name: cached.site_name,
-                            });
-                            continue;
-                        }
-                        // Unfurl
-                        if let Some(info) = super::embeds::unfurl_url(&client, &url).await {
-                            let _ = crate::db::queries::embeds::upsert_embed(
-                                &pool,
-                                &info.url,
-                                info.title.as_deref(),
-                                info.description.as_deref(),
-                                info.image_url.as_deref(),
-                                info.site_name.as_deref(),
-                            )
-                            .await;
-                            embeds.push(info);
-                        }
-                    }
-                    if !embeds.is_empty() {
-                        let embed_event = ChatEvent::MessageEmbed {
-                            message_id: msg_id,
-                            server_id: server_id_owned,
-                            channel: channel_name_owned,
-                            embeds,
-                        };
-                        for sender in &member_senders {
-                            let _ = sender.try_send(embed_event.clone());
-                        }
-                    }
-                });
-            }
+            // Stage 6: post-processing
+            self.spawn_link_unfurl(msg_id, server_id, &channel_id, &channel_name, content);
         } else {
             // DM
             let target_session_id = self
-                .nick_to_session
-                .get(target)
-                .ok_or(format!("No such user: {target}"))?;
+                .get_session_id_by_nick(target)
+                .ok_or_else(|| EngineError::NoSuchNick(target.to_string()))?;
 
             if let Some(pool) = &self.db {
-                let pool = pool.clone();
-                let id = msg_id.to_string();
-                let sender_uid = session
-                    .user_id
-                    .clone()
-                    .unwrap_or_else(|| session_id.to_string());
-                let nick = session.nickname.clone();
                 let target_uid = self
                     .sessions
-                    .get(target_session_id.value())
+                    .get(&target_session_id)
                     .and_then(|s| s.user_id.clone())
-                    .unwrap_or_else(|| target_session_id.value().to_string());
-                let msg = content.to_string();
-                tokio::spawn(async move {
-                    if let Err(e) = crate::db::queries::messages::insert_dm(
-                        &pool,
-                        &id,
-                        &sender_uid,
-                        &nick,
-                        &target_uid,
-                        &msg,
-                    )
-                    .await
-                    {
-                        error!(error = %e, "failed to persist DM");
-                    }
-                });
+                    .unwrap_or_else(|| target_session_id.to_string());
+                if let Err(e) = crate::db::queries::messages::insert_dm(
+                    pool,
+                    &msg_id.to_string(),
+                    &sender_uid,
+                    &session.nickname,
+                    &target_uid,
+                    content,
+                )
+                .await
+                {
+                    error!(error = %e, "failed to persist DM");
+                }
             }
 
-            if let Some(target_session) = self.sessions.get(target_session_id.value()) {
+            if let Some(target_session) = self.sessions.get(&target_session_id) {
                 let _ = target_session.send(event);
             }
 
@@ -1487,185 +1470,1058 @@ impl ChatEngine {
             }
         }
 
+        METRICS.messages.inc();
         Ok(())
     }
 
-    /// Set the topic for a channel.
-    pub fn set_topic(
+    /// Reject senders who are timed out in this server.
+    async fn check_not_timed_out(
+        &self,
+        session: &UserSession,
+        server_id: &str,
+    ) -> Result<(), EngineError> {
+        let (Some(pool), Some(uid)) = (&self.db, &session.user_id) else {
+            return Ok(());
+        };
+        if let Ok(Some(until)) =
+            crate::db::queries::moderation::get_member_timeout(pool, server_id, uid).await
+            && let Ok(timeout_dt) =
+                chrono::NaiveDateTime::parse_from_str(&until, "%Y-%m-%d %H:%M:%S")
+        {
+            let until = timeout_dt.and_utc();
+            if until > Utc::now() {
+                return Err(EngineError::TimedOut { until });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the session may post in `target`, returning the resolved
+    /// channel ID and normalized name. The channel entry is released before any
+    /// permission lookup is awaited.
+    async fn check_channel_send(
         &self,
+        session: &UserSession,
         session_id: SessionId,
         server_id: &str,
-        channel_name: &str,
-        topic: String,
-    ) -> Result<(), String> {
-        validation::validate_topic(&topic)?;
-        let topic = validation::sanitize_html(&topic);
-        let channel_name = normalize_channel_name(channel_name);
+        target: &str,
+    ) -> Result<(String, String), EngineError> {
+        let channel_name = normalize_channel_name(target);
         let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
 
-        let session = self
-            .sessions
-            .get(&session_id)
-            .ok_or("Session not found")?
-            .clone();
-
-        let mut channel = self
-            .channels
-            .get_mut(&channel_id)
-            .ok_or(format!("No such channel: {channel_name}"))?;
-
-        if !channel.members.contains(&session_id) {
-            return Err(format!("You are not in channel {channel_name}"));
-        }
-
-        channel.topic.clone_from(&topic);
-        channel.topic_set_by = Some(session.nickname.clone());
-        channel.topic_set_at = Some(Utc::now());
+        let (is_private, is_read_only) = {
+            let channel = self
+                .channels
+                .get(&channel_id)
+                .ok_or_else(|| EngineError::NoSuchChannel(channel_name.clone()))?;
 
-        drop(channel);
+            // Check if thread is archived
+            if channel.archived {
+                return Err(EngineError::CannotSend(
+                    "This thread is archived and no longer accepts messages".into(),
+                ));
+            }
+            if !channel.members.contains(&session_id) {
+                return Err(EngineError::NotInChannel(channel_name));
+            }
+            (channel.is_private, channel.is_read_only)
+        };
 
-        if let Some(pool) = &self.db {
-            let pool = pool.clone();
-            let ch = channel_id.clone();
-            let t = topic.clone();
-            let by = session.nickname.clone();
-            tokio::spawn(async move {
-                if let Err(e) = crate::db::queries::channels::set_topic(&pool, &ch, &t, &by).await {
-                    error!(error = %e, "failed to persist topic");
+        // Private channel access control: require VIEW_CHANNELS even if user is in-memory member
+        if is_private {
+            match &session.user_id {
+                Some(uid) if self.db.is_some() => {
+                    let perms = self
+                        .get_effective_permissions(server_id, Some(&channel_id), uid)
+                        .await;
+                    if !perms.contains(Permissions::VIEW_CHANNELS) {
+                        return Err(EngineError::PermissionDenied {
+                            perm: Permissions::VIEW_CHANNELS,
+                        });
+                    }
                 }
-            });
+                Some(_) => {}
+                None => return Err(EngineError::AuthRequired),
+            }
         }
 
-        let event = ChatEvent::TopicChange {
-            server_id: server_id.to_string(),
-            channel: channel_name,
-            set_by: session.nickname.clone(),
-            topic,
-        };
-        self.broadcast_to_channel(&channel_id, &event, None);
+        // Check SEND_MESSAGES permission (only when DB is available for role/override lookups)
+        if self.db.is_some() {
+            let sender_user_id = session
+                .user_id
+                .clone()
+                .unwrap_or_else(|| session_id.to_string());
+            let perms = self
+                .get_effective_permissions(server_id, Some(&channel_id), &sender_user_id)
+                .await;
+            if !perms.contains(Permissions::SEND_MESSAGES) {
+                return Err(EngineError::PermissionDenied {
+                    perm: Permissions::SEND_MESSAGES,
+                });
+            }
+            if is_read_only && !perms.contains(Permissions::MANAGE_MESSAGES) {
+                return Err(EngineError::CannotSend("This channel is read-only".into()));
+            }
+        }
 
-        Ok(())
+        Ok((channel_id, channel_name))
     }
 
-    /// Fetch message history for a channel, including edits, replies, and reactions.
-    pub async fn fetch_history(
+    /// Enforce slow mode: check the per-channel cooldown.
+    /// The DB catches sends from before this process started; the in-memory map is
+    /// checked and updated atomically so concurrent sends (e.g. two browser tabs)
+    /// cannot both pass.
+    async fn check_slowmode(
         &self,
+        session: &UserSession,
         server_id: &str,
-        channel_name: &str,
-        before: Option<&str>,
-        limit: i64,
-        user_id: Option<&str>,
-    ) -> Result<(Vec<HistoryMessage>, bool), String> {
+        target: &str,
+    ) -> Result<(), EngineError> {
         let Some(pool) = &self.db else {
-            return Ok((vec![], false));
+            return Ok(());
+        };
+        let Ok(Some(ch)) =
+            crate::db::queries::channels::get_channel_by_name(pool, server_id, target).await
+        else {
+            return Ok(());
         };
+        if ch.slowmode_seconds <= 0 {
+            return Ok(());
+        }
 
-        let channel_name = normalize_channel_name(channel_name);
-        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+        let cooldown = std::time::Duration::from_secs(ch.slowmode_seconds as u64);
+        let sender_uid = session
+            .user_id
+            .clone()
+            .unwrap_or_else(|| session.nickname.clone());
 
-        // Private channel access control
-        if let Some(ch) = self.channels.get(&channel_id)
-            && ch.is_private
+        if let Ok(Some(last)) =
+            crate::db::queries::messages::get_last_user_message_time(pool, &ch.id, &sender_uid)
+                .await
+            && let Ok(last_dt) = chrono::NaiveDateTime::parse_from_str(&last, "%Y-%m-%d %H:%M:%S")
+            && let Ok(elapsed) = (Utc::now() - last_dt.and_utc()).to_std()
+            && elapsed < cooldown
         {
-            if let Some(uid) = user_id {
-                let perms = self
-                    .get_effective_permissions(server_id, Some(&channel_id), uid)
-                    .await;
-                if !perms.contains(crate::engine::permissions::Permissions::VIEW_CHANNELS) {
-                    return Err(
-                        "You do not have permission to view this private channel".to_string()
-                    );
-                }
-            } else {
-                return Err("Authentication required to view private channels".to_string());
-            }
+            return Err(EngineError::RateLimited {
+                retry_after: Some(cooldown - elapsed),
+            });
         }
 
-        let rows = crate::db::queries::messages::fetch_channel_history(
-            pool,
-            &channel_id,
-            before,
-            limit + 1,
-        )
-        .await
-        .map_err(|e| format!("Failed to fetch history: {e}"))?;
-
-        let has_more = rows.len() as i64 > limit;
-        let rows: Vec<_> = rows.into_iter().take(limit as usize).collect();
-
-        // Collect message IDs for batch reaction lookup
-        let msg_ids: Vec<String> = rows.iter().map(|r| r.id.clone()).collect();
-
-        // Fetch reactions for all messages in batch
-        let reaction_rows =
-            crate::db::queries::messages::get_reactions_for_messages(pool, &msg_ids)
-                .await
-                .unwrap_or_default();
+        match self.slowmode_last_sent.entry((sender_uid, ch.id)) {
+            dashmap::Entry::Occupied(last) if last.get().elapsed() < cooldown => {
+                Err(EngineError::RateLimited {
+                    retry_after: Some(cooldown - last.get().elapsed()),
+                })
+            }
+            dashmap::Entry::Occupied(mut last) => {
+                last.insert(Instant::now());
+                Ok(())
+            }
+            dashmap::Entry::Vacant(slot) => {
+                slot.insert(Instant::now());
+                Ok(())
+            }
+        }
+    }
 
-        // Group reactions by message_id -> emoji -> user_ids
-        let mut reaction_map: std::collections::HashMap<
-            String,
-            std::collections::HashMap<String, Vec<String>>,
-        > = std::collections::HashMap::new();
-        for r in &reaction_rows {
-            reaction_map
-                .entry(r.message_id.clone())
-                .or_default()
-                .entry(r.emoji.clone())
-                .or_default()
-                .push(r.user_id.clone());
+    /// Evaluate the server's enabled automod rules, honouring their channel and
+    /// role exemptions, and carry out the actions of every rule the message
+    /// triggers. Fails if a rule blocks the message or holds it for approval;
+    /// otherwise returns the rules that flagged it for review.
+    async fn apply_automod(
+        &self,
+        session: &UserSession,
+        server_id: &str,
+        target: &str,
+        channel_id: &str,
+        content: &str,
+        reply_to_id: Option<&str>,
+    ) -> Result<Vec<Arc<CompiledRule>>, EngineError> {
+        let Some(pool) = &self.db else {
+            return Ok(Vec::new());
+        };
+        let rules = self.automod_rules(pool, server_id).await;
+        if rules.is_empty() {
+            return Ok(Vec::new());
         }
+        let role_ids = match &session.user_id {
+            Some(user_id) if rules.has_role_exemptions() => {
+                self.member_role_ids(pool, server_id, user_id).await
+            }
+            _ => Arc::default(),
+        };
+        let invite_servers = if rules.has_invite_rules() {
+            invite_link_servers(pool, content).await
+        } else {
+            Vec::new()
+        };
+        let msg = automod::MessageCheck {
+            content,
+            server_id,
+            channel_id: Some(channel_id),
+            role_ids: &role_ids,
+            sender: session.user_id.as_deref().unwrap_or(&session.nickname),
+            invite_servers: &invite_servers,
+        };
 
-        // Collect reply_to_ids for batch lookup
-        let reply_ids: Vec<String> = rows.iter().filter_map(|r| r.reply_to_id.clone()).collect();
-        let mut reply_map: std::collections::HashMap<String, ReplyInfo> =
-            std::collections::HashMap::new();
-        if !reply_ids.is_empty() {
-            for rid in &reply_ids {
-                if let Ok(Some(parent)) =
-                    crate::db::queries::messages::get_message_by_id(pool, rid).await
-                {
-                    reply_map.insert(
-                        parent.id.clone(),
-                        ReplyInfo {
-                            id: parent.id,
-                            from: parent.sender_nick,
-                            content_preview: parent.content.chars().take(100).collect(),
-                        },
-                    );
+        let actor_id = session.user_id.as_deref();
+        let mut blocked_by = None;
+        let mut held_by = None;
+        let mut flagged_by = Vec::new();
+        for rule in rules.check(&msg, &self.automod_counters) {
+            METRICS.automod_triggers.inc(&rule.rule_type);
+            for action in &rule.actions {
+                match action {
+                    AutomodAction::Block => {
+                        blocked_by.get_or_insert_with(|| rule.clone());
+                    }
+                    AutomodAction::RequireApproval => {
+                        held_by.get_or_insert_with(|| rule.clone());
+                    }
+                    AutomodAction::Flag => flagged_by.push(rule.clone()),
+                    AutomodAction::Alert {
+                        channel_id: alert_channel_id,
+                    } => {
+                        let alert = format!(
+                            "Rule \"{}\" triggered by {} in {target}: {content}",
+                            rule.name, session.nickname
+                        );
+                        let changes = serde_json::json!({
+                            "alert_channel_id": alert_channel_id,
+                            "target": target,
+                        });
+                        self.post_automod_alert(
+                            pool,
+                            server_id,
+                            alert_channel_id,
+                            &rule,
+                            actor_id,
+                            alert,
+                            &changes,
+                        )
+                        .await;
+                    }
+                    AutomodAction::Timeout { duration_seconds } => {
+                        if let Some(user_id) = actor_id {
+                            self.automod_timeout(
+                                pool,
+                                server_id,
+                                user_id,
+                                &rule,
+                                *duration_seconds,
+                            )
+                            .await;
+                        }
+                    }
                 }
             }
+            // Rules that punish the sender also count as a strike against them
+            if let Some(user_id) = actor_id
+                && rule.actions.iter().any(|action| {
+                    matches!(action, AutomodAction::Block | AutomodAction::Timeout { .. })
+                })
+            {
+                let reason = format!("AutoMod: {}", rule.name);
+                self.record_strike(pool, server_id, user_id, None, "automod", Some(&reason))
+                    .await;
+            }
         }
 
-        // Fetch attachments for all messages in batch
-        let attachment_rows =
-            crate::db::queries::attachments::get_attachments_for_messages(pool, &msg_ids)
-                .await
-                .unwrap_or_default();
+        if let Some(rule) = blocked_by {
+            let changes = serde_json::json!({ "target": target, "content": content });
+            self.audit_automod(pool, server_id, actor_id, &rule, "automod_block", &changes)
+                .await;
+            return Err(EngineError::CannotSend(format!(
+                "Message blocked by automod rule: {}",
+                rule.name
+            )));
+        }
+        if let Some(rule) = held_by {
+            let sender_uid = session.user_id.as_deref().unwrap_or_default();
+            let params = crate::db::models::CreateAutomodQueueParams {
+                id: &Uuid::new_v4().to_string(),
+                server_id,
+                channel_id,
+                rule_id: &rule.id,
+                rule_name: &rule.name,
+                user_id: sender_uid,
+                user_nick: &session.nickname,
+                content,
+                reply_to_id,
+                message_id: None,
+                status: "pending",
+            };
+            self.queue_for_review(pool, session, &rule, &params).await;
+            return Err(EngineError::CannotSend(format!(
+                "Message held for moderator review by automod rule: {}",
+                rule.name
+            )));
+        }
+        Ok(flagged_by)
+    }
 
-        // Group attachments by message_id
-        let mut attachment_map: std::collections::HashMap<
-            String,
-            Vec<super::events::AttachmentInfo>,
-        > = std::collections::HashMap::new();
-        for a in &attachment_rows {
-            if let Some(ref mid) = a.message_id {
-                attachment_map.entry(mid.clone()).or_default().push(
-                    super::events::AttachmentInfo {
-                        id: a.id.clone(),
-                        filename: a.original_filename.clone(),
-                        content_type: a.content_type.clone(),
-                        file_size: a.file_size,
-                        url: format!("/api/uploads/{}", a.id),
-                    },
-                );
+    /// Evaluate the server's join rules (join_burst, raid) for a user about to
+    /// join. Alerts are posted and raid lockdowns started here; fails if a rule
+    /// blocks the join, otherwise returns the timeouts to apply once the user
+    /// is a member.
+    async fn apply_join_automod(
+        &self,
+        pool: &SqlitePool,
+        server_id: &str,
+        user_id: &str,
+    ) -> Result<Vec<(Arc<CompiledRule>, u64)>, EngineError> {
+        let rules = self.automod_rules(pool, server_id).await;
+        if !rules.has_join_rules() {
+            return Ok(Vec::new());
+        }
+        let account = crate::db::queries::users::find_user_account(pool, user_id)
+            .await
+            .ok()
+            .flatten();
+        let account_age = account
+            .as_ref()
+            .and_then(|a| {
+                chrono::NaiveDateTime::parse_from_str(&a.created_at, "%Y-%m-%d %H:%M:%S").ok()
+            })
+            .and_then(|created| (Utc::now() - created.and_utc()).to_std().ok());
+        let name = account.map_or_else(|| user_id.to_string(), |a| a.username);
+
+        let mut blocked_by = None;
+        let mut timeouts = Vec::new();
+        for rule in rules.check_join(account_age, &self.automod_counters) {
+            METRICS.automod_triggers.inc(&rule.rule_type);
+            if let Some(policy) = rule.lockdown() {
+                self.start_lockdown(server_id, policy);
+                warn!(%server_id, rule = %rule.name, "raid detected, server locked down");
+                let changes = serde_json::json!({
+                    "lockdown_seconds": policy.duration.as_secs(),
+                    "new_member_seconds": policy.new_member_age.as_secs(),
+                });
+                self.audit_automod(
+                    pool,
+                    server_id,
+                    Some(user_id),
+                    &rule,
+                    "automod_lockdown",
+                    &changes,
+                )
+                .await;
+            }
+            for action in &rule.actions {
+                match action {
+                    AutomodAction::Block => {
+                        blocked_by.get_or_insert_with(|| rule.clone());
+                    }
+                    AutomodAction::Alert {
+                        channel_id: alert_channel_id,
+                    } => {
+                        let alert = match rule.lockdown() {
+                            Some(policy) => format!(
+                                "Rule \"{}\" detected a raid as {name} joined; server locked down for {}s",
+                                rule.name,
+                                policy.duration.as_secs()
+                            ),
+                            None => format!("Rule \"{}\" triggered by {name} joining", rule.name),
+                        };
+                        let changes = serde_json::json!({ "alert_channel_id": alert_channel_id });
+                        self.post_automod_alert(
+                            pool,
+                            server_id,
+                            alert_channel_id,
+                            &rule,
+                            Some(user_id),
+                            alert,
+                            &changes,
+                        )
+                        .await;
+                    }
+                    AutomodAction::Timeout { duration_seconds } => {
+                        timeouts.push((rule.clone(), *duration_seconds));
+                    }
+                    // Rejected for join rules when the rule is saved
+                    AutomodAction::Flag | AutomodAction::RequireApproval => {}
+                }
             }
         }
 
-        let messages: Vec<HistoryMessage> = rows
-            .into_iter()
-            .map(|row| {
-                let reactions = reaction_map.get(&row.id).map(|emoji_map| {
+        if let Some(rule) = blocked_by {
+            self.audit_automod(
+                pool,
+                server_id,
+                Some(user_id),
+                &rule,
+                "automod_block_join",
+                &serde_json::json!({}),
+            )
+            .await;
+            return Err(EngineError::Forbidden(format!(
+                "Join blocked by automod rule: {}",
+                rule.name
+            )));
+        }
+        Ok(timeouts)
+    }
+
+    /// Lock the server down, or extend a running lockdown, and tell its members.
+    fn start_lockdown(&self, server_id: &str, policy: automod::LockdownPolicy) {
+        let until = Utc::now()
+            + chrono::Duration::from_std(policy.duration).unwrap_or(chrono::Duration::zero());
+        let new_member_seconds = policy.new_member_age.as_secs();
+        {
+            let Some(mut server) = self.servers.get_mut(server_id) else {
+                return;
+            };
+            let lockdown = match server.active_lockdown() {
+                Some(current) => Lockdown {
+                    until: current.until.max(until),
+                    new_member_seconds: current.new_member_seconds.max(new_member_seconds),
+                },
+                None => Lockdown {
+                    until,
+                    new_member_seconds,
+                },
+            };
+            server.lockdown = Some(lockdown);
+        }
+        self.broadcast_lockdown(server_id);
+    }
+
+    /// Send the server's current lockdown state to its members.
+    fn broadcast_lockdown(&self, server_id: &str) {
+        let Some(lockdown) = self.servers.get(server_id).map(|s| s.active_lockdown()) else {
+            return;
+        };
+        self.broadcast_to_server(
+            server_id,
+            &ChatEvent::ServerLockdown {
+                server_id: server_id.to_string(),
+                until: lockdown.map(|l| l.until),
+                new_member_seconds: lockdown.map_or(0, |l| l.new_member_seconds),
+            },
+        );
+    }
+
+    /// Start a lockdown for `duration_seconds`, or lift it with 0. Requires
+    /// MANAGE_SERVER.
+    pub async fn set_server_lockdown(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        duration_seconds: u64,
+        new_member_seconds: Option<u64>,
+    ) -> Result<(), EngineError> {
+        let user_id = self
+            .require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
+            .await?;
+        if duration_seconds > automod::MAX_TIMEOUT_SECONDS {
+            return Err(EngineError::Validation(format!(
+                "Lockdown duration must be at most {} seconds",
+                automod::MAX_TIMEOUT_SECONDS
+            )));
+        }
+        let new_member_seconds = new_member_seconds.unwrap_or(10 * 60);
+        if new_member_seconds > automod::MAX_TIMEOUT_SECONDS {
+            return Err(EngineError::Validation(format!(
+                "new_member_seconds must be at most {}",
+                automod::MAX_TIMEOUT_SECONDS
+            )));
+        }
+
+        {
+            let mut server = self
+                .servers
+                .get_mut(server_id)
+                .ok_or_else(|| EngineError::NotFound("Server not found".into()))?;
+            server.lockdown = (duration_seconds > 0).then(|| Lockdown {
+                until: Utc::now() + chrono::Duration::seconds(duration_seconds as i64),
+                new_member_seconds,
+            });
+        }
+        self.broadcast_lockdown(server_id);
+
+        if let Some(pool) = &self.db {
+            let changes = serde_json::json!({
+                "duration_seconds": duration_seconds,
+                "new_member_seconds": new_member_seconds,
+            })
+            .to_string();
+            let action_type = if duration_seconds > 0 {
+                "server_lockdown"
+            } else {
+                "server_lockdown_lift"
+            };
+            if let Err(e) = crate::db::queries::audit_log::create_entry(
+                pool,
+                &crate::db::models::CreateAuditLogParams {
+                    id: &Uuid::new_v4().to_string(),
+                    server_id,
+                    actor_id: &user_id,
+                    action_type,
+                    target_type: Some("server"),
+                    target_id: Some(server_id),
+                    reason: None,
+                    changes: Some(&changes),
+                },
+            )
+            .await
+            {
+                warn!(error = %e, "Failed to write audit log entry");
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject members who joined too recently to send during a lockdown.
+    async fn check_lockdown(
+        &self,
+        session: &UserSession,
+        server_id: &str,
+    ) -> Result<(), EngineError> {
+        let Some(lockdown) = self
+            .servers
+            .get(server_id)
+            .and_then(|s| s.active_lockdown())
+        else {
+            return Ok(());
+        };
+        let (Some(pool), Some(uid)) = (&self.db, &session.user_id) else {
+            return Ok(());
+        };
+        let Ok(Some(member)) =
+            crate::db::queries::servers::get_server_member(pool, server_id, uid).await
+        else {
+            return Ok(());
+        };
+        if let Ok(joined) =
+            chrono::NaiveDateTime::parse_from_str(&member.joined_at, "%Y-%m-%d %H:%M:%S")
+            && Utc::now() - joined.and_utc()
+                < chrono::Duration::seconds(lockdown.new_member_seconds as i64)
+        {
+            return Err(EngineError::CannotSend(
+                "This server is locked down; new members can't send messages yet".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reject writes to a quarantined server.
+    fn check_not_quarantined(&self, server_id: &str) -> Result<(), EngineError> {
+        if self.servers.get(server_id).is_some_and(|s| s.quarantined) {
+            return Err(EngineError::Forbidden(
+                "This server has been quarantined by the instance administrators and is read-only"
+                    .into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Post an automod hit to the rule's alert channel. `actor_id` is the user
+    /// the hit is audit-logged against.
+    #[allow(clippy::too_many_arguments)]
+    async fn post_automod_alert(
+        &self,
+        pool: &SqlitePool,
+        server_id: &str,
+        alert_channel_id: &str,
+        rule: &CompiledRule,
+        actor_id: Option<&str>,
+        alert: String,
+        changes: &serde_json::Value,
+    ) {
+        let Some(channel_name) = self
+            .channels
+            .get(alert_channel_id)
+            .filter(|ch| ch.server_id == server_id)
+            .map(|ch| ch.name.clone())
+        else {
+            warn!(rule = %rule.name, %alert_channel_id, "automod alert channel not found");
+            return;
+        };
+
+        let msg_id = Uuid::new_v4();
+        let id = msg_id.to_string();
+        let params = crate::db::queries::messages::InsertMessageParams {
+            id: &id,
+            server_id,
+            channel_id: alert_channel_id,
+            sender_id: &format!("automod:{}", rule.id),
+            sender_nick: AUTOMOD_NICK,
+            content: &alert,
+            reply_to_id: None,
+        };
+        if let Err(e) = crate::db::queries::messages::insert_message(pool, &params).await {
+            error!(error = %e, "failed to persist automod alert");
+        }
+        let event = ChatEvent::Message {
+            id: msg_id,
+            server_id: Some(server_id.to_string()),
+            from: AUTOMOD_NICK.to_string(),
+            target: channel_name,
+            content: alert,
+            timestamp: Utc::now(),
+            avatar_url: None,
+            reply_to: None,
+            attachments: None,
+        };
+        self.broadcast_to_channel(alert_channel_id, &event, None);
+
+        self.audit_automod(pool, server_id, actor_id, rule, "automod_alert", changes)
+            .await;
+    }
+
+    /// Time out a user who triggered a rule.
+    async fn automod_timeout(
+        &self,
+        pool: &SqlitePool,
+        server_id: &str,
+        user_id: &str,
+        rule: &CompiledRule,
+        duration_seconds: u64,
+    ) {
+        let until = (Utc::now() + chrono::Duration::seconds(duration_seconds as i64))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        if let Err(e) = self
+            .apply_member_timeout(pool, server_id, user_id, Some(&until))
+            .await
+        {
+            warn!(error = %e, rule = %rule.name, "failed to apply automod timeout");
+            return;
+        }
+        let changes = serde_json::json!({ "timeout_until": until });
+        self.audit_automod(
+            pool,
+            server_id,
+            Some(user_id),
+            rule,
+            "automod_timeout",
+            &changes,
+        )
+        .await;
+    }
+
+    /// Add a flagged or held message to the review queue.
+    async fn queue_for_review(
+        &self,
+        pool: &SqlitePool,
+        session: &UserSession,
+        rule: &CompiledRule,
+        params: &crate::db::models::CreateAutomodQueueParams<'_>,
+    ) {
+        if let Err(e) = crate::db::queries::automod::create_queue_entry(pool, params).await {
+            error!(error = %e, "failed to queue message for automod review");
+            return;
+        }
+        let action = if params.message_id.is_some() {
+            "automod_flag"
+        } else {
+            "automod_hold"
+        };
+        let changes = serde_json::json!({ "queue_id": params.id, "content": params.content });
+        self.audit_automod(
+            pool,
+            params.server_id,
+            session.user_id.as_deref(),
+            rule,
+            action,
+            &changes,
+        )
+        .await;
+    }
+
+    /// Audit-log an automod action. The user who triggered it is recorded as
+    /// the actor since the audit log needs a user; guests without an account
+    /// are not logged.
+    async fn audit_automod(
+        &self,
+        pool: &SqlitePool,
+        server_id: &str,
+        actor_id: Option<&str>,
+        rule: &CompiledRule,
+        action_type: &str,
+        changes: &serde_json::Value,
+    ) {
+        let Some(user_id) = actor_id else {
+            return;
+        };
+        let changes = changes.to_string();
+        if let Err(e) = crate::db::queries::audit_log::create_entry(
+            pool,
+            &crate::db::models::CreateAuditLogParams {
+                id: &Uuid::new_v4().to_string(),
+                server_id,
+                actor_id: user_id,
+                action_type,
+                target_type: Some("automod_rule"),
+                target_id: Some(&rule.id),
+                reason: Some(&rule.name),
+                changes: Some(&changes),
+            },
+        )
+        .await
+        {
+            warn!(error = %e, "Failed to write automod audit log entry");
+        }
+    }
+
+    /// The server's compiled automod rules, loaded from the database on a miss.
+    async fn automod_rules(&self, pool: &SqlitePool, server_id: &str) -> Arc<AutomodRules> {
+        let generation = match self
+            .servers
+            .get(server_id)
+            .map(|s| (s.automod_rules.clone(), s.automod_generation))
+        {
+            Some((Some(rules), _)) => return rules,
+            Some((None, generation)) => Some(generation),
+            None => None,
+        };
+        let rows = match crate::db::queries::automod::get_enabled_rules(pool, server_id).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                warn!(error = %e, %server_id, "failed to load automod rules");
+                return Arc::default();
+            }
+        };
+        let rules = Arc::new(AutomodRules::compile(&rows));
+        if let Some(generation) = generation
+            && let Some(mut server) = self.servers.get_mut(server_id)
+            && server.automod_generation == generation
+        {
+            server.automod_rules = Some(rules.clone());
+        }
+        rules
+    }
+
+    /// Recompile the server's automod rules after one was created, changed or deleted.
+    async fn reload_automod_rules(&self, pool: &SqlitePool, server_id: &str) {
+        if let Some(mut server) = self.servers.get_mut(server_id) {
+            server.automod_generation += 1;
+            server.automod_rules = None;
+        }
+        self.automod_rules(pool, server_id).await;
+    }
+
+    /// Preview of the message being replied to, if it exists.
+    async fn reply_info(&self, reply_to_id: Option<&str>) -> Option<ReplyInfo> {
+        let (Some(ref_id), Some(pool)) = (reply_to_id, &self.db) else {
+            return None;
+        };
+        match crate::db::queries::messages::get_message_by_id(pool, ref_id).await {
+            Ok(Some(row)) => Some(ReplyInfo {
+                id: row.id,
+                from: row.sender_nick,
+                content_preview: row.content.chars().take(100).collect::<String>(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Look up attachment metadata for the given upload IDs.
+    async fn attachment_infos(
+        &self,
+        attachment_ids: Option<&[String]>,
+    ) -> Option<Vec<super::events::AttachmentInfo>> {
+        let (Some(ids), Some(pool)) = (attachment_ids, &self.db) else {
+            return None;
+        };
+        if ids.is_empty() {
+            return None;
+        }
+        let infos = crate::db::queries::attachments::get_attachments_by_ids(pool, ids)
+            .await
+            .unwrap_or_default();
+        if infos.is_empty() {
+            return None;
+        }
+        Some(
+            infos
+                .into_iter()
+                .map(|a| super::events::AttachmentInfo {
+                    id: a.id.clone(),
+                    filename: a.original_filename,
+                    content_type: a.content_type,
+                    file_size: a.file_size,
+                    url: format!("/api/uploads/{}", a.id),
+                })
+                .collect(),
+        )
+    }
+
+    /// Async link embed unfurling — extract URLs, resolve OG metadata and send a
+    /// `MessageEmbed` to the channel's members once it's ready.
+    fn spawn_link_unfurl(
+        &self,
+        msg_id: Uuid,
+        server_id: &str,
+        channel_id: &str,
+        channel_name: &str,
+        content: &str,
+    ) {
+        let urls = super::embeds::extract_urls(content);
+        let Some(pool) = &self.db else {
+            return;
+        };
+        if urls.is_empty() {
+            return;
+        }
+        let pool = pool.clone();
+        let client = self.http_client.clone();
+        let server_id = server_id.to_string();
+        let channel_name = channel_name.to_string();
+        // Collect senders for channel members before spawning
+        let member_senders: Vec<mpsc::Sender<ChatEvent>> =
+            if let Some(channel) = self.channels.get(channel_id) {
+                channel
+                    .members
+                    .iter()
+                    .filter_map(|sid| self.sessions.get(sid).map(|s| s.outbound.clone()))
+                    .collect()
+            } else {
+                vec![]
+            };
+        tokio::spawn(async move {
+            let mut embeds = Vec::new();
+            for url in urls {
+                // Check cache first
+                if let Ok(Some(cached)) =
+                    crate::db::queries::embeds::get_cached_embed(&pool, &url).await
+                {
+                    embeds.push(super::events::EmbedInfo {
+                        url: cached.url,
+                        title: cached.title,
+                        description: cached.description,
+                        image_url: cached.image_url,
+                        site_name: cached.site_name,
+                    });
+                    continue;
+                }
+                // Unfurl
+                if let Some(info) = super::embeds::unfurl_url(&client, &url).await {
+                    let _ = crate::db::queries::embeds::upsert_embed(
+                        &pool,
+                        &info.url,
+                        info.title.as_deref(),
+                        info.description.as_deref(),
+                        info.image_url.as_deref(),
+                        info.site_name.as_deref(),
+                    )
+                    .await;
+                    embeds.push(info);
+                }
+            }
+            if !embeds.is_empty() {
+                let embed_event = ChatEvent::MessageEmbed {
+                    message_id: msg_id,
+                    server_id,
+                    channel: channel_name,
+                    embeds,
+                };
+                for sender in &member_senders {
+                    let _ = sender.try_send(embed_event.clone());
+                }
+            }
+        });
+    }
+
+    /// Set the topic for a channel.
+    pub async fn set_topic(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        channel_name: &str,
+        topic: String,
+    ) -> Result<(), EngineError> {
+        self.check_not_quarantined(server_id)?;
+        validation::validate_topic(&topic)?;
+        let topic = validation::sanitize_html(&topic);
+        let channel_name = normalize_channel_name(channel_name);
+        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+
+        let session = self
+            .sessions
+            .get(&session_id)
+            .ok_or(EngineError::SessionNotFound)?
+            .clone();
+
+        let (is_member, topic_locked) = {
+            let channel = self
+                .channels
+                .get(&channel_id)
+                .ok_or_else(|| EngineError::NoSuchChannel(channel_name.to_string()))?;
+            (channel.members.contains(&session_id), channel.topic_locked)
+        };
+        if !is_member {
+            return Err(EngineError::NotInChannel(channel_name));
+        }
+
+        // Topic lock (+t): only members with MANAGE_CHANNELS may change the topic.
+        if topic_locked && self.db.is_some() {
+            let allowed = match session.user_id.as_deref() {
+                Some(uid) => self
+                    .get_effective_permissions(server_id, Some(&channel_id), uid)
+                    .await
+                    .contains(Permissions::MANAGE_CHANNELS),
+                None => false,
+            };
+            if !allowed {
+                return Err(EngineError::Forbidden(
+                    "The topic of this channel is locked".into(),
+                ));
+            }
+        }
+
+        let mut channel = self
+            .channels
+            .get_mut(&channel_id)
+            .ok_or_else(|| EngineError::NoSuchChannel(channel_name.to_string()))?;
+        channel.topic.clone_from(&topic);
+        channel.topic_set_by = Some(session.nickname.clone());
+        channel.topic_set_at = Some(Utc::now());
+
+        drop(channel);
+
+        if let Some(pool) = &self.db {
+            let pool = pool.clone();
+            let ch = channel_id.clone();
+            let t = topic.clone();
+            let by = session.nickname.clone();
+            tokio::spawn(async move {
+                if let Err(e) = crate::db::queries::channels::set_topic(&pool, &ch, &t, &by).await {
+                    error!(error = %e, "failed to persist topic");
+                }
+            });
+        }
+
+        let event = ChatEvent::TopicChange {
+            server_id: server_id.to_string(),
+            channel: channel_name,
+            set_by: session.nickname.clone(),
+            topic,
+        };
+        self.broadcast_to_channel(&channel_id, &event, None);
+
+        Ok(())
+    }
+
+    /// Fetch message history for a channel, including edits, replies, and reactions.
+    pub async fn fetch_history(
+        &self,
+        server_id: &str,
+        channel_name: &str,
+        before: Option<&str>,
+        limit: i64,
+        user_id: Option<&str>,
+    ) -> Result<(Vec<HistoryMessage>, bool), EngineError> {
+        let Some(pool) = &self.db else {
+            return Ok((vec![], false));
+        };
+
+        let channel_name = normalize_channel_name(channel_name);
+        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+
+        // Private channel access control
+        if let Some(ch) = self.channels.get(&channel_id)
+            && ch.is_private
+        {
+            if let Some(uid) = user_id {
+                let perms = self
+                    .get_effective_permissions(server_id, Some(&channel_id), uid)
+                    .await;
+                if !perms.contains(crate::engine::permissions::Permissions::VIEW_CHANNELS) {
+                    return Err(EngineError::PermissionDenied {
+                        perm: Permissions::VIEW_CHANNELS,
+                    });
+                }
+            } else {
+                return Err(EngineError::AuthRequired);
+            }
+        }
+
+        let rows = crate::db::queries::messages::fetch_channel_history(
+            pool,
+            &channel_id,
+            before,
+            limit + 1,
+        )
+        .await
+        .map_err(|e| EngineError::Internal(format!("Failed to fetch history: {e}")))?;
+
+        let has_more = rows.len() as i64 > limit;
+        let rows: Vec<_> = rows.into_iter().take(limit as usize).collect();
+
+        // Collect message IDs for batch reaction lookup
+        let msg_ids: Vec<String> = rows.iter().map(|r| r.id.clone()).collect();
+
+        // Fetch reactions for all messages in batch
+        let reaction_rows =
+            crate::db::queries::messages::get_reactions_for_messages(pool, &msg_ids)
+                .await
+                .unwrap_or_default();
+
+        // Group reactions by message_id -> emoji -> user_ids
+        let mut reaction_map: std::collections::HashMap<
+            String,
+            std::collections::HashMap<String, Vec<String>>,
+        > = std::collections::HashMap::new();
+        for r in &reaction_rows {
+            reaction_map
+                .entry(r.message_id.clone())
+                .or_default()
+                .entry(r.emoji.clone())
+                .or_default()
+                .push(r.user_id.clone());
+        }
+
+        // Collect reply_to_ids for batch lookup
+        let reply_ids: Vec<String> = rows.iter().filter_map(|r| r.reply_to_id.clone()).collect();
+        let mut reply_map: std::collections::HashMap<String, ReplyInfo> =
+            std::collections::HashMap::new();
+        if !reply_ids.is_empty() {
+            for rid in &reply_ids {
+                if let Ok(Some(parent)) =
+                    crate::db::queries::messages::get_message_by_id(pool, rid).await
+                {
+                    reply_map.insert(
+                        parent.id.clone(),
+                        ReplyInfo {
+                            id: parent.id,
+                            from: parent.sender_nick,
+                            content_preview: parent.content.chars().take(100).collect(),
+                        },
+                    );
+                }
+            }
+        }
+
+        // Fetch attachments for all messages in batch
+        let attachment_rows =
+            crate::db::queries::attachments::get_attachments_for_messages(pool, &msg_ids)
+                .await
+                .unwrap_or_default();
+
+        // Group attachments by message_id
+        let mut attachment_map: std::collections::HashMap<
+            String,
+            Vec<super::events::AttachmentInfo>,
+        > = std::collections::HashMap::new();
+        for a in &attachment_rows {
+            if let Some(ref mid) = a.message_id {
+                attachment_map.entry(mid.clone()).or_default().push(
+                    super::events::AttachmentInfo {
+                        id: a.id.clone(),
+                        filename: a.original_filename.clone(),
+                        content_type: a.content_type.clone(),
+                        file_size: a.file_size,
+                        url: format!("/api/uploads/{}", a.id),
+                    },
+                );
+            }
+        }
+
+        let messages: Vec<HistoryMessage> = rows
+            .into_iter()
+            .map(|row| {
+                let reactions = reaction_map.get(&row.id).map(|emoji_map| {
                     emoji_map
                         .iter()
                         .map(|(emoji, user_ids)| ReactionGroup {
@@ -1675,2385 +2531,4743 @@ impl ChatEngine {
                         })
                         .collect()
                 });
-                let reply_to = row
-                    .reply_to_id
+                let reply_to = row
+                    .reply_to_id
+                    .as_ref()
+                    .and_then(|rid| reply_map.get(rid).cloned());
+                let edited_at = row.edited_at.as_ref().and_then(|s| s.parse().ok());
+                let attachments = attachment_map.remove(&row.id);
+
+                HistoryMessage {
+                    id: row.id.parse().unwrap_or_default(),
+                    from: row.sender_nick,
+                    content: row.content,
+                    timestamp: row.created_at.parse().unwrap_or_else(|_| Utc::now()),
+                    edited_at,
+                    reply_to,
+                    reactions,
+                    attachments,
+                    embeds: None,
+                }
+            })
+            .collect();
+
+        Ok((messages, has_more))
+    }
+
+    /// List all channels in a server.
+    pub fn list_channels(&self, server_id: &str) -> Vec<ChannelInfo> {
+        self.channels
+            .iter()
+            .filter(|ch| ch.server_id == server_id)
+            .map(|entry| ChannelInfo {
+                id: entry.id.clone(),
+                server_id: entry.server_id.clone(),
+                name: entry.name.clone(),
+                topic: entry.topic.clone(),
+                member_count: entry.member_count(),
+                category_id: entry.category_id.clone(),
+                position: entry.position,
+                is_private: entry.is_private,
+                channel_type: entry.channel_type.clone(),
+                thread_parent_message_id: entry.thread_parent_message_id.clone(),
+                archived: entry.archived,
+                is_read_only: entry.is_read_only,
+                topic_locked: entry.topic_locked,
+            })
+            .collect()
+    }
+
+    /// Get members of a channel.
+    pub fn get_members(
+        &self,
+        server_id: &str,
+        channel_name: &str,
+    ) -> Result<Vec<MemberInfo>, EngineError> {
+        let channel_name = normalize_channel_name(channel_name);
+        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+
+        let channel = self
+            .channels
+            .get(&channel_id)
+            .ok_or_else(|| EngineError::NoSuchChannel(channel_name.to_string()))?;
+
+        Ok(channel
+            .members
+            .iter()
+            .filter_map(|sid| {
+                self.sessions.get(sid).map(|s| MemberInfo {
+                    nickname: s.nickname.clone(),
+                    avatar_url: s.avatar_url.clone(),
+                    server_avatar_url: None,
+                    status: None,
+                    custom_status: None,
+                    status_emoji: None,
+                    user_id: s.user_id.clone(),
+                })
+            })
+            .collect())
+    }
+
+    // ── Message editing & deletion ─────────────────────────────────
+
+    /// Edit a message's content. Only the sender or a moderator+ can edit.
+    pub async fn edit_message(
+        &self,
+        session_id: SessionId,
+        message_id: &str,
+        new_content: &str,
+    ) -> Result<(), EngineError> {
+        validation::validate_message_with_limit(new_content, self.max_message_length)?;
+        let new_content = &validation::sanitize_html(new_content);
+
+        let session = self
+            .sessions
+            .get(&session_id)
+            .ok_or(EngineError::SessionNotFound)?
+            .clone();
+
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Message not found".into()))?;
+        if let Some(server_id) = &msg.server_id {
+            self.check_not_quarantined(server_id)?;
+        }
+
+        // Only the sender can edit their own messages, unless user has MANAGE_MESSAGES
+        let sender_id = session
+            .user_id
+            .as_deref()
+            .ok_or(EngineError::AuthRequired)?;
+        if msg.sender_id != sender_id {
+            let server_id = msg
+                .server_id
+                .as_deref()
+                .ok_or_else(|| EngineError::NotFound("Message has no server".into()))?;
+            let channel_id = msg
+                .channel_id
+                .as_deref()
+                .ok_or_else(|| EngineError::NotFound("Message has no channel".into()))?;
+            let perms = self
+                .get_effective_permissions(server_id, Some(channel_id), sender_id)
+                .await;
+            if !perms.contains(Permissions::MANAGE_MESSAGES) {
+                return Err(EngineError::Forbidden(
+                    "You can only edit your own messages".into(),
+                ));
+            }
+        }
+
+        crate::db::queries::messages::update_message_content(pool, message_id, new_content).await?;
+
+        let server_id = msg
+            .server_id
+            .ok_or_else(|| EngineError::NotFound("Message has no server".into()))?;
+        let channel_id = msg
+            .channel_id
+            .ok_or_else(|| EngineError::NotFound("Message has no channel".into()))?;
+
+        // Find the channel name for the event
+        let channel_name = self
+            .channels
+            .get(&channel_id)
+            .map(|ch| ch.name.clone())
+            .unwrap_or_default();
+
+        let event = ChatEvent::MessageEdit {
+            id: message_id.parse().unwrap_or_default(),
+            server_id: server_id.clone(),
+            channel: channel_name,
+            content: new_content.to_string(),
+            edited_at: Utc::now(),
+        };
+
+        // Broadcast to the channel (including sender)
+        self.broadcast_to_channel(&channel_id, &event, None);
+
+        Ok(())
+    }
+
+    /// Delete a message (soft delete). Sender can delete own, moderator+ can delete any.
+    pub async fn delete_message(
+        &self,
+        session_id: SessionId,
+        message_id: &str,
+    ) -> Result<(), EngineError> {
+        let user_id = self.session_user_id(session_id)?;
+        self.delete_message_by(&user_id, message_id).await
+    }
+
+    /// Delete a message on behalf of `sender_id`, who must be its sender or have
+    /// MANAGE_MESSAGES in its channel.
+    pub async fn delete_message_by(
+        &self,
+        sender_id: &str,
+        message_id: &str,
+    ) -> Result<(), EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Message not found".into()))?;
+        if let Some(server_id) = msg.server_id.as_deref() {
+            self.check_not_quarantined(server_id)?;
+        }
+
+        let is_sender = msg.sender_id == sender_id;
+
+        if !is_sender {
+            // Check if user has MANAGE_MESSAGES permission
+            let server_id = msg
+                .server_id
+                .as_deref()
+                .ok_or_else(|| EngineError::NotFound("Message has no server".into()))?;
+            let channel_id_ref = msg
+                .channel_id
+                .as_deref()
+                .ok_or_else(|| EngineError::NotFound("Message has no channel".into()))?;
+            let perms = self
+                .get_effective_permissions(server_id, Some(channel_id_ref), sender_id)
+                .await;
+            if !perms.contains(Permissions::MANAGE_MESSAGES) {
+                return Err(EngineError::Forbidden(
+                    "You can only delete your own messages".into(),
+                ));
+            }
+        }
+
+        crate::db::queries::messages::soft_delete_message(pool, message_id).await?;
+
+        let server_id = msg
+            .server_id
+            .ok_or_else(|| EngineError::NotFound("Message has no server".into()))?;
+        let channel_id = msg
+            .channel_id
+            .ok_or_else(|| EngineError::NotFound("Message has no channel".into()))?;
+
+        let channel_name = self
+            .channels
+            .get(&channel_id)
+            .map(|ch| ch.name.clone())
+            .unwrap_or_default();
+
+        let event = ChatEvent::MessageDelete {
+            id: message_id.parse().unwrap_or_default(),
+            server_id,
+            channel: channel_name,
+        };
+
+        self.broadcast_to_channel(&channel_id, &event, None);
+
+        Ok(())
+    }
+
+    // ── Reactions ────────────────────────────────────────────────────
+
+    /// Add a reaction to a message.
+    pub async fn add_reaction(
+        &self,
+        session_id: SessionId,
+        message_id: &str,
+        emoji: &str,
+    ) -> Result<(), EngineError> {
+        let session = self
+            .sessions
+            .get(&session_id)
+            .ok_or(EngineError::SessionNotFound)?
+            .clone();
+
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Message not found".into()))?;
+        if let Some(server_id) = &msg.server_id {
+            self.check_not_quarantined(server_id)?;
+        }
+
+        let user_id = session.user_id.as_deref().unwrap_or(&session.nickname);
+
+        crate::db::queries::messages::add_reaction(pool, message_id, user_id, emoji).await?;
+
+        let server_id = msg
+            .server_id
+            .ok_or_else(|| EngineError::NotFound("Message has no server".into()))?;
+        let channel_id = msg
+            .channel_id
+            .ok_or_else(|| EngineError::NotFound("Message has no channel".into()))?;
+
+        let channel_name = self
+            .channels
+            .get(&channel_id)
+            .map(|ch| ch.name.clone())
+            .unwrap_or_default();
+
+        let event = ChatEvent::ReactionAdd {
+            message_id: message_id.parse().unwrap_or_default(),
+            server_id,
+            channel: channel_name,
+            user_id: user_id.to_string(),
+            nickname: session.nickname.clone(),
+            emoji: emoji.to_string(),
+        };
+
+        self.broadcast_to_channel(&channel_id, &event, None);
+
+        Ok(())
+    }
+
+    /// Remove a reaction from a message.
+    pub async fn remove_reaction(
+        &self,
+        session_id: SessionId,
+        message_id: &str,
+        emoji: &str,
+    ) -> Result<(), EngineError> {
+        let session = self
+            .sessions
+            .get(&session_id)
+            .ok_or(EngineError::SessionNotFound)?
+            .clone();
+
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Message not found".into()))?;
+        if let Some(server_id) = msg.server_id.as_deref() {
+            self.check_not_quarantined(server_id)?;
+        }
+
+        let user_id = session.user_id.as_deref().unwrap_or(&session.nickname);
+
+        crate::db::queries::messages::remove_reaction(pool, message_id, user_id, emoji).await?;
+
+        let server_id = msg
+            .server_id
+            .ok_or_else(|| EngineError::NotFound("Message has no server".into()))?;
+        let channel_id = msg
+            .channel_id
+            .ok_or_else(|| EngineError::NotFound("Message has no channel".into()))?;
+
+        let channel_name = self
+            .channels
+            .get(&channel_id)
+            .map(|ch| ch.name.clone())
+            .unwrap_or_default();
+
+        let event = ChatEvent::ReactionRemove {
+            message_id: message_id.parse().unwrap_or_default(),
+            server_id,
+            channel: channel_name,
+            user_id: user_id.to_string(),
+            nickname: session.nickname.clone(),
+            emoji: emoji.to_string(),
+        };
+
+        self.broadcast_to_channel(&channel_id, &event, None);
+
+        Ok(())
+    }
+
+    // ── Typing indicators ────────────────────────────────────────────
+
+    /// Broadcast a typing indicator to a channel.
+    pub fn send_typing(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        channel_name: &str,
+    ) -> Result<(), EngineError> {
+        let channel_name = normalize_channel_name(channel_name);
+
+        let session = self
+            .sessions
+            .get(&session_id)
+            .ok_or(EngineError::SessionNotFound)?
+            .clone();
+
+        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+
+        let event = ChatEvent::TypingStart {
+            server_id: server_id.to_string(),
+            channel: channel_name,
+            nickname: session.nickname.clone(),
+        };
+
+        self.broadcast_to_channel(&channel_id, &event, Some(session_id));
+
+        Ok(())
+    }
+
+    // ── Read state ────────────────────────────────────────────────────
+
+    /// Mark a channel as read for a user, up to a specific message ID.
+    pub async fn mark_read(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        channel_name: &str,
+        message_id: &str,
+    ) -> Result<(), EngineError> {
+        let session = self
+            .sessions
+            .get(&session_id)
+            .ok_or(EngineError::SessionNotFound)?
+            .clone();
+
+        let user_id = session
+            .user_id
+            .as_deref()
+            .ok_or(EngineError::AuthRequired)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let channel_name = normalize_channel_name(channel_name);
+        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+
+        crate::db::queries::messages::mark_channel_read(pool, user_id, &channel_id, message_id)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Move a channel's read marker forward to a message, never back past one
+    /// the user has already read up to on another client.
+    pub async fn advance_read_marker(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        channel_name: &str,
+        message_id: &str,
+    ) -> Result<(), EngineError> {
+        let user_id = self.session_user_id(session_id)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        let channel_id =
+            self.resolve_channel_id(server_id, &normalize_channel_name(channel_name))?;
+
+        crate::db::queries::messages::advance_channel_read(pool, &user_id, &channel_id, message_id)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get unread counts for all channels in a server for a user.
+    pub async fn get_unread_counts(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+    ) -> Result<Vec<super::events::UnreadCount>, EngineError> {
+        let session = self
+            .sessions
+            .get(&session_id)
+            .ok_or(EngineError::SessionNotFound)?
+            .clone();
+
+        let user_id = session
+            .user_id
+            .as_deref()
+            .ok_or(EngineError::AuthRequired)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let rows =
+            crate::db::queries::messages::get_unread_counts(pool, user_id, server_id).await?;
+
+        // Map channel_id -> channel_name
+        Ok(rows
+            .into_iter()
+            .filter_map(|r| {
+                let name = self.channels.get(&r.channel_id).map(|ch| ch.name.clone())?;
+                Some(super::events::UnreadCount {
+                    channel_name: name,
+                    count: r.unread_count,
+                })
+            })
+            .collect())
+    }
+
+    /// Fetch the messages a session's user hasn't read in a channel, oldest first.
+    ///
+    /// "Unread" means newer than the user's read marker, or created at or after
+    /// `since` (a `YYYY-MM-DD HH:MM:SS` UTC timestamp) when there is no marker.
+    /// At most the `limit` most recent unread messages are returned.
+    pub async fn fetch_unread_messages(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        channel_name: &str,
+        since: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<HistoryMessage>, EngineError> {
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let user_id = session
+            .user_id
+            .as_deref()
+            .ok_or(EngineError::AuthRequired)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let channel_name = normalize_channel_name(channel_name);
+        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+
+        let rows = crate::db::queries::messages::fetch_messages_after_read_marker(
+            pool,
+            user_id,
+            &channel_id,
+            since,
+            limit,
+        )
+        .await
+        .map_err(|e| EngineError::Internal(format!("Failed to fetch unread messages: {e}")))?;
+
+        Ok(rows
+            .into_iter()
+            .rev()
+            .map(|row| HistoryMessage {
+                id: row.id.parse().unwrap_or_default(),
+                from: row.sender_nick,
+                content: row.content,
+                timestamp: chrono::NaiveDateTime::parse_from_str(
+                    &row.created_at,
+                    "%Y-%m-%d %H:%M:%S",
+                )
+                .map(|dt| dt.and_utc())
+                .unwrap_or_else(|_| Utc::now()),
+                edited_at: None,
+                reply_to: None,
+                reactions: None,
+                attachments: None,
+                embeds: None,
+            })
+            .collect())
+    }
+
+    // ── Roles ────────────────────────────────────────────────────────
+
+    /// Get effective permissions for a user in a channel.
+    ///
+    /// Roles, the user's role assignments and the channel's overrides come from
+    /// the server's `PermissionCache`, loaded from the database on a miss.
+    pub async fn get_effective_permissions(
+        &self,
+        server_id: &str,
+        channel_id: Option<&str>,
+        user_id: &str,
+    ) -> Permissions {
+        let is_owner = self.is_server_owner(server_id, user_id);
+        if is_owner {
+            return Permissions::all();
+        }
+
+        let Some(pool) = &self.db else {
+            return DEFAULT_EVERYONE;
+        };
+
+        let roles = match self.with_permission_cache(server_id, |c| c.roles()) {
+            Some(Some(roles)) => roles,
+            cached => {
+                let generation = self.permission_cache_generation(server_id);
+                let roles = Arc::new(load_cached_roles(pool, server_id).await);
+                if cached.is_some() {
+                    self.with_permission_cache_mut(server_id, |c| {
+                        c.store_roles(generation, roles.clone())
+                    });
+                }
+                roles
+            }
+        };
+
+        let member_roles = self.member_role_ids(pool, server_id, user_id).await;
+        let role_perms: Vec<(String, Permissions)> = member_roles
+            .iter()
+            .filter_map(|id| roles.permissions.get(id).map(|p| (id.clone(), *p)))
+            .collect();
+
+        let overrides = match channel_id {
+            None => Arc::new(Vec::new()),
+            Some(ch_id) => {
+                match self.with_permission_cache(server_id, |c| c.channel_overrides(ch_id)) {
+                    Some(Some(overrides)) => overrides,
+                    cached => {
+                        let generation = self.permission_cache_generation(server_id);
+                        let overrides = Arc::new(load_channel_overrides(pool, ch_id).await);
+                        if cached.is_some() {
+                            self.with_permission_cache_mut(server_id, |c| {
+                                c.store_channel_overrides(generation, ch_id, overrides.clone())
+                            });
+                        }
+                        overrides
+                    }
+                }
+            }
+        };
+
+        permissions::compute_effective_permissions(
+            roles.everyone,
+            &role_perms,
+            &overrides,
+            &roles.everyone_role_id,
+            user_id,
+            is_owner,
+        )
+    }
+
+    /// IDs of the roles assigned to a member, from the permission cache.
+    async fn member_role_ids(
+        &self,
+        pool: &SqlitePool,
+        server_id: &str,
+        user_id: &str,
+    ) -> Arc<Vec<String>> {
+        match self.with_permission_cache(server_id, |c| c.member_roles(user_id)) {
+            Some(Some(ids)) => ids,
+            cached => {
+                let generation = self.permission_cache_generation(server_id);
+                let ids: Arc<Vec<String>> = Arc::new(
+                    crate::db::queries::roles::get_user_roles(pool, server_id, user_id)
+                        .await
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|r| r.id)
+                        .collect(),
+                );
+                if cached.is_some() {
+                    self.with_permission_cache_mut(server_id, |c| {
+                        c.store_member_roles(generation, user_id, ids.clone())
+                    });
+                }
+                ids
+            }
+        }
+    }
+
+    /// `get_effective_permissions` straight from the database, bypassing the cache.
+    #[cfg(test)]
+    pub(crate) async fn get_effective_permissions_uncached(
+        &self,
+        server_id: &str,
+        channel_id: Option<&str>,
+        user_id: &str,
+    ) -> Permissions {
+        let is_owner = self.is_server_owner(server_id, user_id);
+        if is_owner {
+            return Permissions::all();
+        }
+        let Some(pool) = &self.db else {
+            return DEFAULT_EVERYONE;
+        };
+        let roles = load_cached_roles(pool, server_id).await;
+        let role_perms: Vec<(String, Permissions)> =
+            crate::db::queries::roles::get_user_roles(pool, server_id, user_id)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|r| (r.id, Permissions::from_bits_truncate(r.permissions as u64)))
+                .collect();
+        let overrides = match channel_id {
+            Some(ch_id) => load_channel_overrides(pool, ch_id).await,
+            None => vec![],
+        };
+        permissions::compute_effective_permissions(
+            roles.everyone,
+            &role_perms,
+            &overrides,
+            &roles.everyone_role_id,
+            user_id,
+            is_owner,
+        )
+    }
+
+    /// Read from a server's permission cache; `None` if the server isn't loaded.
+    fn with_permission_cache<T>(
+        &self,
+        server_id: &str,
+        f: impl FnOnce(&PermissionCache) -> T,
+    ) -> Option<T> {
+        self.servers.get(server_id).map(|s| f(&s.permission_cache))
+    }
+
+    fn with_permission_cache_mut(&self, server_id: &str, f: impl FnOnce(&mut PermissionCache)) {
+        if let Some(mut server) = self.servers.get_mut(server_id) {
+            f(&mut server.permission_cache);
+        }
+    }
+
+    fn permission_cache_generation(&self, server_id: &str) -> u64 {
+        self.with_permission_cache(server_id, |c| c.generation())
+            .unwrap_or_default()
+    }
+
+    /// Check that a user has a required permission for a change to the server.
+    /// Fails while the server is quarantined. Returns Ok(user_id) or Err(message).
+    pub async fn require_permission(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        channel_id: Option<&str>,
+        required: Permissions,
+    ) -> Result<String, EngineError> {
+        let user_id = self.session_user_id(session_id)?;
+        self.require_user_permission(&user_id, server_id, channel_id, required)
+            .await?;
+        Ok(user_id)
+    }
+
+    /// Like `require_permission`, for a user known by ID rather than by a
+    /// connected session (REST handlers, services).
+    pub async fn require_user_permission(
+        &self,
+        user_id: &str,
+        server_id: &str,
+        channel_id: Option<&str>,
+        required: Permissions,
+    ) -> Result<(), EngineError> {
+        self.require_user_read_permission(user_id, server_id, channel_id, required)
+            .await?;
+        self.check_not_quarantined(server_id)
+    }
+
+    /// Like `require_permission`, for reads, which a quarantined server still allows.
+    pub async fn require_read_permission(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        channel_id: Option<&str>,
+        required: Permissions,
+    ) -> Result<String, EngineError> {
+        let user_id = self.session_user_id(session_id)?;
+        self.require_user_read_permission(&user_id, server_id, channel_id, required)
+            .await?;
+        Ok(user_id)
+    }
+
+    /// Like `require_user_permission`, for reads.
+    pub async fn require_user_read_permission(
+        &self,
+        user_id: &str,
+        server_id: &str,
+        channel_id: Option<&str>,
+        required: Permissions,
+    ) -> Result<(), EngineError> {
+        let perms = self
+            .get_effective_permissions(server_id, channel_id, user_id)
+            .await;
+
+        if perms.contains(required) {
+            Ok(())
+        } else {
+            Err(EngineError::PermissionDenied { perm: required })
+        }
+    }
+
+    /// The account behind a session, failing for guests.
+    fn session_user_id(&self, session_id: SessionId) -> Result<String, EngineError> {
+        self.sessions
+            .get(&session_id)
+            .ok_or(EngineError::SessionNotFound)?
+            .user_id
+            .clone()
+            .ok_or(EngineError::AuthRequired)
+    }
+
+    /// List roles for a server.
+    pub async fn list_roles(&self, server_id: &str) -> Result<Vec<RoleInfo>, EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        let rows = crate::db::queries::roles::list_roles(pool, server_id).await?;
+        Ok(rows.into_iter().map(role_row_to_info).collect())
+    }
+
+    /// Create a custom role in a server.
+    pub async fn create_role(
+        &self,
+        server_id: &str,
+        name: &str,
+        color: Option<&str>,
+        permissions: i64,
+    ) -> Result<RoleInfo, EngineError> {
+        self.check_not_quarantined(server_id)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        // Determine position: max + 1
+        let existing = crate::db::queries::roles::list_roles(pool, server_id).await?;
+        let max_pos = existing.iter().map(|r| r.position).max().unwrap_or(0);
+
+        let role_id = Uuid::new_v4().to_string();
+        let params = crate::db::queries::roles::CreateRoleParams {
+            id: &role_id,
+            server_id,
+            name,
+            color,
+            icon_url: None,
+            position: max_pos + 1,
+            permissions,
+            is_default: false,
+        };
+        crate::db::queries::roles::create_role(pool, &params)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to create role: {e}")))?;
+        self.with_permission_cache_mut(server_id, PermissionCache::invalidate_roles);
+
+        let role = crate::db::queries::roles::get_role(pool, &role_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Role not found after creation".into()))?;
+
+        Ok(role_row_to_info(role))
+    }
+
+    /// Update a custom role.
+    pub async fn update_role(
+        &self,
+        server_id: &str,
+        role_id: &str,
+        name: &str,
+        color: Option<&str>,
+        permissions: i64,
+    ) -> Result<RoleInfo, EngineError> {
+        self.check_not_quarantined(server_id)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        // Verify the role belongs to the expected server (prevents cross-server manipulation)
+        let role = crate::db::queries::roles::get_role(pool, role_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Role not found".into()))?;
+        if role.server_id != server_id {
+            return Err(EngineError::NotFound(
+                "Role does not belong to this server".into(),
+            ));
+        }
+        crate::db::queries::roles::update_role(pool, role_id, name, color, None, permissions)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to update role: {e}")))?;
+        self.with_permission_cache_mut(server_id, PermissionCache::invalidate_roles);
+        let role = crate::db::queries::roles::get_role(pool, role_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Role not found".into()))?;
+        Ok(role_row_to_info(role))
+    }
+
+    /// Delete a custom role.
+    pub async fn delete_role(&self, server_id: &str, role_id: &str) -> Result<(), EngineError> {
+        self.check_not_quarantined(server_id)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        // Prevent deleting the @everyone default role
+        let role = crate::db::queries::roles::get_role(pool, role_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Role not found".into()))?;
+        if role.is_default != 0 {
+            return Err(EngineError::Forbidden(
+                "Cannot delete the default @everyone role".into(),
+            ));
+        }
+        // Verify the role belongs to the expected server (prevents cross-server manipulation)
+        if role.server_id != server_id {
+            return Err(EngineError::NotFound(
+                "Role does not belong to this server".into(),
+            ));
+        }
+        crate::db::queries::roles::delete_role(pool, role_id)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to delete role: {e}")))?;
+        self.with_permission_cache_mut(server_id, PermissionCache::invalidate_roles_and_members);
+        Ok(())
+    }
+
+    /// Get the highest role position for a user in a server.
+    /// Server owner gets i32::MAX. Returns 0 if no roles found (base @everyone level).
+    pub async fn get_user_highest_role_position(&self, server_id: &str, user_id: &str) -> i32 {
+        if self.is_server_owner(server_id, user_id) {
+            return i32::MAX;
+        }
+        let Some(pool) = &self.db else { return 0 };
+        crate::db::queries::roles::get_user_roles(pool, server_id, user_id)
+            .await
+            .unwrap_or_default()
+            .iter()
+            .map(|r| r.position)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Validate role hierarchy: actor must have a higher position than target role.
+    pub async fn check_role_hierarchy(
+        &self,
+        server_id: &str,
+        actor_user_id: &str,
+        target_role_id: &str,
+    ) -> Result<(), EngineError> {
+        // Server owner bypasses hierarchy checks
+        if self.is_server_owner(server_id, actor_user_id) {
+            return Ok(());
+        }
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        let target_role = crate::db::queries::roles::get_role(pool, target_role_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Role not found".into()))?;
+        let actor_highest = self
+            .get_user_highest_role_position(server_id, actor_user_id)
+            .await;
+        if actor_highest <= target_role.position {
+            return Err(EngineError::Forbidden(
+                "You cannot manage a role at or above your own highest role".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Assign a role to a user. Enforces role hierarchy.
+    pub async fn assign_role(
+        &self,
+        server_id: &str,
+        actor_user_id: &str,
+        target_user_id: &str,
+        role_id: &str,
+    ) -> Result<Vec<String>, EngineError> {
+        self.check_not_quarantined(server_id)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.check_role_hierarchy(server_id, actor_user_id, role_id)
+            .await?;
+        crate::db::queries::roles::assign_role(pool, server_id, target_user_id, role_id)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to assign role: {e}")))?;
+        self.with_permission_cache_mut(server_id, |c| c.invalidate_member(target_user_id));
+        let roles =
+            crate::db::queries::roles::get_user_roles(pool, server_id, target_user_id).await?;
+        Ok(roles.into_iter().map(|r| r.id).collect())
+    }
+
+    /// Remove a role from a user. Enforces role hierarchy.
+    pub async fn remove_role(
+        &self,
+        server_id: &str,
+        actor_user_id: &str,
+        target_user_id: &str,
+        role_id: &str,
+    ) -> Result<Vec<String>, EngineError> {
+        self.check_not_quarantined(server_id)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.check_role_hierarchy(server_id, actor_user_id, role_id)
+            .await?;
+        crate::db::queries::roles::remove_role(pool, server_id, target_user_id, role_id)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to remove role: {e}")))?;
+        self.with_permission_cache_mut(server_id, |c| c.invalidate_member(target_user_id));
+        let roles =
+            crate::db::queries::roles::get_user_roles(pool, server_id, target_user_id).await?;
+        Ok(roles.into_iter().map(|r| r.id).collect())
+    }
+
+    /// Set (upsert) a channel permission override for a role or user. Tests
+    /// only: nothing checks the caller's permissions.
+    #[cfg(test)]
+    pub(crate) async fn set_channel_override(
+        &self,
+        server_id: &str,
+        channel_name: &str,
+        target: &ChannelOverride,
+    ) -> Result<(), EngineError> {
+        self.check_not_quarantined(server_id)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        let channel_id =
+            self.resolve_channel_id(server_id, &normalize_channel_name(channel_name))?;
+        crate::db::queries::channels::set_channel_override(
+            pool,
+            &Uuid::new_v4().to_string(),
+            &channel_id,
+            override_target_type(&target.target_type),
+            &target.target_id,
+            target.allow.bits() as i64,
+            target.deny.bits() as i64,
+        )
+        .await
+        .map_err(|e| EngineError::Internal(format!("Failed to set channel override: {e}")))?;
+        self.with_permission_cache_mut(server_id, |c| c.invalidate_channel(&channel_id));
+        Ok(())
+    }
+
+    /// Remove a channel permission override. Tests only, like
+    /// `set_channel_override`.
+    #[cfg(test)]
+    pub(crate) async fn delete_channel_override(
+        &self,
+        server_id: &str,
+        channel_name: &str,
+        target_type: &OverrideTargetType,
+        target_id: &str,
+    ) -> Result<(), EngineError> {
+        self.check_not_quarantined(server_id)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        let channel_id =
+            self.resolve_channel_id(server_id, &normalize_channel_name(channel_name))?;
+        crate::db::queries::channels::delete_channel_override(
+            pool,
+            &channel_id,
+            override_target_type(target_type),
+            target_id,
+        )
+        .await
+        .map_err(|e| EngineError::Internal(format!("Failed to delete channel override: {e}")))?;
+        self.with_permission_cache_mut(server_id, |c| c.invalidate_channel(&channel_id));
+        Ok(())
+    }
+
+    // ── Categories ──────────────────────────────────────────────────
+
+    /// List categories for a server.
+    pub async fn list_categories(&self, server_id: &str) -> Result<Vec<CategoryInfo>, EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        let rows = crate::db::queries::categories::list_categories(pool, server_id).await?;
+        Ok(rows.into_iter().map(category_row_to_info).collect())
+    }
+
+    /// Create a channel category.
+    pub async fn create_category(
+        &self,
+        server_id: &str,
+        name: &str,
+    ) -> Result<CategoryInfo, EngineError> {
+        self.check_not_quarantined(server_id)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        let existing = crate::db::queries::categories::list_categories(pool, server_id).await?;
+        let max_pos = existing.iter().map(|c| c.position).max().unwrap_or(-1);
+
+        let cat_id = Uuid::new_v4().to_string();
+        crate::db::queries::categories::create_category(
+            pool,
+            &cat_id,
+            server_id,
+            name,
+            max_pos + 1,
+        )
+        .await
+        .map_err(|e| EngineError::Internal(format!("Failed to create category: {e}")))?;
+
+        Ok(CategoryInfo {
+            id: cat_id,
+            server_id: server_id.to_string(),
+            name: name.to_string(),
+            position: max_pos + 1,
+        })
+    }
+
+    /// Update a channel category name.
+    pub async fn update_category(
+        &self,
+        category_id: &str,
+        name: &str,
+    ) -> Result<CategoryInfo, EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        crate::db::queries::categories::update_category(pool, category_id, name)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to update category: {e}")))?;
+        let cat = crate::db::queries::categories::get_category(pool, category_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Category not found".into()))?;
+        Ok(category_row_to_info(cat))
+    }
+
+    /// Delete a channel category.
+    pub async fn delete_category(&self, category_id: &str) -> Result<(), EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        crate::db::queries::categories::delete_category(pool, category_id)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to delete category: {e}")))?;
+        // Channels referencing this category get NULL (ON DELETE SET NULL)
+        // Update in-memory state
+        for mut ch in self.channels.iter_mut() {
+            if ch.category_id.as_deref() == Some(category_id) {
+                ch.category_id = None;
+            }
+        }
+        Ok(())
+    }
+
+    // ── Channel organization ────────────────────────────────────────
+
+    /// Reorder channels: update position and category for a batch of channels.
+    pub async fn reorder_channels(
+        &self,
+        server_id: &str,
+        updates: &[ChannelPositionInfo],
+    ) -> Result<(), EngineError> {
+        self.check_not_quarantined(server_id)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        for update in updates {
+            crate::db::queries::channels::update_channel_position(
+                pool,
+                &update.id,
+                update.position,
+            )
+            .await
+            .map_err(|e| {
+                EngineError::Internal(format!("Failed to update channel position: {e}"))
+            })?;
+            crate::db::queries::channels::update_channel_category(
+                pool,
+                &update.id,
+                update.category_id.as_deref(),
+            )
+            .await
+            .map_err(|e| {
+                EngineError::Internal(format!("Failed to update channel category: {e}"))
+            })?;
+
+            // Update in-memory state
+            if let Some(mut ch) = self.channels.get_mut(&update.id) {
+                ch.position = update.position;
+                ch.category_id.clone_from(&update.category_id);
+            }
+        }
+        Ok(())
+    }
+
+    // ── Profiles ───────────────────────────────────────────────────
+
+    /// Get a user's full profile.
+    pub async fn get_user_profile(
+        &self,
+        user_id: &str,
+    ) -> Result<super::events::UserProfileInfo, EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        // Get basic user info
+        let (id, username, _email, avatar_url) = crate::db::queries::users::get_user(pool, user_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("User not found".into()))?;
+
+        // Get profile
+        let profile = crate::db::queries::profiles::get_profile(pool, &id).await?;
+
+        // Get user created_at
+        let created_at =
+            sqlx::query_scalar::<_, String>("SELECT created_at FROM users WHERE id = ?")
+                .bind(&id)
+                .fetch_one(pool)
+                .await
+                .unwrap_or_else(|_| "unknown".into());
+
+        Ok(super::events::UserProfileInfo {
+            user_id: id,
+            username,
+            avatar_url,
+            bio: profile.as_ref().and_then(|p| p.bio.clone()),
+            pronouns: profile.as_ref().and_then(|p| p.pronouns.clone()),
+            banner_url: profile.as_ref().and_then(|p| p.banner_url.clone()),
+            created_at,
+        })
+    }
+
+    // ── Utility ─────────────────────────────────────────────────────
+
+    /// Get a reference to the database pool (if configured).
+    pub fn db(&self) -> Option<&SqlitePool> {
+        self.db.as_ref()
+    }
+
+    /// Number of connected sessions using `protocol`.
+    pub fn session_count(&self, protocol: Protocol) -> usize {
+        self.sessions
+            .iter()
+            .filter(|s| s.protocol == protocol)
+            .count()
+    }
+
+    /// Check if a nickname is available, ignoring ASCII case.
+    pub fn is_nick_available(&self, nickname: &str) -> bool {
+        !self.nick_to_session.contains_key(&nick_key(nickname))
+    }
+
+    /// Look up a session ID by nickname, ignoring ASCII case. Returns None if
+    /// no session with that nick exists.
+    pub fn get_session_id_by_nick(&self, nickname: &str) -> Option<SessionId> {
+        self.nick_to_session.get(&nick_key(nickname)).map(|r| *r)
+    }
+
+    /// Get the user_id for a session. Returns None if session not found or has no user.
+    pub fn get_session_user_id(&self, session_id: SessionId) -> Option<String> {
+        self.sessions
+            .get(&session_id)
+            .and_then(|s| s.user_id.clone())
+    }
+
+    /// Get (server_id, channel_name) pairs for all channels a session is in.
+    pub fn get_session_channels(&self, session_id: SessionId) -> Vec<(String, String)> {
+        if !self.sessions.contains_key(&session_id) {
+            return vec![];
+        }
+        self.channels
+            .iter()
+            .filter(|ch| ch.members.contains(&session_id))
+            .map(|ch| (ch.server_id.clone(), ch.name.clone()))
+            .collect()
+    }
+
+    /// Get a session by ID.
+    pub fn get_session(&self, session_id: SessionId) -> Option<Arc<UserSession>> {
+        self.sessions.get(&session_id).map(|s| s.clone())
+    }
+
+    /// Record the hostname and address a session's client connected from.
+    pub fn set_client_host(&self, session_id: SessionId, host: (String, IpAddr)) {
+        if let Some(mut session) = self.sessions.get_mut(&session_id) {
+            let mut updated = UserSession::clone(&session);
+            updated.client_host = Some(host);
+            *session = Arc::new(updated);
+        }
+    }
+
+    /// Get the database pool (if configured).
+    pub fn get_db(&self) -> Option<SqlitePool> {
+        self.db.clone()
+    }
+
+    /// Resolve a channel name within a server to its channel ID.
+    pub fn resolve_channel_id(
+        &self,
+        server_id: &str,
+        channel_name: &str,
+    ) -> Result<String, EngineError> {
+        self.channel_name_index
+            .get(&(server_id.to_string(), channel_name.to_string()))
+            .map(|r| r.clone())
+            .ok_or_else(|| EngineError::NoSuchChannel(channel_name.to_string()))
+    }
+
+    /// Broadcast an event to all members of a channel, optionally excluding one session.
+    fn broadcast_to_channel(
+        &self,
+        channel_id: &str,
+        event: &ChatEvent,
+        exclude: Option<SessionId>,
+    ) {
+        let Some(channel) = self.channels.get(channel_id) else {
+            return;
+        };
+
+        for member_id in &channel.members {
+            if Some(*member_id) == exclude {
+                continue;
+            }
+            if let Some(session) = self.sessions.get(member_id)
+                && !session.send(event.clone())
+            {
+                warn!(%member_id, "failed to send event to session (channel closed)");
+            }
+        }
+    }
+
+    // ── Presence ─────────────────────────────────────────────
+
+    /// Update a user's presence and broadcast to members of shared servers.
+    pub async fn set_presence(
+        &self,
+        session_id: SessionId,
+        status: &str,
+        custom_status: Option<&str>,
+        status_emoji: Option<&str>,
+    ) -> Result<(), EngineError> {
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let user_id = session.user_id.clone().ok_or(EngineError::AuthRequired)?;
+
+        // Validate status
+        match status {
+            "online" | "idle" | "dnd" | "invisible" => {}
+            _ => {
+                return Err(EngineError::Validation(
+                    "Invalid status. Must be: online, idle, dnd, invisible".into(),
+                ));
+            }
+        }
+
+        // Persist to DB
+        if let Some(pool) = &self.db {
+            crate::db::queries::presence::upsert_presence(
+                pool,
+                &user_id,
+                status,
+                custom_status,
+                status_emoji,
+            )
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to update presence: {e}")))?;
+        }
+
+        // Invisible users look offline to anyone monitoring their nicknames
+        let now_invisible = status == "invisible";
+        let was_invisible = if now_invisible {
+            !self.invisible_users.insert(user_id.clone())
+        } else {
+            self.invisible_users.remove(&user_id).is_some()
+        };
+        if now_invisible != was_invisible {
+            let nicks: Vec<String> = self
+                .sessions
+                .iter()
+                .filter(|s| s.user_id.as_deref() == Some(user_id.as_str()))
+                .map(|s| s.nickname.clone())
+                .collect();
+            for nick in nicks {
+                self.notify_monitors(&nick, !now_invisible);
+            }
+        }
+
+        // Build presence info
+        let presence = super::events::PresenceInfo {
+            user_id: user_id.clone(),
+            nickname: session.nickname.clone(),
+            avatar_url: session.avatar_url.clone(),
+            status: if status == "invisible" {
+                "offline".into()
+            } else {
+                status.into()
+            },
+            custom_status: custom_status.map(|s| s.to_string()),
+            status_emoji: status_emoji.map(|s| s.to_string()),
+        };
+
+        // Broadcast to all servers the user is a member of
+        for server in self.servers.iter() {
+            if server.member_user_ids.contains(&user_id) {
+                let event = ChatEvent::PresenceUpdate {
+                    server_id: server.id.clone(),
+                    presence: presence.clone(),
+                };
+                // Send to all sessions in this server's channels, deduplicated per server
+                let mut notified = std::collections::HashSet::new();
+                for channel_id in server.channel_ids.iter() {
+                    if let Some(channel) = self.channels.get(channel_id) {
+                        for &member_sid in &channel.members {
+                            if member_sid != session_id
+                                && notified.insert(member_sid)
+                                && let Some(s) = self.sessions.get(&member_sid)
+                            {
+                                let _ = s.send(event.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get presence list for all members of a server.
+    pub async fn get_server_presences(
+        &self,
+        server_id: &str,
+    ) -> Result<Vec<super::events::PresenceInfo>, EngineError> {
+        let server = self
+            .servers
+            .get(server_id)
+            .ok_or_else(|| EngineError::NotFound(format!("Server not found: {server_id}")))?;
+
+        let user_ids: Vec<String> = server.member_user_ids.iter().cloned().collect();
+        drop(server);
+
+        let mut presences = Vec::new();
+
+        // Get DB presence records
+        if let Some(pool) = &self.db {
+            let rows = crate::db::queries::presence::get_presences_for_users(pool, &user_ids)
+                .await
+                .unwrap_or_default();
+
+            for row in rows {
+                // Find nickname for this user from active sessions
+                let (nickname, avatar_url) = self.find_user_display_info(&row.user_id);
+                let visible_status = if row.status == "invisible" {
+                    "offline".to_string()
+                } else {
+                    row.status
+                };
+                presences.push(super::events::PresenceInfo {
+                    user_id: row.user_id,
+                    nickname,
+                    avatar_url,
+                    status: visible_status,
+                    custom_status: row.custom_status,
+                    status_emoji: row.status_emoji,
+                });
+            }
+        }
+
+        // Add online users who may not have a presence row yet
+        for uid in &user_ids {
+            if !presences.iter().any(|p| p.user_id == *uid) {
+                let (nickname, avatar_url) = self.find_user_display_info(uid);
+                let is_online = self
+                    .sessions
+                    .iter()
+                    .any(|s| s.user_id.as_deref() == Some(uid));
+                presences.push(super::events::PresenceInfo {
+                    user_id: uid.clone(),
+                    nickname,
+                    avatar_url,
+                    status: if is_online {
+                        "online".into()
+                    } else {
+                        "offline".into()
+                    },
+                    custom_status: None,
+                    status_emoji: None,
+                });
+            }
+        }
+
+        Ok(presences)
+    }
+
+    /// Find a user's display info (nickname, avatar) from active sessions or return defaults.
+    fn find_user_display_info(&self, user_id: &str) -> (String, Option<String>) {
+        for session in self.sessions.iter() {
+            if session.user_id.as_deref() == Some(user_id) {
+                return (session.nickname.clone(), session.avatar_url.clone());
+            }
+        }
+        (format!("user-{}", &user_id[..8.min(user_id.len())]), None)
+    }
+
+    // ── Monitoring ───────────────────────────────────────────
+
+    /// Add nicknames to a session's monitor list, up to `MONITOR_LIMIT` entries.
+    /// Returns the current status of each added nickname (using its online
+    /// spelling when connected) and the nicknames that didn't fit.
+    pub fn monitor_add(
+        &self,
+        session_id: SessionId,
+        nicknames: &[String],
+    ) -> (Vec<(String, bool)>, Vec<String>) {
+        let mut statuses = Vec::new();
+        let mut rejected = Vec::new();
+        let mut list = self.monitors.entry(session_id).or_default();
+        for nickname in nicknames {
+            let key = nickname.to_ascii_lowercase();
+            if !list.iter().any(|n| n.to_ascii_lowercase() == key) {
+                if list.len() >= MONITOR_LIMIT {
+                    rejected.push(nickname.clone());
+                    continue;
+                }
+                list.push(nickname.clone());
+                self.monitor_watchers
+                    .entry(key)
+                    .or_default()
+                    .insert(session_id);
+            }
+            statuses.push(match self.visible_nick(nickname) {
+                Some(online) => (online, true),
+                None => (nickname.clone(), false),
+            });
+        }
+        (statuses, rejected)
+    }
+
+    /// Remove nicknames from a session's monitor list.
+    pub fn monitor_remove(&self, session_id: SessionId, nicknames: &[String]) {
+        let Some(mut list) = self.monitors.get_mut(&session_id) else {
+            return;
+        };
+        for nickname in nicknames {
+            let key = nickname.to_ascii_lowercase();
+            list.retain(|n| n.to_ascii_lowercase() != key);
+            self.unwatch(&key, session_id);
+        }
+    }
+
+    /// Empty a session's monitor list.
+    pub fn monitor_clear(&self, session_id: SessionId) {
+        if let Some((_, list)) = self.monitors.remove(&session_id) {
+            for nickname in list {
+                self.unwatch(&nickname.to_ascii_lowercase(), session_id);
+            }
+        }
+    }
+
+    /// The nicknames on a session's monitor list.
+    pub fn monitor_list(&self, session_id: SessionId) -> Vec<String> {
+        self.monitors
+            .get(&session_id)
+            .map(|list| list.clone())
+            .unwrap_or_default()
+    }
+
+    /// Number of sessions monitoring a nickname.
+    pub fn monitor_watcher_count(&self, nickname: &str) -> usize {
+        self.monitor_watchers
+            .get(&nickname.to_ascii_lowercase())
+            .map_or(0, |w| w.len())
+    }
+
+    /// The connected spelling of a nickname, if it is online and not invisible.
+    pub fn visible_nick(&self, nickname: &str) -> Option<String> {
+        let session_id = self.get_session_id_by_nick(nickname)?;
+        let session = self.get_session(session_id)?;
+        (!self.is_invisible(&session)).then(|| session.nickname.clone())
+    }
+
+    fn is_invisible(&self, session: &UserSession) -> bool {
+        session
+            .user_id
+            .as_ref()
+            .is_some_and(|uid| self.invisible_users.contains(uid))
+    }
+
+    fn unwatch(&self, key: &str, session_id: SessionId) {
+        if let Some(mut watchers) = self.monitor_watchers.get_mut(key) {
+            watchers.remove(&session_id);
+        }
+        self.monitor_watchers.remove_if(key, |_, w| w.is_empty());
+    }
+
+    /// Tell every session monitoring `nickname` that it came online or went offline.
+    fn notify_monitors(&self, nickname: &str, online: bool) {
+        let Some(watchers) = self
+            .monitor_watchers
+            .get(&nickname.to_ascii_lowercase())
+            .map(|w| w.clone())
+        else {
+            return;
+        };
+        let event = ChatEvent::MonitorStatus {
+            nickname: nickname.to_string(),
+            online,
+        };
+        for sid in watchers {
+            if let Some(s) = self.sessions.get(&sid) {
+                let _ = s.send(event.clone());
+            }
+        }
+    }
+
+    // ── Server Nicknames ─────────────────────────────────────
+
+    /// Set a user's server-specific display name.
+    pub async fn set_server_nickname(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        nickname: Option<&str>,
+    ) -> Result<(), EngineError> {
+        self.check_not_quarantined(server_id)?;
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let user_id = session.user_id.clone().ok_or(EngineError::AuthRequired)?;
+
+        // Verify membership
+        let server = self
+            .servers
+            .get(server_id)
+            .ok_or_else(|| EngineError::NotFound(format!("Server not found: {server_id}")))?;
+        if !server.member_user_ids.contains(&user_id) {
+            return Err(EngineError::Forbidden("Not a member of this server".into()));
+        }
+        drop(server);
+
+        if let Some(pool) = &self.db {
+            crate::db::queries::servers::set_server_nickname(pool, server_id, &user_id, nickname)
+                .await
+                .map_err(|e| EngineError::Internal(format!("Failed to set nickname: {e}")))?;
+        }
+
+        // Broadcast nickname change
+        let event = ChatEvent::ServerNicknameUpdate {
+            server_id: server_id.to_string(),
+            user_id: user_id.clone(),
+            nickname: nickname.map(|s| s.to_string()),
+        };
+        if let Some(server) = self.servers.get(server_id) {
+            for channel_id in server.channel_ids.iter() {
+                self.broadcast_to_channel(channel_id, &event, None);
+            }
+        }
+
+        Ok(())
+    }
+
+    // ── Search ───────────────────────────────────────────────
+
+    /// Search messages in a server using full-text search.
+    pub async fn search_messages(
+        &self,
+        server_id: &str,
+        query: &str,
+        channel_name: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<super::events::SearchResultMessage>, i64), EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        // Resolve channel name to ID if provided (normalize for case-insensitive lookup)
+        let channel_id = if let Some(ch_name) = channel_name {
+            let ch_name = normalize_channel_name(ch_name);
+            Some(self.resolve_channel_id(server_id, &ch_name)?)
+        } else {
+            None
+        };
+
+        let (rows, total) = crate::db::queries::search::search_messages(
+            pool,
+            server_id,
+            query,
+            channel_id.as_deref(),
+            limit.min(50),
+            offset,
+        )
+        .await
+        .map_err(|e| EngineError::Internal(format!("Search failed: {e}")))?;
+
+        let results: Vec<super::events::SearchResultMessage> = rows
+            .into_iter()
+            .filter_map(|row| {
+                let channel_name = row
+                    .channel_id
                     .as_ref()
-                    .and_then(|rid| reply_map.get(rid).cloned());
-                let edited_at = row.edited_at.as_ref().and_then(|s| s.parse().ok());
-                let attachments = attachment_map.remove(&row.id);
+                    .and_then(|cid| self.channels.get(cid).map(|ch| ch.name.clone()))
+                    .unwrap_or_default();
 
-                HistoryMessage {
-                    id: row.id.parse().unwrap_or_default(),
+                Some(super::events::SearchResultMessage {
+                    id: row.id.parse().ok()?,
                     from: row.sender_nick,
                     content: row.content,
-                    timestamp: row.created_at.parse().unwrap_or_else(|_| Utc::now()),
-                    edited_at,
-                    reply_to,
-                    reactions,
-                    attachments,
-                    embeds: None,
-                }
+                    timestamp: row.created_at.parse().ok()?,
+                    channel_id: row.channel_id.unwrap_or_default(),
+                    channel_name,
+                    edited_at: row.edited_at.as_ref().and_then(|s| s.parse().ok()),
+                })
             })
             .collect();
 
-        Ok((messages, has_more))
+        Ok((results, total))
+    }
+
+    // ── Notification Settings ────────────────────────────────
+
+    /// Update notification settings for a user in a server or channel.
+    pub async fn update_notification_settings(
+        &self,
+        session_id: SessionId,
+        params: &UpdateNotificationSettingsParams<'_>,
+    ) -> Result<(), EngineError> {
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let user_id = session.user_id.clone().ok_or(EngineError::AuthRequired)?;
+
+        match params.level {
+            "all" | "mentions" | "none" | "default" => {}
+            _ => {
+                return Err(EngineError::Validation(
+                    "Invalid level. Must be: all, mentions, none, default".into(),
+                ));
+            }
+        }
+
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        let id = Uuid::new_v4().to_string();
+
+        let db_params = crate::db::models::UpsertNotificationParams {
+            id: &id,
+            user_id: &user_id,
+            server_id: Some(params.server_id),
+            channel_id: params.channel_id,
+            level: params.level,
+            suppress_everyone: params.suppress_everyone,
+            suppress_roles: params.suppress_roles,
+            muted: params.muted,
+            mute_until: params.mute_until,
+        };
+        crate::db::queries::notifications::upsert_notification_setting(pool, &db_params)
+            .await
+            .map_err(|e| {
+                EngineError::Internal(format!("Failed to update notification settings: {e}"))
+            })?;
+
+        Ok(())
+    }
+
+    /// Get notification settings for a user in a server.
+    pub async fn get_notification_settings(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+    ) -> Result<Vec<super::events::NotificationSettingInfo>, EngineError> {
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let user_id = session.user_id.clone().ok_or(EngineError::AuthRequired)?;
+
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let rows =
+            crate::db::queries::notifications::get_notification_settings(pool, &user_id, server_id)
+                .await
+                .map_err(|e| {
+                    EngineError::Internal(format!("Failed to get notification settings: {e}"))
+                })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| super::events::NotificationSettingInfo {
+                id: r.id,
+                server_id: r.server_id,
+                channel_id: r.channel_id,
+                level: r.level,
+                suppress_everyone: r.suppress_everyone != 0,
+                suppress_roles: r.suppress_roles != 0,
+                muted: r.muted != 0,
+                mute_until: r.mute_until,
+            })
+            .collect())
+    }
+
+    // ── Pinning ─────────────────────────────────────────────────
+
+    /// Pin a message in a channel. Requires MANAGE_MESSAGES permission or ownership of the message.
+    pub async fn pin_message(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        channel_name: &str,
+        message_id: &str,
+    ) -> Result<(), EngineError> {
+        self.check_not_quarantined(server_id)?;
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let channel_name = normalize_channel_name(channel_name);
+        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+
+        // Look up the message
+        let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Message not found".into()))?;
+
+        // Check permission: MANAGE_MESSAGES or own message
+        let user_id = session
+            .user_id
+            .as_deref()
+            .ok_or(EngineError::AuthRequired)?;
+        let is_own = msg.sender_id == user_id || msg.sender_nick == session.nickname;
+        if !is_own {
+            let perms = self
+                .get_effective_permissions(server_id, Some(&channel_id), user_id)
+                .await;
+            if !perms.contains(Permissions::MANAGE_MESSAGES) {
+                return Err(EngineError::PermissionDenied {
+                    perm: Permissions::MANAGE_MESSAGES,
+                });
+            }
+        }
+
+        // Check pin count limit (max 50 per channel)
+        let pin_count = crate::db::queries::pins::count_pins(pool, &channel_id).await?;
+        if pin_count >= 50 {
+            return Err(EngineError::Conflict(
+                "Channel has reached the maximum of 50 pinned messages".into(),
+            ));
+        }
+
+        let pin_id = Uuid::new_v4().to_string();
+        crate::db::queries::pins::pin_message(pool, &pin_id, &channel_id, message_id, user_id)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to pin message: {e}")))?;
+
+        let pin = PinnedMessageInfo {
+            id: pin_id,
+            message_id: message_id.to_string(),
+            channel_id: channel_id.clone(),
+            pinned_by: user_id.to_string(),
+            pinned_at: Utc::now().to_rfc3339(),
+            from: msg.sender_nick,
+            content: msg.content,
+            timestamp: msg.created_at,
+        };
+
+        let event = ChatEvent::MessagePin {
+            server_id: server_id.to_string(),
+            channel: channel_name,
+            pin,
+        };
+        self.broadcast_to_channel(&channel_id, &event, None);
+
+        Ok(())
+    }
+
+    /// Unpin a message from a channel. Requires MANAGE_MESSAGES permission.
+    pub async fn unpin_message(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        channel_name: &str,
+        message_id: &str,
+    ) -> Result<(), EngineError> {
+        let channel_name = normalize_channel_name(channel_name);
+        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+
+        self.require_permission(
+            session_id,
+            server_id,
+            Some(&channel_id),
+            Permissions::MANAGE_MESSAGES,
+        )
+        .await?;
+
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        crate::db::queries::pins::unpin_message(pool, &channel_id, message_id)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to unpin message: {e}")))?;
+
+        let event = ChatEvent::MessageUnpin {
+            server_id: server_id.to_string(),
+            channel: channel_name,
+            message_id: message_id.to_string(),
+        };
+        self.broadcast_to_channel(&channel_id, &event, None);
+
+        Ok(())
     }
 
-    /// List all channels in a server.
-    pub fn list_channels(&self, server_id: &str) -> Vec<ChannelInfo> {
-        self.channels
-            .iter()
-            .filter(|ch| ch.server_id == server_id)
-            .map(|entry| ChannelInfo {
-                id: entry.id.clone(),
-                server_id: entry.server_id.clone(),
-                name: entry.name.clone(),
-                topic: entry.topic.clone(),
-                member_count: entry.member_count(),
-                category_id: entry.category_id.clone(),
-                position: entry.position,
-                is_private: entry.is_private,
-                channel_type: entry.channel_type.clone(),
-                thread_parent_message_id: entry.thread_parent_message_id.clone(),
-                archived: entry.archived,
-            })
-            .collect()
+    /// Get all pinned messages in a channel. Sends PinnedMessages event to the requesting session.
+    pub async fn get_pinned_messages(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        channel_name: &str,
+    ) -> Result<(), EngineError> {
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let pins = self
+            .fetch_pinned_messages(session_id, server_id, channel_name)
+            .await?;
+
+        let _ = session.send(ChatEvent::PinnedMessages {
+            server_id: server_id.to_string(),
+            channel: normalize_channel_name(channel_name),
+            pins,
+        });
+
+        Ok(())
     }
 
-    /// Get members of a channel.
-    pub fn get_members(
+    /// Fetch the pinned messages in a channel, with message content denormalized.
+    /// The session must be a server member with VIEW_CHANNELS on the channel.
+    pub async fn fetch_pinned_messages(
         &self,
+        session_id: SessionId,
         server_id: &str,
         channel_name: &str,
-    ) -> Result<Vec<MemberInfo>, String> {
+    ) -> Result<Vec<PinnedMessageInfo>, EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
         let channel_name = normalize_channel_name(channel_name);
         let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
 
-        let channel = self
-            .channels
-            .get(&channel_id)
-            .ok_or(format!("No such channel: {channel_name}"))?;
+        let user_id = self.session_user_id(session_id)?;
+        if !self.user_is_server_member(server_id, &user_id) {
+            return Err(EngineError::Forbidden("Not a member of this server".into()));
+        }
+        self.require_user_read_permission(
+            &user_id,
+            server_id,
+            Some(&channel_id),
+            Permissions::VIEW_CHANNELS,
+        )
+        .await?;
 
-        Ok(channel
-            .members
-            .iter()
-            .filter_map(|sid| {
-                self.sessions.get(sid).map(|s| MemberInfo {
-                    nickname: s.nickname.clone(),
-                    avatar_url: s.avatar_url.clone(),
-                    server_avatar_url: None,
-                    status: None,
-                    custom_status: None,
-                    status_emoji: None,
-                    user_id: s.user_id.clone(),
-                })
-            })
-            .collect())
+        let pin_rows = crate::db::queries::pins::get_pinned_messages(pool, &channel_id).await?;
+
+        let mut pins = Vec::new();
+        for row in pin_rows {
+            // Look up message content for each pin
+            let (from, content, timestamp) = match crate::db::queries::messages::get_message_by_id(
+                pool,
+                &row.message_id,
+            )
+            .await
+            {
+                Ok(Some(msg)) => (msg.sender_nick, msg.content, msg.created_at),
+                _ => (
+                    "unknown".to_string(),
+                    "[deleted]".to_string(),
+                    String::new(),
+                ),
+            };
+
+            pins.push(PinnedMessageInfo {
+                id: row.id,
+                message_id: row.message_id,
+                channel_id: row.channel_id,
+                pinned_by: row.pinned_by,
+                pinned_at: row.pinned_at,
+                from,
+                content,
+                timestamp,
+            });
+        }
+
+        Ok(pins)
     }
 
-    // ── Message editing & deletion ─────────────────────────────────
+    // ── Threads ─────────────────────────────────────────────────
 
-    /// Edit a message's content. Only the sender or a moderator+ can edit.
-    pub async fn edit_message(
+    /// Create a thread from a message in a channel.
+    pub async fn create_thread(
         &self,
         session_id: SessionId,
+        server_id: &str,
+        parent_channel_name: &str,
+        name: &str,
         message_id: &str,
-        new_content: &str,
-    ) -> Result<(), String> {
-        validation::validate_message_with_limit(new_content, self.max_message_length)?;
-        let new_content = &validation::sanitize_html(new_content);
-
+        is_private: bool,
+    ) -> Result<(), EngineError> {
         let session = self
-            .sessions
-            .get(&session_id)
-            .ok_or("Session not found")?
-            .clone();
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let _user_id = session
+            .user_id
+            .as_deref()
+            .ok_or(EngineError::AuthRequired)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
 
-        let pool = self.db.as_ref().ok_or("No database configured")?;
+        self.check_not_quarantined(server_id)?;
+        let parent_channel_name = normalize_channel_name(parent_channel_name);
+        let parent_channel_id = self.resolve_channel_id(server_id, &parent_channel_name)?;
 
-        let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Message not found")?;
+        // Validate thread name
+        if name.is_empty() || name.len() > 100 {
+            return Err(EngineError::Validation(
+                "Thread name must be between 1 and 100 characters".into(),
+            ));
+        }
 
-        // Only the sender can edit their own messages, unless user has MANAGE_MESSAGES
-        let sender_id = session
-            .user_id
-            .as_deref()
-            .ok_or("Authentication required to edit messages")?;
-        if msg.sender_id != sender_id {
-            let server_id = msg.server_id.as_deref().ok_or("Message has no server")?;
-            let channel_id = msg.channel_id.as_deref().ok_or("Message has no channel")?;
-            let perms = self
-                .get_effective_permissions(server_id, Some(channel_id), sender_id)
-                .await;
-            if !perms.contains(Permissions::MANAGE_MESSAGES) {
-                return Err("You can only edit your own messages".into());
-            }
+        let channel_type = if is_private {
+            "private_thread"
+        } else {
+            "public_thread"
+        };
+
+        let thread_id = Uuid::new_v4().to_string();
+        let thread_name = normalize_channel_name(name);
+
+        // Check name uniqueness within server
+        if self
+            .channel_name_index
+            .contains_key(&(server_id.to_string(), thread_name.clone()))
+        {
+            return Err(EngineError::Conflict(format!(
+                "A channel or thread named {thread_name} already exists"
+            )));
         }
 
-        crate::db::queries::messages::update_message_content(pool, message_id, new_content)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
+        crate::db::queries::threads::create_thread(
+            pool,
+            &thread_id,
+            server_id,
+            &thread_name,
+            channel_type,
+            message_id,
+            1440, // default auto-archive: 24h
+        )
+        .await
+        .map_err(|e| EngineError::Internal(format!("Failed to create thread: {e}")))?;
 
-        let server_id = msg.server_id.ok_or("Message has no server")?;
-        let channel_id = msg.channel_id.ok_or("Message has no channel")?;
+        // Add to in-memory state
+        let mut ch = ChannelState::new(
+            thread_id.clone(),
+            server_id.to_string(),
+            thread_name.clone(),
+        );
+        ch.channel_type = channel_type.to_string();
+        ch.thread_parent_message_id = Some(message_id.to_string());
+        ch.auto_archive_minutes = 1440;
+        ch.is_private = is_private;
 
-        // Find the channel name for the event
-        let channel_name = self
-            .channels
-            .get(&channel_id)
-            .map(|ch| ch.name.clone())
-            .unwrap_or_default();
+        self.channel_name_index.insert(
+            (server_id.to_string(), thread_name.clone()),
+            thread_id.clone(),
+        );
+        if let Some(mut srv) = self.servers.get_mut(server_id) {
+            srv.channel_ids.insert(thread_id.clone());
+        }
+        self.channels.insert(thread_id.clone(), ch);
 
-        let event = ChatEvent::MessageEdit {
-            id: message_id.parse().unwrap_or_default(),
-            server_id: server_id.clone(),
-            channel: channel_name,
-            content: new_content.to_string(),
-            edited_at: Utc::now(),
+        let thread_info = ThreadInfo {
+            id: thread_id,
+            name: thread_name,
+            channel_type: channel_type.to_string(),
+            parent_message_id: Some(message_id.to_string()),
+            archived: false,
+            auto_archive_minutes: 1440,
+            message_count: 0,
+            created_at: Utc::now().to_rfc3339(),
         };
 
-        // Broadcast to the channel (including sender)
-        self.broadcast_to_channel(&channel_id, &event, None);
+        let event = ChatEvent::ThreadCreate {
+            server_id: server_id.to_string(),
+            parent_channel: parent_channel_name,
+            thread: thread_info,
+        };
+        self.broadcast_to_channel(&parent_channel_id, &event, None);
 
         Ok(())
     }
 
-    /// Delete a message (soft delete). Sender can delete own, moderator+ can delete any.
-    pub async fn delete_message(
+    /// Archive a thread. Requires MANAGE_CHANNELS permission.
+    pub async fn archive_thread(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        thread_id: &str,
+    ) -> Result<(), EngineError> {
+        self.require_permission(
+            session_id,
+            server_id,
+            Some(thread_id),
+            Permissions::MANAGE_CHANNELS,
+        )
+        .await?;
+
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        crate::db::queries::threads::archive_thread(pool, thread_id)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to archive thread: {e}")))?;
+
+        // Update in-memory state
+        let thread_info = if let Some(mut ch) = self.channels.get_mut(thread_id) {
+            ch.archived = true;
+            ThreadInfo {
+                id: ch.id.clone(),
+                name: ch.name.clone(),
+                channel_type: ch.channel_type.clone(),
+                parent_message_id: ch.thread_parent_message_id.clone(),
+                archived: true,
+                auto_archive_minutes: ch.auto_archive_minutes,
+                message_count: 0, // not tracked in-memory
+                created_at: ch.created_at.to_rfc3339(),
+            }
+        } else {
+            return Err(EngineError::NotFound("Thread not found".into()));
+        };
+
+        let event = ChatEvent::ThreadUpdate {
+            server_id: server_id.to_string(),
+            thread: thread_info,
+        };
+        self.broadcast_to_channel(thread_id, &event, None);
+
+        Ok(())
+    }
+
+    /// List threads for a channel. Sends ThreadList event to the requesting session.
+    pub async fn list_threads(
         &self,
         session_id: SessionId,
-        message_id: &str,
-    ) -> Result<(), String> {
+        server_id: &str,
+        channel_name: &str,
+    ) -> Result<(), EngineError> {
         let session = self
-            .sessions
-            .get(&session_id)
-            .ok_or("Session not found")?
-            .clone();
-
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-
-        let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Message not found")?;
-
-        let sender_id = session
-            .user_id
-            .as_deref()
-            .ok_or("Authentication required to delete messages")?;
-        let is_sender = msg.sender_id == sender_id;
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
 
-        if !is_sender {
-            // Check if user has MANAGE_MESSAGES permission
-            let server_id = msg.server_id.as_deref().ok_or("Message has no server")?;
-            let channel_id_ref = msg.channel_id.as_deref().ok_or("Message has no channel")?;
-            let perms = self
-                .get_effective_permissions(server_id, Some(channel_id_ref), sender_id)
-                .await;
-            if !perms.contains(Permissions::MANAGE_MESSAGES) {
-                return Err("You can only delete your own messages".into());
-            }
-        }
-
-        crate::db::queries::messages::soft_delete_message(pool, message_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
+        let channel_name = normalize_channel_name(channel_name);
+        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
 
-        let server_id = msg.server_id.ok_or("Message has no server")?;
-        let channel_id = msg.channel_id.ok_or("Message has no channel")?;
+        let rows =
+            crate::db::queries::threads::get_threads_for_channel(pool, &channel_id, server_id)
+                .await?;
 
-        let channel_name = self
-            .channels
-            .get(&channel_id)
-            .map(|ch| ch.name.clone())
-            .unwrap_or_default();
+        let threads: Vec<ThreadInfo> = rows
+            .into_iter()
+            .map(|row| ThreadInfo {
+                id: row.id,
+                name: row.name,
+                channel_type: row.channel_type,
+                parent_message_id: row.thread_parent_message_id,
+                archived: row.archived != 0,
+                auto_archive_minutes: row.thread_auto_archive_minutes,
+                message_count: 0, // would need a count query; returning 0 for now
+                created_at: row.created_at,
+            })
+            .collect();
 
-        let event = ChatEvent::MessageDelete {
-            id: message_id.parse().unwrap_or_default(),
-            server_id,
+        let _ = session.send(ChatEvent::ThreadList {
+            server_id: server_id.to_string(),
             channel: channel_name,
-        };
-
-        self.broadcast_to_channel(&channel_id, &event, None);
+            threads,
+        });
 
         Ok(())
     }
 
-    // ── Reactions ────────────────────────────────────────────────────
+    // ── Bookmarks ───────────────────────────────────────────────
 
-    /// Add a reaction to a message.
-    pub async fn add_reaction(
+    /// Add a bookmark on a message for the authenticated user.
+    pub async fn add_bookmark(
         &self,
         session_id: SessionId,
         message_id: &str,
-        emoji: &str,
-    ) -> Result<(), String> {
+        note: Option<&str>,
+    ) -> Result<(), EngineError> {
         let session = self
-            .sessions
-            .get(&session_id)
-            .ok_or("Session not found")?
-            .clone();
-
-        let pool = self.db.as_ref().ok_or("No database configured")?;
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let user_id = session
+            .user_id
+            .as_deref()
+            .ok_or(EngineError::AuthRequired)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
 
+        // Look up the message for BookmarkInfo
         let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Message not found")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Message not found".into()))?;
 
-        let user_id = session.user_id.as_deref().unwrap_or(&session.nickname);
-
-        crate::db::queries::messages::add_reaction(pool, message_id, user_id, emoji)
+        let bookmark_id = Uuid::new_v4().to_string();
+        crate::db::queries::bookmarks::add_bookmark(pool, &bookmark_id, user_id, message_id, note)
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to add bookmark: {e}")))?;
 
-        let server_id = msg.server_id.ok_or("Message has no server")?;
-        let channel_id = msg.channel_id.ok_or("Message has no channel")?;
-
-        let channel_name = self
-            .channels
-            .get(&channel_id)
-            .map(|ch| ch.name.clone())
-            .unwrap_or_default();
-
-        let event = ChatEvent::ReactionAdd {
-            message_id: message_id.parse().unwrap_or_default(),
-            server_id,
-            channel: channel_name,
-            user_id: user_id.to_string(),
-            nickname: session.nickname.clone(),
-            emoji: emoji.to_string(),
+        let bookmark = BookmarkInfo {
+            id: bookmark_id,
+            message_id: message_id.to_string(),
+            channel_id: msg.channel_id.unwrap_or_default(),
+            from: msg.sender_nick,
+            content: msg.content,
+            timestamp: msg.created_at,
+            note: note.map(|s| s.to_string()),
+            created_at: Utc::now().to_rfc3339(),
         };
 
-        self.broadcast_to_channel(&channel_id, &event, None);
+        let _ = session.send(ChatEvent::BookmarkAdd { bookmark });
 
         Ok(())
     }
 
-    /// Remove a reaction from a message.
-    pub async fn remove_reaction(
+    /// Remove a bookmark for the authenticated user.
+    pub async fn remove_bookmark(
         &self,
         session_id: SessionId,
         message_id: &str,
-        emoji: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let session = self
-            .sessions
-            .get(&session_id)
-            .ok_or("Session not found")?
-            .clone();
-
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-
-        let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Message not found")?;
-
-        let user_id = session.user_id.as_deref().unwrap_or(&session.nickname);
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let user_id = session
+            .user_id
+            .as_deref()
+            .ok_or(EngineError::AuthRequired)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
 
-        crate::db::queries::messages::remove_reaction(pool, message_id, user_id, emoji)
+        crate::db::queries::bookmarks::remove_bookmark(pool, user_id, message_id)
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to remove bookmark: {e}")))?;
 
-        let server_id = msg.server_id.ok_or("Message has no server")?;
-        let channel_id = msg.channel_id.ok_or("Message has no channel")?;
+        let _ = session.send(ChatEvent::BookmarkRemove {
+            message_id: message_id.to_string(),
+        });
 
-        let channel_name = self
-            .channels
-            .get(&channel_id)
-            .map(|ch| ch.name.clone())
-            .unwrap_or_default();
+        Ok(())
+    }
 
-        let event = ChatEvent::ReactionRemove {
-            message_id: message_id.parse().unwrap_or_default(),
-            server_id,
-            channel: channel_name,
-            user_id: user_id.to_string(),
-            nickname: session.nickname.clone(),
-            emoji: emoji.to_string(),
-        };
+    /// List all bookmarks for the authenticated user. Sends BookmarkList event to the session.
+    pub async fn list_bookmarks(&self, session_id: SessionId) -> Result<(), EngineError> {
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let bookmarks = self.fetch_bookmarks(session_id).await?;
 
-        self.broadcast_to_channel(&channel_id, &event, None);
+        let _ = session.send(ChatEvent::BookmarkList { bookmarks });
 
         Ok(())
     }
 
-    // ── Typing indicators ────────────────────────────────────────────
-
-    /// Broadcast a typing indicator to a channel.
-    pub fn send_typing(
+    /// Fetch the authenticated user's bookmarks, with message content denormalized.
+    pub async fn fetch_bookmarks(
         &self,
         session_id: SessionId,
-        server_id: &str,
-        channel_name: &str,
-    ) -> Result<(), String> {
-        let channel_name = normalize_channel_name(channel_name);
-
+    ) -> Result<Vec<BookmarkInfo>, EngineError> {
         let session = self
-            .sessions
-            .get(&session_id)
-            .ok_or("Session not found")?
-            .clone();
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        let user_id = session
+            .user_id
+            .as_deref()
+            .ok_or(EngineError::AuthRequired)?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
 
-        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+        let rows = crate::db::queries::bookmarks::list_bookmarks(pool, user_id).await?;
 
-        let event = ChatEvent::TypingStart {
-            server_id: server_id.to_string(),
-            channel: channel_name,
-            nickname: session.nickname.clone(),
-        };
+        let mut bookmarks = Vec::new();
+        for row in rows {
+            // Look up message content for each bookmark
+            let (from, content, timestamp, channel_id) =
+                match crate::db::queries::messages::get_message_by_id(pool, &row.message_id).await {
+                    Ok(Some(msg)) => (
+                        msg.sender_nick,
+                        msg.content,
+                        msg.created_at,
+                        msg.channel_id.unwrap_or_default(),
+                    ),
+                    _ => (
+                        "unknown".to_string(),
+                        "[deleted]".to_string(),
+                        String::new(),
+                        String::new(),
+                    ),
+                };
 
-        self.broadcast_to_channel(&channel_id, &event, Some(session_id));
+            bookmarks.push(BookmarkInfo {
+                id: row.id,
+                message_id: row.message_id,
+                channel_id,
+                from,
+                content,
+                timestamp,
+                note: row.note,
+                created_at: row.created_at,
+            });
+        }
 
-        Ok(())
+        Ok(bookmarks)
     }
 
-    // ── Read state ────────────────────────────────────────────────────
-
-    /// Mark a channel as read for a user, up to a specific message ID.
-    pub async fn mark_read(
-        &self,
-        session_id: SessionId,
-        server_id: &str,
-        channel_name: &str,
-        message_id: &str,
-    ) -> Result<(), String> {
-        let session = self
-            .sessions
-            .get(&session_id)
-            .ok_or("Session not found")?
-            .clone();
-
-        let user_id = session.user_id.as_deref().ok_or("AUTH_REQUIRED")?;
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-
-        let channel_name = normalize_channel_name(channel_name);
-        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+    // ── Phase 6: Moderation ─────────────────────────────────────
 
-        crate::db::queries::messages::mark_channel_read(pool, user_id, &channel_id, message_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
+    /// Broadcast a ChatEvent to all connected sessions that belong to a server.
+    pub fn broadcast_to_server(&self, server_id: &str, event: &ChatEvent) {
+        let Some(server) = self.servers.get(server_id) else {
+            return;
+        };
+        let member_ids: Vec<String> = server.member_user_ids.iter().cloned().collect();
+        drop(server);
 
-        Ok(())
+        for session in self.sessions.iter() {
+            if let Some(uid) = &session.user_id
+                && member_ids.contains(uid)
+            {
+                let _ = session.send(event.clone());
+            }
+        }
     }
 
-    /// Get unread counts for all channels in a server for a user.
-    pub async fn get_unread_counts(
+    /// Kick a member from a server.
+    pub async fn kick_member(
         &self,
         session_id: SessionId,
         server_id: &str,
-    ) -> Result<Vec<super::events::UnreadCount>, String> {
-        let session = self
-            .sessions
-            .get(&session_id)
-            .ok_or("Session not found")?
-            .clone();
-
-        let user_id = session.user_id.as_deref().ok_or("AUTH_REQUIRED")?;
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-
-        let rows = crate::db::queries::messages::get_unread_counts(pool, user_id, server_id)
+        target_user_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), EngineError> {
+        self.kick_member_in_channel(session_id, server_id, target_user_id, reason, None)
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
-
-        // Map channel_id -> channel_name
-        Ok(rows
-            .into_iter()
-            .filter_map(|r| {
-                let name = self.channels.get(&r.channel_id).map(|ch| ch.name.clone())?;
-                Some(super::events::UnreadCount {
-                    channel_name: name,
-                    count: r.unread_count,
-                })
-            })
-            .collect())
     }
 
-    // ── Roles ────────────────────────────────────────────────────────
+    /// Kick a member with channel-scoped permission check.
+    /// When `channel_id` is Some, the permission check considers channel overrides,
+    /// allowing moderators with per-channel KICK_MEMBERS to kick from that channel.
+    pub async fn kick_member_in_channel(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        target_user_id: &str,
+        reason: Option<&str>,
+        channel_id: Option<&str>,
+    ) -> Result<(), EngineError> {
+        let actor_id = self.session_user_id(session_id)?;
+        self.kick_member_by(actor_id, server_id, target_user_id, reason, channel_id)
+            .await
+    }
 
-    /// Get effective permissions for a user in a channel.
-    pub async fn get_effective_permissions(
+    /// Kick a member on behalf of `actor_id`, who needs KICK_MEMBERS.
+    pub async fn kick_member_by(
         &self,
+        actor_id: String,
         server_id: &str,
+        target_user_id: &str,
+        reason: Option<&str>,
         channel_id: Option<&str>,
-        user_id: &str,
-    ) -> Permissions {
-        let is_owner = self.is_server_owner(server_id, user_id);
-        if is_owner {
-            return Permissions::all();
+    ) -> Result<(), EngineError> {
+        self.require_user_permission(&actor_id, server_id, channel_id, Permissions::KICK_MEMBERS)
+            .await?;
+
+        // Prevent kicking the server owner
+        if self.is_server_owner(server_id, target_user_id) {
+            return Err(EngineError::Forbidden(
+                "Cannot kick the server owner".into(),
+            ));
         }
 
         let Some(pool) = &self.db else {
-            return DEFAULT_EVERYONE;
-        };
-
-        // Get default (@everyone) role base permissions
-        let base = match crate::db::queries::roles::get_default_role(pool, server_id).await {
-            Ok(Some(role)) => Permissions::from_bits_truncate(role.permissions as u64),
-            _ => DEFAULT_EVERYONE,
-        };
-
-        // Get user's assigned roles
-        let user_roles = crate::db::queries::roles::get_user_roles(pool, server_id, user_id)
-            .await
-            .unwrap_or_default();
-        let role_perms: Vec<(String, Permissions)> = user_roles
-            .iter()
-            .map(|r| {
-                (
-                    r.id.clone(),
-                    Permissions::from_bits_truncate(r.permissions as u64),
-                )
-            })
-            .collect();
-
-        // Get channel overrides if a channel was specified
-        let overrides = if let Some(ch_id) = channel_id {
-            crate::db::queries::channels::get_channel_overrides(pool, ch_id)
-                .await
-                .unwrap_or_default()
-                .into_iter()
-                .map(|o| ChannelOverride {
-                    target_type: if o.target_type == "role" {
-                        OverrideTargetType::Role
-                    } else {
-                        OverrideTargetType::User
-                    },
-                    target_id: o.target_id,
-                    allow: Permissions::from_bits_truncate(o.allow_bits as u64),
-                    deny: Permissions::from_bits_truncate(o.deny_bits as u64),
-                })
-                .collect::<Vec<_>>()
-        } else {
-            vec![]
+            return Err(EngineError::NoDatabase);
         };
 
-        // Get @everyone role id
-        let everyone_role_id =
-            match crate::db::queries::roles::get_default_role(pool, server_id).await {
-                Ok(Some(role)) => role.id,
-                _ => String::new(),
-            };
-
-        permissions::compute_effective_permissions(
-            base,
-            &role_perms,
-            &overrides,
-            &everyone_role_id,
-            user_id,
-            is_owner,
+        self.kick_user(pool, actor_id.clone(), server_id, target_user_id, reason)
+            .await?;
+        self.record_strike(
+            pool,
+            server_id,
+            target_user_id,
+            Some(&actor_id),
+            "kick",
+            reason,
         )
+        .await;
+        Ok(())
     }
 
-    /// Check that a user has a required permission. Returns Ok(user_id) or Err(message).
-    pub async fn require_permission(
+    /// Remove a member from a server, audit-logged against `actor_id`.
+    async fn kick_user(
         &self,
-        session_id: SessionId,
+        pool: &SqlitePool,
+        actor_id: String,
         server_id: &str,
-        channel_id: Option<&str>,
-        required: Permissions,
-    ) -> Result<String, String> {
-        let session = self
-            .sessions
-            .get(&session_id)
-            .ok_or("Session not found")?
-            .clone();
-        let user_id = session
-            .user_id
-            .as_deref()
-            .ok_or("AUTH_REQUIRED")?
-            .to_string();
+        target_user_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), EngineError> {
+        crate::db::queries::moderation::kick_member(pool, server_id, target_user_id)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to kick member: {e}")))?;
 
-        let perms = self
-            .get_effective_permissions(server_id, channel_id, &user_id)
-            .await;
+        // Remove from in-memory server state; their role assignments are gone too
+        if let Some(mut server) = self.servers.get_mut(server_id) {
+            server.member_user_ids.remove(target_user_id);
+            server.permission_cache.invalidate_member(target_user_id);
+        }
 
-        if perms.contains(required) {
-            Ok(user_id)
-        } else {
-            Err("FORBIDDEN: insufficient permissions".into())
+        // Log to audit log
+        let audit_id = Uuid::new_v4().to_string();
+        if let Err(e) = crate::db::queries::audit_log::create_entry(
+            pool,
+            &crate::db::models::CreateAuditLogParams {
+                id: &audit_id,
+                server_id,
+                actor_id: &actor_id,
+                action_type: "member_kick",
+                target_type: Some("user"),
+                target_id: Some(target_user_id),
+                reason,
+                changes: None,
+            },
+        )
+        .await
+        {
+            tracing::warn!(error = %e, "Failed to write kick audit log entry");
         }
+
+        // Broadcast kick event to server members
+        let event = ChatEvent::MemberKick {
+            server_id: server_id.to_string(),
+            user_id: target_user_id.to_string(),
+            kicked_by: actor_id,
+            reason: reason.map(String::from),
+        };
+        self.broadcast_to_server(server_id, &event);
+
+        Ok(())
     }
 
-    /// List roles for a server.
-    pub async fn list_roles(&self, server_id: &str) -> Result<Vec<RoleInfo>, String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        let rows = crate::db::queries::roles::list_roles(pool, server_id)
+    /// Ban a user from a server, optionally for a limited time and deleting
+    /// their recent messages. The user doesn't have to be a member.
+    pub async fn ban_member(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        target_user_id: &str,
+        params: &BanParams<'_>,
+    ) -> Result<(), EngineError> {
+        let actor_id = self.session_user_id(session_id)?;
+        self.ban_member_by(actor_id, server_id, target_user_id, params)
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
-        Ok(rows.into_iter().map(role_row_to_info).collect())
     }
 
-    /// Create a custom role in a server.
-    pub async fn create_role(
+    /// Ban a user on behalf of `actor_id`, who needs BAN_MEMBERS. Banning an
+    /// already banned user replaces their ban's reason and expiry.
+    pub async fn ban_member_by(
         &self,
+        actor_id: String,
         server_id: &str,
-        name: &str,
-        color: Option<&str>,
-        permissions: i64,
-    ) -> Result<RoleInfo, String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
+        target_user_id: &str,
+        params: &BanParams<'_>,
+    ) -> Result<(), EngineError> {
+        self.require_user_permission(&actor_id, server_id, None, Permissions::BAN_MEMBERS)
+            .await?;
 
-        // Determine position: max + 1
-        let existing = crate::db::queries::roles::list_roles(pool, server_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
-        let max_pos = existing.iter().map(|r| r.position).max().unwrap_or(0);
+        if target_user_id.is_empty() || target_user_id.len() > 256 {
+            return Err(EngineError::Validation("Invalid user ID".into()));
+        }
+        // Prevent banning the server owner
+        if self.is_server_owner(server_id, target_user_id) {
+            return Err(EngineError::Forbidden("Cannot ban the server owner".into()));
+        }
+        if params
+            .duration_seconds
+            .is_some_and(|d| !(1..=MAX_BAN_DURATION_SECONDS).contains(&d))
+        {
+            return Err(EngineError::Validation(format!(
+                "Ban duration must be between 1 and {MAX_BAN_DURATION_SECONDS} seconds"
+            )));
+        }
 
-        let role_id = Uuid::new_v4().to_string();
-        let params = crate::db::queries::roles::CreateRoleParams {
-            id: &role_id,
-            server_id,
-            name,
-            color,
-            icon_url: None,
-            position: max_pos + 1,
-            permissions,
-            is_default: false,
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
         };
-        crate::db::queries::roles::create_role(pool, &params)
-            .await
-            .map_err(|e| format!("Failed to create role: {e}"))?;
 
-        let role = crate::db::queries::roles::get_role(pool, &role_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Role not found after creation")?;
-
-        Ok(role_row_to_info(role))
+        self.ban_user(pool, actor_id.clone(), server_id, target_user_id, params)
+            .await?;
+        self.record_strike(
+            pool,
+            server_id,
+            target_user_id,
+            Some(&actor_id),
+            "ban",
+            params.reason,
+        )
+        .await;
+        Ok(())
     }
 
-    /// Update a custom role.
-    pub async fn update_role(
+    /// Ban every user in `user_ids`, then send the session the outcome.
+    pub async fn ban_members(
         &self,
+        session_id: SessionId,
         server_id: &str,
-        role_id: &str,
-        name: &str,
-        color: Option<&str>,
-        permissions: i64,
-    ) -> Result<RoleInfo, String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        // Verify the role belongs to the expected server (prevents cross-server manipulation)
-        let role = crate::db::queries::roles::get_role(pool, role_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Role not found")?;
-        if role.server_id != server_id {
-            return Err("Role does not belong to this server".into());
-        }
-        crate::db::queries::roles::update_role(pool, role_id, name, color, None, permissions)
-            .await
-            .map_err(|e| format!("Failed to update role: {e}"))?;
-        let role = crate::db::queries::roles::get_role(pool, role_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Role not found")?;
-        Ok(role_row_to_info(role))
-    }
-
-    /// Delete a custom role.
-    pub async fn delete_role(&self, server_id: &str, role_id: &str) -> Result<(), String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        // Prevent deleting the @everyone default role
-        let role = crate::db::queries::roles::get_role(pool, role_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Role not found")?;
-        if role.is_default != 0 {
-            return Err("Cannot delete the default @everyone role".into());
-        }
-        // Verify the role belongs to the expected server (prevents cross-server manipulation)
-        if role.server_id != server_id {
-            return Err("Role does not belong to this server".into());
+        user_ids: &[String],
+        params: &BanParams<'_>,
+    ) -> Result<(), EngineError> {
+        let actor_id = self.session_user_id(session_id)?;
+        let (banned, failed) = self
+            .ban_members_by(&actor_id, server_id, user_ids, params)
+            .await?;
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::BulkBanResult {
+                server_id: server_id.to_string(),
+                banned,
+                failed,
+            });
         }
-        crate::db::queries::roles::delete_role(pool, role_id)
-            .await
-            .map_err(|e| format!("Failed to delete role: {e}"))?;
         Ok(())
     }
 
-    /// Get the highest role position for a user in a server.
-    /// Server owner gets i32::MAX. Returns 0 if no roles found (base @everyone level).
-    pub async fn get_user_highest_role_position(&self, server_id: &str, user_id: &str) -> i32 {
-        if self.is_server_owner(server_id, user_id) {
-            return i32::MAX;
+    /// Ban a list of users on behalf of `actor_id`, who needs BAN_MEMBERS.
+    /// Each user is banned independently; returns the IDs banned and those
+    /// that failed, with the reason.
+    pub async fn ban_members_by(
+        &self,
+        actor_id: &str,
+        server_id: &str,
+        user_ids: &[String],
+        params: &BanParams<'_>,
+    ) -> Result<(Vec<String>, Vec<BulkBanFailure>), EngineError> {
+        self.require_user_permission(actor_id, server_id, None, Permissions::BAN_MEMBERS)
+            .await?;
+        if user_ids.is_empty() || user_ids.len() > MAX_BULK_BAN {
+            return Err(EngineError::Validation(format!(
+                "Bulk ban takes between 1 and {MAX_BULK_BAN} user IDs"
+            )));
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        let mut banned = Vec::new();
+        let mut failed = Vec::new();
+        for user_id in user_ids.iter().filter(|id| seen.insert(id.as_str())) {
+            match self
+                .ban_member_by(actor_id.to_string(), server_id, user_id, params)
+                .await
+            {
+                Ok(()) => banned.push(user_id.clone()),
+                Err(e) => failed.push(BulkBanFailure {
+                    user_id: user_id.clone(),
+                    error: e.to_string(),
+                }),
+            }
         }
-        let Some(pool) = &self.db else { return 0 };
-        crate::db::queries::roles::get_user_roles(pool, server_id, user_id)
-            .await
-            .unwrap_or_default()
-            .iter()
-            .map(|r| r.position)
-            .max()
-            .unwrap_or(0)
+        Ok((banned, failed))
     }
 
-    /// Validate role hierarchy: actor must have a higher position than target role.
-    pub async fn check_role_hierarchy(
+    /// Ban and remove a user, audit-logged against `actor_id`.
+    async fn ban_user(
         &self,
+        pool: &SqlitePool,
+        actor_id: String,
         server_id: &str,
-        actor_user_id: &str,
-        target_role_id: &str,
-    ) -> Result<(), String> {
-        // Server owner bypasses hierarchy checks
-        if self.is_server_owner(server_id, actor_user_id) {
-            return Ok(());
-        }
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        let target_role = crate::db::queries::roles::get_role(pool, target_role_id)
+        target_user_id: &str,
+        params: &BanParams<'_>,
+    ) -> Result<(), EngineError> {
+        let ban_id = Uuid::new_v4().to_string();
+        let purge_seconds = params.delete_message_seconds.min(MAX_BAN_PURGE_SECONDS) as i64;
+        let expires_at = params.duration_seconds.map(|seconds| {
+            (Utc::now() + chrono::Duration::seconds(seconds as i64))
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        });
+
+        crate::db::queries::bans::create_ban(
+            pool,
+            &crate::db::models::CreateBanParams {
+                id: &ban_id,
+                server_id,
+                user_id: target_user_id,
+                banned_by: &actor_id,
+                reason: params.reason,
+                delete_message_seconds: purge_seconds,
+                expires_at: expires_at.as_deref(),
+            },
+        )
+        .await
+        .map_err(|e| EngineError::Internal(format!("Failed to ban member: {e}")))?;
+
+        // Also kick them from the server
+        crate::db::queries::moderation::kick_member(pool, server_id, target_user_id)
             .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Role not found")?;
-        let actor_highest = self
-            .get_user_highest_role_position(server_id, actor_user_id)
-            .await;
-        if actor_highest <= target_role.position {
-            return Err("You cannot manage a role at or above your own highest role".to_string());
+            .map_err(|e| EngineError::Internal(format!("Failed to kick banned member: {e}")))?;
+
+        // Delete messages if requested
+        if purge_seconds > 0
+            && let Err(e) = crate::db::queries::moderation::delete_user_messages(
+                pool,
+                server_id,
+                target_user_id,
+                purge_seconds,
+            )
+            .await
+        {
+            tracing::warn!(error = %e, "Failed to delete messages for banned user");
+        }
+
+        // Remove from in-memory server state; their role assignments are gone too
+        if let Some(mut server) = self.servers.get_mut(server_id) {
+            server.member_user_ids.remove(target_user_id);
+            server.permission_cache.invalidate_member(target_user_id);
+        }
+
+        // Audit log
+        let audit_id = Uuid::new_v4().to_string();
+        let changes = serde_json::json!({
+            "expires_at": expires_at,
+            "delete_message_seconds": purge_seconds,
+        })
+        .to_string();
+        if let Err(e) = crate::db::queries::audit_log::create_entry(
+            pool,
+            &crate::db::models::CreateAuditLogParams {
+                id: &audit_id,
+                server_id,
+                actor_id: &actor_id,
+                action_type: "member_ban",
+                target_type: Some("user"),
+                target_id: Some(target_user_id),
+                reason: params.reason,
+                changes: Some(&changes),
+            },
+        )
+        .await
+        {
+            tracing::warn!(error = %e, "Failed to write ban audit log entry");
         }
+
+        // Broadcast
+        let event = ChatEvent::MemberBan {
+            server_id: server_id.to_string(),
+            user_id: target_user_id.to_string(),
+            banned_by: actor_id,
+            reason: params.reason.map(String::from),
+            expires_at,
+        };
+        self.broadcast_to_server(server_id, &event);
+
         Ok(())
     }
 
-    /// Assign a role to a user. Enforces role hierarchy.
-    pub async fn assign_role(
-        &self,
-        server_id: &str,
-        actor_user_id: &str,
-        target_user_id: &str,
-        role_id: &str,
-    ) -> Result<Vec<String>, String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        self.check_role_hierarchy(server_id, actor_user_id, role_id)
-            .await?;
-        crate::db::queries::roles::assign_role(pool, server_id, target_user_id, role_id)
-            .await
-            .map_err(|e| format!("Failed to assign role: {e}"))?;
-        let roles = crate::db::queries::roles::get_user_roles(pool, server_id, target_user_id)
+    /// Lift temporary bans whose time is up. Run periodically; returns how
+    /// many bans were lifted.
+    pub async fn expire_bans(&self) -> usize {
+        let Some(pool) = &self.db else {
+            return 0;
+        };
+        let expired = match crate::db::queries::bans::list_expired_bans(pool).await {
+            Ok(expired) => expired,
+            Err(e) => {
+                warn!(error = %e, "failed to list expired bans");
+                return 0;
+            }
+        };
+
+        let mut lifted = 0;
+        for ban in expired {
+            match crate::db::queries::bans::remove_expired_ban(pool, &ban.server_id, &ban.user_id)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    warn!(error = %e, server_id = %ban.server_id, "failed to lift expired ban");
+                    continue;
+                }
+            }
+            lifted += 1;
+
+            // Logged against the moderator who set the ban
+            if let Err(e) = crate::db::queries::audit_log::create_entry(
+                pool,
+                &crate::db::models::CreateAuditLogParams {
+                    id: &Uuid::new_v4().to_string(),
+                    server_id: &ban.server_id,
+                    actor_id: &ban.banned_by,
+                    action_type: "member_unban",
+                    target_type: Some("user"),
+                    target_id: Some(&ban.user_id),
+                    reason: Some("Ban expired"),
+                    changes: None,
+                },
+            )
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
-        Ok(roles.into_iter().map(|r| r.id).collect())
+            {
+                tracing::warn!(error = %e, "Failed to write unban audit log entry");
+            }
+
+            let event = ChatEvent::MemberUnban {
+                server_id: ban.server_id.clone(),
+                user_id: ban.user_id,
+            };
+            self.broadcast_to_server(&ban.server_id, &event);
+        }
+        lifted
     }
 
-    /// Remove a role from a user. Enforces role hierarchy.
-    pub async fn remove_role(
+    /// Unban a member from a server.
+    pub async fn unban_member(
         &self,
+        session_id: SessionId,
         server_id: &str,
-        actor_user_id: &str,
         target_user_id: &str,
-        role_id: &str,
-    ) -> Result<Vec<String>, String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        self.check_role_hierarchy(server_id, actor_user_id, role_id)
+    ) -> Result<(), EngineError> {
+        let actor_id = self
+            .require_permission(session_id, server_id, None, Permissions::BAN_MEMBERS)
             .await?;
-        crate::db::queries::roles::remove_role(pool, server_id, target_user_id, role_id)
-            .await
-            .map_err(|e| format!("Failed to remove role: {e}"))?;
-        let roles = crate::db::queries::roles::get_user_roles(pool, server_id, target_user_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
-        Ok(roles.into_iter().map(|r| r.id).collect())
-    }
 
-    // ── Categories ──────────────────────────────────────────────────
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
+        };
 
-    /// List categories for a server.
-    pub async fn list_categories(&self, server_id: &str) -> Result<Vec<CategoryInfo>, String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        let rows = crate::db::queries::categories::list_categories(pool, server_id)
+        let removed = crate::db::queries::bans::remove_ban(pool, server_id, target_user_id)
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
-        Ok(rows.into_iter().map(category_row_to_info).collect())
-    }
+            .map_err(|e| EngineError::Internal(format!("Failed to unban member: {e}")))?;
 
-    /// Create a channel category.
-    pub async fn create_category(
-        &self,
-        server_id: &str,
-        name: &str,
-    ) -> Result<CategoryInfo, String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        let existing = crate::db::queries::categories::list_categories(pool, server_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
-        let max_pos = existing.iter().map(|c| c.position).max().unwrap_or(-1);
+        if !removed {
+            return Err(EngineError::NotFound("User is not banned".into()));
+        }
 
-        let cat_id = Uuid::new_v4().to_string();
-        crate::db::queries::categories::create_category(
+        // Audit log
+        let audit_id = Uuid::new_v4().to_string();
+        if let Err(e) = crate::db::queries::audit_log::create_entry(
             pool,
-            &cat_id,
-            server_id,
-            name,
-            max_pos + 1,
+            &crate::db::models::CreateAuditLogParams {
+                id: &audit_id,
+                server_id,
+                actor_id: &actor_id,
+                action_type: "member_unban",
+                target_type: Some("user"),
+                target_id: Some(target_user_id),
+                reason: None,
+                changes: None,
+            },
         )
         .await
-        .map_err(|e| format!("Failed to create category: {e}"))?;
+        {
+            tracing::warn!(error = %e, "Failed to write unban audit log entry");
+        }
 
-        Ok(CategoryInfo {
-            id: cat_id,
+        // Broadcast
+        let event = ChatEvent::MemberUnban {
             server_id: server_id.to_string(),
-            name: name.to_string(),
-            position: max_pos + 1,
-        })
-    }
-
-    /// Update a channel category name.
-    pub async fn update_category(
-        &self,
-        category_id: &str,
-        name: &str,
-    ) -> Result<CategoryInfo, String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        crate::db::queries::categories::update_category(pool, category_id, name)
-            .await
-            .map_err(|e| format!("Failed to update category: {e}"))?;
-        let cat = crate::db::queries::categories::get_category(pool, category_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Category not found")?;
-        Ok(category_row_to_info(cat))
-    }
+            user_id: target_user_id.to_string(),
+        };
+        self.broadcast_to_server(server_id, &event);
 
-    /// Delete a channel category.
-    pub async fn delete_category(&self, category_id: &str) -> Result<(), String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        crate::db::queries::categories::delete_category(pool, category_id)
-            .await
-            .map_err(|e| format!("Failed to delete category: {e}"))?;
-        // Channels referencing this category get NULL (ON DELETE SET NULL)
-        // Update in-memory state
-        for mut ch in self.channels.iter_mut() {
-            if ch.category_id.as_deref() == Some(category_id) {
-                ch.category_id = None;
-            }
-        }
         Ok(())
     }
 
-    // ── Channel organization ────────────────────────────────────────
-
-    /// Reorder channels: update position and category for a batch of channels.
-    pub async fn reorder_channels(
+    /// Get the list of bans for a server.
+    pub async fn list_bans(
         &self,
-        _server_id: &str,
-        updates: &[ChannelPositionInfo],
-    ) -> Result<(), String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        for update in updates {
-            crate::db::queries::channels::update_channel_position(
-                pool,
-                &update.id,
-                update.position,
-            )
-            .await
-            .map_err(|e| format!("Failed to update channel position: {e}"))?;
-            crate::db::queries::channels::update_channel_category(
-                pool,
-                &update.id,
-                update.category_id.as_deref(),
-            )
-            .await
-            .map_err(|e| format!("Failed to update channel category: {e}"))?;
+        session_id: SessionId,
+        server_id: &str,
+    ) -> Result<(), EngineError> {
+        let bans = self.get_bans(session_id, server_id).await?;
 
-            // Update in-memory state
-            if let Some(mut ch) = self.channels.get_mut(&update.id) {
-                ch.position = update.position;
-                ch.category_id.clone_from(&update.category_id);
-            }
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::BanList {
+                server_id: server_id.to_string(),
+                bans,
+            });
         }
+
         Ok(())
     }
 
-    // ── Profiles ───────────────────────────────────────────────────
-
-    /// Get a user's full profile.
-    pub async fn get_user_profile(
+    /// Fetch the bans for a server (requires BAN_MEMBERS).
+    pub async fn get_bans(
         &self,
-        user_id: &str,
-    ) -> Result<super::events::UserProfileInfo, String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
+        session_id: SessionId,
+        server_id: &str,
+    ) -> Result<Vec<BanInfo>, EngineError> {
+        self.require_read_permission(session_id, server_id, None, Permissions::BAN_MEMBERS)
+            .await?;
 
-        // Get basic user info
-        let (id, username, _email, avatar_url) = crate::db::queries::users::get_user(pool, user_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("User not found")?;
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
+        };
 
-        // Get profile
-        let profile = crate::db::queries::profiles::get_profile(pool, &id)
+        let rows = crate::db::queries::bans::list_bans(pool, server_id)
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to list bans: {e}")))?;
 
-        // Get user created_at
-        let created_at =
-            sqlx::query_scalar::<_, String>("SELECT created_at FROM users WHERE id = ?")
-                .bind(&id)
-                .fetch_one(pool)
-                .await
-                .unwrap_or_else(|_| "unknown".into());
+        let bans = rows
+            .into_iter()
+            .map(|r| BanInfo {
+                id: r.id,
+                user_id: r.user_id,
+                banned_by: r.banned_by,
+                reason: r.reason,
+                expires_at: r.expires_at,
+                created_at: r.created_at,
+            })
+            .collect();
 
-        Ok(super::events::UserProfileInfo {
-            user_id: id,
-            username,
-            avatar_url,
-            bio: profile.as_ref().and_then(|p| p.bio.clone()),
-            pronouns: profile.as_ref().and_then(|p| p.pronouns.clone()),
-            banner_url: profile.as_ref().and_then(|p| p.banner_url.clone()),
-            created_at,
-        })
+        Ok(bans)
     }
 
-    // ── Utility ─────────────────────────────────────────────────────
-
-    /// Get a reference to the database pool (if configured).
-    pub fn db(&self) -> Option<&SqlitePool> {
-        self.db.as_ref()
+    /// Set a timeout on a member (or clear it).
+    pub async fn timeout_member(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        target_user_id: &str,
+        timeout_until: Option<&str>,
+        reason: Option<&str>,
+    ) -> Result<(), EngineError> {
+        let actor_id = self.session_user_id(session_id)?;
+        self.timeout_member_by(&actor_id, server_id, target_user_id, timeout_until, reason)
+            .await
     }
 
-    /// Check if a nickname is available.
-    pub fn is_nick_available(&self, nickname: &str) -> bool {
-        !self.nick_to_session.contains_key(nickname)
-    }
+    /// Time out a member on behalf of `actor_id`, who needs KICK_MEMBERS.
+    pub async fn timeout_member_by(
+        &self,
+        actor_id: &str,
+        server_id: &str,
+        target_user_id: &str,
+        timeout_until: Option<&str>,
+        reason: Option<&str>,
+    ) -> Result<(), EngineError> {
+        self.require_user_permission(actor_id, server_id, None, Permissions::KICK_MEMBERS)
+            .await?;
 
-    /// Look up a session ID by nickname. Returns None if no session with that nick exists.
-    pub fn get_session_id_by_nick(&self, nickname: &str) -> Option<SessionId> {
-        self.nick_to_session.get(nickname).map(|r| *r)
-    }
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
+        };
 
-    /// Get the user_id for a session. Returns None if session not found or has no user.
-    pub fn get_session_user_id(&self, session_id: SessionId) -> Option<String> {
-        self.sessions
-            .get(&session_id)
-            .and_then(|s| s.user_id.clone())
-    }
+        self.apply_member_timeout(pool, server_id, target_user_id, timeout_until)
+            .await?;
+
+        // Audit log
+        let audit_id = Uuid::new_v4().to_string();
+        let changes_json = timeout_until.map(|t| format!("{{\"timeout_until\":\"{t}\"}}"));
+        if let Err(e) = crate::db::queries::audit_log::create_entry(
+            pool,
+            &crate::db::models::CreateAuditLogParams {
+                id: &audit_id,
+                server_id,
+                actor_id,
+                action_type: "member_timeout",
+                target_type: Some("user"),
+                target_id: Some(target_user_id),
+                reason,
+                changes: changes_json.as_deref(),
+            },
+        )
+        .await
+        {
+            tracing::warn!(error = %e, "Failed to write timeout audit log entry");
+        }
 
-    /// Get (server_id, channel_name) pairs for all channels a session is in.
-    pub fn get_session_channels(&self, session_id: SessionId) -> Vec<(String, String)> {
-        if !self.sessions.contains_key(&session_id) {
-            return vec![];
+        if timeout_until.is_some() {
+            self.record_strike(
+                pool,
+                server_id,
+                target_user_id,
+                Some(actor_id),
+                "timeout",
+                reason,
+            )
+            .await;
         }
-        self.channels
-            .iter()
-            .filter(|ch| ch.members.contains(&session_id))
-            .map(|ch| (ch.server_id.clone(), ch.name.clone()))
-            .collect()
+        Ok(())
     }
 
-    /// Get a session by ID.
-    pub fn get_session(&self, session_id: SessionId) -> Option<Arc<UserSession>> {
-        self.sessions.get(&session_id).map(|s| s.clone())
-    }
+    /// Store a member's timeout (`None` clears it) and tell the server.
+    async fn apply_member_timeout(
+        &self,
+        pool: &SqlitePool,
+        server_id: &str,
+        user_id: &str,
+        timeout_until: Option<&str>,
+    ) -> Result<(), EngineError> {
+        crate::db::queries::moderation::set_member_timeout(pool, server_id, user_id, timeout_until)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to set timeout: {e}")))?;
 
-    /// Get the database pool (if configured).
-    pub fn get_db(&self) -> Option<SqlitePool> {
-        self.db.clone()
+        let event = ChatEvent::MemberTimeout {
+            server_id: server_id.to_string(),
+            user_id: user_id.to_string(),
+            timeout_until: timeout_until.map(String::from),
+        };
+        self.broadcast_to_server(server_id, &event);
+        Ok(())
     }
 
-    /// Resolve a channel name within a server to its channel ID.
-    pub fn resolve_channel_id(
+    /// Set slow mode on a channel.
+    pub async fn set_slowmode(
         &self,
+        session_id: SessionId,
         server_id: &str,
         channel_name: &str,
-    ) -> Result<String, String> {
-        self.channel_name_index
-            .get(&(server_id.to_string(), channel_name.to_string()))
-            .map(|r| r.clone())
-            .ok_or(format!("No such channel: {channel_name}"))
-    }
+        seconds: i32,
+    ) -> Result<(), EngineError> {
+        self.require_permission(session_id, server_id, None, Permissions::MANAGE_CHANNELS)
+            .await?;
 
-    /// Broadcast an event to all members of a channel, optionally excluding one session.
-    fn broadcast_to_channel(
-        &self,
-        channel_id: &str,
-        event: &ChatEvent,
-        exclude: Option<SessionId>,
-    ) {
-        let Some(channel) = self.channels.get(channel_id) else {
-            return;
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
         };
 
-        for member_id in &channel.members {
-            if Some(*member_id) == exclude {
-                continue;
-            }
-            if let Some(session) = self.sessions.get(member_id)
-                && !session.send(event.clone())
-            {
-                warn!(%member_id, "failed to send event to session (channel closed)");
-            }
-        }
-    }
-
-    // ── Presence ─────────────────────────────────────────────
-
-    /// Update a user's presence and broadcast to members of shared servers.
-    pub async fn set_presence(
-        &self,
-        session_id: SessionId,
-        status: &str,
-        custom_status: Option<&str>,
-        status_emoji: Option<&str>,
-    ) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
-        let user_id = session.user_id.clone().ok_or("Not authenticated")?;
+        let channel_id = self
+            .channel_name_index
+            .get(&(server_id.to_string(), channel_name.to_string()))
+            .map(|v| v.clone())
+            .ok_or_else(|| EngineError::NotFound("Channel not found".into()))?;
 
-        // Validate status
-        match status {
-            "online" | "idle" | "dnd" | "invisible" => {}
-            _ => return Err("Invalid status. Must be: online, idle, dnd, invisible".into()),
-        }
+        let seconds = seconds.clamp(0, 21600); // max 6 hours
 
-        // Persist to DB
-        if let Some(pool) = &self.db {
-            crate::db::queries::presence::upsert_presence(
-                pool,
-                &user_id,
-                status,
-                custom_status,
-                status_emoji,
-            )
+        crate::db::queries::moderation::set_slowmode(pool, &channel_id, seconds)
             .await
-            .map_err(|e| format!("Failed to update presence: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to set slow mode: {e}")))?;
+
+        // Update in-memory state
+        if let Some(mut ch) = self.channels.get_mut(&channel_id) {
+            ch.slowmode_seconds = seconds;
         }
 
-        // Build presence info
-        let presence = super::events::PresenceInfo {
-            user_id: user_id.clone(),
-            nickname: session.nickname.clone(),
-            avatar_url: session.avatar_url.clone(),
-            status: if status == "invisible" {
-                "offline".into()
-            } else {
-                status.into()
-            },
-            custom_status: custom_status.map(|s| s.to_string()),
-            status_emoji: status_emoji.map(|s| s.to_string()),
+        // Broadcast
+        let event = ChatEvent::SlowModeUpdate {
+            server_id: server_id.to_string(),
+            channel: channel_name.to_string(),
+            seconds,
         };
-
-        // Broadcast to all servers the user is a member of
-        for server in self.servers.iter() {
-            if server.member_user_ids.contains(&user_id) {
-                let event = ChatEvent::PresenceUpdate {
-                    server_id: server.id.clone(),
-                    presence: presence.clone(),
-                };
-                // Send to all sessions in this server's channels, deduplicated per server
-                let mut notified = std::collections::HashSet::new();
-                for channel_id in server.channel_ids.iter() {
-                    if let Some(channel) = self.channels.get(channel_id) {
-                        for &member_sid in &channel.members {
-                            if member_sid != session_id
-                                && notified.insert(member_sid)
-                                && let Some(s) = self.sessions.get(&member_sid)
-                            {
-                                let _ = s.send(event.clone());
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        self.broadcast_to_server(server_id, &event);
 
         Ok(())
     }
 
-    /// Get presence list for all members of a server.
-    pub async fn get_server_presences(
+    /// Set NSFW flag on a channel.
+    pub async fn set_nsfw(
         &self,
+        session_id: SessionId,
         server_id: &str,
-    ) -> Result<Vec<super::events::PresenceInfo>, String> {
-        let server = self
-            .servers
-            .get(server_id)
-            .ok_or(format!("Server not found: {server_id}"))?;
-
-        let user_ids: Vec<String> = server.member_user_ids.iter().cloned().collect();
-        drop(server);
+        channel_name: &str,
+        is_nsfw: bool,
+    ) -> Result<(), EngineError> {
+        self.require_permission(session_id, server_id, None, Permissions::MANAGE_CHANNELS)
+            .await?;
 
-        let mut presences = Vec::new();
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
+        };
 
-        // Get DB presence records
-        if let Some(pool) = &self.db {
-            let rows = crate::db::queries::presence::get_presences_for_users(pool, &user_ids)
-                .await
-                .unwrap_or_default();
+        let channel_id = self
+            .channel_name_index
+            .get(&(server_id.to_string(), channel_name.to_string()))
+            .map(|v| v.clone())
+            .ok_or_else(|| EngineError::NotFound("Channel not found".into()))?;
 
-            for row in rows {
-                // Find nickname for this user from active sessions
-                let (nickname, avatar_url) = self.find_user_display_info(&row.user_id);
-                let visible_status = if row.status == "invisible" {
-                    "offline".to_string()
-                } else {
-                    row.status
-                };
-                presences.push(super::events::PresenceInfo {
-                    user_id: row.user_id,
-                    nickname,
-                    avatar_url,
-                    status: visible_status,
-                    custom_status: row.custom_status,
-                    status_emoji: row.status_emoji,
-                });
-            }
-        }
+        crate::db::queries::moderation::set_nsfw(pool, &channel_id, is_nsfw)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to set NSFW: {e}")))?;
 
-        // Add online users who may not have a presence row yet
-        for uid in &user_ids {
-            if !presences.iter().any(|p| p.user_id == *uid) {
-                let (nickname, avatar_url) = self.find_user_display_info(uid);
-                let is_online = self
-                    .sessions
-                    .iter()
-                    .any(|s| s.user_id.as_deref() == Some(uid));
-                presences.push(super::events::PresenceInfo {
-                    user_id: uid.clone(),
-                    nickname,
-                    avatar_url,
-                    status: if is_online {
-                        "online".into()
-                    } else {
-                        "offline".into()
-                    },
-                    custom_status: None,
-                    status_emoji: None,
-                });
-            }
+        // Update in-memory state
+        if let Some(mut ch) = self.channels.get_mut(&channel_id) {
+            ch.is_nsfw = is_nsfw;
         }
 
-        Ok(presences)
-    }
+        // Broadcast
+        let event = ChatEvent::NsfwUpdate {
+            server_id: server_id.to_string(),
+            channel: channel_name.to_string(),
+            is_nsfw,
+        };
+        self.broadcast_to_server(server_id, &event);
 
-    /// Find a user's display info (nickname, avatar) from active sessions or return defaults.
-    fn find_user_display_info(&self, user_id: &str) -> (String, Option<String>) {
-        for session in self.sessions.iter() {
-            if session.user_id.as_deref() == Some(user_id) {
-                return (session.nickname.clone(), session.avatar_url.clone());
-            }
-        }
-        (format!("user-{}", &user_id[..8.min(user_id.len())]), None)
+        Ok(())
     }
 
-    // ── Server Nicknames ─────────────────────────────────────
-
-    /// Set a user's server-specific display name.
-    pub async fn set_server_nickname(
+    /// Mark a channel private (members-only). IRC `+s`/`+p`.
+    pub async fn set_channel_private(
         &self,
         session_id: SessionId,
         server_id: &str,
-        nickname: Option<&str>,
-    ) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
-        let user_id = session.user_id.clone().ok_or("Not authenticated")?;
+        channel_name: &str,
+        is_private: bool,
+    ) -> Result<(), EngineError> {
+        let (pool, channel_id, set_by) = self
+            .prepare_channel_flag_change(session_id, server_id, channel_name)
+            .await?;
 
-        // Verify membership
-        let server = self
-            .servers
-            .get(server_id)
-            .ok_or(format!("Server not found: {server_id}"))?;
-        if !server.member_user_ids.contains(&user_id) {
-            return Err("Not a member of this server".into());
-        }
-        drop(server);
+        crate::db::queries::channels::set_channel_private(&pool, &channel_id, is_private)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to set channel private: {e}")))?;
 
-        if let Some(pool) = &self.db {
-            crate::db::queries::servers::set_server_nickname(pool, server_id, &user_id, nickname)
-                .await
-                .map_err(|e| format!("Failed to set nickname: {e}"))?;
+        if let Some(mut ch) = self.channels.get_mut(&channel_id) {
+            ch.is_private = is_private;
         }
 
-        // Broadcast nickname change
-        let event = ChatEvent::ServerNicknameUpdate {
+        let event = ChatEvent::ChannelPrivateUpdate {
             server_id: server_id.to_string(),
-            user_id: user_id.clone(),
-            nickname: nickname.map(|s| s.to_string()),
+            channel: channel_name.to_string(),
+            is_private,
+            set_by,
         };
-        if let Some(server) = self.servers.get(server_id) {
-            for channel_id in server.channel_ids.iter() {
-                self.broadcast_to_channel(channel_id, &event, None);
-            }
-        }
+        self.broadcast_to_server(server_id, &event);
 
         Ok(())
     }
 
-    // ── Search ───────────────────────────────────────────────
-
-    /// Search messages in a server using full-text search.
-    pub async fn search_messages(
+    /// Make a channel read-only: only members with MANAGE_MESSAGES may post. IRC `+m`.
+    pub async fn set_read_only(
         &self,
+        session_id: SessionId,
         server_id: &str,
-        query: &str,
-        channel_name: Option<&str>,
-        limit: i64,
-        offset: i64,
-    ) -> Result<(Vec<super::events::SearchResultMessage>, i64), String> {
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-
-        // Resolve channel name to ID if provided (normalize for case-insensitive lookup)
-        let channel_id = if let Some(ch_name) = channel_name {
-            let ch_name = normalize_channel_name(ch_name);
-            Some(self.resolve_channel_id(server_id, &ch_name)?)
-        } else {
-            None
-        };
+        channel_name: &str,
+        is_read_only: bool,
+    ) -> Result<(), EngineError> {
+        let (pool, channel_id, set_by) = self
+            .prepare_channel_flag_change(session_id, server_id, channel_name)
+            .await?;
 
-        let (rows, total) = crate::db::queries::search::search_messages(
-            pool,
-            server_id,
-            query,
-            channel_id.as_deref(),
-            limit.min(50),
-            offset,
-        )
-        .await
-        .map_err(|e| format!("Search failed: {e}"))?;
+        crate::db::queries::moderation::set_read_only(&pool, &channel_id, is_read_only)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to set read-only: {e}")))?;
 
-        let results: Vec<super::events::SearchResultMessage> = rows
-            .into_iter()
-            .filter_map(|row| {
-                let channel_name = row
-                    .channel_id
-                    .as_ref()
-                    .and_then(|cid| self.channels.get(cid).map(|ch| ch.name.clone()))
-                    .unwrap_or_default();
+        if let Some(mut ch) = self.channels.get_mut(&channel_id) {
+            ch.is_read_only = is_read_only;
+        }
 
-                Some(super::events::SearchResultMessage {
-                    id: row.id.parse().ok()?,
-                    from: row.sender_nick,
-                    content: row.content,
-                    timestamp: row.created_at.parse().ok()?,
-                    channel_id: row.channel_id.unwrap_or_default(),
-                    channel_name,
-                    edited_at: row.edited_at.as_ref().and_then(|s| s.parse().ok()),
-                })
-            })
-            .collect();
+        let event = ChatEvent::ReadOnlyUpdate {
+            server_id: server_id.to_string(),
+            channel: channel_name.to_string(),
+            is_read_only,
+            set_by,
+        };
+        self.broadcast_to_server(server_id, &event);
 
-        Ok((results, total))
+        Ok(())
     }
 
-    // ── Notification Settings ────────────────────────────────
-
-    /// Update notification settings for a user in a server or channel.
-    pub async fn update_notification_settings(
+    /// Lock a channel's topic: only members with MANAGE_CHANNELS may change it. IRC `+t`.
+    pub async fn set_topic_locked(
         &self,
         session_id: SessionId,
-        params: &UpdateNotificationSettingsParams<'_>,
-    ) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
-        let user_id = session.user_id.clone().ok_or("Not authenticated")?;
+        server_id: &str,
+        channel_name: &str,
+        topic_locked: bool,
+    ) -> Result<(), EngineError> {
+        let (pool, channel_id, set_by) = self
+            .prepare_channel_flag_change(session_id, server_id, channel_name)
+            .await?;
 
-        match params.level {
-            "all" | "mentions" | "none" | "default" => {}
-            _ => return Err("Invalid level. Must be: all, mentions, none, default".into()),
-        }
+        crate::db::queries::moderation::set_topic_locked(&pool, &channel_id, topic_locked)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to set topic lock: {e}")))?;
 
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-        let id = Uuid::new_v4().to_string();
+        if let Some(mut ch) = self.channels.get_mut(&channel_id) {
+            ch.topic_locked = topic_locked;
+        }
 
-        let db_params = crate::db::models::UpsertNotificationParams {
-            id: &id,
-            user_id: &user_id,
-            server_id: Some(params.server_id),
-            channel_id: params.channel_id,
-            level: params.level,
-            suppress_everyone: params.suppress_everyone,
-            suppress_roles: params.suppress_roles,
-            muted: params.muted,
-            mute_until: params.mute_until,
+        let event = ChatEvent::TopicLockUpdate {
+            server_id: server_id.to_string(),
+            channel: channel_name.to_string(),
+            topic_locked,
+            set_by,
         };
-        crate::db::queries::notifications::upsert_notification_setting(pool, &db_params)
-            .await
-            .map_err(|e| format!("Failed to update notification settings: {e}"))?;
+        self.broadcast_to_server(server_id, &event);
 
         Ok(())
     }
 
-    /// Get notification settings for a user in a server.
-    pub async fn get_notification_settings(
+    /// Shared checks for channel flag setters: requires MANAGE_CHANNELS and a
+    /// database, and resolves the channel. Returns (pool, channel_id, actor nickname).
+    async fn prepare_channel_flag_change(
         &self,
         session_id: SessionId,
         server_id: &str,
-    ) -> Result<Vec<super::events::NotificationSettingInfo>, String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
-        let user_id = session.user_id.clone().ok_or("Not authenticated")?;
+        channel_name: &str,
+    ) -> Result<(SqlitePool, String, String), EngineError> {
+        self.require_permission(session_id, server_id, None, Permissions::MANAGE_CHANNELS)
+            .await?;
 
-        let pool = self.db.as_ref().ok_or("No database configured")?;
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
+        };
 
-        let rows =
-            crate::db::queries::notifications::get_notification_settings(pool, &user_id, server_id)
-                .await
-                .map_err(|e| format!("Failed to get notification settings: {e}"))?;
+        let channel_id = self
+            .channel_name_index
+            .get(&(server_id.to_string(), channel_name.to_string()))
+            .map(|v| v.clone())
+            .ok_or_else(|| EngineError::NotFound("Channel not found".into()))?;
 
-        Ok(rows
-            .into_iter()
-            .map(|r| super::events::NotificationSettingInfo {
-                id: r.id,
-                server_id: r.server_id,
-                channel_id: r.channel_id,
-                level: r.level,
-                suppress_everyone: r.suppress_everyone != 0,
-                suppress_roles: r.suppress_roles != 0,
-                muted: r.muted != 0,
-                mute_until: r.mute_until,
-            })
-            .collect())
-    }
+        let set_by = self
+            .get_session(session_id)
+            .map(|s| s.nickname.clone())
+            .unwrap_or_default();
 
-    // ── Pinning ─────────────────────────────────────────────────
+        Ok((pool.clone(), channel_id, set_by))
+    }
 
-    /// Pin a message in a channel. Requires MANAGE_MESSAGES permission or ownership of the message.
-    pub async fn pin_message(
+    /// Bulk delete messages in a channel (up to 100).
+    pub async fn bulk_delete_messages(
         &self,
         session_id: SessionId,
         server_id: &str,
         channel_name: &str,
-        message_id: &str,
-    ) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-
-        let channel_name = normalize_channel_name(channel_name);
-        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
-
-        // Look up the message
-        let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Message not found")?;
+        message_ids: Vec<String>,
+    ) -> Result<(), EngineError> {
+        self.require_permission(session_id, server_id, None, Permissions::MANAGE_MESSAGES)
+            .await?;
 
-        // Check permission: MANAGE_MESSAGES or own message
-        let user_id = session.user_id.as_deref().ok_or("AUTH_REQUIRED")?;
-        let is_own = msg.sender_id == user_id || msg.sender_nick == session.nickname;
-        if !is_own {
-            let perms = self
-                .get_effective_permissions(server_id, Some(&channel_id), user_id)
-                .await;
-            if !perms.contains(Permissions::MANAGE_MESSAGES) {
-                return Err("FORBIDDEN: insufficient permissions to pin messages".into());
-            }
+        if message_ids.is_empty() {
+            return Err(EngineError::Validation("No messages to delete".into()));
         }
-
-        // Check pin count limit (max 50 per channel)
-        let pin_count = crate::db::queries::pins::count_pins(pool, &channel_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
-        if pin_count >= 50 {
-            return Err("Channel has reached the maximum of 50 pinned messages".into());
+        if message_ids.len() > 100 {
+            return Err(EngineError::Validation(
+                "Cannot bulk delete more than 100 messages".into(),
+            ));
         }
 
-        let pin_id = Uuid::new_v4().to_string();
-        crate::db::queries::pins::pin_message(pool, &pin_id, &channel_id, message_id, user_id)
-            .await
-            .map_err(|e| format!("Failed to pin message: {e}"))?;
-
-        let pin = PinnedMessageInfo {
-            id: pin_id,
-            message_id: message_id.to_string(),
-            channel_id: channel_id.clone(),
-            pinned_by: user_id.to_string(),
-            pinned_at: Utc::now().to_rfc3339(),
-            from: msg.sender_nick,
-            content: msg.content,
-            timestamp: msg.created_at,
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
         };
 
-        let event = ChatEvent::MessagePin {
+        crate::db::queries::moderation::bulk_delete_messages(pool, &message_ids)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to bulk delete: {e}")))?;
+
+        // Broadcast
+        let event = ChatEvent::BulkMessageDelete {
             server_id: server_id.to_string(),
-            channel: channel_name,
-            pin,
+            channel: channel_name.to_string(),
+            message_ids,
         };
-        self.broadcast_to_channel(&channel_id, &event, None);
+        self.broadcast_to_server(server_id, &event);
 
         Ok(())
     }
 
-    /// Unpin a message from a channel. Requires MANAGE_MESSAGES permission.
-    pub async fn unpin_message(
+    /// Get audit log entries for a server.
+    pub async fn get_audit_log(
         &self,
         session_id: SessionId,
         server_id: &str,
-        channel_name: &str,
-        message_id: &str,
-    ) -> Result<(), String> {
-        let channel_name = normalize_channel_name(channel_name);
-        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+        action_type: Option<&str>,
+        limit: i64,
+        before: Option<&str>,
+    ) -> Result<(), EngineError> {
+        self.require_read_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
+            .await?;
 
-        self.require_permission(
-            session_id,
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
+        };
+
+        let limit = limit.clamp(1, 100);
+        let rows = crate::db::queries::audit_log::list_entries(
+            pool,
             server_id,
-            Some(&channel_id),
-            Permissions::MANAGE_MESSAGES,
+            action_type,
+            limit,
+            before,
         )
-        .await?;
-
-        let pool = self.db.as_ref().ok_or("No database configured")?;
+        .await
+        .map_err(|e| EngineError::Internal(format!("Failed to get audit log: {e}")))?;
 
-        crate::db::queries::pins::unpin_message(pool, &channel_id, message_id)
-            .await
-            .map_err(|e| format!("Failed to unpin message: {e}"))?;
+        let entries: Vec<AuditLogEntry> = rows
+            .into_iter()
+            .map(|r| AuditLogEntry {
+                id: r.id,
+                actor_id: r.actor_id,
+                action_type: r.action_type,
+                target_type: r.target_type,
+                target_id: r.target_id,
+                reason: r.reason,
+                changes: r.changes,
+                created_at: r.created_at,
+            })
+            .collect();
 
-        let event = ChatEvent::MessageUnpin {
-            server_id: server_id.to_string(),
-            channel: channel_name,
-            message_id: message_id.to_string(),
-        };
-        self.broadcast_to_channel(&channel_id, &event, None);
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::AuditLogEntries {
+                server_id: server_id.to_string(),
+                entries,
+            });
+        }
 
         Ok(())
     }
 
-    /// Get all pinned messages in a channel. Sends PinnedMessages event to the requesting session.
-    pub async fn get_pinned_messages(
-        &self,
-        session_id: SessionId,
-        server_id: &str,
-        channel_name: &str,
-    ) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
-        let pool = self.db.as_ref().ok_or("No database configured")?;
+    // ── AutoMod ──
+
+    /// Create an automod rule.
+    pub async fn create_automod_rule(
+        &self,
+        session_id: SessionId,
+        params: &crate::db::models::CreateAutomodRuleParams<'_>,
+    ) -> Result<(), EngineError> {
+        let server_id = params.server_id;
+        let name = params.name;
+        let rule_type = params.rule_type;
+        let config = params.config;
+        let action_type = params.action_type;
+        let timeout_duration_seconds = params.timeout_duration_seconds;
+        self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
+            .await?;
+
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
+        };
 
-        let channel_name = normalize_channel_name(channel_name);
-        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
+        // Validate rule_type
+        if !automod::RULE_TYPES.contains(&rule_type) {
+            return Err(EngineError::Validation(format!(
+                "Invalid rule type. Must be one of: {}",
+                automod::RULE_TYPES.join(", ")
+            )));
+        }
+        // Validate action_type
+        if !["delete", "timeout", "flag"].contains(&action_type) {
+            return Err(EngineError::Validation(
+                "Invalid action type. Must be 'delete', 'timeout', or 'flag'".into(),
+            ));
+        }
+        // Validate config JSON matches rule_type schema
+        validate_automod_config(rule_type, config)?;
 
-        let pin_rows = crate::db::queries::pins::get_pinned_messages(pool, &channel_id)
+        let rule_id = Uuid::new_v4().to_string();
+        let db_params = crate::db::models::CreateAutomodRuleParams {
+            id: &rule_id,
+            server_id,
+            name,
+            rule_type,
+            config,
+            action_type,
+            timeout_duration_seconds,
+        };
+        crate::db::queries::automod::create_rule(pool, &db_params)
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to create automod rule: {e}")))?;
+        self.reload_automod_rules(pool, server_id).await;
 
-        let mut pins = Vec::new();
-        for row in pin_rows {
-            // Look up message content for each pin
-            let (from, content, timestamp) = match crate::db::queries::messages::get_message_by_id(
-                pool,
-                &row.message_id,
-            )
-            .await
-            {
-                Ok(Some(msg)) => (msg.sender_nick, msg.content, msg.created_at),
-                _ => (
-                    "unknown".to_string(),
-                    "[deleted]".to_string(),
-                    String::new(),
-                ),
-            };
+        let rule = AutomodRuleInfo {
+            id: rule_id,
+            name: name.to_string(),
+            enabled: true,
+            rule_type: rule_type.to_string(),
+            config: config.to_string(),
+            action_type: action_type.to_string(),
+            timeout_duration_seconds,
+        };
 
-            pins.push(PinnedMessageInfo {
-                id: row.id,
-                message_id: row.message_id,
-                channel_id: row.channel_id,
-                pinned_by: row.pinned_by,
-                pinned_at: row.pinned_at,
-                from,
-                content,
-                timestamp,
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::AutomodRuleUpdate {
+                server_id: server_id.to_string(),
+                rule,
             });
         }
 
-        let _ = session.send(ChatEvent::PinnedMessages {
-            server_id: server_id.to_string(),
-            channel: channel_name,
-            pins,
-        });
-
         Ok(())
     }
 
-    // ── Threads ─────────────────────────────────────────────────
-
-    /// Create a thread from a message in a channel.
-    pub async fn create_thread(
+    /// Update an automod rule.
+    pub async fn update_automod_rule(
         &self,
         session_id: SessionId,
-        server_id: &str,
-        parent_channel_name: &str,
-        name: &str,
-        message_id: &str,
-        is_private: bool,
-    ) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
-        let _user_id = session.user_id.as_deref().ok_or("AUTH_REQUIRED")?;
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-
-        let parent_channel_name = normalize_channel_name(parent_channel_name);
-        let parent_channel_id = self.resolve_channel_id(server_id, &parent_channel_name)?;
+        params: &crate::db::models::UpdateAutomodRuleParams<'_>,
+    ) -> Result<(), EngineError> {
+        let server_id = params.server_id;
+        let rule_id = params.rule_id;
+        let name = params.name;
+        let enabled = params.enabled;
+        let config = params.config;
+        let action_type = params.action_type;
+        let timeout_duration_seconds = params.timeout_duration_seconds;
 
-        // Validate thread name
-        if name.is_empty() || name.len() > 100 {
-            return Err("Thread name must be between 1 and 100 characters".into());
-        }
+        self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
+            .await?;
 
-        let channel_type = if is_private {
-            "private_thread"
-        } else {
-            "public_thread"
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
         };
 
-        let thread_id = Uuid::new_v4().to_string();
-        let thread_name = normalize_channel_name(name);
-
-        // Check name uniqueness within server
-        if self
-            .channel_name_index
-            .contains_key(&(server_id.to_string(), thread_name.clone()))
-        {
-            return Err(format!(
-                "A channel or thread named {thread_name} already exists"
+        // Validate action_type
+        if !["delete", "timeout", "flag"].contains(&action_type) {
+            return Err(EngineError::Validation(
+                "Invalid action type. Must be 'delete', 'timeout', or 'flag'".into(),
             ));
         }
 
-        crate::db::queries::threads::create_thread(
+        // Fetch existing rule to get rule_type for config validation
+        let existing = crate::db::queries::automod::get_rule(pool, rule_id)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to fetch automod rule: {e}")))?
+            .ok_or_else(|| EngineError::NotFound("Automod rule not found".into()))?;
+        validate_automod_config(&existing.rule_type, config)?;
+
+        crate::db::queries::automod::update_rule(
             pool,
-            &thread_id,
-            server_id,
-            &thread_name,
-            channel_type,
-            message_id,
-            1440, // default auto-archive: 24h
+            rule_id,
+            name,
+            enabled,
+            config,
+            action_type,
+            timeout_duration_seconds,
         )
         .await
-        .map_err(|e| format!("Failed to create thread: {e}"))?;
+        .map_err(|e| EngineError::Internal(format!("Failed to update automod rule: {e}")))?;
+        self.reload_automod_rules(pool, server_id).await;
 
-        // Add to in-memory state
-        let mut ch = ChannelState::new(
-            thread_id.clone(),
-            server_id.to_string(),
-            thread_name.clone(),
-        );
-        ch.channel_type = channel_type.to_string();
-        ch.thread_parent_message_id = Some(message_id.to_string());
-        ch.auto_archive_minutes = 1440;
-        ch.is_private = is_private;
+        let rule = AutomodRuleInfo {
+            id: rule_id.to_string(),
+            name: name.to_string(),
+            enabled,
+            rule_type: existing.rule_type,
+            config: config.to_string(),
+            action_type: action_type.to_string(),
+            timeout_duration_seconds,
+        };
 
-        self.channel_name_index.insert(
-            (server_id.to_string(), thread_name.clone()),
-            thread_id.clone(),
-        );
-        if let Some(mut srv) = self.servers.get_mut(server_id) {
-            srv.channel_ids.insert(thread_id.clone());
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::AutomodRuleUpdate {
+                server_id: server_id.to_string(),
+                rule,
+            });
         }
-        self.channels.insert(thread_id.clone(), ch);
 
-        let thread_info = ThreadInfo {
-            id: thread_id,
-            name: thread_name,
-            channel_type: channel_type.to_string(),
-            parent_message_id: Some(message_id.to_string()),
-            archived: false,
-            auto_archive_minutes: 1440,
-            message_count: 0,
-            created_at: Utc::now().to_rfc3339(),
-        };
+        Ok(())
+    }
 
-        let event = ChatEvent::ThreadCreate {
-            server_id: server_id.to_string(),
-            parent_channel: parent_channel_name,
-            thread: thread_info,
+    /// Delete an automod rule.
+    pub async fn delete_automod_rule(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        rule_id: &str,
+    ) -> Result<(), EngineError> {
+        self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
+            .await?;
+
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
         };
-        self.broadcast_to_channel(&parent_channel_id, &event, None);
+
+        crate::db::queries::automod::delete_rule(pool, rule_id)
+            .await
+            .map_err(|e| EngineError::Internal(format!("Failed to delete automod rule: {e}")))?;
+        self.reload_automod_rules(pool, server_id).await;
+
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::AutomodRuleDelete {
+                server_id: server_id.to_string(),
+                rule_id: rule_id.to_string(),
+            });
+        }
 
         Ok(())
     }
 
-    /// Archive a thread. Requires MANAGE_CHANNELS permission.
-    pub async fn archive_thread(
+    /// List automod rules for a server.
+    pub async fn list_automod_rules(
         &self,
         session_id: SessionId,
         server_id: &str,
-        thread_id: &str,
-    ) -> Result<(), String> {
-        self.require_permission(
-            session_id,
-            server_id,
-            Some(thread_id),
-            Permissions::MANAGE_CHANNELS,
-        )
-        .await?;
+    ) -> Result<(), EngineError> {
+        self.require_read_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
+            .await?;
 
-        let pool = self.db.as_ref().ok_or("No database configured")?;
+        let Some(pool) = &self.db else {
+            return Err(EngineError::NoDatabase);
+        };
 
-        crate::db::queries::threads::archive_thread(pool, thread_id)
+        let rows = crate::db::queries::automod::list_rules(pool, server_id)
             .await
-            .map_err(|e| format!("Failed to archive thread: {e}"))?;
-
-        // Update in-memory state
-        let thread_info = if let Some(mut ch) = self.channels.get_mut(thread_id) {
-            ch.archived = true;
-            ThreadInfo {
-                id: ch.id.clone(),
-                name: ch.name.clone(),
-                channel_type: ch.channel_type.clone(),
-                parent_message_id: ch.thread_parent_message_id.clone(),
-                archived: true,
-                auto_archive_minutes: ch.auto_archive_minutes,
-                message_count: 0, // not tracked in-memory
-                created_at: ch.created_at.to_rfc3339(),
-            }
-        } else {
-            return Err("Thread not found".into());
-        };
+            .map_err(|e| EngineError::Internal(format!("Failed to list automod rules: {e}")))?;
 
-        let event = ChatEvent::ThreadUpdate {
-            server_id: server_id.to_string(),
-            thread: thread_info,
-        };
-        self.broadcast_to_channel(thread_id, &event, None);
+        let rules: Vec<AutomodRuleInfo> = rows
+            .into_iter()
+            .map(|r| AutomodRuleInfo {
+                id: r.id,
+                name: r.name,
+                enabled: r.enabled != 0,
+                rule_type: r.rule_type,
+                config: r.config,
+                action_type: r.action_type,
+                timeout_duration_seconds: r.timeout_duration_seconds,
+            })
+            .collect();
+
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::AutomodRuleList {
+                server_id: server_id.to_string(),
+                rules,
+            });
+        }
 
         Ok(())
     }
 
-    /// List threads for a channel. Sends ThreadList event to the requesting session.
-    pub async fn list_threads(
+    /// List messages awaiting automod review. Requires MANAGE_MESSAGES.
+    pub async fn list_automod_queue(
         &self,
         session_id: SessionId,
         server_id: &str,
-        channel_name: &str,
-    ) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-
-        let channel_name = normalize_channel_name(channel_name);
-        let channel_id = self.resolve_channel_id(server_id, &channel_name)?;
-
-        let rows =
-            crate::db::queries::threads::get_threads_for_channel(pool, &channel_id, server_id)
-                .await
-                .map_err(|e| format!("DB error: {e}"))?;
+    ) -> Result<(), EngineError> {
+        self.require_read_permission(session_id, server_id, None, Permissions::MANAGE_MESSAGES)
+            .await?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
 
-        let threads: Vec<ThreadInfo> = rows
+        let entries = crate::db::queries::automod::list_open_queue(pool, server_id)
+            .await?
             .into_iter()
-            .map(|row| ThreadInfo {
-                id: row.id,
-                name: row.name,
-                channel_type: row.channel_type,
-                parent_message_id: row.thread_parent_message_id,
-                archived: row.archived != 0,
-                auto_archive_minutes: row.thread_auto_archive_minutes,
-                message_count: 0, // would need a count query; returning 0 for now
-                created_at: row.created_at,
-            })
+            .map(automod_queue_row_to_info)
             .collect();
-
-        let _ = session.send(ChatEvent::ThreadList {
-            server_id: server_id.to_string(),
-            channel: channel_name,
-            threads,
-        });
-
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::AutomodQueue {
+                server_id: server_id.to_string(),
+                entries,
+            });
+        }
         Ok(())
     }
 
-    // ── Bookmarks ───────────────────────────────────────────────
-
-    /// Add a bookmark on a message for the authenticated user.
-    pub async fn add_bookmark(
+    /// Approve or reject a message in the automod review queue. Requires
+    /// MANAGE_MESSAGES. Approving a held message delivers it to its channel as
+    /// its author (without attachments); rejecting it discards it. Flagged
+    /// messages were already delivered, so reviewing them only closes the entry.
+    pub async fn review_automod_entry(
         &self,
         session_id: SessionId,
-        message_id: &str,
-        note: Option<&str>,
-    ) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
-        let user_id = session.user_id.as_deref().ok_or("AUTH_REQUIRED")?;
-        let pool = self.db.as_ref().ok_or("No database configured")?;
-
-        // Look up the message for BookmarkInfo
-        let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Message not found")?;
+        server_id: &str,
+        entry_id: &str,
+        approve: bool,
+    ) -> Result<(), EngineError> {
+        let actor_id = self
+            .require_permission(session_id, server_id, None, Permissions::MANAGE_MESSAGES)
+            .await?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let entry = crate::db::queries::automod::get_queue_entry(pool, entry_id)
+            .await?
+            .filter(|e| e.server_id == server_id)
+            .ok_or_else(|| EngineError::NotFound("Automod queue entry not found".into()))?;
+        let status = if approve { "approved" } else { "rejected" };
+        if !crate::db::queries::automod::review_queue_entry(pool, entry_id, status, &actor_id)
+            .await?
+        {
+            return Err(EngineError::Conflict(
+                "Automod queue entry was already reviewed".into(),
+            ));
+        }
 
-        let bookmark_id = Uuid::new_v4().to_string();
-        crate::db::queries::bookmarks::add_bookmark(pool, &bookmark_id, user_id, message_id, note)
-            .await
-            .map_err(|e| format!("Failed to add bookmark: {e}"))?;
+        if approve && entry.status == "pending" {
+            self.deliver_held_message(pool, &entry).await?;
+        }
 
-        let bookmark = BookmarkInfo {
-            id: bookmark_id,
-            message_id: message_id.to_string(),
-            channel_id: msg.channel_id.unwrap_or_default(),
-            from: msg.sender_nick,
-            content: msg.content,
-            timestamp: msg.created_at,
-            note: note.map(|s| s.to_string()),
-            created_at: Utc::now().to_rfc3339(),
+        let audit_action = if approve {
+            "automod_approve"
+        } else {
+            "automod_reject"
         };
+        if let Err(e) = crate::db::queries::audit_log::create_entry(
+            pool,
+            &crate::db::models::CreateAuditLogParams {
+                id: &Uuid::new_v4().to_string(),
+                server_id,
+                actor_id: &actor_id,
+                action_type: audit_action,
+                target_type: Some("user"),
+                target_id: Some(&entry.user_id),
+                reason: Some(&entry.rule_name),
+                changes: Some(&serde_json::json!({ "queue_id": entry_id }).to_string()),
+            },
+        )
+        .await
+        {
+            warn!(error = %e, "Failed to write automod review audit log entry");
+        }
 
-        let _ = session.send(ChatEvent::BookmarkAdd { bookmark });
-
+        if let Some(entry) = crate::db::queries::automod::get_queue_entry(pool, entry_id).await?
+            && let Some(session) = self.get_session(session_id)
+        {
+            let _ = session.send(ChatEvent::AutomodQueueUpdate {
+                server_id: server_id.to_string(),
+                entry: automod_queue_row_to_info(entry),
+            });
+        }
         Ok(())
     }
 
-    /// Remove a bookmark for the authenticated user.
-    pub async fn remove_bookmark(
+    /// Persist and broadcast a held message once a moderator approves it.
+    async fn deliver_held_message(
         &self,
-        session_id: SessionId,
-        message_id: &str,
-    ) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
-        let user_id = session.user_id.as_deref().ok_or("AUTH_REQUIRED")?;
-        let pool = self.db.as_ref().ok_or("No database configured")?;
+        pool: &SqlitePool,
+        entry: &crate::db::models::AutomodQueueRow,
+    ) -> Result<(), EngineError> {
+        let channel_name = self.resolve_channel_name_from_id(&entry.channel_id)?;
+        let msg_id = Uuid::new_v4();
+        let id = msg_id.to_string();
+        let params = crate::db::queries::messages::InsertMessageParams {
+            id: &id,
+            server_id: &entry.server_id,
+            channel_id: &entry.channel_id,
+            sender_id: &entry.user_id,
+            sender_nick: &entry.user_nick,
+            content: &entry.content,
+            reply_to_id: entry.reply_to_id.as_deref(),
+        };
+        crate::db::queries::messages::insert_message(pool, &params).await?;
 
-        crate::db::queries::bookmarks::remove_bookmark(pool, user_id, message_id)
-            .await
-            .map_err(|e| format!("Failed to remove bookmark: {e}"))?;
+        let event = ChatEvent::Message {
+            id: msg_id,
+            server_id: Some(entry.server_id.clone()),
+            from: entry.user_nick.clone(),
+            target: channel_name.clone(),
+            content: entry.content.clone(),
+            timestamp: Utc::now(),
+            avatar_url: None,
+            reply_to: self.reply_info(entry.reply_to_id.as_deref()).await,
+            attachments: None,
+        };
+        self.broadcast_to_channel(&entry.channel_id, &event, None);
+        self.spawn_link_unfurl(
+            msg_id,
+            &entry.server_id,
+            &entry.channel_id,
+            &channel_name,
+            &entry.content,
+        );
+        Ok(())
+    }
 
-        let _ = session.send(ChatEvent::BookmarkRemove {
-            message_id: message_id.to_string(),
-        });
+    // ── Reports ──
 
+    /// File a report and send it back to the reporter's session.
+    pub async fn report(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        message_id: Option<&str>,
+        target_user_id: Option<&str>,
+        reason: &str,
+    ) -> Result<(), EngineError> {
+        let reporter_id = self.session_user_id(session_id)?;
+        let report = self
+            .create_report(&reporter_id, server_id, message_id, target_user_id, reason)
+            .await?;
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::ReportUpdate {
+                server_id: server_id.to_string(),
+                report,
+            });
+        }
         Ok(())
     }
 
-    /// List all bookmarks for the authenticated user. Sends BookmarkList event to the session.
-    pub async fn list_bookmarks(&self, session_id: SessionId) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
-        let user_id = session.user_id.as_deref().ok_or("AUTH_REQUIRED")?;
-        let pool = self.db.as_ref().ok_or("No database configured")?;
+    /// Report a message, or with no `message_id` the member `target_user_id`.
+    /// The reporter must belong to the server and, for a message, be able to
+    /// see its channel.
+    pub async fn create_report(
+        &self,
+        reporter_id: &str,
+        server_id: &str,
+        message_id: Option<&str>,
+        target_user_id: Option<&str>,
+        reason: &str,
+    ) -> Result<ReportInfo, EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        if !self.user_is_server_member(server_id, reporter_id) {
+            return Err(EngineError::Forbidden("Not a member of this server".into()));
+        }
+        let reason = reason.trim();
+        if reason.is_empty() || reason.chars().count() > MAX_REPORT_REASON_LENGTH {
+            return Err(EngineError::Validation(format!(
+                "Report reason must be 1-{MAX_REPORT_REASON_LENGTH} characters"
+            )));
+        }
+
+        let (target_user_id, channel_id, content) = match message_id {
+            Some(message_id) => {
+                let msg = crate::db::queries::messages::get_message_by_id(pool, message_id)
+                    .await?
+                    .filter(|m| m.server_id.as_deref() == Some(server_id))
+                    .ok_or_else(|| EngineError::NotFound("Message not found".into()))?;
+                let channel_id = msg
+                    .channel_id
+                    .ok_or_else(|| EngineError::NotFound("Message not found".into()))?;
+                let perms = self
+                    .get_effective_permissions(server_id, Some(&channel_id), reporter_id)
+                    .await;
+                if !perms.contains(Permissions::VIEW_CHANNELS) {
+                    return Err(EngineError::NotFound("Message not found".into()));
+                }
+                (msg.sender_id, Some(channel_id), Some(msg.content))
+            }
+            None => {
+                let target = target_user_id.ok_or_else(|| {
+                    EngineError::Validation("A report needs a message or a user".into())
+                })?;
+                (target.to_string(), None, None)
+            }
+        };
+        if target_user_id == reporter_id {
+            return Err(EngineError::Validation("You can't report yourself".into()));
+        }
 
-        let rows = crate::db::queries::bookmarks::list_bookmarks(pool, user_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
+        let id = Uuid::new_v4().to_string();
+        let params = crate::db::models::CreateReportParams {
+            id: &id,
+            server_id,
+            reporter_id,
+            target_user_id: &target_user_id,
+            message_id,
+            channel_id: channel_id.as_deref(),
+            message_content: content.as_deref(),
+            reason,
+        };
+        match crate::db::queries::reports::create_report(pool, &params).await {
+            Ok(()) => {}
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                return Err(EngineError::Conflict(
+                    "You already have an open report about this".into(),
+                ));
+            }
+            Err(e) => return Err(e.into()),
+        }
+        info!(%server_id, report_id = %id, "report filed");
 
-        let mut bookmarks = Vec::new();
-        for row in rows {
-            // Look up message content for each bookmark
-            let (from, content, timestamp, channel_id) =
-                match crate::db::queries::messages::get_message_by_id(pool, &row.message_id).await {
-                    Ok(Some(msg)) => (
-                        msg.sender_nick,
-                        msg.content,
-                        msg.created_at,
-                        msg.channel_id.unwrap_or_default(),
-                    ),
-                    _ => (
-                        "unknown".to_string(),
-                        "[deleted]".to_string(),
-                        String::new(),
-                        String::new(),
-                    ),
-                };
+        let row = crate::db::queries::reports::get_report(pool, &id)
+            .await?
+            .ok_or_else(|| EngineError::Internal("Report vanished after insert".into()))?;
+        Ok(report_row_to_info(row, Vec::new()))
+    }
 
-            bookmarks.push(BookmarkInfo {
-                id: row.id,
-                message_id: row.message_id,
-                channel_id,
-                from,
-                content,
-                timestamp,
-                note: row.note,
-                created_at: row.created_at,
+    /// List open reports in a server. Requires MANAGE_MESSAGES.
+    pub async fn list_reports(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+    ) -> Result<(), EngineError> {
+        let user_id = self.session_user_id(session_id)?;
+        let reports = self.fetch_open_reports(&user_id, server_id).await?;
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::ReportList {
+                server_id: server_id.to_string(),
+                reports,
             });
         }
+        Ok(())
+    }
 
-        let _ = session.send(ChatEvent::BookmarkList { bookmarks });
+    /// Open reports in a server, oldest first, each message report with the
+    /// messages around it. Requires MANAGE_MESSAGES; reports from channels the
+    /// moderator can't view are left out.
+    pub async fn fetch_open_reports(
+        &self,
+        user_id: &str,
+        server_id: &str,
+    ) -> Result<Vec<ReportInfo>, EngineError> {
+        self.require_user_read_permission(user_id, server_id, None, Permissions::MANAGE_MESSAGES)
+            .await?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let rows = crate::db::queries::reports::list_open_reports(pool, server_id).await?;
+        let mut reports = Vec::with_capacity(rows.len());
+        for row in rows {
+            if !self.can_view_report(user_id, &row).await {
+                continue;
+            }
+            let context = report_context(pool, &row).await?;
+            reports.push(report_row_to_info(row, context));
+        }
+        Ok(reports)
+    }
 
+    /// Whether a moderator may see a report: message reports quote their
+    /// channel, so they need VIEW_CHANNELS there.
+    async fn can_view_report(&self, user_id: &str, row: &crate::db::models::ReportRow) -> bool {
+        let Some(channel_id) = row.channel_id.as_deref() else {
+            return true;
+        };
+        self.get_effective_permissions(&row.server_id, Some(channel_id), user_id)
+            .await
+            .contains(Permissions::VIEW_CHANNELS)
+    }
+
+    /// Resolve a report and send the result to the moderator's session.
+    pub async fn resolve_report(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        params: &ResolveReportParams<'_>,
+    ) -> Result<(), EngineError> {
+        let actor_id = self.session_user_id(session_id)?;
+        let report = self.resolve_report_by(&actor_id, server_id, params).await?;
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::ReportUpdate {
+                server_id: server_id.to_string(),
+                report,
+            });
+        }
         Ok(())
     }
 
-    // ── Phase 6: Moderation ─────────────────────────────────────
+    /// Close an open report with `params.action`: `dismiss`, or `delete`,
+    /// `timeout`, `kick` or `ban`, which go through the matching moderation
+    /// method and so need its permission as well as MANAGE_MESSAGES. The
+    /// reporter is told the outcome.
+    pub async fn resolve_report_by(
+        &self,
+        actor_id: &str,
+        server_id: &str,
+        params: &ResolveReportParams<'_>,
+    ) -> Result<ReportInfo, EngineError> {
+        self.require_user_permission(actor_id, server_id, None, Permissions::MANAGE_MESSAGES)
+            .await?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let report = crate::db::queries::reports::get_report(pool, params.report_id)
+            .await?
+            .filter(|r| r.server_id == server_id)
+            .ok_or_else(|| EngineError::NotFound("Report not found".into()))?;
+        if !self.can_view_report(actor_id, &report).await {
+            return Err(EngineError::NotFound("Report not found".into()));
+        }
+        if report.status != "open" {
+            return Err(EngineError::Conflict("Report was already resolved".into()));
+        }
+        if !REPORT_ACTIONS.contains(&params.action) {
+            return Err(EngineError::Validation(format!(
+                "Invalid report action. Must be one of: {}",
+                REPORT_ACTIONS.join(", ")
+            )));
+        }
+        if params.action == "delete" && report.message_id.is_none() {
+            return Err(EngineError::Validation(
+                "Only message reports can be resolved by deleting the message".into(),
+            ));
+        }
+        let timeout_seconds = params.timeout_seconds.unwrap_or(60 * 60);
+        if params.action == "timeout"
+            && !(1..=automod::MAX_TIMEOUT_SECONDS).contains(&timeout_seconds)
+        {
+            return Err(EngineError::Validation(format!(
+                "Timeout must be between 1 and {} seconds",
+                automod::MAX_TIMEOUT_SECONDS
+            )));
+        }
 
-    /// Broadcast a ChatEvent to all connected sessions that belong to a server.
-    pub fn broadcast_to_server(&self, server_id: &str, event: &ChatEvent) {
-        let Some(server) = self.servers.get(server_id) else {
-            return;
+        // Claim the report first so two moderators can't both act on it
+        if !crate::db::queries::reports::resolve_report(
+            pool,
+            &report.id,
+            params.action,
+            params.note,
+            actor_id,
+        )
+        .await?
+        {
+            return Err(EngineError::Conflict("Report was already resolved".into()));
+        }
+
+        let reason = params.note.unwrap_or(&report.reason);
+        let target = report.target_user_id.as_str();
+        let result = match params.action {
+            "delete" => {
+                let message_id = report.message_id.as_deref().unwrap_or_default();
+                self.delete_message_by(actor_id, message_id).await
+            }
+            "timeout" => {
+                let until = (Utc::now() + chrono::Duration::seconds(timeout_seconds as i64))
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string();
+                self.timeout_member_by(actor_id, server_id, target, Some(&until), Some(reason))
+                    .await
+            }
+            "kick" => {
+                self.kick_member_by(actor_id.to_string(), server_id, target, Some(reason), None)
+                    .await
+            }
+            "ban" => {
+                let params = BanParams {
+                    reason: Some(reason),
+                    ..Default::default()
+                };
+                self.ban_member_by(actor_id.to_string(), server_id, target, &params)
+                    .await
+            }
+            _ => Ok(()),
         };
-        let member_ids: Vec<String> = server.member_user_ids.iter().cloned().collect();
-        drop(server);
+        if let Err(e) = result {
+            if let Err(e) = crate::db::queries::reports::reopen_report(pool, &report.id).await {
+                error!(error = %e, report_id = %report.id, "failed to reopen report");
+            }
+            return Err(e);
+        }
 
+        let changes = serde_json::json!({
+            "action": params.action,
+            "target_user_id": target,
+            "message_id": report.message_id,
+        })
+        .to_string();
+        if let Err(e) = crate::db::queries::audit_log::create_entry(
+            pool,
+            &crate::db::models::CreateAuditLogParams {
+                id: &Uuid::new_v4().to_string(),
+                server_id,
+                actor_id,
+                action_type: "report_resolve",
+                target_type: Some("report"),
+                target_id: Some(&report.id),
+                reason: params.note,
+                changes: Some(&changes),
+            },
+        )
+        .await
+        {
+            warn!(error = %e, "Failed to write report audit log entry");
+        }
+
+        let row = crate::db::queries::reports::get_report(pool, &report.id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Report not found".into()))?;
+        let resolved = report_row_to_info(row, Vec::new());
+        // The reporter learns the outcome but not which moderator handled it
+        self.send_to_user(
+            &resolved.reporter_id,
+            &ChatEvent::ReportUpdate {
+                server_id: server_id.to_string(),
+                report: ReportInfo {
+                    resolved_by: None,
+                    ..resolved.clone()
+                },
+            },
+        );
+        Ok(resolved)
+    }
+
+    /// Send an event to every session signed in as `user_id`.
+    fn send_to_user(&self, user_id: &str, event: &ChatEvent) {
         for session in self.sessions.iter() {
-            if let Some(uid) = &session.user_id
-                && member_ids.contains(uid)
-            {
+            if session.user_id.as_deref() == Some(user_id) {
                 let _ = session.send(event.clone());
             }
         }
     }
 
-    /// Kick a member from a server.
-    pub async fn kick_member(
+    // ── Infractions ──
+
+    /// Warn a member. The moderator gets the member's updated ledger back.
+    pub async fn warn_member(
         &self,
         session_id: SessionId,
         server_id: &str,
         target_user_id: &str,
-        reason: Option<&str>,
-    ) -> Result<(), String> {
-        self.kick_member_in_channel(session_id, server_id, target_user_id, reason, None)
+        reason: &str,
+        expires_in_seconds: Option<u64>,
+    ) -> Result<(), EngineError> {
+        let actor_id = self.session_user_id(session_id)?;
+        self.warn_member_by(
+            &actor_id,
+            server_id,
+            target_user_id,
+            reason,
+            expires_in_seconds,
+        )
+        .await?;
+        self.list_infractions(session_id, server_id, target_user_id)
             .await
     }
 
-    /// Kick a member with channel-scoped permission check.
-    /// When `channel_id` is Some, the permission check considers channel overrides,
-    /// allowing moderators with per-channel KICK_MEMBERS to kick from that channel.
-    pub async fn kick_member_in_channel(
+    /// Warn a member on behalf of `actor_id`, who needs KICK_MEMBERS. The
+    /// warning counts as a strike until it expires (90 days by default) and
+    /// may trip the server's escalation policies.
+    pub async fn warn_member_by(
         &self,
-        session_id: SessionId,
+        actor_id: &str,
         server_id: &str,
         target_user_id: &str,
-        reason: Option<&str>,
-        channel_id: Option<&str>,
-    ) -> Result<(), String> {
-        let actor_id = self
-            .require_permission(session_id, server_id, channel_id, Permissions::KICK_MEMBERS)
+        reason: &str,
+        expires_in_seconds: Option<u64>,
+    ) -> Result<InfractionInfo, EngineError> {
+        self.require_user_permission(actor_id, server_id, None, Permissions::KICK_MEMBERS)
             .await?;
-
-        // Prevent kicking the server owner
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let reason = reason.trim();
+        if reason.is_empty() || reason.chars().count() > MAX_REPORT_REASON_LENGTH {
+            return Err(EngineError::Validation(format!(
+                "Warning reason must be 1-{MAX_REPORT_REASON_LENGTH} characters"
+            )));
+        }
+        let expires_in = expires_in_seconds.unwrap_or(STRIKE_EXPIRY_SECONDS);
+        if !(1..=MAX_STRIKE_EXPIRY_SECONDS).contains(&expires_in) {
+            return Err(EngineError::Validation(format!(
+                "Warning expiry must be between 1 and {MAX_STRIKE_EXPIRY_SECONDS} seconds"
+            )));
+        }
+        if !self.user_is_server_member(server_id, target_user_id) {
+            return Err(EngineError::NotFound(
+                "User is not a member of this server".into(),
+            ));
+        }
+        if target_user_id == actor_id {
+            return Err(EngineError::Validation("You can't warn yourself".into()));
+        }
         if self.is_server_owner(server_id, target_user_id) {
-            return Err("Cannot kick the server owner".into());
+            return Err(EngineError::Forbidden(
+                "Cannot warn the server owner".into(),
+            ));
         }
 
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
-        };
-
-        crate::db::queries::moderation::kick_member(pool, server_id, target_user_id)
-            .await
-            .map_err(|e| format!("Failed to kick member: {e}"))?;
-
-        // Remove from in-memory server state
-        if let Some(mut server) = self.servers.get_mut(server_id) {
-            server.member_user_ids.remove(target_user_id);
-        }
+        let id = Uuid::new_v4().to_string();
+        let expires_at = strike_expiry(expires_in);
+        crate::db::queries::infractions::create_infraction(
+            pool,
+            &crate::db::models::CreateInfractionParams {
+                id: &id,
+                server_id,
+                user_id: target_user_id,
+                actor_id: Some(actor_id),
+                action: "warn",
+                source: "manual",
+                reason: Some(reason),
+                expires_at: Some(&expires_at),
+            },
+        )
+        .await?;
 
-        // Log to audit log
-        let audit_id = Uuid::new_v4().to_string();
         if let Err(e) = crate::db::queries::audit_log::create_entry(
             pool,
             &crate::db::models::CreateAuditLogParams {
-                id: &audit_id,
+                id: &Uuid::new_v4().to_string(),
                 server_id,
-                actor_id: &actor_id,
-                action_type: "member_kick",
+                actor_id,
+                action_type: "member_warn",
                 target_type: Some("user"),
                 target_id: Some(target_user_id),
-                reason,
+                reason: Some(reason),
                 changes: None,
             },
         )
         .await
         {
-            tracing::warn!(error = %e, "Failed to write kick audit log entry");
+            warn!(error = %e, "Failed to write warn audit log entry");
         }
 
-        // Broadcast kick event to server members
-        let event = ChatEvent::MemberKick {
-            server_id: server_id.to_string(),
-            user_id: target_user_id.to_string(),
-            kicked_by: actor_id,
-            reason: reason.map(String::from),
-        };
-        self.broadcast_to_server(server_id, &event);
+        self.send_to_user(
+            target_user_id,
+            &ChatEvent::MemberWarn {
+                server_id: server_id.to_string(),
+                user_id: target_user_id.to_string(),
+                warned_by: actor_id.to_string(),
+                reason: reason.to_string(),
+            },
+        );
+        self.escalate(pool, server_id, target_user_id).await;
 
-        Ok(())
+        let row = crate::db::queries::infractions::get_infraction(pool, &id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Infraction not found".into()))?;
+        Ok(infraction_row_to_info(row))
     }
 
-    /// Ban a member from a server, optionally deleting their messages.
-    pub async fn ban_member(
+    /// Send a member's infractions ledger to the session.
+    pub async fn list_infractions(
         &self,
         session_id: SessionId,
         server_id: &str,
-        target_user_id: &str,
-        reason: Option<&str>,
-        delete_message_days: i32,
-    ) -> Result<(), String> {
-        let actor_id = self
-            .require_permission(session_id, server_id, None, Permissions::BAN_MEMBERS)
+        user_id: &str,
+    ) -> Result<(), EngineError> {
+        let actor_id = self.session_user_id(session_id)?;
+        let infractions = self
+            .fetch_infractions(&actor_id, server_id, user_id)
             .await?;
-
-        // Prevent banning the server owner
-        if self.is_server_owner(server_id, target_user_id) {
-            return Err("Cannot ban the server owner".into());
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::InfractionList {
+                server_id: server_id.to_string(),
+                user_id: user_id.to_string(),
+                infractions,
+            });
         }
+        Ok(())
+    }
 
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
-        };
-
-        let ban_id = Uuid::new_v4().to_string();
-        let days = delete_message_days.clamp(0, 7);
+    /// A member's infractions ledger, newest first, including expired and
+    /// pardoned entries. Requires KICK_MEMBERS.
+    pub async fn fetch_infractions(
+        &self,
+        actor_id: &str,
+        server_id: &str,
+        user_id: &str,
+    ) -> Result<Vec<InfractionInfo>, EngineError> {
+        self.require_user_read_permission(actor_id, server_id, None, Permissions::KICK_MEMBERS)
+            .await?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
 
-        crate::db::queries::bans::create_ban(
-            pool,
-            &ban_id,
-            server_id,
-            target_user_id,
-            &actor_id,
-            reason,
-            days,
-        )
-        .await
-        .map_err(|e| format!("Failed to ban member: {e}"))?;
+        let rows =
+            crate::db::queries::infractions::list_member_infractions(pool, server_id, user_id)
+                .await?;
+        Ok(rows.into_iter().map(infraction_row_to_info).collect())
+    }
 
-        // Also kick them from the server
-        crate::db::queries::moderation::kick_member(pool, server_id, target_user_id)
+    /// Pardon an infraction so it stops counting as a strike. The moderator
+    /// gets the member's updated ledger back.
+    pub async fn pardon_infraction(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        infraction_id: &str,
+    ) -> Result<(), EngineError> {
+        let actor_id = self.session_user_id(session_id)?;
+        let pardoned = self
+            .pardon_infraction_by(&actor_id, server_id, infraction_id)
+            .await?;
+        self.list_infractions(session_id, server_id, &pardoned.user_id)
             .await
-            .map_err(|e| format!("Failed to kick banned member: {e}"))?;
+    }
 
-        // Delete messages if requested
-        if days > 0
-            && let Err(e) = crate::db::queries::moderation::delete_user_messages(
-                pool,
-                server_id,
-                target_user_id,
-                days,
-            )
-            .await
+    /// Pardon an infraction on behalf of `actor_id`, who needs KICK_MEMBERS.
+    pub async fn pardon_infraction_by(
+        &self,
+        actor_id: &str,
+        server_id: &str,
+        infraction_id: &str,
+    ) -> Result<InfractionInfo, EngineError> {
+        self.require_user_permission(actor_id, server_id, None, Permissions::KICK_MEMBERS)
+            .await?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        let infraction = crate::db::queries::infractions::get_infraction(pool, infraction_id)
+            .await?
+            .filter(|i| i.server_id == server_id)
+            .ok_or_else(|| EngineError::NotFound("Infraction not found".into()))?;
+        if !crate::db::queries::infractions::pardon_infraction(pool, infraction_id, actor_id)
+            .await?
         {
-            tracing::warn!(error = %e, "Failed to delete messages for banned user");
-        }
-
-        // Remove from in-memory server state
-        if let Some(mut server) = self.servers.get_mut(server_id) {
-            server.member_user_ids.remove(target_user_id);
+            return Err(EngineError::Conflict(
+                "Infraction was already pardoned".into(),
+            ));
         }
 
-        // Audit log
-        let audit_id = Uuid::new_v4().to_string();
         if let Err(e) = crate::db::queries::audit_log::create_entry(
             pool,
             &crate::db::models::CreateAuditLogParams {
-                id: &audit_id,
+                id: &Uuid::new_v4().to_string(),
                 server_id,
-                actor_id: &actor_id,
-                action_type: "member_ban",
+                actor_id,
+                action_type: "infraction_pardon",
                 target_type: Some("user"),
-                target_id: Some(target_user_id),
-                reason,
+                target_id: Some(&infraction.user_id),
+                reason: infraction.reason.as_deref(),
                 changes: None,
             },
         )
         .await
         {
-            tracing::warn!(error = %e, "Failed to write ban audit log entry");
+            warn!(error = %e, "Failed to write pardon audit log entry");
         }
 
-        // Broadcast
-        let event = ChatEvent::MemberBan {
-            server_id: server_id.to_string(),
-            user_id: target_user_id.to_string(),
-            banned_by: actor_id,
-            reason: reason.map(String::from),
-        };
-        self.broadcast_to_server(server_id, &event);
+        let row = crate::db::queries::infractions::get_infraction(pool, infraction_id)
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Infraction not found".into()))?;
+        Ok(infraction_row_to_info(row))
+    }
 
+    /// Send the server's escalation policies to the session.
+    pub async fn list_escalation_policies(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+    ) -> Result<(), EngineError> {
+        let user_id = self.session_user_id(session_id)?;
+        let policies = self.fetch_escalation_policies(&user_id, server_id).await?;
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::EscalationPolicyList {
+                server_id: server_id.to_string(),
+                policies,
+            });
+        }
         Ok(())
     }
 
-    /// Unban a member from a server.
-    pub async fn unban_member(
+    /// The server's escalation ladder, lowest threshold first. Requires
+    /// KICK_MEMBERS.
+    pub async fn fetch_escalation_policies(
         &self,
-        session_id: SessionId,
+        user_id: &str,
         server_id: &str,
-        target_user_id: &str,
-    ) -> Result<(), String> {
-        let actor_id = self
-            .require_permission(session_id, server_id, None, Permissions::BAN_MEMBERS)
+    ) -> Result<Vec<EscalationPolicyInfo>, EngineError> {
+        self.require_user_read_permission(user_id, server_id, None, Permissions::KICK_MEMBERS)
             .await?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
 
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
-        };
+        let rows =
+            crate::db::queries::infractions::list_escalation_policies(pool, server_id).await?;
+        Ok(rows
+            .into_iter()
+            .map(|r| EscalationPolicyInfo {
+                threshold: r.threshold as u32,
+                window_seconds: r.window_seconds as u64,
+                action: r.action,
+                duration_seconds: r.duration_seconds.map(|d| d as u64),
+            })
+            .collect())
+    }
 
-        let removed = crate::db::queries::bans::remove_ban(pool, server_id, target_user_id)
-            .await
-            .map_err(|e| format!("Failed to unban member: {e}"))?;
+    /// Replace the server's escalation ladder, then send it back to the session.
+    pub async fn set_escalation_policies(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+        policies: &[EscalationPolicyInfo],
+    ) -> Result<(), EngineError> {
+        let actor_id = self.session_user_id(session_id)?;
+        self.set_escalation_policies_by(&actor_id, server_id, policies)
+            .await?;
+        self.list_escalation_policies(session_id, server_id).await
+    }
 
-        if !removed {
-            return Err("User is not banned".into());
+    /// Replace the server's escalation ladder on behalf of `actor_id`, who
+    /// needs MANAGE_SERVER. An empty list turns escalation off.
+    pub async fn set_escalation_policies_by(
+        &self,
+        actor_id: &str,
+        server_id: &str,
+        policies: &[EscalationPolicyInfo],
+    ) -> Result<(), EngineError> {
+        self.require_user_permission(actor_id, server_id, None, Permissions::MANAGE_SERVER)
+            .await?;
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+
+        if policies.len() > MAX_ESCALATION_POLICIES {
+            return Err(EngineError::Validation(format!(
+                "At most {MAX_ESCALATION_POLICIES} escalation policies are allowed"
+            )));
+        }
+        let mut thresholds = std::collections::HashSet::new();
+        for policy in policies {
+            if !(1..=100).contains(&policy.threshold) {
+                return Err(EngineError::Validation(
+                    "Escalation threshold must be between 1 and 100 strikes".into(),
+                ));
+            }
+            if !thresholds.insert(policy.threshold) {
+                return Err(EngineError::Validation(format!(
+                    "Duplicate escalation threshold: {}",
+                    policy.threshold
+                )));
+            }
+            if !(1..=MAX_STRIKE_EXPIRY_SECONDS).contains(&policy.window_seconds) {
+                return Err(EngineError::Validation(format!(
+                    "Escalation window must be between 1 and {MAX_STRIKE_EXPIRY_SECONDS} seconds"
+                )));
+            }
+            if !ESCALATION_ACTIONS.contains(&policy.action.as_str()) {
+                return Err(EngineError::Validation(format!(
+                    "Invalid escalation action. Must be one of: {}",
+                    ESCALATION_ACTIONS.join(", ")
+                )));
+            }
+            if policy.action == "timeout"
+                && !policy
+                    .duration_seconds
+                    .is_some_and(|d| (1..=automod::MAX_TIMEOUT_SECONDS).contains(&d))
+            {
+                return Err(EngineError::Validation(format!(
+                    "Timeout escalations need a duration between 1 and {} seconds",
+                    automod::MAX_TIMEOUT_SECONDS
+                )));
+            }
         }
 
-        // Audit log
-        let audit_id = Uuid::new_v4().to_string();
+        let ids: Vec<String> = policies
+            .iter()
+            .map(|_| Uuid::new_v4().to_string())
+            .collect();
+        let params: Vec<_> = policies
+            .iter()
+            .zip(&ids)
+            .map(
+                |(policy, id)| crate::db::models::CreateEscalationPolicyParams {
+                    id,
+                    threshold: policy.threshold as i64,
+                    window_seconds: policy.window_seconds as i64,
+                    action: &policy.action,
+                    // Only timeouts have a length
+                    duration_seconds: policy
+                        .duration_seconds
+                        .filter(|_| policy.action == "timeout")
+                        .map(|d| d as i64),
+                },
+            )
+            .collect();
+        crate::db::queries::infractions::replace_escalation_policies(pool, server_id, &params)
+            .await?;
+
+        let changes = serde_json::json!({ "policies": policies }).to_string();
         if let Err(e) = crate::db::queries::audit_log::create_entry(
             pool,
             &crate::db::models::CreateAuditLogParams {
-                id: &audit_id,
+                id: &Uuid::new_v4().to_string(),
                 server_id,
-                actor_id: &actor_id,
-                action_type: "member_unban",
-                target_type: Some("user"),
-                target_id: Some(target_user_id),
+                actor_id,
+                action_type: "escalation_policies_update",
+                target_type: Some("server"),
+                target_id: Some(server_id),
                 reason: None,
-                changes: None,
+                changes: Some(&changes),
             },
         )
         .await
         {
-            tracing::warn!(error = %e, "Failed to write unban audit log entry");
+            warn!(error = %e, "Failed to write escalation policy audit log entry");
         }
-
-        // Broadcast
-        let event = ChatEvent::MemberUnban {
-            server_id: server_id.to_string(),
-            user_id: target_user_id.to_string(),
-        };
-        self.broadcast_to_server(server_id, &event);
-
         Ok(())
     }
 
-    /// Get the list of bans for a server.
-    pub async fn list_bans(&self, session_id: SessionId, server_id: &str) -> Result<(), String> {
-        self.require_permission(session_id, server_id, None, Permissions::BAN_MEMBERS)
-            .await?;
+    /// Record a strike against a member after a kick, ban, timeout or AutoMod
+    /// hit, then apply any escalation policy it trips. `actor_id` is the
+    /// moderator, or `None` for AutoMod. Failures are logged, since the action
+    /// itself already happened.
+    async fn record_strike(
+        &self,
+        pool: &SqlitePool,
+        server_id: &str,
+        user_id: &str,
+        actor_id: Option<&str>,
+        action: &str,
+        reason: Option<&str>,
+    ) {
+        let expires_at = strike_expiry(STRIKE_EXPIRY_SECONDS);
+        if let Err(e) = crate::db::queries::infractions::create_infraction(
+            pool,
+            &crate::db::models::CreateInfractionParams {
+                id: &Uuid::new_v4().to_string(),
+                server_id,
+                user_id,
+                actor_id,
+                action,
+                source: if actor_id.is_some() {
+                    "manual"
+                } else {
+                    "automod"
+                },
+                reason,
+                expires_at: Some(&expires_at),
+            },
+        )
+        .await
+        {
+            warn!(error = %e, %server_id, %user_id, "failed to record infraction");
+            return;
+        }
+        self.escalate(pool, server_id, user_id).await;
+    }
 
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+    /// Apply the escalation policy whose threshold a member's strikes just
+    /// reached; when several do, the one with the highest threshold wins.
+    /// Only current members are escalated, so a kick or ban ends the ladder.
+    /// Escalations act as the system user, not the moderator or the offender.
+    async fn escalate(&self, pool: &SqlitePool, server_id: &str, user_id: &str) {
+        let actor_id = SYSTEM_USER_ID;
+        if !self.user_is_server_member(server_id, user_id)
+            || self.is_server_owner(server_id, user_id)
+        {
+            return;
+        }
+        let policies = match crate::db::queries::infractions::list_escalation_policies(
+            pool, server_id,
+        )
+        .await
+        {
+            Ok(policies) => policies,
+            Err(e) => {
+                warn!(error = %e, %server_id, "failed to load escalation policies");
+                return;
+            }
         };
-
-        let rows = crate::db::queries::bans::list_bans(pool, server_id)
+        let mut tripped = None;
+        for policy in policies {
+            match crate::db::queries::infractions::count_strikes(
+                pool,
+                server_id,
+                user_id,
+                policy.window_seconds,
+            )
             .await
-            .map_err(|e| format!("Failed to list bans: {e}"))?;
-
-        let bans: Vec<BanInfo> = rows
-            .into_iter()
-            .map(|r| BanInfo {
-                id: r.id,
-                user_id: r.user_id,
-                banned_by: r.banned_by,
-                reason: r.reason,
-                created_at: r.created_at,
-            })
-            .collect();
-
-        if let Some(session) = self.get_session(session_id) {
-            let _ = session.send(ChatEvent::BanList {
-                server_id: server_id.to_string(),
-                bans,
-            });
+            {
+                Ok(strikes) if strikes == policy.threshold => tripped = Some(policy),
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(error = %e, %server_id, %user_id, "failed to count strikes");
+                    return;
+                }
+            }
         }
+        let Some(policy) = tripped else {
+            return;
+        };
 
-        Ok(())
-    }
-
-    /// Set a timeout on a member (or clear it).
-    pub async fn timeout_member(
-        &self,
-        session_id: SessionId,
-        server_id: &str,
-        target_user_id: &str,
-        timeout_until: Option<&str>,
-        reason: Option<&str>,
-    ) -> Result<(), String> {
-        let actor_id = self
-            .require_permission(session_id, server_id, None, Permissions::KICK_MEMBERS)
-            .await?;
-
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+        let reason = format!(
+            "Escalation: {} strikes within {} days",
+            policy.threshold,
+            (policy.window_seconds + 24 * 60 * 60 - 1) / (24 * 60 * 60)
+        );
+        let mut timeout_until = None;
+        let result = match policy.action.as_str() {
+            "timeout" => {
+                let seconds = policy.duration_seconds.unwrap_or(60 * 60);
+                let until = (Utc::now() + chrono::Duration::seconds(seconds))
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string();
+                let result = self
+                    .apply_member_timeout(pool, server_id, user_id, Some(&until))
+                    .await;
+                timeout_until = Some(until);
+                result
+            }
+            "kick" => {
+                self.kick_user(
+                    pool,
+                    actor_id.to_string(),
+                    server_id,
+                    user_id,
+                    Some(&reason),
+                )
+                .await
+            }
+            "ban" => {
+                let params = BanParams {
+                    reason: Some(&reason),
+                    ..Default::default()
+                };
+                self.ban_user(pool, actor_id.to_string(), server_id, user_id, &params)
+                    .await
+            }
+            _ => return,
         };
+        if let Err(e) = result {
+            warn!(error = %e, %server_id, %user_id, action = %policy.action, "failed to apply escalation");
+            return;
+        }
 
-        crate::db::queries::moderation::set_member_timeout(
+        if let Err(e) = crate::db::queries::infractions::create_infraction(
             pool,
-            server_id,
-            target_user_id,
-            timeout_until,
+            &crate::db::models::CreateInfractionParams {
+                id: &Uuid::new_v4().to_string(),
+                server_id,
+                user_id,
+                actor_id: None,
+                action: &policy.action,
+                source: "escalation",
+                reason: Some(&reason),
+                expires_at: None,
+            },
         )
         .await
-        .map_err(|e| format!("Failed to set timeout: {e}"))?;
-
-        // Audit log
-        let audit_id = Uuid::new_v4().to_string();
-        let changes_json = timeout_until.map(|t| format!("{{\"timeout_until\":\"{t}\"}}"));
+        {
+            warn!(error = %e, %server_id, %user_id, "failed to record escalation");
+        }
+        let changes = serde_json::json!({
+            "policy_id": policy.id,
+            "action": policy.action,
+            "timeout_until": timeout_until,
+        })
+        .to_string();
         if let Err(e) = crate::db::queries::audit_log::create_entry(
             pool,
             &crate::db::models::CreateAuditLogParams {
-                id: &audit_id,
+                id: &Uuid::new_v4().to_string(),
                 server_id,
-                actor_id: &actor_id,
-                action_type: "member_timeout",
+                actor_id,
+                action_type: "infraction_escalate",
                 target_type: Some("user"),
-                target_id: Some(target_user_id),
-                reason,
-                changes: changes_json.as_deref(),
+                target_id: Some(user_id),
+                reason: Some(&reason),
+                changes: Some(&changes),
             },
         )
         .await
         {
-            tracing::warn!(error = %e, "Failed to write timeout audit log entry");
+            warn!(error = %e, "Failed to write escalation audit log entry");
         }
-
-        // Broadcast
-        let event = ChatEvent::MemberTimeout {
-            server_id: server_id.to_string(),
-            user_id: target_user_id.to_string(),
-            timeout_until: timeout_until.map(String::from),
-        };
-        self.broadcast_to_server(server_id, &event);
-
-        Ok(())
     }
 
-    /// Set slow mode on a channel.
-    pub async fn set_slowmode(
-        &self,
-        session_id: SessionId,
-        server_id: &str,
-        channel_name: &str,
-        seconds: i32,
-    ) -> Result<(), String> {
-        self.require_permission(session_id, server_id, None, Permissions::MANAGE_CHANNELS)
-            .await?;
-
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
-        };
-
-        let channel_id = self
-            .channel_name_index
-            .get(&(server_id.to_string(), channel_name.to_string()))
-            .map(|v| v.clone())
-            .ok_or_else(|| "Channel not found".to_string())?;
-
-        let seconds = seconds.clamp(0, 21600); // max 6 hours
-
-        crate::db::queries::moderation::set_slowmode(pool, &channel_id, seconds)
-            .await
-            .map_err(|e| format!("Failed to set slow mode: {e}"))?;
+    // ── Instance administration ──
 
-        // Update in-memory state
-        if let Some(mut ch) = self.channels.get_mut(&channel_id) {
-            ch.slowmode_seconds = seconds;
+    /// Fail unless the user is a system admin.
+    async fn require_system_admin(
+        &self,
+        pool: &SqlitePool,
+        user_id: &str,
+    ) -> Result<(), EngineError> {
+        if crate::db::queries::servers::is_system_admin(pool, user_id).await? {
+            Ok(())
+        } else {
+            Err(EngineError::Forbidden("Not a system admin".into()))
         }
-
-        // Broadcast
-        let event = ChatEvent::SlowModeUpdate {
-            server_id: server_id.to_string(),
-            channel: channel_name.to_string(),
-            seconds,
-        };
-        self.broadcast_to_server(server_id, &event);
-
-        Ok(())
     }
 
-    /// Set NSFW flag on a channel.
-    pub async fn set_nsfw(
-        &self,
-        session_id: SessionId,
-        server_id: &str,
-        channel_name: &str,
-        is_nsfw: bool,
-    ) -> Result<(), String> {
-        self.require_permission(session_id, server_id, None, Permissions::MANAGE_CHANNELS)
-            .await?;
-
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
-        };
-
-        let channel_id = self
-            .channel_name_index
-            .get(&(server_id.to_string(), channel_name.to_string()))
-            .map(|v| v.clone())
-            .ok_or_else(|| "Channel not found".to_string())?;
-
-        crate::db::queries::moderation::set_nsfw(pool, &channel_id, is_nsfw)
-            .await
-            .map_err(|e| format!("Failed to set NSFW: {e}"))?;
-
-        // Update in-memory state
-        if let Some(mut ch) = self.channels.get_mut(&channel_id) {
-            ch.is_nsfw = is_nsfw;
+    /// Close every live session a user has, WebSocket and IRC alike, telling
+    /// each why first. Returns the number of sessions closed.
+    pub async fn disconnect_user(&self, user_id: &str, message: &str) -> usize {
+        let session_ids: Vec<SessionId> = self
+            .sessions
+            .iter()
+            .filter(|s| s.user_id.as_deref() == Some(user_id))
+            .map(|s| *s.key())
+            .collect();
+        for &session_id in &session_ids {
+            if let Some(session) = self.get_session(session_id) {
+                let _ = session.send(ChatEvent::Error {
+                    code: "SESSION_REVOKED".into(),
+                    message: message.to_string(),
+                });
+            }
+            self.disconnect(session_id).await;
         }
-
-        // Broadcast
-        let event = ChatEvent::NsfwUpdate {
-            server_id: server_id.to_string(),
-            channel: channel_name.to_string(),
-            is_nsfw,
-        };
-        self.broadcast_to_server(server_id, &event);
-
-        Ok(())
+        if !session_ids.is_empty() {
+            info!(%user_id, sessions = session_ids.len(), "user disconnected by instance admin");
+        }
+        session_ids.len()
     }
 
-    /// Bulk delete messages in a channel (up to 100).
-    pub async fn bulk_delete_messages(
+    /// Suspend a user across the instance, or reinstate them. Suspending
+    /// revokes every credential they hold and closes their live sessions.
+    pub async fn set_user_suspended_by(
         &self,
-        session_id: SessionId,
-        server_id: &str,
-        channel_name: &str,
-        message_ids: Vec<String>,
-    ) -> Result<(), String> {
-        self.require_permission(session_id, server_id, None, Permissions::MANAGE_MESSAGES)
-            .await?;
-
-        if message_ids.is_empty() {
-            return Err("No messages to delete".into());
-        }
-        if message_ids.len() > 100 {
-            return Err("Cannot bulk delete more than 100 messages".into());
+        admin_id: &str,
+        user_id: &str,
+        suspended: bool,
+        reason: Option<&str>,
+    ) -> Result<(), EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.require_system_admin(pool, admin_id).await?;
+        let reason = admin_reason(reason)?;
+        if suspended && user_id == admin_id {
+            return Err(EngineError::Validation("You can't suspend yourself".into()));
         }
 
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+        if !crate::db::queries::users::set_suspended(pool, user_id, suspended).await? {
+            return Err(EngineError::NotFound("User not found".into()));
+        }
+        let changes = if suspended {
+            let revoked = crate::db::queries::users::revoke_user_tokens(pool, user_id).await?;
+            let sessions = self
+                .disconnect_user(user_id, "Your account has been suspended")
+                .await;
+            Some(serde_json::json!({ "tokens_revoked": revoked, "sessions_closed": sessions }))
+        } else {
+            None
         };
 
-        crate::db::queries::moderation::bulk_delete_messages(pool, &message_ids)
-            .await
-            .map_err(|e| format!("Failed to bulk delete: {e}"))?;
-
-        // Broadcast
-        let event = ChatEvent::BulkMessageDelete {
-            server_id: server_id.to_string(),
-            channel: channel_name.to_string(),
-            message_ids,
+        let action_type = if suspended {
+            "user_suspend"
+        } else {
+            "user_unsuspend"
         };
-        self.broadcast_to_server(server_id, &event);
-
+        instance_audit(
+            pool,
+            admin_id,
+            action_type,
+            "user",
+            user_id,
+            reason,
+            changes,
+        )
+        .await;
+        info!(%admin_id, %user_id, suspended, "user suspension updated");
         Ok(())
     }
 
-    /// Get audit log entries for a server.
-    pub async fn get_audit_log(
+    /// Revoke every session and token a user holds and close their live
+    /// connections. Returns the number of stored tokens deleted.
+    pub async fn revoke_user_sessions_by(
         &self,
-        session_id: SessionId,
-        server_id: &str,
-        action_type: Option<&str>,
-        limit: i64,
-        before: Option<&str>,
-    ) -> Result<(), String> {
-        self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
-            .await?;
-
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
-        };
+        admin_id: &str,
+        user_id: &str,
+    ) -> Result<u64, EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.require_system_admin(pool, admin_id).await?;
+        if crate::db::queries::users::get_user(pool, user_id)
+            .await?
+            .is_none()
+        {
+            return Err(EngineError::NotFound("User not found".into()));
+        }
 
-        let limit = limit.clamp(1, 100);
-        let rows = crate::db::queries::audit_log::list_entries(
+        let revoked = crate::db::queries::users::revoke_user_tokens(pool, user_id).await?;
+        let sessions = self
+            .disconnect_user(user_id, "Your sessions have been revoked")
+            .await;
+        let changes = serde_json::json!({ "tokens_revoked": revoked, "sessions_closed": sessions });
+        instance_audit(
             pool,
-            server_id,
-            action_type,
-            limit,
-            before,
+            admin_id,
+            "user_sessions_revoke",
+            "user",
+            user_id,
+            None,
+            Some(changes),
         )
-        .await
-        .map_err(|e| format!("Failed to get audit log: {e}"))?;
+        .await;
+        Ok(revoked)
+    }
 
-        let entries: Vec<AuditLogEntry> = rows
+    /// List identities banned from the instance.
+    pub async fn fetch_instance_bans(
+        &self,
+        admin_id: &str,
+    ) -> Result<Vec<InstanceBanInfo>, EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.require_system_admin(pool, admin_id).await?;
+        let rows = crate::db::queries::instance::list_instance_bans(pool).await?;
+        Ok(rows
             .into_iter()
-            .map(|r| AuditLogEntry {
-                id: r.id,
-                actor_id: r.actor_id,
-                action_type: r.action_type,
-                target_type: r.target_type,
-                target_id: r.target_id,
+            .map(|r| InstanceBanInfo {
+                user_id: r.user_id,
                 reason: r.reason,
-                changes: r.changes,
+                banned_by: r.banned_by,
                 created_at: r.created_at,
             })
-            .collect();
-
-        if let Some(session) = self.get_session(session_id) {
-            let _ = session.send(ChatEvent::AuditLogEntries {
-                server_id: server_id.to_string(),
-                entries,
-            });
-        }
-
-        Ok(())
+            .collect())
     }
 
-    // ── AutoMod ──
-
-    /// Create an automod rule.
-    pub async fn create_automod_rule(
-        &self,
-        session_id: SessionId,
-        params: &crate::db::models::CreateAutomodRuleParams<'_>,
-    ) -> Result<(), String> {
-        let server_id = params.server_id;
-        let name = params.name;
-        let rule_type = params.rule_type;
-        let config = params.config;
-        let action_type = params.action_type;
-        let timeout_duration_seconds = params.timeout_duration_seconds;
-        self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
-            .await?;
-
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
-        };
-
-        // Validate rule_type
-        if !["keyword", "mention_spam", "link_filter"].contains(&rule_type) {
-            return Err(
-                "Invalid rule type. Must be 'keyword', 'mention_spam', or 'link_filter'".into(),
-            );
-        }
-        // Validate action_type
-        if !["delete", "timeout", "flag"].contains(&action_type) {
-            return Err("Invalid action type. Must be 'delete', 'timeout', or 'flag'".into());
-        }
-        // Validate config JSON matches rule_type schema
-        validate_automod_config(rule_type, config)?;
-
-        let rule_id = Uuid::new_v4().to_string();
-        let db_params = crate::db::models::CreateAutomodRuleParams {
-            id: &rule_id,
-            server_id,
-            name,
-            rule_type,
-            config,
-            action_type,
-            timeout_duration_seconds,
-        };
-        crate::db::queries::automod::create_rule(pool, &db_params)
-            .await
-            .map_err(|e| format!("Failed to create automod rule: {e}"))?;
-
-        let rule = AutomodRuleInfo {
-            id: rule_id,
-            name: name.to_string(),
-            enabled: true,
-            rule_type: rule_type.to_string(),
-            config: config.to_string(),
-            action_type: action_type.to_string(),
-            timeout_duration_seconds,
-        };
-
-        if let Some(session) = self.get_session(session_id) {
-            let _ = session.send(ChatEvent::AutomodRuleUpdate {
-                server_id: server_id.to_string(),
-                rule,
-            });
+    /// Ban an identity from the whole instance. `user_id` is a DID, which need
+    /// not have signed in here yet, or an existing account's ID. A banned
+    /// account loses its credentials and live sessions and can't sign in again.
+    pub async fn instance_ban_by(
+        &self,
+        admin_id: &str,
+        user_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.require_system_admin(pool, admin_id).await?;
+        let reason = admin_reason(reason)?;
+        if user_id == admin_id {
+            return Err(EngineError::Validation("You can't ban yourself".into()));
+        }
+        let exists = crate::db::queries::users::get_user(pool, user_id)
+            .await?
+            .is_some();
+        if !exists && !is_did(user_id) {
+            return Err(EngineError::Validation(
+                "Expected a DID or an existing user ID".into(),
+            ));
+        }
+        if exists && crate::db::queries::servers::is_system_admin(pool, user_id).await? {
+            return Err(EngineError::Forbidden(
+                "System admins can't be banned; demote them first".into(),
+            ));
         }
 
+        crate::db::queries::instance::create_instance_ban(pool, user_id, admin_id, reason).await?;
+        let changes = if exists {
+            let revoked = crate::db::queries::users::revoke_user_tokens(pool, user_id).await?;
+            let sessions = self
+                .disconnect_user(user_id, "You have been banned from this instance")
+                .await;
+            Some(serde_json::json!({ "tokens_revoked": revoked, "sessions_closed": sessions }))
+        } else {
+            None
+        };
+        instance_audit(
+            pool,
+            admin_id,
+            "instance_ban",
+            "user",
+            user_id,
+            reason,
+            changes,
+        )
+        .await;
+        info!(%admin_id, %user_id, "identity banned from instance");
         Ok(())
     }
 
-    /// Update an automod rule.
-    pub async fn update_automod_rule(
+    /// Lift an instance ban.
+    pub async fn instance_unban_by(
         &self,
-        session_id: SessionId,
-        params: &crate::db::models::UpdateAutomodRuleParams<'_>,
-    ) -> Result<(), String> {
-        let server_id = params.server_id;
-        let rule_id = params.rule_id;
-        let name = params.name;
-        let enabled = params.enabled;
-        let config = params.config;
-        let action_type = params.action_type;
-        let timeout_duration_seconds = params.timeout_duration_seconds;
-
-        self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
-            .await?;
-
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
-        };
+        admin_id: &str,
+        user_id: &str,
+    ) -> Result<(), EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.require_system_admin(pool, admin_id).await?;
+        if !crate::db::queries::instance::delete_instance_ban(pool, user_id).await? {
+            return Err(EngineError::NotFound("That identity isn't banned".into()));
+        }
+        instance_audit(
+            pool,
+            admin_id,
+            "instance_unban",
+            "user",
+            user_id,
+            None,
+            None,
+        )
+        .await;
+        Ok(())
+    }
 
-        // Validate action_type
-        if !["delete", "timeout", "flag"].contains(&action_type) {
-            return Err("Invalid action type. Must be 'delete', 'timeout', or 'flag'".into());
+    /// Quarantine a server, hiding it from discovery and freezing it
+    /// read-only, or lift the quarantine. Members are told either way.
+    pub async fn set_server_quarantine_by(
+        &self,
+        admin_id: &str,
+        server_id: &str,
+        quarantined: bool,
+        reason: Option<&str>,
+    ) -> Result<(), EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.require_system_admin(pool, admin_id).await?;
+        let reason = admin_reason(reason)?;
+        if !self.servers.contains_key(server_id) {
+            return Err(EngineError::NotFound("Server not found".into()));
         }
 
-        // Fetch existing rule to get rule_type for config validation
-        let existing = crate::db::queries::automod::get_rule(pool, rule_id)
-            .await
-            .map_err(|e| format!("Failed to fetch automod rule: {e}"))?
-            .ok_or_else(|| "Automod rule not found".to_string())?;
-        validate_automod_config(&existing.rule_type, config)?;
+        crate::db::queries::servers::set_quarantine(pool, server_id, quarantined, reason).await?;
+        if let Some(mut server) = self.servers.get_mut(server_id) {
+            server.quarantined = quarantined;
+        }
+        self.broadcast_to_server(
+            server_id,
+            &ChatEvent::ServerQuarantine {
+                server_id: server_id.to_string(),
+                quarantined,
+                reason: reason.map(String::from),
+            },
+        );
 
-        crate::db::queries::automod::update_rule(
+        let action_type = if quarantined {
+            "server_quarantine"
+        } else {
+            "server_unquarantine"
+        };
+        instance_audit(
             pool,
-            rule_id,
-            name,
-            enabled,
-            config,
+            admin_id,
             action_type,
-            timeout_duration_seconds,
+            "server",
+            server_id,
+            reason,
+            None,
         )
-        .await
-        .map_err(|e| format!("Failed to update automod rule: {e}"))?;
+        .await;
+        info!(%admin_id, %server_id, quarantined, "server quarantine updated");
+        Ok(())
+    }
 
-        let rule = AutomodRuleInfo {
-            id: rule_id.to_string(),
-            name: name.to_string(),
-            enabled,
-            rule_type: existing.rule_type,
-            config: config.to_string(),
-            action_type: action_type.to_string(),
-            timeout_duration_seconds,
-        };
+    /// Delete any server on the instance.
+    pub async fn delete_server_by(
+        &self,
+        admin_id: &str,
+        server_id: &str,
+        reason: Option<&str>,
+    ) -> Result<(), EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.require_system_admin(pool, admin_id).await?;
+        let reason = admin_reason(reason)?;
+        let name = self
+            .get_server_name(server_id)
+            .ok_or_else(|| EngineError::NotFound("Server not found".into()))?;
+
+        self.remove_server(server_id).await?;
+        let changes = serde_json::json!({ "name": name });
+        instance_audit(
+            pool,
+            admin_id,
+            "server_delete",
+            "server",
+            server_id,
+            reason,
+            Some(changes),
+        )
+        .await;
+        Ok(())
+    }
 
-        if let Some(session) = self.get_session(session_id) {
-            let _ = session.send(ChatEvent::AutomodRuleUpdate {
-                server_id: server_id.to_string(),
-                rule,
-            });
+    // ── Admin CLI changes ───────────────────────────────────────────
+
+    /// Apply the changes the admin CLI recorded after `after_id` to the
+    /// in-memory state. Returns the ID of the last change applied, which the
+    /// caller passes back on its next poll.
+    pub async fn apply_admin_changes(&self, after_id: i64) -> Result<i64, EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        let changes =
+            crate::db::queries::instance::list_admin_changes_after(pool, after_id).await?;
+
+        let mut last_id = after_id;
+        for change in changes {
+            let target = change.target_id.as_deref().unwrap_or_default();
+            match change.kind.as_str() {
+                "server_delete" => self.forget_server(target),
+                "server_transfer" => self.reload_server_owner(pool, target).await?,
+                "user_suspend" => {
+                    self.disconnect_user(target, "Your account has been suspended")
+                        .await;
+                }
+                "user_delete" => {
+                    self.disconnect_user(target, "Your account has been deleted")
+                        .await;
+                }
+                "restore" => self.reload_after_restore().await?,
+                kind => warn!(%kind, "ignoring unknown admin change"),
+            }
+            info!(id = change.id, kind = %change.kind, %target, "applied admin change");
+            last_id = change.id;
         }
-
-        Ok(())
+        Ok(last_id)
     }
 
-    /// Delete an automod rule.
-    pub async fn delete_automod_rule(
+    /// Pick up a server's new owner after an ownership transfer.
+    async fn reload_server_owner(
         &self,
-        session_id: SessionId,
+        pool: &SqlitePool,
         server_id: &str,
-        rule_id: &str,
-    ) -> Result<(), String> {
-        self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
-            .await?;
-
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+    ) -> Result<(), EngineError> {
+        let Some(row) = crate::db::queries::servers::get_server(pool, server_id).await? else {
+            return Ok(());
         };
+        if let Some(mut server) = self.servers.get_mut(server_id) {
+            server.member_user_ids.insert(row.owner_id.clone());
+            server.owner_id = row.owner_id;
+            server.permission_cache.invalidate_roles_and_members();
+        }
+        Ok(())
+    }
 
-        crate::db::queries::automod::delete_rule(pool, rule_id)
-            .await
-            .map_err(|e| format!("Failed to delete automod rule: {e}"))?;
-
-        if let Some(session) = self.get_session(session_id) {
-            let _ = session.send(ChatEvent::AutomodRuleDelete {
-                server_id: server_id.to_string(),
-                rule_id: rule_id.to_string(),
-            });
+    /// Close every session and reload servers and channels once a restore
+    /// has replaced the database contents underneath them.
+    async fn reload_after_restore(&self) -> Result<(), EngineError> {
+        let session_ids: Vec<SessionId> = self.sessions.iter().map(|s| *s.key()).collect();
+        for session_id in session_ids {
+            if let Some(session) = self.get_session(session_id) {
+                let _ = session.send(ChatEvent::Error {
+                    code: "SESSION_REVOKED".into(),
+                    message: "The server was restored from a backup".into(),
+                });
+            }
+            self.disconnect(session_id).await;
         }
 
-        Ok(())
+        self.channels.clear();
+        self.channel_name_index.clear();
+        self.servers.clear();
+        self.load_servers_from_db().await?;
+        self.load_channels_from_db().await
     }
 
-    /// List automod rules for a server.
-    pub async fn list_automod_rules(
+    /// Grant or revoke system admin.
+    pub async fn set_system_admin_by(
         &self,
-        session_id: SessionId,
-        server_id: &str,
-    ) -> Result<(), String> {
-        self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
-            .await?;
-
-        let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+        admin_id: &str,
+        user_id: &str,
+        is_admin: bool,
+    ) -> Result<(), EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.require_system_admin(pool, admin_id).await?;
+        crate::db::queries::servers::set_system_admin(pool, user_id, is_admin).await?;
+        let action_type = if is_admin {
+            "admin_grant"
+        } else {
+            "admin_revoke"
         };
+        instance_audit(pool, admin_id, action_type, "user", user_id, None, None).await;
+        Ok(())
+    }
 
-        let rows = crate::db::queries::automod::list_rules(pool, server_id)
-            .await
-            .map_err(|e| format!("Failed to list automod rules: {e}"))?;
-
-        let rules: Vec<AutomodRuleInfo> = rows
+    /// Read the instance audit log, newest first.
+    pub async fn fetch_instance_audit_log(
+        &self,
+        admin_id: &str,
+        action_type: Option<&str>,
+        limit: i64,
+        before: Option<&str>,
+    ) -> Result<Vec<AuditLogEntry>, EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.require_system_admin(pool, admin_id).await?;
+        let rows = crate::db::queries::instance::list_audit_entries(
+            pool,
+            action_type,
+            limit.clamp(1, 100),
+            before,
+        )
+        .await?;
+        Ok(rows
             .into_iter()
-            .map(|r| AutomodRuleInfo {
+            .map(|r| AuditLogEntry {
                 id: r.id,
-                name: r.name,
-                enabled: r.enabled != 0,
-                rule_type: r.rule_type,
-                config: r.config,
+                actor_id: r.actor_id,
                 action_type: r.action_type,
-                timeout_duration_seconds: r.timeout_duration_seconds,
+                target_type: r.target_type,
+                target_id: r.target_id,
+                reason: r.reason,
+                changes: r.changes,
+                created_at: r.created_at,
             })
-            .collect();
+            .collect())
+    }
 
-        if let Some(session) = self.get_session(session_id) {
-            let _ = session.send(ChatEvent::AutomodRuleList {
-                server_id: server_id.to_string(),
-                rules,
+    /// Open reports across every server, oldest first, for instance admins.
+    pub async fn fetch_instance_reports(
+        &self,
+        admin_id: &str,
+    ) -> Result<Vec<InstanceReportInfo>, EngineError> {
+        let pool = self.db.as_ref().ok_or(EngineError::NoDatabase)?;
+        self.require_system_admin(pool, admin_id).await?;
+
+        let rows =
+            crate::db::queries::reports::list_all_open_reports(pool, MAX_INSTANCE_REPORTS).await?;
+        let mut reports = Vec::with_capacity(rows.len());
+        for row in rows {
+            let server_id = row.server_id.clone();
+            let server_name = self
+                .get_server_name(&server_id)
+                .unwrap_or_else(|| server_id.clone());
+            let context = report_context(pool, &row).await?;
+            reports.push(InstanceReportInfo {
+                server_id,
+                server_name,
+                report: report_row_to_info(row, context),
             });
         }
-
-        Ok(())
+        Ok(reports)
     }
 
     // ── Phase 7: Community & Discovery ─────────────────────────────
@@ -4068,13 +7282,14 @@ impl ChatEngine {
         max_uses: Option<i32>,
         expires_at: Option<&str>,
         channel_id: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let user_id = self
             .require_permission(session_id, server_id, None, Permissions::CREATE_INVITES)
             .await?;
+        self.check_invites_open(server_id)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let invite_id = Uuid::new_v4().to_string();
@@ -4090,7 +7305,7 @@ impl ChatEngine {
             pool, &invite_id, server_id, &code, &user_id, max_uses, expires_at, channel_id,
         )
         .await
-        .map_err(|e| format!("Failed to create invite: {e}"))?;
+        .map_err(|e| EngineError::Internal(format!("Failed to create invite: {e}")))?;
 
         let invite = InviteInfo {
             id: invite_id,
@@ -4115,17 +7330,21 @@ impl ChatEngine {
     }
 
     /// List invites for a server. Requires MANAGE_SERVER permission.
-    pub async fn list_invites(&self, session_id: SessionId, server_id: &str) -> Result<(), String> {
-        self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
+    pub async fn list_invites(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+    ) -> Result<(), EngineError> {
+        self.require_read_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let rows = crate::db::queries::invites::list_server_invites(pool, server_id)
             .await
-            .map_err(|e| format!("Failed to list invites: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to list invites: {e}")))?;
 
         let invites: Vec<InviteInfo> = rows
             .into_iter()
@@ -4158,17 +7377,17 @@ impl ChatEngine {
         session_id: SessionId,
         server_id: &str,
         invite_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         crate::db::queries::invites::delete_invite(pool, invite_id)
             .await
-            .map_err(|e| format!("Failed to delete invite: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to delete invite: {e}")))?;
 
         if let Some(session) = self.get_session(session_id) {
             let _ = session.send(ChatEvent::InviteDelete {
@@ -4180,45 +7399,66 @@ impl ChatEngine {
         Ok(())
     }
 
+    /// Invites are paused while the server is locked down or quarantined.
+    fn check_invites_open(&self, server_id: &str) -> Result<(), EngineError> {
+        self.check_not_quarantined(server_id)?;
+        if self
+            .servers
+            .get(server_id)
+            .is_some_and(|s| s.active_lockdown().is_some())
+        {
+            return Err(EngineError::Forbidden(
+                "Invites are paused while this server is locked down".into(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Use an invite code to join a server. Any authenticated user can use this.
-    pub async fn use_invite(&self, session_id: SessionId, code: &str) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
+    pub async fn use_invite(&self, session_id: SessionId, code: &str) -> Result<(), EngineError> {
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
         let user_id = session
             .user_id
             .as_deref()
-            .ok_or("AUTH_REQUIRED")?
+            .ok_or(EngineError::AuthRequired)?
             .to_string();
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let invite = crate::db::queries::invites::get_invite_by_code(pool, code)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Invalid invite code")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Invalid invite code".into()))?;
 
         // Check if invite has expired
         if let Some(ref expires_at) = invite.expires_at
             && let Ok(expiry) = expires_at.parse::<chrono::DateTime<Utc>>()
             && Utc::now() > expiry
         {
-            return Err("Invite has expired".into());
+            return Err(EngineError::Validation("Invite has expired".into()));
         }
+        self.check_invites_open(&invite.server_id)?;
 
         // Check if user is already a member
         if let Some(server) = self.servers.get(&invite.server_id)
             && server.member_user_ids.contains(&user_id)
         {
-            return Err("Already a member of this server".into());
+            return Err(EngineError::Conflict(
+                "Already a member of this server".into(),
+            ));
         }
 
         // Atomically check max_uses and increment use_count (prevents TOCTOU race)
         let used = crate::db::queries::invites::try_use_invite(pool, &invite.id)
             .await
-            .map_err(|e| format!("Failed to use invite: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to use invite: {e}")))?;
         if !used {
-            return Err("Invite has reached maximum uses".into());
+            return Err(EngineError::Validation(
+                "Invite has reached maximum uses".into(),
+            ));
         }
 
         // Add user as server member
@@ -4230,7 +7470,9 @@ impl ChatEngine {
             .get(&(invite.server_id.clone(), "#general".to_string()))
             .map(|r| r.clone());
         if default_channel.is_some() {
-            let _ = self.join_channel(session_id, &invite.server_id, "#general");
+            let _ = self
+                .join_channel(session_id, &invite.server_id, "#general")
+                .await;
         }
 
         // Send updated server list to the user
@@ -4247,7 +7489,7 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         params: &crate::db::models::CreateServerEventParams<'_>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         self.require_permission(
             session_id,
             params.server_id,
@@ -4257,12 +7499,12 @@ impl ChatEngine {
         .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         crate::db::queries::events::create_event(pool, params)
             .await
-            .map_err(|e| format!("Failed to create event: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to create event: {e}")))?;
 
         let event_info = EventInfo {
             id: params.id.to_string(),
@@ -4289,17 +7531,39 @@ impl ChatEngine {
     }
 
     /// List events for a server. Requires VIEW_CHANNELS permission.
-    pub async fn list_events(&self, session_id: SessionId, server_id: &str) -> Result<(), String> {
-        self.require_permission(session_id, server_id, None, Permissions::VIEW_CHANNELS)
+    pub async fn list_events(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+    ) -> Result<(), EngineError> {
+        let events = self.fetch_events(session_id, server_id).await?;
+
+        if let Some(session) = self.get_session(session_id) {
+            let _ = session.send(ChatEvent::EventList {
+                server_id: server_id.to_string(),
+                events,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the events for a server with RSVP counts. Requires VIEW_CHANNELS permission.
+    pub async fn fetch_events(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+    ) -> Result<Vec<EventInfo>, EngineError> {
+        self.require_read_permission(session_id, server_id, None, Permissions::VIEW_CHANNELS)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let rows = crate::db::queries::events::list_server_events(pool, server_id)
             .await
-            .map_err(|e| format!("Failed to list events: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to list events: {e}")))?;
 
         let mut events = Vec::new();
         for row in rows {
@@ -4322,14 +7586,7 @@ impl ChatEngine {
             });
         }
 
-        if let Some(session) = self.get_session(session_id) {
-            let _ = session.send(ChatEvent::EventList {
-                server_id: server_id.to_string(),
-                events,
-            });
-        }
-
-        Ok(())
+        Ok(events)
     }
 
     /// Update an event's status. Requires MANAGE_SERVER permission.
@@ -4339,28 +7596,29 @@ impl ChatEngine {
         server_id: &str,
         event_id: &str,
         status: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         // Validate status
         if !["scheduled", "active", "completed", "cancelled"].contains(&status) {
-            return Err("Invalid status. Must be: scheduled, active, completed, cancelled".into());
+            return Err(EngineError::Validation(
+                "Invalid status. Must be: scheduled, active, completed, cancelled".into(),
+            ));
         }
 
         crate::db::queries::events::update_event_status(pool, event_id, status)
             .await
-            .map_err(|e| format!("Failed to update event status: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to update event status: {e}")))?;
 
         // Fetch updated event
         let row = crate::db::queries::events::get_event(pool, event_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Event not found")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Event not found".into()))?;
 
         let rsvp_count = crate::db::queries::events::get_rsvp_count(pool, event_id)
             .await
@@ -4396,17 +7654,17 @@ impl ChatEngine {
         session_id: SessionId,
         server_id: &str,
         event_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         crate::db::queries::events::delete_event(pool, event_id)
             .await
-            .map_err(|e| format!("Failed to delete event: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to delete event: {e}")))?;
 
         let event = ChatEvent::EventDelete {
             server_id: server_id.to_string(),
@@ -4424,28 +7682,28 @@ impl ChatEngine {
         server_id: &str,
         event_id: &str,
         status: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let user_id = self
             .require_permission(session_id, server_id, None, Permissions::VIEW_CHANNELS)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         // Validate RSVP status
         if !["interested", "going", "not_going"].contains(&status) {
-            return Err("Invalid RSVP status. Must be: interested, going, not_going".into());
+            return Err(EngineError::Validation(
+                "Invalid RSVP status. Must be: interested, going, not_going".into(),
+            ));
         }
 
         crate::db::queries::events::set_rsvp(pool, event_id, &user_id, status)
             .await
-            .map_err(|e| format!("Failed to set RSVP: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to set RSVP: {e}")))?;
 
         // Send updated RSVP list to the session
-        let rsvp_rows = crate::db::queries::events::get_rsvps(pool, event_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
+        let rsvp_rows = crate::db::queries::events::get_rsvps(pool, event_id).await?;
 
         let rsvps: Vec<RsvpInfo> = rsvp_rows
             .into_iter()
@@ -4471,31 +7729,33 @@ impl ChatEngine {
         session_id: SessionId,
         server_id: &str,
         event_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let user_id = self
             .require_permission(session_id, server_id, None, Permissions::VIEW_CHANNELS)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         crate::db::queries::events::remove_rsvp(pool, event_id, &user_id)
             .await
-            .map_err(|e| format!("Failed to remove RSVP: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to remove RSVP: {e}")))?;
 
         Ok(())
     }
 
     /// List RSVPs for an event. Sends EventRsvpList to the requesting session.
-    pub async fn list_rsvps(&self, session_id: SessionId, event_id: &str) -> Result<(), String> {
+    pub async fn list_rsvps(
+        &self,
+        session_id: SessionId,
+        event_id: &str,
+    ) -> Result<(), EngineError> {
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
-        let rsvp_rows = crate::db::queries::events::get_rsvps(pool, event_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
+        let rsvp_rows = crate::db::queries::events::get_rsvps(pool, event_id).await?;
 
         let rsvps: Vec<RsvpInfo> = rsvp_rows
             .into_iter()
@@ -4528,12 +7788,12 @@ impl ChatEngine {
         welcome_message: Option<&str>,
         rules_text: Option<&str>,
         category: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         crate::db::queries::community::update_server_community(
@@ -4546,7 +7806,7 @@ impl ChatEngine {
             category,
         )
         .await
-        .map_err(|e| format!("Failed to update community settings: {e}"))?;
+        .map_err(|e| EngineError::Internal(format!("Failed to update community settings: {e}")))?;
 
         let community = ServerCommunityInfo {
             server_id: server_id.to_string(),
@@ -4569,18 +7829,17 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         server_id: &str,
-    ) -> Result<(), String> {
-        self.require_permission(session_id, server_id, None, Permissions::VIEW_CHANNELS)
+    ) -> Result<(), EngineError> {
+        self.require_read_permission(session_id, server_id, None, Permissions::VIEW_CHANNELS)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let server = crate::db::queries::servers::get_server(pool, server_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Server not found")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Server not found".into()))?;
 
         let community = ServerCommunityInfo {
             server_id: server.id,
@@ -4603,19 +7862,34 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         category: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         // Verify the session exists (must be authenticated)
-        let session = self.get_session(session_id).ok_or("Session not found")?;
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+
+        let servers = self.fetch_discoverable_servers(category).await?;
+        let _ = session.send(ChatEvent::DiscoverServers { servers });
+
+        Ok(())
+    }
 
+    /// Fetch public (discoverable) servers, optionally filtered by category.
+    pub async fn fetch_discoverable_servers(
+        &self,
+        category: Option<&str>,
+    ) -> Result<Vec<ServerCommunityInfo>, EngineError> {
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let rows = crate::db::queries::community::list_discoverable_servers(pool, category, 100, 0)
             .await
-            .map_err(|e| format!("Failed to list discoverable servers: {e}"))?;
+            .map_err(|e| {
+                EngineError::Internal(format!("Failed to list discoverable servers: {e}"))
+            })?;
 
-        let servers: Vec<ServerCommunityInfo> = rows
+        Ok(rows
             .into_iter()
             .map(|r| ServerCommunityInfo {
                 server_id: r.id,
@@ -4625,39 +7899,41 @@ impl ChatEngine {
                 rules_text: r.rules_text,
                 category: r.category,
             })
-            .collect();
-
-        let _ = session.send(ChatEvent::DiscoverServers { servers });
-
-        Ok(())
+            .collect())
     }
 
     /// Accept server rules as a member.
-    pub async fn accept_rules(&self, session_id: SessionId, server_id: &str) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
+    pub async fn accept_rules(
+        &self,
+        session_id: SessionId,
+        server_id: &str,
+    ) -> Result<(), EngineError> {
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
         let user_id = session
             .user_id
             .as_deref()
-            .ok_or("AUTH_REQUIRED")?
+            .ok_or(EngineError::AuthRequired)?
             .to_string();
 
         // Verify membership
         let server = self
             .servers
             .get(server_id)
-            .ok_or(format!("Server not found: {server_id}"))?;
+            .ok_or_else(|| EngineError::NotFound(format!("Server not found: {server_id}")))?;
         if !server.member_user_ids.contains(&user_id) {
-            return Err("Not a member of this server".into());
+            return Err(EngineError::Forbidden("Not a member of this server".into()));
         }
         drop(server);
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         crate::db::queries::community::accept_rules(pool, server_id, &user_id)
             .await
-            .map_err(|e| format!("Failed to accept rules: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to accept rules: {e}")))?;
 
         Ok(())
     }
@@ -4671,12 +7947,12 @@ impl ChatEngine {
         server_id: &str,
         channel_name: &str,
         is_announcement: bool,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         self.require_permission(session_id, server_id, None, Permissions::MANAGE_CHANNELS)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let channel_name = normalize_channel_name(channel_name);
@@ -4684,7 +7960,9 @@ impl ChatEngine {
 
         crate::db::queries::community::set_announcement_channel(pool, &channel_id, is_announcement)
             .await
-            .map_err(|e| format!("Failed to set announcement channel: {e}"))?;
+            .map_err(|e| {
+                EngineError::Internal(format!("Failed to set announcement channel: {e}"))
+            })?;
 
         Ok(())
     }
@@ -4696,13 +7974,13 @@ impl ChatEngine {
         session_id: SessionId,
         source_channel_id: &str,
         target_channel_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         // Determine the target channel's server for permission check
         let target_server_id = self
             .channels
             .get(target_channel_id)
             .map(|ch| ch.server_id.clone())
-            .ok_or("Target channel not found")?;
+            .ok_or_else(|| EngineError::NotFound("Target channel not found".into()))?;
 
         let user_id = self
             .require_permission(
@@ -4714,7 +7992,7 @@ impl ChatEngine {
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let follow_id = Uuid::new_v4().to_string();
@@ -4726,7 +8004,7 @@ impl ChatEngine {
             &user_id,
         )
         .await
-        .map_err(|e| format!("Failed to create channel follow: {e}"))?;
+        .map_err(|e| EngineError::Internal(format!("Failed to create channel follow: {e}")))?;
 
         let follow = ChannelFollowInfo {
             id: follow_id,
@@ -4747,22 +8025,20 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         follow_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         // Look up the follow record to determine the source channel and server.
         let follow = crate::db::queries::community::get_channel_follow(pool, follow_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Follow not found")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Follow not found".into()))?;
 
         // From the source channel, determine the server_id.
         let channel = crate::db::queries::channels::get_channel(pool, &follow.source_channel_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Source channel not found")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Source channel not found".into()))?;
 
         // Require MANAGE_CHANNELS permission on that server.
         self.require_permission(
@@ -4775,9 +8051,11 @@ impl ChatEngine {
 
         crate::db::queries::community::delete_channel_follow(pool, follow_id)
             .await
-            .map_err(|e| format!("Failed to delete channel follow: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to delete channel follow: {e}")))?;
 
-        let session = self.get_session(session_id).ok_or("Session not found")?;
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
         let _ = session.send(ChatEvent::ChannelFollowDelete {
             follow_id: follow_id.to_string(),
         });
@@ -4790,16 +8068,16 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         channel_id: &str,
-    ) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
+    ) -> Result<(), EngineError> {
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
-        let rows = crate::db::queries::community::list_channel_follows(pool, channel_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
+        let rows = crate::db::queries::community::list_channel_follows(pool, channel_id).await?;
 
         let follows: Vec<ChannelFollowInfo> = rows
             .into_iter()
@@ -4829,13 +8107,13 @@ impl ChatEngine {
         server_id: &str,
         name: &str,
         description: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let user_id = self
             .require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         // Snapshot server config: channels, categories, roles
@@ -4861,7 +8139,7 @@ impl ChatEngine {
             &config_str,
         )
         .await
-        .map_err(|e| format!("Failed to create template: {e}"))?;
+        .map_err(|e| EngineError::Internal(format!("Failed to create template: {e}")))?;
 
         let template = TemplateInfo {
             id: template_id,
@@ -4888,16 +8166,16 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         server_id: &str,
-    ) -> Result<(), String> {
-        let session = self.get_session(session_id).ok_or("Session not found")?;
+    ) -> Result<(), EngineError> {
+        let session = self
+            .get_session(session_id)
+            .ok_or(EngineError::SessionNotFound)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
-        let rows = crate::db::queries::community::list_templates(pool, server_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
+        let rows = crate::db::queries::community::list_templates(pool, server_id).await?;
 
         let templates: Vec<TemplateInfo> = rows
             .into_iter()
@@ -4926,17 +8204,17 @@ impl ChatEngine {
         session_id: SessionId,
         server_id: &str,
         template_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         crate::db::queries::community::delete_template(pool, template_id)
             .await
-            .map_err(|e| format!("Failed to delete template: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to delete template: {e}")))?;
 
         if let Some(session) = self.get_session(session_id) {
             let _ = session.send(ChatEvent::TemplateDelete {
@@ -4959,22 +8237,24 @@ impl ChatEngine {
         name: &str,
         webhook_type: &str,
         url: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         if webhook_type != "incoming" && webhook_type != "outgoing" {
-            return Err("webhook_type must be 'incoming' or 'outgoing'".into());
+            return Err(EngineError::Validation(
+                "webhook_type must be 'incoming' or 'outgoing'".into(),
+            ));
         }
 
         let id = Uuid::new_v4().to_string();
         let raw_token = format!("{}.{}", id, Uuid::new_v4());
         let token_hash = crate::auth::token::hash_irc_token(&raw_token)
-            .map_err(|e| format!("Failed to hash webhook token: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to hash webhook token: {e}")))?;
 
         use crate::db::models::CreateWebhookParams;
         let params = CreateWebhookParams {
@@ -4991,7 +8271,7 @@ impl ChatEngine {
 
         crate::db::queries::webhooks::create_webhook(pool, &params)
             .await
-            .map_err(|e| format!("Failed to create webhook: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to create webhook: {e}")))?;
 
         let webhook = WebhookInfo {
             id: id.clone(),
@@ -5022,17 +8302,17 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         server_id: &str,
-    ) -> Result<(), String> {
-        self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
+    ) -> Result<(), EngineError> {
+        self.require_read_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let rows = crate::db::queries::webhooks::list_webhooks(pool, server_id)
             .await
-            .map_err(|e| format!("Failed to list webhooks: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to list webhooks: {e}")))?;
 
         let webhooks: Vec<WebhookInfo> = rows.into_iter().map(webhook_row_to_info).collect();
 
@@ -5054,15 +8334,14 @@ impl ChatEngine {
         name: &str,
         avatar_url: Option<&str>,
         channel_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let wh = crate::db::queries::webhooks::get_webhook(pool, webhook_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Webhook not found")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Webhook not found".into()))?;
 
         self.require_permission(session_id, &wh.server_id, None, Permissions::MANAGE_SERVER)
             .await?;
@@ -5071,7 +8350,7 @@ impl ChatEngine {
             pool, webhook_id, name, avatar_url, channel_id,
         )
         .await
-        .map_err(|e| format!("Failed to update webhook: {e}"))?;
+        .map_err(|e| EngineError::Internal(format!("Failed to update webhook: {e}")))?;
 
         let sid = wh.server_id.clone();
         let updated = WebhookInfo {
@@ -5103,22 +8382,21 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         webhook_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let wh = crate::db::queries::webhooks::get_webhook(pool, webhook_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Webhook not found")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Webhook not found".into()))?;
 
         self.require_permission(session_id, &wh.server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         crate::db::queries::webhooks::delete_webhook(pool, webhook_id)
             .await
-            .map_err(|e| format!("Failed to delete webhook: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to delete webhook: {e}")))?;
 
         let sid = wh.server_id;
         self.broadcast_to_server(
@@ -5138,11 +8416,11 @@ impl ChatEngine {
         session_id: SessionId,
         username: &str,
         avatar_url: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let _creator_id = self.get_user_id(session_id)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         validation::validate_nickname(username)?;
@@ -5150,13 +8428,13 @@ impl ChatEngine {
         let bot_user_id = Uuid::new_v4().to_string();
         crate::db::queries::bots::create_bot_user(pool, &bot_user_id, username, avatar_url)
             .await
-            .map_err(|e| format!("Failed to create bot: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to create bot: {e}")))?;
 
         // Generate an initial token
         let token_id = Uuid::new_v4().to_string();
         let raw_token = format!("bot_{}.{}", bot_user_id, Uuid::new_v4());
         let token_hash = crate::auth::token::hash_irc_token(&raw_token)
-            .map_err(|e| format!("Failed to hash token: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to hash token: {e}")))?;
 
         crate::db::queries::bots::create_bot_token(
             pool,
@@ -5167,7 +8445,7 @@ impl ChatEngine {
             "bot",
         )
         .await
-        .map_err(|e| format!("Failed to create bot token: {e}"))?;
+        .map_err(|e| EngineError::Internal(format!("Failed to create bot token: {e}")))?;
 
         if let Some(session) = self.get_session(session_id) {
             // Send back the raw token (only time it's visible)
@@ -5186,18 +8464,18 @@ impl ChatEngine {
         bot_user_id: &str,
         name: &str,
         scopes: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         // Verify the caller has MANAGE_SERVER on at least one server the bot belongs to.
-        let bot_servers = crate::db::queries::bots::list_bot_server_ids(pool, bot_user_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
+        let bot_servers = crate::db::queries::bots::list_bot_server_ids(pool, bot_user_id).await?;
 
         if bot_servers.is_empty() {
-            return Err("Bot is not a member of any server".into());
+            return Err(EngineError::Forbidden(
+                "Bot is not a member of any server".into(),
+            ));
         }
 
         let mut has_permission = false;
@@ -5212,13 +8490,15 @@ impl ChatEngine {
             }
         }
         if !has_permission {
-            return Err("FORBIDDEN: insufficient permissions".into());
+            return Err(EngineError::PermissionDenied {
+                perm: Permissions::MANAGE_SERVER,
+            });
         }
 
         let token_id = Uuid::new_v4().to_string();
         let raw_token = format!("bot_{}.{}", bot_user_id, Uuid::new_v4());
         let token_hash = crate::auth::token::hash_irc_token(&raw_token)
-            .map_err(|e| format!("Failed to hash token: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to hash token: {e}")))?;
 
         crate::db::queries::bots::create_bot_token(
             pool,
@@ -5229,7 +8509,7 @@ impl ChatEngine {
             scopes.unwrap_or("bot"),
         )
         .await
-        .map_err(|e| format!("Failed to create bot token: {e}"))?;
+        .map_err(|e| EngineError::Internal(format!("Failed to create bot token: {e}")))?;
 
         if let Some(session) = self.get_session(session_id) {
             let _ = session.send(ChatEvent::ServerNotice {
@@ -5245,16 +8525,16 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         bot_user_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let _user_id = self.get_user_id(session_id)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let rows = crate::db::queries::bots::list_bot_tokens(pool, bot_user_id)
             .await
-            .map_err(|e| format!("Failed to list bot tokens: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to list bot tokens: {e}")))?;
 
         let tokens: Vec<BotTokenInfo> = rows
             .into_iter()
@@ -5282,16 +8562,16 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         token_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let _user_id = self.get_user_id(session_id)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         crate::db::queries::bots::delete_bot_token(pool, token_id)
             .await
-            .map_err(|e| format!("Failed to delete bot token: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to delete bot token: {e}")))?;
 
         Ok(())
     }
@@ -5302,17 +8582,17 @@ impl ChatEngine {
         session_id: SessionId,
         server_id: &str,
         bot_user_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         crate::db::queries::bots::add_bot_to_server(pool, server_id, bot_user_id)
             .await
-            .map_err(|e| format!("Failed to add bot to server: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to add bot to server: {e}")))?;
 
         Ok(())
     }
@@ -5323,17 +8603,17 @@ impl ChatEngine {
         session_id: SessionId,
         server_id: &str,
         bot_user_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         self.require_permission(session_id, server_id, None, Permissions::MANAGE_SERVER)
             .await?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         crate::db::queries::bots::remove_bot_from_server(pool, server_id, bot_user_id)
             .await
-            .map_err(|e| format!("Failed to remove bot from server: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to remove bot from server: {e}")))?;
 
         Ok(())
     }
@@ -5346,22 +8626,24 @@ impl ChatEngine {
         name: &str,
         description: &str,
         options_json: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let _user_id = self.get_user_id(session_id)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         if name.is_empty() || name.len() > 32 || name.contains(' ') {
-            return Err("Command name must be 1-32 chars with no spaces".into());
+            return Err(EngineError::Validation(
+                "Command name must be 1-32 chars with no spaces".into(),
+            ));
         }
 
         let id = Uuid::new_v4().to_string();
         let opts = options_json.unwrap_or("[]");
         // Validate JSON
         serde_json::from_str::<Vec<SlashCommandOption>>(opts)
-            .map_err(|e| format!("Invalid options JSON: {e}"))?;
+            .map_err(|e| EngineError::Validation(format!("Invalid options JSON: {e}")))?;
 
         use crate::db::models::CreateSlashCommandParams;
         let params = CreateSlashCommandParams {
@@ -5375,7 +8657,7 @@ impl ChatEngine {
 
         crate::db::queries::slash_commands::create_command(pool, &params)
             .await
-            .map_err(|e| format!("Failed to register command: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to register command: {e}")))?;
 
         let options: Vec<SlashCommandOption> = serde_json::from_str(opts).unwrap_or_default();
         let cmd = SlashCommandInfo {
@@ -5402,16 +8684,16 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         server_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let _user_id = self.get_user_id(session_id)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let rows = crate::db::queries::slash_commands::list_commands_for_server(pool, server_id)
             .await
-            .map_err(|e| format!("Failed to list commands: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to list commands: {e}")))?;
 
         let commands: Vec<SlashCommandInfo> = rows
             .into_iter()
@@ -5443,15 +8725,14 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         command_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let cmd = crate::db::queries::slash_commands::get_command(pool, command_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Command not found")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Command not found".into()))?;
 
         // Require MANAGE_SERVER permission on the command's server.
         if let Some(sid) = &cmd.server_id {
@@ -5464,7 +8745,7 @@ impl ChatEngine {
 
         crate::db::queries::slash_commands::delete_command(pool, command_id)
             .await
-            .map_err(|e| format!("Failed to delete command: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to delete command: {e}")))?;
 
         if let Some(sid) = &cmd.server_id {
             self.broadcast_to_server(
@@ -5487,23 +8768,21 @@ impl ChatEngine {
         channel: &str,
         command_name: &str,
         args_json: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let user_id = self.get_user_id(session_id)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         // Find the command by name in this server
         let commands =
-            crate::db::queries::slash_commands::list_commands_for_server(pool, server_id)
-                .await
-                .map_err(|e| format!("DB error: {e}"))?;
+            crate::db::queries::slash_commands::list_commands_for_server(pool, server_id).await?;
 
         let cmd = commands
             .iter()
             .find(|c| c.name == command_name)
-            .ok_or_else(|| format!("Unknown command: /{command_name}"))?;
+            .ok_or_else(|| EngineError::NotFound(format!("Unknown command: /{command_name}")))?;
 
         let interaction_id = Uuid::new_v4().to_string();
         let data: serde_json::Value = args_json
@@ -5526,7 +8805,7 @@ impl ChatEngine {
         };
         crate::db::queries::slash_commands::create_interaction(pool, &interaction_params)
             .await
-            .map_err(|e| format!("Failed to create interaction: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to create interaction: {e}")))?;
 
         let interaction = InteractionInfo {
             id: interaction_id.clone(),
@@ -5569,21 +8848,20 @@ impl ChatEngine {
         embeds_json: Option<&str>,
         components_json: Option<&str>,
         ephemeral: bool,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let _user_id = self.get_user_id(session_id)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let interaction = crate::db::queries::slash_commands::get_interaction(pool, interaction_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Interaction not found")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Interaction not found".into()))?;
 
         crate::db::queries::slash_commands::mark_interaction_responded(pool, interaction_id)
             .await
-            .map_err(|e| format!("Failed to mark interaction: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to mark interaction: {e}")))?;
 
         let embeds = embeds_json.and_then(|s| serde_json::from_str(s).ok());
         let components = components_json.and_then(|s| serde_json::from_str(s).ok());
@@ -5637,19 +8915,19 @@ impl ChatEngine {
         name: &str,
         description: Option<&str>,
         redirect_uris: &[String],
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let user_id = self.get_user_id(session_id)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let id = Uuid::new_v4().to_string();
         let raw_secret = format!("secret_{}", Uuid::new_v4());
         let secret_hash = crate::auth::token::hash_irc_token(&raw_secret)
-            .map_err(|e| format!("Failed to hash OAuth2 secret: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to hash OAuth2 secret: {e}")))?;
         let uris_json = serde_json::to_string(redirect_uris)
-            .map_err(|e| format!("Invalid redirect URIs: {e}"))?;
+            .map_err(|e| EngineError::Validation(format!("Invalid redirect URIs: {e}")))?;
 
         use crate::db::models::CreateOAuth2AppParams;
         let params = CreateOAuth2AppParams {
@@ -5665,7 +8943,7 @@ impl ChatEngine {
 
         crate::db::queries::oauth2::create_app(pool, &params)
             .await
-            .map_err(|e| format!("Failed to create OAuth2 app: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to create OAuth2 app: {e}")))?;
 
         if let Some(session) = self.get_session(session_id) {
             let app = OAuth2AppInfo {
@@ -5692,16 +8970,16 @@ impl ChatEngine {
     }
 
     /// List OAuth2 apps owned by the current user.
-    pub async fn list_oauth2_apps(&self, session_id: SessionId) -> Result<(), String> {
+    pub async fn list_oauth2_apps(&self, session_id: SessionId) -> Result<(), EngineError> {
         let user_id = self.get_user_id(session_id)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let rows = crate::db::queries::oauth2::list_apps_by_owner(pool, &user_id)
             .await
-            .map_err(|e| format!("Failed to list OAuth2 apps: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to list OAuth2 apps: {e}")))?;
 
         let apps: Vec<OAuth2AppInfo> = rows
             .into_iter()
@@ -5733,25 +9011,26 @@ impl ChatEngine {
         &self,
         session_id: SessionId,
         app_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let user_id = self.get_user_id(session_id)?;
 
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let app = crate::db::queries::oauth2::get_app(pool, app_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("OAuth2 app not found")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("OAuth2 app not found".into()))?;
 
         if app.owner_id != user_id {
-            return Err("You can only delete your own apps".into());
+            return Err(EngineError::Forbidden(
+                "You can only delete your own apps".into(),
+            ));
         }
 
         crate::db::queries::oauth2::delete_app(pool, app_id)
             .await
-            .map_err(|e| format!("Failed to delete app: {e}"))?;
+            .map_err(|e| EngineError::Internal(format!("Failed to delete app: {e}")))?;
 
         Ok(())
     }
@@ -5765,24 +9044,26 @@ impl ChatEngine {
         content: &str,
         username_override: Option<&str>,
         avatar_override: Option<&str>,
-    ) -> Result<(), String> {
+    ) -> Result<(), EngineError> {
         let Some(pool) = &self.db else {
-            return Err("No database configured".into());
+            return Err(EngineError::NoDatabase);
         };
 
         let wh = crate::db::queries::webhooks::get_webhook(pool, webhook_id)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?
-            .ok_or("Invalid webhook")?;
+            .await?
+            .ok_or_else(|| EngineError::NotFound("Invalid webhook".into()))?;
 
         // Verify the token against the stored hash
         if !crate::auth::token::verify_irc_token(webhook_token, &wh.token) {
-            return Err("Invalid webhook token".into());
+            return Err(EngineError::InvalidToken("Invalid webhook token".into()));
         }
 
         if wh.webhook_type != "incoming" {
-            return Err("This endpoint is only for incoming webhooks".into());
+            return Err(EngineError::Validation(
+                "This endpoint is only for incoming webhooks".into(),
+            ));
         }
+        self.check_not_quarantined(&wh.server_id)?;
 
         let channel_name = self.resolve_channel_name_from_id(&wh.channel_id)?;
         // Tag webhook display names to prevent impersonation of real users
@@ -5833,44 +9114,41 @@ impl ChatEngine {
     }
 
     /// Helper: get user_id for a session.
-    fn get_user_id(&self, session_id: SessionId) -> Result<String, String> {
-        let session = self.sessions.get(&session_id).ok_or("Session not found")?;
-        session
-            .user_id
-            .clone()
-            .ok_or_else(|| "AUTH_REQUIRED".into())
+    fn get_user_id(&self, session_id: SessionId) -> Result<String, EngineError> {
+        let session = self
+            .sessions
+            .get(&session_id)
+            .ok_or(EngineError::SessionNotFound)?;
+        session.user_id.clone().ok_or(EngineError::AuthRequired)
     }
 
     /// Helper: resolve channel name from a channel_id by looking it up in self.channels.
-    fn resolve_channel_name_from_id(&self, channel_id: &str) -> Result<String, String> {
+    fn resolve_channel_name_from_id(&self, channel_id: &str) -> Result<String, EngineError> {
         self.channels
             .get(channel_id)
             .map(|ch| ch.name.clone())
-            .ok_or_else(|| format!("Channel ID {channel_id} not found"))
+            .ok_or_else(|| EngineError::NotFound(format!("Channel ID {channel_id} not found")))
     }
 }
 
 /// Validate automod rule config JSON matches the expected schema for its rule_type.
-fn validate_automod_config(rule_type: &str, config: &str) -> Result<(), String> {
-    let parsed: serde_json::Value =
-        serde_json::from_str(config).map_err(|_| "Invalid JSON in automod config".to_string())?;
+fn validate_automod_config(rule_type: &str, config: &str) -> Result<(), EngineError> {
+    let parsed: serde_json::Value = serde_json::from_str(config)
+        .map_err(|_| EngineError::Validation("Invalid JSON in automod config".into()))?;
 
     match rule_type {
         "keyword" => {
-            // Expect {"words": ["word1", "word2", ...]}
-            let words = parsed
-                .get("words")
-                .and_then(|v| v.as_array())
-                .ok_or("keyword config must have a 'words' array")?;
-            if words.is_empty() {
-                return Err("keyword config 'words' array must not be empty".into());
-            }
-            if words.len() > 1000 {
-                return Err("keyword config 'words' array exceeds maximum of 1000 entries".into());
-            }
-            for w in words {
-                if w.as_str().is_none() {
-                    return Err("keyword config 'words' must contain only strings".into());
+            // Expect {"words": [...], "patterns": [...], "allow": [...]}, with at
+            // least one word or pattern
+            for key in ["words", "patterns", "allow"] {
+                if parsed
+                    .get(key)
+                    .and_then(|v| v.as_array())
+                    .is_some_and(|a| a.len() > 1000)
+                {
+                    return Err(EngineError::Validation(format!(
+                        "keyword config '{key}' array exceeds maximum of 1000 entries"
+                    )));
                 }
             }
         }
@@ -5879,25 +9157,265 @@ fn validate_automod_config(rule_type: &str, config: &str) -> Result<(), String>
             let max = parsed
                 .get("max_mentions")
                 .and_then(|v| v.as_i64())
-                .ok_or("mention_spam config must have a 'max_mentions' integer")?;
+                .ok_or_else(|| {
+                    EngineError::Validation(
+                        "mention_spam config must have a 'max_mentions' integer".into(),
+                    )
+                })?;
             if !(1..=100).contains(&max) {
-                return Err("mention_spam 'max_mentions' must be between 1 and 100".into());
+                return Err(EngineError::Validation(
+                    "mention_spam 'max_mentions' must be between 1 and 100".into(),
+                ));
             }
         }
         "link_filter" => {
             // Expect {"block_all": <bool>} or {"allowed_domains": [...]}
             if parsed.get("block_all").is_none() && parsed.get("allowed_domains").is_none() {
-                return Err(
+                return Err(EngineError::Validation(
                     "link_filter config must have 'block_all' (bool) or 'allowed_domains' (array)"
                         .into(),
-                );
+                ));
             }
         }
+        // Behavioural rules: every setting is optional, and bounds are
+        // checked by automod::validate_config below
+        "duplicate_spam" | "message_flood" | "excessive_caps" | "zalgo" | "invite_spam"
+        | "join_burst" | "raid" => {}
         _ => {
-            return Err(format!("Unknown rule type: {rule_type}"));
+            return Err(EngineError::Validation(format!(
+                "Unknown rule type: {rule_type}"
+            )));
         }
     }
-    Ok(())
+    // Optional {"actions": [{"type": "block"}, {"type": "alert", "channel_id": ...}, ...]};
+    // without it the rule's action_type applies
+    if let Some(actions) = parsed.get("actions") {
+        automod::parse_actions(rule_type, actions)?;
+    }
+    automod::validate_config(rule_type, &parsed)
+}
+
+/// The server each invite link in `content` points at, for invite_spam rules.
+/// Links to other services, and codes that aren't invites here, map to `None`.
+async fn invite_link_servers(pool: &SqlitePool, content: &str) -> Vec<Option<String>> {
+    let mut servers = Vec::new();
+    for link in automod::invite_links(content) {
+        servers.push(match link {
+            automod::InviteLink::Code(code) => {
+                crate::db::queries::invites::get_invite_by_code(pool, &code)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|invite| invite.server_id)
+            }
+            automod::InviteLink::External => None,
+        });
+    }
+    servers
+}
+
+/// Load a server's roles for the permission cache. Database errors fall back to
+/// the built-in @everyone permissions, as if the server had no roles.
+async fn load_cached_roles(pool: &SqlitePool, server_id: &str) -> CachedRoles {
+    let (everyone_role_id, everyone) =
+        match crate::db::queries::roles::get_default_role(pool, server_id).await {
+            Ok(Some(role)) => (
+                role.id,
+                Permissions::from_bits_truncate(role.permissions as u64),
+            ),
+            _ => (String::new(), DEFAULT_EVERYONE),
+        };
+    let permissions = crate::db::queries::roles::list_roles(pool, server_id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| (r.id, Permissions::from_bits_truncate(r.permissions as u64)))
+        .collect();
+    CachedRoles {
+        everyone_role_id,
+        everyone,
+        permissions,
+    }
+}
+
+/// Database spelling of an override's `target_type`.
+#[cfg(test)]
+fn override_target_type(target_type: &OverrideTargetType) -> &'static str {
+    match target_type {
+        OverrideTargetType::Role => "role",
+        OverrideTargetType::User => "user",
+    }
+}
+
+/// Load a channel's permission overrides.
+async fn load_channel_overrides(pool: &SqlitePool, channel_id: &str) -> Vec<ChannelOverride> {
+    crate::db::queries::channels::get_channel_overrides(pool, channel_id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|o| ChannelOverride {
+            target_type: if o.target_type == "role" {
+                OverrideTargetType::Role
+            } else {
+                OverrideTargetType::User
+            },
+            target_id: o.target_id,
+            allow: Permissions::from_bits_truncate(o.allow_bits as u64),
+            deny: Permissions::from_bits_truncate(o.deny_bits as u64),
+        })
+        .collect()
+}
+
+/// When a strike issued now stops counting, in SQLite's datetime format.
+fn strike_expiry(seconds: u64) -> String {
+    (Utc::now() + chrono::Duration::seconds(seconds as i64))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+fn infraction_row_to_info(row: crate::db::models::InfractionRow) -> InfractionInfo {
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let active = row.pardoned_at.is_none()
+        && row.source != "escalation"
+        && row.expires_at.as_ref().is_none_or(|at| *at > now);
+    InfractionInfo {
+        id: row.id,
+        user_id: row.user_id,
+        actor_id: row.actor_id,
+        action: row.action,
+        source: row.source,
+        reason: row.reason,
+        expires_at: row.expires_at,
+        pardoned_by: row.pardoned_by,
+        pardoned_at: row.pardoned_at,
+        active,
+        created_at: row.created_at,
+    }
+}
+
+fn report_row_to_info(
+    row: crate::db::models::ReportRow,
+    context: Vec<HistoryMessage>,
+) -> ReportInfo {
+    ReportInfo {
+        id: row.id,
+        reporter_id: row.reporter_id,
+        target_user_id: row.target_user_id,
+        message_id: row.message_id,
+        channel_id: row.channel_id,
+        message_content: row.message_content,
+        reason: row.reason,
+        status: row.status,
+        resolution: row.resolution,
+        resolution_note: row.resolution_note,
+        resolved_by: row.resolved_by,
+        resolved_at: row.resolved_at,
+        created_at: row.created_at,
+        context,
+    }
+}
+
+/// Trim an optional admin reason, treating a blank one as none.
+fn admin_reason(reason: Option<&str>) -> Result<Option<&str>, EngineError> {
+    let reason = reason.map(str::trim).filter(|r| !r.is_empty());
+    if reason.is_some_and(|r| r.chars().count() > MAX_ADMIN_REASON_LENGTH) {
+        return Err(EngineError::Validation(format!(
+            "Reason must be at most {MAX_ADMIN_REASON_LENGTH} characters"
+        )));
+    }
+    Ok(reason)
+}
+
+/// Whether an ID looks like a DID (`did:<method>:<id>`).
+fn is_did(id: &str) -> bool {
+    let mut parts = id.splitn(3, ':');
+    parts.next() == Some("did")
+        && parts
+            .next()
+            .is_some_and(|m| !m.is_empty() && m.chars().all(|c| c.is_ascii_lowercase()))
+        && parts.next().is_some_and(|rest| !rest.is_empty())
+        && id.len() <= 2048
+}
+
+/// Record a system admin's action in the instance audit log.
+async fn instance_audit(
+    pool: &SqlitePool,
+    actor_id: &str,
+    action_type: &str,
+    target_type: &str,
+    target_id: &str,
+    reason: Option<&str>,
+    changes: Option<serde_json::Value>,
+) {
+    let changes = changes.map(|c| c.to_string());
+    if let Err(e) = crate::db::queries::instance::create_audit_entry(
+        pool,
+        &crate::db::models::CreateInstanceAuditParams {
+            id: &Uuid::new_v4().to_string(),
+            actor_id,
+            action_type,
+            target_type: Some(target_type),
+            target_id: Some(target_id),
+            reason,
+            changes: changes.as_deref(),
+        },
+    )
+    .await
+    {
+        warn!(error = %e, "Failed to write instance audit log entry");
+    }
+}
+
+/// The messages around a reported one, for moderators reviewing it.
+async fn report_context(
+    pool: &SqlitePool,
+    row: &crate::db::models::ReportRow,
+) -> Result<Vec<HistoryMessage>, EngineError> {
+    let Some(message_id) = &row.message_id else {
+        return Ok(Vec::new());
+    };
+    Ok(crate::db::queries::messages::fetch_message_context(
+        pool,
+        message_id,
+        REPORT_CONTEXT_MESSAGES,
+    )
+    .await?
+    .into_iter()
+    .map(context_message)
+    .collect())
+}
+
+/// A message shown around a reported one. Deleted messages keep their content
+/// here, since moderators need to see what was reported.
+fn context_message(row: crate::db::models::MessageRow) -> HistoryMessage {
+    HistoryMessage {
+        id: row.id.parse().unwrap_or_default(),
+        from: row.sender_nick,
+        content: row.content,
+        timestamp: chrono::NaiveDateTime::parse_from_str(&row.created_at, "%Y-%m-%d %H:%M:%S")
+            .map(|dt| dt.and_utc())
+            .unwrap_or_else(|_| Utc::now()),
+        edited_at: None,
+        reply_to: None,
+        reactions: None,
+        attachments: None,
+        embeds: None,
+    }
+}
+
+fn automod_queue_row_to_info(row: crate::db::models::AutomodQueueRow) -> AutomodQueueEntryInfo {
+    AutomodQueueEntryInfo {
+        id: row.id,
+        channel_id: row.channel_id,
+        rule_id: row.rule_id,
+        rule_name: row.rule_name,
+        user_id: row.user_id,
+        user_nick: row.user_nick,
+        content: row.content,
+        message_id: row.message_id,
+        status: row.status,
+        created_at: row.created_at,
+    }
 }
 
 /// Convert a RoleRow to a RoleInfo for client consumption.
@@ -5940,6 +9458,12 @@ fn webhook_row_to_info(row: crate::db::models::WebhookRow) -> WebhookInfo {
     }
 }
 
+/// Key of `nick_to_session`. Nicks compare ignoring ASCII case, matching the
+/// `CASEMAPPING=ascii` advertised to IRC clients.
+fn nick_key(nickname: &str) -> String {
+    nickname.to_ascii_lowercase()
+}
+
 /// Ensure channel names are lowercase and start with #.
 fn normalize_channel_name(name: &str) -> String {
     let name = name.to_lowercase();
@@ -5983,7 +9507,7 @@ mod tests {
             .unwrap();
         assert!(!engine.is_nick_available("alice"));
 
-        engine.disconnect(session_id);
+        engine.disconnect(session_id).await;
         assert!(engine.is_nick_available("alice"));
     }
 
@@ -6002,6 +9526,105 @@ mod tests {
         assert!(engine.get_session(sid2).is_some());
     }
 
+    #[tokio::test]
+    async fn test_change_nickname() {
+        let engine = setup_engine();
+
+        let (sid1, mut rx1) = engine
+            .connect(None, "alice".into(), Protocol::Irc, None)
+            .unwrap();
+        let (sid2, mut rx2) = engine
+            .connect(None, "bob".into(), Protocol::WebSocket, None)
+            .unwrap();
+        let (_sid3, mut rx3) = engine
+            .connect(None, "carol".into(), Protocol::WebSocket, None)
+            .unwrap();
+        engine
+            .join_channel(sid1, DEFAULT_SERVER_ID, "#general")
+            .await
+            .unwrap();
+        engine
+            .join_channel(sid2, DEFAULT_SERVER_ID, "#general")
+            .await
+            .unwrap();
+        while rx1.try_recv().is_ok() {}
+        while rx2.try_recv().is_ok() {}
+        while rx3.try_recv().is_ok() {}
+
+        // Taken and invalid nicknames are rejected
+        assert!(engine.change_nickname(sid1, "bob").await.is_err());
+        assert!(engine.change_nickname(sid1, "bad nick").await.is_err());
+
+        let old = engine.change_nickname(sid1, "alicia").await.unwrap();
+        assert_eq!(old, "alice");
+        assert!(engine.is_nick_available("alice"));
+        assert_eq!(engine.get_session_id_by_nick("alicia"), Some(sid1));
+        assert_eq!(engine.get_session(sid1).unwrap().nickname, "alicia");
+
+        // The renamed session and channel peers are notified; strangers are not
+        for rx in [&mut rx1, &mut rx2] {
+            match rx.try_recv().unwrap() {
+                ChatEvent::NickChange { old_nick, new_nick } => {
+                    assert_eq!(old_nick, "alice");
+                    assert_eq!(new_nick, "alicia");
+                }
+                other => panic!("Expected NickChange, got {other:?}"),
+            }
+        }
+        assert!(rx3.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_monitor_notifications() {
+        let engine = setup_engine();
+        let (watcher, mut rx) = engine
+            .connect(None, "watcher".into(), Protocol::Irc, None)
+            .unwrap();
+        let (statuses, rejected) = engine.monitor_add(watcher, &["Dana".into(), "erin".into()]);
+        assert_eq!(
+            statuses,
+            vec![("Dana".to_string(), false), ("erin".to_string(), false)]
+        );
+        assert!(rejected.is_empty());
+
+        let mut next = || match rx.try_recv().unwrap() {
+            ChatEvent::MonitorStatus { nickname, online } => (nickname, online),
+            other => panic!("Expected MonitorStatus, got {other:?}"),
+        };
+
+        let (dana, _dana_rx) = engine
+            .connect(
+                Some("dana-id".into()),
+                "dana".into(),
+                Protocol::WebSocket,
+                None,
+            )
+            .unwrap();
+        assert_eq!(next(), ("dana".to_string(), true));
+        assert_eq!(engine.visible_nick("DANA"), Some("dana".to_string()));
+
+        // Invisible users look offline, and come back online when visible again
+        engine
+            .set_presence(dana, "invisible", None, None)
+            .await
+            .unwrap();
+        assert_eq!(next(), ("dana".to_string(), false));
+        assert_eq!(engine.visible_nick("dana"), None);
+        engine.set_presence(dana, "idle", None, None).await.unwrap();
+        assert_eq!(next(), ("dana".to_string(), true));
+
+        engine.change_nickname(dana, "erin").await.unwrap();
+        assert_eq!(next(), ("dana".to_string(), false));
+        assert_eq!(next(), ("erin".to_string(), true));
+
+        engine.disconnect(dana).await;
+        assert_eq!(next(), ("erin".to_string(), false));
+
+        // A watcher's list is dropped when it disconnects
+        engine.disconnect(watcher).await;
+        assert_eq!(engine.monitor_watcher_count("erin"), 0);
+    }
+
     #[tokio::test]
     async fn test_join_and_message() {
         let engine = setup_engine();
@@ -6015,9 +9638,11 @@ mod tests {
 
         engine
             .join_channel(sid1, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         engine
             .join_channel(sid2, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
 
         while rx1.try_recv().is_ok() {}
@@ -6033,6 +9658,7 @@ mod tests {
                 None,
                 None,
             )
+            .await
             .unwrap();
 
         let event = rx2.try_recv().unwrap();
@@ -6063,9 +9689,11 @@ mod tests {
 
         engine
             .join_channel(sid1, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         engine
             .join_channel(sid2, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
 
         while rx1.try_recv().is_ok() {}
@@ -6090,6 +9718,7 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         while rx.try_recv().is_ok() {}
 
@@ -6100,6 +9729,7 @@ mod tests {
                 "#general",
                 "Welcome to Concord!".into(),
             )
+            .await
             .unwrap();
 
         let event = rx.try_recv().unwrap();
@@ -6124,6 +9754,7 @@ mod tests {
 
         engine
             .send_message(sid1, DEFAULT_SERVER_ID, "bob", "Hey Bob!", None, None, None)
+            .await
             .unwrap();
 
         let event = rx2.try_recv().unwrap();
@@ -6142,8 +9773,8 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_list_channels() {
+    #[tokio::test]
+    async fn test_list_channels() {
         let engine = setup_engine();
 
         let (sid, _rx) = engine
@@ -6151,9 +9782,11 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#rust")
+            .await
             .unwrap();
 
         let channels = engine.list_channels(DEFAULT_SERVER_ID);
@@ -6196,16 +9829,24 @@ mod tests {
             .connect(None, "alice".into(), Protocol::WebSocket, None)
             .unwrap();
 
-        engine.join_channel(sid, &server_a, "#general").unwrap();
+        engine
+            .join_channel(sid, &server_a, "#general")
+            .await
+            .unwrap();
         while rx.try_recv().is_ok() {}
 
         let (sid2, _rx2) = engine
             .connect(None, "bob".into(), Protocol::WebSocket, None)
             .unwrap();
-        engine.join_channel(sid2, &server_b, "#general").unwrap();
+        engine
+            .join_channel(sid2, &server_b, "#general")
+            .await
+            .unwrap();
 
         // Alice is not in server_b's #general — should fail
-        let result = engine.send_message(sid, &server_b, "#general", "Hello", None, None, None);
+        let result = engine
+            .send_message(sid, &server_b, "#general", "Hello", None, None, None)
+            .await;
         assert!(result.is_err());
     }
 
@@ -6218,7 +9859,7 @@ mod tests {
         let engine = setup_engine();
         let fake_id = Uuid::new_v4();
         // Should not panic
-        engine.disconnect(fake_id);
+        engine.disconnect(fake_id).await;
     }
 
     #[tokio::test]
@@ -6300,15 +9941,17 @@ mod tests {
         let (sid, _rx) = engine
             .connect(None, "alice".into(), Protocol::WebSocket, None)
             .unwrap();
-        let result = engine.send_message(
-            sid,
-            DEFAULT_SERVER_ID,
-            "#nonexistent",
-            "hello",
-            None,
-            None,
-            None,
-        );
+        let result = engine
+            .send_message(
+                sid,
+                DEFAULT_SERVER_ID,
+                "#nonexistent",
+                "hello",
+                None,
+                None,
+                None,
+            )
+            .await;
         assert!(result.is_err());
     }
 
@@ -6316,8 +9959,9 @@ mod tests {
     async fn test_send_message_with_invalid_session() {
         let engine = setup_engine();
         let fake = Uuid::new_v4();
-        let result =
-            engine.send_message(fake, DEFAULT_SERVER_ID, "#general", "hi", None, None, None);
+        let result = engine
+            .send_message(fake, DEFAULT_SERVER_ID, "#general", "hi", None, None, None)
+            .await;
         assert!(result.is_err());
     }
 
@@ -6329,7 +9973,7 @@ mod tests {
             .unwrap();
         // join_channel creates channels on-the-fly even for servers not
         // registered in the servers map, so this should succeed.
-        let result = engine.join_channel(sid, "no-such-server", "#general");
+        let result = engine.join_channel(sid, "no-such-server", "#general").await;
         assert!(result.is_ok());
     }
 
@@ -6343,6 +9987,7 @@ mod tests {
         assert!(engine.list_channels(DEFAULT_SERVER_ID).is_empty());
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#new-channel")
+            .await
             .unwrap();
         let channels = engine.list_channels(DEFAULT_SERVER_ID);
         assert_eq!(channels.len(), 1);
@@ -6357,9 +10002,12 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         // Joining again should be a no-op, not an error
-        let result = engine.join_channel(sid, DEFAULT_SERVER_ID, "#general");
+        let result = engine
+            .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await;
         assert!(result.is_ok());
     }
 
@@ -6375,6 +10023,7 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid2, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         // alice never joined, so parting should fail
         let result = engine.part_channel(sid, DEFAULT_SERVER_ID, "#general", None);
@@ -6390,6 +10039,7 @@ mod tests {
         // Joining with "General" should normalize to "#general"
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "General")
+            .await
             .unwrap();
         let channels = engine.list_channels(DEFAULT_SERVER_ID);
         assert_eq!(channels[0].name, "#general");
@@ -6403,6 +10053,7 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         // Drain join events
         while rx.try_recv().is_ok() {}
@@ -6417,6 +10068,7 @@ mod tests {
                 None,
                 None,
             )
+            .await
             .unwrap();
         // Message should NOT be echoed back to the sender (only a MessageAck)
         let event = rx.try_recv().unwrap();
@@ -6432,8 +10084,11 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
-        let result = engine.send_message(sid, DEFAULT_SERVER_ID, "#general", "", None, None, None);
+        let result = engine
+            .send_message(sid, DEFAULT_SERVER_ID, "#general", "", None, None, None)
+            .await;
         assert!(result.is_err());
     }
 
@@ -6445,9 +10100,11 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
-        let result =
-            engine.send_message(sid, DEFAULT_SERVER_ID, "#general", "   ", None, None, None);
+        let result = engine
+            .send_message(sid, DEFAULT_SERVER_ID, "#general", "   ", None, None, None)
+            .await;
         assert!(result.is_err());
     }
 
@@ -6459,17 +10116,20 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         let long_msg = "x".repeat(4001);
-        let result = engine.send_message(
-            sid,
-            DEFAULT_SERVER_ID,
-            "#general",
-            &long_msg,
-            None,
-            None,
-            None,
-        );
+        let result = engine
+            .send_message(
+                sid,
+                DEFAULT_SERVER_ID,
+                "#general",
+                &long_msg,
+                None,
+                None,
+                None,
+            )
+            .await;
         assert!(result.is_err());
     }
 
@@ -6481,17 +10141,20 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         let max_msg = "x".repeat(4000);
-        let result = engine.send_message(
-            sid,
-            DEFAULT_SERVER_ID,
-            "#general",
-            &max_msg,
-            None,
-            None,
-            None,
-        );
+        let result = engine
+            .send_message(
+                sid,
+                DEFAULT_SERVER_ID,
+                "#general",
+                &max_msg,
+                None,
+                None,
+                None,
+            )
+            .await;
         assert!(result.is_ok());
     }
 
@@ -6507,9 +10170,12 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         let long_topic = "t".repeat(501);
-        let result = engine.set_topic(sid, DEFAULT_SERVER_ID, "#general", long_topic);
+        let result = engine
+            .set_topic(sid, DEFAULT_SERVER_ID, "#general", long_topic)
+            .await;
         assert!(result.is_err());
     }
 
@@ -6521,18 +10187,21 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         while rx.try_recv().is_ok() {}
 
         // Set a topic first
         engine
             .set_topic(sid, DEFAULT_SERVER_ID, "#general", "Hello".into())
+            .await
             .unwrap();
         while rx.try_recv().is_ok() {}
 
         // Clear topic
         engine
             .set_topic(sid, DEFAULT_SERVER_ID, "#general", "".into())
+            .await
             .unwrap();
         let event = rx.try_recv().unwrap();
         match event {
@@ -6642,9 +10311,11 @@ mod tests {
         // Alice joins #general, Bob joins #rust
         engine
             .join_channel(sid_alice, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         engine
             .join_channel(sid_bob, DEFAULT_SERVER_ID, "#rust")
+            .await
             .unwrap();
         while rx_alice.try_recv().is_ok() {}
         while rx_bob.try_recv().is_ok() {}
@@ -6660,6 +10331,7 @@ mod tests {
                 None,
                 None,
             )
+            .await
             .unwrap();
         assert!(rx_bob.try_recv().is_err());
     }
@@ -6676,13 +10348,15 @@ mod tests {
 
         engine
             .join_channel(sid_alice, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         engine
             .join_channel(sid_bob, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         while rx_bob.try_recv().is_ok() {}
 
-        engine.disconnect(sid_alice);
+        engine.disconnect(sid_alice).await;
 
         let event = rx_bob.try_recv().unwrap();
         match event {
@@ -6699,9 +10373,10 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
 
-        engine.disconnect(sid);
+        engine.disconnect(sid).await;
 
         // Channel should have 0 members now
         let channels = engine.list_channels(DEFAULT_SERVER_ID);
@@ -6742,8 +10417,9 @@ mod tests {
         let (sid, _rx) = engine
             .connect(None, "alice".into(), Protocol::WebSocket, None)
             .unwrap();
-        let result =
-            engine.send_message(sid, DEFAULT_SERVER_ID, "nobody", "hello", None, None, None);
+        let result = engine
+            .send_message(sid, DEFAULT_SERVER_ID, "nobody", "hello", None, None, None)
+            .await;
         // DMs to non-existent users fail because there's no channel and no user session
         assert!(result.is_err());
     }
@@ -6760,33 +10436,38 @@ mod tests {
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
 
         // The default rate limiter allows burst of 10.
         // Send 10 messages — all should succeed.
         for i in 0..10 {
-            let result = engine.send_message(
+            let result = engine
+                .send_message(
+                    sid,
+                    DEFAULT_SERVER_ID,
+                    "#general",
+                    &format!("msg {i}"),
+                    None,
+                    None,
+                    None,
+                )
+                .await;
+            assert!(result.is_ok(), "Message {i} should succeed");
+        }
+
+        // 11th should be rate-limited
+        let result = engine
+            .send_message(
                 sid,
                 DEFAULT_SERVER_ID,
                 "#general",
-                &format!("msg {i}"),
+                "msg 10",
                 None,
                 None,
                 None,
-            );
-            assert!(result.is_ok(), "Message {i} should succeed");
-        }
-
-        // 11th should be rate-limited
-        let result = engine.send_message(
-            sid,
-            DEFAULT_SERVER_ID,
-            "#general",
-            "msg 10",
-            None,
-            None,
-            None,
-        );
+            )
+            .await;
         assert!(result.is_err());
     }
 
@@ -6809,7 +10490,15 @@ mod tests {
     async fn test_join_server_nonexistent() {
         let engine = setup_engine();
         let result = engine.join_server("user1", "nonexistent").await;
-        assert!(result.is_err());
+        assert!(matches!(result, Err(EngineError::NotFound(_))));
+        let result = engine
+            .update_server_settings("nonexistent", Some("Renamed"), None)
+            .await;
+        assert!(matches!(result, Err(EngineError::NotFound(_))));
+        let result = engine
+            .create_channel_in_server("nonexistent", "#new", None, false)
+            .await;
+        assert!(matches!(result, Err(EngineError::NotFound(_))));
     }
 
     #[tokio::test]
@@ -6853,9 +10542,11 @@ mod tests {
 
         engine
             .join_channel(sid_alice, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         engine
             .join_channel(sid_bob, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         while rx_alice.try_recv().is_ok() {}
 
@@ -6891,14 +10582,15 @@ mod tests {
         assert!(result.is_err());
     }
 
-    #[test]
-    fn test_resolve_channel_id_after_join() {
+    #[tokio::test]
+    async fn test_resolve_channel_id_after_join() {
         let engine = setup_engine();
         let (sid, _rx) = engine
             .connect(None, "alice".into(), Protocol::WebSocket, None)
             .unwrap();
         engine
             .join_channel(sid, DEFAULT_SERVER_ID, "#general")
+            .await
             .unwrap();
         let result = engine.resolve_channel_id(DEFAULT_SERVER_ID, "#general");
         assert!(result.is_ok());
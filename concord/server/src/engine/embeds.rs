@@ -1,6 +1,8 @@
 use std::net::IpAddr;
+use std::time::Instant;
 
 use super::events::EmbedInfo;
+use crate::metrics::METRICS;
 
 /// Check if an IP address is in a private/reserved range (SSRF protection).
 fn is_private_ip(ip: IpAddr) -> bool {
@@ -67,6 +69,16 @@ pub fn extract_urls(content: &str) -> Vec<String> {
 /// Fetch Open Graph metadata for a URL.
 /// Returns None if the fetch fails or no OG tags are found.
 pub async fn unfurl_url(client: &reqwest::Client, url: &str) -> Option<EmbedInfo> {
+    let started = Instant::now();
+    let embed = fetch_open_graph(client, url).await;
+    METRICS.embed_latency.observe(started.elapsed());
+    if embed.is_none() {
+        METRICS.embed_failures.inc();
+    }
+    embed
+}
+
+async fn fetch_open_graph(client: &reqwest::Client, url: &str) -> Option<EmbedInfo> {
     // SSRF protection: block requests to private/internal IP ranges
     if !is_safe_url(url).await {
         return None;
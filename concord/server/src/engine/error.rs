@@ -0,0 +1,202 @@
+use std::fmt;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use super::permissions::Permissions;
+
+/// Why a `ChatEngine` operation failed. Each protocol maps these onto its own
+/// error surface: an HTTP status and JSON code, a WS `Error.code`, or an IRC numeric.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineError {
+    /// The session is not signed in to an account.
+    AuthRequired,
+    /// The session ID doesn't belong to a connected client.
+    SessionNotFound,
+    /// A bearer token (e.g. a webhook token) didn't match.
+    InvalidToken(String),
+    /// No channel with this name in the server.
+    NoSuchChannel(String),
+    /// No connected user with this nickname.
+    NoSuchNick(String),
+    /// Anything else that doesn't exist, described by the message.
+    NotFound(String),
+    /// The session isn't in the channel it's acting on.
+    NotInChannel(String),
+    /// The user lacks a permission bit.
+    PermissionDenied { perm: Permissions },
+    /// The action is disallowed for a reason other than a missing permission.
+    Forbidden(String),
+    /// The channel refuses the message (read-only, archived, blocked by AutoMod).
+    CannotSend(String),
+    /// Too many requests; retry after the given delay if known.
+    RateLimited { retry_after: Option<Duration> },
+    /// The request itself is malformed or out of range.
+    Validation(String),
+    /// The request clashes with existing state.
+    Conflict(String),
+    /// The nickname is taken by another session or account.
+    NicknameInUse(String),
+    /// The user is banned from the server.
+    Banned,
+    /// The user is timed out in the server.
+    TimedOut { until: DateTime<Utc> },
+    /// The engine was started without a database.
+    NoDatabase,
+    /// A database or other internal failure.
+    Internal(String),
+}
+
+impl EngineError {
+    /// Stable machine-readable code, used for WS `Error.code` and REST error bodies.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::AuthRequired => "AUTH_REQUIRED",
+            Self::SessionNotFound => "SESSION_NOT_FOUND",
+            Self::InvalidToken(_) => "UNAUTHORIZED",
+            Self::NoSuchChannel(_) => "NO_SUCH_CHANNEL",
+            Self::NoSuchNick(_) | Self::NotFound(_) => "NOT_FOUND",
+            Self::NotInChannel(_) => "NOT_IN_CHANNEL",
+            Self::PermissionDenied { .. } | Self::Forbidden(_) => "FORBIDDEN",
+            Self::CannotSend(_) => "CANNOT_SEND",
+            Self::RateLimited { .. } => "RATE_LIMITED",
+            Self::Validation(_) => "VALIDATION",
+            Self::Conflict(_) => "CONFLICT",
+            Self::NicknameInUse(_) => "NICKNAME_IN_USE",
+            Self::Banned => "BANNED",
+            Self::TimedOut { .. } => "TIMED_OUT",
+            Self::NoDatabase => "UNAVAILABLE",
+            Self::Internal(_) => "INTERNAL",
+        }
+    }
+
+    /// HTTP status code for REST responses.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            Self::AuthRequired | Self::SessionNotFound | Self::InvalidToken(_) => 401,
+            Self::NoSuchChannel(_) | Self::NoSuchNick(_) | Self::NotFound(_) => 404,
+            Self::NotInChannel(_)
+            | Self::PermissionDenied { .. }
+            | Self::Forbidden(_)
+            | Self::CannotSend(_)
+            | Self::Banned
+            | Self::TimedOut { .. } => 403,
+            Self::RateLimited { .. } => 429,
+            Self::Validation(_) => 400,
+            Self::Conflict(_) | Self::NicknameInUse(_) => 409,
+            Self::NoDatabase => 503,
+            Self::Internal(_) => 500,
+        }
+    }
+
+    /// Whole seconds until a rate-limited request may be retried, rounded up
+    /// so clients never retry early. None if the delay isn't known.
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            Self::RateLimited {
+                retry_after: Some(delay),
+            } => Some((delay.as_secs_f64().ceil() as u64).max(1)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AuthRequired => write!(f, "Authentication required"),
+            Self::SessionNotFound => write!(f, "Session not found"),
+            Self::NoSuchChannel(name) => write!(f, "No such channel: {name}"),
+            Self::NoSuchNick(name) => write!(f, "No such nick: {name}"),
+            Self::NotInChannel(name) => write!(f, "You are not in channel {name}"),
+            Self::PermissionDenied { perm } => {
+                let names: Vec<_> = perm.iter_names().map(|(name, _)| name).collect();
+                write!(f, "Missing permission: {}", names.join(", "))
+            }
+            Self::RateLimited {
+                retry_after: Some(_),
+            } => write!(
+                f,
+                "Rate limit exceeded. Try again in {} seconds.",
+                self.retry_after_secs().unwrap_or(1)
+            ),
+            Self::RateLimited { retry_after: None } => {
+                write!(f, "Rate limit exceeded. Please slow down.")
+            }
+            Self::Banned => write!(f, "You are banned from this server"),
+            Self::TimedOut { until } => write!(
+                f,
+                "You are timed out until {}",
+                until.format("%Y-%m-%d %H:%M:%S UTC")
+            ),
+            Self::NoDatabase => write!(f, "No database configured"),
+            Self::NotFound(message)
+            | Self::InvalidToken(message)
+            | Self::Forbidden(message)
+            | Self::CannotSend(message)
+            | Self::Validation(message)
+            | Self::Conflict(message)
+            | Self::Internal(message) => write!(f, "{message}"),
+            Self::NicknameInUse(nick) => write!(f, "Nickname {nick} is already in use"),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+/// Lets code that still reports failures as plain strings (bridges, IRC services)
+/// propagate engine errors with `?`.
+impl From<EngineError> for String {
+    fn from(e: EngineError) -> Self {
+        e.to_string()
+    }
+}
+
+impl From<sqlx::Error> for EngineError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Internal(format!("DB error: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_denied_names_the_permission() {
+        let err = EngineError::PermissionDenied {
+            perm: Permissions::SEND_MESSAGES,
+        };
+        assert_eq!(err.to_string(), "Missing permission: SEND_MESSAGES");
+        assert_eq!(err.code(), "FORBIDDEN");
+        assert_eq!(err.http_status(), 403);
+    }
+
+    #[test]
+    fn test_rate_limited_reports_retry_after() {
+        let err = EngineError::RateLimited {
+            retry_after: Some(Duration::from_millis(2500)),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Rate limit exceeded. Try again in 3 seconds."
+        );
+        assert_eq!(err.retry_after_secs(), Some(3));
+        assert_eq!(err.http_status(), 429);
+    }
+
+    #[test]
+    fn test_invalid_token_is_unauthorized() {
+        let err = EngineError::InvalidToken("Invalid webhook token".into());
+        assert_eq!(err.code(), "UNAUTHORIZED");
+        assert_eq!(err.http_status(), 401);
+        assert_eq!(EngineError::Forbidden("nope".into()).http_status(), 403);
+    }
+
+    #[test]
+    fn test_sqlx_errors_are_internal() {
+        let err = EngineError::from(sqlx::Error::RowNotFound);
+        assert_eq!(err.code(), "INTERNAL");
+        assert_eq!(err.http_status(), 500);
+    }
+}
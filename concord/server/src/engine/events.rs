@@ -214,6 +214,9 @@ pub enum ChatEvent {
         presence: PresenceInfo,
     },
 
+    /// A nickname on this session's monitor list came online or went offline.
+    MonitorStatus { nickname: String, online: bool },
+
     /// Bulk presence list for a server (sent on connect/join).
     PresenceList {
         server_id: String,
@@ -330,6 +333,9 @@ pub enum ChatEvent {
         user_id: String,
         banned_by: String,
         reason: Option<String>,
+        /// When a temporary ban lifts.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires_at: Option<String>,
     },
 
     /// A ban was removed from the server.
@@ -356,6 +362,30 @@ pub enum ChatEvent {
         is_nsfw: bool,
     },
 
+    /// Channel private (members-only) flag was updated.
+    ChannelPrivateUpdate {
+        server_id: String,
+        channel: String,
+        is_private: bool,
+        set_by: String,
+    },
+
+    /// Channel read-only flag was updated.
+    ReadOnlyUpdate {
+        server_id: String,
+        channel: String,
+        is_read_only: bool,
+        set_by: String,
+    },
+
+    /// Channel topic lock was updated.
+    TopicLockUpdate {
+        server_id: String,
+        channel: String,
+        topic_locked: bool,
+        set_by: String,
+    },
+
     /// Bulk messages were deleted.
     BulkMessageDelete {
         server_id: String,
@@ -375,6 +405,13 @@ pub enum ChatEvent {
         bans: Vec<BanInfo>,
     },
 
+    /// Outcome of a bulk ban, sent to the moderator who ran it.
+    BulkBanResult {
+        server_id: String,
+        banned: Vec<String>,
+        failed: Vec<BulkBanFailure>,
+    },
+
     /// AutoMod rules list response.
     AutomodRuleList {
         server_id: String,
@@ -390,6 +427,69 @@ pub enum ChatEvent {
     /// AutoMod rule deleted.
     AutomodRuleDelete { server_id: String, rule_id: String },
 
+    /// AutoMod review queue response.
+    AutomodQueue {
+        server_id: String,
+        entries: Vec<AutomodQueueEntryInfo>,
+    },
+
+    /// AutoMod review queue entry approved or rejected.
+    AutomodQueueUpdate {
+        server_id: String,
+        entry: AutomodQueueEntryInfo,
+    },
+
+    /// Server lockdown started or lifted. While `until` is set, invites are
+    /// paused and members who joined within `new_member_seconds` can't send.
+    ServerLockdown {
+        server_id: String,
+        until: Option<DateTime<Utc>>,
+        new_member_seconds: u64,
+    },
+
+    /// A system admin quarantined the server or lifted its quarantine. While
+    /// quarantined the server is hidden from discovery and read-only.
+    ServerQuarantine {
+        server_id: String,
+        quarantined: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<String>,
+    },
+
+    /// Open reports in a server, for moderators.
+    ReportList {
+        server_id: String,
+        reports: Vec<ReportInfo>,
+    },
+
+    /// A report was filed or resolved. Sent to the reporter and to the
+    /// moderator who resolved it.
+    ReportUpdate {
+        server_id: String,
+        report: ReportInfo,
+    },
+
+    /// A member was warned. Sent only to the warned member.
+    MemberWarn {
+        server_id: String,
+        user_id: String,
+        warned_by: String,
+        reason: String,
+    },
+
+    /// A member's infractions ledger, for moderators.
+    InfractionList {
+        server_id: String,
+        user_id: String,
+        infractions: Vec<InfractionInfo>,
+    },
+
+    /// A server's escalation policies.
+    EscalationPolicyList {
+        server_id: String,
+        policies: Vec<EscalationPolicyInfo>,
+    },
+
     // ── Phase 7: Community & Discovery ──
     /// Invite list response.
     InviteList {
@@ -595,6 +695,9 @@ pub struct ServerInfo {
     /// Effective permission bitfield for the requesting user in this server.
     #[serde(default)]
     pub my_permissions: i64,
+    /// Set while a system admin has quarantined the server.
+    #[serde(default)]
+    pub quarantined: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -612,6 +715,8 @@ pub struct ChannelInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thread_parent_message_id: Option<String>,
     pub archived: bool,
+    pub is_read_only: bool,
+    pub topic_locked: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -848,9 +953,19 @@ pub struct BanInfo {
     pub banned_by: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reason: Option<String>,
+    /// When a temporary ban lifts; permanent when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
     pub created_at: String,
 }
 
+/// A user a bulk ban couldn't ban, and why.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkBanFailure {
+    pub user_id: String,
+    pub error: String,
+}
+
 /// AutoMod rule info sent to clients.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutomodRuleInfo {
@@ -864,6 +979,113 @@ pub struct AutomodRuleInfo {
     pub timeout_duration_seconds: Option<i32>,
 }
 
+/// A member's report of a message or user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportInfo {
+    pub id: String,
+    pub reporter_id: String,
+    pub target_user_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<String>,
+    /// The reported message as it was when reported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_content: Option<String>,
+    pub reason: String,
+    /// `open` or `resolved`.
+    pub status: String,
+    /// `dismiss`, `delete`, `timeout`, `kick` or `ban`, once resolved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution_note: Option<String>,
+    /// The resolving moderator; not shown to the reporter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<String>,
+    pub created_at: String,
+    /// Messages around the reported one, in the moderator queue.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<HistoryMessage>,
+}
+
+/// An open report in the instance-wide admin view.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceReportInfo {
+    pub server_id: String,
+    pub server_name: String,
+    #[serde(flatten)]
+    pub report: ReportInfo,
+}
+
+/// An identity banned from the whole instance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceBanInfo {
+    pub user_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub banned_by: String,
+    pub created_at: String,
+}
+
+/// An entry in a member's infractions ledger.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InfractionInfo {
+    pub id: String,
+    pub user_id: String,
+    /// The moderator; absent for AutoMod and escalation entries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor_id: Option<String>,
+    /// `warn`, `timeout`, `kick`, `ban` or `automod`.
+    pub action: String,
+    /// `manual`, `automod` or `escalation`.
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pardoned_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pardoned_at: Option<String>,
+    /// Whether the entry still counts as a strike.
+    pub active: bool,
+    pub created_at: String,
+}
+
+/// "`threshold` strikes within `window_seconds` -> `action`".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscalationPolicyInfo {
+    pub threshold: u32,
+    pub window_seconds: u64,
+    /// `timeout`, `kick` or `ban`.
+    pub action: String,
+    /// Timeout length; required for `timeout`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<u64>,
+}
+
+/// A message flagged or held by AutoMod, sent to moderators.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomodQueueEntryInfo {
+    pub id: String,
+    pub channel_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<String>,
+    pub rule_name: String,
+    pub user_id: String,
+    pub user_nick: String,
+    pub content: String,
+    /// The delivered message, for flagged entries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    /// `flagged`, `pending`, `approved` or `rejected`.
+    pub status: String,
+    pub created_at: String,
+}
+
 /// Server invite info sent to clients.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InviteInfo {
@@ -1463,14 +1685,19 @@ mod tests {
             user_id: "user1".into(),
             banned_by: "admin1".into(),
             reason: None,
+            expires_at: Some("2026-03-01 00:00:00".into()),
         };
         let restored = roundtrip(&event);
         match restored {
             ChatEvent::MemberBan {
-                user_id, reason, ..
+                user_id,
+                reason,
+                expires_at,
+                ..
             } => {
                 assert_eq!(user_id, "user1");
                 assert!(reason.is_none());
+                assert_eq!(expires_at.as_deref(), Some("2026-03-01 00:00:00"));
             }
             _ => panic!("Wrong variant"),
         }
@@ -1497,6 +1724,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_escalation_policy_list_roundtrip() {
+        let event = ChatEvent::EscalationPolicyList {
+            server_id: "srv1".into(),
+            policies: vec![EscalationPolicyInfo {
+                threshold: 3,
+                window_seconds: 30 * 86400,
+                action: "timeout".into(),
+                duration_seconds: Some(3600),
+            }],
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "escalation_policy_list");
+        assert_eq!(json["policies"][0]["duration_seconds"], 3600);
+        match roundtrip(&event) {
+            ChatEvent::EscalationPolicyList { policies, .. } => {
+                assert_eq!(policies[0].threshold, 3);
+                assert_eq!(policies[0].action, "timeout");
+            }
+            _ => panic!("Wrong variant"),
+        }
+    }
+
     #[test]
     fn test_slow_mode_update_event_roundtrip() {
         let event = ChatEvent::SlowModeUpdate {
@@ -1928,6 +2178,7 @@ mod tests {
                 member_count: 0,
                 role: None,
                 my_permissions: 0,
+                quarantined: false,
             }
         );
         let _ = format!(
@@ -1944,6 +2195,8 @@ mod tests {
                 channel_type: "text".into(),
                 thread_parent_message_id: None,
                 archived: false,
+                is_read_only: false,
+                topic_locked: false,
             }
         );
         let _ = format!(
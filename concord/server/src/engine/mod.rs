@@ -1,6 +1,8 @@
+pub mod automod;
 pub mod channel;
 pub mod chat_engine;
 pub mod embeds;
+pub mod error;
 pub mod events;
 pub mod permissions;
 pub mod rate_limiter;
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
@@ -57,6 +57,44 @@ impl RateLimiter {
     }
 }
 
+/// Counts events per key over a sliding time window (messages sent, server
+/// joins). Unlike `RateLimiter` it only counts; callers compare the count with
+/// their own threshold, so one counter can serve rules with different limits.
+#[derive(Default)]
+pub struct SlidingWindowCounter {
+    events: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl SlidingWindowCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an event for `key` and return how many events it has had within
+    /// the last `window`, including this one.
+    pub fn record(&self, key: &str, window: Duration) -> usize {
+        let mut events = self.events.lock().unwrap();
+        let now = Instant::now();
+        let times = events.entry(key.to_string()).or_default();
+        while times
+            .front()
+            .is_some_and(|&t| now.duration_since(t) >= window)
+        {
+            times.pop_front();
+        }
+        times.push_back(now);
+        times.len()
+    }
+
+    /// Forget keys whose latest event is older than the given duration. Pass
+    /// at least the longest window in use, or counts will restart early.
+    pub fn cleanup(&self, older_than: Duration) {
+        let mut events = self.events.lock().unwrap();
+        let cutoff = Instant::now() - older_than;
+        events.retain(|_, times| times.back().is_some_and(|&t| t > cutoff));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -219,4 +257,32 @@ mod tests {
         assert!(!limiter.check("user"));
         assert!(!limiter.check("user"));
     }
+
+    #[test]
+    fn test_sliding_window_counts_recent_events() {
+        let counter = SlidingWindowCounter::new();
+        let window = Duration::from_secs(10);
+        assert_eq!(counter.record("a", window), 1);
+        assert_eq!(counter.record("a", window), 2);
+        assert_eq!(counter.record("b", window), 1);
+
+        // Age the existing events past the window
+        {
+            let mut events = counter.events.lock().unwrap();
+            for t in events.get_mut("a").unwrap() {
+                *t = Instant::now() - Duration::from_secs(11);
+            }
+        }
+        assert_eq!(counter.record("a", window), 1);
+    }
+
+    #[test]
+    fn test_sliding_window_cleanup() {
+        let counter = SlidingWindowCounter::new();
+        counter.record("old", Duration::from_secs(10));
+        counter.cleanup(Duration::from_secs(60));
+        assert!(counter.events.lock().unwrap().contains_key("old"));
+        counter.cleanup(Duration::from_secs(0));
+        assert!(counter.events.lock().unwrap().is_empty());
+    }
 }
@@ -1,4 +1,10 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+
+use super::automod::AutomodRules;
+use super::permissions::{ChannelOverride, Permissions};
 
 /// In-memory state for a server (guild).
 #[derive(Debug)]
@@ -11,6 +17,27 @@ pub struct ServerState {
     pub channel_ids: HashSet<String>,
     /// User IDs who are members of this server (persistent membership).
     pub member_user_ids: HashSet<String>,
+    /// Roles, role assignments and channel overrides used to resolve permissions.
+    pub permission_cache: PermissionCache,
+    /// Compiled AutoMod rules, loaded on first use and rebuilt when a rule changes.
+    pub automod_rules: Option<Arc<AutomodRules>>,
+    /// Bumped when `automod_rules` is dropped, so a load that raced with a rule
+    /// change is not stored.
+    pub automod_generation: u64,
+    /// Set while the server is locked down after a raid, or by a moderator.
+    /// Kept in memory only, so a restart lifts it.
+    pub lockdown: Option<Lockdown>,
+    /// Set while a system admin has quarantined the server: it is hidden from
+    /// discovery and read-only.
+    pub quarantined: bool,
+}
+
+/// A server lockdown: invites are paused and recent joiners can't send.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lockdown {
+    pub until: DateTime<Utc>,
+    /// Members who joined less than this many seconds ago can't send messages.
+    pub new_member_seconds: u64,
 }
 
 impl ServerState {
@@ -22,6 +49,152 @@ impl ServerState {
             owner_id,
             channel_ids: HashSet::new(),
             member_user_ids: HashSet::new(),
+            permission_cache: PermissionCache::default(),
+            automod_rules: None,
+            automod_generation: 0,
+            lockdown: None,
+            quarantined: false,
+        }
+    }
+
+    /// The lockdown in force, if one is set and hasn't expired.
+    pub fn active_lockdown(&self) -> Option<Lockdown> {
+        self.lockdown.filter(|l| l.until > Utc::now())
+    }
+}
+
+/// The server's roles as needed for permission resolution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedRoles {
+    /// ID of the default (@everyone) role, empty if the server has none.
+    pub everyone_role_id: String,
+    /// Base permissions granted by @everyone.
+    pub everyone: Permissions,
+    /// Role ID -> permissions, for every role in the server.
+    pub permissions: HashMap<String, Permissions>,
+}
+
+/// Lazily filled cache of permission inputs. Each part is loaded from the
+/// database on first use and dropped by the engine when the rows behind it
+/// change. Loads started before an invalidation are discarded on store, so a
+/// slow query can never overwrite newer data.
+#[derive(Debug, Default)]
+pub struct PermissionCache {
+    generation: u64,
+    roles: Option<Arc<CachedRoles>>,
+    /// User ID -> IDs of the roles assigned to them.
+    member_roles: HashMap<String, Arc<Vec<String>>>,
+    /// Channel ID -> that channel's permission overrides.
+    channel_overrides: HashMap<String, Arc<Vec<ChannelOverride>>>,
+}
+
+impl PermissionCache {
+    /// Counter bumped by every invalidation. Read it before loading from the
+    /// database and pass it back when storing the result.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn roles(&self) -> Option<Arc<CachedRoles>> {
+        self.roles.clone()
+    }
+
+    pub fn member_roles(&self, user_id: &str) -> Option<Arc<Vec<String>>> {
+        self.member_roles.get(user_id).cloned()
+    }
+
+    pub fn channel_overrides(&self, channel_id: &str) -> Option<Arc<Vec<ChannelOverride>>> {
+        self.channel_overrides.get(channel_id).cloned()
+    }
+
+    pub fn store_roles(&mut self, generation: u64, roles: Arc<CachedRoles>) {
+        if generation == self.generation {
+            self.roles = Some(roles);
         }
     }
+
+    pub fn store_member_roles(&mut self, generation: u64, user_id: &str, roles: Arc<Vec<String>>) {
+        if generation == self.generation {
+            self.member_roles.insert(user_id.to_string(), roles);
+        }
+    }
+
+    pub fn store_channel_overrides(
+        &mut self,
+        generation: u64,
+        channel_id: &str,
+        overrides: Arc<Vec<ChannelOverride>>,
+    ) {
+        if generation == self.generation {
+            self.channel_overrides
+                .insert(channel_id.to_string(), overrides);
+        }
+    }
+
+    /// A role was created or its permissions changed.
+    pub fn invalidate_roles(&mut self) {
+        self.generation += 1;
+        self.roles = None;
+    }
+
+    /// A role was deleted, which also removes it from every member.
+    pub fn invalidate_roles_and_members(&mut self) {
+        self.invalidate_roles();
+        self.member_roles.clear();
+    }
+
+    /// A member's role assignments changed.
+    pub fn invalidate_member(&mut self, user_id: &str) {
+        self.generation += 1;
+        self.member_roles.remove(user_id);
+    }
+
+    /// A channel's overrides changed or the channel was deleted.
+    pub fn invalidate_channel(&mut self, channel_id: &str) {
+        self.generation += 1;
+        self.channel_overrides.remove(channel_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roles() -> Arc<CachedRoles> {
+        Arc::new(CachedRoles {
+            everyone_role_id: "everyone".into(),
+            everyone: Permissions::SEND_MESSAGES,
+            permissions: HashMap::new(),
+        })
+    }
+
+    #[test]
+    fn test_store_after_invalidation_is_discarded() {
+        let mut cache = PermissionCache::default();
+        let generation = cache.generation();
+        cache.invalidate_roles();
+        cache.store_roles(generation, roles());
+        assert!(cache.roles().is_none());
+
+        cache.store_roles(cache.generation(), roles());
+        assert_eq!(cache.roles(), Some(roles()));
+    }
+
+    #[test]
+    fn test_invalidation_is_scoped() {
+        let mut cache = PermissionCache::default();
+        let generation = cache.generation();
+        cache.store_member_roles(generation, "alice", Arc::new(vec!["r1".into()]));
+        cache.store_member_roles(generation, "bob", Arc::new(vec![]));
+        cache.store_channel_overrides(generation, "c1", Arc::new(vec![]));
+
+        cache.invalidate_member("alice");
+        assert!(cache.member_roles("alice").is_none());
+        assert!(cache.member_roles("bob").is_some());
+        assert!(cache.channel_overrides("c1").is_some());
+
+        cache.invalidate_roles_and_members();
+        assert!(cache.member_roles("bob").is_none());
+        assert!(cache.channel_overrides("c1").is_some());
+    }
 }
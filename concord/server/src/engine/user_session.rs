@@ -1,7 +1,10 @@
+use std::net::IpAddr;
+
 use chrono::{DateTime, Utc};
 use tokio::sync::mpsc;
 
 use super::events::{ChatEvent, SessionId};
+use crate::metrics::METRICS;
 
 /// Maximum queued outbound events per session (prevents memory exhaustion from slow clients).
 pub const MAX_OUTBOUND_QUEUE: usize = 1024;
@@ -11,11 +14,13 @@ pub const MAX_OUTBOUND_QUEUE: usize = 1024;
 pub enum Protocol {
     Irc,
     WebSocket,
+    /// A user on an external network, mirrored in by a bridge.
+    Bridge,
 }
 
 /// A connected user session. Protocol-agnostic — the engine doesn't care
 /// whether this is an IRC client or a web browser.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UserSession {
     pub id: SessionId,
     /// Database user ID (None for unauthenticated/guest sessions).
@@ -27,6 +32,8 @@ pub struct UserSession {
     pub connected_at: DateTime<Utc>,
     /// Avatar URL (from Bluesky profile or other source).
     pub avatar_url: Option<String>,
+    /// Hostname and address the client connected from, for IRC WHOIS.
+    pub client_host: Option<(String, IpAddr)>,
 }
 
 impl UserSession {
@@ -46,12 +53,17 @@ impl UserSession {
             outbound,
             connected_at: Utc::now(),
             avatar_url,
+            client_host: None,
         }
     }
 
     /// Send an event to this session. Returns false if the channel is closed
     /// or the outbound queue is full (slow client protection — drops event rather than blocking).
     pub fn send(&self, event: ChatEvent) -> bool {
-        self.outbound.try_send(event).is_ok()
+        let sent = self.outbound.try_send(event).is_ok();
+        if !sent {
+            METRICS.outbound_dropped.inc();
+        }
+        sent
     }
 }
@@ -1,3 +1,5 @@
+use super::error::EngineError;
+
 /// Maximum message content length (bytes).
 pub const MAX_MESSAGE_LENGTH: usize = 2000;
 
@@ -14,56 +16,60 @@ pub const MAX_SERVER_NAME_LENGTH: usize = 100;
 pub const MAX_NICKNAME_LENGTH: usize = 32;
 
 /// Validate a server name. Must be 1-100 chars, non-empty after trimming.
-pub fn validate_server_name(name: &str) -> Result<(), String> {
+pub fn validate_server_name(name: &str) -> Result<(), EngineError> {
     let trimmed = name.trim();
     if trimmed.is_empty() {
-        return Err("Server name cannot be empty".into());
+        return Err(EngineError::Validation(
+            "Server name cannot be empty".into(),
+        ));
     }
     if trimmed.len() > MAX_SERVER_NAME_LENGTH {
-        return Err(format!(
+        return Err(EngineError::Validation(format!(
             "Server name too long (max {} characters)",
             MAX_SERVER_NAME_LENGTH
-        ));
+        )));
     }
     Ok(())
 }
 
 /// Validate a nickname. Must be 1-32 chars, alphanumeric + underscore/hyphen/dot.
 /// Dots are allowed to support Bluesky handles (e.g., `dollspace.gay`).
-pub fn validate_nickname(nick: &str) -> Result<(), String> {
+pub fn validate_nickname(nick: &str) -> Result<(), EngineError> {
     if nick.is_empty() {
-        return Err("Nickname cannot be empty".into());
+        return Err(EngineError::Validation("Nickname cannot be empty".into()));
     }
     if nick.len() > MAX_NICKNAME_LENGTH {
-        return Err(format!(
+        return Err(EngineError::Validation(format!(
             "Nickname too long (max {} characters)",
             MAX_NICKNAME_LENGTH
-        ));
+        )));
     }
     if !nick
         .chars()
         .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
     {
-        return Err(
+        return Err(EngineError::Validation(
             "Nickname can only contain letters, numbers, underscores, hyphens, and dots".into(),
-        );
+        ));
     }
     Ok(())
 }
 
 /// Validate a channel name. Must start with #, 2-50 chars, no spaces.
-pub fn validate_channel_name(name: &str) -> Result<(), String> {
+pub fn validate_channel_name(name: &str) -> Result<(), EngineError> {
     if name.len() < 2 {
-        return Err("Channel name too short".into());
+        return Err(EngineError::Validation("Channel name too short".into()));
     }
     if name.len() > MAX_CHANNEL_NAME_LENGTH {
-        return Err(format!(
+        return Err(EngineError::Validation(format!(
             "Channel name too long (max {} characters)",
             MAX_CHANNEL_NAME_LENGTH
-        ));
+        )));
     }
     if name.contains(' ') {
-        return Err("Channel name cannot contain spaces".into());
+        return Err(EngineError::Validation(
+            "Channel name cannot contain spaces".into(),
+        ));
     }
     Ok(())
 }
@@ -78,49 +84,60 @@ pub fn sanitize_html(input: &str) -> String {
 }
 
 /// Validate message content. Must be non-empty and under the length limit.
-pub fn validate_message(content: &str) -> Result<(), String> {
+pub fn validate_message(content: &str) -> Result<(), EngineError> {
     validate_message_with_limit(content, MAX_MESSAGE_LENGTH)
 }
 
 /// Validate message content with a configurable length limit.
-pub fn validate_message_with_limit(content: &str, max_length: usize) -> Result<(), String> {
+pub fn validate_message_with_limit(content: &str, max_length: usize) -> Result<(), EngineError> {
     if content.trim().is_empty() {
-        return Err("Message cannot be empty".into());
+        return Err(EngineError::Validation("Message cannot be empty".into()));
     }
     if content.len() > max_length {
-        return Err(format!("Message too long (max {} characters)", max_length));
+        return Err(EngineError::Validation(format!(
+            "Message too long (max {} characters)",
+            max_length
+        )));
     }
     Ok(())
 }
 
 /// Validate a vanity invite code. Must be 2-32 lowercase alphanumeric + hyphens,
 /// no leading/trailing hyphens.
-pub fn validate_vanity_code(code: &str) -> Result<(), String> {
+pub fn validate_vanity_code(code: &str) -> Result<(), EngineError> {
     if code.len() < 2 {
-        return Err("Vanity code too short (min 2 characters)".into());
+        return Err(EngineError::Validation(
+            "Vanity code too short (min 2 characters)".into(),
+        ));
     }
     if code.len() > 32 {
-        return Err("Vanity code too long (max 32 characters)".into());
+        return Err(EngineError::Validation(
+            "Vanity code too long (max 32 characters)".into(),
+        ));
     }
     if code.starts_with('-') || code.ends_with('-') {
-        return Err("Vanity code cannot start or end with a hyphen".into());
+        return Err(EngineError::Validation(
+            "Vanity code cannot start or end with a hyphen".into(),
+        ));
     }
     if !code
         .chars()
         .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
     {
-        return Err("Vanity code can only contain lowercase letters, digits, and hyphens".into());
+        return Err(EngineError::Validation(
+            "Vanity code can only contain lowercase letters, digits, and hyphens".into(),
+        ));
     }
     Ok(())
 }
 
 /// Validate a topic string. Can be empty (to clear topic) but has a length limit.
-pub fn validate_topic(topic: &str) -> Result<(), String> {
+pub fn validate_topic(topic: &str) -> Result<(), EngineError> {
     if topic.len() > MAX_TOPIC_LENGTH {
-        return Err(format!(
+        return Err(EngineError::Validation(format!(
             "Topic too long (max {} characters)",
             MAX_TOPIC_LENGTH
-        ));
+        )));
     }
     Ok(())
 }
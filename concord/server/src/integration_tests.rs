@@ -9,11 +9,13 @@ mod tests {
     use uuid::Uuid;
 
     use crate::db::models::{
-        CreateAuditLogParams, CreateAutomodRuleParams, CreateServerEventParams, CreateWebhookParams,
+        CreateAuditLogParams, CreateAutomodRuleParams, CreateServerEventParams,
+        CreateWebhookParams, UpdateAutomodRuleParams,
     };
     use crate::db::pool::{create_pool, run_migrations};
     use crate::db::queries;
-    use crate::engine::chat_engine::ChatEngine;
+    use crate::engine::chat_engine::{ChatEngine, DEFAULT_SERVER_ID, SYSTEM_USER_ID};
+    use crate::engine::error::EngineError;
     use crate::engine::events::ChatEvent;
     use crate::engine::permissions::{
         ChannelOverride, DEFAULT_EVERYONE, DEFAULT_MODERATOR, OverrideTargetType, Permissions,
@@ -94,7 +96,7 @@ mod tests {
                 .fetch_one(&pool)
                 .await
                 .unwrap();
-        assert_eq!(max_version, 16, "All 16 migrations should be recorded");
+        assert_eq!(max_version, 28, "All 28 migrations should be recorded");
     }
 
     #[tokio::test]
@@ -109,7 +111,7 @@ mod tests {
             .fetch_one(&pool)
             .await
             .unwrap();
-        assert_eq!(count, 16, "No duplicate migration entries after re-run");
+        assert_eq!(count, 28, "No duplicate migration entries after re-run");
     }
 
     #[tokio::test]
@@ -259,7 +261,8 @@ mod tests {
         assert!(!perms.contains(Permissions::MANAGE_CHANNELS));
     }
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    // Runs on a current-thread runtime: the send pipeline must never block.
+    #[tokio::test]
     async fn test_message_send_edit_delete_lifecycle() {
         let (engine, pool) = setup_engine().await;
 
@@ -271,7 +274,10 @@ mod tests {
 
         // Connect user and join #general
         let (sid, mut rx) = connect_user(&engine, Some(&user_id), "alice");
-        engine.join_channel(sid, &server_id, "#general").unwrap();
+        engine
+            .join_channel(sid, &server_id, "#general")
+            .await
+            .unwrap();
         drain_events(&mut rx);
 
         // Send a message
@@ -285,12 +291,11 @@ mod tests {
                 None,
                 None,
             )
+            .await
             .unwrap();
 
         // The sender should NOT receive their own message via the channel broadcast
-        // (protocol convention), but we should be able to find the message in the DB.
-        // The DB insert happens in a tokio::spawn, so we need a small yield/delay.
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        // (protocol convention), but the message is persisted before send returns.
 
         // First, get the channel ID for lookup.
         let ch = queries::channels::get_channel_by_name(&pool, &server_id, "#general")
@@ -610,6 +615,61 @@ mod tests {
         );
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_private_join_and_disconnect_on_current_thread_runtime() {
+        let (engine, pool) = setup_engine().await;
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        let server_id = engine
+            .create_server("Private Test".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        engine.join_server(&bob_id, &server_id).await.unwrap();
+        engine
+            .create_channel_in_server(&server_id, "#secret", None, true)
+            .await
+            .unwrap();
+        engine
+            .set_channel_override(
+                &server_id,
+                "#secret",
+                &ChannelOverride {
+                    target_type: OverrideTargetType::User,
+                    target_id: bob_id.clone(),
+                    allow: Permissions::empty(),
+                    deny: Permissions::VIEW_CHANNELS,
+                },
+            )
+            .await
+            .unwrap();
+
+        let (alice, _rx_a) = connect_user(&engine, Some(&alice_id), "alice");
+        let (bob, _rx_b) = connect_user(&engine, Some(&bob_id), "bob");
+        engine
+            .join_channel(alice, &server_id, "#secret")
+            .await
+            .unwrap();
+        assert!(matches!(
+            engine.join_channel(bob, &server_id, "#secret").await,
+            Err(EngineError::PermissionDenied { .. })
+        ));
+
+        // Let the spawned "online" update land before going offline
+        while queries::presence::get_presence(&pool, &alice_id)
+            .await
+            .unwrap()
+            .is_none_or(|p| p.status != "online")
+        {
+            tokio::task::yield_now().await;
+        }
+        engine.disconnect(alice).await;
+        let presence = queries::presence::get_presence(&pool, &alice_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(presence.status, "offline");
+    }
+
     // ═══════════════════════════════════════════════════════════════
     //  4. Webhook End-to-End Tests
     // ═══════════════════════════════════════════════════════════════
@@ -1151,12 +1211,15 @@ mod tests {
         let ban_id = Uuid::new_v4().to_string();
         queries::bans::create_ban(
             &pool,
-            &ban_id,
-            server_id,
-            &user_id,
-            &owner_id,
-            Some("Spamming"),
-            0,
+            &crate::db::models::CreateBanParams {
+                id: &ban_id,
+                server_id,
+                user_id: &user_id,
+                banned_by: &owner_id,
+                reason: Some("Spamming"),
+                delete_message_seconds: 0,
+                expires_at: None,
+            },
         )
         .await
         .unwrap();
@@ -1597,7 +1660,7 @@ mod tests {
         .unwrap();
 
         // Tag the thread
-        queries::forum_tags::set_thread_tags(&pool, &thread_id, &[tag1_id.clone()])
+        queries::forum_tags::set_thread_tags(&pool, &thread_id, std::slice::from_ref(&tag1_id))
             .await
             .unwrap();
 
@@ -1682,7 +1745,7 @@ mod tests {
     //  9. Cross-Protocol Event Consistency Tests
     // ═══════════════════════════════════════════════════════════════
 
-    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    #[tokio::test]
     async fn test_message_event_contains_all_fields() {
         let (engine, pool) = setup_engine().await;
 
@@ -1693,14 +1756,20 @@ mod tests {
             .unwrap();
 
         let (sid1, _rx1) = connect_user(&engine, Some(&user_id), "alice");
-        engine.join_channel(sid1, &server_id, "#general").unwrap();
+        engine
+            .join_channel(sid1, &server_id, "#general")
+            .await
+            .unwrap();
 
         // Create a second user to receive the event
         let user2_id = create_test_user(&pool, "bob").await;
         engine.join_server(&user2_id, &server_id).await.unwrap();
 
         let (sid2, mut rx2) = connect_user(&engine, Some(&user2_id), "bob");
-        engine.join_channel(sid2, &server_id, "#general").unwrap();
+        engine
+            .join_channel(sid2, &server_id, "#general")
+            .await
+            .unwrap();
         drain_events(&mut rx2);
 
         // Send a message
@@ -1714,6 +1783,7 @@ mod tests {
                 None,
                 None,
             )
+            .await
             .unwrap();
 
         let event = rx2.try_recv().unwrap();
@@ -1749,7 +1819,10 @@ mod tests {
             .unwrap();
 
         let (sid1, mut rx1) = connect_user(&engine, Some(&user_id), "alice");
-        engine.join_channel(sid1, &server_id, "#general").unwrap();
+        engine
+            .join_channel(sid1, &server_id, "#general")
+            .await
+            .unwrap();
         drain_events(&mut rx1);
 
         // Second user joins
@@ -1757,7 +1830,10 @@ mod tests {
         engine.join_server(&user2_id, &server_id).await.unwrap();
 
         let (sid2, _rx2) = connect_user(&engine, Some(&user2_id), "bob");
-        engine.join_channel(sid2, &server_id, "#general").unwrap();
+        engine
+            .join_channel(sid2, &server_id, "#general")
+            .await
+            .unwrap();
 
         // Alice should receive the Join event for Bob
         let event = rx1.try_recv().unwrap();
@@ -1997,9 +2073,10 @@ mod tests {
         assert!(!dup, "Duplicate reaction should be ignored");
 
         // Get reactions
-        let reactions = queries::messages::get_reactions_for_messages(&pool, &[msg_id.clone()])
-            .await
-            .unwrap();
+        let reactions =
+            queries::messages::get_reactions_for_messages(&pool, std::slice::from_ref(&msg_id))
+                .await
+                .unwrap();
         assert_eq!(reactions.len(), 3);
 
         // Remove a reaction
@@ -2009,7 +2086,7 @@ mod tests {
         assert!(removed);
 
         let reactions_after =
-            queries::messages::get_reactions_for_messages(&pool, &[msg_id.clone()])
+            queries::messages::get_reactions_for_messages(&pool, std::slice::from_ref(&msg_id))
                 .await
                 .unwrap();
         assert_eq!(reactions_after.len(), 2);
@@ -2531,207 +2608,1677 @@ mod tests {
     //  Slowmode & NSFW Channel Flags
     // ═══════════════════════════════════════════════════════════════
 
+    // Current-thread runtime on purpose: none of these checks may block.
     #[tokio::test]
-    async fn test_slowmode_and_nsfw_flags() {
-        let pool = setup_db().await;
-
-        let owner_id = create_test_user(&pool, "alice").await;
+    async fn test_send_pipeline_enforces_slowmode_and_automod() {
+        let (engine, pool) = setup_engine().await;
 
-        let server_id = "flags-server";
-        queries::servers::create_server(&pool, server_id, "Flags Test", &owner_id, None)
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        let server_id = engine
+            .create_server("Pipeline".into(), alice_id.clone(), None)
             .await
             .unwrap();
+        engine.join_server(&bob_id, &server_id).await.unwrap();
 
-        let channel_id = Uuid::new_v4().to_string();
-        queries::channels::ensure_channel(&pool, &channel_id, server_id, "#test")
+        let (alice, _rx_a) = connect_user(&engine, Some(&alice_id), "alice");
+        let (bob, _rx_b) = connect_user(&engine, Some(&bob_id), "bob");
+        engine
+            .join_channel(alice, &server_id, "#general")
             .await
             .unwrap();
-
-        // Set slowmode
-        queries::moderation::set_slowmode(&pool, &channel_id, 30)
+        engine
+            .join_channel(bob, &server_id, "#general")
             .await
             .unwrap();
-        let ch = queries::channels::get_channel(&pool, &channel_id)
+
+        let channel_id = engine.resolve_channel_id(&server_id, "#general").unwrap();
+        queries::moderation::set_slowmode(&pool, &channel_id, 30)
             .await
-            .unwrap()
             .unwrap();
-        assert_eq!(ch.slowmode_seconds, 30);
 
-        // Set NSFW
-        queries::moderation::set_nsfw(&pool, &channel_id, true)
+        engine
+            .send_message(alice, &server_id, "#general", "first", None, None, None)
             .await
             .unwrap();
-        let ch2 = queries::channels::get_channel(&pool, &channel_id)
+        let err = engine
+            .send_message(alice, &server_id, "#general", "second", None, None, None)
             .await
-            .unwrap()
-            .unwrap();
-        assert_eq!(ch2.is_nsfw, 1);
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            EngineError::RateLimited {
+                retry_after: Some(_)
+            }
+        ));
 
-        // Clear both
-        queries::moderation::set_slowmode(&pool, &channel_id, 0)
+        // Created through the engine so the compiled rule set is reloaded.
+        engine
+            .create_automod_rule(
+                alice,
+                &CreateAutomodRuleParams {
+                    id: "",
+                    server_id: &server_id,
+                    name: "No Spam",
+                    rule_type: "keyword",
+                    config: r#"{"words":["spam"]}"#,
+                    action_type: "delete",
+                    timeout_duration_seconds: None,
+                },
+            )
             .await
             .unwrap();
-        queries::moderation::set_nsfw(&pool, &channel_id, false)
+
+        let err = engine
+            .send_message(
+                bob,
+                &server_id,
+                "#general",
+                "buy spam now",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            EngineError::CannotSend("Message blocked by automod rule: No Spam".into())
+        );
+        // A blocked message doesn't start the sender's slow mode cooldown.
+        engine
+            .send_message(bob, &server_id, "#general", "hello", None, None, None)
             .await
             .unwrap();
-        let ch3 = queries::channels::get_channel(&pool, &channel_id)
+
+        // DMs belong to no server, so its rules don't see them
+        engine
+            .send_message(bob, &server_id, "alice", "buy spam now", None, None, None)
             .await
-            .unwrap()
             .unwrap();
-        assert_eq!(ch3.slowmode_seconds, 0);
-        assert_eq!(ch3.is_nsfw, 0);
     }
 
-    // ═══════════════════════════════════════════════════════════════
-    //  Server Nickname
-    // ═══════════════════════════════════════════════════════════════
-
-    // NOTE: test_server_nickname_set_and_clear removed because the `nickname` column
-    // does not exist on the `server_members` table in any migration. The functions
-    // get_server_nickname / set_server_nickname reference a column that was never added.
-
-    // ═══════════════════════════════════════════════════════════════
-    //  Message Reply Chain
-    // ═══════════════════════════════════════════════════════════════
-
     #[tokio::test]
-    async fn test_message_reply_chain() {
-        let pool = setup_db().await;
-
-        let user_id = create_test_user(&pool, "alice").await;
+    async fn test_automod_rules_reload_on_update() {
+        let (engine, pool) = setup_engine().await;
 
-        let server_id = "reply-server";
-        queries::servers::create_server(&pool, server_id, "Reply Test", &user_id, None)
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        let server_id = engine
+            .create_server("AutoMod".into(), alice_id.clone(), None)
             .await
             .unwrap();
+        engine.join_server(&bob_id, &server_id).await.unwrap();
 
-        let channel_id = Uuid::new_v4().to_string();
-        queries::channels::ensure_channel(&pool, &channel_id, server_id, "#general")
+        let (alice, _rx_a) = connect_user(&engine, Some(&alice_id), "alice");
+        let (bob, _rx_b) = connect_user(&engine, Some(&bob_id), "bob");
+        engine
+            .join_channel(bob, &server_id, "#general")
             .await
             .unwrap();
 
-        // Send original message
-        let msg1_id = Uuid::new_v4().to_string();
-        queries::messages::insert_message(
-            &pool,
-            &queries::messages::InsertMessageParams {
-                id: &msg1_id,
-                server_id,
-                channel_id: &channel_id,
-                sender_id: &user_id,
-                sender_nick: "alice",
-                content: "Original message",
-                reply_to_id: None,
-            },
-        )
-        .await
-        .unwrap();
-
-        // Reply to it
-        let msg2_id = Uuid::new_v4().to_string();
-        queries::messages::insert_message(
-            &pool,
-            &queries::messages::InsertMessageParams {
-                id: &msg2_id,
-                server_id,
-                channel_id: &channel_id,
-                sender_id: &user_id,
-                sender_nick: "alice",
-                content: "This is a reply",
-                reply_to_id: Some(&msg1_id),
-            },
-        )
-        .await
-        .unwrap();
-
-        // Verify the reply references the original
-        let reply = queries::messages::get_message_by_id(&pool, &msg2_id)
+        engine
+            .create_automod_rule(
+                alice,
+                &CreateAutomodRuleParams {
+                    id: "",
+                    server_id: &server_id,
+                    name: "Crypto",
+                    rule_type: "keyword",
+                    config: r#"{"words":["*coin"]}"#,
+                    action_type: "delete",
+                    timeout_duration_seconds: None,
+                },
+            )
             .await
-            .unwrap()
             .unwrap();
-        assert_eq!(reply.reply_to_id, Some(msg1_id.clone()));
+        let send = |content: &'static str| {
+            engine.send_message(bob, &server_id, "#general", content, None, None, None)
+        };
+        // Cyrillic с and о fold to Latin
+        assert!(matches!(
+            send("free bitсоin").await,
+            Err(EngineError::CannotSend(_))
+        ));
 
-        // Original should not have a reply_to
-        let original = queries::messages::get_message_by_id(&pool, &msg1_id)
+        let rule_id = queries::automod::list_rules(&pool, &server_id)
             .await
             .unwrap()
+            .remove(0)
+            .id;
+        let channel_id = engine.resolve_channel_id(&server_id, "#general").unwrap();
+        let config = format!(r#"{{"words":["*coin"],"exempt_channels":["{channel_id}"]}}"#);
+        engine
+            .update_automod_rule(
+                alice,
+                &UpdateAutomodRuleParams {
+                    rule_id: &rule_id,
+                    server_id: &server_id,
+                    name: "Crypto",
+                    enabled: true,
+                    config: &config,
+                    action_type: "delete",
+                    timeout_duration_seconds: None,
+                },
+            )
+            .await
             .unwrap();
-        assert!(original.reply_to_id.is_none());
+        send("free bitcoin").await.unwrap();
     }
 
-    // ═══════════════════════════════════════════════════════════════
-    //  Concurrent Server Member Operations
-    // ═══════════════════════════════════════════════════════════════
-
     #[tokio::test]
-    async fn test_concurrent_server_joins() {
-        let pool = setup_db().await;
-
-        let owner_id = create_test_user(&pool, "owner").await;
+    async fn test_automod_actions_alert_flag_hold_and_timeout() {
+        let (engine, pool) = setup_engine().await;
 
-        let server_id = "concurrent-server";
-        queries::servers::create_server(&pool, server_id, "Concurrent Test", &owner_id, None)
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        let server_id = engine
+            .create_server("AutoMod".into(), alice_id.clone(), None)
             .await
             .unwrap();
-
-        // Create 10 users
-        let mut user_ids = Vec::new();
-        for i in 0..10 {
-            let uid = create_test_user(&pool, &format!("user{i}")).await;
-            user_ids.push(uid);
-        }
-
-        // Join all concurrently (INSERT OR IGNORE handles conflicts)
-        let mut handles = Vec::new();
-        for uid in &user_ids {
-            let pool_clone = pool.clone();
-            let sid = server_id.to_string();
-            let uid = uid.clone();
-            handles.push(tokio::spawn(async move {
-                queries::servers::add_server_member(&pool_clone, &sid, &uid, "member")
-                    .await
-                    .unwrap();
-            }));
-        }
-        for h in handles {
-            h.await.unwrap();
-        }
-
-        // Verify all 11 members (owner + 10 users)
-        let member_count = queries::servers::get_member_count(&pool, server_id)
+        engine.join_server(&bob_id, &server_id).await.unwrap();
+        engine
+            .create_channel_in_server(&server_id, "#mod-log", None, false)
             .await
             .unwrap();
-        assert_eq!(member_count, 11);
-    }
-
-    // ═══════════════════════════════════════════════════════════════
-    //  Multi-Server User Flow
-    // ═══════════════════════════════════════════════════════════════
-
-    #[tokio::test]
-    async fn test_user_across_multiple_servers() {
-        let pool = setup_db().await;
-
-        let user_id = create_test_user(&pool, "alice").await;
+        let mod_log_id = engine.resolve_channel_id(&server_id, "#mod-log").unwrap();
 
-        // Create 3 servers, alice owns all of them
-        let mut server_ids = Vec::new();
-        for i in 0..3 {
-            let sid = format!("multi-srv-{i}");
-            queries::servers::create_server(&pool, &sid, &format!("Server {i}"), &user_id, None)
+        let (alice, mut rx_a) = connect_user(&engine, Some(&alice_id), "alice");
+        let (bob, _rx_b) = connect_user(&engine, Some(&bob_id), "bob");
+        engine
+            .join_channel(alice, &server_id, "#mod-log")
+            .await
+            .unwrap();
+        engine
+            .join_channel(alice, &server_id, "#general")
+            .await
+            .unwrap();
+        engine
+            .join_channel(bob, &server_id, "#general")
+            .await
+            .unwrap();
+
+        let rules = [
+            (
+                "Links",
+                format!(
+                    r#"{{"words":["link"],"actions":[{{"type":"flag"}},{{"type":"alert","channel_id":"{mod_log_id}"}}]}}"#
+                ),
+            ),
+            (
+                "Invites",
+                r#"{"words":["invite"],"actions":[{"type":"require_approval"}]}"#.to_string(),
+            ),
+            (
+                "Slurs",
+                r#"{"words":["slur"],"actions":[{"type":"block"},{"type":"timeout","duration_seconds":600}]}"#
+                    .to_string(),
+            ),
+        ];
+        for (name, config) in &rules {
+            engine
+                .create_automod_rule(
+                    alice,
+                    &CreateAutomodRuleParams {
+                        id: "",
+                        server_id: &server_id,
+                        name,
+                        rule_type: "keyword",
+                        config,
+                        action_type: "delete",
+                        timeout_duration_seconds: None,
+                    },
+                )
                 .await
                 .unwrap();
-            server_ids.push(sid);
         }
+        drain_events(&mut rx_a);
 
-        // Alice should be a member of all 3
-        let alice_servers = queries::servers::list_servers_for_user(&pool, &user_id)
+        // Flag + alert: delivered, and the alert lands in #mod-log
+        engine
+            .send_message(bob, &server_id, "#general", "a link", None, None, None)
             .await
             .unwrap();
-        assert_eq!(alice_servers.len(), 3);
+        let mut targets = Vec::new();
+        while let Ok(event) = rx_a.try_recv() {
+            if let ChatEvent::Message { from, target, .. } = event {
+                targets.push((from, target));
+            }
+        }
+        assert!(targets.contains(&("bob".into(), "#general".into())));
+        assert!(targets.contains(&(
+            crate::engine::chat_engine::AUTOMOD_NICK.into(),
+            "#mod-log".into()
+        )));
 
-        // Leave one server
-        queries::servers::remove_server_member(&pool, &server_ids[1], &user_id)
+        // Require approval: held until a moderator approves it
+        let err = engine
+            .send_message(bob, &server_id, "#general", "an invite", None, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, EngineError::CannotSend(_)));
+        let queue = queries::automod::list_open_queue(&pool, &server_id)
+            .await
+            .unwrap();
+        assert_eq!(queue.len(), 2);
+        let held = queue.iter().find(|e| e.status == "pending").unwrap();
+        assert_eq!(held.content, "an invite");
+        assert!(
+            queue
+                .iter()
+                .any(|e| e.status == "flagged" && e.message_id.is_some())
+        );
+
+        drain_events(&mut rx_a);
+        engine
+            .review_automod_entry(alice, &server_id, &held.id, true)
+            .await
+            .unwrap();
+        let delivered = std::iter::from_fn(|| rx_a.try_recv().ok()).any(|e| {
+            matches!(e, ChatEvent::Message { ref content, ref from, .. }
+                if content == "an invite" && from == "bob")
+        });
+        assert!(delivered);
+        assert!(matches!(
+            engine
+                .review_automod_entry(alice, &server_id, &held.id, false)
+                .await,
+            Err(EngineError::Conflict(_))
+        ));
+        // Members can't review
+        assert!(
+            engine
+                .review_automod_entry(bob, &server_id, &held.id, true)
+                .await
+                .is_err()
+        );
+
+        // Block + timeout
+        let err = engine
+            .send_message(bob, &server_id, "#general", "a slur", None, None, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err,
+            EngineError::CannotSend("Message blocked by automod rule: Slurs".into())
+        );
+        assert!(
+            queries::moderation::get_member_timeout(&pool, &server_id, &bob_id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        let audit = queries::audit_log::list_entries(&pool, &server_id, None, 50, None)
+            .await
+            .unwrap();
+        for action in [
+            "automod_flag",
+            "automod_alert",
+            "automod_hold",
+            "automod_approve",
+            "automod_block",
+            "automod_timeout",
+        ] {
+            assert!(
+                audit.iter().any(|e| e.action_type == action),
+                "missing audit entry {action}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_automod_flood_and_raid_lockdown() {
+        let (engine, pool) = setup_engine().await;
+
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        let server_id = engine
+            .create_server("Raided".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        let (alice, mut rx_a) = connect_user(&engine, Some(&alice_id), "alice");
+        let (bob, _rx_b) = connect_user(&engine, Some(&bob_id), "bob");
+
+        for (name, rule_type, config) in [
+            (
+                "Flood",
+                "message_flood",
+                r#"{"max_messages":2,"window_seconds":60,"actions":[{"type":"block"}]}"#,
+            ),
+            (
+                "Raid",
+                "raid",
+                r#"{"max_joins":1,"window_seconds":60,"lockdown_seconds":600,"actions":[{"type":"alert","channel_id":"nowhere"}]}"#,
+            ),
+        ] {
+            engine
+                .create_automod_rule(
+                    alice,
+                    &CreateAutomodRuleParams {
+                        id: "",
+                        server_id: &server_id,
+                        name,
+                        rule_type,
+                        config,
+                        action_type: "delete",
+                        timeout_duration_seconds: None,
+                    },
+                )
+                .await
+                .unwrap();
+        }
+        drain_events(&mut rx_a);
+
+        // Rejoining as an existing member isn't a new join
+        engine.join_server(&bob_id, &server_id).await.unwrap();
+        engine.join_server(&bob_id, &server_id).await.unwrap();
+        assert!(
+            !std::iter::from_fn(|| rx_a.try_recv().ok())
+                .any(|e| matches!(e, ChatEvent::ServerLockdown { .. }))
+        );
+
+        // The second join within the window is a raid
+        let carol_id = create_test_user(&pool, "carol").await;
+        engine.join_server(&carol_id, &server_id).await.unwrap();
+        let locked = std::iter::from_fn(|| rx_a.try_recv().ok())
+            .any(|e| matches!(e, ChatEvent::ServerLockdown { until: Some(_), .. }));
+        assert!(locked);
+
+        // Invites are paused and new members can't send
+        assert!(matches!(
+            engine
+                .create_invite(alice, &server_id, None, None, None)
+                .await,
+            Err(EngineError::Forbidden(_))
+        ));
+        engine
+            .join_channel(bob, &server_id, "#general")
+            .await
+            .unwrap();
+        assert!(matches!(
+            engine
+                .send_message(bob, &server_id, "#general", "hi", None, None, None)
+                .await,
+            Err(EngineError::CannotSend(_))
+        ));
+
+        // Members can't lift it; moderators can
+        assert!(
+            engine
+                .set_server_lockdown(bob, &server_id, 0, None)
+                .await
+                .is_err()
+        );
+        engine
+            .set_server_lockdown(alice, &server_id, 0, None)
+            .await
+            .unwrap();
+        engine
+            .create_invite(alice, &server_id, None, None, None)
+            .await
+            .unwrap();
+
+        // Flooding: the third message within the window is blocked
+        let send = || engine.send_message(bob, &server_id, "#general", "hi", None, None, None);
+        send().await.unwrap();
+        send().await.unwrap();
+        assert_eq!(
+            send().await.unwrap_err(),
+            EngineError::CannotSend("Message blocked by automod rule: Flood".into())
+        );
+
+        let audit = queries::audit_log::list_entries(&pool, &server_id, None, 50, None)
+            .await
+            .unwrap();
+        for action in ["automod_lockdown", "server_lockdown_lift", "automod_block"] {
+            assert!(
+                audit.iter().any(|e| e.action_type == action),
+                "missing audit entry {action}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_review_queue() {
+        use crate::engine::chat_engine::ResolveReportParams;
+
+        let (engine, pool) = setup_engine().await;
+
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        let carol_id = create_test_user(&pool, "carol").await;
+        let server_id = engine
+            .create_server("Reports".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        engine.join_server(&bob_id, &server_id).await.unwrap();
+        engine.join_server(&carol_id, &server_id).await.unwrap();
+
+        let (bob, mut rx_b) = connect_user(&engine, Some(&bob_id), "bob");
+        let (carol, _rx_c) = connect_user(&engine, Some(&carol_id), "carol");
+        engine
+            .join_channel(bob, &server_id, "#general")
+            .await
+            .unwrap();
+        engine
+            .join_channel(carol, &server_id, "#general")
+            .await
+            .unwrap();
+
+        for content in ["hello", "buy cheap stuff", "bye"] {
+            engine
+                .send_message(carol, &server_id, "#general", content, None, None, None)
+                .await
+                .unwrap();
+        }
+        let spam_id = std::iter::from_fn(|| rx_b.try_recv().ok())
+            .find_map(|e| match e {
+                ChatEvent::Message { id, content, .. } if content == "buy cheap stuff" => {
+                    Some(id.to_string())
+                }
+                _ => None,
+            })
+            .unwrap();
+
+        // Bob reports the message; a second identical report is rejected
+        engine
+            .report(bob, &server_id, Some(&spam_id), None, "spam")
+            .await
+            .unwrap();
+        assert!(matches!(
+            engine
+                .create_report(&bob_id, &server_id, Some(&spam_id), None, "spam")
+                .await,
+            Err(EngineError::Conflict(_))
+        ));
+        // Nobody can report themselves
+        assert!(
+            engine
+                .create_report(&carol_id, &server_id, None, Some(&carol_id), "me")
+                .await
+                .is_err()
+        );
+
+        // Only moderators see the queue, which includes surrounding messages
+        assert!(
+            engine
+                .fetch_open_reports(&bob_id, &server_id)
+                .await
+                .is_err()
+        );
+        let reports = engine
+            .fetch_open_reports(&alice_id, &server_id)
+            .await
+            .unwrap();
+        assert_eq!(reports.len(), 1);
+        let report = &reports[0];
+        assert_eq!(report.target_user_id, carol_id);
+        assert_eq!(report.message_content.as_deref(), Some("buy cheap stuff"));
+        let context: Vec<&str> = report.context.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(context, ["hello", "buy cheap stuff", "bye"]);
+
+        // A moderator who can't view #general doesn't see reports from it
+        let dave_id = create_test_user(&pool, "dave").await;
+        engine.join_server(&dave_id, &server_id).await.unwrap();
+        let mods = engine
+            .create_role(
+                &server_id,
+                "Mods",
+                None,
+                (Permissions::VIEW_CHANNELS | Permissions::MANAGE_MESSAGES).bits() as i64,
+            )
+            .await
+            .unwrap();
+        engine
+            .assign_role(&server_id, &alice_id, &dave_id, &mods.id)
+            .await
+            .unwrap();
+        engine
+            .set_channel_override(
+                &server_id,
+                "#general",
+                &ChannelOverride {
+                    target_type: OverrideTargetType::User,
+                    target_id: dave_id.clone(),
+                    allow: Permissions::empty(),
+                    deny: Permissions::VIEW_CHANNELS,
+                },
+            )
+            .await
+            .unwrap();
+        assert!(
+            engine
+                .fetch_open_reports(&dave_id, &server_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let hidden = ResolveReportParams {
+            report_id: &report.id,
+            action: "dismiss",
+            note: None,
+            timeout_seconds: None,
+        };
+        assert!(matches!(
+            engine
+                .resolve_report_by(&dave_id, &server_id, &hidden)
+                .await,
+            Err(EngineError::NotFound(_))
+        ));
+
+        // Resolve by timing out the author
+        let params = ResolveReportParams {
+            report_id: &report.id,
+            action: "timeout",
+            note: Some("first offence"),
+            timeout_seconds: Some(600),
+        };
+        assert!(
+            engine
+                .resolve_report_by(&bob_id, &server_id, &params)
+                .await
+                .is_err()
+        );
+        drain_events(&mut rx_b);
+        let resolved = engine
+            .resolve_report_by(&alice_id, &server_id, &params)
+            .await
+            .unwrap();
+        assert_eq!(resolved.status, "resolved");
+        assert_eq!(resolved.resolved_by.as_deref(), Some(alice_id.as_str()));
+        assert!(
+            queries::moderation::get_member_timeout(&pool, &server_id, &carol_id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        // Resolving twice is a conflict
+        assert!(matches!(
+            engine
+                .resolve_report_by(&alice_id, &server_id, &params)
+                .await,
+            Err(EngineError::Conflict(_))
+        ));
+
+        // The reporter hears the outcome, but not who handled it
+        let update = std::iter::from_fn(|| rx_b.try_recv().ok())
+            .find_map(|e| match e {
+                ChatEvent::ReportUpdate { report, .. } => Some(report),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(update.resolution.as_deref(), Some("timeout"));
+        assert!(update.resolved_by.is_none());
+
+        assert!(
+            engine
+                .fetch_open_reports(&alice_id, &server_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let audit = queries::audit_log::list_entries(&pool, &server_id, None, 50, None)
+            .await
+            .unwrap();
+        assert!(audit.iter().any(|e| e.action_type == "report_resolve"));
+    }
+
+    #[tokio::test]
+    async fn test_infractions_escalate_to_timeout_and_ban() {
+        use crate::engine::events::EscalationPolicyInfo;
+
+        let (engine, pool) = setup_engine().await;
+
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        let carol_id = create_test_user(&pool, "carol").await;
+        let server_id = engine
+            .create_server("Strikes".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        engine.join_server(&bob_id, &server_id).await.unwrap();
+        engine.join_server(&carol_id, &server_id).await.unwrap();
+
+        let (alice, _rx_a) = connect_user(&engine, Some(&alice_id), "alice");
+        let (bob, mut rx_b) = connect_user(&engine, Some(&bob_id), "bob");
+        engine
+            .join_channel(bob, &server_id, "#general")
+            .await
+            .unwrap();
+
+        let month = 30 * 24 * 60 * 60;
+        let mut ladder = vec![
+            EscalationPolicyInfo {
+                threshold: 2,
+                window_seconds: month,
+                action: "timeout".into(),
+                duration_seconds: None,
+            },
+            EscalationPolicyInfo {
+                threshold: 3,
+                window_seconds: month,
+                action: "ban".into(),
+                duration_seconds: None,
+            },
+        ];
+        // Timeouts need a length
+        assert!(matches!(
+            engine
+                .set_escalation_policies_by(&alice_id, &server_id, &ladder)
+                .await,
+            Err(EngineError::Validation(_))
+        ));
+        ladder[0].duration_seconds = Some(3600);
+        assert!(
+            engine
+                .set_escalation_policies_by(&carol_id, &server_id, &ladder)
+                .await
+                .is_err()
+        );
+        engine
+            .set_escalation_policies_by(&alice_id, &server_id, &ladder)
+            .await
+            .unwrap();
+        engine
+            .create_automod_rule(
+                alice,
+                &CreateAutomodRuleParams {
+                    id: "",
+                    server_id: &server_id,
+                    name: "Slurs",
+                    rule_type: "keyword",
+                    config: r#"{"words":["slur"],"actions":[{"type":"block"}]}"#,
+                    action_type: "delete",
+                    timeout_duration_seconds: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        // First strike: a warning, delivered to bob
+        assert!(
+            engine
+                .warn_member_by(&carol_id, &server_id, &bob_id, "be nice", None)
+                .await
+                .is_err()
+        );
+        drain_events(&mut rx_b);
+        let first = engine
+            .warn_member_by(&alice_id, &server_id, &bob_id, "be nice", None)
+            .await
+            .unwrap();
+        assert!(first.active);
+        let warned = std::iter::from_fn(|| rx_b.try_recv().ok())
+            .any(|e| matches!(e, ChatEvent::MemberWarn { ref reason, .. } if reason == "be nice"));
+        assert!(warned);
+
+        // Second strike from AutoMod trips the timeout
+        assert!(
+            engine
+                .send_message(bob, &server_id, "#general", "a slur", None, None, None)
+                .await
+                .is_err()
+        );
+        assert!(
+            queries::moderation::get_member_timeout(&pool, &server_id, &bob_id)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        // Third strike bans
+        engine
+            .warn_member_by(&alice_id, &server_id, &bob_id, "last chance", None)
+            .await
+            .unwrap();
+        assert!(!engine.user_is_server_member(&server_id, &bob_id));
+        // Automatic escalations act as the system user, not the offender
+        let ban = queries::bans::get_ban(&pool, &server_id, &bob_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ban.banned_by, SYSTEM_USER_ID);
+
+        let history = engine
+            .fetch_infractions(&alice_id, &server_id, &bob_id)
+            .await
+            .unwrap();
+        let entries: Vec<(&str, &str)> = history
+            .iter()
+            .map(|i| (i.action.as_str(), i.source.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("ban", "escalation"),
+                ("warn", "manual"),
+                ("timeout", "escalation"),
+                ("automod", "automod"),
+                ("warn", "manual"),
+            ]
+        );
+        assert!(
+            engine
+                .fetch_infractions(&carol_id, &server_id, &bob_id)
+                .await
+                .is_err()
+        );
+
+        // Pardoned strikes stop counting
+        let pardoned = engine
+            .pardon_infraction_by(&alice_id, &server_id, &first.id)
+            .await
+            .unwrap();
+        assert!(!pardoned.active);
+        assert_eq!(pardoned.pardoned_by.as_deref(), Some(alice_id.as_str()));
+        assert!(matches!(
+            engine
+                .pardon_infraction_by(&alice_id, &server_id, &first.id)
+                .await,
+            Err(EngineError::Conflict(_))
+        ));
+        assert_eq!(
+            queries::infractions::count_strikes(&pool, &server_id, &bob_id, month as i64)
+                .await
+                .unwrap(),
+            2
+        );
+
+        let audit = queries::audit_log::list_entries(&pool, &server_id, None, 50, None)
+            .await
+            .unwrap();
+        for action in [
+            "escalation_policies_update",
+            "member_warn",
+            "infraction_escalate",
+            "member_ban",
+            "infraction_pardon",
+        ] {
+            assert!(
+                audit.iter().any(|e| e.action_type == action),
+                "missing audit entry {action}"
+            );
+        }
+        assert!(
+            audit
+                .iter()
+                .filter(|e| e.action_type == "infraction_escalate")
+                .all(|e| e.actor_id == SYSTEM_USER_ID)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_temporary_preemptive_and_bulk_bans() {
+        use crate::engine::chat_engine::BanParams;
+
+        let (engine, pool) = setup_engine().await;
+
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        let carol_id = create_test_user(&pool, "carol").await;
+        let server_id = engine
+            .create_server("Bans".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        engine.join_server(&bob_id, &server_id).await.unwrap();
+        engine.join_server(&carol_id, &server_id).await.unwrap();
+
+        let (alice, mut rx_a) = connect_user(&engine, Some(&alice_id), "alice");
+        let (bob, _rx_b) = connect_user(&engine, Some(&bob_id), "bob");
+        engine
+            .join_channel(bob, &server_id, "#general")
+            .await
+            .unwrap();
+        engine
+            .send_message(bob, &server_id, "#general", "spam", None, None, None)
+            .await
+            .unwrap();
+
+        let live_messages = |user_id: String| {
+            let pool = pool.clone();
+            async move {
+                sqlx::query_scalar::<_, i64>(
+                    "SELECT COUNT(*) FROM messages WHERE sender_id = ? AND deleted_at IS NULL",
+                )
+                .bind(user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+            }
+        };
+        assert_eq!(live_messages(bob_id.clone()).await, 1);
+
+        // A one-hour ban that purges the last hour of messages
+        assert!(matches!(
+            engine
+                .ban_member(
+                    alice,
+                    &server_id,
+                    &bob_id,
+                    &BanParams {
+                        duration_seconds: Some(0),
+                        ..Default::default()
+                    },
+                )
+                .await,
+            Err(EngineError::Validation(_))
+        ));
+        engine
+            .ban_member(
+                alice,
+                &server_id,
+                &bob_id,
+                &BanParams {
+                    reason: Some("cool off"),
+                    duration_seconds: Some(3600),
+                    delete_message_seconds: 3600,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(live_messages(bob_id.clone()).await, 0);
+        assert!(!engine.user_is_server_member(&server_id, &bob_id));
+        let bans = engine.get_bans(alice, &server_id).await.unwrap();
+        assert_eq!(bans.len(), 1);
+        assert!(bans[0].expires_at.is_some());
+        assert_eq!(
+            engine.join_server(&bob_id, &server_id).await,
+            Err(EngineError::Banned)
+        );
+
+        // Nothing to lift until the ban runs out
+        assert_eq!(engine.expire_bans().await, 0);
+        sqlx::query("UPDATE bans SET expires_at = datetime('now', '-1 minute') WHERE user_id = ?")
+            .bind(&bob_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        drain_events(&mut rx_a);
+        assert_eq!(engine.expire_bans().await, 1);
+        let unbanned = std::iter::from_fn(|| rx_a.try_recv().ok())
+            .any(|e| matches!(e, ChatEvent::MemberUnban { ref user_id, .. } if *user_id == bob_id));
+        assert!(unbanned);
+        engine.join_server(&bob_id, &server_id).await.unwrap();
+
+        // Bulk ban: members, unknown accounts and duplicates; the owner fails
+        let list = vec![
+            carol_id.clone(),
+            "did:plc:stranger".to_string(),
+            carol_id.clone(),
+            alice_id.clone(),
+        ];
+        assert!(
+            engine
+                .ban_members_by(&bob_id, &server_id, &list, &BanParams::default())
+                .await
+                .is_err()
+        );
+        let (banned, failed) = engine
+            .ban_members_by(&alice_id, &server_id, &list, &BanParams::default())
+            .await
+            .unwrap();
+        assert_eq!(banned, [carol_id.clone(), "did:plc:stranger".to_string()]);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].user_id, alice_id);
+        assert!(!engine.user_is_server_member(&server_id, &carol_id));
+        assert!(
+            queries::bans::is_banned(&pool, &server_id, "did:plc:stranger")
+                .await
+                .unwrap()
+        );
+        // Permanent bans are never swept
+        assert_eq!(engine.expire_bans().await, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_instance_suspension_bans_and_quarantine() {
+        let (engine, pool) = setup_engine().await;
+
+        let admin_id = create_test_user(&pool, "admin").await;
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        queries::servers::set_system_admin(&pool, &admin_id, true)
+            .await
+            .unwrap();
+        let server_id = engine
+            .create_server("Shady".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        engine.join_server(&bob_id, &server_id).await.unwrap();
+
+        // Only system admins reach instance administration
+        assert!(matches!(
+            engine
+                .set_user_suspended_by(&alice_id, &bob_id, true, None)
+                .await,
+            Err(EngineError::Forbidden(_))
+        ));
+        assert!(matches!(
+            engine.fetch_instance_reports(&alice_id).await,
+            Err(EngineError::Forbidden(_))
+        ));
+
+        // Suspension closes live sessions and blocks the account
+        let (bob, mut rx_b) = connect_user(&engine, Some(&bob_id), "bob");
+        engine
+            .set_user_suspended_by(&admin_id, &bob_id, true, Some("spam"))
+            .await
+            .unwrap();
+        assert!(engine.get_session(bob).is_none());
+        assert!(matches!(
+            rx_b.try_recv(),
+            Ok(ChatEvent::Error { code, .. }) if code == "SESSION_REVOKED"
+        ));
+        assert!(
+            !queries::users::is_account_active(&pool, &bob_id)
+                .await
+                .unwrap()
+        );
+        engine
+            .set_user_suspended_by(&admin_id, &bob_id, false, None)
+            .await
+            .unwrap();
+        assert!(
+            queries::users::is_account_active(&pool, &bob_id)
+                .await
+                .unwrap()
+        );
+
+        // A DID that has never signed in can be banned ahead of time
+        engine
+            .instance_ban_by(&admin_id, "did:plc:raider", Some("raids"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            engine.instance_ban_by(&admin_id, "not a did", None).await,
+            Err(EngineError::Validation(_))
+        ));
+        assert!(matches!(
+            engine.instance_ban_by(&admin_id, &admin_id, None).await,
+            Err(EngineError::Validation(_))
+        ));
+        let bans = engine.fetch_instance_bans(&admin_id).await.unwrap();
+        assert_eq!(bans.len(), 1);
+        assert_eq!(bans[0].user_id, "did:plc:raider");
+        engine
+            .instance_unban_by(&admin_id, "did:plc:raider")
+            .await
+            .unwrap();
+        assert!(matches!(
+            engine.instance_unban_by(&admin_id, "did:plc:raider").await,
+            Err(EngineError::NotFound(_))
+        ));
+
+        // Reports from every server show up in the admin view
+        engine
+            .create_report(&bob_id, &server_id, None, Some(&alice_id), "scam server")
+            .await
+            .unwrap();
+        let reports = engine.fetch_instance_reports(&admin_id).await.unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].server_name, "Shady");
+        assert_eq!(reports[0].report.reason, "scam server");
+
+        // Quarantine freezes the server read-only and tells its members
+        let (alice, mut rx_a) = connect_user(&engine, Some(&alice_id), "alice");
+        engine
+            .join_channel(alice, &server_id, "#general")
+            .await
+            .unwrap();
+        drain_events(&mut rx_a);
+        engine
+            .set_server_quarantine_by(&admin_id, &server_id, true, Some("scams"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            rx_a.try_recv(),
+            Ok(ChatEvent::ServerQuarantine {
+                quarantined: true,
+                ..
+            })
+        ));
+        assert!(matches!(
+            engine
+                .send_message(alice, &server_id, "#general", "hi", None, None, None)
+                .await,
+            Err(EngineError::Forbidden(_))
+        ));
+        let carol_id = create_test_user(&pool, "carol").await;
+        assert!(matches!(
+            engine.join_server(&carol_id, &server_id).await,
+            Err(EngineError::Forbidden(_))
+        ));
+        assert!(engine.list_all_servers()[0].quarantined);
+
+        engine
+            .set_server_quarantine_by(&admin_id, &server_id, false, None)
+            .await
+            .unwrap();
+        engine
+            .send_message(alice, &server_id, "#general", "hi", None, None, None)
+            .await
+            .unwrap();
+
+        // Every action landed in the instance audit log
+        let log = engine
+            .fetch_instance_audit_log(&admin_id, None, 50, None)
+            .await
+            .unwrap();
+        let actions: Vec<_> = log.iter().rev().map(|e| e.action_type.as_str()).collect();
+        assert_eq!(
+            actions,
+            [
+                "user_suspend",
+                "user_unsuspend",
+                "instance_ban",
+                "instance_unban",
+                "server_quarantine",
+                "server_unquarantine",
+            ]
+        );
+        assert!(log.iter().all(|e| e.actor_id == admin_id));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_quarantine_freezes_every_write_path() {
+        use crate::engine::chat_engine::BanParams;
+
+        let (engine, pool) = setup_engine().await;
+        let admin_id = create_test_user(&pool, "admin").await;
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        queries::servers::set_system_admin(&pool, &admin_id, true)
+            .await
+            .unwrap();
+        let server_id = engine
+            .create_server("Frozen".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        engine.join_server(&bob_id, &server_id).await.unwrap();
+
+        let (alice, mut rx_a) = connect_user(&engine, Some(&alice_id), "alice");
+        engine
+            .join_channel(alice, &server_id, "#general")
+            .await
+            .unwrap();
+        drain_events(&mut rx_a);
+        engine
+            .send_message(alice, &server_id, "#general", "before", None, None, None)
+            .await
+            .unwrap();
+        let message_id = match rx_a.try_recv() {
+            Ok(ChatEvent::MessageAck { id, .. }) => id.to_string(),
+            other => panic!("expected message ack, got {other:?}"),
+        };
+        engine.add_reaction(alice, &message_id, "👍").await.unwrap();
+        let role = engine
+            .create_role(&server_id, "Helpers", None, 0)
+            .await
+            .unwrap();
+        let rule_id = Uuid::new_v4().to_string();
+        engine
+            .create_automod_rule(
+                alice,
+                &CreateAutomodRuleParams {
+                    id: &rule_id,
+                    server_id: &server_id,
+                    name: "Spam",
+                    rule_type: "keyword",
+                    config: r#"{"words":["spam"]}"#,
+                    action_type: "delete",
+                    timeout_duration_seconds: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        engine
+            .set_server_quarantine_by(&admin_id, &server_id, true, None)
+            .await
+            .unwrap();
+
+        macro_rules! assert_frozen {
+            ($call:expr) => {
+                assert!(
+                    matches!($call, Err(EngineError::Forbidden(ref m)) if m.contains("quarantined")),
+                    "{} was not blocked",
+                    stringify!($call)
+                );
+            };
+        }
+
+        let event_id = Uuid::new_v4().to_string();
+        let override_target = ChannelOverride {
+            target_type: OverrideTargetType::Role,
+            target_id: role.id.clone(),
+            allow: Permissions::empty(),
+            deny: Permissions::SEND_MESSAGES,
+        };
+        assert_frozen!(
+            engine
+                .send_message(alice, &server_id, "#general", "hi", None, None, None)
+                .await
+        );
+        assert_frozen!(engine.edit_message(alice, &message_id, "edited").await);
+        assert_frozen!(engine.delete_message(alice, &message_id).await);
+        assert_frozen!(engine.add_reaction(alice, &message_id, "🎉").await);
+        assert_frozen!(engine.remove_reaction(alice, &message_id, "👍").await);
+        assert_frozen!(
+            engine
+                .pin_message(alice, &server_id, "#general", &message_id)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .unpin_message(alice, &server_id, "#general", &message_id)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .create_thread(alice, &server_id, "#general", "t", &message_id, false)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .set_topic(alice, &server_id, "#general", "new".into())
+                .await
+        );
+        assert_frozen!(
+            engine
+                .update_server_settings(&server_id, Some("Renamed"), None)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .create_channel_in_server(&server_id, "#more", None, false)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .delete_channel_in_server(&server_id, "#general")
+                .await
+        );
+        assert_frozen!(engine.create_category(&server_id, "Voice").await);
+        assert_frozen!(engine.reorder_channels(&server_id, &[]).await);
+        assert_frozen!(engine.create_role(&server_id, "Mods", None, 0).await);
+        assert_frozen!(
+            engine
+                .update_role(&server_id, &role.id, "Renamed", None, 0)
+                .await
+        );
+        assert_frozen!(engine.delete_role(&server_id, &role.id).await);
+        assert_frozen!(
+            engine
+                .assign_role(&server_id, &alice_id, &bob_id, &role.id)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .remove_role(&server_id, &alice_id, &bob_id, &role.id)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .set_channel_override(&server_id, "#general", &override_target)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .delete_channel_override(
+                    &server_id,
+                    "#general",
+                    &OverrideTargetType::Role,
+                    &role.id
+                )
+                .await
+        );
+        assert_frozen!(
+            engine
+                .set_server_nickname(alice, &server_id, Some("ali"))
+                .await
+        );
+        assert_frozen!(
+            engine
+                .create_event(
+                    alice,
+                    &CreateServerEventParams {
+                        id: &event_id,
+                        server_id: &server_id,
+                        name: "Party",
+                        description: None,
+                        channel_id: None,
+                        start_time: "2030-01-01T00:00:00Z",
+                        end_time: None,
+                        image_url: None,
+                        created_by: &alice_id,
+                    },
+                )
+                .await
+        );
+        assert_frozen!(
+            engine
+                .update_automod_rule(
+                    alice,
+                    &UpdateAutomodRuleParams {
+                        rule_id: &rule_id,
+                        server_id: &server_id,
+                        name: "Spam",
+                        enabled: false,
+                        config: r#"{"words":["spam"]}"#,
+                        action_type: "delete",
+                        timeout_duration_seconds: None,
+                    },
+                )
+                .await
+        );
+        assert_frozen!(
+            engine
+                .delete_automod_rule(alice, &server_id, &rule_id)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .ban_member(alice, &server_id, &bob_id, &BanParams::default())
+                .await
+        );
+        assert_frozen!(engine.kick_member(alice, &server_id, &bob_id, None).await);
+        assert_frozen!(
+            engine
+                .timeout_member(alice, &server_id, &bob_id, None, None)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .warn_member(alice, &server_id, &bob_id, "rude", None)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .set_server_lockdown(alice, &server_id, 60, None)
+                .await
+        );
+        assert_frozen!(
+            engine
+                .create_invite(alice, &server_id, None, None, None)
+                .await
+        );
+        assert_frozen!(engine.delete_server(&server_id).await);
+
+        // Moderators can still read, and members can still report and leave
+        assert!(engine.get_bans(alice, &server_id).await.unwrap().is_empty());
+        engine.list_automod_rules(alice, &server_id).await.unwrap();
+        engine
+            .create_report(&bob_id, &server_id, None, Some(&alice_id), "help")
+            .await
+            .unwrap();
+        assert_eq!(
+            engine
+                .fetch_open_reports(&alice_id, &server_id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        engine.leave_server(&bob_id, &server_id).await.unwrap();
+
+        // Instance admins can still remove the server
+        engine
+            .delete_server_by(&admin_id, &server_id, Some("abuse"))
+            .await
+            .unwrap();
+        assert!(engine.get_server_name(&server_id).is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_admin_cli_changes_reach_the_engine() {
+        use queries::instance::record_admin_change;
+
+        let (engine, pool) = setup_engine().await;
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        let server_id = engine
+            .create_server("Kept".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        let doomed_id = engine
+            .create_server("Doomed".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        let (alice, _rx_a) = connect_user(&engine, Some(&alice_id), "alice");
+        let (bob, _rx_b) = connect_user(&engine, Some(&bob_id), "bob");
+
+        // The CLI writes to the database and records what it did
+        queries::servers::transfer_ownership(&pool, &server_id, &bob_id)
+            .await
+            .unwrap();
+        record_admin_change(&pool, "server_transfer", Some(&server_id))
+            .await
+            .unwrap();
+        queries::servers::delete_server(&pool, &doomed_id)
+            .await
+            .unwrap();
+        record_admin_change(&pool, "server_delete", Some(&doomed_id))
+            .await
+            .unwrap();
+        assert!(engine.is_server_owner(&server_id, &alice_id));
+
+        let cursor = engine.apply_admin_changes(0).await.unwrap();
+        assert_eq!(cursor, 2);
+        assert!(engine.is_server_owner(&server_id, &bob_id));
+        assert!(engine.user_is_server_member(&server_id, &bob_id));
+        assert!(engine.get_server_name(&doomed_id).is_none());
+        assert_eq!(engine.apply_admin_changes(cursor).await.unwrap(), cursor);
+
+        queries::users::set_suspended(&pool, &bob_id, true)
+            .await
+            .unwrap();
+        record_admin_change(&pool, "user_suspend", Some(&bob_id))
+            .await
+            .unwrap();
+        let cursor = engine.apply_admin_changes(cursor).await.unwrap();
+        assert!(engine.get_session(bob).is_none());
+        assert!(engine.get_session(alice).is_some());
+
+        // A restore reloads servers and closes every session
+        queries::servers::update_server(&pool, &server_id, "Restored", None)
+            .await
+            .unwrap();
+        record_admin_change(&pool, "restore", None).await.unwrap();
+        engine.apply_admin_changes(cursor).await.unwrap();
+        assert_eq!(
+            engine.get_server_name(&server_id).as_deref(),
+            Some("Restored")
+        );
+        assert!(engine.get_session(alice).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_slowmode_and_nsfw_flags() {
+        let pool = setup_db().await;
+
+        let owner_id = create_test_user(&pool, "alice").await;
+
+        let server_id = "flags-server";
+        queries::servers::create_server(&pool, server_id, "Flags Test", &owner_id, None)
+            .await
+            .unwrap();
+
+        let channel_id = Uuid::new_v4().to_string();
+        queries::channels::ensure_channel(&pool, &channel_id, server_id, "#test")
+            .await
+            .unwrap();
+
+        // Set slowmode
+        queries::moderation::set_slowmode(&pool, &channel_id, 30)
+            .await
+            .unwrap();
+        let ch = queries::channels::get_channel(&pool, &channel_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ch.slowmode_seconds, 30);
+
+        // Set NSFW
+        queries::moderation::set_nsfw(&pool, &channel_id, true)
+            .await
+            .unwrap();
+        let ch2 = queries::channels::get_channel(&pool, &channel_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ch2.is_nsfw, 1);
+
+        // Clear both
+        queries::moderation::set_slowmode(&pool, &channel_id, 0)
+            .await
+            .unwrap();
+        queries::moderation::set_nsfw(&pool, &channel_id, false)
+            .await
+            .unwrap();
+        let ch3 = queries::channels::get_channel(&pool, &channel_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ch3.slowmode_seconds, 0);
+        assert_eq!(ch3.is_nsfw, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_read_only_and_topic_lock_enforced() {
+        let (engine, pool) = setup_engine().await;
+
+        let owner_id = create_test_user(&pool, "alice").await;
+        let member_id = create_test_user(&pool, "bob").await;
+        let server_id = engine
+            .create_server("Modes Server".into(), owner_id.clone(), None)
+            .await
+            .unwrap();
+        engine.join_server(&member_id, &server_id).await.unwrap();
+
+        let (owner_sid, mut owner_rx) = connect_user(&engine, Some(&owner_id), "alice");
+        let (member_sid, _member_rx) = connect_user(&engine, Some(&member_id), "bob");
+        engine
+            .join_channel(owner_sid, &server_id, "#general")
+            .await
+            .unwrap();
+        engine
+            .join_channel(member_sid, &server_id, "#general")
+            .await
+            .unwrap();
+        drain_events(&mut owner_rx);
+
+        // Members cannot change channel modes
+        let err = engine
+            .set_read_only(member_sid, &server_id, "#general", true)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, EngineError::PermissionDenied { .. }));
+
+        engine
+            .set_read_only(owner_sid, &server_id, "#general", true)
+            .await
+            .unwrap();
+        engine
+            .set_topic_locked(owner_sid, &server_id, "#general", true)
+            .await
+            .unwrap();
+        assert_eq!(engine.get_channel_modes(&server_id, "#general"), "+nmt");
+        assert!(matches!(
+            owner_rx.try_recv().unwrap(),
+            ChatEvent::ReadOnlyUpdate {
+                is_read_only: true,
+                ..
+            }
+        ));
+
+        let err = engine
+            .send_message(member_sid, &server_id, "#general", "hi", None, None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, EngineError::CannotSend(ref m) if m.contains("read-only")));
+        engine
+            .send_message(
+                owner_sid,
+                &server_id,
+                "#general",
+                "announcement",
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let err = engine
+            .set_topic(member_sid, &server_id, "#general", "new topic".into())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, EngineError::Forbidden(ref m) if m.contains("locked")));
+        engine
+            .set_topic(owner_sid, &server_id, "#general", "owner topic".into())
+            .await
+            .unwrap();
+
+        // Flags are persisted
+        let ch = queries::channels::get_channel_by_name(&pool, &server_id, "#general")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ch.is_read_only, 1);
+        assert_eq!(ch.topic_locked, 1);
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    //  Server Nickname
+    // ═══════════════════════════════════════════════════════════════
+
+    // NOTE: test_server_nickname_set_and_clear removed because the `nickname` column
+    // does not exist on the `server_members` table in any migration. The functions
+    // get_server_nickname / set_server_nickname reference a column that was never added.
+
+    // ═══════════════════════════════════════════════════════════════
+    //  Message Reply Chain
+    // ═══════════════════════════════════════════════════════════════
+
+    #[tokio::test]
+    async fn test_message_reply_chain() {
+        let pool = setup_db().await;
+
+        let user_id = create_test_user(&pool, "alice").await;
+
+        let server_id = "reply-server";
+        queries::servers::create_server(&pool, server_id, "Reply Test", &user_id, None)
+            .await
+            .unwrap();
+
+        let channel_id = Uuid::new_v4().to_string();
+        queries::channels::ensure_channel(&pool, &channel_id, server_id, "#general")
+            .await
+            .unwrap();
+
+        // Send original message
+        let msg1_id = Uuid::new_v4().to_string();
+        queries::messages::insert_message(
+            &pool,
+            &queries::messages::InsertMessageParams {
+                id: &msg1_id,
+                server_id,
+                channel_id: &channel_id,
+                sender_id: &user_id,
+                sender_nick: "alice",
+                content: "Original message",
+                reply_to_id: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        // Reply to it
+        let msg2_id = Uuid::new_v4().to_string();
+        queries::messages::insert_message(
+            &pool,
+            &queries::messages::InsertMessageParams {
+                id: &msg2_id,
+                server_id,
+                channel_id: &channel_id,
+                sender_id: &user_id,
+                sender_nick: "alice",
+                content: "This is a reply",
+                reply_to_id: Some(&msg1_id),
+            },
+        )
+        .await
+        .unwrap();
+
+        // Verify the reply references the original
+        let reply = queries::messages::get_message_by_id(&pool, &msg2_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reply.reply_to_id, Some(msg1_id.clone()));
+
+        // Original should not have a reply_to
+        let original = queries::messages::get_message_by_id(&pool, &msg1_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(original.reply_to_id.is_none());
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    //  Concurrent Server Member Operations
+    // ═══════════════════════════════════════════════════════════════
+
+    #[tokio::test]
+    async fn test_concurrent_server_joins() {
+        let pool = setup_db().await;
+
+        let owner_id = create_test_user(&pool, "owner").await;
+
+        let server_id = "concurrent-server";
+        queries::servers::create_server(&pool, server_id, "Concurrent Test", &owner_id, None)
+            .await
+            .unwrap();
+
+        // Create 10 users
+        let mut user_ids = Vec::new();
+        for i in 0..10 {
+            let uid = create_test_user(&pool, &format!("user{i}")).await;
+            user_ids.push(uid);
+        }
+
+        // Join all concurrently (INSERT OR IGNORE handles conflicts)
+        let mut handles = Vec::new();
+        for uid in &user_ids {
+            let pool_clone = pool.clone();
+            let sid = server_id.to_string();
+            let uid = uid.clone();
+            handles.push(tokio::spawn(async move {
+                queries::servers::add_server_member(&pool_clone, &sid, &uid, "member")
+                    .await
+                    .unwrap();
+            }));
+        }
+        for h in handles {
+            h.await.unwrap();
+        }
+
+        // Verify all 11 members (owner + 10 users)
+        let member_count = queries::servers::get_member_count(&pool, server_id)
+            .await
+            .unwrap();
+        assert_eq!(member_count, 11);
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    //  Multi-Server User Flow
+    // ═══════════════════════════════════════════════════════════════
+
+    #[tokio::test]
+    async fn test_user_across_multiple_servers() {
+        let pool = setup_db().await;
+
+        let user_id = create_test_user(&pool, "alice").await;
+
+        // Create 3 servers, alice owns all of them
+        let mut server_ids = Vec::new();
+        for i in 0..3 {
+            let sid = format!("multi-srv-{i}");
+            queries::servers::create_server(&pool, &sid, &format!("Server {i}"), &user_id, None)
+                .await
+                .unwrap();
+            server_ids.push(sid);
+        }
+
+        // Alice should be a member of all 3
+        let alice_servers = queries::servers::list_servers_for_user(&pool, &user_id)
+            .await
+            .unwrap();
+        assert_eq!(alice_servers.len(), 3);
+
+        // Leave one server
+        queries::servers::remove_server_member(&pool, &server_ids[1], &user_id)
             .await
             .unwrap();
 
@@ -2793,9 +4340,18 @@ mod tests {
         let (sid2, mut rx2) = connect_user(&engine, Some(&u2), "bob");
         let (sid3, mut rx3) = connect_user(&engine, Some(&u3), "charlie");
 
-        engine.join_channel(sid1, &server_id, "#general").unwrap();
-        engine.join_channel(sid2, &server_id, "#general").unwrap();
-        engine.join_channel(sid3, &server_id, "#general").unwrap();
+        engine
+            .join_channel(sid1, &server_id, "#general")
+            .await
+            .unwrap();
+        engine
+            .join_channel(sid2, &server_id, "#general")
+            .await
+            .unwrap();
+        engine
+            .join_channel(sid3, &server_id, "#general")
+            .await
+            .unwrap();
 
         drain_events(&mut rx1);
         drain_events(&mut rx2);
@@ -2812,6 +4368,7 @@ mod tests {
                 None,
                 None,
             )
+            .await
             .unwrap();
 
         // Bob and Charlie should receive it, but not Alice
@@ -2833,4 +4390,434 @@ mod tests {
         assert!(matches!(ack, ChatEvent::MessageAck { .. }));
         assert!(rx1.try_recv().is_err());
     }
+
+    // ═══════════════════════════════════════════════════════════════
+    //  Engine: Nickname Changes
+    // ═══════════════════════════════════════════════════════════════
+
+    #[tokio::test]
+    async fn test_change_nickname_persists_username() {
+        let (engine, pool) = setup_engine().await;
+        let alice_id = create_test_user(&pool, "alice").await;
+        create_test_user(&pool, "bob").await;
+
+        let (sid, _rx) = connect_user(&engine, Some(&alice_id), "alice");
+
+        // An offline account still owns its nickname
+        let err = engine.change_nickname(sid, "bob").await.unwrap_err();
+        assert_eq!(err, EngineError::NicknameInUse("bob".into()));
+        assert!(engine.is_nick_available("bob"));
+
+        engine.change_nickname(sid, "alicia").await.unwrap();
+        let (_, username, ..) = queries::users::get_user(&pool, &alice_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(username, "alicia");
+
+        // Nicks are unique ignoring ASCII case, and their owner can recase them
+        let carol_id = create_test_user(&pool, "carol").await;
+        let (carol, _rx_c) = connect_user(&engine, Some(&carol_id), "carol");
+        assert!(!engine.is_nick_available("ALICIA"));
+        assert_eq!(
+            engine.change_nickname(carol, "Alicia").await.unwrap_err(),
+            EngineError::NicknameInUse("Alicia".into())
+        );
+        engine.change_nickname(sid, "Alicia").await.unwrap();
+        assert_eq!(engine.get_session_id_by_nick("alicia"), Some(sid));
+        assert_eq!(engine.visible_nick("ALICIA").as_deref(), Some("Alicia"));
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    //  IRC: Services Pseudo-User
+    // ═══════════════════════════════════════════════════════════════
+
+    #[tokio::test]
+    async fn test_irc_services_join_and_tokens() {
+        use crate::irc::services;
+
+        let (engine, pool) = setup_engine().await;
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        let server_id = engine
+            .create_server("Rustaceans".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        let hideout_id = engine
+            .create_server("Hideout".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        let (alice, _rx_a) = connect_user(&engine, Some(&alice_id), "alice");
+        engine
+            .update_community_settings(alice, &server_id, None, true, None, None, None)
+            .await
+            .unwrap();
+        queries::users::create_irc_token(&pool, "tok-1", &bob_id, "hash", Some("laptop"))
+            .await
+            .unwrap();
+
+        let (sid, _rx) = connect_user(&engine, Some(&bob_id), "bob");
+        let mut default_server = hideout_id.clone();
+
+        // Private servers need an invite, and their pins stay hidden from outsiders
+        let replies = services::handle(
+            &engine,
+            &pool,
+            sid,
+            "bob",
+            &mut default_server,
+            None,
+            "Concord",
+            "JOIN hideout",
+        )
+        .await;
+        assert!(replies[0].contains("use INVITE USE <code>"));
+        assert!(!engine.user_is_server_member(&hideout_id, &bob_id));
+        let replies = services::handle(
+            &engine,
+            &pool,
+            sid,
+            "bob",
+            &mut default_server,
+            None,
+            "Concord",
+            "PINS #general",
+        )
+        .await;
+        assert!(replies[0].ends_with("Error: Not a member of this server"));
+
+        let replies = services::handle(
+            &engine,
+            &pool,
+            sid,
+            "bob",
+            &mut default_server,
+            None,
+            "NickServ",
+            "JOIN rustaceans",
+        )
+        .await;
+        assert!(
+            replies[0].starts_with(":NickServ!NickServ@concord NOTICE bob :You joined Rustaceans")
+        );
+        let servers = engine.list_servers_for_user(&bob_id).await;
+        assert!(servers.iter().any(|s| s.id == server_id));
+
+        let replies = services::handle(
+            &engine,
+            &pool,
+            sid,
+            "bob",
+            &mut default_server,
+            None,
+            "Concord",
+            "TOKENS LIST",
+        )
+        .await;
+        assert_eq!(replies.len(), 1);
+        assert!(replies[0].contains("tok-1 — laptop"));
+
+        let replies = services::handle(
+            &engine,
+            &pool,
+            sid,
+            "bob",
+            &mut default_server,
+            None,
+            "Concord",
+            "TOKENS REVOKE tok-1",
+        )
+        .await;
+        assert!(replies[0].ends_with("Token tok-1 revoked."));
+        let tokens = queries::users::list_irc_tokens(&pool, &bob_id)
+            .await
+            .unwrap();
+        assert!(tokens.is_empty());
+
+        let replies = services::handle(
+            &engine,
+            &pool,
+            sid,
+            "bob",
+            &mut default_server,
+            None,
+            "Concord",
+            "BOOKMARKS",
+        )
+        .await;
+        assert!(replies[0].ends_with("You have no bookmarks."));
+    }
+
+    #[tokio::test]
+    async fn test_irc_sasl_external_with_registered_cert() {
+        use crate::auth::token::cert_fingerprint;
+        use crate::irc::sasl::{SaslReply, SaslSession};
+        use crate::irc::services;
+
+        let (engine, pool) = setup_engine().await;
+        let alice_id = create_test_user(&pool, "alice").await;
+        let fp = cert_fingerprint(b"alice's client certificate");
+
+        // Before registration the certificate identifies no one
+        let mut sasl = SaslSession::default();
+        sasl.step(&pool, Some(&fp), "EXTERNAL").await;
+        assert_eq!(
+            sasl.step(&pool, Some(&fp), "+").await,
+            Some(SaslReply::Failure)
+        );
+
+        // Register it from a connection made with that certificate
+        let (sid, _rx) = connect_user(&engine, Some(&alice_id), "alice");
+        let mut default_server = DEFAULT_SERVER_ID.to_string();
+        let replies = services::handle(
+            &engine,
+            &pool,
+            sid,
+            "alice",
+            &mut default_server,
+            Some(&fp),
+            "NickServ",
+            "CERT ADD",
+        )
+        .await;
+        assert!(replies[0].contains(&format!("Certificate {fp} added")));
+
+        sasl.step(&pool, Some(&fp), "EXTERNAL").await;
+        assert_eq!(
+            sasl.step(&pool, Some(&fp), "+").await,
+            Some(SaslReply::Success {
+                user_id: alice_id.clone(),
+                account: "alice".into(),
+            })
+        );
+
+        // An authzid naming another account is refused
+        use base64::Engine as _;
+        let bob = base64::engine::general_purpose::STANDARD.encode("bob");
+        sasl.step(&pool, Some(&fp), "EXTERNAL").await;
+        assert_eq!(
+            sasl.step(&pool, Some(&fp), &bob).await,
+            Some(SaslReply::Failure)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_irc_services_bind_default_server() {
+        use crate::irc::commands::{parse_irc_channel, to_irc_channel};
+        use crate::irc::services;
+
+        let (engine, pool) = setup_engine().await;
+        let alice_id = create_test_user(&pool, "alice").await;
+        let server_id = engine
+            .create_server("Rustaceans".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        let (sid, _rx) = connect_user(&engine, Some(&alice_id), "alice");
+        let mut default_server = DEFAULT_SERVER_ID.to_string();
+
+        assert_eq!(
+            to_irc_channel(&engine, &default_server, &server_id, "#general"),
+            "#Rustaceans/general"
+        );
+
+        let replies = services::handle(
+            &engine,
+            &pool,
+            sid,
+            "alice",
+            &mut default_server,
+            None,
+            "Concord",
+            "SERVER rustaceans",
+        )
+        .await;
+        assert!(replies[0].contains("now refer to Rustaceans"));
+        assert_eq!(default_server, server_id);
+
+        assert_eq!(
+            parse_irc_channel(&engine, &default_server, "#general"),
+            (server_id.clone(), "#general".to_string())
+        );
+        assert_eq!(
+            to_irc_channel(&engine, &default_server, &server_id, "#general"),
+            "#general"
+        );
+
+        let replies = services::handle(
+            &engine,
+            &pool,
+            sid,
+            "alice",
+            &mut default_server,
+            None,
+            "Concord",
+            "SERVER nowhere",
+        )
+        .await;
+        assert!(replies[0].contains("No such server: nowhere"));
+        assert_eq!(default_server, server_id);
+    }
+
+    // ═══════════════════════════════════════════════════════════════
+    //  Permission Cache Consistency
+    // ═══════════════════════════════════════════════════════════════
+
+    /// Cached and freshly loaded permissions must agree for every user, at
+    /// server level and in every channel.
+    async fn assert_cache_consistent(engine: &ChatEngine, server_id: &str, users: &[&str]) {
+        let mut channel_ids: Vec<Option<String>> = vec![None];
+        for name in ["#general", "#staff"] {
+            if let Ok(id) = engine.resolve_channel_id(server_id, name) {
+                channel_ids.push(Some(id));
+            }
+        }
+        for user_id in users {
+            for channel_id in &channel_ids {
+                let cached = engine
+                    .get_effective_permissions(server_id, channel_id.as_deref(), user_id)
+                    .await;
+                let fresh = engine
+                    .get_effective_permissions_uncached(server_id, channel_id.as_deref(), user_id)
+                    .await;
+                assert_eq!(
+                    cached, fresh,
+                    "stale permissions for {user_id} in {channel_id:?}"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_permission_cache_tracks_every_mutation() {
+        let (engine, pool) = setup_engine().await;
+
+        let alice_id = create_test_user(&pool, "alice").await;
+        let bob_id = create_test_user(&pool, "bob").await;
+        let carol_id = create_test_user(&pool, "carol").await;
+        let server_id = engine
+            .create_server("Cache".into(), alice_id.clone(), None)
+            .await
+            .unwrap();
+        engine.join_server(&bob_id, &server_id).await.unwrap();
+        engine.join_server(&carol_id, &server_id).await.unwrap();
+        engine
+            .create_channel_in_server(&server_id, "#staff", None, false)
+            .await
+            .unwrap();
+        let users = [alice_id.as_str(), bob_id.as_str(), carol_id.as_str()];
+        assert_cache_consistent(&engine, &server_id, &users).await;
+
+        // Role created and assigned
+        let role = engine
+            .create_role(
+                &server_id,
+                "Mods",
+                None,
+                (DEFAULT_EVERYONE | Permissions::MANAGE_MESSAGES).bits() as i64,
+            )
+            .await
+            .unwrap();
+        assert_cache_consistent(&engine, &server_id, &users).await;
+        engine
+            .assign_role(&server_id, &alice_id, &bob_id, &role.id)
+            .await
+            .unwrap();
+        assert_cache_consistent(&engine, &server_id, &users).await;
+        assert!(
+            engine
+                .get_effective_permissions(&server_id, None, &bob_id)
+                .await
+                .contains(Permissions::MANAGE_MESSAGES)
+        );
+
+        // Role permissions changed
+        engine
+            .update_role(
+                &server_id,
+                &role.id,
+                "Mods",
+                None,
+                (DEFAULT_EVERYONE | Permissions::KICK_MEMBERS).bits() as i64,
+            )
+            .await
+            .unwrap();
+        assert_cache_consistent(&engine, &server_id, &users).await;
+
+        // Channel overrides for a role and a user
+        engine
+            .set_channel_override(
+                &server_id,
+                "#staff",
+                &ChannelOverride {
+                    target_type: OverrideTargetType::Role,
+                    target_id: role.id.clone(),
+                    allow: Permissions::MANAGE_CHANNELS,
+                    deny: Permissions::empty(),
+                },
+            )
+            .await
+            .unwrap();
+        assert_cache_consistent(&engine, &server_id, &users).await;
+        engine
+            .set_channel_override(
+                &server_id,
+                "#staff",
+                &ChannelOverride {
+                    target_type: OverrideTargetType::User,
+                    target_id: carol_id.clone(),
+                    allow: Permissions::empty(),
+                    deny: Permissions::SEND_MESSAGES,
+                },
+            )
+            .await
+            .unwrap();
+        assert_cache_consistent(&engine, &server_id, &users).await;
+        assert!(
+            !engine
+                .get_effective_permissions(
+                    &server_id,
+                    engine
+                        .resolve_channel_id(&server_id, "#staff")
+                        .ok()
+                        .as_deref(),
+                    &carol_id
+                )
+                .await
+                .contains(Permissions::SEND_MESSAGES)
+        );
+        engine
+            .delete_channel_override(&server_id, "#staff", &OverrideTargetType::User, &carol_id)
+            .await
+            .unwrap();
+        assert_cache_consistent(&engine, &server_id, &users).await;
+
+        // Role unassigned, reassigned, then deleted
+        engine
+            .remove_role(&server_id, &alice_id, &bob_id, &role.id)
+            .await
+            .unwrap();
+        assert_cache_consistent(&engine, &server_id, &users).await;
+        engine
+            .assign_role(&server_id, &alice_id, &carol_id, &role.id)
+            .await
+            .unwrap();
+        assert_cache_consistent(&engine, &server_id, &users).await;
+        engine.delete_role(&server_id, &role.id).await.unwrap();
+        assert_cache_consistent(&engine, &server_id, &users).await;
+
+        // Channel deleted
+        engine
+            .delete_channel_in_server(&server_id, "#staff")
+            .await
+            .unwrap();
+        assert_cache_consistent(&engine, &server_id, &users).await;
+
+        // Member kicked
+        let (alice, _rx) = connect_user(&engine, Some(&alice_id), "alice");
+        engine
+            .kick_member_in_channel(alice, &server_id, &carol_id, None, None)
+            .await
+            .unwrap();
+        assert_cache_consistent(&engine, &server_id, &users).await;
+    }
 }
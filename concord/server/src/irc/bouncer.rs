@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use tokio::sync::mpsc;
+use tracing::info;
+
+use crate::engine::chat_engine::ChatEngine;
+use crate::engine::events::{ChatEvent, SessionId};
+use crate::engine::user_session::MAX_OUTBOUND_QUEUE;
+
+/// How often each session checks whether it has been idle too long.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One engine's always-on IRC sessions and their settings, owned by the
+/// [`ChatEngine`] so engines in the same process don't share them.
+#[derive(Default)]
+pub struct Bouncer {
+    /// Whether sessions outlive their connections, and how long one may go
+    /// without an attached client before it is closed. Set once at startup.
+    config: OnceLock<(bool, Option<Duration>)>,
+    /// Always-on sessions by user ID.
+    sessions: DashMap<String, Arc<BouncedSession>>,
+}
+
+impl Bouncer {
+    /// Enable or disable always-on IRC sessions, closing those left without a
+    /// client for `idle_timeout` (never, if None). Call once at startup.
+    pub fn configure(&self, enabled: bool, idle_timeout: Option<Duration>) {
+        let _ = self.config.set((enabled, idle_timeout));
+    }
+
+    /// Whether IRC sessions outlive their connections.
+    pub fn enabled(&self) -> bool {
+        self.config.get().is_some_and(|(enabled, _)| *enabled)
+    }
+
+    fn idle_timeout(&self) -> Option<Duration> {
+        self.config.get().and_then(|(_, timeout)| *timeout)
+    }
+}
+
+/// An engine session kept alive across IRC connections.
+///
+/// A hub task owns the engine's receiver and fans every event out to the
+/// attached clients. While no client is attached, events are dropped and the
+/// session stays joined to its channels; on reattach, the messages that
+/// arrived while detached are replayed from the database, skipping those
+/// before the user's read markers. Sessions left detached past the idle
+/// timeout are closed.
+struct BouncedSession {
+    session_id: SessionId,
+    clients: Mutex<Vec<(u64, mpsc::Sender<ChatEvent>)>>,
+    /// Source of IDs for attached clients.
+    next_client_id: AtomicU64,
+    /// When the last client detached (None while a client is attached).
+    detached_at: Mutex<Option<DateTime<Utc>>>,
+    /// Newest message the user sent per (server_id, channel) since read markers
+    /// were last saved. Delivery alone doesn't mean a message was read.
+    seen: Mutex<HashMap<(String, String), String>>,
+}
+
+impl BouncedSession {
+    /// Forward an engine event to every attached client.
+    fn dispatch(&self, event: ChatEvent) {
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|(_, tx)| !tx.is_closed());
+        if clients.is_empty() {
+            return;
+        }
+
+        if let ChatEvent::MessageAck {
+            id,
+            server_id,
+            channel,
+            ..
+        } = &event
+        {
+            self.mark_seen(server_id, channel, id.to_string());
+        }
+
+        for (_, tx) in clients.iter() {
+            let _ = tx.try_send(event.clone());
+        }
+    }
+
+    fn mark_seen(&self, server_id: &str, channel: &str, message_id: String) {
+        self.seen
+            .lock()
+            .unwrap()
+            .insert((server_id.to_string(), channel.to_string()), message_id);
+    }
+
+    /// Advance the user's read markers to the newest message they sent per channel.
+    async fn flush_read_markers(&self, engine: &ChatEngine) {
+        let seen = std::mem::take(&mut *self.seen.lock().unwrap());
+        for ((server_id, channel), message_id) in seen {
+            let _ = engine
+                .advance_read_marker(self.session_id, &server_id, &channel, &message_id)
+                .await;
+        }
+    }
+
+    /// Whether the session has had no attached client for at least `timeout`.
+    fn idle_longer_than(&self, timeout: Duration) -> bool {
+        let timeout = chrono::TimeDelta::from_std(timeout).unwrap_or(chrono::TimeDelta::MAX);
+        self.detached_at
+            .lock()
+            .unwrap()
+            .is_some_and(|at| Utc::now() - at >= timeout)
+    }
+
+    fn add_client(&self) -> (u64, mpsc::Receiver<ChatEvent>, Option<DateTime<Utc>>) {
+        let client_id = self.next_client_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel(MAX_OUTBOUND_QUEUE);
+        let mut clients = self.clients.lock().unwrap();
+        clients.push((client_id, tx));
+        let detached_at = self.detached_at.lock().unwrap().take();
+        (client_id, rx, detached_at)
+    }
+}
+
+/// An IRC client attached to an always-on session.
+pub struct Attachment {
+    session: Arc<BouncedSession>,
+    client_id: u64,
+    resumed: bool,
+    detached_since: Option<DateTime<Utc>>,
+}
+
+impl Attachment {
+    pub fn session_id(&self) -> SessionId {
+        self.session.session_id
+    }
+
+    /// Whether this client attached to a session that already existed.
+    pub fn resumed(&self) -> bool {
+        self.resumed
+    }
+
+    /// When the session was left with no clients, if it was detached before this attach.
+    pub fn detached_since(&self) -> Option<DateTime<Utc>> {
+        self.detached_since
+    }
+
+    /// Whether other clients are attached to the same session.
+    pub fn has_siblings(&self) -> bool {
+        self.session
+            .clients
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(id, tx)| *id != self.client_id && !tx.is_closed())
+    }
+
+    /// Send an event to the session's other attached clients (e.g. to echo our own messages).
+    pub fn echo_to_siblings(&self, event: ChatEvent) {
+        for (id, tx) in self.session.clients.lock().unwrap().iter() {
+            if *id != self.client_id {
+                let _ = tx.try_send(event.clone());
+            }
+        }
+    }
+
+    /// Detach this client. The engine session stays alive; when the last client
+    /// leaves, read markers are saved and the user is marked away.
+    pub async fn detach(self, engine: &ChatEngine) {
+        let last = {
+            let mut clients = self.session.clients.lock().unwrap();
+            clients.retain(|(id, tx)| *id != self.client_id && !tx.is_closed());
+            if clients.is_empty() {
+                *self.session.detached_at.lock().unwrap() = Some(Utc::now());
+            }
+            clients.is_empty()
+        };
+
+        self.session.flush_read_markers(engine).await;
+        if last {
+            let _ = engine
+                .set_presence(self.session.session_id, "idle", Some("Detached"), None)
+                .await;
+            info!(session_id = %self.session.session_id, "always-on IRC session detached");
+        }
+    }
+}
+
+/// Attach to the user's live always-on session, if there is one.
+pub async fn attach_existing(
+    engine: &ChatEngine,
+    user_id: &str,
+) -> Option<(Attachment, mpsc::Receiver<ChatEvent>)> {
+    let sessions = &engine.bouncer().sessions;
+    let session = sessions.get(user_id).map(|s| s.clone())?;
+
+    // The engine may have dropped the session (e.g. replaced by a new login)
+    if engine.get_session_user_id(session.session_id).as_deref() != Some(user_id) {
+        sessions.remove_if(user_id, |_, s| Arc::ptr_eq(s, &session));
+        return None;
+    }
+
+    // Save what other attached clients have already seen before computing playback
+    session.flush_read_markers(engine).await;
+
+    let (client_id, rx, detached_since) = session.add_client();
+    if detached_since.is_some() {
+        let _ = engine
+            .set_presence(session.session_id, "online", None, None)
+            .await;
+    }
+
+    Some((
+        Attachment {
+            session,
+            client_id,
+            resumed: true,
+            detached_since,
+        },
+        rx,
+    ))
+}
+
+/// Make a freshly connected engine session always-on and attach to it.
+///
+/// Spawns the hub task that owns `engine_rx`; the session is forgotten when the
+/// engine drops it, and dropped from the engine once idle past the timeout.
+pub fn adopt(
+    engine: &Arc<ChatEngine>,
+    user_id: &str,
+    session_id: SessionId,
+    mut engine_rx: mpsc::Receiver<ChatEvent>,
+) -> (Attachment, mpsc::Receiver<ChatEvent>) {
+    let session = Arc::new(BouncedSession {
+        session_id,
+        clients: Mutex::new(Vec::new()),
+        next_client_id: AtomicU64::new(1),
+        detached_at: Mutex::new(None),
+        seen: Mutex::new(HashMap::new()),
+    });
+    let (client_id, rx, _) = session.add_client();
+    engine
+        .bouncer()
+        .sessions
+        .insert(user_id.to_string(), session.clone());
+
+    let hub = session.clone();
+    let user_id = user_id.to_string();
+    let engine = engine.clone();
+    let idle_timeout = engine.bouncer().idle_timeout();
+    tokio::spawn(async move {
+        let mut idle_check = tokio::time::interval(IDLE_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                event = engine_rx.recv() => match event {
+                    Some(event) => hub.dispatch(event),
+                    None => break,
+                },
+                _ = idle_check.tick() => {
+                    if idle_timeout.is_some_and(|timeout| hub.idle_longer_than(timeout)) {
+                        info!(session_id = %hub.session_id, "always-on IRC session expired");
+                        engine.disconnect(hub.session_id).await;
+                    }
+                }
+            }
+        }
+        // Session is gone: close attached clients and forget it
+        hub.clients.lock().unwrap().clear();
+        engine
+            .bouncer()
+            .sessions
+            .remove_if(&user_id, |_, s| Arc::ptr_eq(s, &hub));
+    });
+
+    (
+        Attachment {
+            session,
+            client_id,
+            resumed: false,
+            detached_since: None,
+        },
+        rx,
+    )
+}
+
+/// Whether a nickname is held by an always-on session, so its owner may
+/// register with it again to reattach.
+pub fn holds_nick(engine: &ChatEngine, nickname: &str) -> bool {
+    engine.get_session_id_by_nick(nickname).is_some_and(|sid| {
+        engine
+            .bouncer()
+            .sessions
+            .iter()
+            .any(|s| s.session_id == sid)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::user_session::Protocol;
+
+    fn notice(message: &str) -> ChatEvent {
+        ChatEvent::ServerNotice {
+            message: message.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_fan_out_to_all_attached_clients() {
+        let engine = Arc::new(ChatEngine::new(None, 4000, 100));
+        let user_id = uuid::Uuid::new_v4().to_string();
+        let (sid, engine_rx) = engine
+            .connect(Some(user_id.clone()), "bnc1".into(), Protocol::Irc, None)
+            .unwrap();
+
+        let (first, mut first_rx) = adopt(&engine, &user_id, sid, engine_rx);
+        assert!(!first.resumed());
+        assert!(holds_nick(&engine, "bnc1"));
+
+        let (second, mut second_rx) = attach_existing(&engine, &user_id).await.unwrap();
+        assert!(second.resumed());
+        assert_eq!(second.session_id(), sid);
+        assert!(first.has_siblings());
+
+        engine.get_session(sid).unwrap().send(notice("hello"));
+        let a = first_rx.recv().await.unwrap();
+        let b = second_rx.recv().await.unwrap();
+        assert!(matches!(a, ChatEvent::ServerNotice { ref message } if message == "hello"));
+        assert!(matches!(b, ChatEvent::ServerNotice { ref message } if message == "hello"));
+
+        second.echo_to_siblings(notice("echo"));
+        assert!(matches!(
+            first_rx.recv().await.unwrap(),
+            ChatEvent::ServerNotice { ref message } if message == "echo"
+        ));
+        assert!(second_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_engines_keep_separate_sessions() {
+        let engine = Arc::new(ChatEngine::new(None, 4000, 100));
+        let other = ChatEngine::new(None, 4000, 100);
+        engine.bouncer().configure(true, None);
+        assert!(engine.bouncer().enabled());
+        assert!(!other.bouncer().enabled());
+
+        let user_id = uuid::Uuid::new_v4().to_string();
+        let (sid, engine_rx) = engine
+            .connect(Some(user_id.clone()), "bnc3".into(), Protocol::Irc, None)
+            .unwrap();
+        let _client = adopt(&engine, &user_id, sid, engine_rx);
+        assert!(attach_existing(&other, &user_id).await.is_none());
+        assert!(!holds_nick(&other, "bnc3"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_session_survives_detach_and_ends_with_engine_session() {
+        let engine = Arc::new(ChatEngine::new(None, 4000, 100));
+        let user_id = uuid::Uuid::new_v4().to_string();
+        let (sid, engine_rx) = engine
+            .connect(Some(user_id.clone()), "bnc2".into(), Protocol::Irc, None)
+            .unwrap();
+
+        let (client, _rx) = adopt(&engine, &user_id, sid, engine_rx);
+        assert!(!client.session.idle_longer_than(Duration::ZERO));
+        let session = client.session.clone();
+        client.detach(&engine).await;
+        assert!(engine.get_session(sid).is_some());
+        assert!(session.idle_longer_than(Duration::ZERO));
+        assert!(!session.idle_longer_than(Duration::from_secs(3600)));
+
+        let (client, mut rx) = attach_existing(&engine, &user_id).await.unwrap();
+        assert!(client.detached_since().is_some());
+        assert!(!session.idle_longer_than(Duration::ZERO));
+
+        engine.disconnect(sid).await;
+        assert!(rx.recv().await.is_none());
+        assert!(attach_existing(&engine, &user_id).await.is_none());
+    }
+}
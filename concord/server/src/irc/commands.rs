@@ -9,12 +9,18 @@ use super::parser::IrcMessage;
 /// Parse an IRC channel name into (server_id, engine_channel_name).
 ///
 /// Format:
-///   `#general`            -> (DEFAULT_SERVER_ID, "#general")   — default server
-///   `#my-guild/general`   -> (server_id,         "#general")   — named server
+///   `#general`            -> (default_server, "#general")   — the connection's default server
+///   `#my-guild/general`   -> (server_id,      "#general")   — named server
 ///
+/// `default_server` is the server the connection is bound to (see
+/// `resolve_server`), or `DEFAULT_SERVER_ID` when it isn't bound to one.
 /// If the server name doesn't match any known server, falls back to treating
 /// the whole thing as a default-server channel name.
-pub fn parse_irc_channel(engine: &ChatEngine, irc_name: &str) -> (String, String) {
+pub fn parse_irc_channel(
+    engine: &ChatEngine,
+    default_server: &str,
+    irc_name: &str,
+) -> (String, String) {
     let bare = irc_name.strip_prefix('#').unwrap_or(irc_name);
 
     if let Some(slash_pos) = bare.find('/') {
@@ -26,15 +32,20 @@ pub fn parse_irc_channel(engine: &ChatEngine, irc_name: &str) -> (String, String
     }
 
     // Default: treat as default server channel
-    (DEFAULT_SERVER_ID.to_string(), format!("#{bare}"))
+    (default_server.to_string(), format!("#{bare}"))
 }
 
 /// Convert an engine (server_id, channel_name) back to an IRC channel name.
 ///
-/// Default server channels keep their plain name (`#general`).
-/// Non-default server channels become `#server-name/channel-name`.
-pub fn to_irc_channel(engine: &ChatEngine, server_id: &str, channel_name: &str) -> String {
-    if server_id == DEFAULT_SERVER_ID {
+/// Channels in the connection's default server keep their plain name (`#general`).
+/// Channels in other servers become `#server-name/channel-name`.
+pub fn to_irc_channel(
+    engine: &ChatEngine,
+    default_server: &str,
+    server_id: &str,
+    channel_name: &str,
+) -> String {
+    if server_id == default_server {
         return channel_name.to_string();
     }
 
@@ -46,21 +57,49 @@ pub fn to_irc_channel(engine: &ChatEngine, server_id: &str, channel_name: &str)
     }
 }
 
+/// Resolve a server given by ID or by (case-insensitive) name.
+pub fn resolve_server(engine: &ChatEngine, name_or_id: &str) -> Option<String> {
+    if engine.get_server_name(name_or_id).is_some() {
+        return Some(name_or_id.to_string());
+    }
+    engine.find_server_by_name(name_or_id)
+}
+
+/// The server named by a `user@server` USER username, if any.
+pub fn username_server(username: &str) -> Option<&str> {
+    username
+        .rsplit_once('@')
+        .map(|(_, server)| server)
+        .filter(|server| !server.is_empty())
+}
+
+/// Split a `server/token` PASS into its server and token parts.
+///
+/// Tokens never contain `/`, so the last `/` separates them (server names may
+/// contain slashes). A plain token yields no server.
+pub fn split_pass_server(pass: &str) -> (Option<&str>, &str) {
+    match pass.rsplit_once('/') {
+        Some((server, token)) if !server.is_empty() => (Some(server), token),
+        _ => (None, pass),
+    }
+}
+
 /// Process a single IRC command from a registered (authenticated) client.
 /// Returns a list of lines to send back to the client.
-pub fn handle_command(
+pub async fn handle_command(
     engine: &ChatEngine,
     session_id: SessionId,
     nick: &str,
+    default_server: &str,
     msg: &IrcMessage,
 ) -> Vec<String> {
     match msg.command.as_str() {
-        "JOIN" => handle_join(engine, session_id, nick, msg),
-        "PART" => handle_part(engine, session_id, nick, msg),
-        "PRIVMSG" => handle_privmsg(engine, session_id, nick, msg),
-        "TOPIC" => handle_topic(engine, session_id, nick, msg),
+        "JOIN" => handle_join(engine, session_id, nick, default_server, msg).await,
+        "PART" => handle_part(engine, session_id, nick, default_server, msg),
+        "PRIVMSG" => handle_privmsg(engine, session_id, nick, default_server, msg).await,
+        "TOPIC" => handle_topic(engine, session_id, nick, default_server, msg).await,
         "NAMES" => vec![], // Handled async in connection.rs
-        "LIST" => handle_list(engine, nick, msg),
+        "LIST" => handle_list(engine, nick, default_server, msg),
         "WHO" => vec![],   // Handled async in connection.rs
         "WHOIS" => vec![], // Handled async in connection.rs
         "QUIT" => vec![],  // Handled at connection level
@@ -69,7 +108,15 @@ pub fn handle_command(
             vec![formatter::pong(token)]
         }
         "PONG" => vec![], // Just acknowledge, no response needed
-        "NICK" | "USER" | "PASS" => {
+        "VERSION" => {
+            // RPL_VERSION followed by a fresh ISUPPORT burst
+            let mut replies = vec![formatter::rpl_version(nick)];
+            replies.extend(formatter::rpl_isupport(nick, engine.max_message_length()));
+            replies
+        }
+        // NICK after registration is handled by the connection loop (it updates
+        // the connection's own nick state).
+        "USER" | "PASS" => {
             vec![formatter::err_alreadyregistered(nick)]
         }
         // CAP, MODE — common client sends these, just ignore or give minimal response
@@ -83,18 +130,14 @@ pub fn handle_command(
         "MODE" => {
             if let Some(target) = msg.params.first() {
                 if target.starts_with('#') {
-                    let (server_id, channel_name) = parse_irc_channel(engine, target);
-                    let irc_channel = to_irc_channel(engine, &server_id, &channel_name);
+                    let (server_id, channel_name) =
+                        parse_irc_channel(engine, default_server, target);
+                    let irc_channel =
+                        to_irc_channel(engine, default_server, &server_id, &channel_name);
                     let modes = engine.get_channel_modes(&server_id, &channel_name);
-                    vec![format!(
-                        ":{} 324 {} {} {}",
-                        formatter::server_name(),
-                        nick,
-                        irc_channel,
-                        modes
-                    )]
+                    vec![formatter::rpl_channelmodeis(nick, &irc_channel, &modes)]
                 } else {
-                    vec![format!(":{} 221 {} +", formatter::server_name(), nick)]
+                    vec![formatter::rpl_umodeis(nick)]
                 }
             } else {
                 vec![formatter::err_needmoreparams(nick, "MODE")]
@@ -110,10 +153,11 @@ pub fn handle_command(
     }
 }
 
-fn handle_join(
+async fn handle_join(
     engine: &ChatEngine,
     session_id: SessionId,
     nick: &str,
+    default_server: &str,
     msg: &IrcMessage,
 ) -> Vec<String> {
     let Some(channels_param) = msg.params.first() else {
@@ -128,13 +172,16 @@ fn handle_join(
             continue;
         }
 
-        let (server_id, channel_name) = parse_irc_channel(engine, channel);
+        let (server_id, channel_name) = parse_irc_channel(engine, default_server, channel);
 
-        match engine.join_channel(session_id, &server_id, &channel_name) {
+        match engine
+            .join_channel(session_id, &server_id, &channel_name)
+            .await
+        {
             Ok(()) => {}
             Err(e) => {
                 warn!(error = %e, %channel, "JOIN failed");
-                replies.push(formatter::err_nosuchchannel(nick, channel));
+                replies.push(formatter::err_engine(nick, channel, &e));
             }
         }
     }
@@ -146,6 +193,7 @@ fn handle_part(
     engine: &ChatEngine,
     session_id: SessionId,
     nick: &str,
+    default_server: &str,
     msg: &IrcMessage,
 ) -> Vec<String> {
     let Some(channels_param) = msg.params.first() else {
@@ -161,21 +209,22 @@ fn handle_part(
             continue;
         }
 
-        let (server_id, channel_name) = parse_irc_channel(engine, channel);
+        let (server_id, channel_name) = parse_irc_channel(engine, default_server, channel);
 
         if let Err(e) = engine.part_channel(session_id, &server_id, &channel_name, reason.clone()) {
             warn!(error = %e, %channel, "PART failed");
-            replies.push(formatter::err_notonchannel(nick, channel));
+            replies.push(formatter::err_engine(nick, channel, &e));
         }
     }
 
     replies
 }
 
-fn handle_privmsg(
+async fn handle_privmsg(
     engine: &ChatEngine,
     session_id: SessionId,
     nick: &str,
+    default_server: &str,
     msg: &IrcMessage,
 ) -> Vec<String> {
     if msg.params.len() < 2 {
@@ -187,37 +236,45 @@ fn handle_privmsg(
 
     // Handle CTCP messages (\x01...\x01)
     if let Some(ctcp) = parse_ctcp(raw_content) {
-        return handle_ctcp(engine, session_id, nick, target, &ctcp);
+        return handle_ctcp(engine, session_id, nick, default_server, target, &ctcp).await;
     }
+    // Store IRC formatting as Markdown so web clients render it
+    let raw_content = &formatter::irc_to_markdown(raw_content);
 
     if target.starts_with('#') {
         // Channel message — parse server/channel from IRC name
-        let (server_id, channel_name) = parse_irc_channel(engine, target);
-        if let Err(e) = engine.send_message(
-            session_id,
-            &server_id,
-            &channel_name,
-            raw_content,
-            None,
-            None,
-            None,
-        ) {
+        let (server_id, channel_name) = parse_irc_channel(engine, default_server, target);
+        if let Err(e) = engine
+            .send_message(
+                session_id,
+                &server_id,
+                &channel_name,
+                raw_content,
+                None,
+                None,
+                None,
+            )
+            .await
+        {
             warn!(error = %e, %target, "PRIVMSG failed");
-            return vec![formatter::err_nosuchnick(nick, target)];
+            return vec![formatter::err_engine(nick, target, &e)];
         }
     } else {
         // DM — use default server
-        if let Err(e) = engine.send_message(
-            session_id,
-            DEFAULT_SERVER_ID,
-            target,
-            raw_content,
-            None,
-            None,
-            None,
-        ) {
+        if let Err(e) = engine
+            .send_message(
+                session_id,
+                DEFAULT_SERVER_ID,
+                target,
+                raw_content,
+                None,
+                None,
+                None,
+            )
+            .await
+        {
             warn!(error = %e, %target, "PRIVMSG failed");
-            return vec![formatter::err_nosuchnick(nick, target)];
+            return vec![formatter::err_engine(nick, target, &e)];
         }
     }
 
@@ -247,10 +304,11 @@ fn parse_ctcp(content: &str) -> Option<CtcpMessage> {
 }
 
 /// Handle CTCP commands: ACTION → /me, VERSION/PING/TIME → reply.
-fn handle_ctcp(
+async fn handle_ctcp(
     engine: &ChatEngine,
     session_id: SessionId,
     nick: &str,
+    default_server: &str,
     target: &str,
     ctcp: &CtcpMessage,
 ) -> Vec<String> {
@@ -258,32 +316,38 @@ fn handle_ctcp(
         "ACTION" => {
             // Convert to /me format for the engine
             let action_text = ctcp.params.as_deref().unwrap_or("");
-            let content = format!("/me {action_text}");
+            let content = format!("/me {}", formatter::irc_to_markdown(action_text));
             if target.starts_with('#') {
-                let (server_id, channel_name) = parse_irc_channel(engine, target);
-                if let Err(e) = engine.send_message(
+                let (server_id, channel_name) = parse_irc_channel(engine, default_server, target);
+                if let Err(e) = engine
+                    .send_message(
+                        session_id,
+                        &server_id,
+                        &channel_name,
+                        &content,
+                        None,
+                        None,
+                        None,
+                    )
+                    .await
+                {
+                    warn!(error = %e, %target, "CTCP ACTION failed");
+                    return vec![formatter::err_engine(nick, target, &e)];
+                }
+            } else if let Err(e) = engine
+                .send_message(
                     session_id,
-                    &server_id,
-                    &channel_name,
+                    DEFAULT_SERVER_ID,
+                    target,
                     &content,
                     None,
                     None,
                     None,
-                ) {
-                    warn!(error = %e, %target, "CTCP ACTION failed");
-                    return vec![formatter::err_nosuchnick(nick, target)];
-                }
-            } else if let Err(e) = engine.send_message(
-                session_id,
-                DEFAULT_SERVER_ID,
-                target,
-                &content,
-                None,
-                None,
-                None,
-            ) {
+                )
+                .await
+            {
                 warn!(error = %e, %target, "CTCP ACTION failed");
-                return vec![formatter::err_nosuchnick(nick, target)];
+                return vec![formatter::err_engine(nick, target, &e)];
             }
             vec![]
         }
@@ -308,23 +372,27 @@ fn handle_ctcp(
     }
 }
 
-fn handle_topic(
+async fn handle_topic(
     engine: &ChatEngine,
     session_id: SessionId,
     nick: &str,
+    default_server: &str,
     msg: &IrcMessage,
 ) -> Vec<String> {
     let Some(channel_param) = msg.params.first() else {
         return vec![formatter::err_needmoreparams(nick, "TOPIC")];
     };
 
-    let (server_id, channel_name) = parse_irc_channel(engine, channel_param);
-    let irc_channel = to_irc_channel(engine, &server_id, &channel_name);
+    let (server_id, channel_name) = parse_irc_channel(engine, default_server, channel_param);
+    let irc_channel = to_irc_channel(engine, default_server, &server_id, &channel_name);
 
     if let Some(new_topic) = msg.params.get(1) {
-        if let Err(e) = engine.set_topic(session_id, &server_id, &channel_name, new_topic.clone()) {
+        if let Err(e) = engine
+            .set_topic(session_id, &server_id, &channel_name, new_topic.clone())
+            .await
+        {
             warn!(error = %e, %channel_name, "TOPIC set failed");
-            return vec![formatter::err_notonchannel(nick, &irc_channel)];
+            return vec![formatter::err_engine(nick, &irc_channel, &e)];
         }
         vec![]
     } else {
@@ -346,7 +414,12 @@ fn handle_topic(
     }
 }
 
-fn handle_list(engine: &ChatEngine, nick: &str, msg: &IrcMessage) -> Vec<String> {
+fn handle_list(
+    engine: &ChatEngine,
+    nick: &str,
+    default_server: &str,
+    msg: &IrcMessage,
+) -> Vec<String> {
     // LIST with no args: show default server channels
     // LIST #server-name/* : show channels for a specific server
     let server_id = if let Some(pattern) = msg.params.first() {
@@ -359,17 +432,17 @@ fn handle_list(engine: &ChatEngine, nick: &str, msg: &IrcMessage) -> Vec<String>
                 return vec![formatter::rpl_listend(nick)];
             }
         } else {
-            DEFAULT_SERVER_ID.to_string()
+            default_server.to_string()
         }
     } else {
-        DEFAULT_SERVER_ID.to_string()
+        default_server.to_string()
     };
 
     let channels = engine.list_channels(&server_id);
     let mut replies = Vec::with_capacity(channels.len() + 1);
 
     for ch in &channels {
-        let irc_name = to_irc_channel(engine, &server_id, &ch.name);
+        let irc_name = to_irc_channel(engine, default_server, &server_id, &ch.name);
         replies.push(formatter::rpl_list(
             nick,
             &irc_name,
@@ -381,4 +454,3 @@ fn handle_list(engine: &ChatEngine, nick: &str, msg: &IrcMessage) -> Vec<String>
 
     replies
 }
-
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
 use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
 
@@ -8,6 +10,13 @@ use tracing::{info, warn};
 
 /// Maximum bytes per IRC line (RFC 2812 says 512; we allow 4096 for safety).
 const MAX_LINE_LENGTH: usize = 4096;
+/// Room a PRIVMSG line needs besides its text: command, target and trailing colon.
+const LINE_OVERHEAD: usize = 512;
+
+/// Longest line a client may send, given the configured message length.
+pub(crate) fn line_length(max_message_length: usize) -> usize {
+    (max_message_length + LINE_OVERHEAD).min(MAX_LINE_LENGTH)
+}
 /// Idle timeout — disconnect clients that send nothing for 5 minutes.
 const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
 /// Command rate limit: burst capacity (commands allowed in a rapid burst).
@@ -19,7 +28,7 @@ const CMD_RATE_PER_SEC: f64 = 2.0;
 static MOTD_LINES: OnceLock<Vec<String>> = OnceLock::new();
 
 /// Supported IRCv3 capabilities.
-const SUPPORTED_CAPS: &str = "server-time message-tags sasl";
+const SUPPORTED_CAPS: &str = "server-time message-tags sasl batch away-notify draft/multiline";
 
 /// Tracks which IRCv3 capabilities a client has negotiated.
 #[derive(Default)]
@@ -27,6 +36,9 @@ struct ClientCaps {
     server_time: bool,
     message_tags: bool,
     sasl: bool,
+    batch: bool,
+    away_notify: bool,
+    multiline: bool,
 }
 
 /// Set the MOTD lines from config. Call once at startup.
@@ -63,20 +75,29 @@ impl CommandRateLimit {
     }
 }
 
-use crate::auth::token::verify_irc_token;
-use crate::db::queries::{presence, users};
-use crate::engine::chat_engine::{ChatEngine, DEFAULT_SERVER_ID};
-use crate::engine::events::{ChatEvent, SessionId};
+use crate::db::queries::{presence, servers, users};
+use crate::engine::chat_engine::{BanParams, ChatEngine, DEFAULT_SERVER_ID};
+use crate::engine::error::EngineError;
+use crate::engine::events::{ChatEvent, HistoryMessage, SessionId};
 use crate::engine::user_session::Protocol;
+use crate::engine::validation;
 
+use super::bouncer;
 use super::commands::{self, parse_irc_channel, to_irc_channel};
-use crate::engine::permissions::Permissions;
 use super::formatter;
-use super::parser::IrcMessage;
+use super::listener::IpSlot;
+use super::modes::{self, ModeChange, ShownStatus};
+use super::monitor::{self, AwayNotify, NotifyStyle};
+use super::multiline::{self, MultilineReceiver, Received};
+use super::parser::{self, IrcMessage};
+use super::proxy;
+use super::sasl::{self, SaslReply, SaslSession, validate_irc_pass};
+use super::services;
+use crate::engine::permissions::Permissions;
 
 /// Read a line from the IRC connection, capped at MAX_LINE_LENGTH bytes.
 /// Returns Ok(0) on EOF, Ok(n) on success, Err on I/O error or line too long.
-async fn read_bounded_line<R: AsyncRead + Unpin>(
+pub(crate) async fn read_bounded_line<R: AsyncRead + Unpin>(
     reader: &mut BufReader<R>,
     buf: &mut String,
 ) -> std::io::Result<usize> {
@@ -117,21 +138,48 @@ enum RegState {
         pass: Option<String>,
         nick: Option<String>,
         user_received: bool,
+        /// Server requested via `USER name@server` or `PASS server/token`.
+        server: Option<String>,
+        /// (user_id, account name) after a successful SASL exchange.
+        account: Option<(String, String)>,
     },
     /// Fully registered with the chat engine.
     Registered { session_id: SessionId, nick: String },
 }
 
+/// Where an IRC connection comes from.
+pub struct IrcPeer {
+    /// Client address: the TCP peer, or the address reported by a trusted proxy.
+    pub addr: SocketAddr,
+    /// SHA-256 fingerprint of the TLS client certificate, if one was presented.
+    pub cert_fp: Option<String>,
+    /// The connection's per-IP slot, released when the connection ends.
+    pub slot: IpSlot,
+}
+
 /// Handle a single IRC client connection from accept to close.
 /// Accepts any stream implementing AsyncRead + AsyncWrite (plain TCP or TLS).
 pub async fn handle_irc_connection<S>(
     stream: S,
-    peer: String,
+    peer: IrcPeer,
     engine: Arc<ChatEngine>,
     db: SqlitePool,
 ) where
     S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
 {
+    let IrcPeer {
+        addr,
+        cert_fp,
+        mut slot,
+    } = peer;
+    // Client address for logs, and (hostname, IP) for WHOIS; WEBIRC may replace both
+    let mut peer = addr.to_string();
+    let mut client_host = (
+        addr.ip().to_canonical().to_string(),
+        addr.ip().to_canonical(),
+    );
+    let mut webirc_allowed = true;
+
     info!(%peer, "IRC client connected");
 
     let (reader, writer) = tokio::io::split(stream);
@@ -156,12 +204,25 @@ pub async fn handle_irc_connection<S>(
         pass: None,
         nick: None,
         user_received: false,
+        server: None,
+        account: None,
     };
 
     let mut line_buf = String::new();
     let mut event_rx: Option<mpsc::Receiver<ChatEvent>> = None;
     let mut cmd_rate = CommandRateLimit::new();
     let mut caps = ClientCaps::default();
+    let mut sasl_session = SaslSession::default();
+    // Server whose channels are addressed by bare names (`#general`) on this connection
+    let mut default_server = DEFAULT_SERVER_ID.to_string();
+    // Set when this connection is one of the clients of an always-on session
+    let mut attachment: Option<bouncer::Attachment> = None;
+    // How MONITOR/WATCH notifications are worded (set by the last command used)
+    let mut notify_style = NotifyStyle::default();
+    let mut away_notify = AwayNotify::default();
+    // Status prefixes shown to the client, so MODE echoes only real changes
+    let mut shown_status = ShownStatus::default();
+    let mut multiline_rx = MultilineReceiver::new(engine.max_message_length());
 
     loop {
         // When registered, also select on engine events
@@ -180,18 +241,37 @@ pub async fn handle_irc_connection<S>(
                         continue;
                     }
 
-                    // Enforce per-connection command rate limit
-                    if !cmd_rate.check() {
+                    let (tags, line) = parser::split_tags(&line);
+
+                    // Enforce per-connection command rate limit (lines of an open
+                    // multiline batch count as one command with the batch)
+                    let batched = caps.multiline && multiline_rx.is_batched(tags);
+                    if !(batched || cmd_rate.check()) {
                         warn!(%peer, "IRC command rate limited");
                         continue;
                     }
 
                     if let RegState::Registered { ref session_id, ref nick } = state {
-                        let msg = match IrcMessage::parse(&line) {
+                        let msg = match IrcMessage::parse(line) {
                             Ok(m) => m,
                             Err(_) => continue,
                         };
 
+                        // draft/multiline: buffer batched lines and handle the whole message at once
+                        let msg = if caps.multiline {
+                            match multiline_rx.receive(tags, &msg) {
+                                Received::Line => msg,
+                                Received::Buffered => continue,
+                                Received::Complete(combined) => combined,
+                                Received::Failed(reply) => {
+                                    send_line(&out_tx, &reply);
+                                    continue;
+                                }
+                            }
+                        } else {
+                            msg
+                        };
+
                         if msg.command == "QUIT" {
                             let reason = msg.params.first().cloned();
                             send_line(&out_tx, &format!(
@@ -202,6 +282,44 @@ pub async fn handle_irc_connection<S>(
                             break;
                         }
 
+                        // NICK after registration — rename the session and track the new nick
+                        if msg.command == "NICK" {
+                            let sid = *session_id;
+                            let (replies, new_nick) = handle_nick(&engine, sid, nick, &msg).await;
+                            for reply in replies {
+                                send_line(&out_tx, &reply);
+                            }
+                            if let Some(new_nick) = new_nick {
+                                state = RegState::Registered {
+                                    session_id: sid,
+                                    nick: new_nick,
+                                };
+                            }
+                            continue;
+                        }
+
+                        // PRIVMSG to the services pseudo-client
+                        if msg.command == "PRIVMSG"
+                            && let [target, text] = msg.params.as_slice()
+                            && services::is_service_nick(target)
+                        {
+                            let replies = services::handle(
+                                &engine,
+                                &db,
+                                *session_id,
+                                nick,
+                                &mut default_server,
+                                cert_fp.as_deref(),
+                                target,
+                                text,
+                            )
+                            .await;
+                            for reply in replies {
+                                send_line(&out_tx, &reply);
+                            }
+                            continue;
+                        }
+
                         // MOTD command — re-send MOTD on demand
                         if msg.command == "MOTD" {
                             let motd = MOTD_LINES.get();
@@ -219,15 +337,31 @@ pub async fn handle_irc_connection<S>(
                             continue;
                         }
 
+                        // MONITOR / WATCH — nickname online notifications
+                        if matches!(msg.command.as_str(), "MONITOR" | "WATCH") {
+                            let replies = if msg.command == "MONITOR" {
+                                notify_style = NotifyStyle::Monitor;
+                                monitor::handle_monitor(&engine, *session_id, nick, &msg)
+                            } else {
+                                notify_style = NotifyStyle::Watch;
+                                monitor::handle_watch(&engine, *session_id, nick, &msg)
+                            };
+                            for reply in replies {
+                                send_line(&out_tx, &reply);
+                            }
+                            continue;
+                        }
+
                         // Async commands — need DB lookups or engine async methods
-                        if matches!(msg.command.as_str(), "KICK" | "AWAY" | "INVITE" | "WHOIS" | "NAMES" | "WHO") {
+                        if matches!(msg.command.as_str(), "KICK" | "MODE" | "AWAY" | "INVITE" | "WHOIS" | "NAMES" | "WHO") {
                             let replies = match msg.command.as_str() {
-                                "KICK" => handle_kick(&engine, &db, *session_id, nick, &msg).await,
+                                "KICK" => handle_kick(&engine, &db, *session_id, nick, &default_server, &msg).await,
+                                "MODE" => handle_mode(&engine, &db, *session_id, nick, &default_server, &msg).await,
                                 "AWAY" => handle_away(&engine, *session_id, nick, &msg).await,
-                                "INVITE" => handle_invite(&engine, &db, *session_id, nick, &msg).await,
-                                "WHOIS" => handle_whois(&engine, &db, nick, &msg).await,
-                                "NAMES" => handle_names_async(&engine, nick, &msg).await,
-                                "WHO" => handle_who_async(&engine, nick, &msg).await,
+                                "INVITE" => handle_invite(&engine, &db, *session_id, nick, &default_server, &msg).await,
+                                "WHOIS" => handle_whois(&engine, &db, nick, &default_server, &msg).await,
+                                "NAMES" => handle_names_async(&engine, nick, &default_server, &msg, &mut shown_status).await,
+                                "WHO" => handle_who_async(&engine, &db, nick, &default_server, &msg).await,
                                 _ => unreachable!(),
                             };
                             for reply in replies {
@@ -236,7 +370,18 @@ pub async fn handle_irc_connection<S>(
                             continue;
                         }
 
-                        let replies = commands::handle_command(&engine, *session_id, nick, &msg);
+                        let replies =
+                            commands::handle_command(&engine, *session_id, nick, &default_server, &msg).await;
+
+                        // Echo our own messages to the account's other attached clients
+                        if replies.is_empty()
+                            && let Some(ref att) = attachment
+                            && att.has_siblings()
+                            && let Some(event) = own_message_event(&engine, &default_server, nick, &msg)
+                        {
+                            att.echo_to_siblings(event);
+                        }
+
                         for reply in replies {
                             send_line(&out_tx, &reply);
                         }
@@ -244,11 +389,46 @@ pub async fn handle_irc_connection<S>(
                 }
                 event = rx.recv() => {
                     let Some(event) = event else { break };
-                    if let RegState::Registered { ref nick, .. } = state {
-                        let lines = event_to_irc_lines(&engine, nick, &event, &caps);
+                    if let RegState::Registered { session_id, ref nick } = state {
+                        match &event {
+                            ChatEvent::MonitorStatus { nickname, online } => {
+                                for line in monitor::notify_lines(nick, notify_style, nickname, *online) {
+                                    send_line(&out_tx, &line);
+                                }
+                            }
+                            ChatEvent::PresenceUpdate { presence, .. } if caps.away_notify => {
+                                if let Some(line) = away_notify.update(nick, presence) {
+                                    send_line(&out_tx, &line);
+                                }
+                            }
+                            ChatEvent::Names { server_id, members, .. } => {
+                                // Rendered with only the owner as `@` (see event_to_irc_lines)
+                                let owner_id = engine.get_server_owner_id(server_id);
+                                for uid in members.iter().filter_map(|m| m.user_id.as_deref()) {
+                                    let prefix = if owner_id.as_deref() == Some(uid) { "@" } else { "" };
+                                    shown_status.record(server_id, uid, prefix);
+                                }
+                            }
+                            _ => {}
+                        }
+                        let lines = event_to_irc_lines(&engine, nick, &default_server, &event, &caps);
                         for line in lines {
                             send_line(&out_tx, &line);
                         }
+                        let tag_prefix = build_tag_prefix(&caps, &event);
+                        for line in mode_echo_lines(&engine, &db, nick, &default_server, &event, &mut shown_status).await {
+                            send_line(&out_tx, &format!("{tag_prefix}{line}"));
+                        }
+
+                        // Another client attached to the same session changed the nick
+                        if let ChatEvent::NickChange { old_nick, new_nick } = &event
+                            && old_nick == nick
+                        {
+                            state = RegState::Registered {
+                                session_id,
+                                nick: new_nick.clone(),
+                            };
+                        }
                     }
                 }
             }
@@ -274,7 +454,8 @@ pub async fn handle_irc_connection<S>(
                 continue;
             }
 
-            let msg = match IrcMessage::parse(&line) {
+            let (_, line) = parser::split_tags(&line);
+            let msg = match IrcMessage::parse(line) {
                 Ok(m) => m,
                 Err(_) => continue,
             };
@@ -284,7 +465,19 @@ pub async fn handle_irc_connection<S>(
                 let sn = formatter::server_name();
                 match msg.params.first().map(|s| s.as_str()) {
                     Some("LS") => {
-                        send_line(&out_tx, &format!(":{sn} CAP * LS :{SUPPORTED_CAPS}"));
+                        // CAP LS 302 clients understand capability values
+                        let cap_302 = msg
+                            .params
+                            .get(1)
+                            .and_then(|v| v.parse::<u32>().ok())
+                            .is_some_and(|v| v >= 302);
+                        send_line(
+                            &out_tx,
+                            &format!(
+                                ":{sn} CAP * LS :{}",
+                                cap_ls(cap_302, engine.max_message_length())
+                            ),
+                        );
                     }
                     Some("REQ") => {
                         // Client requests specific capabilities
@@ -304,6 +497,18 @@ pub async fn handle_irc_connection<S>(
                                         caps.sasl = true;
                                         ack.push(cap);
                                     }
+                                    "batch" => {
+                                        caps.batch = true;
+                                        ack.push(cap);
+                                    }
+                                    "away-notify" => {
+                                        caps.away_notify = true;
+                                        ack.push(cap);
+                                    }
+                                    "draft/multiline" => {
+                                        caps.multiline = true;
+                                        ack.push(cap);
+                                    }
                                     _ => {} // Ignore unsupported caps
                                 }
                             }
@@ -320,87 +525,76 @@ pub async fn handle_irc_connection<S>(
 
             // Handle SASL AUTHENTICATE during registration
             if msg.command == "AUTHENTICATE" {
-                let sn = formatter::server_name();
-                if let Some(param) = msg.params.first() {
-                    if param == "PLAIN" {
-                        // Acknowledge, ask for credentials
-                        send_line(&out_tx, "AUTHENTICATE +");
-                    } else if param == "*" {
-                        // Client aborts SASL
-                        send_line(
-                            &out_tx,
-                            &format!(":{sn} 906 * :SASL authentication aborted"),
-                        );
-                    } else {
-                        // base64 payload: \0username\0token
-                        use base64::Engine as _;
-                        let decoded = base64::engine::general_purpose::STANDARD.decode(param);
-                        if let Ok(bytes) = decoded {
-                            // Split on NUL: [authzid, authcid, passwd]
-                            let parts: Vec<&[u8]> = bytes.splitn(3, |&b| b == 0).collect();
-                            if parts.len() == 3 {
-                                let _authzid = String::from_utf8_lossy(parts[0]);
-                                let authcid = String::from_utf8_lossy(parts[1]);
-                                let passwd = String::from_utf8_lossy(parts[2]);
-                                // Validate the token
-                                let nick_hint = if authcid.is_empty() { "*" } else { &authcid };
-                                match validate_irc_pass(&db, &passwd, nick_hint).await {
-                                    Ok(Some(uid)) => {
-                                        if let RegState::Unregistered { ref mut pass, .. } = state {
-                                            *pass = Some(passwd.into_owned());
-                                        }
-                                        send_line(
-                                            &out_tx,
-                                            &format!(
-                                                ":{sn} 900 * {uid} :You are now logged in as {uid}"
-                                            ),
-                                        );
-                                        send_line(
-                                            &out_tx,
-                                            &format!(":{sn} 903 * :SASL authentication successful"),
-                                        );
-                                    }
-                                    Ok(None) => {
-                                        send_line(
-                                            &out_tx,
-                                            &format!(":{sn} 904 * :SASL authentication failed"),
-                                        );
-                                    }
-                                    Err(_) => {
-                                        send_line(
-                                            &out_tx,
-                                            &format!(":{sn} 904 * :SASL authentication failed"),
-                                        );
-                                    }
-                                }
-                            } else {
-                                send_line(
-                                    &out_tx,
-                                    &format!(":{sn} 904 * :SASL authentication failed"),
-                                );
-                            }
-                        } else {
-                            send_line(&out_tx, &format!(":{sn} 904 * :SASL authentication failed"));
-                        }
-                    }
+                let Some(param) = msg.params.first() else {
+                    continue;
+                };
+                let Some(reply) = sasl_session.step(&db, cert_fp.as_deref(), param).await else {
+                    continue; // more payload chunks to come
+                };
+                let current_nick = match state {
+                    RegState::Unregistered {
+                        nick: Some(ref nick),
+                        ..
+                    } => nick.clone(),
+                    _ => "*".to_string(),
+                };
+                for line in sasl_reply_lines(&current_nick, reply, &mut state) {
+                    send_line(&out_tx, &line);
                 }
                 continue;
             }
 
             // Process registration commands
             match msg.command.as_str() {
+                // WEBIRC must come before NICK/USER; it replaces the gateway's address with the user's
+                "WEBIRC" if webirc_allowed => {
+                    webirc_allowed = false;
+                    let webirc = match proxy::accept_webirc(client_host.1, &msg.params) {
+                        Ok(webirc) => webirc,
+                        Err(e) => {
+                            warn!(%peer, error = %e, "WEBIRC rejected");
+                            send_line(&out_tx, &format!("ERROR :{e}"));
+                            break;
+                        }
+                    };
+                    let Some(client_slot) = slot.transfer(webirc.ip) else {
+                        warn!(%peer, ip = %webirc.ip, "WEBIRC client rejected: per-IP limit reached");
+                        send_line(&out_tx, "ERROR :Too many connections from your host");
+                        break;
+                    };
+                    slot = client_slot;
+                    info!(%peer, ip = %webirc.ip, gateway = %webirc.gateway, "WEBIRC client");
+                    peer = format!("{} via {}", webirc.ip, webirc.gateway);
+                    client_host = (webirc.hostname, webirc.ip);
+                }
                 "PASS" => {
-                    if let RegState::Unregistered { ref mut pass, .. } = state {
-                        *pass = msg.params.first().cloned();
+                    if let RegState::Unregistered {
+                        ref mut pass,
+                        ref mut server,
+                        ..
+                    } = state
+                        && let Some(param) = msg.params.first()
+                    {
+                        // `PASS server/token` binds the connection to a server
+                        let (server_name, token) = commands::split_pass_server(param);
+                        *pass = Some(token.to_string());
+                        if let Some(server_name) = server_name {
+                            *server = Some(server_name.to_string());
+                        }
                     }
                 }
                 "NICK" => {
+                    webirc_allowed = false;
                     let Some(wanted_nick) = msg.params.first() else {
                         send_line(&out_tx, &formatter::err_nonicknamegiven("*"));
                         continue;
                     };
 
-                    if !engine.is_nick_available(wanted_nick) {
+                    // An always-on session's nick stays claimable by its owner (checked at PASS/SASL)
+                    if (!engine.is_nick_available(wanted_nick)
+                        && !bouncer::holds_nick(&engine, wanted_nick))
+                        || services::is_service_nick(wanted_nick)
+                    {
                         send_line(&out_tx, &formatter::err_nicknameinuse("*", wanted_nick));
                         continue;
                     }
@@ -410,12 +604,22 @@ pub async fn handle_irc_connection<S>(
                     }
                 }
                 "USER" => {
+                    webirc_allowed = false;
                     if let RegState::Unregistered {
                         ref mut user_received,
+                        ref mut server,
                         ..
                     } = state
                     {
                         *user_received = true;
+                        // `USER name@server` binds the connection to a server
+                        if let Some(server_name) = msg
+                            .params
+                            .first()
+                            .and_then(|username| commands::username_server(username))
+                        {
+                            *server = Some(server_name.to_string());
+                        }
                     }
                 }
                 "QUIT" => break,
@@ -430,11 +634,28 @@ pub async fn handle_irc_connection<S>(
                 ref pass,
                 ref nick,
                 user_received,
+                ref server,
+                ref account,
             } = state
                 && let (Some(nick_val), true) = (nick.as_ref(), user_received)
             {
-                // If a PASS was provided, validate it as an IRC token
-                let user_id = if let Some(pass_token) = pass {
+                // A SASL login stands in for PASS; otherwise validate PASS as an IRC token
+                let user_id = if let Some((uid, account_name)) = account {
+                    if !account_name.eq_ignore_ascii_case(nick_val) {
+                        send_line(
+                            &out_tx,
+                            &format!(
+                                ":{} {} {} :You must use the nickname of your SASL account ({})",
+                                formatter::server_name(),
+                                super::numerics::ERR_NICKLOCKED,
+                                nick_val,
+                                account_name,
+                            ),
+                        );
+                        break;
+                    }
+                    Some(uid.clone())
+                } else if let Some(pass_token) = pass {
                     match validate_irc_pass(&db, pass_token, nick_val).await {
                         Ok(Some(uid)) => Some(uid),
                         Ok(None) => {
@@ -474,16 +695,47 @@ pub async fn handle_irc_connection<S>(
                     break;
                 };
 
+                // Always-on accounts reattach to their live session instead of starting a new one
+                let resumed = match user_id.as_deref() {
+                    Some(uid) if engine.bouncer().enabled() => {
+                        bouncer::attach_existing(&engine, uid).await
+                    }
+                    _ => None,
+                };
+
                 // Try to register with the engine
-                match engine.connect(user_id, nick_val.clone(), Protocol::Irc, None) {
-                    Ok((sid, rx)) => {
-                        let nick_owned = nick_val.clone();
+                let connected = match resumed {
+                    Some((att, rx)) => Ok((att.session_id(), rx, Some(att))),
+                    None => engine
+                        .connect(user_id.clone(), nick_val.clone(), Protocol::Irc, None)
+                        .map(|(sid, rx)| match user_id.as_deref() {
+                            Some(uid) if engine.bouncer().enabled() => {
+                                let (att, rx) = bouncer::adopt(&engine, uid, sid, rx);
+                                (sid, rx, Some(att))
+                            }
+                            _ => (sid, rx, None),
+                        }),
+                };
+
+                match connected {
+                    Ok((sid, rx, att)) => {
+                        // A resumed session keeps the nickname it already has
+                        let nick_owned = engine
+                            .get_session(sid)
+                            .map(|s| s.nickname.clone())
+                            .unwrap_or_else(|| nick_val.clone());
+                        engine.set_client_host(sid, client_host.clone());
 
                         // Send welcome burst
                         send_line(&out_tx, &formatter::rpl_welcome(&nick_owned));
                         send_line(&out_tx, &formatter::rpl_yourhost(&nick_owned));
                         send_line(&out_tx, &formatter::rpl_created(&nick_owned));
                         send_line(&out_tx, &formatter::rpl_myinfo(&nick_owned));
+                        for line in
+                            formatter::rpl_isupport(&nick_owned, engine.max_message_length())
+                        {
+                            send_line(&out_tx, &line);
+                        }
 
                         // Send MOTD or ERR_NOMOTD
                         let motd = MOTD_LINES.get();
@@ -499,11 +751,53 @@ pub async fn handle_irc_connection<S>(
                             send_line(&out_tx, &formatter::err_nomotd(&nick_owned));
                         }
 
+                        if let Some(server_name) = server {
+                            let notice = match commands::resolve_server(&engine, server_name) {
+                                Some(server_id) => {
+                                    let name = engine
+                                        .get_server_name(&server_id)
+                                        .unwrap_or_else(|| server_id.clone());
+                                    default_server = server_id;
+                                    format!(
+                                        "Channel names without a server prefix now refer to {name}"
+                                    )
+                                }
+                                None => format!("No such server: {server_name}"),
+                            };
+                            send_line(
+                                &out_tx,
+                                &format!(
+                                    ":{} NOTICE {} :{}",
+                                    formatter::server_name(),
+                                    nick_owned,
+                                    notice
+                                ),
+                            );
+                        }
+
+                        if let Some(ref att) = att
+                            && att.resumed()
+                        {
+                            let lines = resume_lines(
+                                &engine,
+                                att,
+                                &nick_owned,
+                                &default_server,
+                                &caps,
+                                &mut shown_status,
+                            )
+                            .await;
+                            for line in lines {
+                                send_line(&out_tx, &line);
+                            }
+                        }
+
                         state = RegState::Registered {
                             session_id: sid,
                             nick: nick_owned,
                         };
                         event_rx = Some(rx);
+                        attachment = att;
                     }
                     Err(e) => {
                         warn!(error = %e, "IRC registration failed");
@@ -514,10 +808,15 @@ pub async fn handle_irc_connection<S>(
         }
     }
 
-    // Disconnect from engine if registered
+    // Disconnect from engine if registered (always-on sessions only detach)
     if let RegState::Registered { session_id, nick } = state {
-        engine.disconnect(session_id);
-        info!(%peer, %nick, "IRC client disconnected");
+        if let Some(attachment) = attachment {
+            attachment.detach(&engine).await;
+            info!(%peer, %nick, "IRC client detached from always-on session");
+        } else {
+            engine.disconnect(session_id).await;
+            info!(%peer, %nick, "IRC client disconnected");
+        }
     } else {
         info!(%peer, "IRC client disconnected (unregistered)");
     }
@@ -525,34 +824,6 @@ pub async fn handle_irc_connection<S>(
     write_handle.abort();
 }
 
-/// Validate an IRC PASS token against stored hashes.
-/// Returns Ok(Some(user_id)) if the token matches, Ok(None) if not.
-async fn validate_irc_pass(
-    db: &SqlitePool,
-    token: &str,
-    nickname: &str,
-) -> Result<Option<String>, String> {
-    // Scoped lookup: only fetch tokens for this nickname (O(1) per user instead of O(n) global)
-    let hashes = users::get_irc_token_hashes_by_nick(db, nickname)
-        .await
-        .map_err(|e| format!("DB error: {}", e))?;
-
-    for (user_id, token_hash) in &hashes {
-        if verify_irc_token(token, token_hash) {
-            // Update last_used timestamp (fire-and-forget)
-            let pool = db.clone();
-            let uid = user_id.clone();
-            let hash = token_hash.clone();
-            tokio::spawn(async move {
-                let _ = users::touch_irc_token(&pool, &uid, &hash).await;
-            });
-            return Ok(Some(user_id.clone()));
-        }
-    }
-
-    Ok(None)
-}
-
 /// Handle IRC KICK command: KICK #channel user [:reason]
 /// Requires async because it does a DB lookup (nickname → user_id) and calls engine.kick_member().
 async fn handle_kick(
@@ -560,6 +831,7 @@ async fn handle_kick(
     db: &SqlitePool,
     session_id: SessionId,
     nick: &str,
+    default_server: &str,
     msg: &IrcMessage,
 ) -> Vec<String> {
     if msg.params.len() < 2 {
@@ -573,7 +845,8 @@ async fn handle_kick(
         return vec![formatter::err_nosuchchannel(nick, target_channel)];
     }
 
-    let (server_id, channel_name) = commands::parse_irc_channel(engine, target_channel);
+    let (server_id, channel_name) =
+        commands::parse_irc_channel(engine, default_server, target_channel);
 
     // Resolve channel name → channel_id for channel-scoped permission check
     let channel_id = engine.resolve_channel_id(&server_id, &channel_name).ok();
@@ -601,7 +874,10 @@ async fn handle_kick(
         Ok(()) => vec![],
         Err(e) => {
             // Map permission errors to IRC numeric 482
-            if e.contains("permission") || e.contains("Permission") {
+            if matches!(
+                e,
+                EngineError::PermissionDenied { .. } | EngineError::Forbidden(_)
+            ) {
                 vec![format!(
                     ":{} 482 {} {} :{}",
                     formatter::server_name(),
@@ -621,6 +897,343 @@ async fn handle_kick(
     }
 }
 
+/// Handle IRC MODE. Channel queries and user modes go through the sync handler;
+/// channel mode changes map onto engine moderation operations, which broadcast
+/// events that are echoed back to IRC clients as MODE lines.
+async fn handle_mode(
+    engine: &ChatEngine,
+    db: &SqlitePool,
+    session_id: SessionId,
+    nick: &str,
+    default_server: &str,
+    msg: &IrcMessage,
+) -> Vec<String> {
+    if msg.params.len() < 2 || !msg.params[0].starts_with('#') {
+        return commands::handle_command(engine, session_id, nick, default_server, msg).await;
+    }
+    let target_channel = &msg.params[0];
+    let (server_id, channel_name) = parse_irc_channel(engine, default_server, target_channel);
+    if engine
+        .resolve_channel_id(&server_id, &channel_name)
+        .is_err()
+    {
+        return vec![formatter::err_nosuchchannel(nick, target_channel)];
+    }
+
+    let mut replies = Vec::new();
+    for change in modes::parse_mode_changes(&msg.params[1], &msg.params[2..]) {
+        let result = match change {
+            ModeChange::NoOp => Ok(()),
+            ModeChange::Unknown(c) => {
+                replies.push(formatter::err_unknownmode(nick, c));
+                continue;
+            }
+            ModeChange::MissingParam(_) => {
+                replies.push(formatter::err_needmoreparams(nick, "MODE"));
+                continue;
+            }
+            ModeChange::Private(on) => {
+                engine
+                    .set_channel_private(session_id, &server_id, &channel_name, on)
+                    .await
+            }
+            ModeChange::ReadOnly(on) => {
+                engine
+                    .set_read_only(session_id, &server_id, &channel_name, on)
+                    .await
+            }
+            ModeChange::TopicLock(on) => {
+                engine
+                    .set_topic_locked(session_id, &server_id, &channel_name, on)
+                    .await
+            }
+            ModeChange::SlowMode(seconds) => {
+                engine
+                    .set_slowmode(session_id, &server_id, &channel_name, seconds)
+                    .await
+            }
+            ModeChange::BanList => match engine.get_bans(session_id, &server_id).await {
+                Ok(bans) => {
+                    for ban in bans {
+                        let mask = modes::ban_mask_for(&user_display_name(db, &ban.user_id).await);
+                        let set_by = user_display_name(db, &ban.banned_by).await;
+                        replies.push(formatter::rpl_banlist(nick, target_channel, &mask, &set_by));
+                    }
+                    replies.push(formatter::rpl_endofbanlist(nick, target_channel));
+                    continue;
+                }
+                Err(e) => Err(e),
+            },
+            ModeChange::Ban { set, mask } => {
+                let Some(target_nick) = modes::ban_mask_nick(&mask) else {
+                    replies.push(format!(
+                        ":{} NOTICE {} :MODE failed: ban masks must name a nickname",
+                        formatter::server_name(),
+                        nick
+                    ));
+                    continue;
+                };
+                let Some(target_user_id) = resolve_user_id(db, target_nick).await else {
+                    replies.push(formatter::err_nosuchnick(nick, target_nick));
+                    continue;
+                };
+                if set {
+                    engine
+                        .ban_member(
+                            session_id,
+                            &server_id,
+                            &target_user_id,
+                            &BanParams::default(),
+                        )
+                        .await
+                } else {
+                    engine
+                        .unban_member(session_id, &server_id, &target_user_id)
+                        .await
+                }
+            }
+            ModeChange::Op {
+                set,
+                nick: target_nick,
+            } => {
+                let Some(target_user_id) = resolve_user_id(db, &target_nick).await else {
+                    replies.push(formatter::err_nosuchnick(nick, &target_nick));
+                    continue;
+                };
+                apply_status_mode(
+                    engine,
+                    db,
+                    session_id,
+                    &server_id,
+                    &target_user_id,
+                    true,
+                    set,
+                )
+                .await
+            }
+            ModeChange::Voice {
+                set,
+                nick: target_nick,
+            } => {
+                let Some(target_user_id) = resolve_user_id(db, &target_nick).await else {
+                    replies.push(formatter::err_nosuchnick(nick, &target_nick));
+                    continue;
+                };
+                apply_status_mode(
+                    engine,
+                    db,
+                    session_id,
+                    &server_id,
+                    &target_user_id,
+                    false,
+                    set,
+                )
+                .await
+            }
+        };
+
+        if let Err(e) = result {
+            if matches!(
+                e,
+                EngineError::PermissionDenied { .. } | EngineError::Forbidden(_)
+            ) {
+                replies.push(formatter::err_chanoprivsneeded(nick, target_channel));
+            } else {
+                replies.push(format!(
+                    ":{} NOTICE {} :MODE failed: {}",
+                    formatter::server_name(),
+                    nick,
+                    e
+                ));
+            }
+        }
+    }
+    replies
+}
+
+/// Apply `+o`/`-o` (op) or `+v`/`-v` (voice) by assigning or removing roles.
+///
+/// Setting assigns the lowest role that confers the prefix; unsetting removes
+/// every role the member holds that confers it. Requires MANAGE_ROLES, and the
+/// engine enforces the role hierarchy.
+async fn apply_status_mode(
+    engine: &ChatEngine,
+    db: &SqlitePool,
+    session_id: SessionId,
+    server_id: &str,
+    target_user_id: &str,
+    op: bool,
+    set: bool,
+) -> Result<(), EngineError> {
+    let actor_id = engine
+        .require_permission(session_id, server_id, None, Permissions::MANAGE_ROLES)
+        .await?;
+
+    let mut role_ids = None;
+    if set {
+        let roles = engine.list_roles(server_id).await?;
+        let role = modes::status_role(&roles, op).ok_or_else(|| {
+            EngineError::NotFound(format!(
+                "no role on this server grants {}",
+                if op { "+o" } else { "+v" }
+            ))
+        })?;
+        role_ids = Some(
+            engine
+                .assign_role(server_id, &actor_id, target_user_id, &role.id)
+                .await?,
+        );
+    } else {
+        let held = crate::db::queries::roles::get_user_roles(db, server_id, target_user_id).await?;
+        for role in held
+            .iter()
+            .filter(|r| r.is_default == 0 && modes::confers_status(r.permissions, op))
+        {
+            role_ids = Some(
+                engine
+                    .remove_role(server_id, &actor_id, target_user_id, &role.id)
+                    .await?,
+            );
+        }
+    }
+
+    if let Some(role_ids) = role_ids {
+        engine.broadcast_to_server(
+            server_id,
+            &ChatEvent::MemberRoleUpdate {
+                server_id: server_id.to_string(),
+                user_id: target_user_id.to_string(),
+                role_ids,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// Resolve an IRC nickname to a user ID via the database.
+async fn resolve_user_id(db: &SqlitePool, nickname: &str) -> Option<String> {
+    match users::get_user_by_nickname(db, nickname).await {
+        Ok(Some((uid, ..))) => Some(uid),
+        Ok(None) => None,
+        Err(e) => {
+            warn!(error = %e, "MODE: DB error resolving nickname");
+            None
+        }
+    }
+}
+
+/// Display name for a user ID (their username), falling back to the ID itself.
+async fn user_display_name(db: &SqlitePool, user_id: &str) -> String {
+    match users::get_user(db, user_id).await {
+        Ok(Some((_, username, ..))) => username,
+        _ => user_id.to_string(),
+    }
+}
+
+/// MODE lines for moderation events that need DB or permission lookups to
+/// render: bans (`+b`/`-b`) and role changes (`+o`/`+v`). Only channels the
+/// recipient has joined in the event's server get a line, and role changes
+/// only when they move the status prefix the client was shown.
+async fn mode_echo_lines(
+    engine: &ChatEngine,
+    db: &SqlitePool,
+    my_nick: &str,
+    default_server: &str,
+    event: &ChatEvent,
+    shown_status: &mut ShownStatus,
+) -> Vec<String> {
+    match event {
+        ChatEvent::MemberBan {
+            server_id,
+            user_id,
+            banned_by,
+            ..
+        } => {
+            let mask = modes::ban_mask_for(&user_display_name(db, user_id).await);
+            let set_by = user_display_name(db, banned_by).await;
+            engine
+                .joined_channels_in_server(my_nick, server_id)
+                .iter()
+                .map(|ch| {
+                    let irc_channel = to_irc_channel(engine, default_server, server_id, ch);
+                    formatter::channel_mode(Some(&set_by), &irc_channel, "+b", &[&mask])
+                })
+                .collect()
+        }
+        ChatEvent::MemberUnban { server_id, user_id } => {
+            let mask = modes::ban_mask_for(&user_display_name(db, user_id).await);
+            engine
+                .joined_channels_in_server(my_nick, server_id)
+                .iter()
+                .map(|ch| {
+                    let irc_channel = to_irc_channel(engine, default_server, server_id, ch);
+                    formatter::channel_mode(None, &irc_channel, "-b", &[&mask])
+                })
+                .collect()
+        }
+        ChatEvent::MemberRoleUpdate {
+            server_id, user_id, ..
+        } => {
+            let Some(target_nick) = engine.nickname_for_user(user_id) else {
+                return vec![];
+            };
+            let prefix = irc_prefix_for_user(engine, server_id, user_id).await;
+            let Some(change) = shown_status.update(server_id, user_id, prefix) else {
+                return vec![];
+            };
+            let args = vec![target_nick.as_str(); change.matches(['o', 'v']).count()];
+            let target_channels = engine.joined_channels_in_server(&target_nick, server_id);
+            engine
+                .joined_channels_in_server(my_nick, server_id)
+                .iter()
+                .filter(|ch| target_channels.contains(ch))
+                .map(|ch| {
+                    let irc_channel = to_irc_channel(engine, default_server, server_id, ch);
+                    formatter::channel_mode(None, &irc_channel, change, &args)
+                })
+                .collect()
+        }
+        _ => vec![],
+    }
+}
+
+/// Handle IRC NICK after registration. Returns error replies, plus the new
+/// nick on success (the NICK line itself arrives as a `NickChange` event).
+async fn handle_nick(
+    engine: &ChatEngine,
+    session_id: SessionId,
+    nick: &str,
+    msg: &IrcMessage,
+) -> (Vec<String>, Option<String>) {
+    let Some(wanted) = msg.params.first() else {
+        return (vec![formatter::err_nonicknamegiven(nick)], None);
+    };
+
+    if validation::validate_nickname(wanted).is_err() {
+        return (vec![formatter::err_erroneusnickname(nick, wanted)], None);
+    }
+
+    if services::is_service_nick(wanted) {
+        return (vec![formatter::err_nicknameinuse(nick, wanted)], None);
+    }
+
+    match engine.change_nickname(session_id, wanted).await {
+        Ok(_) => (vec![], Some(wanted.clone())),
+        Err(EngineError::NicknameInUse(_)) => {
+            (vec![formatter::err_nicknameinuse(nick, wanted)], None)
+        }
+        Err(e) => (
+            vec![format!(
+                ":{} NOTICE {} :NICK failed: {}",
+                formatter::server_name(),
+                nick,
+                e
+            )],
+            None,
+        ),
+    }
+}
+
 /// Handle IRC AWAY command: AWAY [:message] / AWAY (no params = back)
 async fn handle_away(
     engine: &ChatEngine,
@@ -655,6 +1268,7 @@ async fn handle_invite(
     db: &SqlitePool,
     _session_id: SessionId,
     nick: &str,
+    default_server: &str,
     msg: &IrcMessage,
 ) -> Vec<String> {
     let sn = formatter::server_name();
@@ -668,7 +1282,8 @@ async fn handle_invite(
         return vec![formatter::err_nosuchchannel(nick, target_channel)];
     }
 
-    let (server_id, channel_name) = commands::parse_irc_channel(engine, target_channel);
+    let (server_id, channel_name) =
+        commands::parse_irc_channel(engine, default_server, target_channel);
 
     // Resolve target nickname → session_id
     let target_sid = match engine.get_session_id_by_nick(target_nick) {
@@ -683,11 +1298,14 @@ async fn handle_invite(
     };
 
     // Join target to the channel
-    if let Err(e) = engine.join_channel(target_sid, &server_id, &channel_name) {
+    if let Err(e) = engine
+        .join_channel(target_sid, &server_id, &channel_name)
+        .await
+    {
         return vec![format!(":{sn} NOTICE {nick} :INVITE failed: {e}")];
     }
 
-    let irc_channel = commands::to_irc_channel(engine, &server_id, &channel_name);
+    let irc_channel = commands::to_irc_channel(engine, default_server, &server_id, &channel_name);
     vec![format!(":{sn} 341 {nick} {target_nick} {irc_channel}")]
 }
 
@@ -696,6 +1314,7 @@ async fn handle_whois(
     engine: &ChatEngine,
     db: &SqlitePool,
     nick: &str,
+    default_server: &str,
     msg: &IrcMessage,
 ) -> Vec<String> {
     let Some(target) = msg.params.first().or(msg.params.get(1)) else {
@@ -718,7 +1337,7 @@ async fn handle_whois(
     if !channels.is_empty() {
         let irc_names: Vec<String> = channels
             .iter()
-            .map(|(sid, cname)| to_irc_channel(engine, sid, cname))
+            .map(|(sid, cname)| to_irc_channel(engine, default_server, sid, cname))
             .collect();
         lines.push(formatter::rpl_whoischannels(
             nick,
@@ -727,6 +1346,20 @@ async fn handle_whois(
         ));
     }
 
+    // 378 RPL_WHOISHOST — the real client address, shown only to the user and system admins
+    if let Some(host) = engine
+        .get_session(target_sid)
+        .and_then(|s| s.client_host.clone())
+        && can_see_client_host(engine, db, nick, target_sid).await
+    {
+        lines.push(formatter::rpl_whoishost(
+            nick,
+            target,
+            &host.0,
+            &host.1.to_string(),
+        ));
+    }
+
     // 301 RPL_AWAY — if the target has an away/idle status with a custom message
     if let Some(session) = engine.get_session(target_sid)
         && let Some(ref uid) = session.user_id
@@ -741,18 +1374,35 @@ async fn handle_whois(
     lines
 }
 
+/// Whether the WHOIS requester may see the target's connecting address.
+async fn can_see_client_host(
+    engine: &ChatEngine,
+    db: &SqlitePool,
+    requester: &str,
+    target_sid: SessionId,
+) -> bool {
+    let Some(requester_sid) = engine.get_session_id_by_nick(requester) else {
+        return false;
+    };
+    if requester_sid == target_sid {
+        return true;
+    }
+    match engine.get_session_user_id(requester_sid) {
+        Some(uid) => servers::is_system_admin(db, &uid).await.unwrap_or(false),
+        None => false,
+    }
+}
+
 /// Determine the IRC prefix character (@, +, or none) for a user in a server.
 /// @ = operator (MANAGE_CHANNELS, KICK_MEMBERS, BAN_MEMBERS, or ADMINISTRATOR)
 /// + = voice (MANAGE_MESSAGES but not operator-level)
 async fn irc_prefix_for_user(engine: &ChatEngine, server_id: &str, user_id: &str) -> &'static str {
-    let perms = engine.get_effective_permissions(server_id, None, user_id).await;
-    if perms.contains(Permissions::ADMINISTRATOR)
-        || perms.contains(Permissions::MANAGE_CHANNELS)
-        || perms.contains(Permissions::KICK_MEMBERS)
-        || perms.contains(Permissions::BAN_MEMBERS)
-    {
+    let perms = engine
+        .get_effective_permissions(server_id, None, user_id)
+        .await;
+    if modes::is_op_level(perms) {
         "@"
-    } else if perms.contains(Permissions::MANAGE_MESSAGES) {
+    } else if modes::is_voice_level(perms) {
         "+"
     } else {
         ""
@@ -763,14 +1413,16 @@ async fn irc_prefix_for_user(engine: &ChatEngine, server_id: &str, user_id: &str
 async fn handle_names_async(
     engine: &ChatEngine,
     nick: &str,
+    default_server: &str,
     msg: &IrcMessage,
+    shown_status: &mut ShownStatus,
 ) -> Vec<String> {
     let Some(channel_param) = msg.params.first() else {
         return vec![formatter::err_needmoreparams(nick, "NAMES")];
     };
 
-    let (server_id, channel_name) = parse_irc_channel(engine, channel_param);
-    let irc_channel = to_irc_channel(engine, &server_id, &channel_name);
+    let (server_id, channel_name) = parse_irc_channel(engine, default_server, channel_param);
+    let irc_channel = to_irc_channel(engine, default_server, &server_id, &channel_name);
 
     match engine.get_members(&server_id, &channel_name) {
         Ok(member_infos) => {
@@ -778,6 +1430,9 @@ async fn handle_names_async(
             for m in &member_infos {
                 let uid = m.user_id.as_deref().unwrap_or("");
                 let prefix = irc_prefix_for_user(engine, &server_id, uid).await;
+                if !uid.is_empty() {
+                    shown_status.record(&server_id, uid, prefix);
+                }
                 nicks.push(format!("{prefix}{}", m.nickname));
             }
             vec![
@@ -789,10 +1444,12 @@ async fn handle_names_async(
     }
 }
 
-/// Handle IRC WHO command with role-based prefixes (@/+).
+/// Handle IRC WHO command with role-based prefixes (@/+) and away (G) flags.
 async fn handle_who_async(
     engine: &ChatEngine,
+    db: &SqlitePool,
     nick: &str,
+    default_server: &str,
     msg: &IrcMessage,
 ) -> Vec<String> {
     let Some(target) = msg.params.first() else {
@@ -802,16 +1459,26 @@ async fn handle_who_async(
     let mut replies = Vec::new();
 
     if target.starts_with('#') {
-        let (server_id, channel_name) = parse_irc_channel(engine, target);
-        let irc_channel = to_irc_channel(engine, &server_id, &channel_name);
+        let (server_id, channel_name) = parse_irc_channel(engine, default_server, target);
+        let irc_channel = to_irc_channel(engine, default_server, &server_id, &channel_name);
 
         if let Ok(members) = engine.get_members(&server_id, &channel_name) {
+            let user_ids: Vec<String> = members.iter().filter_map(|m| m.user_id.clone()).collect();
+            let away: HashSet<String> = presence::get_presences_for_users(db, &user_ids)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|p| p.status == "idle" || p.status == "dnd")
+                .map(|p| p.user_id)
+                .collect();
+
             for member in &members {
                 let uid = member.user_id.as_deref().unwrap_or("");
                 let prefix = irc_prefix_for_user(engine, &server_id, uid).await;
+                let here = if away.contains(uid) { 'G' } else { 'H' };
                 // RFC 2812: 352 <requestor> <channel> <user> <host> <server> <nick> <H|G>[*][@|+] :<hopcount> <realname>
                 replies.push(format!(
-                    ":{} {} {} {} {} {} {} {} H{prefix} :0 {}",
+                    ":{} {} {} {} {} {} {} {} {here}{prefix} :0 {}",
                     formatter::server_name(),
                     super::numerics::RPL_WHOREPLY,
                     nick,
@@ -825,24 +1492,287 @@ async fn handle_who_async(
             }
         }
 
-        replies.push(format!(
-            ":{} {} {} {} :End of /WHO list",
-            formatter::server_name(),
-            super::numerics::RPL_ENDOFWHO,
-            nick,
-            irc_channel,
-        ));
+        replies.push(format!(
+            ":{} {} {} {} :End of /WHO list",
+            formatter::server_name(),
+            super::numerics::RPL_ENDOFWHO,
+            nick,
+            irc_channel,
+        ));
+    } else {
+        replies.push(format!(
+            ":{} {} {} {} :End of /WHO list",
+            formatter::server_name(),
+            super::numerics::RPL_ENDOFWHO,
+            nick,
+            target,
+        ));
+    }
+
+    replies
+}
+
+/// Messages replayed per channel when reattaching to an always-on session.
+const PLAYBACK_LIMIT: i64 = 200;
+
+/// Build the engine event for a PRIVMSG this client just sent, so it can be
+/// echoed to the other clients attached to the same always-on session.
+fn own_message_event(
+    engine: &ChatEngine,
+    default_server: &str,
+    nick: &str,
+    msg: &IrcMessage,
+) -> Option<ChatEvent> {
+    let [target, text] = msg.params.as_slice() else {
+        return None;
+    };
+    if msg.command != "PRIVMSG" {
+        return None;
+    }
+
+    // CTCP ACTION is stored as /me; other CTCP requests aren't messages
+    let content = match text.strip_prefix("\x01ACTION ") {
+        Some(action) => format!(
+            "/me {}",
+            formatter::irc_to_markdown(action.trim_end_matches('\x01'))
+        ),
+        None if text.starts_with('\x01') => return None,
+        None => formatter::irc_to_markdown(text),
+    };
+
+    let (server_id, target) = if target.starts_with('#') {
+        let (server_id, channel_name) = parse_irc_channel(engine, default_server, target);
+        (Some(server_id), channel_name)
+    } else {
+        (None, target.clone())
+    };
+
+    Some(ChatEvent::Message {
+        id: uuid::Uuid::new_v4(),
+        server_id,
+        from: nick.to_string(),
+        target,
+        content,
+        timestamp: chrono::Utc::now(),
+        avatar_url: None,
+        reply_to: None,
+        attachments: None,
+    })
+}
+
+/// Channel state and missed messages for a client reattaching to an always-on
+/// session: a JOIN, topic, and NAMES burst per channel, then playback of the
+/// messages after the user's read marker.
+async fn resume_lines(
+    engine: &ChatEngine,
+    attachment: &bouncer::Attachment,
+    nick: &str,
+    default_server: &str,
+    caps: &ClientCaps,
+    shown_status: &mut ShownStatus,
+) -> Vec<String> {
+    let session_id = attachment.session_id();
+    let since = attachment
+        .detached_since()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string());
+
+    let mut channels = engine.get_session_channels(session_id);
+    channels.sort();
+
+    let mut lines = Vec::new();
+    for (server_id, channel_name) in channels {
+        let irc_channel = to_irc_channel(engine, default_server, &server_id, &channel_name);
+        let query = |command: &str| IrcMessage {
+            prefix: None,
+            command: command.into(),
+            params: vec![irc_channel.clone()],
+        };
+
+        lines.push(formatter::join(nick, &irc_channel));
+        lines.extend(
+            commands::handle_command(engine, session_id, nick, default_server, &query("TOPIC"))
+                .await,
+        );
+        lines.extend(
+            handle_names_async(engine, nick, default_server, &query("NAMES"), shown_status).await,
+        );
+
+        let messages = match engine
+            .fetch_unread_messages(
+                session_id,
+                &server_id,
+                &channel_name,
+                since.as_deref(),
+                PLAYBACK_LIMIT,
+            )
+            .await
+        {
+            Ok(messages) => messages,
+            Err(e) => {
+                warn!(error = %e, %channel_name, "IRC playback failed");
+                continue;
+            }
+        };
+        lines.extend(playback_lines(
+            engine,
+            nick,
+            default_server,
+            caps,
+            &server_id,
+            &channel_name,
+            &messages,
+        ));
+    }
+    lines
+}
+
+/// Render replayed channel messages.
+///
+/// With `batch`, messages are wrapped in a `chathistory` BATCH; otherwise they
+/// are framed by NOTICEs. Without `server-time`, each message carries its
+/// timestamp as a text prefix.
+fn playback_lines(
+    engine: &ChatEngine,
+    nick: &str,
+    default_server: &str,
+    caps: &ClientCaps,
+    server_id: &str,
+    channel_name: &str,
+    messages: &[HistoryMessage],
+) -> Vec<String> {
+    if messages.is_empty() {
+        return vec![];
+    }
+
+    let sn = formatter::server_name();
+    let irc_channel = to_irc_channel(engine, default_server, server_id, channel_name);
+    let batch_id = caps
+        .batch
+        .then(|| uuid::Uuid::new_v4().simple().to_string());
+
+    let mut lines = Vec::with_capacity(messages.len() + 2);
+    match &batch_id {
+        Some(id) => lines.push(format!(":{sn} BATCH +{id} chathistory {irc_channel}")),
+        None => lines.push(format!(
+            ":{sn} NOTICE {nick} :Replaying {} missed message(s) in {irc_channel}",
+            messages.len()
+        )),
+    }
+
+    for m in messages {
+        let content = if caps.server_time {
+            m.content.clone()
+        } else {
+            playback_content(&m.content, m.timestamp)
+        };
+        let event = ChatEvent::Message {
+            id: m.id,
+            server_id: Some(server_id.to_string()),
+            from: m.from.clone(),
+            target: channel_name.to_string(),
+            content,
+            timestamp: m.timestamp,
+            avatar_url: None,
+            reply_to: None,
+            attachments: None,
+        };
+        for line in event_to_irc_lines(engine, nick, default_server, &event, caps) {
+            lines.push(match &batch_id {
+                Some(id) => with_batch_tag(&line, id),
+                None => line,
+            });
+        }
+    }
+
+    match batch_id {
+        Some(id) => lines.push(format!(":{sn} BATCH -{id}")),
+        None => lines.push(format!(
+            ":{sn} NOTICE {nick} :End of playback for {irc_channel}"
+        )),
+    }
+    lines
+}
+
+/// Prefix replayed message text with its timestamp (for clients without server-time).
+fn playback_content(content: &str, timestamp: chrono::DateTime<chrono::Utc>) -> String {
+    let stamp = timestamp.format("[%Y-%m-%d %H:%M]");
+    match content.strip_prefix("/me ") {
+        Some(action) => format!("/me {stamp} {action}"),
+        None => format!("{stamp} {content}"),
+    }
+}
+
+/// Add a `batch` tag to a (possibly already tagged) IRC line. Lines already in
+/// a nested batch keep their own tag.
+fn with_batch_tag(line: &str, batch_id: &str) -> String {
+    if line.starts_with("@batch=") {
+        return line.to_string();
+    }
+    match line.strip_prefix('@') {
+        Some(rest) => format!("@batch={batch_id};{rest}"),
+        None => format!("@batch={batch_id} {line}"),
+    }
+}
+
+/// Capabilities listed in reply to CAP LS; version 302 clients also get values.
+fn cap_ls(cap_302: bool, max_message_length: usize) -> String {
+    if cap_302 {
+        SUPPORTED_CAPS
+            .replace("sasl", &format!("sasl={}", sasl::MECHANISMS))
+            .replace(
+                "draft/multiline",
+                &format!(
+                    "draft/multiline={}",
+                    multiline::cap_value(max_message_length)
+                ),
+            )
     } else {
-        replies.push(format!(
-            ":{} {} {} {} :End of /WHO list",
-            formatter::server_name(),
-            super::numerics::RPL_ENDOFWHO,
-            nick,
-            target,
-        ));
+        SUPPORTED_CAPS.to_string()
     }
+}
 
-    replies
+/// Numeric replies for a SASL step. A successful login is recorded in `state`.
+fn sasl_reply_lines(nick: &str, reply: SaslReply, state: &mut RegState) -> Vec<String> {
+    use super::numerics::{
+        ERR_SASLABORTED, ERR_SASLFAIL, RPL_LOGGEDIN, RPL_SASLMECHS, RPL_SASLSUCCESS,
+    };
+    let sn = formatter::server_name();
+    match reply {
+        SaslReply::Challenge(data) => vec![format!("AUTHENTICATE {data}")],
+        SaslReply::Success { user_id, account } => {
+            let lines = vec![
+                format!(
+                    ":{sn} {RPL_LOGGEDIN} {nick} {nick}!{account}@concord {account} :You are now logged in as {account}"
+                ),
+                format!(":{sn} {RPL_SASLSUCCESS} {nick} :SASL authentication successful"),
+            ];
+            if let RegState::Unregistered {
+                account: ref mut logged_in,
+                ..
+            } = *state
+            {
+                *logged_in = Some((user_id, account));
+            }
+            lines
+        }
+        SaslReply::Failure => {
+            vec![format!(
+                ":{sn} {ERR_SASLFAIL} {nick} :SASL authentication failed"
+            )]
+        }
+        SaslReply::Aborted => {
+            vec![format!(
+                ":{sn} {ERR_SASLABORTED} {nick} :SASL authentication aborted"
+            )]
+        }
+        SaslReply::UnknownMechanism => vec![
+            format!(
+                ":{sn} {RPL_SASLMECHS} {nick} {} :are available SASL mechanisms",
+                sasl::MECHANISMS
+            ),
+            format!(":{sn} {ERR_SASLFAIL} {nick} :SASL authentication failed"),
+        ],
+    }
 }
 
 /// Build an IRCv3 tag prefix string based on event metadata and negotiated caps.
@@ -875,11 +1805,33 @@ fn build_tag_prefix(caps: &ClientCaps, event: &ChatEvent) -> String {
 fn event_to_irc_lines(
     engine: &ChatEngine,
     my_nick: &str,
+    default_server: &str,
     event: &ChatEvent,
     caps: &ClientCaps,
 ) -> Vec<String> {
     let tag_prefix = build_tag_prefix(caps, event);
-    let mut lines = event_to_irc_lines_inner(engine, my_nick, event);
+
+    // Multi-line and long messages go out as one draft/multiline batch
+    if caps.multiline
+        && caps.batch
+        && let ChatEvent::Message {
+            from, attachments, ..
+        } = event
+        && let Some((irc_target, text)) = message_text(engine, default_server, event)
+        && !text.starts_with("/me ")
+        && multiline::needs_split(from, &irc_target, &text)
+    {
+        let mut lines = multiline::batch_lines(from, &irc_target, &text, &tag_prefix);
+        for att in attachments.iter().flatten() {
+            lines.push(format!(
+                "{tag_prefix}{}",
+                formatter::privmsg(from, &irc_target, &att.url)
+            ));
+        }
+        return lines;
+    }
+
+    let mut lines = event_to_irc_lines_inner(engine, my_nick, default_server, event);
     if !tag_prefix.is_empty() {
         for line in &mut lines {
             line.insert_str(0, &tag_prefix);
@@ -888,37 +1840,57 @@ fn event_to_irc_lines(
     lines
 }
 
+/// The IRC target and display text of a message event, with reply context.
+fn message_text(
+    engine: &ChatEngine,
+    default_server: &str,
+    event: &ChatEvent,
+) -> Option<(String, String)> {
+    let ChatEvent::Message {
+        server_id,
+        target,
+        content,
+        reply_to,
+        ..
+    } = event
+    else {
+        return None;
+    };
+    let irc_target = if target.starts_with('#') {
+        let sid = server_id.as_deref().unwrap_or(DEFAULT_SERVER_ID);
+        to_irc_channel(engine, default_server, sid, target)
+    } else {
+        target.clone()
+    };
+    // Build display content with reply context prefix
+    let content = formatter::markdown_to_irc(content);
+    let display = if let Some(reply) = reply_to {
+        format!(
+            "[re: {} \"{}\"] {}",
+            reply.from, reply.content_preview, content
+        )
+    } else {
+        content
+    };
+    Some((irc_target, display))
+}
+
 /// Inner function that produces raw IRC lines without tags.
-fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEvent) -> Vec<String> {
+fn event_to_irc_lines_inner(
+    engine: &ChatEngine,
+    my_nick: &str,
+    default_server: &str,
+    event: &ChatEvent,
+) -> Vec<String> {
     match event {
         ChatEvent::Message {
-            server_id,
-            from,
-            target,
-            content,
-            reply_to,
-            attachments,
-            ..
+            from, attachments, ..
         } => {
-            let irc_target = if target.starts_with('#') {
-                let sid = server_id.as_deref().unwrap_or(DEFAULT_SERVER_ID);
-                to_irc_channel(engine, sid, target)
-            } else {
-                target.clone()
-            };
-            // Build display content with reply context prefix
-            let display = if let Some(reply) = reply_to {
-                format!("[re: {} \"{}\"] {}", reply.from, reply.content_preview, content)
-            } else {
-                content.clone()
+            let Some((irc_target, display)) = message_text(engine, default_server, event) else {
+                return vec![];
             };
-            let mut lines = Vec::new();
-            // Convert /me prefix to CTCP ACTION
-            if let Some(action) = display.strip_prefix("/me ") {
-                lines.push(formatter::ctcp_action(from, &irc_target, action));
-            } else {
-                lines.push(formatter::privmsg(from, &irc_target, &display));
-            }
+            // One PRIVMSG per line of text (split when too long); /me becomes CTCP ACTION
+            let mut lines = multiline::privmsg_lines(from, &irc_target, &display);
             // Append attachment URLs as separate messages
             if let Some(atts) = attachments {
                 for att in atts {
@@ -933,7 +1905,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
             channel,
             ..
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
             vec![formatter::join(nickname, &irc_channel)]
         }
         ChatEvent::Part {
@@ -942,7 +1914,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
             channel,
             reason,
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
             vec![formatter::part(nickname, &irc_channel, reason.as_deref())]
         }
         ChatEvent::Quit { nickname, reason } => {
@@ -954,7 +1926,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
             set_by,
             topic,
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
             vec![formatter::topic_change(set_by, &irc_channel, topic)]
         }
         ChatEvent::NickChange { old_nick, new_nick } => {
@@ -965,7 +1937,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
             channel,
             members,
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
             let owner_id = engine.get_server_owner_id(server_id);
             let nicks: Vec<String> = members
                 .iter()
@@ -988,7 +1960,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
             channel,
             topic,
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
             if topic.is_empty() {
                 vec![formatter::rpl_notopic(my_nick, &irc_channel)]
             } else {
@@ -1016,7 +1988,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
         ChatEvent::MessageEdit {
             server_id, channel, ..
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
             vec![format!(
                 ":{} NOTICE {} :* A message was edited in {}",
                 formatter::server_name(),
@@ -1028,7 +2000,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
         ChatEvent::MessageDelete {
             server_id, channel, ..
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
             vec![format!(
                 ":{} NOTICE {} :* A message was deleted in {}",
                 formatter::server_name(),
@@ -1046,7 +2018,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
             emoji,
             ..
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
             vec![formatter::ctcp_action(nickname, &irc_channel, &format!("reacted with {emoji}"))]
         }
         ChatEvent::ReactionRemove {
@@ -1056,7 +2028,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
             emoji,
             ..
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
             vec![formatter::ctcp_action(nickname, &irc_channel, &format!("removed reaction {emoji}"))]
         }
         // Typing indicators are not sent to IRC
@@ -1069,7 +2041,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
             channel,
             pin,
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
             vec![format!(
                 ":{} NOTICE {} :\u{1f4cc} {} pinned a message from {}",
                 formatter::server_name(),
@@ -1081,7 +2053,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
         ChatEvent::MessageUnpin {
             server_id, channel, ..
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
             vec![format!(
                 ":{} NOTICE {} :\u{1f4cc} Message unpinned in {}",
                 formatter::server_name(),
@@ -1095,7 +2067,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
             parent_channel,
             thread,
         } => {
-            let irc_channel = to_irc_channel(engine, server_id, parent_channel);
+            let irc_channel = to_irc_channel(engine, default_server, server_id, parent_channel);
             vec![format!(
                 ":{} NOTICE {} :\u{1f9f5} New thread: {}",
                 formatter::server_name(),
@@ -1128,26 +2100,123 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
                 reason_text
             )]
         }
-        ChatEvent::MemberBan { server_id: _, user_id: _, banned_by, reason } => {
+        ChatEvent::MemberBan {
+            banned_by,
+            reason,
+            expires_at,
+            ..
+        } => {
             let reason_text = reason.as_deref().unwrap_or("No reason given");
+            let until = expires_at
+                .as_deref()
+                .map(|at| format!(" until {at}"))
+                .unwrap_or_default();
             vec![format!(
-                ":{} NOTICE {} :{} banned a member: {}",
+                ":{} NOTICE {} :{} banned a member{}: {}",
                 formatter::server_name(),
                 my_nick,
                 banned_by,
+                until,
                 reason_text
             )]
         }
         ChatEvent::MemberUnban { .. } => vec![],
         ChatEvent::MemberTimeout { .. } => vec![],
-        ChatEvent::SlowModeUpdate { .. } => vec![],
+        // Channel flags are echoed as MODE changes (bans and roles: see mode_echo_lines)
+        ChatEvent::SlowModeUpdate {
+            server_id,
+            channel,
+            seconds,
+        } => {
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
+            if *seconds > 0 {
+                let secs = seconds.to_string();
+                vec![formatter::channel_mode(None, &irc_channel, "+N", &[&secs])]
+            } else {
+                vec![formatter::channel_mode(None, &irc_channel, "-N", &[])]
+            }
+        }
+        ChatEvent::ChannelPrivateUpdate {
+            server_id,
+            channel,
+            is_private,
+            set_by,
+        } => {
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
+            let change = if *is_private { "+s" } else { "-s" };
+            vec![formatter::channel_mode(Some(set_by), &irc_channel, change, &[])]
+        }
+        ChatEvent::ReadOnlyUpdate {
+            server_id,
+            channel,
+            is_read_only,
+            set_by,
+        } => {
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
+            let change = if *is_read_only { "+m" } else { "-m" };
+            vec![formatter::channel_mode(Some(set_by), &irc_channel, change, &[])]
+        }
+        ChatEvent::TopicLockUpdate {
+            server_id,
+            channel,
+            topic_locked,
+            set_by,
+        } => {
+            let irc_channel = to_irc_channel(engine, default_server, server_id, channel);
+            let change = if *topic_locked { "+t" } else { "-t" };
+            vec![formatter::channel_mode(Some(set_by), &irc_channel, change, &[])]
+        }
         ChatEvent::NsfwUpdate { .. } => vec![],
         ChatEvent::BulkMessageDelete { .. } => vec![],
         ChatEvent::AuditLogEntries { .. } => vec![],
-        ChatEvent::BanList { .. } => vec![],
+        ChatEvent::BanList { .. } | ChatEvent::BulkBanResult { .. } => vec![],
         ChatEvent::AutomodRuleList { .. } => vec![],
         ChatEvent::AutomodRuleUpdate { .. } => vec![],
         ChatEvent::AutomodRuleDelete { .. } => vec![],
+        ChatEvent::AutomodQueue { .. } => vec![],
+        ChatEvent::AutomodQueueUpdate { .. } => vec![],
+        ChatEvent::ServerLockdown { .. } => vec![],
+        ChatEvent::ServerQuarantine {
+            server_id,
+            quarantined,
+            ..
+        } => {
+            let server = engine
+                .get_server_name(server_id)
+                .unwrap_or_else(|| server_id.clone());
+            let text = if *quarantined {
+                format!("{server} has been quarantined by the instance administrators and is read-only")
+            } else {
+                format!("{server} is no longer quarantined")
+            };
+            vec![formatter::notice(services::SERVICE_NICK, my_nick, &text)]
+        }
+        ChatEvent::ReportList { .. } => vec![],
+        // Reporters hear back from services when a moderator resolves their report
+        ChatEvent::ReportUpdate { server_id, report } if report.status == "resolved" => {
+            let server = engine
+                .get_server_name(server_id)
+                .unwrap_or_else(|| server_id.clone());
+            vec![formatter::notice(
+                services::SERVICE_NICK,
+                my_nick,
+                &services::report_resolution_text(&server, report),
+            )]
+        }
+        ChatEvent::ReportUpdate { .. } => vec![],
+        ChatEvent::MemberWarn {
+            server_id, reason, ..
+        } => {
+            let server = engine
+                .get_server_name(server_id)
+                .unwrap_or_else(|| server_id.clone());
+            vec![formatter::notice(
+                services::SERVICE_NICK,
+                my_nick,
+                &format!("You have been warned in {server}: {reason}"),
+            )]
+        }
+        ChatEvent::InfractionList { .. } | ChatEvent::EscalationPolicyList { .. } => vec![],
         // These events are WebSocket-specific and don't map to IRC
         ChatEvent::ChannelList { .. }
         | ChatEvent::History { .. }
@@ -1162,6 +2231,7 @@ fn event_to_irc_lines_inner(engine: &ChatEngine, my_nick: &str, event: &ChatEven
         | ChatEvent::CategoryDelete { .. }
         | ChatEvent::ChannelReorder { .. }
         | ChatEvent::PresenceUpdate { .. }
+        | ChatEvent::MonitorStatus { .. }
         | ChatEvent::PresenceList { .. }
         | ChatEvent::UserProfile { .. }
         | ChatEvent::ServerNicknameUpdate { .. }
@@ -1228,7 +2298,7 @@ mod tests {
 
     /// Test helper — calls the inner (tag-free) event formatter.
     fn event_to_irc_lines(engine: &ChatEngine, my_nick: &str, event: &ChatEvent) -> Vec<String> {
-        event_to_irc_lines_inner(engine, my_nick, event)
+        event_to_irc_lines_inner(engine, my_nick, DEFAULT_SERVER_ID, event)
     }
 
     // ── Message event ──
@@ -1807,11 +2877,25 @@ mod tests {
                 user_id: "uid1".into(),
                 banned_by: "admin".into(),
                 reason: Some("Spam".into()),
+                expires_at: None,
             },
         );
         assert_eq!(lines.len(), 1);
         assert!(lines[0].contains("banned"));
         assert!(lines[0].contains("Spam"));
+
+        let lines = event_to_irc_lines(
+            &engine,
+            "viewer",
+            &ChatEvent::MemberBan {
+                server_id: DEFAULT_SERVER_ID.into(),
+                user_id: "uid1".into(),
+                banned_by: "admin".into(),
+                reason: None,
+                expires_at: Some("2026-03-01 00:00:00".into()),
+            },
+        );
+        assert!(lines[0].contains("until 2026-03-01 00:00:00"));
     }
 
     #[test]
@@ -1829,9 +2913,9 @@ mod tests {
     }
 
     #[test]
-    fn test_slow_mode_update_is_silent() {
+    fn test_slow_mode_update_renders_mode() {
         let engine = test_engine();
-        let lines = event_to_irc_lines(
+        let set = event_to_irc_lines(
             &engine,
             "viewer",
             &ChatEvent::SlowModeUpdate {
@@ -1840,7 +2924,58 @@ mod tests {
                 seconds: 5,
             },
         );
-        assert!(lines.is_empty());
+        assert_eq!(set, vec![":concord MODE #general +N 5"]);
+
+        let cleared = event_to_irc_lines(
+            &engine,
+            "viewer",
+            &ChatEvent::SlowModeUpdate {
+                server_id: DEFAULT_SERVER_ID.into(),
+                channel: "#general".into(),
+                seconds: 0,
+            },
+        );
+        assert_eq!(cleared, vec![":concord MODE #general -N"]);
+    }
+
+    #[test]
+    fn test_channel_flag_updates_render_mode() {
+        let engine = test_engine();
+        let lines = event_to_irc_lines(
+            &engine,
+            "viewer",
+            &ChatEvent::ReadOnlyUpdate {
+                server_id: DEFAULT_SERVER_ID.into(),
+                channel: "#general".into(),
+                is_read_only: true,
+                set_by: "admin".into(),
+            },
+        );
+        assert_eq!(lines, vec![":admin!admin@concord MODE #general +m"]);
+
+        let lines = event_to_irc_lines(
+            &engine,
+            "viewer",
+            &ChatEvent::TopicLockUpdate {
+                server_id: DEFAULT_SERVER_ID.into(),
+                channel: "#general".into(),
+                topic_locked: false,
+                set_by: "admin".into(),
+            },
+        );
+        assert_eq!(lines, vec![":admin!admin@concord MODE #general -t"]);
+
+        let lines = event_to_irc_lines(
+            &engine,
+            "viewer",
+            &ChatEvent::ChannelPrivateUpdate {
+                server_id: DEFAULT_SERVER_ID.into(),
+                channel: "#general".into(),
+                is_private: true,
+                set_by: "admin".into(),
+            },
+        );
+        assert_eq!(lines, vec![":admin!admin@concord MODE #general +s"]);
     }
 
     #[test]
@@ -1910,6 +3045,330 @@ mod tests {
         }
     }
 
+    // ── Default server binding ──
+
+    #[test]
+    fn test_bound_server_channels_render_bare() {
+        let engine = test_engine();
+        let event = ChatEvent::Message {
+            id: Uuid::new_v4(),
+            server_id: Some("srv-guild".into()),
+            from: "alice".into(),
+            target: "#general".into(),
+            content: "hi".into(),
+            timestamp: Utc::now(),
+            avatar_url: None,
+            reply_to: None,
+            attachments: None,
+        };
+        let lines = event_to_irc_lines_inner(&engine, "viewer", "srv-guild", &event);
+        assert_eq!(lines, vec![":alice!alice@concord PRIVMSG #general hi"]);
+    }
+
+    #[test]
+    fn test_bare_channels_resolve_to_bound_server() {
+        let engine = test_engine();
+        assert_eq!(
+            parse_irc_channel(&engine, "srv-guild", "#general"),
+            ("srv-guild".to_string(), "#general".to_string())
+        );
+        assert_eq!(
+            parse_irc_channel(&engine, DEFAULT_SERVER_ID, "#general"),
+            (DEFAULT_SERVER_ID.to_string(), "#general".to_string())
+        );
+    }
+
+    #[test]
+    fn test_server_binding_from_user_and_pass() {
+        assert_eq!(
+            commands::username_server("alice@my-guild"),
+            Some("my-guild")
+        );
+        assert_eq!(commands::username_server("alice"), None);
+        assert_eq!(commands::username_server("alice@"), None);
+
+        assert_eq!(
+            commands::split_pass_server("my-guild/abc123"),
+            (Some("my-guild"), "abc123")
+        );
+        assert_eq!(
+            commands::split_pass_server("a/b guild/abc123"),
+            (Some("a/b guild"), "abc123")
+        );
+        assert_eq!(commands::split_pass_server("abc123"), (None, "abc123"));
+    }
+
+    // ── WHOIS client host ──
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_whois_host_visible_only_to_self() {
+        let engine = test_engine();
+        let pool = crate::db::pool::create_pool("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::pool::run_migrations(&pool).await.unwrap();
+
+        let (target_sid, _rx1) = engine
+            .connect(None, "webuser".into(), Protocol::Irc, None)
+            .unwrap();
+        let (_other_sid, _rx2) = engine
+            .connect(None, "other".into(), Protocol::Irc, None)
+            .unwrap();
+        engine.set_client_host(
+            target_sid,
+            ("user.example.net".into(), "203.0.113.7".parse().unwrap()),
+        );
+
+        let whois = IrcMessage {
+            prefix: None,
+            command: "WHOIS".into(),
+            params: vec!["webuser".into()],
+        };
+        let own = handle_whois(&engine, &pool, "webuser", DEFAULT_SERVER_ID, &whois).await;
+        assert!(own.iter().any(|l| {
+            l.contains(" 378 webuser webuser :is connecting from *@user.example.net 203.0.113.7")
+        }));
+
+        let theirs = handle_whois(&engine, &pool, "other", DEFAULT_SERVER_ID, &whois).await;
+        assert!(!theirs.iter().any(|l| l.contains(" 378 ")));
+    }
+
+    // ── SASL ──
+
+    #[test]
+    fn test_cap_ls_advertises_sasl_mechanisms_to_302_clients() {
+        assert_eq!(cap_ls(false, 4000), SUPPORTED_CAPS);
+        assert!(cap_ls(true, 4000).contains("sasl=PLAIN,EXTERNAL,SCRAM-SHA-256"));
+        assert!(cap_ls(true, 4000).contains("server-time"));
+        assert!(cap_ls(true, 4000).contains("draft/multiline=max-bytes=4000,max-lines=100"));
+    }
+
+    #[test]
+    fn test_sasl_reply_lines() {
+        let sn = formatter::server_name();
+        let mut state = RegState::Unregistered {
+            pass: None,
+            nick: None,
+            user_received: false,
+            server: None,
+            account: None,
+        };
+
+        let lines = sasl_reply_lines("*", SaslReply::UnknownMechanism, &mut state);
+        assert_eq!(
+            lines[0],
+            format!(":{sn} 908 * PLAIN,EXTERNAL,SCRAM-SHA-256 :are available SASL mechanisms")
+        );
+        assert_eq!(lines[1], format!(":{sn} 904 * :SASL authentication failed"));
+
+        let lines = sasl_reply_lines(
+            "alice",
+            SaslReply::Success {
+                user_id: "u1".into(),
+                account: "alice".into(),
+            },
+            &mut state,
+        );
+        assert!(lines[0].starts_with(&format!(":{sn} 900 alice ")));
+        assert_eq!(
+            lines[1],
+            format!(":{sn} 903 alice :SASL authentication successful")
+        );
+        assert!(matches!(
+            state,
+            RegState::Unregistered { account: Some((ref uid, ref name)), .. }
+                if uid == "u1" && name == "alice"
+        ));
+    }
+
+    // ── Always-on playback ──
+
+    fn history(content: &str) -> HistoryMessage {
+        HistoryMessage {
+            id: Uuid::new_v4(),
+            from: "alice".into(),
+            content: content.into(),
+            timestamp: chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 3, 1, 9, 5, 0).unwrap(),
+            edited_at: None,
+            reply_to: None,
+            reactions: None,
+            attachments: None,
+            embeds: None,
+        }
+    }
+
+    #[test]
+    fn test_with_batch_tag() {
+        assert_eq!(
+            with_batch_tag(":a!a@concord PRIVMSG #c hi", "b1"),
+            "@batch=b1 :a!a@concord PRIVMSG #c hi"
+        );
+        assert_eq!(
+            with_batch_tag("@time=x :a!a@concord PRIVMSG #c hi", "b1"),
+            "@batch=b1;time=x :a!a@concord PRIVMSG #c hi"
+        );
+    }
+
+    #[test]
+    fn test_playback_content_prefixes_timestamp() {
+        let ts = history("").timestamp;
+        assert_eq!(playback_content("hello", ts), "[2026-03-01 09:05] hello");
+        assert_eq!(
+            playback_content("/me waves", ts),
+            "/me [2026-03-01 09:05] waves"
+        );
+    }
+
+    #[test]
+    fn test_playback_lines_notice_framing() {
+        let engine = test_engine();
+        let sn = formatter::server_name();
+        let lines = playback_lines(
+            &engine,
+            "viewer",
+            DEFAULT_SERVER_ID,
+            &ClientCaps::default(),
+            DEFAULT_SERVER_ID,
+            "#general",
+            &[history("hi")],
+        );
+        assert_eq!(
+            lines,
+            vec![
+                format!(":{sn} NOTICE viewer :Replaying 1 missed message(s) in #general"),
+                ":alice!alice@concord PRIVMSG #general :[2026-03-01 09:05] hi".to_string(),
+                format!(":{sn} NOTICE viewer :End of playback for #general"),
+            ]
+        );
+        assert!(
+            playback_lines(
+                &engine,
+                "viewer",
+                DEFAULT_SERVER_ID,
+                &ClientCaps::default(),
+                DEFAULT_SERVER_ID,
+                "#general",
+                &[],
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_playback_lines_batch_with_server_time() {
+        let engine = test_engine();
+        let caps = ClientCaps {
+            server_time: true,
+            batch: true,
+            ..Default::default()
+        };
+        let lines = playback_lines(
+            &engine,
+            "viewer",
+            DEFAULT_SERVER_ID,
+            &caps,
+            DEFAULT_SERVER_ID,
+            "#general",
+            &[history("hi")],
+        );
+        assert_eq!(lines.len(), 3);
+
+        let open = lines[0].split(' ').collect::<Vec<_>>();
+        assert_eq!(open[1], "BATCH");
+        assert_eq!(open[3..], ["chathistory", "#general"]);
+        let id = open[2].strip_prefix('+').unwrap();
+
+        assert!(lines[1].starts_with(&format!("@batch={id};time=2026-03-01T09:05:00")));
+        assert!(lines[1].ends_with("PRIVMSG #general hi"));
+        assert!(lines[2].ends_with(&format!("BATCH -{id}")));
+    }
+
+    #[test]
+    fn test_multiline_message_is_batched_when_negotiated() {
+        let engine = test_engine();
+        let event = ChatEvent::Message {
+            id: Uuid::new_v4(),
+            server_id: Some(DEFAULT_SERVER_ID.to_string()),
+            from: "alice".into(),
+            target: "#general".into(),
+            content: "line one\nline two".into(),
+            timestamp: chrono::Utc::now(),
+            avatar_url: None,
+            reply_to: None,
+            attachments: None,
+        };
+
+        let plain = super::event_to_irc_lines(
+            &engine,
+            "viewer",
+            DEFAULT_SERVER_ID,
+            &event,
+            &ClientCaps::default(),
+        );
+        assert_eq!(
+            plain,
+            vec![
+                ":alice!alice@concord PRIVMSG #general :line one",
+                ":alice!alice@concord PRIVMSG #general :line two",
+            ]
+        );
+
+        let caps = ClientCaps {
+            message_tags: true,
+            batch: true,
+            multiline: true,
+            ..Default::default()
+        };
+        let batched =
+            super::event_to_irc_lines(&engine, "viewer", DEFAULT_SERVER_ID, &event, &caps);
+        assert_eq!(batched.len(), 4);
+        assert!(batched[0].starts_with("@msgid="));
+        assert!(batched[0].contains(" BATCH +"));
+        assert!(batched[1].ends_with("PRIVMSG #general :line one"));
+        assert!(batched[2].ends_with("PRIVMSG #general :line two"));
+        assert!(batched[3].contains(" BATCH -"));
+
+        // The batch nests inside a chathistory batch during playback
+        let nested = with_batch_tag(&batched[1], "outer");
+        assert_eq!(nested, batched[1]);
+    }
+
+    #[test]
+    fn test_messages_translate_markdown_formatting() {
+        let engine = test_engine();
+        let event = ChatEvent::Message {
+            id: Uuid::new_v4(),
+            server_id: Some(DEFAULT_SERVER_ID.to_string()),
+            from: "alice".into(),
+            target: "#general".into(),
+            content: "**hi** ||spoiler||".into(),
+            timestamp: chrono::Utc::now(),
+            avatar_url: None,
+            reply_to: None,
+            attachments: None,
+        };
+        let lines = super::event_to_irc_lines(
+            &engine,
+            "viewer",
+            DEFAULT_SERVER_ID,
+            &event,
+            &ClientCaps::default(),
+        );
+        assert_eq!(
+            lines,
+            vec![":alice!alice@concord PRIVMSG #general :\x02hi\x02 \x0301,01spoiler\x03"]
+        );
+
+        // Formatting typed on IRC is stored as Markdown
+        let msg = IrcMessage::parse("PRIVMSG #general :\x02hi\x02 \x1Dthere").unwrap();
+        let Some(ChatEvent::Message { content, .. }) =
+            own_message_event(&engine, DEFAULT_SERVER_ID, "alice", &msg)
+        else {
+            panic!("expected a message event");
+        };
+        assert_eq!(content, "**hi** *there*");
+    }
+
     // ── send_line helper test ──
 
     #[test]
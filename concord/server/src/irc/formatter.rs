@@ -1,10 +1,19 @@
+use super::connection::line_length;
 use super::numerics::*;
 use super::parser::IrcMessage;
+use crate::engine::chat_engine::MONITOR_LIMIT;
+use crate::engine::error::EngineError;
+use crate::engine::validation::{
+    MAX_CHANNEL_NAME_LENGTH, MAX_NICKNAME_LENGTH, MAX_SERVER_NAME_LENGTH, MAX_TOPIC_LENGTH,
+};
 
 /// Helper to build IRC reply lines. All functions return formatted strings
 /// ready to send (caller appends \r\n).
 const SERVER_NAME: &str = "concord";
 
+/// Network name advertised in ISUPPORT.
+const NETWORK_NAME: &str = "Concord";
+
 pub fn server_name() -> &'static str {
     SERVER_NAME
 }
@@ -58,6 +67,68 @@ pub fn rpl_myinfo(nick: &str) -> String {
     .format()
 }
 
+/// Maximum ISUPPORT tokens per 005 line (keeps each line well under 512 bytes).
+const MAX_ISUPPORT_TOKENS_PER_LINE: usize = 13;
+
+/// Build the RPL_ISUPPORT token list from the engine's real limits.
+///
+/// Channel names are rendered as `#server-name/channel` (see
+/// `commands::parse_irc_channel`), so CHANNELLEN covers the server prefix too.
+pub fn isupport_tokens(max_message_length: usize) -> Vec<String> {
+    let channel_len = 1 + MAX_SERVER_NAME_LENGTH + MAX_CHANNEL_NAME_LENGTH;
+    vec![
+        "CASEMAPPING=ascii".into(),
+        "CHANLIMIT=#:".into(),
+        "CHANMODES=b,,N,mnpst".into(),
+        format!("CHANNELLEN={channel_len}"),
+        "CHANNELSCHEME=#server/channel".into(),
+        "CHANTYPES=#".into(),
+        format!("LINELEN={}", line_length(max_message_length)),
+        "MAXTARGETS=1".into(),
+        format!("MODES={}", super::modes::MAX_MODE_PARAMS),
+        format!("MONITOR={MONITOR_LIMIT}"),
+        format!("MSGLEN={max_message_length}"),
+        format!("NETWORK={NETWORK_NAME}"),
+        format!("NICKLEN={MAX_NICKNAME_LENGTH}"),
+        "PREFIX=(ov)@+".into(),
+        "TARGMAX=JOIN:,PART:,PRIVMSG:1,KICK:1,INVITE:1,WHOIS:1".into(),
+        format!("TOPICLEN={MAX_TOPIC_LENGTH}"),
+        "UTF8ONLY".into(),
+        format!("WATCH={MONITOR_LIMIT}"),
+    ]
+}
+
+/// :concord 005 nick TOKEN=value ... :are supported by this server
+///
+/// Returns one line per batch of tokens, as clients expect multiple 005 lines.
+pub fn rpl_isupport(nick: &str, max_message_length: usize) -> Vec<String> {
+    isupport_tokens(max_message_length)
+        .chunks(MAX_ISUPPORT_TOKENS_PER_LINE)
+        .map(|tokens| {
+            let mut params = Vec::with_capacity(tokens.len() + 2);
+            params.push(nick.to_string());
+            params.extend(tokens.iter().cloned());
+            params.push("are supported by this server".into());
+            IrcMessage::server_reply(SERVER_NAME, RPL_ISUPPORT, params).format()
+        })
+        .collect()
+}
+
+/// :concord 351 nick 0.1.0 concord :Concord IRC-compatible chat server
+pub fn rpl_version(nick: &str) -> String {
+    IrcMessage::server_reply(
+        SERVER_NAME,
+        RPL_VERSION,
+        vec![
+            nick.into(),
+            "0.1.0".into(),
+            SERVER_NAME.into(),
+            "Concord IRC-compatible chat server".into(),
+        ],
+    )
+    .format()
+}
+
 /// :concord 375 nick :- concord Message of the Day -
 pub fn rpl_motdstart(nick: &str) -> String {
     IrcMessage::server_reply(
@@ -145,6 +216,16 @@ pub fn ctcp_action(nick: &str, target: &str, action: &str) -> String {
     .format()
 }
 
+/// :nick!nick@concord NOTICE target :message
+pub fn notice(nick: &str, target: &str, message: &str) -> String {
+    IrcMessage {
+        prefix: Some(format!("{}!{}@{}", nick, nick, SERVER_NAME)),
+        command: "NOTICE".into(),
+        params: vec![target.into(), message.into()],
+    }
+    .format()
+}
+
 /// :concord NOTICE nick :\x01COMMAND response\x01
 pub fn ctcp_reply(nick: &str, command: &str, response: &str) -> String {
     IrcMessage {
@@ -169,6 +250,16 @@ pub fn quit(nick: &str, reason: Option<&str>) -> String {
     .format()
 }
 
+/// :nick!nick@concord AWAY [:message] (away-notify; no message = back)
+pub fn away(nick: &str, message: Option<&str>) -> String {
+    IrcMessage {
+        prefix: Some(format!("{}!{}@{}", nick, nick, SERVER_NAME)),
+        command: "AWAY".into(),
+        params: message.map(|m| vec![m.to_string()]).unwrap_or_default(),
+    }
+    .format()
+}
+
 /// :nick!nick@concord NICK newnick
 pub fn nick_change(old_nick: &str, new_nick: &str) -> String {
     IrcMessage {
@@ -286,6 +377,20 @@ pub fn rpl_whoisserver(requestor: &str, nick: &str) -> String {
     .format()
 }
 
+/// :concord 378 requestor nick :is connecting from *@host ip
+pub fn rpl_whoishost(requestor: &str, nick: &str, host: &str, ip: &str) -> String {
+    IrcMessage::server_reply(
+        SERVER_NAME,
+        RPL_WHOISHOST,
+        vec![
+            requestor.into(),
+            nick.into(),
+            format!("is connecting from *@{host} {ip}"),
+        ],
+    )
+    .format()
+}
+
 /// :concord 319 requestor nick :#channel1 #channel2 ...
 pub fn rpl_whoischannels(requestor: &str, nick: &str, channels: &str) -> String {
     IrcMessage::server_reply(
@@ -316,6 +421,64 @@ pub fn rpl_endofwhois(requestor: &str, nick: &str) -> String {
     .format()
 }
 
+// Modes
+
+/// :nick!nick@concord MODE #channel +b nick!*@*
+///
+/// With no `source` the change is attributed to the server.
+pub fn channel_mode(source: Option<&str>, channel: &str, modes: &str, args: &[&str]) -> String {
+    let prefix = match source {
+        Some(nick) => format!("{}!{}@{}", nick, nick, SERVER_NAME),
+        None => SERVER_NAME.to_string(),
+    };
+    let mut params = vec![channel.to_string(), modes.to_string()];
+    params.extend(args.iter().map(|a| a.to_string()));
+    IrcMessage {
+        prefix: Some(prefix),
+        command: "MODE".into(),
+        params,
+    }
+    .format()
+}
+
+/// :concord 324 nick #channel +nt
+///
+/// `modes` may carry parameters (e.g. `+nN 10`); they become separate params.
+pub fn rpl_channelmodeis(nick: &str, channel: &str, modes: &str) -> String {
+    let mut params = vec![nick.to_string(), channel.to_string()];
+    params.extend(modes.split_whitespace().map(String::from));
+    IrcMessage::server_reply(SERVER_NAME, RPL_CHANNELMODEIS, params).format()
+}
+
+/// :concord 221 nick +
+pub fn rpl_umodeis(nick: &str) -> String {
+    IrcMessage::server_reply(SERVER_NAME, RPL_UMODEIS, vec![nick.into(), "+".into()]).format()
+}
+
+/// :concord 367 nick #channel mask setter
+pub fn rpl_banlist(nick: &str, channel: &str, mask: &str, set_by: &str) -> String {
+    IrcMessage::server_reply(
+        SERVER_NAME,
+        RPL_BANLIST,
+        vec![nick.into(), channel.into(), mask.into(), set_by.into()],
+    )
+    .format()
+}
+
+/// :concord 368 nick #channel :End of channel ban list
+pub fn rpl_endofbanlist(nick: &str, channel: &str) -> String {
+    IrcMessage::server_reply(
+        SERVER_NAME,
+        RPL_ENDOFBANLIST,
+        vec![
+            nick.into(),
+            channel.into(),
+            "End of channel ban list".into(),
+        ],
+    )
+    .format()
+}
+
 // Error replies
 
 /// :concord 401 nick target :No such nick/channel
@@ -358,6 +521,16 @@ pub fn err_nonicknamegiven(nick: &str) -> String {
     .format()
 }
 
+/// :concord 432 nick newnick :Erroneous nickname
+pub fn err_erroneusnickname(nick: &str, wanted: &str) -> String {
+    IrcMessage::server_reply(
+        SERVER_NAME,
+        ERR_ERRONEUSNICKNAME,
+        vec![nick.into(), wanted.into(), "Erroneous nickname".into()],
+    )
+    .format()
+}
+
 /// :concord 433 nick newnick :Nickname is already in use
 pub fn err_nicknameinuse(nick: &str, wanted: &str) -> String {
     IrcMessage::server_reply(
@@ -396,6 +569,34 @@ pub fn err_notregistered() -> String {
     .format()
 }
 
+/// :concord 472 nick c :is unknown mode char to me
+pub fn err_unknownmode(nick: &str, mode: char) -> String {
+    IrcMessage::server_reply(
+        SERVER_NAME,
+        ERR_UNKNOWNMODE,
+        vec![
+            nick.into(),
+            mode.to_string(),
+            "is unknown mode char to me".into(),
+        ],
+    )
+    .format()
+}
+
+/// :concord 482 nick #channel :You're not channel operator
+pub fn err_chanoprivsneeded(nick: &str, channel: &str) -> String {
+    IrcMessage::server_reply(
+        SERVER_NAME,
+        ERR_CHANOPRIVSNEEDED,
+        vec![
+            nick.into(),
+            channel.into(),
+            "You're not channel operator".into(),
+        ],
+    )
+    .format()
+}
+
 /// :concord 461 nick command :Not enough parameters
 pub fn err_needmoreparams(nick: &str, command: &str) -> String {
     IrcMessage::server_reply(
@@ -416,6 +617,48 @@ pub fn err_alreadyregistered(nick: &str) -> String {
     .format()
 }
 
+/// :concord 474 nick #channel :Cannot join channel (+b)
+pub fn err_bannedfromchan(nick: &str, channel: &str) -> String {
+    IrcMessage::server_reply(
+        SERVER_NAME,
+        ERR_BANNEDFROMCHAN,
+        vec![
+            nick.into(),
+            channel.into(),
+            "Cannot join channel (+b)".into(),
+        ],
+    )
+    .format()
+}
+
+/// Map an engine failure on `target` (a channel or nick) to the matching IRC numeric.
+/// Errors with no numeric equivalent become a server NOTICE carrying the message.
+pub fn err_engine(nick: &str, target: &str, err: &EngineError) -> String {
+    let reply = |numeric: &str, text: String| {
+        IrcMessage::server_reply(SERVER_NAME, numeric, vec![nick.into(), target.into(), text])
+            .format()
+    };
+    match err {
+        EngineError::NoSuchChannel(_) => err_nosuchchannel(nick, target),
+        EngineError::NoSuchNick(_) => err_nosuchnick(nick, target),
+        EngineError::NotInChannel(_) => err_notonchannel(nick, target),
+        EngineError::NicknameInUse(wanted) => err_nicknameinuse(nick, wanted),
+        EngineError::Banned => err_bannedfromchan(nick, target),
+        EngineError::PermissionDenied { .. } | EngineError::Forbidden(_) => {
+            reply(ERR_CHANOPRIVSNEEDED, err.to_string())
+        }
+        EngineError::CannotSend(_)
+        | EngineError::RateLimited { .. }
+        | EngineError::TimedOut { .. } => reply(ERR_CANNOTSENDTOCHAN, err.to_string()),
+        _ => IrcMessage {
+            prefix: Some(SERVER_NAME.into()),
+            command: "NOTICE".into(),
+            params: vec![nick.into(), err.to_string()],
+        }
+        .format(),
+    }
+}
+
 /// PING :token
 pub fn ping(token: &str) -> String {
     IrcMessage {
@@ -436,6 +679,209 @@ pub fn pong(token: &str) -> String {
     .format()
 }
 
+// ── Markdown <-> mIRC formatting ──
+
+// mIRC formatting control codes
+const BOLD: char = '\x02';
+const COLOR: char = '\x03';
+const HEX_COLOR: char = '\x04';
+const RESET: char = '\x0F';
+const MONOSPACE: char = '\x11';
+const REVERSE: char = '\x16';
+const ITALIC: char = '\x1D';
+const STRIKETHROUGH: char = '\x1E';
+const UNDERLINE: char = '\x1F';
+
+/// Foreground and background of spoilers (black on black), revealed by selecting the text.
+const SPOILER_COLOR: &str = "01,01";
+
+/// Colour of the `>` marking quoted lines (grey).
+const QUOTE_COLOR: &str = "14";
+
+/// Render Concord Markdown as mIRC formatting codes.
+///
+/// Mirrors the web client's renderer: `**bold**`, `*italic*`, `~~strike~~`,
+/// `` `code` ``, `||spoiler||`, fenced code blocks and `>` quotes. Anything
+/// the web client would show literally is left as is.
+pub fn markdown_to_irc(content: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+    for line in content.split('\n') {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            lines.push(if line.is_empty() {
+                String::new()
+            } else {
+                format!("{MONOSPACE}{line}{MONOSPACE}")
+            });
+        } else if let Some(quoted) = line.strip_prefix('>') {
+            let quoted = quoted.strip_prefix(' ').unwrap_or(quoted);
+            lines.push(format!(
+                "{COLOR}{QUOTE_COLOR}>{COLOR} {}",
+                inline_markdown_to_irc(quoted)
+            ));
+        } else {
+            lines.push(inline_markdown_to_irc(line));
+        }
+    }
+    lines.join("\n")
+}
+
+fn inline_markdown_to_irc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let span = match c {
+            '`' => delimited(rest, "`").map(|(code, len)| (wrap(MONOSPACE, code), len)),
+            '*' if rest.starts_with("**") => delimited(rest, "**")
+                .map(|(inner, len)| (wrap(BOLD, &inline_markdown_to_irc(inner)), len)),
+            // Italic only when the closing `*` isn't part of a `**`
+            '*' => delimited(rest, "*")
+                .filter(|(inner, len)| !inner.is_empty() && !rest[*len..].starts_with('*'))
+                .map(|(inner, len)| (wrap(ITALIC, &inline_markdown_to_irc(inner)), len)),
+            '~' if rest.starts_with("~~") => delimited(rest, "~~")
+                .map(|(inner, len)| (wrap(STRIKETHROUGH, &inline_markdown_to_irc(inner)), len)),
+            '|' if rest.starts_with("||") => delimited(rest, "||").map(|(inner, len)| {
+                let inner = inline_markdown_to_irc(inner);
+                (format!("{COLOR}{SPOILER_COLOR}{inner}{COLOR}"), len)
+            }),
+            _ => None,
+        };
+        match span {
+            Some((formatted, len)) => {
+                out.push_str(&formatted);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    out
+}
+
+/// For `rest` starting with `delim`, the text up to the next `delim` and the
+/// length of the whole span including both delimiters.
+fn delimited<'a>(rest: &'a str, delim: &str) -> Option<(&'a str, usize)> {
+    let body = &rest[delim.len()..];
+    let end = body.find(delim)?;
+    Some((&body[..end], end + 2 * delim.len()))
+}
+
+fn wrap(code: char, text: &str) -> String {
+    format!("{code}{text}{code}")
+}
+
+/// Translate mIRC formatting codes in an IRC message into Concord Markdown.
+///
+/// Bold, italic, strikethrough and monospace map to their Markdown spans, and
+/// text coloured the same in front and back becomes a `||spoiler||`. Other
+/// colours, underline and reverse have no Markdown form and are dropped.
+/// Spans are closed at the end of the message so the result is well formed.
+pub fn irc_to_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    // Open spans, innermost last, and whether their opener has been written.
+    // Openers wait for the next visible character so spans hug their text and
+    // empty spans vanish.
+    let mut open: Vec<(char, bool)> = Vec::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            BOLD | ITALIC | STRIKETHROUGH | MONOSPACE => toggle_span(&mut out, &mut open, c),
+            COLOR => {
+                let (fg, bg, len) = parse_color(rest);
+                rest = &rest[len..];
+                let spoiler = fg.is_some() && fg == bg;
+                if spoiler != open.iter().any(|(style, _)| *style == COLOR) {
+                    toggle_span(&mut out, &mut open, COLOR);
+                }
+            }
+            HEX_COLOR => rest = &rest[hex_color_len(rest)..],
+            RESET => close_spans(&mut out, &mut open),
+            UNDERLINE | REVERSE => {}
+            _ => {
+                if !c.is_whitespace() {
+                    for (style, written) in open.iter_mut().filter(|(_, w)| !*w) {
+                        out.push_str(markdown_delimiter(*style));
+                        *written = true;
+                    }
+                }
+                out.push(c);
+            }
+        }
+    }
+    close_spans(&mut out, &mut open);
+    out
+}
+
+fn markdown_delimiter(style: char) -> &'static str {
+    match style {
+        BOLD => "**",
+        ITALIC => "*",
+        STRIKETHROUGH => "~~",
+        MONOSPACE => "`",
+        _ => "||",
+    }
+}
+
+/// Open or close a span. Markdown spans must nest, so spans opened inside
+/// this one are closed first and reopened after it.
+fn toggle_span(out: &mut String, open: &mut Vec<(char, bool)>, style: char) {
+    let Some(idx) = open.iter().position(|(s, _)| *s == style) else {
+        open.push((style, false));
+        return;
+    };
+    let reopen: Vec<char> = open[idx + 1..].iter().map(|(s, _)| *s).collect();
+    let mut closing = open.split_off(idx);
+    close_spans(out, &mut closing);
+    open.extend(reopen.into_iter().map(|style| (style, false)));
+}
+
+/// Close the spans in `open` from the innermost out, skipping unwritten ones.
+fn close_spans(out: &mut String, open: &mut Vec<(char, bool)>) {
+    while let Some((style, written)) = open.pop() {
+        if written {
+            out.push_str(markdown_delimiter(style));
+        }
+    }
+}
+
+/// Parse the `fg[,bg]` numbers after a colour code. Returns them and the
+/// number of bytes they take.
+fn parse_color(s: &str) -> (Option<u8>, Option<u8>, usize) {
+    let digits = |s: &str| s.bytes().take(2).take_while(u8::is_ascii_digit).count();
+    let fg_len = digits(s);
+    if fg_len == 0 {
+        return (None, None, 0);
+    }
+    let fg = s[..fg_len].parse().ok();
+    if let Some(after) = s[fg_len..].strip_prefix(',') {
+        let bg_len = digits(after);
+        if bg_len > 0 {
+            return (fg, after[..bg_len].parse().ok(), fg_len + 1 + bg_len);
+        }
+    }
+    (fg, None, fg_len)
+}
+
+/// Length of the `RRGGBB[,RRGGBB]` after a hex colour code.
+fn hex_color_len(s: &str) -> usize {
+    let is_hex = |s: &str| s.len() >= 6 && s.bytes().take(6).all(|b| b.is_ascii_hexdigit());
+    if !is_hex(s) {
+        return 0;
+    }
+    match s[6..].strip_prefix(',') {
+        Some(after) if is_hex(after) => 13,
+        _ => 6,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -476,6 +922,147 @@ mod tests {
         assert_eq!(result, ":concord 004 alice concord 0.1.0 o o");
     }
 
+    // ── ISUPPORT / VERSION ──
+
+    #[test]
+    fn test_isupport_reflects_validation_limits() {
+        let tokens = isupport_tokens(4000);
+        assert!(tokens.contains(&format!("NICKLEN={MAX_NICKNAME_LENGTH}")));
+        assert!(tokens.contains(&format!("TOPICLEN={MAX_TOPIC_LENGTH}")));
+        assert!(tokens.contains(&"CHANNELLEN=151".to_string()));
+        assert!(tokens.contains(&"MSGLEN=4000".to_string()));
+        assert!(tokens.contains(&"CHANTYPES=#".to_string()));
+        assert!(tokens.contains(&"PREFIX=(ov)@+".to_string()));
+        assert!(tokens.contains(&"UTF8ONLY".to_string()));
+        assert!(tokens.contains(&format!("MONITOR={MONITOR_LIMIT}")));
+        assert!(tokens.contains(&format!("WATCH={MONITOR_LIMIT}")));
+    }
+
+    #[test]
+    fn test_isupport_uses_configured_message_length() {
+        let tokens = isupport_tokens(1234);
+        assert!(tokens.contains(&"MSGLEN=1234".to_string()));
+        assert!(tokens.contains(&"LINELEN=1746".to_string()));
+        assert!(tokens.contains(&"CHANNELSCHEME=#server/channel".to_string()));
+
+        // Lines never exceed what the connection will read
+        assert!(isupport_tokens(4000).contains(&"LINELEN=4096".to_string()));
+    }
+
+    #[test]
+    fn test_rpl_isupport_lines() {
+        let lines = rpl_isupport("alice", 4000);
+        assert!(
+            lines.len() >= 2,
+            "tokens should be split over several lines"
+        );
+        let total: usize = lines
+            .iter()
+            .map(|l| {
+                assert!(l.starts_with(":concord 005 alice "));
+                assert!(l.ends_with(" :are supported by this server"));
+                assert!(l.len() < 512);
+                l.split(' ').count() - 8
+            })
+            .sum();
+        assert_eq!(total, isupport_tokens(4000).len());
+    }
+
+    #[test]
+    fn test_rpl_version() {
+        let result = rpl_version("alice");
+        assert_eq!(
+            result,
+            ":concord 351 alice 0.1.0 concord :Concord IRC-compatible chat server"
+        );
+    }
+
+    // ── Modes ──
+
+    #[test]
+    fn test_isupport_advertises_writable_modes() {
+        let tokens = isupport_tokens(4000);
+        assert!(tokens.contains(&"CHANMODES=b,,N,mnpst".to_string()));
+        assert!(tokens.contains(&"MODES=4".to_string()));
+    }
+
+    #[test]
+    fn test_channel_mode_from_user() {
+        let result = channel_mode(Some("alice"), "#general", "+b", &["bob!*@*"]);
+        assert_eq!(result, ":alice!alice@concord MODE #general +b bob!*@*");
+    }
+
+    #[test]
+    fn test_channel_mode_from_server() {
+        let result = channel_mode(None, "#general", "+N", &["10"]);
+        assert_eq!(result, ":concord MODE #general +N 10");
+    }
+
+    #[test]
+    fn test_rpl_channelmodeis_with_param() {
+        let result = rpl_channelmodeis("alice", "#general", "+ntN 10");
+        assert_eq!(result, ":concord 324 alice #general +ntN 10");
+    }
+
+    #[test]
+    fn test_rpl_banlist_and_end() {
+        assert_eq!(
+            rpl_banlist("alice", "#general", "bob!*@*", "carol"),
+            ":concord 367 alice #general bob!*@* carol"
+        );
+        assert_eq!(
+            rpl_endofbanlist("alice", "#general"),
+            ":concord 368 alice #general :End of channel ban list"
+        );
+    }
+
+    #[test]
+    fn test_err_unknownmode() {
+        let result = err_unknownmode("alice", 'k');
+        assert_eq!(result, ":concord 472 alice k :is unknown mode char to me");
+    }
+
+    #[test]
+    fn test_err_chanoprivsneeded() {
+        let result = err_chanoprivsneeded("alice", "#general");
+        assert_eq!(
+            result,
+            ":concord 482 alice #general :You're not channel operator"
+        );
+    }
+
+    #[test]
+    fn test_err_engine_maps_to_numerics() {
+        let denied = EngineError::PermissionDenied {
+            perm: crate::engine::permissions::Permissions::MANAGE_CHANNELS,
+        };
+        assert_eq!(
+            err_engine("alice", "#general", &denied),
+            ":concord 482 alice #general :Missing permission: MANAGE_CHANNELS"
+        );
+        let read_only = EngineError::CannotSend("This channel is read-only".into());
+        assert_eq!(
+            err_engine("alice", "#general", &read_only),
+            ":concord 404 alice #general :This channel is read-only"
+        );
+        assert_eq!(
+            err_engine("alice", "#general", &EngineError::Banned),
+            ":concord 474 alice #general :Cannot join channel (+b)"
+        );
+        assert_eq!(
+            err_engine(
+                "alice",
+                "#nope",
+                &EngineError::NoSuchChannel("#nope".into())
+            ),
+            err_nosuchchannel("alice", "#nope")
+        );
+        assert_eq!(
+            err_engine("alice", "#general", &EngineError::NoDatabase),
+            ":concord NOTICE alice :No database configured"
+        );
+    }
+
     // ── MOTD ──
 
     #[test]
@@ -511,6 +1098,15 @@ mod tests {
         assert_eq!(result, ":alice!alice@concord PRIVMSG #general :Hello world");
     }
 
+    #[test]
+    fn test_notice_format() {
+        let result = notice("Concord", "alice", "No bookmarks");
+        assert_eq!(
+            result,
+            ":Concord!Concord@concord NOTICE alice :No bookmarks"
+        );
+    }
+
     #[test]
     fn test_privmsg_single_word() {
         let result = privmsg("alice", "bob", "hello");
@@ -538,6 +1134,15 @@ mod tests {
         assert_eq!(result, ":alice!alice@concord NICK alice_away");
     }
 
+    #[test]
+    fn test_away_notify() {
+        assert_eq!(
+            away("alice", Some("Gone to lunch")),
+            ":alice!alice@concord AWAY :Gone to lunch"
+        );
+        assert_eq!(away("alice", None), ":alice!alice@concord AWAY");
+    }
+
     // ── TOPIC ──
 
     #[test]
@@ -627,6 +1232,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rpl_whoishost() {
+        let result = rpl_whoishost("alice", "bob", "bob.example.net", "203.0.113.7");
+        assert_eq!(
+            result,
+            ":concord 378 alice bob :is connecting from *@bob.example.net 203.0.113.7"
+        );
+    }
+
     #[test]
     fn test_rpl_endofwhois() {
         let result = rpl_endofwhois("alice", "bob");
@@ -686,12 +1300,94 @@ mod tests {
         assert_eq!(result, ":concord 461 alice JOIN :Not enough parameters");
     }
 
+    #[test]
+    fn test_err_erroneusnickname() {
+        let result = err_erroneusnickname("alice", "bad nick");
+        assert_eq!(result, ":concord 432 alice bad nick :Erroneous nickname");
+    }
+
     #[test]
     fn test_err_alreadyregistered() {
         let result = err_alreadyregistered("alice");
         assert_eq!(result, ":concord 462 alice :You may not reregister");
     }
 
+    // ── Markdown <-> mIRC formatting ──
+
+    #[test]
+    fn test_markdown_to_irc_inline() {
+        assert_eq!(
+            markdown_to_irc("**bold** *italic* ~~gone~~ `code`"),
+            "\x02bold\x02 \x1Ditalic\x1D \x1Egone\x1E \x11code\x11"
+        );
+        assert_eq!(
+            markdown_to_irc("**bold *and italic* too**"),
+            "\x02bold \x1Dand italic\x1D too\x02"
+        );
+        // Code spans are literal
+        assert_eq!(markdown_to_irc("`**x**`"), "\x11**x**\x11");
+        // Unclosed markers and lone asterisks stay as typed
+        assert_eq!(markdown_to_irc("2 * 3 = 6, **oops"), "2 * 3 = 6, **oops");
+        assert_eq!(markdown_to_irc("héllo **wörld**"), "héllo \x02wörld\x02");
+    }
+
+    #[test]
+    fn test_markdown_spoiler_is_same_colour() {
+        assert_eq!(
+            markdown_to_irc("the end: ||1 dies||"),
+            "the end: \x0301,011 dies\x03"
+        );
+    }
+
+    #[test]
+    fn test_markdown_blocks_to_irc() {
+        assert_eq!(
+            markdown_to_irc("> quoted **text**\nreply"),
+            "\x0314>\x03 quoted \x02text\x02\nreply"
+        );
+        assert_eq!(
+            markdown_to_irc("look:\n```rust\nfn main() {}\n\n```"),
+            "look:\n\x11fn main() {}\x11\n"
+        );
+    }
+
+    #[test]
+    fn test_irc_to_markdown() {
+        assert_eq!(
+            irc_to_markdown("\x02bold\x02 \x1Ditalic\x1D \x1Estrike\x1E \x11mono\x11"),
+            "**bold** *italic* ~~strike~~ `mono`"
+        );
+        // Unclosed spans are closed at the end; reset closes everything
+        assert_eq!(irc_to_markdown("\x02loud"), "**loud**");
+        assert_eq!(
+            irc_to_markdown("\x02\x1Dboth\x0F plain"),
+            "***both*** plain"
+        );
+        // Overlapping spans are re-nested
+        assert_eq!(
+            irc_to_markdown("\x02bold \x1Dboth\x02 italic\x1D"),
+            "**bold *both*** *italic*"
+        );
+        // Empty spans vanish
+        assert_eq!(irc_to_markdown("a\x02\x02b"), "ab");
+    }
+
+    #[test]
+    fn test_irc_colours_to_markdown() {
+        assert_eq!(irc_to_markdown("\x0304red\x03 text"), "red text");
+        assert_eq!(irc_to_markdown("\x0304,12 on blue"), " on blue");
+        assert_eq!(irc_to_markdown("\x0301,01secret\x03!"), "||secret||!");
+        assert_eq!(irc_to_markdown("\x03,5 comma"), ",5 comma");
+        assert_eq!(irc_to_markdown("\x04FF0000,00FF00hex\x04"), "hex");
+        assert_eq!(irc_to_markdown("\x1Funder\x1F \x16rev\x16"), "under rev");
+    }
+
+    #[test]
+    fn test_formatting_round_trip() {
+        let markdown = "**bold** *italic* ~~strike~~ `code` ||spoiler||";
+        assert_eq!(irc_to_markdown(&markdown_to_irc(markdown)), markdown);
+    }
+
     // ── PING / PONG ──
 
     #[test]
@@ -1,18 +1,27 @@
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Duration;
 
 use dashmap::DashMap;
 use sqlx::SqlitePool;
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
 use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::client::danger::HandshakeSignatureValid;
+use tokio_rustls::rustls::crypto::{
+    CryptoProvider, verify_tls12_signature, verify_tls13_signature,
+};
+use tokio_rustls::rustls::pki_types::{CertificateDer, UnixTime};
+use tokio_rustls::rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
+use tokio_rustls::rustls::{DigitallySignedStruct, DistinguishedName, SignatureScheme};
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
+use crate::auth::token::cert_fingerprint;
 use crate::engine::chat_engine::ChatEngine;
+use crate::metrics::METRICS;
 
-use super::connection::handle_irc_connection;
+use super::connection::{IrcPeer, handle_irc_connection};
+use super::proxy;
 
 /// Maximum concurrent IRC connections per IP address.
 const MAX_CONNECTIONS_PER_IP: u32 = 5;
@@ -20,6 +29,9 @@ const MAX_CONNECTIONS_PER_IP: u32 = 5;
 /// Timeout for TLS handshake — prevents malicious clients from holding connections indefinitely.
 const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Timeout for a trusted proxy to send its PROXY protocol header.
+const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Start the IRC TCP listener. Accepts connections and spawns a handler task for each.
 /// If a TLS acceptor is provided, connections are wrapped in TLS.
 /// Stops accepting new connections when the cancellation token is triggered.
@@ -40,8 +52,8 @@ pub async fn start_irc_listener(
         info!("IRC listener started on {} (plaintext)", bind_addr);
     }
 
-    // Track active connection count per IP
-    let ip_counts: Arc<DashMap<IpAddr, AtomicU32>> = Arc::new(DashMap::new());
+    // Track active connection count per client IP
+    let limiter = IpLimiter::new(MAX_CONNECTIONS_PER_IP);
 
     loop {
         tokio::select! {
@@ -52,51 +64,13 @@ pub async fn start_irc_listener(
             result = listener.accept() => {
                 match result {
                     Ok((stream, addr)) => {
-                        let ip = addr.ip();
-
-                        // Enforce per-IP connection limit
-                        let count = ip_counts
-                            .entry(ip)
-                            .or_insert_with(|| AtomicU32::new(0));
-                        let current = count.load(Ordering::Relaxed);
-                        if current >= MAX_CONNECTIONS_PER_IP {
-                            warn!(%ip, count = current, "IRC connection rejected: per-IP limit reached");
-                            drop(stream);
-                            continue;
-                        }
-                        count.fetch_add(1, Ordering::Relaxed);
-
                         let engine = engine.clone();
                         let db = db.clone();
-                        let peer = addr.to_string();
-                        let ip_counts = ip_counts.clone();
-                        if let Some(ref acceptor) = tls_acceptor {
-                            let acceptor = acceptor.clone();
-                            tokio::spawn(async move {
-                                match tokio::time::timeout(
-                                    TLS_HANDSHAKE_TIMEOUT,
-                                    acceptor.accept(stream),
-                                )
-                                .await
-                                {
-                                    Ok(Ok(tls_stream)) => {
-                                        handle_irc_connection(tls_stream, peer, engine, db).await;
-                                    }
-                                    Ok(Err(e)) => {
-                                        warn!(%peer, error = %e, "TLS handshake failed");
-                                    }
-                                    Err(_) => {
-                                        warn!(%peer, "TLS handshake timed out");
-                                    }
-                                }
-                                decrement_ip_count(&ip_counts, ip);
-                            });
-                        } else {
-                            tokio::spawn(async move {
-                                handle_irc_connection(stream, peer, engine, db).await;
-                                decrement_ip_count(&ip_counts, ip);
-                            });
-                        }
+                        let limiter = limiter.clone();
+                        let tls_acceptor = tls_acceptor.clone();
+                        tokio::spawn(async move {
+                            accept_connection(stream, addr, engine, db, limiter, tls_acceptor).await;
+                        });
                     }
                     Err(e) => {
                         error!(error = %e, "failed to accept IRC connection");
@@ -107,13 +81,239 @@ pub async fn start_irc_listener(
     }
 }
 
-/// Decrement the connection count for an IP, removing the entry when it reaches zero.
-fn decrement_ip_count(ip_counts: &DashMap<IpAddr, AtomicU32>, ip: IpAddr) {
-    if let Some(entry) = ip_counts.get(&ip) {
-        let prev = entry.fetch_sub(1, Ordering::Relaxed);
-        drop(entry);
-        if prev <= 1 {
-            ip_counts.remove(&ip);
+/// Resolve the client address (PROXY protocol), enforce the per-IP limit,
+/// complete the TLS handshake if enabled, and run the connection.
+async fn accept_connection(
+    mut stream: TcpStream,
+    addr: SocketAddr,
+    engine: Arc<ChatEngine>,
+    db: SqlitePool,
+    limiter: Arc<IpLimiter>,
+    tls_acceptor: Option<TlsAcceptor>,
+) {
+    // Connections from a trusted proxy start with the real client address
+    let addr = if proxy::is_trusted_proxy(addr.ip()) {
+        match tokio::time::timeout(PROXY_HEADER_TIMEOUT, proxy::read_proxy_header(&mut stream))
+            .await
+        {
+            Ok(Ok(Some(client))) => client,
+            Ok(Ok(None)) => addr,
+            Ok(Err(e)) => {
+                warn!(%addr, error = %e, "invalid PROXY protocol header");
+                return;
+            }
+            Err(_) => {
+                warn!(%addr, "PROXY protocol header timed out");
+                return;
+            }
+        }
+    } else {
+        addr
+    };
+
+    // Enforce per-IP connection limit
+    let Some(slot) = limiter.acquire(addr.ip()) else {
+        warn!(ip = %addr.ip(), "IRC connection rejected: per-IP limit reached");
+        return;
+    };
+
+    let Some(acceptor) = tls_acceptor else {
+        let peer = IrcPeer {
+            addr,
+            cert_fp: None,
+            slot,
+        };
+        handle_irc_connection(stream, peer, engine, db).await;
+        return;
+    };
+
+    match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+        Ok(Ok(tls_stream)) => {
+            let cert_fp = tls_stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| cert_fingerprint(cert.as_ref()));
+            let peer = IrcPeer {
+                addr,
+                cert_fp,
+                slot,
+            };
+            handle_irc_connection(tls_stream, peer, engine, db).await;
+        }
+        Ok(Err(e)) => {
+            warn!(%addr, error = %e, "TLS handshake failed");
+        }
+        Err(_) => {
+            warn!(%addr, "TLS handshake timed out");
+        }
+    }
+}
+
+/// Active IRC connection counts per client IP.
+pub struct IpLimiter {
+    counts: DashMap<IpAddr, u32>,
+    max_per_ip: u32,
+}
+
+impl IpLimiter {
+    pub fn new(max_per_ip: u32) -> Arc<Self> {
+        Arc::new(Self {
+            counts: DashMap::new(),
+            max_per_ip,
+        })
+    }
+
+    /// Reserve a connection slot for an IP. Returns None if the IP is at its limit.
+    pub fn acquire(self: &Arc<Self>, ip: IpAddr) -> Option<IpSlot> {
+        let ip = ip.to_canonical();
+        let mut count = self.counts.entry(ip).or_insert(0);
+        if *count >= self.max_per_ip {
+            return None;
         }
+        *count += 1;
+        METRICS.irc_connections.inc(&ip.to_string());
+        Some(IpSlot {
+            limiter: self.clone(),
+            ip,
+        })
+    }
+}
+
+/// A connection's place in the per-IP limit, released when dropped.
+pub struct IpSlot {
+    limiter: Arc<IpLimiter>,
+    ip: IpAddr,
+}
+
+impl IpSlot {
+    /// Move the connection to another client IP (e.g. after WEBIRC).
+    /// Returns None if that IP is at its limit; this slot is kept either way
+    /// until the caller drops it.
+    pub fn transfer(&self, ip: IpAddr) -> Option<IpSlot> {
+        self.limiter.acquire(ip)
+    }
+}
+
+impl Drop for IpSlot {
+    fn drop(&mut self) {
+        METRICS.irc_connections.dec(&self.ip.to_string());
+        // Remove the entry when the count reaches zero
+        self.limiter.counts.remove_if_mut(&self.ip, |_, count| {
+            *count = count.saturating_sub(1);
+            *count == 0
+        });
+    }
+}
+
+/// TLS client certificate verifier for IRC CertFP.
+///
+/// Client certificates are optional and may be self-signed: they are not checked
+/// against any CA. The handshake still proves the client holds the certificate's
+/// key, and its fingerprint is matched to an account during SASL EXTERNAL.
+#[derive(Debug)]
+pub struct CertFpVerifier {
+    provider: Arc<CryptoProvider>,
+}
+
+impl CertFpVerifier {
+    pub fn new(provider: Arc<CryptoProvider>) -> Self {
+        Self { provider }
+    }
+}
+
+impl ClientCertVerifier for CertFpVerifier {
+    fn client_auth_mandatory(&self) -> bool {
+        false
+    }
+
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> Result<ClientCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_limiter_releases_slots_on_drop() {
+        let limiter = IpLimiter::new(2);
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+
+        let first = limiter.acquire(ip).unwrap();
+        let second = limiter.acquire(ip).unwrap();
+        assert!(limiter.acquire(ip).is_none());
+        // IPv4-mapped IPv6 counts as the same client
+        assert!(
+            limiter
+                .acquire("::ffff:192.0.2.1".parse().unwrap())
+                .is_none()
+        );
+
+        drop(first);
+        let third = limiter.acquire(ip).unwrap();
+        drop(second);
+        drop(third);
+        assert!(limiter.counts.is_empty());
+    }
+
+    #[test]
+    fn test_ip_slot_transfer() {
+        let limiter = IpLimiter::new(1);
+        let gateway: IpAddr = "10.0.0.1".parse().unwrap();
+        let client: IpAddr = "198.51.100.4".parse().unwrap();
+
+        let slot = limiter.acquire(gateway).unwrap();
+        let moved = slot.transfer(client).unwrap();
+        drop(slot);
+
+        // The gateway is free again; the client holds its one slot
+        assert!(limiter.acquire(gateway).is_some());
+        assert!(moved.transfer(client).is_none());
     }
 }
@@ -1,6 +1,13 @@
+pub mod bouncer;
 pub mod commands;
 pub mod connection;
 pub mod formatter;
 pub mod listener;
+pub mod modes;
+pub mod monitor;
+pub mod multiline;
 pub mod numerics;
 pub mod parser;
+pub mod proxy;
+pub mod sasl;
+pub mod services;
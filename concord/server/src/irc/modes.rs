@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+
+use crate::engine::events::RoleInfo;
+use crate::engine::permissions::Permissions;
+
+/// Maximum number of parameterised mode changes accepted per MODE line
+/// (advertised as `MODES` in ISUPPORT).
+pub const MAX_MODE_PARAMS: usize = 4;
+
+/// A single channel mode change parsed from `MODE #chan <modestring> [args...]`.
+///
+/// Each variant maps onto a Concord moderation operation:
+/// `s`/`p` private channel, `m` read-only, `t` topic lock, `N` slow mode,
+/// `b` server ban, `o`/`v` role assignment.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModeChange {
+    Private(bool),
+    ReadOnly(bool),
+    TopicLock(bool),
+    /// Slow mode cooldown in seconds (0 = disabled).
+    SlowMode(i32),
+    Ban {
+        set: bool,
+        mask: String,
+    },
+    /// `b` without a mask — list the current bans.
+    BanList,
+    Op {
+        set: bool,
+        nick: String,
+    },
+    Voice {
+        set: bool,
+        nick: String,
+    },
+    /// `n` is always on; setting it is accepted and ignored.
+    NoOp,
+    /// A mode that needs a parameter was given none (or an invalid one).
+    MissingParam(char),
+    Unknown(char),
+}
+
+/// Parse a channel mode string and its arguments into individual changes.
+///
+/// Parameters are consumed left to right as in RFC 2811. At most
+/// `MAX_MODE_PARAMS` parameterised changes are returned; the rest are dropped.
+pub fn parse_mode_changes(modestring: &str, args: &[String]) -> Vec<ModeChange> {
+    let mut changes = Vec::new();
+    let mut args = args.iter();
+    let mut set = true;
+    let mut param_count = 0;
+
+    for c in modestring.chars() {
+        let change = match c {
+            '+' => {
+                set = true;
+                continue;
+            }
+            '-' => {
+                set = false;
+                continue;
+            }
+            'n' => ModeChange::NoOp,
+            's' | 'p' => ModeChange::Private(set),
+            'm' => ModeChange::ReadOnly(set),
+            't' => ModeChange::TopicLock(set),
+            'N' if !set => ModeChange::SlowMode(0),
+            'N' => match args.next().and_then(|a| a.parse::<i32>().ok()) {
+                Some(seconds) if seconds >= 0 => {
+                    param_count += 1;
+                    ModeChange::SlowMode(seconds)
+                }
+                _ => ModeChange::MissingParam('N'),
+            },
+            'b' => match args.next() {
+                Some(mask) => {
+                    param_count += 1;
+                    ModeChange::Ban {
+                        set,
+                        mask: mask.clone(),
+                    }
+                }
+                None => ModeChange::BanList,
+            },
+            'o' | 'v' => match args.next() {
+                Some(nick) => {
+                    param_count += 1;
+                    let nick = nick.clone();
+                    if c == 'o' {
+                        ModeChange::Op { set, nick }
+                    } else {
+                        ModeChange::Voice { set, nick }
+                    }
+                }
+                None => ModeChange::MissingParam(c),
+            },
+            other => ModeChange::Unknown(other),
+        };
+
+        if param_count > MAX_MODE_PARAMS {
+            break;
+        }
+        changes.push(change);
+    }
+
+    changes
+}
+
+/// Extract the nickname from a ban mask (`nick`, `nick!*@*`, `nick!*@concord`).
+///
+/// Concord bans are per-account, so masks that wildcard the nickname cannot
+/// be mapped and return None.
+pub fn ban_mask_nick(mask: &str) -> Option<&str> {
+    let nick = mask.split('!').next().unwrap_or(mask);
+    if nick.is_empty() || nick.contains(['*', '?']) {
+        None
+    } else {
+        Some(nick)
+    }
+}
+
+/// The ban mask shown to IRC clients for a banned nickname.
+pub fn ban_mask_for(nick: &str) -> String {
+    format!("{nick}!*@*")
+}
+
+/// Whether a permission set earns the `@` prefix.
+pub fn is_op_level(perms: Permissions) -> bool {
+    perms.intersects(
+        Permissions::ADMINISTRATOR
+            | Permissions::MANAGE_CHANNELS
+            | Permissions::KICK_MEMBERS
+            | Permissions::BAN_MEMBERS,
+    )
+}
+
+/// Whether a permission set earns the `+` prefix (and not `@`).
+pub fn is_voice_level(perms: Permissions) -> bool {
+    !is_op_level(perms) && perms.contains(Permissions::MANAGE_MESSAGES)
+}
+
+/// Whether a role's permissions confer the `@` (op) or `+` (voice) prefix.
+pub fn confers_status(permissions: i64, op: bool) -> bool {
+    let perms = Permissions::from_bits_truncate(permissions as u64);
+    if op {
+        is_op_level(perms)
+    } else {
+        is_voice_level(perms)
+    }
+}
+
+/// Pick the role that `+o` (or `+v`) assigns: the lowest-positioned
+/// non-default role whose permissions confer that prefix.
+pub fn status_role(roles: &[RoleInfo], op: bool) -> Option<&RoleInfo> {
+    roles
+        .iter()
+        .filter(|r| !r.is_default && confers_status(r.permissions, op))
+        .min_by_key(|r| r.position)
+}
+
+/// The status prefix (`@`, `+` or none) a client was last shown for each
+/// server member, so that role changes which leave it alone send no MODE.
+#[derive(Default)]
+pub struct ShownStatus {
+    /// Keyed by (server ID, user ID). Members missing here were shown no prefix.
+    known: HashMap<(String, String), &'static str>,
+}
+
+impl ShownStatus {
+    /// Record the prefix a NAMES reply showed for a member.
+    pub fn record(&mut self, server_id: &str, user_id: &str, prefix: &'static str) {
+        self.known
+            .insert((server_id.to_string(), user_id.to_string()), prefix);
+    }
+
+    /// The mode string moving a member to `prefix`, if the client was shown
+    /// something else.
+    pub fn update(
+        &mut self,
+        server_id: &str,
+        user_id: &str,
+        prefix: &'static str,
+    ) -> Option<&'static str> {
+        let previous = self
+            .known
+            .insert((server_id.to_string(), user_id.to_string()), prefix)
+            .unwrap_or("");
+        match (previous, prefix) {
+            ("", "@") => Some("+o"),
+            ("", "+") => Some("+v"),
+            ("@", "") => Some("-o"),
+            ("+", "") => Some("-v"),
+            ("@", "+") => Some("-o+v"),
+            ("+", "@") => Some("-v+o"),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::permissions::{DEFAULT_ADMIN, DEFAULT_EVERYONE, DEFAULT_MODERATOR};
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn role(id: &str, position: i32, perms: Permissions, is_default: bool) -> RoleInfo {
+        RoleInfo {
+            id: id.into(),
+            server_id: "srv".into(),
+            name: id.into(),
+            color: None,
+            icon_url: None,
+            position,
+            permissions: perms.bits() as i64,
+            is_default,
+        }
+    }
+
+    #[test]
+    fn test_parse_flag_modes() {
+        let changes = parse_mode_changes("+mst-p", &[]);
+        assert_eq!(
+            changes,
+            vec![
+                ModeChange::ReadOnly(true),
+                ModeChange::Private(true),
+                ModeChange::TopicLock(true),
+                ModeChange::Private(false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_slowmode() {
+        assert_eq!(
+            parse_mode_changes("+N", &args(&["10"])),
+            vec![ModeChange::SlowMode(10)]
+        );
+        assert_eq!(parse_mode_changes("-N", &[]), vec![ModeChange::SlowMode(0)]);
+        assert_eq!(
+            parse_mode_changes("+N", &args(&["soon"])),
+            vec![ModeChange::MissingParam('N')]
+        );
+    }
+
+    #[test]
+    fn test_parse_ban_and_ban_list() {
+        assert_eq!(parse_mode_changes("+b", &[]), vec![ModeChange::BanList]);
+        assert_eq!(
+            parse_mode_changes("-b", &args(&["bob!*@*"])),
+            vec![ModeChange::Ban {
+                set: false,
+                mask: "bob!*@*".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_op_voice_consume_args_in_order() {
+        let changes = parse_mode_changes("+ov-o", &args(&["alice", "bob", "carol"]));
+        assert_eq!(
+            changes,
+            vec![
+                ModeChange::Op {
+                    set: true,
+                    nick: "alice".into()
+                },
+                ModeChange::Voice {
+                    set: true,
+                    nick: "bob".into()
+                },
+                ModeChange::Op {
+                    set: false,
+                    nick: "carol".into()
+                },
+            ]
+        );
+        assert_eq!(
+            parse_mode_changes("+o", &[]),
+            vec![ModeChange::MissingParam('o')]
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown_and_noop() {
+        assert_eq!(
+            parse_mode_changes("+nk", &args(&["key"])),
+            vec![ModeChange::NoOp, ModeChange::Unknown('k')]
+        );
+    }
+
+    #[test]
+    fn test_parse_caps_parameterised_changes() {
+        let changes = parse_mode_changes("+ooooo", &args(&["a", "b", "c", "d", "e"]));
+        assert_eq!(changes.len(), MAX_MODE_PARAMS);
+    }
+
+    #[test]
+    fn test_ban_mask_nick() {
+        assert_eq!(ban_mask_nick("bob"), Some("bob"));
+        assert_eq!(ban_mask_nick("bob!*@*"), Some("bob"));
+        assert_eq!(ban_mask_nick("*!*@host"), None);
+        assert_eq!(ban_mask_nick("b?b!*@*"), None);
+        assert_eq!(ban_mask_nick(""), None);
+        assert_eq!(ban_mask_for("bob"), "bob!*@*");
+    }
+
+    #[test]
+    fn test_shown_status_only_reports_changes() {
+        let mut shown = ShownStatus::default();
+        assert_eq!(shown.update("s1", "u1", ""), None);
+        assert_eq!(shown.update("s1", "u1", "+"), Some("+v"));
+        assert_eq!(shown.update("s1", "u1", "+"), None);
+        assert_eq!(shown.update("s1", "u1", "@"), Some("-v+o"));
+        assert_eq!(shown.update("s1", "u1", ""), Some("-o"));
+
+        // NAMES already showed the prefix
+        shown.record("s2", "u1", "@");
+        assert_eq!(shown.update("s2", "u1", "@"), None);
+        assert_eq!(shown.update("s2", "u1", "+"), Some("-o+v"));
+    }
+
+    #[test]
+    fn test_status_role_picks_lowest_matching_role() {
+        let roles = vec![
+            role("everyone", 0, DEFAULT_EVERYONE, true),
+            role("admin", 2, DEFAULT_ADMIN, false),
+            role("moderator", 1, DEFAULT_MODERATOR, false),
+        ];
+        assert_eq!(status_role(&roles, true).unwrap().id, "moderator");
+        assert!(status_role(&roles, false).is_none());
+
+        let mut with_helper = roles.clone();
+        with_helper.push(role(
+            "helper",
+            1,
+            DEFAULT_EVERYONE | Permissions::MANAGE_MESSAGES,
+            false,
+        ));
+        assert_eq!(status_role(&with_helper, false).unwrap().id, "helper");
+        assert!(!confers_status(DEFAULT_ADMIN.bits() as i64, false));
+    }
+}
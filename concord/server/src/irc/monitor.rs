@@ -0,0 +1,420 @@
+use std::collections::HashMap;
+
+use crate::engine::chat_engine::{ChatEngine, MONITOR_LIMIT};
+use crate::engine::events::{PresenceInfo, SessionId};
+
+use super::formatter;
+use super::numerics::{
+    ERR_MONLISTFULL, ERR_TOOMANYWATCH, RPL_ENDOFMONLIST, RPL_ENDOFWATCHLIST, RPL_LOGOFF, RPL_LOGON,
+    RPL_MONLIST, RPL_MONOFFLINE, RPL_MONONLINE, RPL_NOWOFF, RPL_NOWON, RPL_WATCHLIST, RPL_WATCHOFF,
+    RPL_WATCHSTAT,
+};
+use super::parser::IrcMessage;
+
+/// Which command a client uses to watch nicknames, which decides how
+/// online/offline notifications are worded.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub enum NotifyStyle {
+    /// IRCv3 MONITOR (730/731).
+    #[default]
+    Monitor,
+    /// Legacy WATCH (600/601).
+    Watch,
+}
+
+/// Handle `MONITOR + targets`, `MONITOR - targets`, `MONITOR C`, `MONITOR L`, `MONITOR S`.
+pub fn handle_monitor(
+    engine: &ChatEngine,
+    session_id: SessionId,
+    nick: &str,
+    msg: &IrcMessage,
+) -> Vec<String> {
+    let Some(op) = msg.params.first() else {
+        return vec![formatter::err_needmoreparams(nick, "MONITOR")];
+    };
+    let targets = split_targets(&msg.params[1..]);
+    let sn = formatter::server_name();
+
+    match op.as_str() {
+        "+" => {
+            if targets.is_empty() {
+                return vec![formatter::err_needmoreparams(nick, "MONITOR")];
+            }
+            let (statuses, rejected) = engine.monitor_add(session_id, &targets);
+            let mut lines = monitor_status_lines(nick, &statuses);
+            if !rejected.is_empty() {
+                lines.push(format!(
+                    ":{sn} {ERR_MONLISTFULL} {nick} {MONITOR_LIMIT} {} :Monitor list is full.",
+                    rejected.join(",")
+                ));
+            }
+            lines
+        }
+        "-" => {
+            engine.monitor_remove(session_id, &targets);
+            vec![]
+        }
+        "C" | "c" => {
+            engine.monitor_clear(session_id);
+            vec![]
+        }
+        "L" | "l" => {
+            let mut lines: Vec<String> = engine
+                .monitor_list(session_id)
+                .chunks(MAX_TARGETS_PER_LINE)
+                .map(|chunk| format!(":{sn} {RPL_MONLIST} {nick} :{}", chunk.join(",")))
+                .collect();
+            lines.push(format!(
+                ":{sn} {RPL_ENDOFMONLIST} {nick} :End of MONITOR list"
+            ));
+            lines
+        }
+        "S" | "s" => monitor_status_lines(nick, &current_statuses(engine, session_id)),
+        _ => vec![formatter::err_unknowncommand(nick, "MONITOR")],
+    }
+}
+
+/// Handle `WATCH [+nick|-nick|C|S|L|l ...]`. With no arguments, lists online entries.
+pub fn handle_watch(
+    engine: &ChatEngine,
+    session_id: SessionId,
+    nick: &str,
+    msg: &IrcMessage,
+) -> Vec<String> {
+    let sn = formatter::server_name();
+    let args = split_targets(&msg.params);
+    if args.is_empty() {
+        return watch_list_lines(engine, session_id, nick, false);
+    }
+
+    let mut lines = Vec::new();
+    for arg in &args {
+        if let Some(target) = arg.strip_prefix('+') {
+            let (statuses, rejected) = engine.monitor_add(session_id, &[target.to_string()]);
+            for (name, online) in &statuses {
+                lines.push(watch_now_line(engine, nick, name, *online));
+            }
+            if !rejected.is_empty() {
+                lines.push(format!(
+                    ":{sn} {ERR_TOOMANYWATCH} {nick} {target} :Maximum size of WATCH-list is {MONITOR_LIMIT} entries"
+                ));
+            }
+        } else if let Some(target) = arg.strip_prefix('-') {
+            engine.monitor_remove(session_id, &[target.to_string()]);
+            lines.push(format!(
+                ":{sn} {RPL_WATCHOFF} {nick} {target} * * 0 :stopped watching"
+            ));
+        } else {
+            match arg.as_str() {
+                "C" | "c" => engine.monitor_clear(session_id),
+                "S" | "s" => {
+                    let list = engine.monitor_list(session_id);
+                    lines.push(format!(
+                        ":{sn} {RPL_WATCHSTAT} {nick} :You have {} and are on {} WATCH entries",
+                        list.len(),
+                        engine.monitor_watcher_count(nick)
+                    ));
+                    for chunk in list.chunks(MAX_TARGETS_PER_LINE) {
+                        lines.push(format!(":{sn} {RPL_WATCHLIST} {nick} :{}", chunk.join(" ")));
+                    }
+                    lines.push(format!(":{sn} {RPL_ENDOFWATCHLIST} {nick} :End of WATCH S"));
+                }
+                "L" => lines.extend(watch_list_lines(engine, session_id, nick, true)),
+                "l" => lines.extend(watch_list_lines(engine, session_id, nick, false)),
+                _ => {}
+            }
+        }
+    }
+    lines
+}
+
+/// Lines for a `MonitorStatus` engine event, worded for the client's style.
+pub fn notify_lines(nick: &str, style: NotifyStyle, nickname: &str, online: bool) -> Vec<String> {
+    let sn = formatter::server_name();
+    match style {
+        NotifyStyle::Monitor => monitor_status_lines(nick, &[(nickname.to_string(), online)]),
+        NotifyStyle::Watch => {
+            let now = chrono::Utc::now().timestamp();
+            let (numeric, user_host, text) = if online {
+                (RPL_LOGON, format!("{nickname} {sn}"), "logged online")
+            } else {
+                (RPL_LOGOFF, "* *".to_string(), "logged offline")
+            };
+            vec![format!(
+                ":{sn} {numeric} {nick} {nickname} {user_host} {now} :{text}"
+            )]
+        }
+    }
+}
+
+/// Targets per 730/731/732/606 line (keeps lines well under 512 bytes).
+const MAX_TARGETS_PER_LINE: usize = 10;
+
+/// Split `a,b c` style target lists from one or more parameters.
+fn split_targets(params: &[String]) -> Vec<String> {
+    params
+        .iter()
+        .flat_map(|p| p.split([',', ' ']))
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn current_statuses(engine: &ChatEngine, session_id: SessionId) -> Vec<(String, bool)> {
+    engine
+        .monitor_list(session_id)
+        .into_iter()
+        .map(|name| match engine.visible_nick(&name) {
+            Some(online) => (online, true),
+            None => (name, false),
+        })
+        .collect()
+}
+
+/// RPL_MONONLINE (`nick!user@host` targets) and RPL_MONOFFLINE lines.
+fn monitor_status_lines(nick: &str, statuses: &[(String, bool)]) -> Vec<String> {
+    let sn = formatter::server_name();
+    let online: Vec<String> = statuses
+        .iter()
+        .filter(|(_, on)| *on)
+        .map(|(name, _)| format!("{name}!{name}@{sn}"))
+        .collect();
+    let offline: Vec<&str> = statuses
+        .iter()
+        .filter(|(_, on)| !*on)
+        .map(|(name, _)| name.as_str())
+        .collect();
+
+    let mut lines = Vec::new();
+    for chunk in online.chunks(MAX_TARGETS_PER_LINE) {
+        lines.push(format!(":{sn} {RPL_MONONLINE} {nick} :{}", chunk.join(",")));
+    }
+    for chunk in offline.chunks(MAX_TARGETS_PER_LINE) {
+        lines.push(format!(
+            ":{sn} {RPL_MONOFFLINE} {nick} :{}",
+            chunk.join(",")
+        ));
+    }
+    lines
+}
+
+/// RPL_NOWON / RPL_NOWOFF for one watched nickname.
+fn watch_now_line(engine: &ChatEngine, nick: &str, name: &str, online: bool) -> String {
+    let sn = formatter::server_name();
+    if online {
+        let since = engine
+            .get_session_id_by_nick(name)
+            .and_then(|sid| engine.get_session(sid))
+            .map_or(0, |s| s.connected_at.timestamp());
+        format!(":{sn} {RPL_NOWON} {nick} {name} {name} {sn} {since} :is online")
+    } else {
+        format!(":{sn} {RPL_NOWOFF} {nick} {name} * * 0 :is offline")
+    }
+}
+
+/// `WATCH L` (all entries) / `WATCH l` (online entries only).
+fn watch_list_lines(
+    engine: &ChatEngine,
+    session_id: SessionId,
+    nick: &str,
+    include_offline: bool,
+) -> Vec<String> {
+    let sn = formatter::server_name();
+    let mut lines: Vec<String> = current_statuses(engine, session_id)
+        .into_iter()
+        .filter(|(_, online)| include_offline || *online)
+        .map(|(name, online)| watch_now_line(engine, nick, &name, online))
+        .collect();
+    let which = if include_offline { "L" } else { "l" };
+    lines.push(format!(
+        ":{sn} {RPL_ENDOFWATCHLIST} {nick} :End of WATCH {which}"
+    ));
+    lines
+}
+
+/// Relays other users' away status to an `away-notify` client.
+///
+/// Presence updates arrive once per shared server, so only changes are relayed.
+#[derive(Default)]
+pub struct AwayNotify {
+    /// Last relayed away message per nickname (None = here).
+    known: HashMap<String, Option<String>>,
+}
+
+impl AwayNotify {
+    /// The AWAY line for a presence update, if it changes what the client knows.
+    pub fn update(&mut self, own_nick: &str, presence: &PresenceInfo) -> Option<String> {
+        if presence.nickname == own_nick {
+            return None;
+        }
+        let away = match presence.status.as_str() {
+            "idle" | "dnd" => Some(
+                presence
+                    .custom_status
+                    .clone()
+                    .unwrap_or_else(|| "Away".into()),
+            ),
+            "online" => None,
+            // Offline users are reported with QUIT, not AWAY
+            _ => {
+                self.known.remove(&presence.nickname);
+                return None;
+            }
+        };
+
+        let previous = self.known.insert(presence.nickname.clone(), away.clone());
+        if previous.as_ref() == Some(&away) || (previous.is_none() && away.is_none()) {
+            return None;
+        }
+        Some(formatter::away(&presence.nickname, away.as_deref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::events::ChatEvent;
+    use crate::engine::user_session::Protocol;
+
+    fn parse(line: &str) -> IrcMessage {
+        IrcMessage::parse(line).unwrap()
+    }
+
+    fn presence(nickname: &str, status: &str, custom: Option<&str>) -> PresenceInfo {
+        PresenceInfo {
+            user_id: "u1".into(),
+            nickname: nickname.into(),
+            avatar_url: None,
+            status: status.into(),
+            custom_status: custom.map(str::to_string),
+            status_emoji: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_monitor_add_list_and_notify() {
+        let engine = ChatEngine::new(None, 4000, 100);
+        let (watcher, mut rx) = engine
+            .connect(None, "watcher".into(), Protocol::Irc, None)
+            .unwrap();
+        let (_bob, _bob_rx) = engine
+            .connect(None, "Bob".into(), Protocol::Irc, None)
+            .unwrap();
+        let sn = formatter::server_name();
+
+        let lines = handle_monitor(&engine, watcher, "watcher", &parse("MONITOR + bob,carol"));
+        assert_eq!(
+            lines,
+            vec![
+                format!(":{sn} 730 watcher :Bob!Bob@{sn}"),
+                format!(":{sn} 731 watcher :carol"),
+            ]
+        );
+
+        let lines = handle_monitor(&engine, watcher, "watcher", &parse("MONITOR L"));
+        assert_eq!(lines[0], format!(":{sn} 732 watcher :bob,carol"));
+        assert!(lines[1].contains(" 733 watcher "));
+
+        let (_carol, _carol_rx) = engine
+            .connect(None, "carol".into(), Protocol::Irc, None)
+            .unwrap();
+        assert!(matches!(
+            rx.try_recv().unwrap(),
+            ChatEvent::MonitorStatus { ref nickname, online: true } if nickname == "carol"
+        ));
+
+        handle_monitor(&engine, watcher, "watcher", &parse("MONITOR - carol"));
+        let lines = handle_monitor(&engine, watcher, "watcher", &parse("MONITOR S"));
+        assert_eq!(lines, vec![format!(":{sn} 730 watcher :Bob!Bob@{sn}")]);
+
+        handle_monitor(&engine, watcher, "watcher", &parse("MONITOR C"));
+        assert!(engine.monitor_list(watcher).is_empty());
+        assert_eq!(engine.monitor_watcher_count("bob"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_monitor_list_full() {
+        let engine = ChatEngine::new(None, 4000, 100);
+        let (watcher, _rx) = engine
+            .connect(None, "watcher".into(), Protocol::Irc, None)
+            .unwrap();
+        let many: Vec<String> = (0..MONITOR_LIMIT).map(|i| format!("n{i}")).collect();
+        engine.monitor_add(watcher, &many);
+
+        let lines = handle_monitor(&engine, watcher, "watcher", &parse("MONITOR + extra"));
+        assert_eq!(
+            lines,
+            vec![format!(
+                ":{} 734 watcher {MONITOR_LIMIT} extra :Monitor list is full.",
+                formatter::server_name()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch_commands() {
+        let engine = ChatEngine::new(None, 4000, 100);
+        let (watcher, _rx) = engine
+            .connect(None, "watcher".into(), Protocol::Irc, None)
+            .unwrap();
+        let (_dave, _dave_rx) = engine
+            .connect(None, "dave".into(), Protocol::Irc, None)
+            .unwrap();
+        let sn = formatter::server_name();
+
+        let lines = handle_watch(&engine, watcher, "watcher", &parse("WATCH +dave +erin"));
+        assert!(lines[0].starts_with(&format!(":{sn} 604 watcher dave dave {sn} ")));
+        assert_eq!(
+            lines[1],
+            format!(":{sn} 605 watcher erin * * 0 :is offline")
+        );
+
+        let lines = handle_watch(&engine, watcher, "watcher", &parse("WATCH"));
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(" 604 watcher dave "));
+        assert!(lines[1].contains(" 607 watcher :End of WATCH l"));
+
+        let lines = handle_watch(&engine, watcher, "watcher", &parse("WATCH S"));
+        assert!(lines[0].contains(" 603 watcher :You have 2 and are on 0 WATCH entries"));
+        assert_eq!(lines[1], format!(":{sn} 606 watcher :dave erin"));
+
+        let lines = handle_watch(&engine, watcher, "watcher", &parse("WATCH -erin"));
+        assert!(lines[0].contains(" 602 watcher erin "));
+        assert_eq!(engine.monitor_list(watcher), vec!["dave".to_string()]);
+    }
+
+    #[test]
+    fn test_notify_lines() {
+        let sn = formatter::server_name();
+        assert_eq!(
+            notify_lines("me", NotifyStyle::Monitor, "bob", false),
+            vec![format!(":{sn} 731 me :bob")]
+        );
+        let watch = notify_lines("me", NotifyStyle::Watch, "bob", true);
+        assert!(watch[0].starts_with(&format!(":{sn} 600 me bob bob {sn} ")));
+        assert!(watch[0].ends_with(":logged online"));
+    }
+
+    #[test]
+    fn test_away_notify_relays_changes_once() {
+        let mut away = AwayNotify::default();
+        let sn = formatter::server_name();
+
+        assert_eq!(
+            away.update("me", &presence("bob", "idle", Some("out to lunch"))),
+            Some(format!(":bob!bob@{sn} AWAY :out to lunch"))
+        );
+        // The same update from a second shared server is not repeated
+        assert_eq!(
+            away.update("me", &presence("bob", "idle", Some("out to lunch"))),
+            None
+        );
+        assert_eq!(
+            away.update("me", &presence("bob", "online", None)),
+            Some(format!(":bob!bob@{sn} AWAY"))
+        );
+        // Coming online when never seen away isn't news
+        assert_eq!(away.update("me", &presence("carol", "online", None)), None);
+        assert_eq!(away.update("me", &presence("me", "dnd", None)), None);
+    }
+}
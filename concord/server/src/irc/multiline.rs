@@ -0,0 +1,499 @@
+use super::formatter;
+use super::parser::{IrcMessage, tag_value};
+
+/// Most lines accepted in one `draft/multiline` batch.
+pub const MAX_LINES: usize = 100;
+
+/// IRC line limit in bytes, including the CRLF but not message tags.
+const MAX_LINE_BYTES: usize = 512;
+
+/// Smallest text budget per line, for pathologically long nicks or targets.
+const MIN_TEXT_BYTES: usize = 64;
+
+/// Ends a PRIVMSG whose text continues in the next one.
+const CONTINUATION_MARKER: &str = "…";
+
+/// Bytes reserved for the `nick!user@host` another server puts on our lines.
+const REMOTE_PREFIX_BYTES: usize = 100;
+
+/// Bytes added around CTCP ACTION text (`\x01ACTION ` and `\x01`).
+const ACTION_OVERHEAD: usize = 9;
+
+/// Value advertised with the `draft/multiline` capability.
+pub fn cap_value(max_bytes: usize) -> String {
+    format!("max-bytes={max_bytes},max-lines={MAX_LINES}")
+}
+
+/// Bytes left for the text of `:from!from@concord PRIVMSG target :text\r\n`.
+fn text_budget(from: &str, target: &str) -> usize {
+    let overhead = format!(
+        ":{from}!{from}@{} PRIVMSG {target} :\r\n",
+        formatter::server_name()
+    )
+    .len();
+    MAX_LINE_BYTES.saturating_sub(overhead).max(MIN_TEXT_BYTES)
+}
+
+/// Whether a message must be split or batched to reach an IRC client intact.
+pub fn needs_split(from: &str, target: &str, content: &str) -> bool {
+    content.contains('\n') || content.len() > text_budget(from, target)
+}
+
+/// Split `text` into pieces of at most `max_bytes` on UTF-8 character
+/// boundaries, preferring to break after a space. The pieces concatenate back
+/// to `text` exactly.
+fn split_line(text: &str, max_bytes: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    while rest.len() > max_bytes {
+        let mut cut = max_bytes;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        // Break at a word boundary unless that leaves a very short piece
+        if let Some(space) = rest[..cut].rfind(' ')
+            && space + 1 > cut / 2
+        {
+            cut = space + 1;
+        }
+        if cut == 0 {
+            cut = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        pieces.push(&rest[..cut]);
+        rest = &rest[cut..];
+    }
+    pieces.push(rest);
+    pieces
+}
+
+/// A message as plain PRIVMSGs: one per line of text, with long lines split
+/// and every piece of a split line but the last ending in `…`. Blank lines are
+/// dropped. `/me` messages become CTCP ACTIONs.
+pub fn privmsg_lines(from: &str, target: &str, content: &str) -> Vec<String> {
+    split_privmsgs(content, text_budget(from, target), |text, action| {
+        if action {
+            formatter::ctcp_action(from, target, text)
+        } else {
+            formatter::privmsg(from, target, text)
+        }
+    })
+}
+
+/// Like [`privmsg_lines`], but for PRIVMSGs we send as a client to another
+/// IRC server: no prefix, and room left for the one the server adds.
+pub fn client_privmsg_lines(target: &str, content: &str) -> Vec<String> {
+    let overhead = format!(":{:REMOTE_PREFIX_BYTES$} PRIVMSG {target} :\r\n", "").len();
+    let budget = MAX_LINE_BYTES.saturating_sub(overhead).max(MIN_TEXT_BYTES);
+    split_privmsgs(content, budget, |text, action| {
+        let text = if action {
+            format!("\x01ACTION {text}\x01")
+        } else {
+            text.to_string()
+        };
+        IrcMessage {
+            prefix: None,
+            command: "PRIVMSG".into(),
+            params: vec![target.into(), text],
+        }
+        .format()
+    })
+}
+
+/// Split `content` into lines of at most `budget` bytes of text, formatted by
+/// `format(text, is_action)`.
+fn split_privmsgs(
+    content: &str,
+    mut budget: usize,
+    format: impl Fn(&str, bool) -> String,
+) -> Vec<String> {
+    let (text, action) = match content.strip_prefix("/me ") {
+        Some(action) => (action, true),
+        None => (content, false),
+    };
+    if action {
+        budget -= ACTION_OVERHEAD;
+    }
+
+    let mut lines = Vec::new();
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        if line.len() <= budget {
+            lines.push(format(line, action));
+            continue;
+        }
+        let pieces = split_line(line, budget - CONTINUATION_MARKER.len());
+        let last = pieces.len() - 1;
+        for (i, piece) in pieces.into_iter().enumerate() {
+            if i == last {
+                lines.push(format(piece, action));
+            } else {
+                lines.push(format(
+                    &format!("{}{CONTINUATION_MARKER}", piece.trim_end()),
+                    action,
+                ));
+            }
+        }
+    }
+    lines
+}
+
+/// A message as a `draft/multiline` batch. `tag_prefix` (server-time, msgid)
+/// goes on the opening BATCH line; long lines are split with
+/// `draft/multiline-concat` so the client can rejoin them exactly.
+pub fn batch_lines(from: &str, target: &str, content: &str, tag_prefix: &str) -> Vec<String> {
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let source = format!("{from}!{from}@{}", formatter::server_name());
+    let budget = text_budget(from, target);
+
+    let mut lines = vec![format!(
+        "{tag_prefix}:{source} BATCH +{id} draft/multiline {target}"
+    )];
+    for line in content.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        for (i, piece) in split_line(line, budget).into_iter().enumerate() {
+            let tags = if i == 0 {
+                format!("@batch={id}")
+            } else {
+                format!("@batch={id};draft/multiline-concat")
+            };
+            lines.push(format!(
+                "{tags} {}",
+                formatter::privmsg(from, target, piece)
+            ));
+        }
+    }
+    lines.push(format!(":{source} BATCH -{id}"));
+    lines
+}
+
+/// What to do with a line from a client that negotiated `draft/multiline`.
+#[derive(Debug, PartialEq)]
+pub enum Received {
+    /// Not part of a multiline batch; handle it as usual.
+    Line,
+    /// Buffered into the open batch.
+    Buffered,
+    /// The batch closed; this PRIVMSG or NOTICE carries the whole message.
+    Complete(IrcMessage),
+    /// The batch was rejected; send this FAIL line. Its remaining lines are dropped.
+    Failed(String),
+}
+
+/// A client's `draft/multiline` batch being received.
+struct OpenBatch {
+    id: String,
+    target: String,
+    command: Option<String>,
+    content: String,
+    lines: usize,
+    failed: bool,
+}
+
+/// Collects `draft/multiline` batches from a client so a multi-line paste
+/// becomes one Concord message.
+pub struct MultilineReceiver {
+    max_bytes: usize,
+    open: Option<OpenBatch>,
+}
+
+impl MultilineReceiver {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            open: None,
+        }
+    }
+
+    /// Whether a line with these tags belongs to the open batch.
+    pub fn is_batched(&self, tags: Option<&str>) -> bool {
+        let id = tags.and_then(|t| tag_value(t, "batch"));
+        self.open
+            .as_ref()
+            .is_some_and(|batch| Some(batch.id.as_str()) == id)
+    }
+
+    /// Feed a parsed client line (and its raw tags) through the receiver.
+    pub fn receive(&mut self, tags: Option<&str>, msg: &IrcMessage) -> Received {
+        if msg.command == "BATCH" {
+            return self.batch_command(msg);
+        }
+        if !self.is_batched(tags) {
+            return Received::Line;
+        }
+        let Some(batch) = self.open.as_mut() else {
+            return Received::Line;
+        };
+        if batch.failed {
+            return Received::Buffered;
+        }
+
+        let [target, text] = msg.params.as_slice() else {
+            return self.fail("MULTILINE_INVALID", "Invalid multiline batch message");
+        };
+        let command_ok = match &batch.command {
+            Some(command) => *command == msg.command,
+            None => matches!(msg.command.as_str(), "PRIVMSG" | "NOTICE"),
+        };
+        if !command_ok {
+            return self.fail("MULTILINE_INVALID", "Invalid multiline batch message");
+        }
+        if !target.eq_ignore_ascii_case(&batch.target) {
+            let detail = format!("{} {target}", batch.target);
+            return self.fail(
+                &format!("MULTILINE_INVALID_TARGET {detail}"),
+                "Invalid multiline target",
+            );
+        }
+
+        let concat = tags.is_some_and(|t| tag_value(t, "draft/multiline-concat").is_some());
+        if batch.lines > 0 && !concat {
+            batch.content.push('\n');
+        }
+        batch.content.push_str(text);
+        batch.lines += 1;
+        batch.command = Some(msg.command.clone());
+
+        if batch.lines > MAX_LINES {
+            return self.fail(
+                &format!("MULTILINE_MAX_LINES {MAX_LINES}"),
+                "Multiline batch max-lines exceeded",
+            );
+        }
+        if batch.content.len() > self.max_bytes {
+            let max_bytes = self.max_bytes;
+            return self.fail(
+                &format!("MULTILINE_MAX_BYTES {max_bytes}"),
+                "Multiline batch max-bytes exceeded",
+            );
+        }
+        Received::Buffered
+    }
+
+    /// `BATCH +id draft/multiline target` / `BATCH -id`.
+    fn batch_command(&mut self, msg: &IrcMessage) -> Received {
+        let Some(reference) = msg.params.first() else {
+            return Received::Line;
+        };
+
+        if let Some(id) = reference.strip_prefix('+') {
+            if msg.params.get(1).map(String::as_str) != Some("draft/multiline") {
+                return Received::Line;
+            }
+            let Some(target) = msg.params.get(2) else {
+                return Received::Failed(fail_line(
+                    "MULTILINE_INVALID",
+                    "Missing multiline batch target",
+                ));
+            };
+            if self.open.is_some() {
+                self.open = None;
+                return Received::Failed(fail_line(
+                    "MULTILINE_INVALID",
+                    "Nested multiline batches are not supported",
+                ));
+            }
+            self.open = Some(OpenBatch {
+                id: id.to_string(),
+                target: target.clone(),
+                command: None,
+                content: String::new(),
+                lines: 0,
+                failed: false,
+            });
+            return Received::Buffered;
+        }
+
+        let Some(id) = reference.strip_prefix('-') else {
+            return Received::Line;
+        };
+        match self.open.take() {
+            Some(batch) if batch.id == id => {
+                if batch.failed {
+                    Received::Buffered
+                } else if batch.content.trim().is_empty() {
+                    Received::Failed(fail_line(
+                        "MULTILINE_INVALID",
+                        "Invalid multiline batch with blank lines only",
+                    ))
+                } else {
+                    Received::Complete(IrcMessage {
+                        prefix: None,
+                        command: batch.command.unwrap_or_else(|| "PRIVMSG".into()),
+                        params: vec![batch.target, batch.content],
+                    })
+                }
+            }
+            other => {
+                self.open = other;
+                Received::Line
+            }
+        }
+    }
+
+    /// Reject the open batch, dropping its content until it closes.
+    fn fail(&mut self, code: &str, description: &str) -> Received {
+        if let Some(batch) = self.open.as_mut() {
+            batch.failed = true;
+            batch.content.clear();
+        }
+        Received::Failed(fail_line(code, description))
+    }
+}
+
+/// `:concord FAIL BATCH <code> [context] :<description>`
+fn fail_line(code: &str, description: &str) -> String {
+    format!(
+        ":{} FAIL BATCH {code} :{description}",
+        formatter::server_name()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irc::parser::split_tags;
+
+    fn feed(rx: &mut MultilineReceiver, line: &str) -> Received {
+        let (tags, rest) = split_tags(line);
+        rx.receive(tags, &IrcMessage::parse(rest).unwrap())
+    }
+
+    #[test]
+    fn test_split_line_is_utf8_safe_and_lossless() {
+        let text = "héllo wörld ".repeat(40);
+        let pieces = split_line(&text, 50);
+        assert!(pieces.len() > 1);
+        assert!(pieces.iter().all(|p| p.len() <= 50));
+        assert_eq!(pieces.concat(), text);
+
+        // No spaces: breaks mid-word on a character boundary
+        let pieces = split_line("日本語日本語", 7);
+        assert_eq!(pieces, vec!["日本", "語日", "本語"]);
+    }
+
+    #[test]
+    fn test_privmsg_lines_split_long_and_multiline_messages() {
+        assert_eq!(
+            privmsg_lines("alice", "#general", "hi"),
+            vec![":alice!alice@concord PRIVMSG #general hi"]
+        );
+
+        let lines = privmsg_lines("alice", "#general", "first\r\n\nsecond line");
+        assert_eq!(
+            lines,
+            vec![
+                ":alice!alice@concord PRIVMSG #general first",
+                ":alice!alice@concord PRIVMSG #general :second line",
+            ]
+        );
+
+        let long = "word ".repeat(300);
+        let lines = privmsg_lines("alice", "#general", &long);
+        assert!(lines.len() >= 3);
+        for line in &lines {
+            assert!(line.len() + 2 <= MAX_LINE_BYTES, "{} bytes", line.len());
+        }
+        assert!(lines[0].ends_with("word…"));
+        assert!(!lines.last().unwrap().ends_with('…'));
+
+        let lines = privmsg_lines("alice", "#general", &format!("/me {long}"));
+        assert!(lines.iter().all(|l| l.contains("\x01ACTION ")));
+        assert!(lines.iter().all(|l| l.len() + 2 <= MAX_LINE_BYTES));
+    }
+
+    #[test]
+    fn test_client_privmsg_lines_leave_room_for_prefix() {
+        assert_eq!(
+            client_privmsg_lines("#rust", "/me waves"),
+            vec!["PRIVMSG #rust :\x01ACTION waves\x01"]
+        );
+
+        let lines = client_privmsg_lines("#rust", &"word ".repeat(300));
+        assert!(lines.len() >= 3);
+        for line in &lines {
+            assert!(line.len() + REMOTE_PREFIX_BYTES + 2 <= MAX_LINE_BYTES);
+        }
+    }
+
+    #[test]
+    fn test_batch_lines() {
+        let long = "x".repeat(600);
+        let lines = batch_lines("bob", "#dev", &format!("one\n{long}"), "@msgid=m1 ");
+        let id = lines[0]
+            .strip_prefix("@msgid=m1 :bob!bob@concord BATCH +")
+            .and_then(|rest| rest.strip_suffix(" draft/multiline #dev"))
+            .unwrap();
+        assert_eq!(
+            lines[1],
+            format!("@batch={id} :bob!bob@concord PRIVMSG #dev one")
+        );
+        assert!(lines[2].starts_with(&format!("@batch={id} ")));
+        assert!(lines[3].starts_with(&format!("@batch={id};draft/multiline-concat ")));
+        assert_eq!(
+            lines.last().unwrap(),
+            &format!(":bob!bob@concord BATCH -{id}")
+        );
+    }
+
+    #[test]
+    fn test_receive_multiline_batch() {
+        let mut rx = MultilineReceiver::new(4000);
+        assert_eq!(feed(&mut rx, "PRIVMSG #a :plain"), Received::Line);
+        assert_eq!(
+            feed(&mut rx, "BATCH +b1 draft/multiline #a"),
+            Received::Buffered
+        );
+        assert!(rx.is_batched(Some("batch=b1")));
+        assert_eq!(
+            feed(&mut rx, "@batch=b1 PRIVMSG #a :hello"),
+            Received::Buffered
+        );
+        assert_eq!(
+            feed(
+                &mut rx,
+                "@batch=b1;draft/multiline-concat PRIVMSG #a : world"
+            ),
+            Received::Buffered
+        );
+        assert_eq!(feed(&mut rx, "@batch=b1 PRIVMSG #a :"), Received::Buffered);
+        assert_eq!(
+            feed(&mut rx, "@batch=b1 PRIVMSG #a :second"),
+            Received::Buffered
+        );
+        assert_eq!(
+            feed(&mut rx, "BATCH -b1"),
+            Received::Complete(IrcMessage {
+                prefix: None,
+                command: "PRIVMSG".into(),
+                params: vec!["#a".into(), "hello world\n\nsecond".into()],
+            })
+        );
+        assert!(!rx.is_batched(Some("batch=b1")));
+    }
+
+    #[test]
+    fn test_receive_rejects_invalid_batches() {
+        let mut rx = MultilineReceiver::new(10);
+        feed(&mut rx, "BATCH +b2 draft/multiline #a");
+        match feed(&mut rx, "@batch=b2 PRIVMSG #other :hi") {
+            Received::Failed(line) => assert!(line.contains("MULTILINE_INVALID_TARGET #a #other")),
+            other => panic!("expected failure, got {other:?}"),
+        }
+        // The rest of a failed batch is swallowed
+        assert_eq!(
+            feed(&mut rx, "@batch=b2 PRIVMSG #a :hi"),
+            Received::Buffered
+        );
+        assert_eq!(feed(&mut rx, "BATCH -b2"), Received::Buffered);
+
+        feed(&mut rx, "BATCH +b3 draft/multiline #a");
+        match feed(&mut rx, "@batch=b3 PRIVMSG #a :far too long") {
+            Received::Failed(line) => assert!(line.contains("MULTILINE_MAX_BYTES 10")),
+            other => panic!("expected failure, got {other:?}"),
+        }
+        feed(&mut rx, "BATCH -b3");
+
+        feed(&mut rx, "BATCH +b4 draft/multiline #a");
+        feed(&mut rx, "@batch=b4 PRIVMSG #a : ");
+        assert!(matches!(feed(&mut rx, "BATCH -b4"), Received::Failed(_)));
+    }
+}
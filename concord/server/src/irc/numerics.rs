@@ -4,12 +4,40 @@ pub const RPL_WELCOME: &str = "001";
 pub const RPL_YOURHOST: &str = "002";
 pub const RPL_CREATED: &str = "003";
 pub const RPL_MYINFO: &str = "004";
+pub const RPL_ISUPPORT: &str = "005";
+
+// Server queries
+pub const RPL_VERSION: &str = "351";
 
 // Away
 pub const RPL_AWAY: &str = "301";
 pub const RPL_UNAWAY: &str = "305";
 pub const RPL_NOWAWAY: &str = "306";
 
+// MONITOR (IRCv3)
+pub const RPL_MONONLINE: &str = "730";
+pub const RPL_MONOFFLINE: &str = "731";
+pub const RPL_MONLIST: &str = "732";
+pub const RPL_ENDOFMONLIST: &str = "733";
+pub const ERR_MONLISTFULL: &str = "734";
+
+// WATCH
+pub const RPL_LOGON: &str = "600";
+pub const RPL_LOGOFF: &str = "601";
+pub const RPL_WATCHOFF: &str = "602";
+pub const RPL_WATCHSTAT: &str = "603";
+pub const RPL_NOWON: &str = "604";
+pub const RPL_NOWOFF: &str = "605";
+pub const RPL_WATCHLIST: &str = "606";
+pub const RPL_ENDOFWATCHLIST: &str = "607";
+pub const ERR_TOOMANYWATCH: &str = "512";
+
+// Modes
+pub const RPL_UMODEIS: &str = "221";
+pub const RPL_CHANNELMODEIS: &str = "324";
+pub const RPL_BANLIST: &str = "367";
+pub const RPL_ENDOFBANLIST: &str = "368";
+
 // Channel operations
 pub const RPL_TOPIC: &str = "332";
 pub const RPL_NOTOPIC: &str = "331";
@@ -28,6 +56,7 @@ pub const RPL_WHOISUSER: &str = "311";
 pub const RPL_WHOISSERVER: &str = "312";
 pub const RPL_ENDOFWHOIS: &str = "318";
 pub const RPL_WHOISCHANNELS: &str = "319";
+pub const RPL_WHOISHOST: &str = "378";
 
 // MOTD
 pub const RPL_MOTDSTART: &str = "375";
@@ -38,7 +67,10 @@ pub const ERR_NOMOTD: &str = "422";
 // SASL
 pub const RPL_LOGGEDIN: &str = "900";
 pub const RPL_SASLSUCCESS: &str = "903";
+pub const ERR_NICKLOCKED: &str = "902";
 pub const ERR_SASLFAIL: &str = "904";
+pub const ERR_SASLABORTED: &str = "906";
+pub const RPL_SASLMECHS: &str = "908";
 
 // Errors
 pub const ERR_NOSUCHNICK: &str = "401";
@@ -46,10 +78,13 @@ pub const ERR_NOSUCHCHANNEL: &str = "403";
 pub const ERR_CANNOTSENDTOCHAN: &str = "404";
 pub const ERR_UNKNOWNCOMMAND: &str = "421";
 pub const ERR_NONICKNAMEGIVEN: &str = "431";
+pub const ERR_ERRONEUSNICKNAME: &str = "432";
 pub const ERR_NICKNAMEINUSE: &str = "433";
 pub const ERR_NOTONCHANNEL: &str = "442";
 pub const ERR_NOTREGISTERED: &str = "451";
 pub const ERR_NEEDMOREPARAMS: &str = "461";
 pub const ERR_ALREADYREGISTERED: &str = "462";
 pub const ERR_PASSWDMISMATCH: &str = "464";
+pub const ERR_BANNEDFROMCHAN: &str = "474";
+pub const ERR_UNKNOWNMODE: &str = "472";
 pub const ERR_CHANOPRIVSNEEDED: &str = "482";
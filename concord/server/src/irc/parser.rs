@@ -96,8 +96,10 @@ impl IrcMessage {
 
         for (i, param) in self.params.iter().enumerate() {
             out.push(' ');
-            // Last param gets colon prefix if it contains spaces or is empty
-            if i == self.params.len() - 1 && (param.contains(' ') || param.is_empty()) {
+            // Last param gets colon prefix if it contains spaces, is empty, or starts with a colon
+            if i == self.params.len() - 1
+                && (param.contains(' ') || param.is_empty() || param.starts_with(':'))
+            {
                 out.push(':');
             }
             // Strip \r\n to prevent IRC command injection via user content
@@ -118,6 +120,27 @@ impl IrcMessage {
     }
 }
 
+/// Split IRCv3 message tags (`@key=value;key2 ...`) off the front of a line.
+/// Returns the raw tag string (without the `@`), if any, and the rest of the line.
+pub fn split_tags(line: &str) -> (Option<&str>, &str) {
+    match line.strip_prefix('@') {
+        Some(rest) => match rest.split_once(' ') {
+            Some((tags, rest)) => (Some(tags), rest.trim_start()),
+            None => (Some(rest), ""),
+        },
+        None => (None, line),
+    }
+}
+
+/// Look up a tag in a raw tag string. Tags without a value yield `Some("")`.
+pub fn tag_value<'a>(tags: &'a str, key: &str) -> Option<&'a str> {
+    tags.split(';').find_map(|tag| match tag.split_once('=') {
+        Some((k, v)) if k == key => Some(v),
+        None if tag == key => Some(""),
+        _ => None,
+    })
+}
+
 #[derive(Debug, PartialEq)]
 pub enum ParseError {
     Empty,
@@ -603,4 +626,27 @@ mod tests {
         let msg = IrcMessage::parse(original).unwrap();
         assert_eq!(msg.format(), original);
     }
+
+    #[test]
+    fn test_format_colon_leading_trailing_param() {
+        let msg = IrcMessage {
+            prefix: None,
+            command: "PRIVMSG".into(),
+            params: vec!["#general".into(), ":)".into()],
+        };
+        assert_eq!(msg.format(), "PRIVMSG #general ::)");
+        assert_eq!(IrcMessage::parse(&msg.format()).unwrap().params[1], ":)");
+    }
+
+    #[test]
+    fn test_split_tags() {
+        let (tags, rest) = split_tags("@batch=abc;draft/multiline-concat PRIVMSG #a :hi");
+        assert_eq!(rest, "PRIVMSG #a :hi");
+        let tags = tags.unwrap();
+        assert_eq!(tag_value(tags, "batch"), Some("abc"));
+        assert_eq!(tag_value(tags, "draft/multiline-concat"), Some(""));
+        assert_eq!(tag_value(tags, "msgid"), None);
+
+        assert_eq!(split_tags("PING x"), (None, "PING x"));
+    }
 }
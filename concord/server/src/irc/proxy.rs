@@ -0,0 +1,285 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::OnceLock;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tracing::warn;
+
+use crate::config::WebircGateway;
+
+/// Proxies allowed to send a PROXY protocol header, initialized at startup from config.
+static TRUSTED_PROXIES: OnceLock<Vec<IpAddr>> = OnceLock::new();
+
+/// Gateways allowed to use WEBIRC, initialized at startup from config.
+static WEBIRC_GATEWAYS: OnceLock<Vec<WebircGateway>> = OnceLock::new();
+
+/// PROXY protocol v2 signature (the first 12 bytes of every v2 header).
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Maximum length of a PROXY protocol v1 header line, including CRLF.
+const V1_MAX_LENGTH: usize = 107;
+
+/// Largest PROXY protocol v2 address block accepted (addresses plus TLVs).
+const V2_MAX_BODY: usize = 1024;
+
+/// Set the proxies whose connections start with a PROXY protocol header. Call once at startup.
+pub fn set_trusted_proxies(addrs: &[String]) {
+    let parsed = addrs
+        .iter()
+        .filter_map(|addr| match addr.parse::<IpAddr>() {
+            Ok(ip) => Some(ip.to_canonical()),
+            Err(_) => {
+                warn!(%addr, "ignoring invalid trusted proxy address");
+                None
+            }
+        })
+        .collect();
+    let _ = TRUSTED_PROXIES.set(parsed);
+}
+
+/// Whether connections from this address carry a PROXY protocol header.
+pub fn is_trusted_proxy(ip: IpAddr) -> bool {
+    TRUSTED_PROXIES
+        .get()
+        .is_some_and(|proxies| proxies.contains(&ip.to_canonical()))
+}
+
+/// Set the gateways allowed to use WEBIRC. Call once at startup.
+pub fn set_webirc_gateways(gateways: Vec<WebircGateway>) {
+    let _ = WEBIRC_GATEWAYS.set(gateways);
+}
+
+/// Read the PROXY protocol (v1 or v2) header that a trusted proxy sends before
+/// any client data. Returns the original client address, or None when the
+/// proxy doesn't know it (`UNKNOWN`, `LOCAL` health checks).
+///
+/// Reads exactly the header, so the stream can then be used for TLS or IRC.
+pub async fn read_proxy_header<R: AsyncRead + Unpin>(
+    reader: &mut R,
+) -> Result<Option<SocketAddr>, String> {
+    let mut start = [0u8; 6];
+    reader
+        .read_exact(&mut start)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if &start == b"PROXY " {
+        let mut line = start.to_vec();
+        while !line.ends_with(b"\r\n") {
+            if line.len() >= V1_MAX_LENGTH {
+                return Err("PROXY v1 header too long".into());
+            }
+            line.push(reader.read_u8().await.map_err(|e| e.to_string())?);
+        }
+        let line = std::str::from_utf8(&line).map_err(|_| "PROXY v1 header is not ASCII")?;
+        return parse_v1(line.trim_end());
+    }
+
+    if start == V2_SIGNATURE[..6] {
+        let mut header = [0u8; 16];
+        header[..6].copy_from_slice(&start);
+        reader
+            .read_exact(&mut header[6..])
+            .await
+            .map_err(|e| e.to_string())?;
+        let len = u16::from_be_bytes([header[14], header[15]]) as usize;
+        if len > V2_MAX_BODY {
+            return Err("PROXY v2 header too long".into());
+        }
+        let mut body = vec![0u8; len];
+        reader
+            .read_exact(&mut body)
+            .await
+            .map_err(|e| e.to_string())?;
+        return parse_v2(&header, &body);
+    }
+
+    Err("missing PROXY protocol header".into())
+}
+
+/// Parse a PROXY protocol v1 line (without CRLF):
+/// `PROXY TCP4|TCP6 <src ip> <dst ip> <src port> <dst port>` or `PROXY UNKNOWN ...`.
+fn parse_v1(line: &str) -> Result<Option<SocketAddr>, String> {
+    let fields: Vec<&str> = line.split(' ').collect();
+    match fields.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => Ok(None),
+        ["PROXY", "TCP4" | "TCP6", src, _dst, src_port, _dst_port] => {
+            let ip: IpAddr = src.parse().map_err(|_| "invalid PROXY v1 source address")?;
+            let port: u16 = src_port
+                .parse()
+                .map_err(|_| "invalid PROXY v1 source port")?;
+            Ok(Some(SocketAddr::new(ip, port)))
+        }
+        _ => Err("malformed PROXY v1 header".into()),
+    }
+}
+
+/// Parse a PROXY protocol v2 header and its address block.
+fn parse_v2(header: &[u8; 16], body: &[u8]) -> Result<Option<SocketAddr>, String> {
+    if header[..12] != V2_SIGNATURE {
+        return Err("invalid PROXY v2 signature".into());
+    }
+    if header[12] >> 4 != 2 {
+        return Err("unsupported PROXY protocol version".into());
+    }
+    match header[12] & 0x0F {
+        0x0 => return Ok(None), // LOCAL: the proxy's own connection (health check)
+        0x1 => {}
+        _ => return Err("unsupported PROXY v2 command".into()),
+    }
+
+    match header[13] >> 4 {
+        // AF_INET: src addr, dst addr, src port, dst port
+        0x1 if body.len() >= 12 => {
+            let ip = Ipv4Addr::new(body[0], body[1], body[2], body[3]);
+            let port = u16::from_be_bytes([body[8], body[9]]);
+            Ok(Some(SocketAddr::new(IpAddr::V4(ip), port)))
+        }
+        // AF_INET6
+        0x2 if body.len() >= 36 => {
+            let octets: [u8; 16] = body[..16].try_into().expect("slice is 16 bytes");
+            let port = u16::from_be_bytes([body[32], body[33]]);
+            Ok(Some(SocketAddr::new(
+                IpAddr::V6(Ipv6Addr::from(octets)),
+                port,
+            )))
+        }
+        // AF_UNSPEC or AF_UNIX: no usable client address
+        0x0 | 0x3 => Ok(None),
+        _ => Err("truncated PROXY v2 address block".into()),
+    }
+}
+
+/// A client identity passed on by a web IRC gateway with WEBIRC.
+#[derive(Debug, PartialEq)]
+pub struct Webirc {
+    /// Name of the matching gateway block in the config.
+    pub gateway: String,
+    pub hostname: String,
+    pub ip: IpAddr,
+}
+
+/// Check `WEBIRC <password> <gateway> <hostname> <ip> [:options]` sent from `peer_ip`.
+/// Returns the client identity if a configured gateway allows it.
+pub fn accept_webirc(peer_ip: IpAddr, params: &[String]) -> Result<Webirc, String> {
+    let [password, _gateway, hostname, ip, ..] = params else {
+        return Err("Not enough parameters".into());
+    };
+    let ip: IpAddr = ip.parse().map_err(|_| "Invalid client IP")?;
+
+    let gateway = WEBIRC_GATEWAYS
+        .get()
+        .into_iter()
+        .flatten()
+        .find(|gw| gateway_allows(gw, peer_ip, password))
+        .ok_or("Invalid WEBIRC credentials")?;
+
+    Ok(Webirc {
+        gateway: gateway.name.clone(),
+        hostname: hostname.clone(),
+        ip: ip.to_canonical(),
+    })
+}
+
+fn gateway_allows(gateway: &WebircGateway, peer_ip: IpAddr, password: &str) -> bool {
+    let peer_ip = peer_ip.to_canonical();
+    let host_ok = gateway
+        .hosts
+        .iter()
+        .filter_map(|host| host.parse::<IpAddr>().ok())
+        .any(|host| host.to_canonical() == peer_ip);
+    let password_ok = gateway.password.len() == password.len()
+        && gateway
+            .password
+            .bytes()
+            .zip(password.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0;
+    host_ok && password_ok && !gateway.password.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v2_header(command: u8, family: u8, body: &[u8]) -> Vec<u8> {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.push(0x20 | command);
+        header.push(family);
+        header.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        header.extend_from_slice(body);
+        header
+    }
+
+    #[test]
+    fn test_parse_v1() {
+        assert_eq!(
+            parse_v1("PROXY TCP4 203.0.113.7 10.0.0.1 51234 6667"),
+            Ok(Some("203.0.113.7:51234".parse().unwrap()))
+        );
+        assert_eq!(
+            parse_v1("PROXY TCP6 2001:db8::1 2001:db8::2 4000 6697"),
+            Ok(Some("[2001:db8::1]:4000".parse().unwrap()))
+        );
+        assert_eq!(parse_v1("PROXY UNKNOWN"), Ok(None));
+        assert!(parse_v1("PROXY TCP4 nonsense 10.0.0.1 1 2").is_err());
+        assert!(parse_v1("PROXY TCP4 1.2.3.4").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_v1_header_leaves_client_data() {
+        let mut input: &[u8] = b"PROXY TCP4 198.51.100.9 10.0.0.1 40000 6667\r\nNICK alice\r\n";
+        let addr = read_proxy_header(&mut input).await.unwrap();
+        assert_eq!(addr, Some("198.51.100.9:40000".parse().unwrap()));
+        assert_eq!(input, b"NICK alice\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_read_v2_header() {
+        let mut body = vec![192, 0, 2, 10, 10, 0, 0, 1];
+        body.extend_from_slice(&5555u16.to_be_bytes());
+        body.extend_from_slice(&6697u16.to_be_bytes());
+        let mut bytes = v2_header(0x1, 0x11, &body);
+        bytes.extend_from_slice(b"CAP LS\r\n");
+
+        let mut input = bytes.as_slice();
+        let addr = read_proxy_header(&mut input).await.unwrap();
+        assert_eq!(addr, Some("192.0.2.10:5555".parse().unwrap()));
+        assert_eq!(input, b"CAP LS\r\n");
+
+        // LOCAL (health check) carries no client address
+        let local = v2_header(0x0, 0x00, &[]);
+        assert_eq!(read_proxy_header(&mut local.as_slice()).await, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn test_missing_header_is_rejected() {
+        let mut input: &[u8] = b"NICK alice\r\n";
+        assert!(read_proxy_header(&mut input).await.is_err());
+
+        let mut long = b"PROXY ".to_vec();
+        long.extend(std::iter::repeat_n(b'1', 200));
+        assert!(read_proxy_header(&mut long.as_slice()).await.is_err());
+    }
+
+    #[test]
+    fn test_gateway_allows_matching_host_and_password() {
+        let gateway = WebircGateway {
+            name: "kiwiirc".into(),
+            password: "s3cret".into(),
+            hosts: vec!["10.1.2.3".into()],
+        };
+        let gw_ip: IpAddr = "10.1.2.3".parse().unwrap();
+        assert!(gateway_allows(&gateway, gw_ip, "s3cret"));
+        assert!(gateway_allows(
+            &gateway,
+            "::ffff:10.1.2.3".parse().unwrap(),
+            "s3cret"
+        ));
+        assert!(!gateway_allows(&gateway, gw_ip, "wrong!"));
+        assert!(!gateway_allows(
+            &gateway,
+            "10.9.9.9".parse().unwrap(),
+            "s3cret"
+        ));
+    }
+}
@@ -0,0 +1,582 @@
+use std::sync::LazyLock;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+use crate::auth::token::{SCRAM_ITERATIONS, hmac_sha256, verify_irc_token};
+use crate::db::queries::users;
+
+/// SASL mechanisms offered to IRC clients, advertised in the `sasl=` CAP value
+/// and in RPL_SASLMECHS.
+pub const MECHANISMS: &str = "PLAIN,EXTERNAL,SCRAM-SHA-256";
+
+/// AUTHENTICATE payloads longer than this are split into chunks of this size.
+const CHUNK_SIZE: usize = 400;
+
+/// Longest reassembled SASL payload accepted (base64).
+const MAX_PAYLOAD: usize = 8192;
+
+/// Key for the SCRAM salts made up for nicks without credentials. Random per
+/// process, so repeated attempts for a nick see the same salt.
+static FAKE_SALT_KEY: LazyLock<[u8; 32]> = LazyLock::new(|| {
+    let mut key = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut key);
+    key
+});
+
+/// What to send the client after an AUTHENTICATE line.
+#[derive(Debug, PartialEq)]
+pub enum SaslReply {
+    /// Send `AUTHENTICATE <data>` (base64, or `+` for an empty challenge).
+    Challenge(String),
+    /// Authentication succeeded for this account.
+    Success {
+        user_id: String,
+        account: String,
+    },
+    Failure,
+    Aborted,
+    /// The requested mechanism isn't one of [`MECHANISMS`].
+    UnknownMechanism,
+}
+
+/// SASL state for one IRC connection.
+#[derive(Default)]
+pub struct SaslSession {
+    exchange: Option<Exchange>,
+    /// Base64 chunks received so far for the current payload.
+    buffer: String,
+}
+
+enum Exchange {
+    Plain,
+    External,
+    /// SCRAM-SHA-256, waiting for client-first-message.
+    ScramStart,
+    /// SCRAM-SHA-256, waiting for client-final-message.
+    Scram(ScramExchange),
+    /// SCRAM-SHA-256 server-final-message sent, waiting for the client's empty reply.
+    ScramVerified {
+        user_id: String,
+        account: String,
+    },
+}
+
+impl SaslSession {
+    /// Process the parameter of an AUTHENTICATE command.
+    ///
+    /// Returns None while a chunked payload is still being received.
+    /// `cert_fp` is the SHA-256 fingerprint of the client's TLS certificate, if any.
+    pub async fn step(
+        &mut self,
+        db: &SqlitePool,
+        cert_fp: Option<&str>,
+        param: &str,
+    ) -> Option<SaslReply> {
+        if param == "*" {
+            *self = Self::default();
+            return Some(SaslReply::Aborted);
+        }
+
+        let Some(exchange) = self.exchange.take() else {
+            return Some(self.start(param));
+        };
+
+        // Reassemble payloads split into 400-byte chunks ("+" ends an exact multiple)
+        if param != "+" {
+            self.buffer.push_str(param);
+        }
+        if self.buffer.len() > MAX_PAYLOAD {
+            *self = Self::default();
+            return Some(SaslReply::Failure);
+        }
+        if param.len() == CHUNK_SIZE {
+            self.exchange = Some(exchange);
+            return None;
+        }
+
+        let encoded = std::mem::take(&mut self.buffer);
+        let Ok(payload) = BASE64.decode(encoded) else {
+            return Some(SaslReply::Failure);
+        };
+
+        let reply = match exchange {
+            Exchange::Plain => authenticate_plain(db, &payload).await,
+            Exchange::External => authenticate_external(db, cert_fp, &payload).await,
+            Exchange::ScramStart => match ScramExchange::start(db, &payload).await {
+                Some(scram) => {
+                    let challenge = BASE64.encode(&scram.server_first);
+                    self.exchange = Some(Exchange::Scram(scram));
+                    SaslReply::Challenge(challenge)
+                }
+                None => SaslReply::Failure,
+            },
+            Exchange::Scram(scram) => match scram.finish(&payload) {
+                Some((keys, server_final)) => {
+                    let _ = users::touch_irc_token(db, &keys.user_id, &keys.token_hash).await;
+                    self.exchange = Some(Exchange::ScramVerified {
+                        user_id: keys.user_id,
+                        account: scram.account,
+                    });
+                    SaslReply::Challenge(BASE64.encode(server_final))
+                }
+                None => SaslReply::Failure,
+            },
+            Exchange::ScramVerified { user_id, account } => SaslReply::Success { user_id, account },
+        };
+
+        // Suspended and instance-banned accounts can't sign in, whatever the mechanism
+        if let SaslReply::Success { user_id, .. } = &reply
+            && !users::is_account_active(db, user_id).await.unwrap_or(false)
+        {
+            return Some(SaslReply::Failure);
+        }
+        Some(reply)
+    }
+
+    fn start(&mut self, mechanism: &str) -> SaslReply {
+        self.exchange = Some(match mechanism.to_ascii_uppercase().as_str() {
+            "PLAIN" => Exchange::Plain,
+            "EXTERNAL" => Exchange::External,
+            "SCRAM-SHA-256" => Exchange::ScramStart,
+            _ => return SaslReply::UnknownMechanism,
+        });
+        SaslReply::Challenge("+".into())
+    }
+}
+
+/// Validate an IRC PASS token against stored hashes.
+/// Returns Ok(Some(user_id)) if the token matches, Ok(None) if not.
+pub async fn validate_irc_pass(
+    db: &SqlitePool,
+    token: &str,
+    nickname: &str,
+) -> Result<Option<String>, String> {
+    // Scoped lookup: only fetch tokens for this nickname (O(1) per user instead of O(n) global)
+    let hashes = users::get_irc_token_hashes_by_nick(db, nickname)
+        .await
+        .map_err(|e| format!("DB error: {}", e))?;
+
+    for (user_id, token_hash) in &hashes {
+        if verify_irc_token(token, token_hash) {
+            // Suspended and instance-banned accounts can't sign in
+            if !users::is_account_active(db, user_id)
+                .await
+                .map_err(|e| format!("DB error: {}", e))?
+            {
+                return Ok(None);
+            }
+            // Update last_used timestamp (fire-and-forget)
+            let pool = db.clone();
+            let uid = user_id.clone();
+            let hash = token_hash.clone();
+            tokio::spawn(async move {
+                let _ = users::touch_irc_token(&pool, &uid, &hash).await;
+            });
+            return Ok(Some(user_id.clone()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// PLAIN: `authzid \0 authcid \0 token`, where authcid is the account's nickname.
+async fn authenticate_plain(db: &SqlitePool, payload: &[u8]) -> SaslReply {
+    let parts: Vec<&[u8]> = payload.splitn(3, |&b| b == 0).collect();
+    let [_authzid, authcid, passwd] = parts.as_slice() else {
+        return SaslReply::Failure;
+    };
+    let (Ok(authcid), Ok(passwd)) = (std::str::from_utf8(authcid), std::str::from_utf8(passwd))
+    else {
+        return SaslReply::Failure;
+    };
+    if authcid.is_empty() {
+        return SaslReply::Failure;
+    }
+
+    match validate_irc_pass(db, passwd, authcid).await {
+        Ok(Some(user_id)) => SaslReply::Success {
+            user_id,
+            account: authcid.to_string(),
+        },
+        _ => SaslReply::Failure,
+    }
+}
+
+/// EXTERNAL: the TLS client certificate identifies the account. The payload is
+/// an optional authzid, which must then name that account.
+async fn authenticate_external(
+    db: &SqlitePool,
+    cert_fp: Option<&str>,
+    payload: &[u8],
+) -> SaslReply {
+    let Some(fingerprint) = cert_fp else {
+        return SaslReply::Failure;
+    };
+    let Ok(Some((user_id, username))) = users::get_user_by_cert_fingerprint(db, fingerprint).await
+    else {
+        return SaslReply::Failure;
+    };
+
+    let authzid = String::from_utf8_lossy(payload);
+    if !authzid.is_empty() && authzid != username {
+        return SaslReply::Failure;
+    }
+    SaslReply::Success {
+        user_id,
+        account: username,
+    }
+}
+
+/// Stored SCRAM keys for one of an account's IRC tokens.
+#[derive(Debug, Clone)]
+struct ScramKeys {
+    user_id: String,
+    token_hash: String,
+    stored_key: Vec<u8>,
+    server_key: Vec<u8>,
+}
+
+/// A parsed SCRAM client-first-message.
+#[derive(Debug, PartialEq)]
+struct ClientFirst {
+    gs2_header: String,
+    bare: String,
+    username: String,
+    nonce: String,
+}
+
+/// Parse `gs2-header client-first-message-bare` (RFC 5802 section 7).
+/// Channel binding is not supported, so `p=` is rejected.
+fn parse_client_first(message: &str) -> Option<ClientFirst> {
+    let mut parts = message.splitn(3, ',');
+    let cb_flag = parts.next()?;
+    let authzid = parts.next()?;
+    let bare = parts.next()?;
+    if cb_flag != "n" && cb_flag != "y" {
+        return None;
+    }
+
+    let mut attrs = bare.split(',');
+    let username = attrs.next()?.strip_prefix("n=")?;
+    let nonce = attrs.next()?.strip_prefix("r=")?;
+    if username.is_empty() || nonce.is_empty() {
+        return None;
+    }
+
+    Some(ClientFirst {
+        gs2_header: format!("{cb_flag},{authzid},"),
+        bare: bare.to_string(),
+        username: username.replace("=2C", ",").replace("=3D", "="),
+        nonce: nonce.to_string(),
+    })
+}
+
+/// Server side of a SCRAM-SHA-256 exchange after the server-first-message.
+struct ScramExchange {
+    client_first: ClientFirst,
+    server_first: String,
+    /// Combined client and server nonce.
+    nonce: String,
+    account: String,
+    /// Keys of every token sharing the advertised salt; the client's proof may match any.
+    candidates: Vec<ScramKeys>,
+}
+
+impl ScramExchange {
+    /// Parse the client-first-message and look up the account's SCRAM keys.
+    async fn start(db: &SqlitePool, payload: &[u8]) -> Option<Self> {
+        let client_first = parse_client_first(std::str::from_utf8(payload).ok()?)?;
+        let rows = users::get_irc_token_scram_by_nick(db, &client_first.username)
+            .await
+            .ok()?;
+        // Nicks without SCRAM credentials get a made-up salt and fail at the
+        // proof, so the exchange doesn't reveal which accounts exist
+        let (salt, iterations) = match rows.first() {
+            Some((_, _, salt, iterations, _, _)) => (salt.clone(), *iterations),
+            None => (
+                fake_salt(&client_first.username),
+                i64::from(SCRAM_ITERATIONS),
+            ),
+        };
+
+        let candidates = rows
+            .into_iter()
+            .filter(|row| row.2 == salt && row.3 == iterations)
+            .filter_map(|(user_id, token_hash, _, _, stored_key, server_key)| {
+                Some(ScramKeys {
+                    user_id,
+                    token_hash,
+                    stored_key: BASE64.decode(stored_key).ok()?,
+                    server_key: BASE64.decode(server_key).ok()?,
+                })
+            })
+            .collect();
+
+        let mut server_nonce = [0u8; 18];
+        rand::thread_rng().fill_bytes(&mut server_nonce);
+        Some(Self::new(
+            client_first,
+            &BASE64.encode(server_nonce),
+            &salt,
+            iterations,
+            candidates,
+        ))
+    }
+
+    fn new(
+        client_first: ClientFirst,
+        server_nonce: &str,
+        salt: &str,
+        iterations: i64,
+        candidates: Vec<ScramKeys>,
+    ) -> Self {
+        let nonce = format!("{}{server_nonce}", client_first.nonce);
+        Self {
+            server_first: format!("r={nonce},s={salt},i={iterations}"),
+            nonce,
+            account: client_first.username.clone(),
+            client_first,
+            candidates,
+        }
+    }
+
+    /// Verify the client-final-message. On success returns the matching token's
+    /// keys and the server-final-message.
+    fn finish(&self, payload: &[u8]) -> Option<(ScramKeys, String)> {
+        let message = std::str::from_utf8(payload).ok()?;
+        let (without_proof, proof) = message.rsplit_once(",p=")?;
+        let proof = BASE64.decode(proof).ok()?;
+
+        let mut attrs = without_proof.split(',');
+        let binding = attrs.next()?.strip_prefix("c=")?;
+        let nonce = attrs.next()?.strip_prefix("r=")?;
+        if binding != BASE64.encode(&self.client_first.gs2_header) || nonce != self.nonce {
+            return None;
+        }
+
+        let auth_message = format!(
+            "{},{},{without_proof}",
+            self.client_first.bare, self.server_first
+        );
+        self.candidates.iter().find_map(|keys| {
+            let signature = hmac_sha256(&keys.stored_key, auth_message.as_bytes());
+            if proof.len() != signature.len() {
+                return None;
+            }
+            let client_key: Vec<u8> = proof.iter().zip(signature).map(|(p, s)| p ^ s).collect();
+            if !constant_time_eq(&Sha256::digest(&client_key), &keys.stored_key) {
+                return None;
+            }
+            let server_signature = hmac_sha256(&keys.server_key, auth_message.as_bytes());
+            Some((
+                keys.clone(),
+                format!("v={}", BASE64.encode(server_signature)),
+            ))
+        })
+    }
+}
+
+/// A salt for a nick that has no SCRAM credentials, stable for the process.
+fn fake_salt(username: &str) -> String {
+    let mac = hmac_sha256(&*FAKE_SALT_KEY, username.to_ascii_lowercase().as_bytes());
+    BASE64.encode(&mac[..16])
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::token::scram_credentials;
+
+    // RFC 7677 section 3 example exchange (user "user", password "pencil")
+    const CLIENT_FIRST: &str = "n,,n=user,r=rOprNGfwEbeRWgbNEkqO";
+    const SERVER_NONCE: &str = "%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0";
+    const SALT: &str = "W22ZaJ0SNY7soEsUEjb6gQ==";
+    const CLIENT_FINAL: &str = "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,\
+                                p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=";
+
+    fn rfc_exchange(password: &str) -> ScramExchange {
+        let creds = scram_credentials(password, &BASE64.decode(SALT).unwrap(), 4096);
+        ScramExchange::new(
+            parse_client_first(CLIENT_FIRST).unwrap(),
+            SERVER_NONCE,
+            SALT,
+            4096,
+            vec![ScramKeys {
+                user_id: "u1".into(),
+                token_hash: "hash".into(),
+                stored_key: creds.stored_key,
+                server_key: creds.server_key,
+            }],
+        )
+    }
+
+    #[test]
+    fn test_parse_client_first() {
+        let first = parse_client_first("n,,n=al=2Cice,r=abc").unwrap();
+        assert_eq!(first.gs2_header, "n,,");
+        assert_eq!(first.bare, "n=al=2Cice,r=abc");
+        assert_eq!(first.username, "al,ice");
+        assert_eq!(first.nonce, "abc");
+
+        assert!(parse_client_first("p=tls-unique,,n=user,r=abc").is_none());
+        assert!(parse_client_first("n,,r=abc").is_none());
+        assert!(parse_client_first("garbage").is_none());
+    }
+
+    #[test]
+    fn test_scram_rfc7677_exchange() {
+        let scram = rfc_exchange("pencil");
+        assert_eq!(
+            scram.server_first,
+            "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096"
+        );
+
+        let (keys, server_final) = scram.finish(CLIENT_FINAL.as_bytes()).unwrap();
+        assert_eq!(keys.user_id, "u1");
+        assert_eq!(
+            server_final,
+            "v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4="
+        );
+    }
+
+    #[test]
+    fn test_scram_rejects_wrong_password_and_nonce() {
+        assert!(
+            rfc_exchange("not-pencil")
+                .finish(CLIENT_FINAL.as_bytes())
+                .is_none()
+        );
+
+        let tampered = CLIENT_FINAL.replace("hNlF$k0", "hNlF$k1");
+        assert!(rfc_exchange("pencil").finish(tampered.as_bytes()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mechanism_selection_and_abort() {
+        let pool = crate::db::pool::create_pool("sqlite::memory:")
+            .await
+            .unwrap();
+        let mut sasl = SaslSession::default();
+
+        assert_eq!(
+            sasl.step(&pool, None, "DIGEST-MD5").await,
+            Some(SaslReply::UnknownMechanism)
+        );
+        assert_eq!(
+            sasl.step(&pool, None, "scram-sha-256").await,
+            Some(SaslReply::Challenge("+".into()))
+        );
+        assert_eq!(sasl.step(&pool, None, "*").await, Some(SaslReply::Aborted));
+
+        // EXTERNAL without a client certificate fails
+        sasl.step(&pool, None, "EXTERNAL").await;
+        assert_eq!(sasl.step(&pool, None, "+").await, Some(SaslReply::Failure));
+    }
+
+    #[tokio::test]
+    async fn test_scram_unknown_nick_fails_at_proof() {
+        let pool = crate::db::pool::create_pool("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::pool::run_migrations(&pool).await.unwrap();
+
+        // An unknown nick is challenged like a real one, with the same salt each time
+        let mut salts = Vec::new();
+        for _ in 0..2 {
+            let mut sasl = SaslSession::default();
+            sasl.step(&pool, None, "SCRAM-SHA-256").await;
+            let Some(SaslReply::Challenge(challenge)) =
+                sasl.step(&pool, None, &BASE64.encode(CLIENT_FIRST)).await
+            else {
+                panic!("expected a server-first-message");
+            };
+            let server_first = String::from_utf8(BASE64.decode(challenge).unwrap()).unwrap();
+            let (nonce, salt) = server_first.split_once(",s=").unwrap();
+            assert!(salt.ends_with(",i=4096"));
+            salts.push(salt.to_string());
+
+            let client_final =
+                format!("c=biws,{nonce},p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=");
+            assert_eq!(
+                sasl.step(&pool, None, &BASE64.encode(client_final)).await,
+                Some(SaslReply::Failure)
+            );
+        }
+        assert_eq!(salts[0], salts[1]);
+    }
+
+    #[tokio::test]
+    async fn test_scram_login_ignores_nick_case() {
+        let pool = crate::db::pool::create_pool("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::pool::run_migrations(&pool).await.unwrap();
+        users::create_with_oauth(
+            &pool,
+            &users::CreateOAuthUser {
+                user_id: "u1",
+                username: "User",
+                email: None,
+                avatar_url: None,
+                oauth_id: "oauth-u1",
+                provider: "github",
+                provider_id: "gh-u1",
+            },
+        )
+        .await
+        .unwrap();
+        users::create_irc_token(&pool, "t1", "u1", "hash", None)
+            .await
+            .unwrap();
+        let creds = scram_credentials("pencil", &BASE64.decode(SALT).unwrap(), 4096);
+        users::set_irc_token_scram(
+            &pool,
+            "t1",
+            SALT,
+            4096,
+            &BASE64.encode(creds.stored_key),
+            &BASE64.encode(creds.server_key),
+        )
+        .await
+        .unwrap();
+
+        // The RFC client logs in as "user"; the account is "User"
+        let scram = ScramExchange::start(&pool, CLIENT_FIRST.as_bytes())
+            .await
+            .unwrap();
+        assert!(scram.server_first.ends_with(&format!(",s={SALT},i=4096")));
+
+        // Replay the RFC's server nonce so its client proof applies
+        let scram = ScramExchange::new(
+            scram.client_first,
+            SERVER_NONCE,
+            SALT,
+            4096,
+            scram.candidates,
+        );
+        let (keys, _) = scram.finish(CLIENT_FINAL.as_bytes()).unwrap();
+        assert_eq!(keys.user_id, "u1");
+    }
+
+    #[tokio::test]
+    async fn test_chunked_payload_is_reassembled() {
+        let pool = crate::db::pool::create_pool("sqlite::memory:")
+            .await
+            .unwrap();
+        let mut sasl = SaslSession::default();
+        sasl.step(&pool, None, "PLAIN").await;
+
+        let chunk = "A".repeat(CHUNK_SIZE);
+        assert_eq!(sasl.step(&pool, None, &chunk).await, None);
+        assert_eq!(sasl.step(&pool, None, &chunk).await, None);
+        assert!(sasl.step(&pool, None, "+").await.is_some());
+    }
+}
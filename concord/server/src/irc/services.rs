@@ -0,0 +1,614 @@
+use sqlx::SqlitePool;
+
+use crate::auth::token::normalize_cert_fingerprint;
+use crate::db::queries::{messages, servers, users};
+use crate::engine::chat_engine::ChatEngine;
+use crate::engine::events::{ReportInfo, SessionId};
+
+use super::commands::{self, parse_irc_channel, to_irc_channel};
+use super::formatter;
+
+/// Nickname of the built-in services pseudo-client.
+pub const SERVICE_NICK: &str = "Concord";
+
+/// Nicknames that reach the services pseudo-client. `NickServ` and `ChanServ`
+/// are accepted so that client scripts written for other networks keep working.
+pub const SERVICE_ALIASES: &[&str] = &[SERVICE_NICK, "NickServ", "ChanServ"];
+
+/// Maximum length of a message excerpt shown in PINS and BOOKMARKS replies.
+const EXCERPT_LENGTH: usize = 120;
+
+const HELP_LINES: &[&str] = &[
+    "Concord services — available commands:",
+    "SERVERS — list the servers you belong to",
+    "SERVER [server] — show or set the server bare #channel names refer to",
+    "JOIN <server> — join a public server by name or ID",
+    "INVITE USE <code> — join a server with an invite code",
+    "DISCOVER [category] — browse public servers",
+    "TOKENS LIST — list your IRC access tokens",
+    "TOKENS REVOKE <id> — revoke an IRC access token",
+    "CERT LIST — list client certificates registered for SASL EXTERNAL",
+    "CERT ADD [fingerprint] — register a certificate (default: the one you connected with)",
+    "CERT DEL <fingerprint> — unregister a client certificate",
+    "PINS <#channel> — show pinned messages in a channel",
+    "BOOKMARKS — list your bookmarked messages",
+    "EVENTS <server> — list scheduled events in a server",
+    "RSVP <server> <event-id> <interested|going|not_going> — respond to an event",
+    "REPORT MSG <msgid> <reason> — report a message to the server's moderators",
+    "REPORT USER <server> <nick> <reason> — report a member to the server's moderators",
+];
+
+/// Whether a PRIVMSG/NOTICE target (or a requested nickname) is the services pseudo-client.
+pub fn is_service_nick(target: &str) -> bool {
+    SERVICE_ALIASES
+        .iter()
+        .any(|alias| alias.eq_ignore_ascii_case(target))
+}
+
+/// A command sent to the services pseudo-client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServiceCommand {
+    Help,
+    Servers,
+    /// Show (None) or set the connection's default server.
+    Server(Option<String>),
+    Join(String),
+    InviteUse(String),
+    Discover(Option<String>),
+    TokensList,
+    TokensRevoke(String),
+    CertList,
+    /// Register a fingerprint (None: the current connection's certificate).
+    CertAdd(Option<String>),
+    CertDel(String),
+    Pins(String),
+    Bookmarks,
+    Events(String),
+    Rsvp {
+        server: String,
+        event_id: String,
+        status: String,
+    },
+    /// Report a message, identified by its IRCv3 `msgid` tag.
+    ReportMessage {
+        message_id: String,
+        reason: String,
+    },
+    ReportUser {
+        server: String,
+        nick: String,
+        reason: String,
+    },
+}
+
+/// Parse the text of a PRIVMSG to services. Returns the usage line on error.
+pub fn parse_command(text: &str) -> Result<ServiceCommand, String> {
+    let mut words = text.split_whitespace();
+    let Some(command) = words.next() else {
+        return Ok(ServiceCommand::Help);
+    };
+    let args: Vec<&str> = words.collect();
+
+    match (command.to_ascii_uppercase().as_str(), args.as_slice()) {
+        ("HELP", _) => Ok(ServiceCommand::Help),
+        ("SERVERS", _) => Ok(ServiceCommand::Servers),
+        ("SERVER", [server, ..]) => Ok(ServiceCommand::Server(Some(server.to_string()))),
+        ("SERVER", []) => Ok(ServiceCommand::Server(None)),
+        ("JOIN", [server, ..]) => Ok(ServiceCommand::Join(server.to_string())),
+        ("JOIN", []) => Err("Usage: JOIN <server>".into()),
+        ("INVITE", [sub, code, ..]) if sub.eq_ignore_ascii_case("USE") => {
+            Ok(ServiceCommand::InviteUse(code.to_string()))
+        }
+        ("INVITE", _) => Err("Usage: INVITE USE <code>".into()),
+        ("DISCOVER", [category, ..]) => Ok(ServiceCommand::Discover(Some(category.to_string()))),
+        ("DISCOVER", []) => Ok(ServiceCommand::Discover(None)),
+        ("TOKENS", [sub]) if sub.eq_ignore_ascii_case("LIST") => Ok(ServiceCommand::TokensList),
+        ("TOKENS", [sub, id, ..]) if sub.eq_ignore_ascii_case("REVOKE") => {
+            Ok(ServiceCommand::TokensRevoke(id.to_string()))
+        }
+        ("TOKENS", _) => Err("Usage: TOKENS LIST | TOKENS REVOKE <id>".into()),
+        ("CERT", [sub]) if sub.eq_ignore_ascii_case("LIST") => Ok(ServiceCommand::CertList),
+        ("CERT", [sub, rest @ ..]) if sub.eq_ignore_ascii_case("ADD") => Ok(
+            ServiceCommand::CertAdd(rest.first().map(|fp| fp.to_string())),
+        ),
+        ("CERT", [sub, fp, ..]) if sub.eq_ignore_ascii_case("DEL") => {
+            Ok(ServiceCommand::CertDel(fp.to_string()))
+        }
+        ("CERT", _) => {
+            Err("Usage: CERT LIST | CERT ADD [fingerprint] | CERT DEL <fingerprint>".into())
+        }
+        ("PINS", [channel, ..]) => Ok(ServiceCommand::Pins(channel.to_string())),
+        ("PINS", []) => Err("Usage: PINS <#channel>".into()),
+        ("BOOKMARKS", _) => Ok(ServiceCommand::Bookmarks),
+        ("EVENTS", [server, ..]) => Ok(ServiceCommand::Events(server.to_string())),
+        ("EVENTS", []) => Err("Usage: EVENTS <server>".into()),
+        ("RSVP", [server, event_id, status, ..]) => Ok(ServiceCommand::Rsvp {
+            server: server.to_string(),
+            event_id: event_id.to_string(),
+            status: status.to_ascii_lowercase(),
+        }),
+        ("RSVP", _) => Err("Usage: RSVP <server> <event-id> <interested|going|not_going>".into()),
+        ("REPORT", [sub, message_id, reason @ ..])
+            if sub.eq_ignore_ascii_case("MSG") && !reason.is_empty() =>
+        {
+            Ok(ServiceCommand::ReportMessage {
+                message_id: message_id.to_string(),
+                reason: reason.join(" "),
+            })
+        }
+        ("REPORT", [sub, server, nick, reason @ ..])
+            if sub.eq_ignore_ascii_case("USER") && !reason.is_empty() =>
+        {
+            Ok(ServiceCommand::ReportUser {
+                server: server.to_string(),
+                nick: nick.to_string(),
+                reason: reason.join(" "),
+            })
+        }
+        ("REPORT", _) => {
+            Err("Usage: REPORT MSG <msgid> <reason> | REPORT USER <server> <nick> <reason>".into())
+        }
+        (other, _) => Err(format!(
+            "Unknown command {other}. Send HELP for a list of commands."
+        )),
+    }
+}
+
+/// Collapse a message body onto one line and shorten it for a NOTICE.
+pub fn excerpt(content: &str) -> String {
+    let flat = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() <= EXCERPT_LENGTH {
+        flat
+    } else {
+        let cut: String = flat.chars().take(EXCERPT_LENGTH).collect();
+        format!("{cut}…")
+    }
+}
+
+/// The notice a reporter gets when a moderator resolves their report.
+pub fn report_resolution_text(server: &str, report: &ReportInfo) -> String {
+    let outcome = match report.resolution.as_deref() {
+        Some("delete") => "the message was deleted",
+        Some("timeout") => "the member was timed out",
+        Some("kick") => "the member was kicked",
+        Some("ban") => "the member was banned",
+        _ => "no action was taken",
+    };
+    let note = report
+        .resolution_note
+        .as_deref()
+        .map(|n| format!(" Moderator note: {}", excerpt(n)))
+        .unwrap_or_default();
+    format!("Your report in {server} was reviewed: {outcome}.{note}")
+}
+
+/// Handle a PRIVMSG addressed to the services pseudo-client.
+///
+/// `service` is the alias the client used; replies are NOTICEs sent from it.
+/// `default_server` is the connection's default server, which SERVER may change.
+/// `cert_fp` is the fingerprint of the connection's TLS client certificate.
+#[allow(clippy::too_many_arguments)]
+pub async fn handle(
+    engine: &ChatEngine,
+    db: &SqlitePool,
+    session_id: SessionId,
+    nick: &str,
+    default_server: &mut String,
+    cert_fp: Option<&str>,
+    service: &str,
+    text: &str,
+) -> Vec<String> {
+    let lines = match parse_command(text) {
+        Ok(command) => run(engine, db, session_id, default_server, cert_fp, command)
+            .await
+            .unwrap_or_else(|e| vec![format!("Error: {e}")]),
+        Err(usage) => vec![usage],
+    };
+
+    lines
+        .iter()
+        .map(|line| formatter::notice(service, nick, line))
+        .collect()
+}
+
+/// Resolve a server argument given either as a server ID or a server name.
+fn resolve_server(engine: &ChatEngine, server: &str) -> Result<String, String> {
+    commands::resolve_server(engine, server).ok_or_else(|| format!("No such server: {server}"))
+}
+
+async fn run(
+    engine: &ChatEngine,
+    db: &SqlitePool,
+    session_id: SessionId,
+    default_server: &mut String,
+    cert_fp: Option<&str>,
+    command: ServiceCommand,
+) -> Result<Vec<String>, String> {
+    let user_id = engine
+        .get_session(session_id)
+        .and_then(|s| s.user_id.clone())
+        .ok_or("AUTH_REQUIRED")?;
+
+    match command {
+        ServiceCommand::Help => Ok(HELP_LINES.iter().map(|l| l.to_string()).collect()),
+
+        ServiceCommand::Servers => {
+            let servers = engine.list_servers_for_user(&user_id).await;
+            if servers.is_empty() {
+                return Ok(vec!["You are not a member of any servers.".into()]);
+            }
+            Ok(servers
+                .into_iter()
+                .map(|s| {
+                    let role = s.role.map(|r| format!(", {r}")).unwrap_or_default();
+                    format!("{} ({}) — {} members{role}", s.name, s.id, s.member_count)
+                })
+                .collect())
+        }
+
+        ServiceCommand::Server(None) => Ok(vec![match engine.get_server_name(default_server) {
+            Some(name) => format!("Channel names without a server prefix refer to {name}."),
+            None => {
+                "This connection is not bound to a server. Use SERVER <server> to pick one.".into()
+            }
+        }]),
+
+        ServiceCommand::Server(Some(server)) => {
+            let server_id = resolve_server(engine, &server)?;
+            let name = engine
+                .get_server_name(&server_id)
+                .unwrap_or(server_id.clone());
+            *default_server = server_id;
+            Ok(vec![format!(
+                "Channel names without a server prefix now refer to {name}. \
+                 Rejoin your channels to see them under their new names."
+            )])
+        }
+
+        ServiceCommand::Join(server) => {
+            let server_id = resolve_server(engine, &server)?;
+            let name = engine
+                .get_server_name(&server_id)
+                .unwrap_or(server_id.clone());
+            // Private servers are joined with an invite, never by name
+            if !engine.user_is_server_member(&server_id, &user_id) {
+                let discoverable = servers::get_server(db, &server_id)
+                    .await
+                    .map_err(|e| format!("Failed to look up server: {e}"))?
+                    .is_some_and(|s| s.is_discoverable != 0);
+                if !discoverable {
+                    return Err(format!(
+                        "{name} is not a public server. Ask a member for an invite and use INVITE USE <code>."
+                    ));
+                }
+            }
+            engine.join_server(&user_id, &server_id).await?;
+            let general = to_irc_channel(engine, default_server, &server_id, "#general");
+            Ok(vec![format!(
+                "You joined {name}. Try /join {general} to start chatting."
+            )])
+        }
+
+        ServiceCommand::InviteUse(code) => {
+            engine.use_invite(session_id, &code).await?;
+            Ok(vec!["Invite accepted.".into()])
+        }
+
+        ServiceCommand::Discover(category) => {
+            let servers = engine
+                .fetch_discoverable_servers(category.as_deref())
+                .await?;
+            if servers.is_empty() {
+                return Ok(vec!["No public servers found.".into()]);
+            }
+            Ok(servers
+                .into_iter()
+                .map(|s| {
+                    let name = engine
+                        .get_server_name(&s.server_id)
+                        .unwrap_or(s.server_id.clone());
+                    let category = s.category.map(|c| format!(" [{c}]")).unwrap_or_default();
+                    let description = s
+                        .description
+                        .map(|d| format!(" — {}", excerpt(&d)))
+                        .unwrap_or_default();
+                    format!("{name} ({}){category}{description}", s.server_id)
+                })
+                .collect())
+        }
+
+        ServiceCommand::TokensList => {
+            let tokens = users::list_irc_tokens(db, &user_id)
+                .await
+                .map_err(|e| format!("Failed to list tokens: {e}"))?;
+            if tokens.is_empty() {
+                return Ok(vec!["You have no IRC tokens.".into()]);
+            }
+            Ok(tokens
+                .into_iter()
+                .map(|(id, label, last_used, created_at)| {
+                    format!(
+                        "{id} — {} — created {created_at}, last used {}",
+                        label.as_deref().unwrap_or("(no label)"),
+                        last_used.as_deref().unwrap_or("never")
+                    )
+                })
+                .collect())
+        }
+
+        ServiceCommand::TokensRevoke(id) => {
+            let deleted = users::delete_irc_token(db, &id, &user_id)
+                .await
+                .map_err(|e| format!("Failed to revoke token: {e}"))?;
+            if deleted {
+                Ok(vec![format!("Token {id} revoked.")])
+            } else {
+                Err(format!("No such token: {id}"))
+            }
+        }
+
+        ServiceCommand::CertList => {
+            let certs = users::list_irc_cert_fingerprints(db, &user_id)
+                .await
+                .map_err(|e| format!("Failed to list certificates: {e}"))?;
+            if certs.is_empty() {
+                return Ok(vec!["You have no registered certificates.".into()]);
+            }
+            Ok(certs
+                .into_iter()
+                .map(|(fingerprint, label, created_at)| {
+                    format!(
+                        "{fingerprint} — {} — added {created_at}",
+                        label.as_deref().unwrap_or("(no label)")
+                    )
+                })
+                .collect())
+        }
+
+        ServiceCommand::CertAdd(fingerprint) => {
+            let fingerprint = match fingerprint {
+                Some(fp) => normalize_cert_fingerprint(&fp)
+                    .ok_or("Fingerprint must be a SHA-256 hash (64 hex characters)")?,
+                None => cert_fp
+                    .ok_or("You are not connected with a TLS client certificate")?
+                    .to_string(),
+            };
+            users::add_irc_cert_fingerprint(db, &fingerprint, &user_id, None)
+                .await
+                .map_err(|_| format!("Certificate {fingerprint} is already registered"))?;
+            Ok(vec![format!(
+                "Certificate {fingerprint} added. You can now log in with SASL EXTERNAL."
+            )])
+        }
+
+        ServiceCommand::CertDel(fingerprint) => {
+            let fingerprint = normalize_cert_fingerprint(&fingerprint).unwrap_or(fingerprint);
+            let deleted = users::delete_irc_cert_fingerprint(db, &fingerprint, &user_id)
+                .await
+                .map_err(|e| format!("Failed to remove certificate: {e}"))?;
+            if deleted {
+                Ok(vec![format!("Certificate {fingerprint} removed.")])
+            } else {
+                Err(format!("No such certificate: {fingerprint}"))
+            }
+        }
+
+        ServiceCommand::Pins(channel) => {
+            let (server_id, channel_name) = parse_irc_channel(engine, default_server, &channel);
+            let pins = engine
+                .fetch_pinned_messages(session_id, &server_id, &channel_name)
+                .await?;
+            if pins.is_empty() {
+                return Ok(vec![format!("No pinned messages in {channel}.")]);
+            }
+            Ok(pins
+                .into_iter()
+                .map(|p| format!("[{}] <{}> {}", p.timestamp, p.from, excerpt(&p.content)))
+                .collect())
+        }
+
+        ServiceCommand::Bookmarks => {
+            let bookmarks = engine.fetch_bookmarks(session_id).await?;
+            if bookmarks.is_empty() {
+                return Ok(vec!["You have no bookmarks.".into()]);
+            }
+            Ok(bookmarks
+                .into_iter()
+                .map(|b| {
+                    let note = b.note.map(|n| format!(" ({n})")).unwrap_or_default();
+                    format!(
+                        "[{}] <{}> {}{note}",
+                        b.timestamp,
+                        b.from,
+                        excerpt(&b.content)
+                    )
+                })
+                .collect())
+        }
+
+        ServiceCommand::Events(server) => {
+            let server_id = resolve_server(engine, &server)?;
+            let events = engine.fetch_events(session_id, &server_id).await?;
+            if events.is_empty() {
+                return Ok(vec!["No scheduled events.".into()]);
+            }
+            Ok(events
+                .into_iter()
+                .map(|e| {
+                    format!(
+                        "{} — {} at {} ({}, {} interested)",
+                        e.id, e.name, e.start_time, e.status, e.interested_count
+                    )
+                })
+                .collect())
+        }
+
+        ServiceCommand::Rsvp {
+            server,
+            event_id,
+            status,
+        } => {
+            let server_id = resolve_server(engine, &server)?;
+            engine
+                .set_rsvp(session_id, &server_id, &event_id, &status)
+                .await?;
+            Ok(vec![format!("RSVP for {event_id} set to {status}.")])
+        }
+
+        ServiceCommand::ReportMessage { message_id, reason } => {
+            let server_id = messages::get_message_by_id(db, &message_id)
+                .await
+                .map_err(|e| format!("Failed to look up message: {e}"))?
+                .and_then(|m| m.server_id)
+                .ok_or_else(|| format!("No such message: {message_id}"))?;
+            engine
+                .create_report(&user_id, &server_id, Some(&message_id), None, &reason)
+                .await?;
+            Ok(vec![
+                "Report sent to the moderators. You'll get a notice when it's reviewed.".into(),
+            ])
+        }
+
+        ServiceCommand::ReportUser {
+            server,
+            nick,
+            reason,
+        } => {
+            let server_id = resolve_server(engine, &server)?;
+            let (target_id, ..) = users::get_user_by_nickname(db, &nick)
+                .await
+                .map_err(|e| format!("Failed to look up user: {e}"))?
+                .ok_or_else(|| format!("No such user: {nick}"))?;
+            engine
+                .create_report(&user_id, &server_id, None, Some(&target_id), &reason)
+                .await?;
+            Ok(vec![
+                "Report sent to the moderators. You'll get a notice when it's reviewed.".into(),
+            ])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_service_nick() {
+        assert!(is_service_nick("Concord"));
+        assert!(is_service_nick("nickserv"));
+        assert!(is_service_nick("CHANSERV"));
+        assert!(!is_service_nick("alice"));
+    }
+
+    #[test]
+    fn test_parse_simple_commands() {
+        assert_eq!(parse_command(""), Ok(ServiceCommand::Help));
+        assert_eq!(parse_command("help"), Ok(ServiceCommand::Help));
+        assert_eq!(parse_command("SERVERS"), Ok(ServiceCommand::Servers));
+        assert_eq!(parse_command("bookmarks"), Ok(ServiceCommand::Bookmarks));
+        assert_eq!(parse_command("SERVER"), Ok(ServiceCommand::Server(None)));
+        assert_eq!(
+            parse_command("server rustaceans"),
+            Ok(ServiceCommand::Server(Some("rustaceans".into())))
+        );
+        assert_eq!(
+            parse_command("DISCOVER"),
+            Ok(ServiceCommand::Discover(None))
+        );
+        assert_eq!(
+            parse_command("DISCOVER gaming"),
+            Ok(ServiceCommand::Discover(Some("gaming".into())))
+        );
+    }
+
+    #[test]
+    fn test_parse_commands_with_arguments() {
+        assert_eq!(
+            parse_command("JOIN rustaceans"),
+            Ok(ServiceCommand::Join("rustaceans".into()))
+        );
+        assert_eq!(
+            parse_command("invite use abc123"),
+            Ok(ServiceCommand::InviteUse("abc123".into()))
+        );
+        assert_eq!(parse_command("TOKENS LIST"), Ok(ServiceCommand::TokensList));
+        assert_eq!(
+            parse_command("TOKENS REVOKE tok-1"),
+            Ok(ServiceCommand::TokensRevoke("tok-1".into()))
+        );
+        assert_eq!(parse_command("cert list"), Ok(ServiceCommand::CertList));
+        assert_eq!(parse_command("CERT ADD"), Ok(ServiceCommand::CertAdd(None)));
+        assert_eq!(
+            parse_command("CERT ADD ab:cd"),
+            Ok(ServiceCommand::CertAdd(Some("ab:cd".into())))
+        );
+        assert_eq!(
+            parse_command("CERT DEL abcd"),
+            Ok(ServiceCommand::CertDel("abcd".into()))
+        );
+        assert_eq!(
+            parse_command("PINS #general"),
+            Ok(ServiceCommand::Pins("#general".into()))
+        );
+        assert_eq!(
+            parse_command("RSVP rustaceans ev-1 Going"),
+            Ok(ServiceCommand::Rsvp {
+                server: "rustaceans".into(),
+                event_id: "ev-1".into(),
+                status: "going".into(),
+            })
+        );
+        assert_eq!(
+            parse_command("report msg m-1 posting  scam links"),
+            Ok(ServiceCommand::ReportMessage {
+                message_id: "m-1".into(),
+                reason: "posting scam links".into(),
+            })
+        );
+        assert_eq!(
+            parse_command("REPORT USER rustaceans mallory spamming DMs"),
+            Ok(ServiceCommand::ReportUser {
+                server: "rustaceans".into(),
+                nick: "mallory".into(),
+                reason: "spamming DMs".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_missing_arguments_returns_usage() {
+        assert!(parse_command("JOIN").unwrap_err().starts_with("Usage"));
+        assert!(
+            parse_command("INVITE abc")
+                .unwrap_err()
+                .starts_with("Usage")
+        );
+        assert!(parse_command("TOKENS").unwrap_err().starts_with("Usage"));
+        assert!(parse_command("CERT DEL").unwrap_err().starts_with("Usage"));
+        assert!(
+            parse_command("RSVP srv ev")
+                .unwrap_err()
+                .starts_with("Usage")
+        );
+        assert!(
+            parse_command("REPORT MSG m1")
+                .unwrap_err()
+                .starts_with("Usage")
+        );
+        assert!(
+            parse_command("FROB")
+                .unwrap_err()
+                .contains("Unknown command")
+        );
+    }
+
+    #[test]
+    fn test_excerpt_flattens_and_truncates() {
+        assert_eq!(excerpt("line one\nline  two"), "line one line two");
+        let long = "x".repeat(EXCERPT_LENGTH + 10);
+        let short = excerpt(&long);
+        assert_eq!(short.chars().count(), EXCERPT_LENGTH + 1);
+        assert!(short.ends_with('…'));
+    }
+}
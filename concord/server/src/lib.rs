@@ -1,8 +1,11 @@
 pub mod auth;
+pub mod bridge;
+pub mod cli;
 pub mod config;
 pub mod db;
 pub mod engine;
 pub mod irc;
+pub mod metrics;
 pub mod web;
 
 #[cfg(test)]
@@ -1,29 +1,50 @@
 use std::sync::Arc;
+use std::time::Duration;
 
+use clap::Parser;
 use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 use tracing_subscriber::EnvFilter;
 
+use concord_server::cli::{self, Cli, Command};
 use concord_server::config::ServerConfig;
 use concord_server::db::pool::{create_pool, run_migrations};
+use concord_server::db::queries::{instance, servers, users};
 use concord_server::engine::chat_engine::ChatEngine;
-use concord_server::irc::listener::start_irc_listener;
+use concord_server::irc::listener::{CertFpVerifier, start_irc_listener};
 use concord_server::web::app_state::AppState;
 use concord_server::web::atproto::AtprotoOAuth;
 use concord_server::web::router::build_router;
 
 #[tokio::main]
 async fn main() {
-    // Initialize logging
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Serve);
+
+    // Initialize logging; admin commands only log problems unless RUST_LOG says otherwise
+    let default_filter = match command {
+        Command::Serve => "info",
+        _ => "warn",
+    };
     tracing_subscriber::fmt()
         .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter)),
         )
         .init();
 
-    // Load configuration (TOML file + env overrides)
-    let mut config = ServerConfig::load("concord.toml");
+    match command {
+        // Load configuration (TOML file + env overrides)
+        Command::Serve => serve(ServerConfig::load(&cli.config)).await,
+        command => {
+            if let Err(e) = cli::run(command, &cli.config).await {
+                eprintln!("error: {e:#}");
+                std::process::exit(1);
+            }
+        }
+    }
+}
 
+async fn serve(mut config: ServerConfig) {
     // Reject the hardcoded default JWT secret in production
     const DEFAULT_SECRET: &str = "concord-dev-secret-change-me";
     if config.auth.jwt_secret == DEFAULT_SECRET || config.auth.jwt_secret.is_empty() {
@@ -51,23 +72,16 @@ async fn main() {
 
     // Bootstrap admin users from config
     for username in &config.admin.admin_users {
-        match sqlx::query_scalar::<_, String>("SELECT id FROM users WHERE username = ?")
-            .bind(username)
-            .fetch_optional(&pool)
-            .await
-        {
-            Ok(Some(user_id)) => {
-                let _ = sqlx::query("UPDATE users SET is_system_admin = 1 WHERE id = ?")
-                    .bind(&user_id)
-                    .execute(&pool)
-                    .await;
-                info!(%username, "bootstrapped as system admin");
-            }
+        match users::find_user_account(&pool, username).await {
+            Ok(Some(account)) => match servers::set_system_admin(&pool, &account.id, true).await {
+                Ok(()) => info!(%username, "bootstrapped as system admin"),
+                Err(e) => warn!(%username, error = %e, "failed to bootstrap admin user"),
+            },
             Ok(None) => {
                 info!(%username, "admin user not found yet (will need manual promotion after first login)");
             }
             Err(e) => {
-                tracing::warn!(%username, error = %e, "failed to bootstrap admin user");
+                warn!(%username, error = %e, "failed to bootstrap admin user");
             }
         }
     }
@@ -101,6 +115,36 @@ async fn main() {
         }
     });
 
+    // Lift temporary bans as they expire
+    let engine_bans = engine.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            let lifted = engine_bans.expire_bans().await;
+            if lifted > 0 {
+                info!(lifted, "Lifted expired bans");
+            }
+        }
+    });
+
+    // Apply changes the admin CLI makes while we run. Anything recorded before
+    // startup is already reflected in what was just loaded.
+    let engine_admin = engine.clone();
+    let mut admin_cursor = instance::latest_admin_change_id(&pool)
+        .await
+        .expect("failed to read admin changes");
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            match engine_admin.apply_admin_changes(admin_cursor).await {
+                Ok(cursor) => admin_cursor = cursor,
+                Err(e) => warn!(error = %e, "failed to apply admin changes"),
+            }
+        }
+    });
+
     // Cancellation token for graceful shutdown
     let cancel = CancellationToken::new();
 
@@ -126,6 +170,13 @@ async fn main() {
 
     // Initialize IRC MOTD from config
     concord_server::irc::connection::set_motd(config.irc.motd.clone());
+    engine.bouncer().configure(
+        config.irc.always_on,
+        (config.irc.always_on_idle_hours > 0)
+            .then(|| Duration::from_secs(config.irc.always_on_idle_hours * 3600)),
+    );
+    concord_server::irc::proxy::set_trusted_proxies(&config.irc.trusted_proxies);
+    concord_server::irc::proxy::set_webirc_gateways(config.irc.webirc.clone());
 
     // Start IRC listener
     let irc_engine = engine.clone();
@@ -143,6 +194,20 @@ async fn main() {
         .await;
     });
 
+    // Start relay bridges to external IRC networks
+    concord_server::bridge::irc::start_irc_bridges(
+        engine.clone(),
+        config.bridge.irc.clone(),
+        cancel.clone(),
+    );
+    if let Some(matrix) = config.bridge.matrix.clone() {
+        tokio::spawn(concord_server::bridge::matrix::run_matrix_bridge(
+            engine.clone(),
+            matrix,
+            cancel.clone(),
+        ));
+    }
+
     let max_file_size = config.storage.max_file_size_mb * 1024 * 1024;
 
     // Build shared app state for the web server
@@ -169,7 +234,24 @@ async fn main() {
         }
     });
 
-    let app = build_router(app_state);
+    let metrics = concord_server::metrics::router(app_state.engine.clone());
+    let mut app = build_router(app_state);
+    match (config.metrics.enabled, &config.metrics.address) {
+        (false, _) => {}
+        (true, None) => app = app.merge(metrics),
+        (true, Some(address)) => {
+            let listener = tokio::net::TcpListener::bind(address)
+                .await
+                .expect("failed to bind metrics listener");
+            info!(%address, "Serving metrics");
+            let metrics_cancel = cancel.clone();
+            tokio::spawn(async move {
+                axum::serve(listener, metrics)
+                    .with_graceful_shutdown(async move { metrics_cancel.cancelled().await })
+                    .await
+            });
+        }
+    }
 
     info!(
         "Concord server starting — Web: {}, IRC: {}",
@@ -220,8 +302,11 @@ fn load_irc_tls_config(
     let key =
         private_key(&mut BufReader::new(key_file))?.ok_or("No private key found in key file")?;
 
-    let config = ServerConfig::builder()
-        .with_no_client_auth()
+    // Request (but don't require) client certificates for SASL EXTERNAL
+    let builder = ServerConfig::builder();
+    let verifier = CertFpVerifier::new(builder.crypto_provider().clone());
+    let config = builder
+        .with_client_cert_verifier(Arc::new(verifier))
         .with_single_cert(certs, key)?;
 
     Ok(tokio_rustls::TlsAcceptor::from(Arc::new(config)))
@@ -0,0 +1,355 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use axum::Router;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use dashmap::DashMap;
+use sqlx::SqlitePool;
+
+use crate::engine::chat_engine::ChatEngine;
+use crate::engine::user_session::Protocol;
+
+/// Process-wide counters, updated where things happen and rendered at `/metrics`.
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// Upper bounds (seconds) of the latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Upper bounds of the IRC connections-per-client-IP histogram buckets.
+const CONNECTIONS_PER_IP_BUCKETS: [i64; 6] = [1, 2, 3, 5, 10, 25];
+
+#[derive(Default)]
+pub struct Metrics {
+    /// Messages accepted by the engine.
+    pub messages: Counter,
+    /// Events dropped because a session's outbound queue was full or closed.
+    pub outbound_dropped: Counter,
+    /// Requests or messages refused by a rate limiter, by limiter.
+    pub rate_limited: LabeledCounter,
+    /// Messages blocked by AutoMod, by rule type.
+    pub automod_triggers: LabeledCounter,
+    /// Time taken to unfurl a link embed.
+    pub embed_latency: Histogram,
+    /// Link embeds that couldn't be unfurled.
+    pub embed_failures: Counter,
+    /// Open IRC connections, by client IP. Only aggregates are rendered, so
+    /// client addresses never appear at `/metrics`.
+    pub irc_connections: LabeledGauge,
+    /// Incoming webhook executions, by result.
+    pub webhook_executions: LabeledCounter,
+}
+
+#[derive(Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default)]
+pub struct LabeledCounter(DashMap<String, u64>);
+
+impl LabeledCounter {
+    pub fn inc(&self, label: &str) {
+        *self.0.entry(label.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn get(&self, label: &str) -> u64 {
+        self.0.get(label).map_or(0, |v| *v)
+    }
+}
+
+/// A gauge per label value; labels are dropped when they reach zero.
+#[derive(Default)]
+pub struct LabeledGauge(DashMap<String, i64>);
+
+impl LabeledGauge {
+    pub fn inc(&self, label: &str) {
+        *self.0.entry(label.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn dec(&self, label: &str) {
+        self.0.remove_if_mut(label, |_, v| {
+            *v -= 1;
+            *v <= 0
+        });
+    }
+
+    pub fn get(&self, label: &str) -> i64 {
+        self.0.get(label).map_or(0, |v| *v)
+    }
+}
+
+#[derive(Default)]
+pub struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicI64,
+}
+
+impl Histogram {
+    pub fn observe(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as i64, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    /// Render every metric in the Prometheus text exposition format. Session
+    /// counts and pool statistics are read from `engine` and `pool` now.
+    pub fn render(&self, engine: &ChatEngine, pool: Option<&SqlitePool>) -> String {
+        let mut out = String::new();
+
+        describe(
+            &mut out,
+            "concord_sessions",
+            "gauge",
+            "Connected sessions by protocol.",
+        );
+        for (protocol, name) in [
+            (Protocol::Irc, "irc"),
+            (Protocol::WebSocket, "websocket"),
+            (Protocol::Bridge, "bridge"),
+        ] {
+            let count = engine.session_count(protocol);
+            let _ = writeln!(out, "concord_sessions{{protocol=\"{name}\"}} {count}");
+        }
+
+        counter(
+            &mut out,
+            "concord_messages_total",
+            "Messages accepted by the chat engine.",
+            &self.messages,
+        );
+        counter(
+            &mut out,
+            "concord_outbound_dropped_total",
+            "Events dropped because a session's outbound queue was full.",
+            &self.outbound_dropped,
+        );
+        labeled(
+            &mut out,
+            "concord_rate_limited_total",
+            "counter",
+            "Requests and messages refused by a rate limiter.",
+            "limiter",
+            &self.rate_limited.0,
+        );
+        labeled(
+            &mut out,
+            "concord_automod_triggers_total",
+            "counter",
+            "Messages blocked by AutoMod rules.",
+            "rule_type",
+            &self.automod_triggers.0,
+        );
+
+        describe(
+            &mut out,
+            "concord_embed_unfurl_seconds",
+            "histogram",
+            "Time taken to unfurl link embeds.",
+        );
+        let histogram = &self.embed_latency;
+        for (bucket, bound) in histogram.buckets.iter().zip(LATENCY_BUCKETS) {
+            let _ = writeln!(
+                out,
+                "concord_embed_unfurl_seconds_bucket{{le=\"{bound}\"}} {}",
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = histogram.count.load(Ordering::Relaxed);
+        let sum = histogram.sum_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = writeln!(
+            out,
+            "concord_embed_unfurl_seconds_bucket{{le=\"+Inf\"}} {count}"
+        );
+        let _ = writeln!(out, "concord_embed_unfurl_seconds_sum {sum}");
+        let _ = writeln!(out, "concord_embed_unfurl_seconds_count {count}");
+        counter(
+            &mut out,
+            "concord_embed_failures_total",
+            "Link embeds that could not be unfurled.",
+            &self.embed_failures,
+        );
+
+        let per_ip: Vec<i64> = self.irc_connections.0.iter().map(|e| *e.value()).collect();
+        describe(
+            &mut out,
+            "concord_irc_connections",
+            "gauge",
+            "Open IRC connections.",
+        );
+        let total: i64 = per_ip.iter().sum();
+        let _ = writeln!(out, "concord_irc_connections {total}");
+        describe(
+            &mut out,
+            "concord_irc_connections_per_ip",
+            "histogram",
+            "Open IRC connections per client IP, counted over connected IPs.",
+        );
+        for bound in CONNECTIONS_PER_IP_BUCKETS {
+            let ips = per_ip.iter().filter(|&&n| n <= bound).count();
+            let _ = writeln!(
+                out,
+                "concord_irc_connections_per_ip_bucket{{le=\"{bound}\"}} {ips}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "concord_irc_connections_per_ip_bucket{{le=\"+Inf\"}} {}",
+            per_ip.len()
+        );
+        let _ = writeln!(out, "concord_irc_connections_per_ip_sum {total}");
+        let _ = writeln!(out, "concord_irc_connections_per_ip_count {}", per_ip.len());
+        labeled(
+            &mut out,
+            "concord_webhook_executions_total",
+            "counter",
+            "Incoming webhook executions by result.",
+            "result",
+            &self.webhook_executions.0,
+        );
+
+        if let Some(pool) = pool {
+            describe(
+                &mut out,
+                "concord_db_pool_connections",
+                "gauge",
+                "SQLite pool connections by state.",
+            );
+            let idle = pool.num_idle();
+            let active = (pool.size() as usize).saturating_sub(idle);
+            let _ = writeln!(out, "concord_db_pool_connections{{state=\"idle\"}} {idle}");
+            let _ = writeln!(
+                out,
+                "concord_db_pool_connections{{state=\"active\"}} {active}"
+            );
+        }
+
+        out
+    }
+}
+
+fn describe(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn counter(out: &mut String, name: &str, help: &str, counter: &Counter) {
+    describe(out, name, "counter", help);
+    let _ = writeln!(out, "{name} {}", counter.get());
+}
+
+fn labeled<V: std::fmt::Display>(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    label: &str,
+    values: &DashMap<String, V>,
+) {
+    describe(out, name, kind, help);
+    let mut values: Vec<_> = values
+        .iter()
+        .map(|e| (e.key().clone(), e.value().to_string()))
+        .collect();
+    values.sort();
+    for (value, n) in values {
+        let _ = writeln!(out, "{name}{{{label}=\"{}\"}} {n}", escape_label(&value));
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// The `/metrics` endpoint, for merging into the web router or serving on
+/// its own admin address.
+pub fn router(engine: Arc<ChatEngine>) -> Router {
+    Router::new()
+        .route("/metrics", axum::routing::get(metrics_handler))
+        .with_state(engine)
+}
+
+async fn metrics_handler(State(engine): State<Arc<ChatEngine>>) -> impl IntoResponse {
+    let pool = engine.get_db();
+    let body = METRICS.render(&engine, pool.as_ref());
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        body,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_render() {
+        let metrics = Metrics::default();
+        let engine = ChatEngine::new(None, 4000, 100);
+        let _alice = engine
+            .connect(None, "alice".into(), Protocol::Irc, None)
+            .unwrap();
+
+        metrics.messages.inc();
+        metrics.messages.inc();
+        metrics.rate_limited.inc("api");
+        metrics.irc_connections.inc("10.0.0.1");
+        metrics.irc_connections.inc("10.0.0.1");
+        metrics.irc_connections.inc("10.0.0.2");
+        metrics.irc_connections.dec("10.0.0.2");
+        metrics.embed_latency.observe(Duration::from_millis(300));
+        metrics.webhook_executions.inc("say \"hi\"");
+
+        let text = metrics.render(&engine, None);
+        assert!(text.contains("# TYPE concord_sessions gauge\n"));
+        assert!(text.contains("concord_sessions{protocol=\"irc\"} 1\n"));
+        assert!(text.contains("concord_sessions{protocol=\"websocket\"} 0\n"));
+        assert!(text.contains("concord_messages_total 2\n"));
+        assert!(text.contains("concord_rate_limited_total{limiter=\"api\"} 1\n"));
+        assert!(text.contains("concord_irc_connections 2\n"));
+        assert!(text.contains("concord_irc_connections_per_ip_bucket{le=\"1\"} 0\n"));
+        assert!(text.contains("concord_irc_connections_per_ip_bucket{le=\"2\"} 1\n"));
+        assert!(text.contains("concord_irc_connections_per_ip_count 1\n"));
+        assert!(!text.contains("10.0.0."));
+        assert!(text.contains("concord_embed_unfurl_seconds_bucket{le=\"0.25\"} 0\n"));
+        assert!(text.contains("concord_embed_unfurl_seconds_bucket{le=\"0.5\"} 1\n"));
+        assert!(text.contains("concord_embed_unfurl_seconds_count 1\n"));
+        assert!(text.contains("concord_webhook_executions_total{result=\"say \\\"hi\\\"\"} 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_render_pool_stats() {
+        let pool = crate::db::pool::create_pool("sqlite::memory:")
+            .await
+            .unwrap();
+        let engine = ChatEngine::new(Some(pool.clone()), 4000, 100);
+        let text = Metrics::default().render(&engine, Some(&pool));
+        assert!(text.contains("concord_db_pool_connections{state=\"idle\"}"));
+    }
+}
@@ -373,6 +373,23 @@ pub async fn atproto_callback(
         }
     };
 
+    // Identities banned from the instance can't sign in or register
+    match crate::db::queries::instance::is_instance_banned(&state.db, &did).await {
+        Ok(false) => {}
+        Ok(true) => {
+            info!(user_id = %did, "rejected login from instance-banned identity");
+            return (
+                StatusCode::FORBIDDEN,
+                "This account is banned from this instance",
+            )
+                .into_response();
+        }
+        Err(e) => {
+            error!(error = %e, "Database error during OAuth");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    }
+
     // Fetch public profile for display name and avatar
     let (display_name, avatar_url) = fetch_bsky_profile(&http_client, &did).await;
     // Use Bluesky handle as username (permanent DID is the user_id)
@@ -416,6 +433,17 @@ pub async fn atproto_callback(
         }
     };
 
+    match users::is_account_active(&state.db, &user_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::FORBIDDEN, "This account has been suspended").into_response();
+        }
+        Err(e) => {
+            error!(error = %e, "Database error during OAuth");
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response();
+        }
+    }
+
     // Store AT Protocol credentials for PDS API access (blob uploads, etc.)
     // Serialize the DPoP private key as JWK JSON to preserve the private key material.
     let dpop_key_str = match jwk::generate(&pending.dpop_key) {
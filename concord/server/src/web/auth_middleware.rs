@@ -7,6 +7,7 @@ use axum::response::{IntoResponse, Response};
 use axum_extra::extract::CookieJar;
 
 use crate::auth::token::validate_session_token;
+use crate::db::queries::users;
 
 use super::app_state::AppState;
 
@@ -35,10 +36,21 @@ impl FromRequestParts<Arc<AppState>> for AuthUser {
             })?;
 
         // Check JWT revocation blocklist
-        if state.jwt_blocklist.is_revoked(&claims.jti) {
+        if state.jwt_blocklist.is_session_revoked(&claims) {
             return Err((StatusCode::UNAUTHORIZED, "Session has been revoked").into_response());
         }
 
+        // Suspension and admin token revocation are recorded on the user row
+        match users::is_session_valid(&state.db, &claims.sub, claims.iat).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return Err((StatusCode::UNAUTHORIZED, "Session has been revoked").into_response());
+            }
+            Err(_) => {
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response());
+            }
+        }
+
         Ok(AuthUser {
             user_id: claims.sub,
         })
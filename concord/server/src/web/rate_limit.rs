@@ -9,6 +9,7 @@ use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
 
 use crate::engine::rate_limiter::RateLimiter;
+use crate::metrics::METRICS;
 
 /// Per-IP rate limiters for different endpoint tiers.
 pub struct ApiRateLimiters {
@@ -78,6 +79,7 @@ pub async fn auth_rate_limit(req: Request<Body>, next: Next) -> Response {
     if let Some(limiters) = limiters {
         let ip = client_ip(&req);
         if !limiters.auth.check(&ip) {
+            METRICS.rate_limited.inc("auth");
             return (
                 StatusCode::TOO_MANY_REQUESTS,
                 "Rate limit exceeded. Please try again later.",
@@ -94,6 +96,7 @@ pub async fn api_rate_limit(req: Request<Body>, next: Next) -> Response {
     if let Some(limiters) = limiters {
         let ip = client_ip(&req);
         if !limiters.api.check(&ip) {
+            METRICS.rate_limited.inc("api");
             return (
                 StatusCode::TOO_MANY_REQUESTS,
                 "Rate limit exceeded. Please try again later.",
@@ -110,6 +113,7 @@ pub async fn ws_rate_limit(req: Request<Body>, next: Next) -> Response {
     if let Some(limiters) = limiters {
         let ip = client_ip(&req);
         if !limiters.ws.check(&ip) {
+            METRICS.rate_limited.inc("ws");
             return (
                 StatusCode::TOO_MANY_REQUESTS,
                 "Too many connections. Please try again later.",
@@ -128,6 +132,7 @@ pub async fn webhook_rate_limit(req: Request<Body>, next: Next) -> Response {
     if let Some(limiters) = limiters {
         let ip = client_ip(&req);
         if !limiters.webhook.check(&ip) {
+            METRICS.rate_limited.inc("webhook");
             return (
                 StatusCode::TOO_MANY_REQUESTS,
                 "Rate limit exceeded. Please try again later.",
@@ -10,18 +10,41 @@ use serde::{Deserialize, Serialize};
 use tracing::{error, info};
 use uuid::Uuid;
 
-use crate::auth::token::{generate_irc_token, hash_irc_token, verify_irc_token};
+use crate::auth::token::{
+    SCRAM_ITERATIONS, generate_irc_token, generate_scram_salt, hash_irc_token,
+    normalize_cert_fingerprint, scram_credentials, verify_irc_token,
+};
 use crate::db::queries::{
     atproto as atproto_queries, attachments, bots, community, emoji, invites, messages, profiles,
     roles, servers, stickers, users,
 };
+use crate::engine::chat_engine::ResolveReportParams;
+use crate::engine::error::EngineError;
 use crate::engine::events::HistoryMessage;
 use crate::engine::permissions::{Permissions, compute_effective_permissions};
+use crate::metrics::METRICS;
 use sqlx;
 
 use super::app_state::AppState;
 use super::auth_middleware::AuthUser;
 
+/// Engine errors become `{"error": CODE, "message": ...}` with the matching status.
+/// Rate limits also carry a `Retry-After` header when the delay is known.
+impl IntoResponse for EngineError {
+    fn into_response(self) -> axum::response::Response {
+        let status =
+            StatusCode::from_u16(self.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let body = Json(serde_json::json!({
+            "error": self.code(),
+            "message": self.to_string(),
+        }));
+        match self.retry_after_secs() {
+            Some(secs) => (status, [(header::RETRY_AFTER, secs.to_string())], body).into_response(),
+            None => (status, body).into_response(),
+        }
+    }
+}
+
 /// Check if a user has a specific permission in a server.
 /// Returns Ok(()) if permitted, or an error response.
 async fn check_server_permission(
@@ -124,6 +147,14 @@ where
             .find(|t| verify_irc_token(token, &t.token_hash))
             .ok_or((StatusCode::UNAUTHORIZED, "Invalid bot token"))?;
 
+        // Suspended and instance-banned bots keep their tokens but can't use them
+        if !users::is_account_active(&app_state.db, &row.user_id)
+            .await
+            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Database error"))?
+        {
+            return Err((StatusCode::FORBIDDEN, "Bot account has been suspended"));
+        }
+
         // Update last_used timestamp in background
         let pool = app_state.db.clone();
         let tid = row.id.clone();
@@ -198,8 +229,10 @@ pub async fn get_channel_history(
         })
         .into_response(),
         Err(e) => {
-            error!(error = %e, "Failed to fetch history");
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch history").into_response()
+            if let EngineError::Internal(_) = e {
+                error!(error = %e, "Failed to fetch history");
+            }
+            e.into_response()
         }
     }
 }
@@ -269,7 +302,7 @@ pub async fn create_server(
                 .find(|s| s.id == server_id);
             (StatusCode::CREATED, Json(server)).into_response()
         }
-        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+        Err(e) => e.into_response(),
     }
 }
 
@@ -308,7 +341,7 @@ pub async fn delete_server(
     }
     match state.engine.delete_server(&server_id).await {
         Ok(()) => StatusCode::NO_CONTENT.into_response(),
-        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+        Err(e) => e.into_response(),
     }
 }
 
@@ -368,8 +401,10 @@ pub async fn get_server_channel_history(
         })
         .into_response(),
         Err(e) => {
-            error!(error = %e, "Failed to fetch history");
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to fetch history").into_response()
+            if let EngineError::Internal(_) = e {
+                error!(error = %e, "Failed to fetch history");
+            }
+            e.into_response()
         }
     }
 }
@@ -408,6 +443,86 @@ pub async fn list_server_members(
     }
 }
 
+// ── Reports ─────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct CreateReportRequest {
+    pub message_id: Option<String>,
+    pub user_id: Option<String>,
+    pub reason: String,
+}
+
+/// POST /api/servers/:id/reports — report a message, or a member when no
+/// `message_id` is given.
+pub async fn create_report(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(server_id): Path<String>,
+    Json(body): Json<CreateReportRequest>,
+) -> impl IntoResponse {
+    match state
+        .engine
+        .create_report(
+            &auth.user_id,
+            &server_id,
+            body.message_id.as_deref(),
+            body.user_id.as_deref(),
+            &body.reason,
+        )
+        .await
+    {
+        Ok(report) => (StatusCode::CREATED, Json(report)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/servers/:id/reports — open reports with message context (moderators).
+pub async fn list_reports(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(server_id): Path<String>,
+) -> impl IntoResponse {
+    match state
+        .engine
+        .fetch_open_reports(&auth.user_id, &server_id)
+        .await
+    {
+        Ok(reports) => Json(reports).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ResolveReportRequest {
+    pub action: String,
+    pub note: Option<String>,
+    pub timeout_seconds: Option<u64>,
+}
+
+/// POST /api/servers/:id/reports/:report_id/resolve — close a report with an
+/// action (moderators).
+pub async fn resolve_report(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path((server_id, report_id)): Path<(String, String)>,
+    Json(body): Json<ResolveReportRequest>,
+) -> impl IntoResponse {
+    let params = ResolveReportParams {
+        report_id: &report_id,
+        action: &body.action,
+        note: body.note.as_deref(),
+        timeout_seconds: body.timeout_seconds,
+    };
+    match state
+        .engine
+        .resolve_report_by(&auth.user_id, &server_id, &params)
+        .await
+    {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
 // ── Admin endpoints (system admin only) ─────────────────
 
 /// GET /api/admin/servers — list all servers (system admin).
@@ -431,16 +546,13 @@ pub async fn admin_delete_server(
     Path(server_id): Path<String>,
     auth: AuthUser,
 ) -> impl IntoResponse {
-    match servers::is_system_admin(&state.db, &auth.user_id).await {
-        Ok(true) => match state.engine.delete_server(&server_id).await {
-            Ok(()) => StatusCode::NO_CONTENT.into_response(),
-            Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
-        },
-        Ok(false) => (StatusCode::FORBIDDEN, "Not a system admin").into_response(),
-        Err(e) => {
-            error!(error = %e, "Failed to check admin status");
-            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
-        }
+    match state
+        .engine
+        .delete_server_by(&auth.user_id, &server_id, None)
+        .await
+    {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
     }
 }
 
@@ -456,21 +568,197 @@ pub async fn admin_set_admin(
     auth: AuthUser,
     Json(body): Json<SetAdminRequest>,
 ) -> impl IntoResponse {
-    match servers::is_system_admin(&state.db, &auth.user_id).await {
-        Ok(true) => {
-            match servers::set_system_admin(&state.db, &target_user_id, body.is_admin).await {
-                Ok(()) => StatusCode::NO_CONTENT.into_response(),
-                Err(e) => {
-                    error!(error = %e, "Failed to set admin flag");
-                    (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
-                }
+    match state
+        .engine
+        .set_system_admin_by(&auth.user_id, &target_user_id, body.is_admin)
+        .await
+    {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SetSuspendedRequest {
+    pub suspended: bool,
+    pub reason: Option<String>,
+}
+
+/// PUT /api/admin/users/:id/suspended — suspend a user across the instance,
+/// or reinstate them. Suspension also revokes their sessions.
+pub async fn admin_set_suspended(
+    State(state): State<Arc<AppState>>,
+    Path(target_user_id): Path<String>,
+    auth: AuthUser,
+    Json(body): Json<SetSuspendedRequest>,
+) -> impl IntoResponse {
+    match state
+        .engine
+        .set_user_suspended_by(
+            &auth.user_id,
+            &target_user_id,
+            body.suspended,
+            body.reason.as_deref(),
+        )
+        .await
+    {
+        Ok(()) => {
+            if body.suspended {
+                state
+                    .jwt_blocklist
+                    .revoke_user(&target_user_id, state.auth_config.session_expiry_hours);
             }
+            StatusCode::NO_CONTENT.into_response()
         }
-        Ok(false) => (StatusCode::FORBIDDEN, "Not a system admin").into_response(),
-        Err(e) => {
-            error!(error = %e, "Failed to check admin status");
-            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(Serialize)]
+pub struct RevokeSessionsResponse {
+    pub tokens_revoked: u64,
+}
+
+/// POST /api/admin/users/:id/revoke-sessions — sign a user out everywhere.
+pub async fn admin_revoke_sessions(
+    State(state): State<Arc<AppState>>,
+    Path(target_user_id): Path<String>,
+    auth: AuthUser,
+) -> impl IntoResponse {
+    match state
+        .engine
+        .revoke_user_sessions_by(&auth.user_id, &target_user_id)
+        .await
+    {
+        Ok(tokens_revoked) => {
+            state
+                .jwt_blocklist
+                .revoke_user(&target_user_id, state.auth_config.session_expiry_hours);
+            Json(RevokeSessionsResponse { tokens_revoked }).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/admin/bans — list identities banned from the instance.
+pub async fn admin_list_bans(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> impl IntoResponse {
+    match state.engine.fetch_instance_bans(&auth.user_id).await {
+        Ok(bans) => Json(bans).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct InstanceBanRequest {
+    pub reason: Option<String>,
+}
+
+/// PUT /api/admin/bans/:user_id — ban a DID or user ID from the instance.
+pub async fn admin_ban(
+    State(state): State<Arc<AppState>>,
+    Path(target_user_id): Path<String>,
+    auth: AuthUser,
+    Json(body): Json<InstanceBanRequest>,
+) -> impl IntoResponse {
+    match state
+        .engine
+        .instance_ban_by(&auth.user_id, &target_user_id, body.reason.as_deref())
+        .await
+    {
+        Ok(()) => {
+            state
+                .jwt_blocklist
+                .revoke_user(&target_user_id, state.auth_config.session_expiry_hours);
+            StatusCode::NO_CONTENT.into_response()
         }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// DELETE /api/admin/bans/:user_id — lift an instance ban.
+pub async fn admin_unban(
+    State(state): State<Arc<AppState>>,
+    Path(target_user_id): Path<String>,
+    auth: AuthUser,
+) -> impl IntoResponse {
+    match state
+        .engine
+        .instance_unban_by(&auth.user_id, &target_user_id)
+        .await
+    {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SetQuarantineRequest {
+    pub quarantined: bool,
+    pub reason: Option<String>,
+}
+
+/// PUT /api/admin/servers/:id/quarantine — hide a server from discovery and
+/// freeze it read-only, or lift the quarantine.
+pub async fn admin_set_quarantine(
+    State(state): State<Arc<AppState>>,
+    Path(server_id): Path<String>,
+    auth: AuthUser,
+    Json(body): Json<SetQuarantineRequest>,
+) -> impl IntoResponse {
+    match state
+        .engine
+        .set_server_quarantine_by(
+            &auth.user_id,
+            &server_id,
+            body.quarantined,
+            body.reason.as_deref(),
+        )
+        .await
+    {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct InstanceAuditLogParams {
+    pub action_type: Option<String>,
+    pub limit: Option<i64>,
+    pub before: Option<String>,
+}
+
+/// GET /api/admin/audit-log — read the instance audit log, newest first.
+pub async fn admin_audit_log(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Query(params): Query<InstanceAuditLogParams>,
+) -> impl IntoResponse {
+    match state
+        .engine
+        .fetch_instance_audit_log(
+            &auth.user_id,
+            params.action_type.as_deref(),
+            params.limit.unwrap_or(50),
+            params.before.as_deref(),
+        )
+        .await
+    {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/admin/reports — open reports across every server.
+pub async fn admin_list_reports(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> impl IntoResponse {
+    match state.engine.fetch_instance_reports(&auth.user_id).await {
+        Ok(reports) => Json(reports).into_response(),
+        Err(e) => e.into_response(),
     }
 }
 
@@ -620,6 +908,11 @@ pub async fn create_irc_token(
         return (StatusCode::INTERNAL_SERVER_ERROR, "Token creation failed").into_response();
     }
 
+    // SCRAM-SHA-256 verifier, sharing the salt of the user's other tokens
+    if let Err(e) = store_irc_token_scram(&state.db, &token_id, &auth.user_id, &token).await {
+        error!(error = %e, "Failed to store SCRAM credentials for IRC token");
+    }
+
     Json(CreateTokenResponse {
         id: token_id,
         token, // shown only once
@@ -669,6 +962,132 @@ pub async fn delete_irc_token(
     }
 }
 
+/// Derive and store the SCRAM-SHA-256 verifier for a new IRC token.
+async fn store_irc_token_scram(
+    pool: &sqlx::SqlitePool,
+    token_id: &str,
+    user_id: &str,
+    token: &str,
+) -> Result<(), sqlx::Error> {
+    use base64::Engine as _;
+    let b64 = base64::engine::general_purpose::STANDARD;
+
+    let (salt, iterations) = match users::get_irc_scram_salt(pool, user_id).await? {
+        Some((salt, iterations)) => match b64.decode(&salt) {
+            Ok(salt) => (salt, iterations as u32),
+            Err(_) => (generate_scram_salt(), SCRAM_ITERATIONS),
+        },
+        None => (generate_scram_salt(), SCRAM_ITERATIONS),
+    };
+    let creds = scram_credentials(token, &salt, iterations);
+    users::set_irc_token_scram(
+        pool,
+        token_id,
+        &b64.encode(&creds.salt),
+        creds.iterations as i64,
+        &b64.encode(&creds.stored_key),
+        &b64.encode(&creds.server_key),
+    )
+    .await
+}
+
+// ── IRC client certificates (SASL EXTERNAL) ──────────────
+
+#[derive(Deserialize)]
+pub struct AddCertRequest {
+    pub fingerprint: String,
+    pub label: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct IrcCertInfo {
+    pub fingerprint: String,
+    pub label: Option<String>,
+    pub created_at: String,
+}
+
+/// GET /api/certs — list the current user's registered client certificate fingerprints.
+pub async fn list_irc_certs(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> impl IntoResponse {
+    match users::list_irc_cert_fingerprints(&state.db, &auth.user_id).await {
+        Ok(rows) => {
+            let certs: Vec<IrcCertInfo> = rows
+                .into_iter()
+                .map(|(fingerprint, label, created_at)| IrcCertInfo {
+                    fingerprint,
+                    label,
+                    created_at,
+                })
+                .collect();
+            Json(certs).into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to list IRC certificates");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// POST /api/certs — register a client certificate's SHA-256 fingerprint for SASL EXTERNAL.
+pub async fn add_irc_cert(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(body): Json<AddCertRequest>,
+) -> impl IntoResponse {
+    let Some(fingerprint) = normalize_cert_fingerprint(&body.fingerprint) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Fingerprint must be a SHA-256 hash (64 hex characters)",
+        )
+            .into_response();
+    };
+
+    match users::add_irc_cert_fingerprint(
+        &state.db,
+        &fingerprint,
+        &auth.user_id,
+        body.label.as_deref(),
+    )
+    .await
+    {
+        Ok(()) => (
+            StatusCode::CREATED,
+            Json(IrcCertInfo {
+                fingerprint,
+                label: body.label,
+                created_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            }),
+        )
+            .into_response(),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            (StatusCode::CONFLICT, "Certificate already registered").into_response()
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to register IRC certificate");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
+/// DELETE /api/certs/:fingerprint — unregister a client certificate.
+pub async fn delete_irc_cert(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(fingerprint): Path<String>,
+) -> impl IntoResponse {
+    let fingerprint = normalize_cert_fingerprint(&fingerprint).unwrap_or(fingerprint);
+    match users::delete_irc_cert_fingerprint(&state.db, &fingerprint, &auth.user_id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (StatusCode::NOT_FOUND, "Certificate not found").into_response(),
+        Err(e) => {
+            error!(error = %e, "Failed to delete IRC certificate");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Database error").into_response()
+        }
+    }
+}
+
 // ── File upload endpoints ─────────────────────────────────
 
 #[derive(Serialize)]
@@ -1358,7 +1777,7 @@ pub async fn execute_webhook(
     Path((webhook_id, token)): Path<(String, String)>,
     Json(body): Json<WebhookExecuteRequest>,
 ) -> impl IntoResponse {
-    match state
+    let result = state
         .engine
         .execute_incoming_webhook(
             &webhook_id,
@@ -1367,24 +1786,15 @@ pub async fn execute_webhook(
             body.username.as_deref(),
             body.avatar_url.as_deref(),
         )
-        .await
-    {
+        .await;
+    METRICS.webhook_executions.inc(match &result {
+        Ok(()) => "ok",
+        Err(EngineError::InvalidToken(_)) => "unauthorized",
+        Err(_) => "error",
+    });
+    match result {
         Ok(()) => StatusCode::NO_CONTENT.into_response(),
-        Err(e) => {
-            if e.contains("Invalid webhook token") {
-                (
-                    StatusCode::UNAUTHORIZED,
-                    Json(serde_json::json!({"error": e})),
-                )
-                    .into_response()
-            } else {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(serde_json::json!({"error": e})),
-                )
-                    .into_response()
-            }
-        }
+        Err(e) => e.into_response(),
     }
 }
 
@@ -1875,6 +2285,30 @@ pub async fn get_server_limits(State(state): State<Arc<AppState>>) -> impl IntoR
 mod tests {
     use super::*;
 
+    // ── EngineError responses ──
+
+    #[tokio::test]
+    async fn test_engine_error_response() {
+        let resp = EngineError::NoSuchChannel("#nope".into()).into_response();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"], "NO_SUCH_CHANNEL");
+        assert_eq!(json["message"], "No such channel: #nope");
+    }
+
+    #[test]
+    fn test_rate_limited_response_has_retry_after() {
+        let resp = EngineError::RateLimited {
+            retry_after: Some(std::time::Duration::from_millis(4200)),
+        }
+        .into_response();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.headers()[header::RETRY_AFTER], "5");
+    }
+
     // ── HistoryParams deserialization ──
 
     #[test]
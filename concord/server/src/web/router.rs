@@ -111,6 +111,15 @@ pub fn build_router(state: Arc<AppState>) -> Router {
             "/api/servers/{id}/members",
             axum::routing::get(rest_api::list_server_members),
         )
+        // Reports and the moderator review queue
+        .route(
+            "/api/servers/{id}/reports",
+            axum::routing::get(rest_api::list_reports).post(rest_api::create_report),
+        )
+        .route(
+            "/api/servers/{id}/reports/{report_id}/resolve",
+            axum::routing::post(rest_api::resolve_report),
+        )
         // Admin endpoints (system admin only)
         .route(
             "/api/admin/servers",
@@ -120,10 +129,38 @@ pub fn build_router(state: Arc<AppState>) -> Router {
             "/api/admin/servers/{id}",
             axum::routing::delete(rest_api::admin_delete_server),
         )
+        .route(
+            "/api/admin/servers/{id}/quarantine",
+            axum::routing::put(rest_api::admin_set_quarantine),
+        )
         .route(
             "/api/admin/users/{id}/admin",
             axum::routing::put(rest_api::admin_set_admin),
         )
+        .route(
+            "/api/admin/users/{id}/suspended",
+            axum::routing::put(rest_api::admin_set_suspended),
+        )
+        .route(
+            "/api/admin/users/{id}/revoke-sessions",
+            axum::routing::post(rest_api::admin_revoke_sessions),
+        )
+        .route(
+            "/api/admin/bans",
+            axum::routing::get(rest_api::admin_list_bans),
+        )
+        .route(
+            "/api/admin/bans/{user_id}",
+            axum::routing::put(rest_api::admin_ban).delete(rest_api::admin_unban),
+        )
+        .route(
+            "/api/admin/audit-log",
+            axum::routing::get(rest_api::admin_audit_log),
+        )
+        .route(
+            "/api/admin/reports",
+            axum::routing::get(rest_api::admin_list_reports),
+        )
         // User profile lookup (public)
         .route(
             "/api/users/{nickname}",
@@ -139,6 +176,14 @@ pub fn build_router(state: Arc<AppState>) -> Router {
             "/api/tokens/{id}",
             axum::routing::delete(rest_api::delete_irc_token),
         )
+        .route(
+            "/api/certs",
+            axum::routing::get(rest_api::list_irc_certs).post(rest_api::add_irc_cert),
+        )
+        .route(
+            "/api/certs/{fingerprint}",
+            axum::routing::delete(rest_api::delete_irc_cert),
+        )
         // File upload/download
         .route(
             "/api/uploads",
@@ -11,8 +11,9 @@ use tracing::{error, info, warn};
 
 use crate::auth::token::validate_session_token;
 use crate::db::queries::users;
-use crate::engine::chat_engine::{ChatEngine, DEFAULT_SERVER_ID};
-use crate::engine::events::ChatEvent;
+use crate::engine::chat_engine::{BanParams, ChatEngine, DEFAULT_SERVER_ID, ResolveReportParams};
+use crate::engine::error::EngineError;
+use crate::engine::events::{ChatEvent, EscalationPolicyInfo};
 use crate::engine::permissions::Permissions;
 use crate::engine::user_session::Protocol;
 
@@ -195,6 +196,10 @@ enum ClientMessage {
         server_id: String,
         nickname: Option<String>,
     },
+    /// Change the session's global nickname (same as IRC NICK).
+    ChangeNick {
+        nickname: String,
+    },
     // ── Phase 4: Search ──
     SearchMessages {
         server_id: String,
@@ -273,6 +278,18 @@ enum ClientMessage {
         reason: Option<String>,
         #[serde(default)]
         delete_message_days: i32,
+        /// Finer-grained purge window; takes precedence over `delete_message_days`.
+        delete_message_seconds: Option<u64>,
+        /// Temporary ban length; permanent when unset.
+        duration_seconds: Option<u64>,
+    },
+    BanMembers {
+        server_id: String,
+        user_ids: Vec<String>,
+        reason: Option<String>,
+        #[serde(default)]
+        delete_message_seconds: u64,
+        duration_seconds: Option<u64>,
     },
     UnbanMember {
         server_id: String,
@@ -297,6 +314,21 @@ enum ClientMessage {
         channel: String,
         is_nsfw: bool,
     },
+    SetChannelPrivate {
+        server_id: String,
+        channel: String,
+        is_private: bool,
+    },
+    SetReadOnly {
+        server_id: String,
+        channel: String,
+        is_read_only: bool,
+    },
+    SetTopicLocked {
+        server_id: String,
+        channel: String,
+        topic_locked: bool,
+    },
     BulkDeleteMessages {
         server_id: String,
         channel: String,
@@ -333,6 +365,58 @@ enum ClientMessage {
     ListAutomodRules {
         server_id: String,
     },
+    ListAutomodQueue {
+        server_id: String,
+    },
+    ReviewAutomodEntry {
+        server_id: String,
+        entry_id: String,
+        approve: bool,
+    },
+    /// Start a lockdown for `duration_seconds`, or lift it with 0.
+    SetServerLockdown {
+        server_id: String,
+        duration_seconds: u64,
+        new_member_seconds: Option<u64>,
+    },
+    /// Report a message, or a member when `message_id` is absent.
+    CreateReport {
+        server_id: String,
+        message_id: Option<String>,
+        user_id: Option<String>,
+        reason: String,
+    },
+    ListReports {
+        server_id: String,
+    },
+    ResolveReport {
+        server_id: String,
+        report_id: String,
+        action: String,
+        note: Option<String>,
+        timeout_seconds: Option<u64>,
+    },
+    WarnMember {
+        server_id: String,
+        user_id: String,
+        reason: String,
+        expires_in_seconds: Option<u64>,
+    },
+    ListInfractions {
+        server_id: String,
+        user_id: String,
+    },
+    PardonInfraction {
+        server_id: String,
+        infraction_id: String,
+    },
+    ListEscalationPolicies {
+        server_id: String,
+    },
+    SetEscalationPolicies {
+        server_id: String,
+        policies: Vec<EscalationPolicyInfo>,
+    },
     // ── Phase 7: Community & Discovery ──
     CreateInvite {
         server_id: String,
@@ -529,6 +613,17 @@ pub async fn ws_upgrade(
     // Try cookie-based auth first
     let (nickname, user_id, avatar_url) = if let Some(cookie) = jar.get("concord_session") {
         if let Ok(claims) = validate_session_token(cookie.value(), &state.auth_config.jwt_secret) {
+            if state.jwt_blocklist.is_session_revoked(&claims)
+                || !users::is_session_valid(&state.db, &claims.sub, claims.iat)
+                    .await
+                    .unwrap_or(false)
+            {
+                return (
+                    axum::http::StatusCode::UNAUTHORIZED,
+                    "Session has been revoked",
+                )
+                    .into_response();
+            }
             match users::get_user(&state.db, &claims.sub).await {
                 Ok(Some((id, username, _email, avatar))) => (username, Some(id), avatar),
                 _ => {
@@ -590,6 +685,8 @@ async fn handle_ws_connection(
                 }
             }
         }
+        // The engine dropped the session (e.g. an admin revoked it): close the socket
+        let _ = ws_sender.send(Message::Close(None)).await;
     });
 
     let engine_ref = engine.clone();
@@ -635,7 +732,7 @@ async fn handle_ws_connection(
         }
     }
 
-    engine.disconnect(session_id);
+    engine.disconnect(session_id).await;
     write_handle.abort();
     info!(%session_id, %nickname, "WebSocket connection closed");
 }
@@ -661,17 +758,21 @@ async fn handle_client_message(
             reply_to,
             attachment_ids,
             nonce,
-        } => engine.send_message(
-            session_id,
-            &server_id,
-            &channel,
-            &content,
-            reply_to.as_deref(),
-            attachment_ids.as_deref(),
-            nonce.as_deref(),
-        ),
+        } => {
+            engine
+                .send_message(
+                    session_id,
+                    &server_id,
+                    &channel,
+                    &content,
+                    reply_to.as_deref(),
+                    attachment_ids.as_deref(),
+                    nonce.as_deref(),
+                )
+                .await
+        }
         ClientMessage::JoinChannel { server_id, channel } => {
-            engine.join_channel(session_id, &server_id, &channel)
+            engine.join_channel(session_id, &server_id, &channel).await
         }
         ClientMessage::PartChannel {
             server_id,
@@ -682,7 +783,11 @@ async fn handle_client_message(
             server_id,
             channel,
             topic,
-        } => engine.set_topic(session_id, &server_id, &channel, topic),
+        } => {
+            engine
+                .set_topic(session_id, &server_id, &channel, topic)
+                .await
+        }
         ClientMessage::FetchHistory {
             server_id,
             channel,
@@ -699,7 +804,9 @@ async fn handle_client_message(
                 })
                 .unwrap_or(false);
             if !is_member {
-                Err("You are not a member of this server".into())
+                Err(EngineError::Forbidden(
+                    "You are not a member of this server".into(),
+                ))
             } else {
                 let user_id = engine
                     .get_session(session_id)
@@ -741,7 +848,9 @@ async fn handle_client_message(
                 })
                 .unwrap_or(false);
             if !is_member {
-                Err("You are not a member of this server".into())
+                Err(EngineError::Forbidden(
+                    "You are not a member of this server".into(),
+                ))
             } else {
                 let channels = engine.list_channels(&server_id);
                 if let Some(session) = engine.get_session(session_id) {
@@ -764,7 +873,9 @@ async fn handle_client_message(
                 })
                 .unwrap_or(false);
             if !is_member {
-                Err("You are not a member of this server".into())
+                Err(EngineError::Forbidden(
+                    "You are not a member of this server".into(),
+                ))
             } else {
                 match engine.get_members(&server_id, &channel) {
                     Ok(member_infos) => {
@@ -994,10 +1105,14 @@ async fn handle_client_message(
                 Ok(_) => {
                     // Prevent assigning "owner" through this path
                     if role == "owner" {
-                        Err("Cannot assign owner role — use ownership transfer".into())
+                        Err(EngineError::Forbidden(
+                            "Cannot assign owner role — use ownership transfer".into(),
+                        ))
                     } else if engine.is_server_owner(&server_id, &user_id) {
                         // Prevent modifying the server owner's role
-                        Err("Cannot change the server owner's role".into())
+                        Err(EngineError::Forbidden(
+                            "Cannot change the server owner's role".into(),
+                        ))
                     } else {
                         // Role hierarchy: caller must outrank the target role
                         let mut hierarchy_ok = true;
@@ -1021,15 +1136,17 @@ async fn handle_client_message(
                             }
                         }
                         if !hierarchy_ok {
-                            Err("Cannot assign a role at or above your own level".into())
+                            Err(EngineError::Forbidden(
+                                "Cannot assign a role at or above your own level".into(),
+                            ))
                         } else if let Some(pool) = engine.db() {
                             crate::db::queries::servers::update_member_role(
                                 pool, &server_id, &user_id, &role,
                             )
                             .await
-                            .map_err(|e| format!("Failed to update role: {e}"))
+                            .map_err(EngineError::from)
                         } else {
-                            Err("No database configured".into())
+                            Err(EngineError::NoDatabase)
                         }
                     }
                 }
@@ -1103,7 +1220,9 @@ async fn handle_client_message(
                         .unwrap_or_default(),
                 )
             {
-                Err("Only the server owner can grant ADMINISTRATOR permission".into())
+                Err(EngineError::Forbidden(
+                    "Only the server owner can grant ADMINISTRATOR permission".into(),
+                ))
             } else {
                 match engine
                     .require_permission(
@@ -1149,7 +1268,9 @@ async fn handle_client_message(
                         .unwrap_or_default(),
                 )
             {
-                Err("Only the server owner can grant ADMINISTRATOR permission".into())
+                Err(EngineError::Forbidden(
+                    "Only the server owner can grant ADMINISTRATOR permission".into(),
+                ))
             } else {
                 match engine
                     .require_permission(
@@ -1243,13 +1364,15 @@ async fn handle_client_message(
                     .await
                 {
                     Ok(role_ids) => {
-                        if let Some(session) = engine.get_session(session_id) {
-                            let _ = session.send(ChatEvent::MemberRoleUpdate {
-                                server_id,
+                        // Broadcast so other members (and IRC, as MODE +o/+v) see the change
+                        engine.broadcast_to_server(
+                            &server_id,
+                            &ChatEvent::MemberRoleUpdate {
+                                server_id: server_id.clone(),
                                 user_id,
                                 role_ids,
-                            });
-                        }
+                            },
+                        );
                         Ok(())
                     }
                     Err(e) => Err(e),
@@ -1276,13 +1399,15 @@ async fn handle_client_message(
                     .await
                 {
                     Ok(role_ids) => {
-                        if let Some(session) = engine.get_session(session_id) {
-                            let _ = session.send(ChatEvent::MemberRoleUpdate {
-                                server_id,
+                        // Broadcast so other members (and IRC, as MODE +o/+v) see the change
+                        engine.broadcast_to_server(
+                            &server_id,
+                            &ChatEvent::MemberRoleUpdate {
+                                server_id: server_id.clone(),
                                 user_id,
                                 role_ids,
-                            });
-                        }
+                            },
+                        );
                         Ok(())
                     }
                     Err(e) => Err(e),
@@ -1454,6 +1579,10 @@ async fn handle_client_message(
                 .set_server_nickname(session_id, &server_id, nickname.as_deref())
                 .await
         }
+        ClientMessage::ChangeNick { nickname } => engine
+            .change_nickname(session_id, &nickname)
+            .await
+            .map(|_| ()),
         // ── Phase 4: Search ──
         ClientMessage::SearchMessages {
             server_id,
@@ -1614,15 +1743,33 @@ async fn handle_client_message(
             user_id,
             reason,
             delete_message_days,
+            delete_message_seconds,
+            duration_seconds,
         } => {
+            let params = BanParams {
+                reason: reason.as_deref(),
+                duration_seconds,
+                delete_message_seconds: delete_message_seconds
+                    .unwrap_or(delete_message_days.max(0) as u64 * 24 * 60 * 60),
+            };
             engine
-                .ban_member(
-                    session_id,
-                    &server_id,
-                    &user_id,
-                    reason.as_deref(),
-                    delete_message_days,
-                )
+                .ban_member(session_id, &server_id, &user_id, &params)
+                .await
+        }
+        ClientMessage::BanMembers {
+            server_id,
+            user_ids,
+            reason,
+            delete_message_seconds,
+            duration_seconds,
+        } => {
+            let params = BanParams {
+                reason: reason.as_deref(),
+                duration_seconds,
+                delete_message_seconds,
+            };
+            engine
+                .ban_members(session_id, &server_id, &user_ids, &params)
                 .await
         }
         ClientMessage::UnbanMember { server_id, user_id } => {
@@ -1663,6 +1810,33 @@ async fn handle_client_message(
                 .set_nsfw(session_id, &server_id, &channel, is_nsfw)
                 .await
         }
+        ClientMessage::SetChannelPrivate {
+            server_id,
+            channel,
+            is_private,
+        } => {
+            engine
+                .set_channel_private(session_id, &server_id, &channel, is_private)
+                .await
+        }
+        ClientMessage::SetReadOnly {
+            server_id,
+            channel,
+            is_read_only,
+        } => {
+            engine
+                .set_read_only(session_id, &server_id, &channel, is_read_only)
+                .await
+        }
+        ClientMessage::SetTopicLocked {
+            server_id,
+            channel,
+            topic_locked,
+        } => {
+            engine
+                .set_topic_locked(session_id, &server_id, &channel, topic_locked)
+                .await
+        }
         ClientMessage::BulkDeleteMessages {
             server_id,
             channel,
@@ -1746,6 +1920,108 @@ async fn handle_client_message(
         ClientMessage::ListAutomodRules { server_id } => {
             engine.list_automod_rules(session_id, &server_id).await
         }
+        ClientMessage::ListAutomodQueue { server_id } => {
+            engine.list_automod_queue(session_id, &server_id).await
+        }
+        ClientMessage::ReviewAutomodEntry {
+            server_id,
+            entry_id,
+            approve,
+        } => {
+            engine
+                .review_automod_entry(session_id, &server_id, &entry_id, approve)
+                .await
+        }
+        ClientMessage::SetServerLockdown {
+            server_id,
+            duration_seconds,
+            new_member_seconds,
+        } => {
+            engine
+                .set_server_lockdown(session_id, &server_id, duration_seconds, new_member_seconds)
+                .await
+        }
+        ClientMessage::CreateReport {
+            server_id,
+            message_id,
+            user_id,
+            reason,
+        } => {
+            engine
+                .report(
+                    session_id,
+                    &server_id,
+                    message_id.as_deref(),
+                    user_id.as_deref(),
+                    &reason,
+                )
+                .await
+        }
+        ClientMessage::ListReports { server_id } => {
+            engine.list_reports(session_id, &server_id).await
+        }
+        ClientMessage::ResolveReport {
+            server_id,
+            report_id,
+            action,
+            note,
+            timeout_seconds,
+        } => {
+            engine
+                .resolve_report(
+                    session_id,
+                    &server_id,
+                    &ResolveReportParams {
+                        report_id: &report_id,
+                        action: &action,
+                        note: note.as_deref(),
+                        timeout_seconds,
+                    },
+                )
+                .await
+        }
+        ClientMessage::WarnMember {
+            server_id,
+            user_id,
+            reason,
+            expires_in_seconds,
+        } => {
+            engine
+                .warn_member(
+                    session_id,
+                    &server_id,
+                    &user_id,
+                    &reason,
+                    expires_in_seconds,
+                )
+                .await
+        }
+        ClientMessage::ListInfractions { server_id, user_id } => {
+            engine
+                .list_infractions(session_id, &server_id, &user_id)
+                .await
+        }
+        ClientMessage::PardonInfraction {
+            server_id,
+            infraction_id,
+        } => {
+            engine
+                .pardon_infraction(session_id, &server_id, &infraction_id)
+                .await
+        }
+        ClientMessage::ListEscalationPolicies { server_id } => {
+            engine
+                .list_escalation_policies(session_id, &server_id)
+                .await
+        }
+        ClientMessage::SetEscalationPolicies {
+            server_id,
+            policies,
+        } => {
+            engine
+                .set_escalation_policies(session_id, &server_id, &policies)
+                .await
+        }
         // ── Phase 7: Community & Discovery ──
         ClientMessage::CreateInvite {
             server_id,
@@ -2073,11 +2349,11 @@ async fn handle_client_message(
                 Ok(_) => {
                     let user_id = engine
                         .get_session_user_id(session_id)
-                        .ok_or_else(|| "Not authenticated".to_string());
+                        .ok_or(EngineError::AuthRequired);
                     match user_id {
                         Err(e) => Err(e),
                         Ok(user_id) => {
-                            let pool = engine.get_db().ok_or("No database".to_string());
+                            let pool = engine.get_db().ok_or(EngineError::NoDatabase);
                             match pool {
                                 Err(e) => Err(e),
                                 Ok(pool) => {
@@ -2098,7 +2374,9 @@ async fn handle_client_message(
                                             engine.broadcast_to_server(&server_id, &event);
                                             Ok(())
                                         }
-                                        Err(e) => Err(format!("Failed to set server avatar: {e}")),
+                                        Err(e) => Err(EngineError::Internal(format!(
+                                            "Failed to set server avatar: {e}"
+                                        ))),
                                     }
                                 }
                             }
@@ -2131,7 +2409,7 @@ async fn handle_client_message(
                     match valid {
                         Err(e) => Err(e),
                         Ok(()) => match engine.get_db() {
-                            None => Err("No database".to_string()),
+                            None => Err(EngineError::NoDatabase),
                             Some(pool) => {
                                 match crate::db::queries::servers::set_vanity_code(
                                     &pool,
@@ -2141,7 +2419,9 @@ async fn handle_client_message(
                                 .await
                                 {
                                     Ok(()) => Ok(()),
-                                    Err(e) => Err(format!("Failed to set vanity code: {e}")),
+                                    Err(e) => Err(EngineError::Internal(format!(
+                                        "Failed to set vanity code: {e}"
+                                    ))),
                                 }
                             }
                         },
@@ -2162,7 +2442,7 @@ async fn handle_client_message(
     };
 
     if let Err(e) = result {
-        send_error(engine, session_id, "COMMAND_FAILED", &e);
+        send_error(engine, session_id, e.code(), &e.to_string());
     }
 }
 
@@ -2325,6 +2605,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_change_nick() {
+        let msg: ClientMessage =
+            parse_msg(r##"{"type":"change_nick","nickname":"alicia"}"##).unwrap();
+        match msg {
+            ClientMessage::ChangeNick { nickname } => assert_eq!(nickname, "alicia"),
+            _ => panic!("Expected ChangeNick"),
+        }
+    }
+
     #[test]
     fn test_fetch_history() {
         let msg: ClientMessage = parse_msg(
@@ -2844,6 +3134,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_warn_member_and_escalation_policies() {
+        let msg: ClientMessage = parse_msg(
+            r##"{
+            "type": "warn_member",
+            "server_id": "srv-1",
+            "user_id": "user-1",
+            "reason": "Keep it civil"
+        }"##,
+        )
+        .unwrap();
+        match msg {
+            ClientMessage::WarnMember {
+                reason,
+                expires_in_seconds,
+                ..
+            } => {
+                assert_eq!(reason, "Keep it civil");
+                assert!(expires_in_seconds.is_none());
+            }
+            _ => panic!("Expected WarnMember"),
+        }
+
+        let msg: ClientMessage = parse_msg(
+            r##"{
+            "type": "set_escalation_policies",
+            "server_id": "srv-1",
+            "policies": [
+                {"threshold": 3, "window_seconds": 2592000, "action": "timeout", "duration_seconds": 3600},
+                {"threshold": 5, "window_seconds": 2592000, "action": "ban"}
+            ]
+        }"##,
+        )
+        .unwrap();
+        match msg {
+            ClientMessage::SetEscalationPolicies { policies, .. } => {
+                assert_eq!(policies.len(), 2);
+                assert_eq!(policies[0].duration_seconds, Some(3600));
+                assert_eq!(policies[1].action, "ban");
+                assert!(policies[1].duration_seconds.is_none());
+            }
+            _ => panic!("Expected SetEscalationPolicies"),
+        }
+    }
+
     #[test]
     fn test_ban_member() {
         let msg: ClientMessage = parse_msg(
@@ -2862,6 +3197,7 @@ mod tests {
                 user_id,
                 reason,
                 delete_message_days,
+                ..
             } => {
                 assert_eq!(server_id, "srv-1");
                 assert_eq!(user_id, "user-1");
@@ -2886,15 +3222,46 @@ mod tests {
             ClientMessage::BanMember {
                 delete_message_days,
                 reason,
+                delete_message_seconds,
+                duration_seconds,
                 ..
             } => {
                 assert_eq!(delete_message_days, 0);
                 assert!(reason.is_none());
+                assert!(delete_message_seconds.is_none());
+                assert!(duration_seconds.is_none());
             }
             _ => panic!("Expected BanMember"),
         }
     }
 
+    #[test]
+    fn test_ban_members() {
+        let msg: ClientMessage = parse_msg(
+            r##"{
+            "type": "ban_members",
+            "server_id": "srv-1",
+            "user_ids": ["user-1", "did:plc:abc"],
+            "duration_seconds": 86400,
+            "delete_message_seconds": 3600
+        }"##,
+        )
+        .unwrap();
+        match msg {
+            ClientMessage::BanMembers {
+                user_ids,
+                duration_seconds,
+                delete_message_seconds,
+                ..
+            } => {
+                assert_eq!(user_ids, ["user-1", "did:plc:abc"]);
+                assert_eq!(duration_seconds, Some(86400));
+                assert_eq!(delete_message_seconds, 3600);
+            }
+            _ => panic!("Expected BanMembers"),
+        }
+    }
+
     #[test]
     fn test_set_slow_mode() {
         let msg: ClientMessage = parse_msg(
@@ -3299,6 +3666,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_set_channel_mode_flags() {
+        let msg: ClientMessage = parse_msg(
+            r##"{
+            "type": "set_read_only",
+            "server_id": "srv-1",
+            "channel": "#announcements",
+            "is_read_only": true
+        }"##,
+        )
+        .unwrap();
+        match msg {
+            ClientMessage::SetReadOnly { is_read_only, .. } => assert!(is_read_only),
+            _ => panic!("Expected SetReadOnly"),
+        }
+
+        let msg: ClientMessage = parse_msg(
+            r##"{"type":"set_topic_locked","server_id":"srv-1","channel":"#general","topic_locked":false}"##,
+        )
+        .unwrap();
+        match msg {
+            ClientMessage::SetTopicLocked { topic_locked, .. } => assert!(!topic_locked),
+            _ => panic!("Expected SetTopicLocked"),
+        }
+
+        let msg: ClientMessage = parse_msg(
+            r##"{"type":"set_channel_private","server_id":"srv-1","channel":"#staff","is_private":true}"##,
+        )
+        .unwrap();
+        match msg {
+            ClientMessage::SetChannelPrivate { is_private, .. } => assert!(is_private),
+            _ => panic!("Expected SetChannelPrivate"),
+        }
+    }
+
     #[test]
     fn test_get_audit_log() {
         let msg: ClientMessage = parse_msg(
@@ -20,6 +20,7 @@ export interface ServerInfo {
   member_count: number;
   role?: string | null;
   my_permissions?: number;
+  quarantined?: boolean; // hidden from discovery and read-only
 }
 
 export interface ChannelInfo {
@@ -36,6 +37,8 @@ export interface ChannelInfo {
   archived: boolean;
   slowmode_seconds: number;
   is_nsfw: boolean;
+  is_read_only: boolean;
+  topic_locked: boolean;
 }
 
 export interface MemberInfo {
@@ -222,19 +225,90 @@ export interface BanInfo {
   user_id: string;
   banned_by: string;
   reason?: string | null;
+  expires_at?: string; // temporary bans only
   created_at: string;
 }
 
+export interface BulkBanFailure {
+  user_id: string;
+  error: string;
+}
+
 export interface AutomodRuleInfo {
   id: string;
   name: string;
   enabled: boolean;
-  rule_type: string; // 'keyword' | 'mention_spam' | 'link_filter'
+  rule_type: string; // 'keyword' | 'mention_spam' | 'link_filter' | 'duplicate_spam' | 'message_flood' | 'excessive_caps' | 'zalgo' | 'invite_spam' | 'join_burst' | 'raid'
   config: string; // JSON string
   action_type: string; // 'delete' | 'timeout' | 'flag'
   timeout_duration_seconds?: number | null;
 }
 
+export interface AutomodQueueEntryInfo {
+  id: string;
+  channel_id: string;
+  rule_id?: string;
+  rule_name: string;
+  user_id: string;
+  user_nick: string;
+  content: string;
+  message_id?: string; // set for flagged (already delivered) messages
+  status: string; // 'flagged' | 'pending' | 'approved' | 'rejected'
+  created_at: string;
+}
+
+export type ReportAction = 'dismiss' | 'delete' | 'timeout' | 'kick' | 'ban';
+
+export interface ReportInfo {
+  id: string;
+  reporter_id: string;
+  target_user_id: string;
+  message_id?: string;
+  channel_id?: string;
+  message_content?: string; // the message as it was when reported
+  reason: string;
+  status: string; // 'open' | 'resolved'
+  resolution?: ReportAction;
+  resolution_note?: string;
+  resolved_by?: string; // omitted in the reporter's copy
+  resolved_at?: string;
+  created_at: string;
+  context?: HistoryMessage[]; // messages around the reported one, in the moderator queue
+}
+
+export interface InstanceReportInfo extends ReportInfo {
+  server_id: string;
+  server_name: string;
+}
+
+export interface InstanceBanInfo {
+  user_id: string; // a DID or user ID
+  reason?: string;
+  banned_by: string;
+  created_at: string;
+}
+
+export interface InfractionInfo {
+  id: string;
+  user_id: string;
+  actor_id?: string; // omitted for automod and escalation entries
+  action: string; // 'warn' | 'timeout' | 'kick' | 'ban' | 'automod'
+  source: string; // 'manual' | 'automod' | 'escalation'
+  reason?: string;
+  expires_at?: string;
+  pardoned_by?: string;
+  pardoned_at?: string;
+  active: boolean; // still counts as a strike
+  created_at: string;
+}
+
+export interface EscalationPolicyInfo {
+  threshold: number; // strikes
+  window_seconds: number;
+  action: 'timeout' | 'kick' | 'ban';
+  duration_seconds?: number; // required for timeout
+}
+
 export interface InviteInfo {
   id: string;
   code: string;
@@ -512,17 +586,30 @@ export type ServerEvent =
   | { type: 'bookmark_add'; bookmark: BookmarkInfo }
   | { type: 'bookmark_remove'; message_id: string }
   | { type: 'member_kick'; server_id: string; user_id: string; kicked_by: string; reason?: string | null }
-  | { type: 'member_ban'; server_id: string; user_id: string; banned_by: string; reason?: string | null }
+  | { type: 'member_ban'; server_id: string; user_id: string; banned_by: string; reason?: string | null; expires_at?: string }
   | { type: 'member_unban'; server_id: string; user_id: string }
   | { type: 'member_timeout'; server_id: string; user_id: string; timeout_until?: string | null }
   | { type: 'slow_mode_update'; server_id: string; channel: string; seconds: number }
   | { type: 'nsfw_update'; server_id: string; channel: string; is_nsfw: boolean }
+  | { type: 'channel_private_update'; server_id: string; channel: string; is_private: boolean; set_by: string }
+  | { type: 'read_only_update'; server_id: string; channel: string; is_read_only: boolean; set_by: string }
+  | { type: 'topic_lock_update'; server_id: string; channel: string; topic_locked: boolean; set_by: string }
   | { type: 'bulk_message_delete'; server_id: string; channel: string; message_ids: string[] }
   | { type: 'audit_log_entries'; server_id: string; entries: AuditLogEntry[] }
   | { type: 'ban_list'; server_id: string; bans: BanInfo[] }
+  | { type: 'bulk_ban_result'; server_id: string; banned: string[]; failed: BulkBanFailure[] }
   | { type: 'automod_rule_list'; server_id: string; rules: AutomodRuleInfo[] }
   | { type: 'automod_rule_update'; server_id: string; rule: AutomodRuleInfo }
   | { type: 'automod_rule_delete'; server_id: string; rule_id: string }
+  | { type: 'automod_queue'; server_id: string; entries: AutomodQueueEntryInfo[] }
+  | { type: 'automod_queue_update'; server_id: string; entry: AutomodQueueEntryInfo }
+  | { type: 'server_lockdown'; server_id: string; until: string | null; new_member_seconds: number }
+  | { type: 'server_quarantine'; server_id: string; quarantined: boolean; reason?: string }
+  | { type: 'report_list'; server_id: string; reports: ReportInfo[] }
+  | { type: 'report_update'; server_id: string; report: ReportInfo }
+  | { type: 'member_warn'; server_id: string; user_id: string; warned_by: string; reason: string }
+  | { type: 'infraction_list'; server_id: string; user_id: string; infractions: InfractionInfo[] }
+  | { type: 'escalation_policy_list'; server_id: string; policies: EscalationPolicyInfo[] }
   | { type: 'invite_list'; server_id: string; invites: InviteInfo[] }
   | { type: 'invite_create'; server_id: string; invite: InviteInfo }
   | { type: 'invite_delete'; server_id: string; invite_id: string }
@@ -591,6 +678,7 @@ export type ClientCommand =
   | { type: 'set_presence'; status: string; custom_status?: string; status_emoji?: string }
   | { type: 'get_presences'; server_id: string }
   | { type: 'set_server_nickname'; server_id: string; nickname?: string }
+  | { type: 'change_nick'; nickname: string }
   | { type: 'search_messages'; server_id: string; query: string; channel?: string; limit?: number; offset?: number }
   | { type: 'update_notification_settings'; server_id: string; channel_id?: string; level: string; suppress_everyone?: boolean; suppress_roles?: boolean; muted?: boolean; mute_until?: string }
   | { type: 'get_notification_settings'; server_id: string }
@@ -605,18 +693,33 @@ export type ClientCommand =
   | { type: 'remove_bookmark'; message_id: string }
   | { type: 'list_bookmarks' }
   | { type: 'kick_member'; server_id: string; user_id: string; reason?: string }
-  | { type: 'ban_member'; server_id: string; user_id: string; reason?: string; delete_message_days?: number }
+  | { type: 'ban_member'; server_id: string; user_id: string; reason?: string; delete_message_days?: number; delete_message_seconds?: number; duration_seconds?: number }
+  | { type: 'ban_members'; server_id: string; user_ids: string[]; reason?: string; delete_message_seconds?: number; duration_seconds?: number }
   | { type: 'unban_member'; server_id: string; user_id: string }
   | { type: 'list_bans'; server_id: string }
   | { type: 'timeout_member'; server_id: string; user_id: string; timeout_until?: string; reason?: string }
   | { type: 'set_slow_mode'; server_id: string; channel: string; seconds: number }
   | { type: 'set_nsfw'; server_id: string; channel: string; is_nsfw: boolean }
+  | { type: 'set_channel_private'; server_id: string; channel: string; is_private: boolean }
+  | { type: 'set_read_only'; server_id: string; channel: string; is_read_only: boolean }
+  | { type: 'set_topic_locked'; server_id: string; channel: string; topic_locked: boolean }
   | { type: 'bulk_delete_messages'; server_id: string; channel: string; message_ids: string[] }
   | { type: 'get_audit_log'; server_id: string; action_type?: string; limit?: number; before?: string }
   | { type: 'create_automod_rule'; server_id: string; name: string; rule_type: string; config: string; action_type: string; timeout_duration_seconds?: number }
   | { type: 'update_automod_rule'; server_id: string; rule_id: string; name: string; enabled: boolean; config: string; action_type: string; timeout_duration_seconds?: number }
   | { type: 'delete_automod_rule'; server_id: string; rule_id: string }
   | { type: 'list_automod_rules'; server_id: string }
+  | { type: 'list_automod_queue'; server_id: string }
+  | { type: 'review_automod_entry'; server_id: string; entry_id: string; approve: boolean }
+  | { type: 'set_server_lockdown'; server_id: string; duration_seconds: number; new_member_seconds?: number }
+  | { type: 'create_report'; server_id: string; message_id?: string; user_id?: string; reason: string }
+  | { type: 'list_reports'; server_id: string }
+  | { type: 'resolve_report'; server_id: string; report_id: string; action: ReportAction; note?: string; timeout_seconds?: number }
+  | { type: 'warn_member'; server_id: string; user_id: string; reason: string; expires_in_seconds?: number }
+  | { type: 'list_infractions'; server_id: string; user_id: string }
+  | { type: 'pardon_infraction'; server_id: string; infraction_id: string }
+  | { type: 'list_escalation_policies'; server_id: string }
+  | { type: 'set_escalation_policies'; server_id: string; policies: EscalationPolicyInfo[] }
   | { type: 'create_invite'; server_id: string; max_uses?: number; expires_at?: string; channel_id?: string }
   | { type: 'list_invites'; server_id: string }
   | { type: 'delete_invite'; server_id: string; invite_id: string }
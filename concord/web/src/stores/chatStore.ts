@@ -988,6 +988,45 @@ export const useChatStore = create<ChatState>((set, get) => ({
         });
         break;
       }
+      case 'channel_private_update': {
+        const e = event as Extract<ServerEvent, { type: 'channel_private_update' }>;
+        const channels = get().channels[e.server_id] ?? [];
+        set({
+          channels: {
+            ...get().channels,
+            [e.server_id]: channels.map(ch =>
+              ch.name === e.channel ? { ...ch, is_private: e.is_private } : ch
+            ),
+          },
+        });
+        break;
+      }
+      case 'read_only_update': {
+        const e = event as Extract<ServerEvent, { type: 'read_only_update' }>;
+        const channels = get().channels[e.server_id] ?? [];
+        set({
+          channels: {
+            ...get().channels,
+            [e.server_id]: channels.map(ch =>
+              ch.name === e.channel ? { ...ch, is_read_only: e.is_read_only } : ch
+            ),
+          },
+        });
+        break;
+      }
+      case 'topic_lock_update': {
+        const e = event as Extract<ServerEvent, { type: 'topic_lock_update' }>;
+        const channels = get().channels[e.server_id] ?? [];
+        set({
+          channels: {
+            ...get().channels,
+            [e.server_id]: channels.map(ch =>
+              ch.name === e.channel ? { ...ch, topic_locked: e.topic_locked } : ch
+            ),
+          },
+        });
+        break;
+      }
       case 'bulk_message_delete': {
         const e = event as Extract<ServerEvent, { type: 'bulk_message_delete' }>;
         const key = channelKey(e.server_id, e.channel);